@@ -0,0 +1,190 @@
+//! Command line companion of the server, for scripting common tasks through the server API.
+
+use std::env;
+
+use anyhow::{anyhow, bail, Result};
+use reqwest::{Client, Method};
+use serde_json::{json, Value};
+
+const DEFAULT_SERVER: &str = "http://localhost:6000";
+
+const USAGE: &str = "Usage: bottle-cli [--server URL] <command>
+
+Commands:
+  feed add <params> [--name NAME] [--account ID] [--unwatched]
+                                      Add a feed, e.g. params '{\"twitter\":{\"posts\":{\"user_id\":1}}}'
+  feed update <community> <feed_id>   Update a feed
+  feed update-all <community>         Update all watched feeds of the community
+  import <params> [--account ID] [--max-posts N] [--download]
+                                      Import the bookmarks, likes or favorites into the library
+  images download                     Download the images in the library not downloaded yet
+  panda download <gallery_id> [--pages RANGES] [--ordered]
+                                      Download a panda gallery, or only the pages, e.g. 1-20,25,
+                                      in page order if ordered
+  album export <output_dir> <album_id>...
+                                      Render the albums into a static HTML gallery on the server
+  jobs                                Show the state of background jobs
+
+The server defaults to BOTTLE_SERVER, or http://localhost:6000.";
+
+#[tokio::main]
+async fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let Err(e) = run(args).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(mut args: Vec<String>) -> Result<()> {
+    let server = take_option(&mut args, "--server")?
+        .or(env::var("BOTTLE_SERVER").ok())
+        .unwrap_or(DEFAULT_SERVER.to_string());
+    let api = Api {
+        client: Client::new(),
+        server: server.trim_end_matches('/').to_string(),
+    };
+
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["feed", "add", params, rest @ ..] => {
+            let mut rest = to_owned(rest);
+            let name = take_option(&mut rest, "--name")?;
+            let account_id = take_option(&mut rest, "--account")?
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+            let watching = !take_flag(&mut rest, "--unwatched");
+            ensure_empty(&rest)?;
+            let body = json!({
+                "params": parse_json(params)?,
+                "info": {
+                    "name": name,
+                    "watching": watching,
+                    "first_fetch_limit": null,
+                    "icon_url": null,
+                    "color": null,
+                },
+                "account_id": account_id,
+            });
+            api.send(Method::POST, "/feed", Some(body)).await
+        }
+        ["feed", "update", community, feed_id] => {
+            api.send(Method::GET, &format!("/{}/feed/{}/update", community, feed_id), None)
+                .await
+        }
+        ["feed", "update-all", community] => {
+            api.send(Method::GET, &format!("/{}/feeds/update", community), None)
+                .await
+        }
+        ["import", params, rest @ ..] => {
+            let mut rest = to_owned(rest);
+            let account_id = take_option(&mut rest, "--account")?
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+            let max_posts = take_option(&mut rest, "--max-posts")?
+                .map(|n| n.parse::<u64>())
+                .transpose()?;
+            let download = take_flag(&mut rest, "--download");
+            ensure_empty(&rest)?;
+            let body = json!({
+                "params": parse_json(params)?,
+                "account_id": account_id,
+                "max_posts": max_posts,
+                "download": download,
+            });
+            api.send(Method::POST, "/import", Some(body)).await
+        }
+        ["images", "download"] => api.send(Method::GET, "/images/download", None).await,
+        ["panda", "download", gallery_id, rest @ ..] => {
+            let mut rest = to_owned(rest);
+            let pages = take_option(&mut rest, "--pages")?;
+            let ordered = take_flag(&mut rest, "--ordered");
+            ensure_empty(&rest)?;
+            let mut query = vec![format!("ordered={}", ordered)];
+            if let Some(pages) = pages {
+                query.push(format!("pages={}", pages));
+            }
+            let path = format!("/panda/gallery/{}/download?{}", gallery_id, query.join("&"));
+            api.send(Method::GET, &path, None).await
+        }
+        ["album", "export", output_dir, album_ids @ ..] if !album_ids.is_empty() => {
+            let album_ids = album_ids
+                .iter()
+                .map(|id| id.parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()?;
+            let body = json!({ "album_ids": album_ids, "output_dir": output_dir });
+            api.send(Method::POST, "/library/export/static_site", Some(body)).await
+        }
+        ["jobs"] => api.send(Method::GET, "/jobs", None).await,
+        _ => {
+            eprintln!("{}", USAGE);
+            bail!("Unknown command");
+        }
+    }
+}
+
+struct Api {
+    client: Client,
+    server: String,
+}
+
+impl Api {
+    /// Send the request, and print the response body, pretty-printed if it is JSON.
+    async fn send(&self, method: Method, path: &str, body: Option<Value>) -> Result<()> {
+        let mut request = self.client.request(method, format!("{}{}", self.server, path));
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            bail!("{} {}", status, text);
+        }
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+            Err(_) if text.is_empty() => println!("OK"),
+            Err(_) => println!("{}", text),
+        }
+        Ok(())
+    }
+}
+
+fn parse_json(text: &str) -> Result<Value> {
+    serde_json::from_str(text).map_err(|e| anyhow!("Invalid JSON {}: {}", text, e))
+}
+
+fn to_owned(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// Remove the option and its value from the arguments.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>> {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        bail!("Missing value of {}", name);
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+/// Remove the flag from the arguments, and return whether it was present.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(index) => {
+            args.remove(index);
+            true
+        }
+        None => false,
+    }
+}
+
+fn ensure_empty(args: &[String]) -> Result<()> {
+    match args.first() {
+        Some(arg) => bail!("Unexpected argument {}", arg),
+        None => Ok(()),
+    }
+}
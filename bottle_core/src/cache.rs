@@ -0,0 +1,256 @@
+use serde::Serialize;
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bottle_util::http::{RequestOutcome, ResponseHook};
+
+/// Default number of entries kept in a cache.
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+/// Default time to live of entries in a cache.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Map of entities fetched from communities, bounded by both the number of entries and their time to live.
+/// The oldest entries are evicted first when the cache is full.
+#[derive(Debug, Clone)]
+pub struct BoundedCache<K, V> {
+    /// Entries with their insertion times and sequence numbers.
+    entries: HashMap<K, (V, Instant, u64)>,
+    /// Keys in the order of insertion, with the sequence numbers to skip the keys inserted again later.
+    order: VecDeque<(K, u64)>,
+    next_seq: u64,
+    capacity: usize,
+    ttl: Duration,
+}
+
+/// Size and limits of a cache, shown to the admin.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub name: String,
+    pub len: usize,
+    pub capacity: usize,
+    pub ttl_secs: u64,
+}
+
+impl<K: Clone + Eq + Hash, V> BoundedCache<K, V> {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            next_seq: 0,
+            capacity,
+            ttl,
+        }
+    }
+
+    /// Get the entry if it's not expired yet.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries
+            .get(key)
+            .filter(|(_, inserted, _)| inserted.elapsed() < self.ttl)
+            .map(|(value, _, _)| value)
+    }
+
+    /// Get the entry mutably if it's not expired yet, without renewing it.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let ttl = self.ttl;
+        self.entries
+            .get_mut(key)
+            .filter(|(_, inserted, _)| inserted.elapsed() < ttl)
+            .map(|(value, _, _)| value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Insert or replace the entry, renewing its time to live, and evict the expired and the oldest entries.
+    pub fn insert(&mut self, key: K, value: V) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.entries.insert(key.clone(), (value, Instant::now(), seq));
+        self.order.push_back((key, seq));
+        self.evict();
+    }
+
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = (K, V)>) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+
+    /// Number of entries, including the expired ones not evicted yet.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    pub fn stats(&self, name: &str) -> CacheStats {
+        CacheStats {
+            name: name.to_string(),
+            len: self.len(),
+            capacity: self.capacity,
+            ttl_secs: self.ttl.as_secs(),
+        }
+    }
+
+    fn evict(&mut self) {
+        while let Some((key, seq)) = self.order.front() {
+            let current = self.entries.get(key).filter(|(_, _, entry_seq)| entry_seq == seq);
+            if let Some((_, inserted, _)) = current {
+                if self.entries.len() <= self.capacity && inserted.elapsed() < self.ttl {
+                    break;
+                }
+                self.entries.remove(key);
+            }
+            self.order.pop_front();
+        }
+        // Drop the keys inserted again later, so that the order doesn't grow with repeated insertions
+        if self.order.len() > self.capacity.saturating_mul(2) {
+            let entries = &self.entries;
+            self.order
+                .retain(|(key, seq)| entries.get(key).is_some_and(|(_, _, entry_seq)| entry_seq == seq));
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Default for BoundedCache<K, V> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY, DEFAULT_CACHE_TTL)
+    }
+}
+
+/// Records the outcome of a request made with the account of the ID.
+pub type RequestRecorder = Arc<dyn Fn(i32, RequestOutcome) + Send + Sync>;
+
+/// Clients of accounts with the credentials they were created with,
+/// reused across feed updates and jobs to keep connections alive.
+#[derive(Clone)]
+pub struct ClientCache<C, K> {
+    clients: HashMap<i32, (K, C)>,
+    recorder: Option<RequestRecorder>,
+}
+
+impl<C: Clone, K: Clone + PartialEq> ClientCache<C, K> {
+    pub fn new() -> Self {
+        Self {
+            clients: HashMap::new(),
+            recorder: None,
+        }
+    }
+
+    /// Record the requests of the clients created from now on.
+    pub fn set_recorder(&mut self, recorder: RequestRecorder) {
+        self.recorder = Some(recorder);
+        self.clients.clear();
+    }
+
+    /// Get the client of the account, or create it if the credential of the account has changed.
+    /// `create` gets the hook to report the requests of the new client with, if they are recorded.
+    pub fn get_or_create<E>(
+        &mut self,
+        account_id: i32,
+        credential: &K,
+        create: impl FnOnce(&K, Option<ResponseHook>) -> std::result::Result<C, E>,
+    ) -> std::result::Result<C, E> {
+        if let Some((cached_credential, client)) = self.clients.get(&account_id) {
+            if cached_credential == credential {
+                return Ok(client.clone());
+            }
+        }
+        let hook = self
+            .recorder
+            .clone()
+            .map(|recorder| ResponseHook::new(move |outcome| recorder(account_id, outcome)));
+        let client = create(credential, hook)?;
+        self.clients.insert(account_id, (credential.clone(), client.clone()));
+        Ok(client)
+    }
+}
+
+impl<C, K> std::fmt::Debug for ClientCache<C, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientCache")
+            .field("accounts", &self.clients.keys().collect::<Vec<_>>())
+            .field("recorded", &self.recorder.is_some())
+            .finish()
+    }
+}
+
+impl<C: Clone, K: Clone + PartialEq> Default for ClientCache<C, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_evict_oldest_over_capacity() {
+        let mut cache = BoundedCache::new(2, DEFAULT_CACHE_TTL);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        cache.insert(3, "c");
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some(&"b"));
+        assert_eq!(cache.get(&3), Some(&"c"));
+    }
+
+    #[test]
+    fn test_expire_on_get() {
+        let mut cache = BoundedCache::new(10, Duration::from_millis(20));
+        cache.insert(1, "a");
+        assert_eq!(cache.get(&1), Some(&"a"));
+        std::thread::sleep(Duration::from_millis(40));
+        assert_eq!(cache.get(&1), None);
+        assert!(!cache.contains_key(&1));
+
+        // Expired entries are evicted on the next insertion
+        cache.insert(2, "b");
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&2), Some(&"b"));
+    }
+
+    #[test]
+    fn test_reinsert_renews_entry() {
+        let mut cache = BoundedCache::new(2, DEFAULT_CACHE_TTL);
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        // The stale order entry of key 1 must not evict the new value
+        cache.insert(1, "a2");
+        cache.insert(3, "c");
+        assert_eq!(cache.get(&1), Some(&"a2"));
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), Some(&"c"));
+
+        // Repeated insertions don't grow the order beyond twice the capacity
+        for i in 0..100 {
+            cache.insert(1, if i % 2 == 0 { "x" } else { "y" });
+        }
+        assert!(cache.order.len() <= 4);
+        assert_eq!(cache.get(&1), Some(&"y"));
+        assert_eq!(cache.get(&3), Some(&"c"));
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut cache = BoundedCache::new(0, DEFAULT_CACHE_TTL);
+        cache.insert(1, "a");
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&1), None);
+        assert!(cache.order.is_empty());
+    }
+}
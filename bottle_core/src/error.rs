@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::feed::FieldError;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
@@ -21,6 +23,8 @@ pub enum Error {
     ObjectAlreadyExists(String),
     #[error("Unknown field: {0}")]
     UnknownField(String),
+    #[error("Invalid fields: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))]
+    InvalidFields(Vec<FieldError>),
 
     #[error("IO Error: {0}")]
     IOError(#[from] std::io::Error),
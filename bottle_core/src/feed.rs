@@ -7,9 +7,10 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
+use std::str::FromStr;
 
-use crate::error::Result;
-use crate::library::{ImageView, WorkView};
+use crate::error::{Error, Result};
+use crate::library::{ImageView, NoteView, WorkFilter, WorkView};
 
 pub type Database<'a> = &'a mut diesel::SqliteConnection;
 
@@ -92,6 +93,12 @@ pub trait Account {
     /// Get the credential information of the account.
     fn credential(&self, db: Database) -> Result<Self::Credential>;
 
+    /// Parse the credential from a request matching `credential_scheme` of the metadata,
+    /// with errors of the fields under `path`.
+    fn parse_credential(value: &serde_json::Value, path: &str) -> Result<Self::Credential>
+    where
+        Self: Sized;
+
     /// Fetch account information from the community.
     async fn fetch(credential: &Self::Credential) -> Result<Self::InfoResponse>;
 }
@@ -142,8 +149,22 @@ pub trait Feed {
     fn get_account(&self, db: Database) -> Result<Self::Account>;
     /// Get the context of the feed.
     fn get_fetch_context(&self, db: Database) -> Result<Self::FetchContext>;
+    /// Whether the context of the next update continues from where an update stopped early,
+    /// so that the pages fetched in an update can be capped without missing posts.
+    fn resumable(&self) -> bool {
+        false
+    }
     /// Fetch posts from the feed with given context and authentication.
     async fn fetch(&self, ctx: &mut Self::FetchContext, auth: Option<&Self::Auth>) -> Result<Self::FetchResult>;
+    /// Fetch a readable name for the feed from the community, like the name of the watched user or list,
+    /// with its avatar. Used as the default name of a new feed.
+    /// Requests are sent with the client in the context, which is taken from the cache of the account.
+    async fn fetch_name(&self, _ctx: &Self::FetchContext) -> Result<Option<FeedName>>
+    where
+        Self::FetchContext: Sync,
+    {
+        Ok(None)
+    }
     /// Save fetched posts to the database.
     fn save(&self, db: Database, fetched: &Self::FetchResult, ctx: &Self::FetchContext) -> Result<SaveResult>;
     /// Callback after fetching posts, with the save results.
@@ -154,16 +175,34 @@ pub trait Feed {
     ) -> Result<()>;
 
     /// Get all the posts of the feed in the database.
-    fn posts(&self, db: Database, page: i64, page_size: i64) -> Result<GeneralResponse>;
+    fn posts(
+        &self,
+        db: Database,
+        filter: &PostFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse>;
+
+    /// Mark posts of the feed as dismissed or not, which are hidden from the feed by default.
+    /// Returns the number of posts affected.
+    fn set_dismissed(&self, db: Database, post_ids: &[i64], dismissed: bool) -> Result<usize>;
 
     /// Get all the posts in the community's library. Static function.
-    fn archived_posts(db: Database, page: i64, page_size: i64) -> Result<GeneralResponse>
+    fn archived_posts(
+        db: Database,
+        filter: &WorkFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse>
     where
         Self: Sized;
 
     /// Get all the artists appeared in the community's library, if the community supports. Static function.
     fn archived_posts_grouped_by_user(
         db: Database,
+        filter: &WorkFilter,
         page: i64,
         page_size: i64,
         recent_count: i64,
@@ -172,7 +211,13 @@ pub trait Feed {
         Self: Sized;
 
     /// Get all the posts of an artist in the community's library, if the community supports. Static function.
-    fn archived_posts_by_user(db: Database, user_id: String, page: i64, page_size: i64) -> Result<GeneralResponse>
+    fn archived_posts_by_user(
+        db: Database,
+        user_id: String,
+        filter: &WorkFilter,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse>
     where
         Self: Sized;
 
@@ -187,6 +232,9 @@ pub trait Feed {
 
     /// Get all the posts of an artist in the feed, if the community supports.
     fn feed_posts_by_user(&self, db: Database, user_id: String, page: i64, page_size: i64) -> Result<GeneralResponse>;
+
+    /// Count the posts of the feed which are first saved after the date.
+    fn new_post_count(&self, db: Database, since: DateTime<Utc>) -> Result<i64>;
 }
 
 /// A post is a piece of content containing one or more images, like a tweet or a Pixiv illustration.
@@ -221,6 +269,146 @@ pub enum Scheme {
     Optional(Box<Scheme>),
     Array(Box<Scheme>),
     Object(HashMap<String, Scheme>),
+    /// String of one of the choices.
+    Enum(Vec<SchemeChoice>),
+    /// Field of an object, with a human-readable label and the default value.
+    Field {
+        label: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        default: Option<serde_json::Value>,
+        scheme: Box<Scheme>,
+    },
+}
+
+impl Scheme {
+    /// Scheme of an object field with the label.
+    pub fn field(label: &str, scheme: Scheme) -> Scheme {
+        Scheme::Field {
+            label: label.to_string(),
+            default: None,
+            scheme: Box::new(scheme),
+        }
+    }
+
+    /// Set the default value of a field. Other schemes are returned as is.
+    pub fn with_default(self, value: serde_json::Value) -> Scheme {
+        match self {
+            Scheme::Field { label, scheme, .. } => Scheme::Field {
+                label,
+                default: Some(value),
+                scheme,
+            },
+            scheme => scheme,
+        }
+    }
+
+    /// Check the value against the scheme, with errors of the fields by their paths under `path`.
+    /// Missing fields are treated as null, which is valid for optional fields and fields with defaults.
+    pub fn validate(&self, value: &serde_json::Value, path: &str) -> Vec<FieldError> {
+        use serde_json::Value;
+
+        let invalid = |expected: &str| vec![FieldError::new(path, format!("Expected {}, got {}", expected, value))];
+        match (self, value) {
+            (Scheme::Null, _) => vec![],
+            (Scheme::Bool, Value::Bool(_)) => vec![],
+            (Scheme::Bool, _) => invalid("a boolean"),
+            (Scheme::Int, value) if value.as_i64().is_some_and(|n| i32::try_from(n).is_ok()) => vec![],
+            (Scheme::Int, _) => invalid("an integer"),
+            (Scheme::Bigint, value) if value.is_i64() => vec![],
+            (Scheme::Bigint, _) => invalid("an integer"),
+            (Scheme::Double, Value::Number(_)) => vec![],
+            (Scheme::Double, _) => invalid("a number"),
+            (Scheme::String, Value::String(_)) => vec![],
+            (Scheme::String, _) => invalid("a string"),
+            (Scheme::Optional(_), Value::Null) => vec![],
+            (Scheme::Optional(scheme), value) => scheme.validate(value, path),
+            (Scheme::Array(scheme), Value::Array(values)) => values
+                .iter()
+                .enumerate()
+                .flat_map(|(index, value)| scheme.validate(value, &format!("{}[{}]", path, index)))
+                .collect(),
+            (Scheme::Array(_), _) => invalid("an array"),
+            (Scheme::Object(fields), Value::Object(values)) => fields
+                .iter()
+                .flat_map(|(name, scheme)| {
+                    let value = values.get(name).unwrap_or(&Value::Null);
+                    scheme.validate(value, &format!("{}.{}", path, name))
+                })
+                .collect(),
+            (Scheme::Object(_), _) => invalid("an object"),
+            (Scheme::Enum(choices), Value::String(s)) if choices.iter().any(|choice| choice.value == *s) => vec![],
+            (Scheme::Enum(choices), _) => {
+                let values = choices.iter().map(|choice| choice.value.as_str()).collect::<Vec<_>>();
+                invalid(&format!("one of {}", values.join(", ")))
+            }
+            (Scheme::Field { default: Some(_), .. }, Value::Null) => vec![],
+            (Scheme::Field { scheme, .. }, value) => scheme.validate(value, path),
+        }
+    }
+
+    /// Scheme of strings from the choices of values and labels.
+    pub fn choices(choices: impl IntoIterator<Item = (impl ToString, impl ToString)>) -> Scheme {
+        let choices = choices
+            .into_iter()
+            .map(|(value, label)| SchemeChoice {
+                value: value.to_string(),
+                label: label.to_string(),
+            })
+            .collect();
+        Scheme::Enum(choices)
+    }
+}
+
+/// Get the string fields of a credential object after checking it against the scheme.
+/// The fields are trimmed and required to be non-empty, with errors of the fields under `path`.
+pub fn credential_fields<const N: usize>(
+    scheme: &Scheme,
+    value: &serde_json::Value,
+    path: &str,
+    names: [&str; N],
+) -> Result<[String; N]> {
+    let mut errors = scheme.validate(value, path);
+    let fields = names.map(|name| value[name].as_str().unwrap_or_default().trim().to_string());
+    for (name, field) in names.iter().zip(fields.iter()) {
+        let field_path = format!("{}.{}", path, name);
+        if field.is_empty() && !errors.iter().any(|e| e.field == field_path) {
+            errors.push(FieldError::new(field_path, "Empty value"));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(Error::InvalidFields(errors));
+    }
+    Ok(fields)
+}
+
+/// Choice of an enum scheme, with the value in requests and the label shown to users.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemeChoice {
+    pub value: String,
+    pub label: String,
+}
+
+/// Error of a field in a request, like feed params not matching the scheme.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    /// Path of the field in the request, e.g. `params.twitter.posts.user_id`.
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
 }
 
 /// Metadata of a community.
@@ -247,17 +435,148 @@ pub struct AccountMetadata {
 #[derive(Debug, Clone, Serialize)]
 pub struct FeedMetadata {
     pub name: String,
+    /// Human-readable name of the feed.
+    pub label: String,
+    /// Scheme of the params, with labels and default values of the fields.
     pub scheme: Scheme,
-    // TODO: Provide default params
     /// Indicates whether fetching the feed requires authentication.
     pub need_auth: bool,
 }
 
+/// App response of a feed group, which organizes feeds of any community like a folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedGroupView {
+    pub id: i32,
+    pub name: String,
+    pub position: i32,
+    pub added_date: DateTime<Utc>,
+    pub modified_date: DateTime<Utc>,
+}
+
+/// Feeds organized by their groups, both in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedTreeView {
+    pub groups: Vec<FeedGroupNode>,
+    /// Feeds not in any group.
+    pub ungrouped: Vec<FeedView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedGroupNode {
+    #[serde(flatten)]
+    pub group: FeedGroupView,
+    pub feeds: Vec<FeedView>,
+}
+
+/// App response of a post in the reading queue, which stacks posts to read later without adding them to the library.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueItemView {
+    pub id: i32,
+    pub community: String,
+    pub post_id: String,
+    pub position: i32,
+    pub added_date: DateTime<Utc>,
+}
+
+/// Items of the reading queue in order, along with their posts, media and users.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadingQueueView {
+    pub items: Vec<QueueItemView>,
+    #[serde(flatten)]
+    pub entities: GeneralResponse,
+}
+
 /// App response of an account.
 #[derive(Debug, Clone, Serialize)]
 pub struct AccountView {
     pub account_id: i32,
     pub community: String,
+    /// Favorite categories of the account, for communities that organize favorites into categories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favorite_categories: Option<Vec<FavoriteCategoryView>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FavoriteCategoryView {
+    pub index: i32,
+    pub name: String,
+    pub gallery_count: i32,
+}
+
+/// Usage of an account, summarized from the requests made with it.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountStatsView {
+    pub account_id: i32,
+    pub community: String,
+    pub request_count: i64,
+    pub rate_limit_count: i64,
+    pub error_count: i64,
+    pub feed_count: i64,
+    pub last_success_date: Option<DateTime<Utc>>,
+    /// Usage of each day recently, latest first.
+    pub days: Vec<AccountDailyUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountDailyUsage {
+    pub date: chrono::NaiveDate,
+    pub request_count: i64,
+    pub rate_limit_count: i64,
+    pub error_count: i64,
+}
+
+/// Posts saved to a feed, from the statistics refreshed periodically.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedStatsView {
+    pub community: String,
+    pub feed_id: i32,
+    pub post_count: i64,
+    /// Posts saved each day, earliest first. Days without posts are omitted.
+    pub days: Vec<FeedDailyPosts>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedDailyPosts {
+    pub date: chrono::NaiveDate,
+    pub post_count: i64,
+}
+
+/// Profile of a user of a community, with the feeds including the user, and the recently archived works.
+/// For communities without users, the user is the artist tag.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserProfileView {
+    pub community: String,
+    pub user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<UserView>,
+    /// Feeds including posts by the user, the most posts first.
+    pub feeds: Vec<UserFeedView>,
+    pub saved_post_count: i64,
+    pub archived_post_count: i64,
+    /// Recently archived posts by the user, along with their works, images and media.
+    pub recent: GeneralResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserFeedView {
+    #[serde(flatten)]
+    pub feed: FeedView,
+    pub post_count: i64,
+}
+
+/// A feed suggested from the library, for an artist with many archived works but no feed of their posts,
+/// or a frequent tag without a search feed.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedSuggestionView {
+    pub community: String,
+    /// Either `artist` or `tag`.
+    pub reason: String,
+    /// User ID, or the tag.
+    pub key: String,
+    pub name: Option<String>,
+    pub work_count: i64,
+    /// Feed parameters ready to submit for adding the feed.
+    pub params: serde_json::Value,
 }
 
 /// Account information in the database processed from the raw data from community.
@@ -279,14 +598,206 @@ pub struct FeedView {
     pub name: Option<String>,
     pub description: String,
     pub watching: bool,
+    pub icon_url: Option<String>,
+    /// Accent color in `#rrggbb`.
+    pub color: Option<String>,
+    /// Collage of the recent works in the library, relative to the image directory.
+    pub cover_path: Option<String>,
+    /// Error of the last update, if it failed.
+    pub last_error: Option<String>,
+    /// Number of consecutive failed updates.
+    pub failure_count: i32,
+    /// Whether posts without media are skipped when saving.
+    pub require_media: bool,
+    /// Minimum resolution of the largest media of the posts saved, on its longer side.
+    pub min_resolution: Option<i32>,
+}
+
+/// A webhook which is notified after new posts are saved,
+/// either of a feed, or of an artist by any feed of the community.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookView {
+    pub id: i32,
+    pub kind: WebhookKind,
+    pub community: String,
+    pub feed_id: Option<i32>,
+    pub user_id: Option<String>,
+    /// Webhook URL for generic and Discord webhooks, or the bot API server like `https://api.telegram.org` for Telegram.
+    pub url: String,
+    /// Chat to send messages to, only for Telegram.
+    pub chat_id: Option<String>,
+    /// Masked token of the Telegram bot, showing only its last characters.
+    pub bot_token: Option<String>,
+    /// Body template with placeholders, only for generic webhooks. If None, a default JSON summary is sent.
+    pub template: Option<String>,
+    pub added_date: DateTime<Utc>,
+}
+
+/// How the new posts are notified to a webhook.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookKind {
+    /// POST a JSON summary, or the rendered template.
+    #[default]
+    Generic,
+    /// Post messages with embedded thumbnails and links via a Discord webhook.
+    Discord,
+    /// Send photos with links via a Telegram bot.
+    Telegram,
+}
+
+impl WebhookKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookKind::Generic => "generic",
+            WebhookKind::Discord => "discord",
+            WebhookKind::Telegram => "telegram",
+        }
+    }
+}
+
+impl std::str::FromStr for WebhookKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "generic" => Ok(WebhookKind::Generic),
+            "discord" => Ok(WebhookKind::Discord),
+            "telegram" => Ok(WebhookKind::Telegram),
+            _ => Err(Error::UnknownField(format!("Webhook kind {}", s))),
+        }
+    }
+}
+
+/// A periodic email summarizing new posts of feeds, failed jobs and disk usage.
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestView {
+    pub id: i32,
+    pub email: String,
+    pub frequency: DigestFrequency,
+    pub last_sent_date: Option<DateTime<Utc>>,
+    pub added_date: DateTime<Utc>,
+    pub last_failed_date: Option<DateTime<Utc>>,
+    pub failure_count: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestFrequency::Daily => "daily",
+            DigestFrequency::Weekly => "weekly",
+        }
+    }
+
+    pub fn period(&self) -> chrono::Duration {
+        match self {
+            DigestFrequency::Daily => chrono::Duration::days(1),
+            DigestFrequency::Weekly => chrono::Duration::weeks(1),
+        }
+    }
+}
+
+impl std::str::FromStr for DigestFrequency {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "daily" => Ok(DigestFrequency::Daily),
+            "weekly" => Ok(DigestFrequency::Weekly),
+            _ => Err(Error::UnknownField(format!("Digest frequency {}", s))),
+        }
+    }
+}
+
+/// Readable name of a feed fetched from the community.
+#[derive(Debug, Clone)]
+pub struct FeedName {
+    pub name: String,
+    /// Avatar of the watched user or list, if any.
+    pub avatar_url: Option<String>,
 }
 
 /// General information needed to create or modify a feed.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeedInfo {
     pub name: Option<String>,
     pub watching: bool,
     pub first_fetch_limit: Option<i32>,
+    /// Resolved from the watched user or list if not provided when creating the feed.
+    /// Kept if not provided when modifying the feed.
+    pub icon_url: Option<String>,
+    /// Accent color in `#rrggbb`. Kept if not provided when modifying the feed.
+    pub color: Option<String>,
+    /// Remove the icon when modifying the feed.
+    #[serde(default)]
+    pub clear_icon: bool,
+    /// Remove the accent color when modifying the feed.
+    #[serde(default)]
+    pub clear_color: bool,
+    /// Skip posts without media when saving, like text-only tweets.
+    #[serde(default)]
+    pub require_media: bool,
+    /// Skip posts whose largest media is smaller than the resolution on its longer side when saving,
+    /// where the sizes are known from the fetched posts. Not supported by Panda.
+    #[serde(default)]
+    pub min_resolution: Option<i32>,
+}
+
+impl FeedInfo {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(min_resolution) = self.min_resolution.filter(|n| *n <= 0) {
+            return Err(Error::InvalidEndpoint(format!("Minimum resolution {}", min_resolution)));
+        }
+        if let Some(color) = &self.color {
+            let valid = color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+            if !valid {
+                return Err(Error::InvalidEndpoint(format!("Color {}", color)));
+            }
+        }
+        if self.clear_icon && self.icon_url.is_some() {
+            return Err(Error::InvalidEndpoint("Both icon and clearing it".to_string()));
+        }
+        if self.clear_color && self.color.is_some() {
+            return Err(Error::InvalidEndpoint("Both color and clearing it".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Change of the icon when modifying a feed: kept if `None`, removed if `Some(None)`.
+    pub fn icon_change(&self) -> Option<Option<String>> {
+        if self.clear_icon {
+            Some(None)
+        } else {
+            self.icon_url.clone().map(Some)
+        }
+    }
+
+    /// Change of the accent color when modifying a feed: kept if `None`, removed if `Some(None)`.
+    pub fn color_change(&self) -> Option<Option<String>> {
+        if self.clear_color {
+            Some(None)
+        } else {
+            self.color.clone().map(Some)
+        }
+    }
+}
+
+/// Whether the largest media is at least the minimum resolution on its longer side, or always if no minimum is given.
+/// Posts without media never meet a minimum.
+pub fn meets_min_resolution(min_resolution: Option<i32>, sizes: impl IntoIterator<Item = (u32, u32)>) -> bool {
+    let Some(min_resolution) = min_resolution else {
+        return true;
+    };
+    sizes
+        .into_iter()
+        .any(|(width, height)| i64::from(width.max(height)) >= i64::from(min_resolution))
 }
 
 /// App response of a post.
@@ -325,6 +836,9 @@ pub struct MediaView {
     pub height: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_url: Option<String>,
+    /// Whether the media is animated, if known from the community.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animated: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extra: Option<serde_json::Value>,
 }
@@ -348,6 +862,12 @@ pub struct UserView {
     pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub post_count: Option<i64>,
+    /// Other pages of the user, like homepages and social accounts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Vec<String>>,
+    /// Other names of the user in the community.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<Vec<String>>,
 }
 
 /// Generic response.
@@ -363,11 +883,38 @@ pub struct GeneralResponse {
     pub works: Option<Vec<WorkView>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub images: Option<Vec<ImageView>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<Vec<NoteView>>,
     pub total_items: i64,
     pub page: i64,
     pub page_size: i64,
 }
 
+/// Endpoint of ad-hoc browsing in the same shape for all communities,
+/// mapped by each community to its own feed params or API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endpoint {
+    /// Posts by a user, by ID or name depending on the community.
+    UserPosts { user_id: String },
+    /// Posts liked, bookmarked or favorited by a user, or by the default account if not given.
+    Likes { user_id: Option<String> },
+    /// Posts matching a query in the syntax of the community.
+    Search { query: String },
+    /// A single post with its media, paginated by the offset for posts with many media.
+    PostDetail { post_id: String },
+}
+
+impl Endpoint {
+    /// Parse an ID of a user or post in the endpoint.
+    pub fn parse_id<T: std::str::FromStr>(value: &str, name: &str) -> Result<T> {
+        value
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidEndpoint(format!("{} {}", name, value)))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct EndpointRequest<Params> {
     pub params: Params,
@@ -398,3 +945,114 @@ pub struct SaveResult {
     pub should_stop: bool,
     pub reached_end: bool,
 }
+
+/// Order of posts when listing a feed or the archived posts of a community, each in descending order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostOrder {
+    /// Order in which the posts are added to the feed, or the works to the library.
+    #[default]
+    Added,
+    /// Date when the posts are created on the community.
+    Created,
+    /// Rating of the archived works, with posts not archived last.
+    Rating,
+    /// Number of media in the posts.
+    MediaCount,
+}
+
+impl FromStr for PostOrder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "added" => Ok(PostOrder::Added),
+            "created" => Ok(PostOrder::Created),
+            "rating" => Ok(PostOrder::Rating),
+            "media_count" => Ok(PostOrder::MediaCount),
+            _ => Err(Error::UnknownField(format!("Post order {}", s))),
+        }
+    }
+}
+
+/// SQL of the post table of a community for ordering posts, e.g. `tweet.id` for the post ID.
+#[derive(Debug, Clone, Copy)]
+pub struct PostColumns {
+    pub community: &'static str,
+    pub table: &'static str,
+    pub post_id: &'static str,
+    pub created_date: &'static str,
+    pub media_count: &'static str,
+    /// Whether the post is expunged, only for communities marking them.
+    pub expunged: Option<&'static str>,
+}
+
+/// Filter of the posts in a feed.
+#[derive(Debug, Clone, Default)]
+pub struct PostFilter {
+    /// Whether the posts are archived in the library.
+    pub archived: Option<bool>,
+    /// Whether the posts are dismissed in the feed.
+    pub dismissed: Option<bool>,
+    /// Whether the posts are expunged from the community.
+    pub expunged: Option<bool>,
+}
+
+impl PostFilter {
+    /// SQL condition on the post table joining with the watch list table to filter posts.
+    /// It is safe to embed since it only contains fixed literals.
+    pub fn sql_condition(&self, columns: &PostColumns, watch_list_table: &str) -> String {
+        let mut conditions = Vec::new();
+        let exists = format!(
+            "EXISTS (SELECT 1 FROM work WHERE work.source = '{}' AND work.post_id_int = {})",
+            columns.community, columns.post_id
+        );
+        match self.archived {
+            Some(true) => conditions.push(exists),
+            Some(false) => conditions.push(format!("NOT {}", exists)),
+            None => {}
+        }
+        if let Some(dismissed) = self.dismissed {
+            conditions.push(format!("{}.dismissed = {}", watch_list_table, dismissed as i32));
+        }
+        if let (Some(expunged), Some(column)) = (self.expunged, columns.expunged) {
+            conditions.push(format!("{} = {}", column, expunged as i32));
+        }
+        if conditions.is_empty() {
+            return "1".to_string();
+        }
+        conditions.join(" AND ")
+    }
+}
+
+impl PostOrder {
+    /// SQL ordering of the posts in a feed, where `sort_index` is the order of the posts added to the feed.
+    /// It is safe to embed since it only contains fixed literals.
+    pub fn feed_sql(&self, columns: &PostColumns, sort_index: &str) -> String {
+        let key = match self {
+            PostOrder::Added => return format!("{} DESC", sort_index),
+            PostOrder::Created => columns.created_date.to_string(),
+            PostOrder::Rating => format!(
+                "(SELECT MAX(work.rating) FROM work WHERE work.source = '{}' AND work.post_id_int = {})",
+                columns.community, columns.post_id
+            ),
+            PostOrder::MediaCount => columns.media_count.to_string(),
+        };
+        format!("{} DESC, {} DESC", key, sort_index)
+    }
+
+    /// SQL ordering of the archived works of the community on the `work` table.
+    /// It is safe to embed since it only contains fixed literals.
+    pub fn work_sql(&self, columns: &PostColumns) -> String {
+        let key = match self {
+            PostOrder::Added => return "work.added_date DESC".to_string(),
+            PostOrder::Created => format!(
+                "(SELECT {} FROM {} WHERE {} = work.post_id_int)",
+                columns.created_date, columns.table, columns.post_id
+            ),
+            PostOrder::Rating => "work.rating".to_string(),
+            PostOrder::MediaCount => "work.image_count".to_string(),
+        };
+        format!("{} DESC, work.added_date DESC", key)
+    }
+}
@@ -1,7 +1,10 @@
+pub mod cache;
 pub mod error;
 pub mod feed;
 pub mod library;
+pub mod mute;
 pub mod schema;
+pub mod setting;
 
 pub use error::*;
 pub use feed::Database;
@@ -2,7 +2,11 @@
 // Many bare functions here mainly to operate Work and images.
 
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use std::str::FromStr;
+
+use crate::error::Error;
 
 /// Remote work means a work that is not downloaded yet.
 /// It can be earlier fetched by a community plugin, or manually added by the user.
@@ -16,6 +20,8 @@ pub struct RemoteWork {
     pub media_count: i32,
     pub name: Option<String>,
     pub caption: Option<String>,
+    /// Content rating mapped from the community-native rating. None if the community has no rating.
+    pub content_rating: Option<ContentRating>,
     pub images: Vec<RemoteImage>,
 }
 
@@ -34,6 +40,278 @@ impl std::fmt::Display for RemoteWork {
     }
 }
 
+/// Normalized content rating of a work, mapped from community-native ratings.
+/// Ordered from the least to the most explicit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentRating {
+    General,
+    Sensitive,
+    Explicit,
+}
+
+impl ContentRating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentRating::General => "general",
+            ContentRating::Sensitive => "sensitive",
+            ContentRating::Explicit => "explicit",
+        }
+    }
+}
+
+impl std::fmt::Display for ContentRating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ContentRating {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "general" => Ok(ContentRating::General),
+            "sensitive" => Ok(ContentRating::Sensitive),
+            "explicit" => Ok(ContentRating::Explicit),
+            _ => Err(Error::UnknownField(format!("Content rating {}", s))),
+        }
+    }
+}
+
+/// Variant of images to download, for communities which provide several sizes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageQuality {
+    /// The file as uploaded.
+    #[default]
+    Original,
+    /// Resized to fit 4096x4096 on Twitter, and the same as the original elsewhere.
+    High,
+    /// Resized for display, i.e. `large` on Twitter and 1200px master images on Pixiv.
+    Large,
+}
+
+/// Orientation of an image, by comparing its width and height.
+/// Images whose sides differ by no more than 5% count as square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    Portrait,
+    Landscape,
+    Square,
+}
+
+impl Orientation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Orientation::Portrait => "portrait",
+            Orientation::Landscape => "landscape",
+            Orientation::Square => "square",
+        }
+    }
+
+    /// SQL condition on the `image` table, for images whose size is known.
+    fn sql_condition(&self) -> &'static str {
+        match self {
+            Orientation::Portrait => "image.height > image.width * 1.05",
+            Orientation::Landscape => "image.width > image.height * 1.05",
+            Orientation::Square => "image.height <= image.width * 1.05 AND image.width <= image.height * 1.05",
+        }
+    }
+}
+
+impl std::fmt::Display for Orientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "portrait" => Ok(Orientation::Portrait),
+            "landscape" => Ok(Orientation::Landscape),
+            "square" => Ok(Orientation::Square),
+            _ => Err(Error::UnknownField(format!("Orientation {}", s))),
+        }
+    }
+}
+
+/// Hues of colors as a bitmask, to find works by the dominant colors of their images.
+/// The hue circle is split into 12 sectors of 30 degrees from red, and nearly gray colors have a bit of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HueSet(pub i32);
+
+impl HueSet {
+    const MONOCHROME: i32 = 1 << 12;
+
+    pub fn from_colors(colors: &[[u8; 3]]) -> Self {
+        HueSet(colors.iter().fold(0, |mask, color| mask | Self::color_bit(*color)))
+    }
+
+    fn color_bit([r, g, b]: [u8; 3]) -> i32 {
+        let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+        let max = r.max(g).max(b);
+        let delta = max - r.min(g).min(b);
+        // Too dark or too unsaturated to tell the hue
+        if max < 0.15 || delta / max < 0.2 {
+            return Self::MONOCHROME;
+        }
+        let hue = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        1 << ((hue / 30.0).round() as i32 % 12)
+    }
+}
+
+/// Parse a color name, or a hex color like `#ff8800` with the `#` optional.
+impl FromStr for HueSet {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let sectors: &[i32] = match s {
+            "red" => &[0],
+            "orange" => &[1],
+            "yellow" => &[2],
+            "green" => &[3, 4, 5],
+            "cyan" => &[6],
+            "blue" => &[7, 8],
+            "purple" => &[9, 10],
+            "pink" => &[11],
+            "monochrome" | "black" | "white" | "gray" | "grey" => return Ok(HueSet(Self::MONOCHROME)),
+            _ => {
+                let hex = s.strip_prefix('#').unwrap_or(s);
+                return match u32::from_str_radix(hex, 16) {
+                    Ok(rgb) if hex.len() == 6 => {
+                        Ok(Self::from_colors(&[[(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]]))
+                    }
+                    _ => Err(Error::UnknownField(format!("Color {}", s))),
+                };
+            }
+        };
+        Ok(HueSet(sectors.iter().fold(0, |mask, sector| mask | 1 << sector)))
+    }
+}
+
+/// Filter on works when listing them.
+#[derive(Debug, Clone, Default)]
+pub struct WorkFilter {
+    /// Only include works with any of the content ratings. `None` in the list stands for unrated works.
+    /// If the field is None, works of all ratings are included.
+    pub content_ratings: Option<Vec<Option<ContentRating>>>,
+    /// Only include works which have been viewed, or never viewed if false.
+    pub viewed: Option<bool>,
+    /// Only include works which are started but not finished reading, to continue reading.
+    pub in_progress: bool,
+    /// Only include works with all of the user tags.
+    pub user_tag_ids: Vec<i32>,
+    /// Only include works with an image of the orientation.
+    pub orientation: Option<Orientation>,
+    /// Only include works with an image at least this wide, in pixels.
+    pub min_width: Option<i32>,
+    /// Only include works with an image at least this tall, in pixels.
+    pub min_height: Option<i32>,
+    /// Only include works with an image whose aspect ratio (width / height) is at least this.
+    pub min_aspect: Option<f64>,
+    /// Only include works with an image whose aspect ratio (width / height) is at most this.
+    pub max_aspect: Option<f64>,
+    /// Only include works with an image having a dominant color of any of the hues.
+    pub color: Option<HueSet>,
+}
+
+impl WorkFilter {
+    /// SQL condition on the `work` table to filter works, for queries joining with it.
+    /// It is safe to embed since it only contains fixed literals.
+    pub fn sql_condition(&self) -> String {
+        let mut conditions = Vec::new();
+        if let Some(ratings) = &self.content_ratings {
+            conditions.push(Self::content_rating_condition(ratings));
+        }
+        match self.viewed {
+            Some(true) => conditions.push("work.viewed_date IS NOT NULL".to_string()),
+            Some(false) => conditions.push("work.viewed_date IS NULL".to_string()),
+            None => {}
+        }
+        if self.in_progress {
+            conditions.push("work.last_read_page < work.image_count - 1".to_string());
+        }
+        for tag_id in &self.user_tag_ids {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM work_user_tag WHERE work_user_tag.work_id = work.id AND work_user_tag.tag_id = {})",
+                tag_id
+            ));
+        }
+        if let Some(condition) = self.image_size_condition() {
+            conditions.push(condition);
+        }
+        if let Some(color) = self.color {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM image WHERE image.work_id = work.id AND image.palette_hues & {} != 0)",
+                color.0
+            ));
+        }
+        if conditions.is_empty() {
+            return "1".to_string();
+        }
+        conditions.join(" AND ")
+    }
+
+    /// Size conditions must all hold on the same image, whose width and height are known.
+    /// Image sizes are only known after downloading, so works not downloaded yet never match.
+    fn image_size_condition(&self) -> Option<String> {
+        let mut conditions = Vec::new();
+        if let Some(orientation) = self.orientation {
+            conditions.push(orientation.sql_condition().to_string());
+        }
+        if let Some(min_width) = self.min_width {
+            conditions.push(format!("image.width >= {}", min_width));
+        }
+        if let Some(min_height) = self.min_height {
+            conditions.push(format!("image.height >= {}", min_height));
+        }
+        if let Some(min_aspect) = self.min_aspect {
+            conditions.push(format!("CAST(image.width AS REAL) / image.height >= {:?}", min_aspect));
+        }
+        if let Some(max_aspect) = self.max_aspect {
+            conditions.push(format!("CAST(image.width AS REAL) / image.height <= {:?}", max_aspect));
+        }
+        if conditions.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "EXISTS (SELECT 1 FROM image WHERE image.work_id = work.id \
+             AND image.width IS NOT NULL AND image.height > 0 AND {})",
+            conditions.join(" AND ")
+        ))
+    }
+
+    fn content_rating_condition(ratings: &[Option<ContentRating>]) -> String {
+        let mut conditions = Vec::new();
+        let values = ratings
+            .iter()
+            .flatten()
+            .map(|r| format!("'{}'", r.as_str()))
+            .collect::<Vec<_>>();
+        if !values.is_empty() {
+            conditions.push(format!("work.content_rating IN ({})", values.join(", ")));
+        }
+        if ratings.iter().any(|r| r.is_none()) {
+            conditions.push("work.content_rating IS NULL".to_string());
+        }
+        if conditions.is_empty() {
+            return "0".to_string();
+        }
+        format!("({})", conditions.join(" OR "))
+    }
+}
+
 /// Remote image means an image of a work that is not downloaded yet.
 /// It can be earlier fetched by a community plugin, or manually added by the user.
 #[derive(Debug, Clone)]
@@ -58,11 +336,16 @@ pub struct WorkView {
     pub caption: Option<String>,
     pub favorite: bool,
     pub rating: i32,
+    pub content_rating: Option<ContentRating>,
     pub thumbnail_path: Option<String>,
     pub small_thumbnail_path: Option<String>,
     pub added_date: DateTime<Utc>,
     pub modified_date: DateTime<Utc>,
     pub viewed_date: Option<DateTime<Utc>>,
+    /// Index of the image last read in the work, to resume reading from.
+    pub last_read_page: Option<i32>,
+    /// Work which this work is a newer version of, e.g. a replaced gallery.
+    pub previous_work_id: Option<i32>,
 }
 
 /// A unified app response of an image.
@@ -80,6 +363,132 @@ pub struct ImageView {
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub size: Option<i32>,
+    pub checksum: Option<String>,
+    pub perceptual_hash: Option<String>,
+    /// Whether the image is an animated GIF, WebP or APNG.
+    pub is_animated: bool,
+    /// Dominant colors of the image in hex like `#ff8800`, most dominant first. None if not extracted yet.
+    pub palette: Option<Vec<String>>,
+}
+
+/// Works added to the library on a day from a community.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryActivityView {
+    pub date: chrono::NaiveDate,
+    /// None for works not from any community.
+    pub community: Option<String>,
+    pub work_count: i64,
+    pub image_count: i64,
+    /// Latest works added as covers.
+    pub covers: Vec<WorkView>,
+}
+
+/// Archived works of an artist, from the statistics refreshed periodically.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtistStatsView {
+    pub community: String,
+    /// User ID, or the artist tag for communities without users.
+    pub artist: String,
+    pub artist_name: Option<String>,
+    pub work_count: i64,
+    pub image_count: i64,
+    pub last_added_date: DateTime<Utc>,
+}
+
+/// Archived works with a tag, from the statistics refreshed periodically.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagStatsView {
+    pub community: String,
+    pub tag: String,
+    pub work_count: i64,
+    /// Works added each month, earliest first.
+    pub months: Vec<TagMonthlyWorks>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagMonthlyWorks {
+    /// In `YYYY-MM`.
+    pub month: String,
+    pub work_count: i64,
+}
+
+/// Result of the last check on the remote source of an archived work.
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteStatusView {
+    pub work_id: i32,
+    /// HTTP status code of the remote file.
+    pub status_code: i32,
+    pub checked_date: DateTime<Utc>,
+    /// When the remote source was first found vanished. None if it is still available.
+    pub vanished_date: Option<DateTime<Utc>>,
+}
+
+/// Archived works whose remote source has vanished, most recently vanished first.
+#[derive(Debug, Clone, Serialize)]
+pub struct VanishedReportView {
+    /// Number of vanished works by each artist, most first.
+    pub artists: Vec<VanishedArtistView>,
+    pub statuses: Vec<RemoteStatusView>,
+    #[serde(flatten)]
+    pub works: crate::feed::GeneralResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VanishedArtistView {
+    pub community: String,
+    pub artist: String,
+    pub artist_name: Option<String>,
+    pub work_count: i64,
+}
+
+/// Inconsistencies found by the repair pass at startup, and how they were handled.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RepairReportView {
+    /// Number of watch list posts left without sort index by interrupted feed updates, which are deleted.
+    pub unsorted_watch_list_posts: usize,
+    /// Works without any image, which are only reported since they may still have user data.
+    pub empty_work_ids: Vec<i32>,
+    /// Downloaded images whose file is missing, which are only reported unless resetting them is requested.
+    pub missing_file_image_ids: Vec<i32>,
+    /// Whether the images with missing files are reset to be downloaded again.
+    pub reset_missing_files: bool,
+    /// Number of community media rows whose post no longer exists, which are deleted.
+    pub orphaned_media: usize,
+}
+
+/// Text extracted from an image by OCR, e.g. the dialogue of a manga page.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageTextView {
+    pub image_id: i32,
+    pub work_id: i32,
+    pub page_index: Option<i32>,
+    pub content: String,
+    pub extracted_date: DateTime<Utc>,
+}
+
+/// Works with images whose text contains the query, most recently added first.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageTextSearchView {
+    /// Texts of the matching images of the works.
+    pub texts: Vec<ImageTextView>,
+    #[serde(flatten)]
+    pub works: crate::feed::GeneralResponse,
+}
+
+/// A link between the identities of an artist in two communities, suggested automatically and confirmed by the user.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtistLinkView {
+    pub id: i32,
+    pub community: String,
+    pub user_id: String,
+    pub linked_community: String,
+    /// User ID, or the username on Twitter if the user is not saved yet.
+    pub linked_user_id: String,
+    /// How the link was found.
+    pub reason: String,
+    /// One of `suggested`, `confirmed` and `rejected`.
+    pub status: String,
+    pub created_date: DateTime<Utc>,
 }
 
 /// A unified app response of an album.
@@ -91,6 +500,45 @@ pub struct AlbumView {
     pub position: i32,
     pub added_date: DateTime<Utc>,
     pub modified_date: DateTime<Utc>,
+    /// Collage of the recent works, relative to the image directory.
+    pub cover_path: Option<String>,
+    /// If present, the album is a smart album, whose works are the ones in the library matching the filter.
+    pub filter: Option<SmartAlbumFilter>,
+}
+
+/// Filter of a smart album, where all the given conditions must be met.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SmartAlbumFilter {
+    pub community: Option<String>,
+    /// Tags of the posts, all of which the works must have. Panda tags are given as `namespace:name`.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// User ID on Twitter and Pixiv, or the artist tag on Yandere and Panda.
+    pub artist: Option<String>,
+    /// Minimum rating of the works.
+    pub min_rating: Option<i32>,
+}
+
+/// A freeform note attached to a post or a work.
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteView {
+    pub id: i32,
+    pub community: Option<String>,
+    pub post_id: Option<String>,
+    pub work_id: Option<i32>,
+    pub content: String,
+    pub added_date: DateTime<Utc>,
+    pub modified_date: DateTime<Utc>,
+}
+
+/// A tag defined by the user to organize works, besides the tags from the communities.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserTagView {
+    pub id: i32,
+    pub name: String,
+    pub added_date: DateTime<Utc>,
+    /// Number of works with the tag.
+    pub work_count: i64,
 }
 
 /// A unified app response of a folder.
@@ -103,3 +551,31 @@ pub struct FolderView {
     pub added_date: DateTime<Utc>,
     pub modified_date: DateTime<Utc>,
 }
+
+/// Hierarchy of the folders and albums, with the folders and albums at the top level.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryTreeView {
+    pub folders: Vec<FolderNodeView>,
+    pub albums: Vec<AlbumNodeView>,
+}
+
+/// A folder in the library tree, with its subfolders and albums in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderNodeView {
+    #[serde(flatten)]
+    pub folder: FolderView,
+    /// Number of works in all the albums within the folder, counted once for each album it is in.
+    pub work_count: i64,
+    /// Cover of the first album within the folder that has one, relative to the image directory.
+    pub cover_path: Option<String>,
+    pub folders: Vec<FolderNodeView>,
+    pub albums: Vec<AlbumNodeView>,
+}
+
+/// An album in the library tree.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlbumNodeView {
+    #[serde(flatten)]
+    pub album: AlbumView,
+    pub work_count: i64,
+}
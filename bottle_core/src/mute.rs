@@ -0,0 +1,78 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+use crate::{
+    error::{Error, Result},
+    feed::Database,
+};
+
+/// User muted across all feeds, whose posts are skipped when any feed of the community is saved,
+/// like accounts reposting others' works or bots polluting timelines and searches.
+#[derive(Queryable, Selectable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::user_mute)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct UserMute {
+    pub community: String,
+    pub user_id: String,
+    pub reason: Option<String>,
+    pub created_date: NaiveDateTime,
+}
+
+impl UserMute {
+    /// Muted users of the community, or of all communities if not given, newest first.
+    pub fn all(db: Database, community: Option<&str>) -> Result<Vec<Self>> {
+        use crate::schema::user_mute;
+
+        let mut query = user_mute::table.into_boxed();
+        if let Some(community) = community {
+            query = query.filter(user_mute::community.eq(community.to_string()));
+        }
+        let mutes = query
+            .order(user_mute::created_date.desc())
+            .select(Self::as_select())
+            .load(db)?;
+        Ok(mutes)
+    }
+
+    /// IDs of the muted users of the community, to skip their posts when saving feeds.
+    pub fn user_ids(db: Database, community: &str) -> Result<HashSet<String>> {
+        use crate::schema::user_mute;
+
+        let user_ids = user_mute::table
+            .filter(user_mute::community.eq(community))
+            .select(user_mute::user_id)
+            .load::<String>(db)?;
+        Ok(user_ids.into_iter().collect())
+    }
+
+    /// Mute the user, or update the reason if already muted.
+    pub fn add(db: Database, community: &str, user_id: &str, reason: Option<&str>) -> Result<Self> {
+        use crate::schema::user_mute;
+
+        let mute = diesel::insert_into(user_mute::table)
+            .values((
+                user_mute::community.eq(community),
+                user_mute::user_id.eq(user_id),
+                user_mute::reason.eq(reason),
+            ))
+            .on_conflict((user_mute::community, user_mute::user_id))
+            .do_update()
+            .set(user_mute::reason.eq(reason))
+            .returning(Self::as_returning())
+            .get_result(db)?;
+        Ok(mute)
+    }
+
+    pub fn remove(db: Database, community: &str, user_id: &str) -> Result<()> {
+        use crate::schema::user_mute;
+
+        let count = diesel::delete(user_mute::table.find((community, user_id))).execute(db)?;
+        if count == 0 {
+            return Err(Error::ObjectNotFound(format!("Muted {} user {}", community, user_id)));
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,17 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    account_request (community, account_id, day) {
+        community -> Text,
+        account_id -> Integer,
+        day -> Date,
+        request_count -> Integer,
+        rate_limit_count -> Integer,
+        error_count -> Integer,
+        last_success_date -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     album (id) {
         id -> Integer,
@@ -8,6 +20,8 @@ diesel::table! {
         position -> Integer,
         added_date -> Timestamp,
         modified_date -> Timestamp,
+        cover_path -> Nullable<Text>,
+        filter -> Nullable<Text>,
     }
 }
 
@@ -19,6 +33,89 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    artist_link (id) {
+        id -> Integer,
+        community -> Text,
+        user_id -> Text,
+        linked_community -> Text,
+        linked_user_id -> Text,
+        reason -> Text,
+        status -> Text,
+        created_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    artist_stat (community, artist) {
+        community -> Text,
+        artist -> Text,
+        artist_name -> Nullable<Text>,
+        work_count -> Integer,
+        image_count -> Integer,
+        last_added_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    booru_upload (booru, image_id) {
+        booru -> Text,
+        image_id -> Integer,
+        remote_id -> Text,
+        uploaded_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    digest (id) {
+        id -> Integer,
+        email -> Text,
+        frequency -> Text,
+        last_sent_date -> Nullable<Timestamp>,
+        last_disk_usage -> Nullable<BigInt>,
+        added_date -> Timestamp,
+        last_failed_date -> Nullable<Timestamp>,
+        failure_count -> Integer,
+    }
+}
+
+diesel::table! {
+    duplicate_post (community, post_id) {
+        community -> Text,
+        post_id -> Text,
+        work_id -> Integer,
+        linked_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    feed_group (id) {
+        id -> Integer,
+        name -> Text,
+        position -> Integer,
+        added_date -> Timestamp,
+        modified_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    feed_group_feed (community, feed_id) {
+        community -> Text,
+        feed_id -> Integer,
+        group_id -> Integer,
+        position -> Integer,
+    }
+}
+
+diesel::table! {
+    feed_stat (community, feed_id, day) {
+        community -> Text,
+        feed_id -> Integer,
+        day -> Date,
+        post_count -> Integer,
+    }
+}
+
 diesel::table! {
     folder (id) {
         id -> Integer,
@@ -43,6 +140,20 @@ diesel::table! {
         width -> Nullable<Integer>,
         height -> Nullable<Integer>,
         size -> Nullable<Integer>,
+        checksum -> Nullable<Text>,
+        perceptual_hash -> Nullable<Text>,
+        is_animated -> Bool,
+        md5 -> Nullable<Text>,
+        palette -> Nullable<Text>,
+        palette_hues -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    image_text (image_id) {
+        image_id -> Integer,
+        content -> Text,
+        extracted_date -> Timestamp,
     }
 }
 
@@ -55,6 +166,38 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    panda_download_failure (gallery_id, media_index) {
+        gallery_id -> BigInt,
+        media_index -> Integer,
+        error -> Text,
+        failed_date -> Timestamp,
+        failure_count -> Integer,
+    }
+}
+
+diesel::table! {
+    panda_download_job (gallery_id) {
+        gallery_id -> BigInt,
+        title -> Text,
+        state -> Text,
+        total -> Integer,
+        success -> Integer,
+        error -> Nullable<Text>,
+        finished_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    panda_favorite_category (account_id, category_index) {
+        account_id -> Integer,
+        category_index -> Integer,
+        name -> Text,
+        gallery_count -> Integer,
+        updated_date -> Timestamp,
+    }
+}
+
 diesel::table! {
     panda_gallery (id) {
         id -> BigInt,
@@ -69,10 +212,16 @@ diesel::table! {
         parent -> Nullable<Text>,
         visible -> Nullable<Bool>,
         language -> Nullable<Text>,
-        file_size -> Nullable<Integer>,
         created_date -> Timestamp,
         added_date -> Timestamp,
         stale -> Bool,
+        invisible_reason -> Nullable<Text>,
+        newer_gallery_id -> Nullable<BigInt>,
+        newer_token -> Nullable<Text>,
+        checked_date -> Nullable<Timestamp>,
+        expunged -> Bool,
+        disowned -> Bool,
+        file_size -> Nullable<BigInt>,
     }
 }
 
@@ -115,6 +264,14 @@ diesel::table! {
         kind -> Text,
         query -> Nullable<Text>,
         reached_end -> Bool,
+        icon_url -> Nullable<Text>,
+        color -> Nullable<Text>,
+        cover_path -> Nullable<Text>,
+        mirror_album_id -> Nullable<Integer>,
+        backfill_offset -> Nullable<Text>,
+        last_error -> Nullable<Text>,
+        failure_count -> Integer,
+        languages -> Nullable<Text>,
     }
 }
 
@@ -124,6 +281,8 @@ diesel::table! {
         gallery_id -> BigInt,
         sort_index -> Nullable<Integer>,
         stale -> Bool,
+        dismissed -> Bool,
+        favorited_date -> Nullable<Timestamp>,
     }
 }
 
@@ -149,6 +308,7 @@ diesel::table! {
         name -> Nullable<Text>,
         username -> Nullable<Text>,
         profile_image_url -> Nullable<Text>,
+        locale -> Nullable<Text>,
     }
 }
 
@@ -167,6 +327,7 @@ diesel::table! {
         thumbnail_url -> Text,
         created_date -> Timestamp,
         added_date -> Timestamp,
+        caption_text -> Nullable<Text>,
     }
 }
 
@@ -201,6 +362,7 @@ diesel::table! {
         pawoo_url -> Nullable<Text>,
         twitter_username -> Nullable<Text>,
         added_date -> Timestamp,
+        profile_updated_date -> Nullable<Timestamp>,
     }
 }
 
@@ -218,6 +380,16 @@ diesel::table! {
         bookmark_tag -> Nullable<Text>,
         illust_type -> Nullable<Text>,
         reached_end -> Bool,
+        icon_url -> Nullable<Text>,
+        color -> Nullable<Text>,
+        cover_path -> Nullable<Text>,
+        search_sort -> Nullable<Text>,
+        search_target -> Nullable<Text>,
+        search_duration -> Nullable<Text>,
+        last_error -> Nullable<Text>,
+        failure_count -> Integer,
+        search_min_bookmarks -> Nullable<Integer>,
+        min_resolution -> Nullable<Integer>,
     }
 }
 
@@ -239,6 +411,55 @@ diesel::table! {
         private_bookmark -> Bool,
         stale -> Bool,
         sort_index -> Nullable<Integer>,
+        dismissed -> Bool,
+    }
+}
+
+diesel::table! {
+    post_note (id) {
+        id -> Integer,
+        community -> Nullable<Text>,
+        post_id -> Nullable<Text>,
+        work_id -> Nullable<Integer>,
+        content -> Text,
+        added_date -> Timestamp,
+        modified_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    reading_queue (id) {
+        id -> Integer,
+        community -> Text,
+        post_id -> Text,
+        position -> Integer,
+        added_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    remote_status (work_id) {
+        work_id -> Integer,
+        status_code -> Integer,
+        checked_date -> Timestamp,
+        vanished_date -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    setting (key) {
+        key -> Text,
+        value -> Text,
+        modified_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    tag_stat (community, tag, month) {
+        community -> Text,
+        tag -> Text,
+        month -> Text,
+        work_count -> Integer,
     }
 }
 
@@ -249,6 +470,7 @@ diesel::table! {
         caption -> Text,
         created_date -> Timestamp,
         added_date -> Timestamp,
+        text -> Nullable<Text>,
     }
 }
 
@@ -298,6 +520,13 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    twitter_tag (tweet_id, tag) {
+        tweet_id -> BigInt,
+        tag -> Text,
+    }
+}
+
 diesel::table! {
     twitter_user (id) {
         id -> BigInt,
@@ -312,6 +541,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    twitter_username_history (user_id, username) {
+        user_id -> BigInt,
+        username -> Text,
+        name -> Text,
+        first_seen_date -> Timestamp,
+    }
+}
+
 diesel::table! {
     twitter_watch_list (id) {
         id -> Integer,
@@ -324,6 +562,15 @@ diesel::table! {
         user_id -> Nullable<BigInt>,
         search_query -> Nullable<Text>,
         reached_end -> Bool,
+        icon_url -> Nullable<Text>,
+        color -> Nullable<Text>,
+        cover_path -> Nullable<Text>,
+        last_error -> Nullable<Text>,
+        failure_count -> Integer,
+        retweets -> Nullable<Text>,
+        search_min_likes -> Nullable<Integer>,
+        require_media -> Bool,
+        min_resolution -> Nullable<Integer>,
     }
 }
 
@@ -347,6 +594,39 @@ diesel::table! {
         tweet_id -> BigInt,
         sort_index -> Nullable<BigInt>,
         stale -> Bool,
+        dismissed -> Bool,
+    }
+}
+
+diesel::table! {
+    user_mute (community, user_id) {
+        community -> Text,
+        user_id -> Text,
+        reason -> Nullable<Text>,
+        created_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    user_tag (id) {
+        id -> Integer,
+        name -> Text,
+        added_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    webhook (id) {
+        id -> Integer,
+        kind -> Text,
+        community -> Text,
+        feed_id -> Nullable<Integer>,
+        user_id -> Nullable<Text>,
+        url -> Text,
+        chat_id -> Nullable<Text>,
+        bot_token -> Nullable<Text>,
+        template -> Nullable<Text>,
+        added_date -> Timestamp,
     }
 }
 
@@ -368,6 +648,28 @@ diesel::table! {
         added_date -> Timestamp,
         modified_date -> Timestamp,
         viewed_date -> Nullable<Timestamp>,
+        content_rating -> Nullable<Text>,
+        last_read_page -> Nullable<Integer>,
+        previous_work_id -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    work_user_tag (work_id, tag_id) {
+        work_id -> Integer,
+        tag_id -> Integer,
+        added_date -> Timestamp,
+    }
+}
+
+diesel::table! {
+    yandere_artist (id) {
+        id -> BigInt,
+        name -> Text,
+        alias_id -> Nullable<BigInt>,
+        group_id -> Nullable<BigInt>,
+        urls -> Text,
+        updated_date -> Timestamp,
     }
 }
 
@@ -440,6 +742,13 @@ diesel::table! {
         search_query -> Nullable<Text>,
         pool_id -> Nullable<Integer>,
         reached_end -> Bool,
+        icon_url -> Nullable<Text>,
+        color -> Nullable<Text>,
+        cover_path -> Nullable<Text>,
+        etag -> Nullable<Text>,
+        last_error -> Nullable<Text>,
+        failure_count -> Integer,
+        min_resolution -> Nullable<Integer>,
     }
 }
 
@@ -458,15 +767,24 @@ diesel::table! {
         watch_list_id -> Integer,
         post_id -> BigInt,
         sort_index -> Nullable<Integer>,
+        dismissed -> Bool,
     }
 }
 
 diesel::joinable!(album -> folder (folder_id));
 diesel::joinable!(album_work -> album (album_id));
 diesel::joinable!(album_work -> work (work_id));
+diesel::joinable!(booru_upload -> image (image_id));
+diesel::joinable!(duplicate_post -> work (work_id));
+diesel::joinable!(feed_group_feed -> feed_group (group_id));
 diesel::joinable!(image -> work (work_id));
+diesel::joinable!(image_text -> image (image_id));
+diesel::joinable!(panda_download_failure -> panda_gallery (gallery_id));
+diesel::joinable!(panda_download_job -> panda_gallery (gallery_id));
+diesel::joinable!(panda_favorite_category -> panda_account (account_id));
 diesel::joinable!(panda_gallery_tag -> panda_gallery (gallery_id));
 diesel::joinable!(panda_media -> panda_gallery (gallery_id));
+diesel::joinable!(panda_watch_list -> album (mirror_album_id));
 diesel::joinable!(panda_watch_list -> panda_account (account_id));
 diesel::joinable!(panda_watch_list_gallery -> panda_gallery (gallery_id));
 diesel::joinable!(panda_watch_list_gallery -> panda_watch_list (watch_list_id));
@@ -479,17 +797,23 @@ diesel::joinable!(pixiv_watch_list -> pixiv_user (user_id));
 diesel::joinable!(pixiv_watch_list_history -> pixiv_watch_list (watch_list_id));
 diesel::joinable!(pixiv_watch_list_illust -> pixiv_illust (illust_id));
 diesel::joinable!(pixiv_watch_list_illust -> pixiv_watch_list (watch_list_id));
+diesel::joinable!(post_note -> work (work_id));
+diesel::joinable!(remote_status -> work (work_id));
 diesel::joinable!(tweet -> twitter_user (user_id));
 diesel::joinable!(twitter_list -> twitter_user (user_id));
 diesel::joinable!(twitter_list_member -> twitter_list (list_id));
 diesel::joinable!(twitter_list_member -> twitter_user (user_id));
 diesel::joinable!(twitter_media -> tweet (tweet_id));
+diesel::joinable!(twitter_tag -> tweet (tweet_id));
+diesel::joinable!(twitter_username_history -> twitter_user (user_id));
 diesel::joinable!(twitter_watch_list -> twitter_account (account_id));
 diesel::joinable!(twitter_watch_list -> twitter_list (twitter_list_id));
 diesel::joinable!(twitter_watch_list -> twitter_user (user_id));
 diesel::joinable!(twitter_watch_list_history -> twitter_watch_list (watch_list_id));
 diesel::joinable!(twitter_watch_list_tweet -> tweet (tweet_id));
 diesel::joinable!(twitter_watch_list_tweet -> twitter_watch_list (watch_list_id));
+diesel::joinable!(work_user_tag -> user_tag (tag_id));
+diesel::joinable!(work_user_tag -> work (work_id));
 diesel::joinable!(yandere_pool_post -> yandere_pool (pool_id));
 diesel::joinable!(yandere_pool_post -> yandere_post (post_id));
 diesel::joinable!(yandere_post_tag -> yandere_post (post_id));
@@ -499,11 +823,24 @@ diesel::joinable!(yandere_watch_list_post -> yandere_post (post_id));
 diesel::joinable!(yandere_watch_list_post -> yandere_watch_list (watch_list_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    account_request,
     album,
     album_work,
+    artist_link,
+    artist_stat,
+    booru_upload,
+    digest,
+    duplicate_post,
+    feed_group,
+    feed_group_feed,
+    feed_stat,
     folder,
     image,
+    image_text,
     panda_account,
+    panda_download_failure,
+    panda_download_job,
+    panda_favorite_category,
     panda_gallery,
     panda_gallery_tag,
     panda_media,
@@ -519,16 +856,28 @@ diesel::allow_tables_to_appear_in_same_query!(
     pixiv_watch_list,
     pixiv_watch_list_history,
     pixiv_watch_list_illust,
+    post_note,
+    reading_queue,
+    remote_status,
+    setting,
+    tag_stat,
     tweet,
     twitter_account,
     twitter_list,
     twitter_list_member,
     twitter_media,
+    twitter_tag,
     twitter_user,
+    twitter_username_history,
     twitter_watch_list,
     twitter_watch_list_history,
     twitter_watch_list_tweet,
+    user_mute,
+    user_tag,
+    webhook,
     work,
+    work_user_tag,
+    yandere_artist,
     yandere_pool,
     yandere_pool_post,
     yandere_post,
@@ -0,0 +1,108 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{Error, Result},
+    feed::Database,
+};
+
+/// Settings changeable at runtime, stored in the `setting` table as JSON values.
+/// Unset settings fall back to the server config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    /// Number of images downloaded at the same time.
+    pub download_concurrency: Option<usize>,
+    /// Delay between fetching pages of a feed.
+    pub feed_update_delay_ms: Option<u64>,
+    /// Maximum number of pages fetched in an update of a feed, if the next update can continue from there.
+    pub max_pages_per_update: Option<usize>,
+    /// Delay between requests of a Panda gallery download.
+    pub panda_download_delay_ms: Option<u64>,
+    /// Whether to archive the newer version of an archived Panda gallery when it is found.
+    pub panda_follow_newer_versions: Option<bool>,
+}
+
+impl Settings {
+    pub fn load(db: Database) -> Result<Self> {
+        use crate::schema::setting;
+
+        let rows = setting::table
+            .select((setting::key, setting::value))
+            .load::<(String, String)>(db)?;
+        let mut map = Map::new();
+        for (key, value) in rows {
+            map.insert(key, serde_json::from_str(&value)?);
+        }
+        // Ignore settings no longer supported
+        let map = map.into_iter().filter(|(key, _)| Self::is_known(key)).collect();
+        Ok(serde_json::from_value(Value::Object(map))?)
+    }
+
+    /// Apply the changes to the stored settings. A null value resets the setting.
+    pub fn update(db: Database, changes: Map<String, Value>) -> Result<Self> {
+        use crate::schema::setting;
+
+        if let Some(key) = changes.keys().find(|key| !Self::is_known(key)) {
+            return Err(Error::InvalidEndpoint(format!("Settings: unknown setting {}", key)));
+        }
+        // Validate the changes against the stored settings
+        let mut merged = serde_json::to_value(Self::load(db)?)?
+            .as_object()
+            .cloned()
+            .unwrap_or_default();
+        merged.extend(changes.clone());
+        let settings: Self = serde_json::from_value(Value::Object(merged))
+            .map_err(|e| Error::InvalidEndpoint(format!("Settings: {}", e)))?;
+        if settings.download_concurrency == Some(0) {
+            return Err(Error::InvalidEndpoint(
+                "Settings: download_concurrency must be at least 1".to_string(),
+            ));
+        }
+
+        db.transaction(|conn| {
+            for (key, value) in changes {
+                if value.is_null() {
+                    diesel::delete(setting::table.find(&key)).execute(conn)?;
+                } else {
+                    let value = value.to_string();
+                    let now = chrono::Utc::now().naive_utc();
+                    diesel::insert_into(setting::table)
+                        .values((
+                            setting::key.eq(&key),
+                            setting::value.eq(&value),
+                            setting::modified_date.eq(now),
+                        ))
+                        .on_conflict(setting::key)
+                        .do_update()
+                        .set((setting::value.eq(&value), setting::modified_date.eq(now)))
+                        .execute(conn)?;
+                }
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(settings)
+    }
+
+    /// Whether the key is a field of the settings. Every field serializes, even when unset.
+    fn is_known(key: &str) -> bool {
+        serde_json::to_value(Self::default())
+            .ok()
+            .and_then(|value| value.as_object().map(|fields| fields.contains_key(key)))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_known() {
+        assert!(Settings::is_known("download_concurrency"));
+        assert!(Settings::is_known("panda_follow_newer_versions"));
+        assert!(!Settings::is_known("panda_job_retention_days"));
+        assert!(!Settings::is_known(""));
+    }
+}
@@ -0,0 +1,46 @@
+use image::imageops::{self, FilterType};
+use image::io::Reader as ImageReader;
+use image::{DynamicImage, RgbImage};
+
+use std::path::Path;
+
+use crate::error::Result;
+use crate::thumb::save_image;
+
+/// Size of each cell of a collage, same as the small thumbnails it is made of.
+const CELL_SIZE: u32 = 300;
+
+/// Number of rows and columns of a collage of the number of images, up to 3x3.
+fn grid_size(count: usize) -> u32 {
+    match count {
+        0 => 0,
+        1..=3 => 1,
+        4..=8 => 2,
+        _ => 3,
+    }
+}
+
+/// Composite the images into a square grid and save it as JPEG,
+/// where each image is cropped to fill its cell and images which cannot be opened are skipped.
+/// Returns false if no image can be opened.
+pub fn create_collage(image_paths: &[impl AsRef<Path>], output_path: impl AsRef<Path>) -> Result<bool> {
+    let images = image_paths
+        .iter()
+        .filter_map(|path| ImageReader::open(path).ok()?.with_guessed_format().ok()?.decode().ok())
+        .collect::<Vec<_>>();
+    let grid = grid_size(images.len());
+    if grid == 0 {
+        return Ok(false);
+    }
+
+    let mut canvas = RgbImage::new(grid * CELL_SIZE, grid * CELL_SIZE);
+    for (i, img) in images.iter().take((grid * grid) as usize).enumerate() {
+        let cell = img.resize_to_fill(CELL_SIZE, CELL_SIZE, FilterType::Triangle).to_rgb8();
+        let x = (i as u32 % grid) * CELL_SIZE;
+        let y = (i as u32 / grid) * CELL_SIZE;
+        imageops::replace(&mut canvas, &cell, x as i64, y as i64);
+    }
+
+    save_image(&DynamicImage::ImageRgb8(canvas), output_path)?;
+    Ok(true)
+}
@@ -0,0 +1,117 @@
+use std::io::Read;
+use std::path::Path;
+
+use image::{imageops::FilterType, DynamicImage};
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+
+/// Hashes of a file for finding the same or similar images.
+#[derive(Debug, Clone)]
+pub struct ImageHashes {
+    /// SHA-256 digest of the file content in hex.
+    pub checksum: String,
+    /// MD5 digest of the file content in hex.
+    pub md5: String,
+    /// Difference hash of the image in hex. None if the file cannot be decoded as an image.
+    pub perceptual_hash: Option<String>,
+}
+
+/// Compute the hashes of an arbitrary file content.
+pub fn hash_image(content: &[u8]) -> ImageHashes {
+    ImageHashes {
+        checksum: checksum(content),
+        md5: md5(content),
+        perceptual_hash: image::load_from_memory(content).ok().map(|img| perceptual_hash(&img)),
+    }
+}
+
+/// Compute the hashes of a file on disk. It reads and decodes the file, so should be run on a blocking thread.
+pub fn hash_file(path: impl AsRef<Path>) -> Result<ImageHashes> {
+    let path = path.as_ref();
+    let (checksum, md5) = file_digests(path)?;
+    Ok(ImageHashes {
+        checksum,
+        md5,
+        perceptual_hash: image::open(path).ok().map(|img| perceptual_hash(&img)),
+    })
+}
+
+/// Compute the SHA-256 and MD5 digests of a file in hex, streaming its content through the digests
+/// instead of reading the whole file into memory.
+pub fn file_digests(path: impl AsRef<Path>) -> Result<(String, String)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut sha256 = Sha256::new();
+    let mut md5 = md5::Context::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        sha256.update(&buf[..n]);
+        md5.consume(&buf[..n]);
+    }
+    let checksum = sha256.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    Ok((checksum, format!("{:x}", md5.compute())))
+}
+
+/// Compute the SHA-256 digest of the content in hex.
+pub fn checksum(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Compute the MD5 digest of the content in hex, as used by boorus to identify files.
+pub fn md5(content: &[u8]) -> String {
+    format!("{:x}", md5::compute(content))
+}
+
+/// Compute the 64-bit difference hash (dHash) of the image in hex.
+/// Visually similar images have hashes with a small Hamming distance.
+pub fn perceptual_hash(img: &DynamicImage) -> String {
+    let gray = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = gray.get_pixel(x, y).0[0];
+            let right = gray.get_pixel(x + 1, y).0[0];
+            hash = (hash << 1) | (left > right) as u64;
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+/// Hamming distance between two perceptual hashes in hex. None if any of them is invalid.
+pub fn hash_distance(a: &str, b: &str) -> Option<u32> {
+    let a = u64::from_str_radix(a, 16).ok()?;
+    let b = u64::from_str_radix(b, 16).ok()?;
+    Some((a ^ b).count_ones())
+}
+
+/// Replace the file at `relpath` with a hard link to the identical file at `existing_relpath`,
+/// so that the same content is stored only once on disk.
+/// Return false if nothing is done, i.e. both paths are the same or the existing file is missing.
+/// NOTE: All paths are relative to the root directory
+pub async fn link_duplicate(
+    root_dir: impl AsRef<Path>,
+    relpath: impl AsRef<Path>,
+    existing_relpath: impl AsRef<Path>,
+) -> Result<bool> {
+    let path = root_dir.as_ref().join(relpath);
+    let existing_path = root_dir.as_ref().join(existing_relpath);
+    if path == existing_path || !tokio::fs::try_exists(&existing_path).await? {
+        return Ok(false);
+    }
+
+    // Link to a temporary path first, so the file is never missing if linking fails
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".link");
+    let temp_path = path.with_file_name(temp_name);
+    if tokio::fs::try_exists(&temp_path).await? {
+        tokio::fs::remove_file(&temp_path).await?;
+    }
+    tokio::fs::hard_link(&existing_path, &temp_path).await?;
+    tokio::fs::rename(&temp_path, &path).await?;
+    Ok(true)
+}
@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// Free space in bytes available on the volume of the path, or `None` if not supported on the platform.
+/// If the path doesn't exist yet, its nearest existing ancestor is used.
+pub fn available_space(path: impl AsRef<Path>) -> Result<Option<u64>> {
+    let path = path.as_ref();
+    let Some(path) = path.ancestors().find(|path| path.exists()) else {
+        return Ok(None);
+    };
+    statvfs_available(path)
+}
+
+/// Fail if the free space on the volume of the path is less than required.
+pub fn ensure_space(path: impl AsRef<Path>, required: u64) -> Result<()> {
+    match available_space(path)? {
+        Some(available) if available < required => Err(Error::InsufficientSpace { required, available }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+fn statvfs_available(path: &Path) -> Result<Option<u64>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).map_err(std::io::Error::from)?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: The path is a valid C string, and `stat` is only read after the call succeeds.
+    let ret = unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    let stat = unsafe { stat.assume_init() };
+    #[allow(clippy::unnecessary_cast)]
+    Ok(Some(stat.f_bavail as u64 * stat.f_frsize as u64))
+}
+
+#[cfg(not(unix))]
+fn statvfs_available(_path: &Path) -> Result<Option<u64>> {
+    Ok(None)
+}
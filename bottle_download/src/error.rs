@@ -16,4 +16,10 @@ pub enum Error {
     JPEGError(#[from] jpeg_encoder::EncodingError),
     #[error("Incomplete download: {0}")]
     IncompleteDownload(String),
+    #[error("Unexpected status {status}: {url}")]
+    UnexpectedStatus { url: String, status: u16 },
+    #[error("Video error: {0}")]
+    VideoError(String),
+    #[error("Insufficient disk space: {required} bytes required, {available} bytes available")]
+    InsufficientSpace { required: u64, available: u64 },
 }
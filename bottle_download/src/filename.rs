@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::DownloadTask;
+
+/// Maximum length of a filename in bytes. Most filesystems allow 255 bytes,
+/// leaving room for suffixes like the thumbnail size `.1200.jpg` and the temp file `.part.validator`.
+pub const MAX_FILENAME_BYTES: usize = 200;
+
+const ILLEGAL_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1", "LPT2",
+    "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Make the filename safe on common filesystems:
+/// illegal and control characters are replaced by `_`, trailing dots and spaces are trimmed,
+/// reserved names on Windows are prefixed, and long names are truncated while keeping the extension.
+pub fn sanitize_filename(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || ILLEGAL_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    let name = name.trim().trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        return "_".to_string();
+    }
+
+    let (stem, extension) = split_extension(name);
+    let stem = if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        format!("_{}", stem)
+    } else {
+        stem.to_string()
+    };
+    format!("{}{}", truncate(&stem, MAX_FILENAME_BYTES - extension.len()), extension)
+}
+
+/// Filename of a page in a gallery, prefixed by the zero-padded index so that pages are sorted in order.
+pub fn indexed_filename(index: i32, count: i32, name: &str) -> String {
+    let width = count.to_string().len();
+    sanitize_filename(&format!("{:0width$}_{}", index, name, width = width))
+}
+
+/// Make the destination of the tasks unique, since different files may have the same name after sanitization.
+/// Names are compared case-insensitively for case-insensitive filesystems,
+/// and a colliding filename is suffixed like `name_1.jpg` in the order of the tasks.
+/// Only the tasks in the batch are checked, not the files already in `subdir`, which are mostly the same images
+/// downloaded before and overwritten on purpose.
+pub fn resolve_collisions(tasks: &mut [DownloadTask]) {
+    // Destination to the URL taking it
+    let mut taken = HashMap::<PathBuf, String>::new();
+    for task in tasks.iter_mut() {
+        if claim(&mut taken, &task.subdir, &task.filename, &task.url) {
+            continue;
+        }
+        let name = task.filename.to_string_lossy().to_string();
+        let (stem, extension) = split_extension(&name);
+        for n in 1.. {
+            let suffix = format!("_{}", n);
+            let stem = truncate(stem, MAX_FILENAME_BYTES - extension.len() - suffix.len());
+            let filename = PathBuf::from(format!("{}{}{}", stem, suffix, extension));
+            if claim(&mut taken, &task.subdir, &filename, &task.url) {
+                task.filename = filename;
+                break;
+            }
+        }
+    }
+}
+
+/// Take the destination for the URL, return false if it's taken by another URL.
+fn claim(taken: &mut HashMap<PathBuf, String>, subdir: &Path, filename: &Path, url: &str) -> bool {
+    let key = subdir.join(filename.to_string_lossy().to_lowercase());
+    match taken.get(&key) {
+        Some(other) => other == url,
+        None => {
+            taken.insert(key, url.to_string());
+            true
+        }
+    }
+}
+
+/// Split the name into the stem and the extension with the dot, e.g. `("name", ".jpg")`.
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(i) if i > 0 && name.len() - i <= 10 => name.split_at(i),
+        _ => (name, ""),
+    }
+}
+
+/// Truncate the string to at most `max_bytes` bytes on a char boundary.
+fn truncate(s: &str, max_bytes: usize) -> &str {
+    if s.len() <= max_bytes {
+        return s;
+    }
+    let mut end = max_bytes;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s[..end].trim_end_matches(['.', ' '])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn task(subdir: &str, filename: &str, url: &str) -> DownloadTask {
+        DownloadTask {
+            url: url.to_string(),
+            root_dir: PathBuf::from("/images"),
+            subdir: PathBuf::from(subdir),
+            filename: PathBuf::from(filename),
+            image_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        let cases = [
+            ("image.jpg", "image.jpg"),
+            ("..", "_"),
+            (".", "_"),
+            ("", "_"),
+            ("../image.jpg", ".._image.jpg"),
+            ("a/b\\c:d.png", "a_b_c_d.png"),
+            ("tab\tname.jpg", "tab_name.jpg"),
+            ("name. . ", "name"),
+            ("con.jpg", "_con.jpg"),
+            ("LPT1", "_LPT1"),
+            ("console.jpg", "console.jpg"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(sanitize_filename(name), expected, "sanitizing {:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_truncate_multibyte() {
+        let name = format!("{}.jpg", "あ".repeat(100));
+        let sanitized = sanitize_filename(&name);
+        assert!(sanitized.len() <= MAX_FILENAME_BYTES);
+        assert!(sanitized.ends_with(".jpg"));
+        // 196 bytes are left for the stem, which holds 65 chars of 3 bytes
+        assert_eq!(sanitized, format!("{}.jpg", "あ".repeat(65)));
+
+        assert_eq!(truncate("aあ", 2), "a");
+        assert_eq!(truncate("abc", 3), "abc");
+        assert_eq!(truncate("ab. c", 4), "ab");
+    }
+
+    #[test]
+    fn test_resolve_collisions() {
+        let mut tasks = vec![
+            task("pixiv/1", "Image.jpg", "https://example.com/1"),
+            task("pixiv/1", "image.jpg", "https://example.com/2"),
+            task("pixiv/1", "IMAGE.JPG", "https://example.com/3"),
+            task("pixiv/1", "Image.jpg", "https://example.com/1"),
+            task("pixiv/2", "image.jpg", "https://example.com/4"),
+            task("pixiv/1", "image_1.jpg", "https://example.com/5"),
+        ];
+        resolve_collisions(&mut tasks);
+        let filenames = tasks
+            .iter()
+            .map(|task| task.filename.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            filenames,
+            [
+                "Image.jpg",
+                "image_1.jpg",
+                "IMAGE_2.JPG",
+                "Image.jpg",
+                "image.jpg",
+                "image_1_1.jpg"
+            ]
+        );
+    }
+}
@@ -1,7 +1,17 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+use image::DynamicImage;
+use tokio::io::AsyncWriteExt;
+
+use bottle_util::http::client_builder;
+
+use crate::dedup;
 use crate::error::{Error, Result};
-use crate::thumb::{create_thumbnail, get_default_thumbnail_relpath, open_image_bytes, save_image};
+use crate::palette::dominant_colors;
+use crate::thumb::{
+    create_thumbnail, get_default_thumbnail_relpath, is_animated_file, open_image_file, save_image, video_poster_frame,
+};
 use crate::{DownloadTask, LocalImage};
 
 const VIDEO_EXTENSIONS: [&str; 8] = ["mp4", "webm", "mkv", "avi", "flv", "mov", "wmv", "m4v"];
@@ -16,86 +26,267 @@ fn get_extension(path: impl AsRef<Path>) -> String {
         .to_lowercase()
 }
 
-async fn fetch(url: &str) -> Result<reqwest::Response> {
-    let response = if url.contains("pximg.net") {
+/// Whether the file is a video by its extension, which has no image dimensions and only a poster frame as thumbnails.
+pub fn is_video(path: impl AsRef<Path>) -> bool {
+    VIDEO_EXTENSIONS.contains(&get_extension(path).as_str())
+}
+
+/// Shared by all downloads, so that images from the same host reuse connections.
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+fn request(method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+    let client = CLIENT.get_or_init(|| client_builder().build().unwrap_or_default());
+    let request = client.request(method, url);
+    if url.contains("pximg.net") {
         // Workaround for Pixiv
-        let client = reqwest::Client::new();
-        client
-            .get(url)
-            .header("Referer", "https://www.pixiv.net/")
-            .send()
-            .await?
+        request.header("Referer", "https://www.pixiv.net/")
     } else {
-        reqwest::get(url).await?
-    };
+        request
+    }
+}
+
+async fn fetch(url: &str) -> Result<reqwest::Response> {
+    let response = request(reqwest::Method::GET, url).send().await?;
+    Ok(response)
+}
+
+/// Request the content from the byte offset with HTTP Range, or the whole content if the offset is 0.
+/// The range is conditional on the validator of the content fetched before,
+/// so that the whole content is sent instead if the remote file has changed since.
+async fn fetch_from(url: &str, offset: u64, validator: &str) -> Result<reqwest::Response> {
+    if offset == 0 {
+        return fetch(url).await;
+    }
+    let response = request(reqwest::Method::GET, url)
+        .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+        .header(reqwest::header::IF_RANGE, validator)
+        .send()
+        .await?;
     Ok(response)
 }
 
+/// Validator of the response to be sent back in `If-Range`, which is a strong `ETag` or `Last-Modified`.
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    let headers = response.headers();
+    let etag = headers
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.starts_with("W/"));
+    etag.or_else(|| {
+        headers
+            .get(reqwest::header::LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+    })
+    .map(|value| value.to_string())
+}
+
+/// Whether the partial response starts at the byte offset, by its `Content-Range` like `bytes 100-999/1000`.
+fn starts_at(response: &reqwest::Response, offset: u64) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("bytes "))
+        .and_then(|value| value.split_once('-'))
+        .and_then(|(start, _)| start.parse::<u64>().ok())
+        == Some(offset)
+}
+
+/// Status code of a HEAD request to the remote file, to tell if it still exists without downloading it.
+pub async fn probe_remote(url: &str) -> Result<u16> {
+    let response = request(reqwest::Method::HEAD, url).send().await?;
+    Ok(response.status().as_u16())
+}
+
+/// Image fetched and saved to disk, whose dimension, hashes and thumbnails are not yet processed.
+#[derive(Debug, Clone)]
+pub struct FetchedImage {
+    /// Path of the saved file.
+    pub path: PathBuf,
+    pub size: u64,
+    pub mime_type: Option<String>,
+    /// Whether the file already exists and is not downloaded again.
+    pub existing: bool,
+}
+
 /// Download an image, return the local image.
 pub async fn download_image(task: &DownloadTask, overwrite: bool) -> Result<LocalImage> {
-    // 1. If not overwrite, and the file exists, directly return the local image info
+    let fetched = fetch_image(task, overwrite).await?;
+    let task = task.clone();
+    tokio::task::spawn_blocking(move || process_image(&task, &fetched))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Download an image and save it to the destination, without processing it.
+pub async fn fetch_image(task: &DownloadTask, overwrite: bool) -> Result<FetchedImage> {
+    // 1. If not overwrite, and the file exists, use the file as is
     let dest_path = task.root_dir.join(&task.subdir).join(&task.filename);
     if !overwrite && tokio::fs::try_exists(&dest_path).await? {
-        return get_local_image_info(task).await;
+        return Ok(FetchedImage {
+            size: tokio::fs::metadata(&dest_path).await?.len(),
+            path: dest_path,
+            mime_type: None,
+            existing: true,
+        });
     }
 
-    // 2. Send request to the URL
-    let mut response = fetch(&task.url).await?;
-    let content_length = response.content_length();
+    // 2. Send request to the URL, resuming the temp file left by a previous attempt if any
+    let dir = task.root_dir.join(&task.subdir);
+    if !tokio::fs::try_exists(&dir).await? {
+        tokio::fs::create_dir_all(&dir).await?;
+    }
+    let part_path = part_path(&dest_path);
+    let validator_path = validator_path(&part_path);
+    // A temp file can only be resumed if the content it came from can be validated
+    let validator = tokio::fs::read_to_string(&validator_path).await.ok();
+    let offset = match (&validator, tokio::fs::metadata(&part_path).await) {
+        (Some(_), Ok(metadata)) => metadata.len(),
+        _ => 0,
+    };
+    let mut response = fetch_from(&task.url, offset, validator.as_deref().unwrap_or_default()).await?;
+    if offset > 0
+        && (response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE
+            || response.status() == reqwest::StatusCode::PARTIAL_CONTENT && !starts_at(&response, offset))
+    {
+        // The temp file doesn't match the remote file, start over
+        response = fetch(&task.url).await?;
+    }
+    let resumed = match response.status() {
+        reqwest::StatusCode::PARTIAL_CONTENT if offset > 0 && starts_at(&response, offset) => true,
+        reqwest::StatusCode::PARTIAL_CONTENT => return Err(unexpected_status(&task.url, &response)),
+        // The whole content is sent, since the remote file has changed or doesn't support ranges
+        status if status.is_success() => false,
+        // Keep the temp file for the next attempt, never overwrite it with an error page
+        _ => return Err(unexpected_status(&task.url, &response)),
+    };
+    if !resumed {
+        match response_validator(&response) {
+            Some(validator) => tokio::fs::write(&validator_path, validator).await?,
+            None => remove_if_exists(&validator_path).await?,
+        }
+    }
+    let offset = if resumed { offset } else { 0 };
+    let expected_size = response.content_length().map(|length| offset + length);
     let mime_type = response
         .headers()
         .get(reqwest::header::CONTENT_TYPE)
         .and_then(|value| value.to_str().ok())
         .map(|s| s.to_string());
 
-    // 3. Download the image, write to a buffer
-    let mut buffer = Vec::new();
-    while let Some(chunk) = response.chunk().await? {
-        buffer.extend_from_slice(&chunk);
-    }
+    // 3. Download the image to the temp file next to the destination.
+    // If interrupted, the temp file is kept to be resumed next time.
+    let size = write_part(&mut response, &part_path, resumed).await?;
 
     // 4. Check if the file is complete
-    let size = buffer.len() as u64;
-    if let Some(content_length) = content_length {
-        if size != content_length {
-            return Err(Error::IncompleteDownload(task.url.clone()));
+    if expected_size.is_some_and(|expected_size| size != expected_size) || size == 0 {
+        // A temp file longer than expected is broken, never resume it
+        if expected_size.is_none_or(|expected_size| size > expected_size) {
+            tokio::fs::remove_file(&part_path).await?;
+            remove_if_exists(&validator_path).await?;
         }
-    }
-    if size == 0 {
         return Err(Error::IncompleteDownload(task.url.clone()));
     }
 
-    // 5. Save the temp file to the destination
-    let dir = task.root_dir.join(&task.subdir);
-    if !tokio::fs::try_exists(&dir).await? {
-        tokio::fs::create_dir_all(&dir).await?;
+    // 5. Move the temp file to the destination
+    // The destination may be a hard link shared with another image, so unlink it instead of writing through it
+    if tokio::fs::try_exists(&dest_path).await? {
+        tokio::fs::remove_file(&dest_path).await?;
     }
-    tokio::fs::write(&dest_path, &buffer).await?;
+    tokio::fs::rename(&part_path, &dest_path).await?;
+    remove_if_exists(&validator_path).await?;
+
+    Ok(FetchedImage {
+        path: dest_path,
+        size,
+        mime_type,
+        existing: false,
+    })
+}
+
+/// Path of the temp file being downloaded, like `name.jpg.part`.
+fn part_path(path: &Path) -> PathBuf {
+    let mut part_path = path.as_os_str().to_owned();
+    part_path.push(".part");
+    PathBuf::from(part_path)
+}
+
+/// Path of the validator of the temp file, like `name.jpg.part.validator`.
+fn validator_path(part_path: &Path) -> PathBuf {
+    let mut validator_path = part_path.as_os_str().to_owned();
+    validator_path.push(".validator");
+    PathBuf::from(validator_path)
+}
+
+async fn remove_if_exists(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn unexpected_status(url: &str, response: &reqwest::Response) -> Error {
+    Error::UnexpectedStatus {
+        url: url.to_string(),
+        status: response.status().as_u16(),
+    }
+}
+
+/// Write the response body to the temp file, or append to it if resumed, and return the size of the whole file.
+async fn write_part(response: &mut reqwest::Response, path: &Path, resumed: bool) -> Result<u64> {
+    let (mut file, mut size) = if resumed {
+        let size = tokio::fs::metadata(path).await?.len();
+        let file = tokio::fs::OpenOptions::new().append(true).open(path).await?;
+        (file, size)
+    } else {
+        (tokio::fs::File::create(path).await?, 0)
+    };
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        size += chunk.len() as u64;
+    }
+    file.sync_all().await?;
+    Ok(size)
+}
+
+/// Get the dimension and hashes of a fetched image, and generate its thumbnails.
+/// It is CPU intensive, so should be run on a blocking thread.
+pub fn process_image(task: &DownloadTask, fetched: &FetchedImage) -> Result<LocalImage> {
+    // 1. Compute the checksum for finding duplicates
+    let (checksum, md5) = dedup::file_digests(&fetched.path)?;
+    let (checksum, md5) = (Some(checksum), Some(md5));
 
     let extension = get_extension(&task.filename);
     let (mut width, mut height) = (None, None);
     let mut thumbnail_relpath = None;
     let mut small_thumbnail_relpath = None;
+    let mut perceptual_hash = None;
+    let mut animated = false;
+    let mut palette = None;
 
     // If the file is not a video, get the dimension of the image and generate thumbnails
     if !VIDEO_EXTENSIONS.contains(&extension.as_str()) {
-        // 7. Get the dimension of the image
-        let img = open_image_bytes(&buffer, &task.filename, mime_type.as_deref())?;
+        // 2. Get the dimension and perceptual hash of the image
+        let img = open_image_file(&fetched.path, fetched.mime_type.as_deref())?;
         width = Some(img.width());
         height = Some(img.height());
+        perceptual_hash = Some(dedup::perceptual_hash(&img));
+        animated = is_animated_file(&fetched.path);
 
-        // 8. Generate thumbnails
-        // 8.1. Large thumbnail
-        let thumbnail = create_thumbnail(&img, THUMBNAIL_SIZE, THUMBNAIL_SIZE);
-        let thumb_relpath = get_default_thumbnail_relpath(&task.subdir, &task.filename, THUMBNAIL_SIZE)?;
-        save_image(&thumbnail, task.root_dir.join(&thumb_relpath))?;
-        thumbnail_relpath = Some(thumb_relpath.to_string_lossy().to_string());
-
-        // 8.2. Small thumbnail
-        let thumbnail = create_thumbnail(&img, SMALL_THUMBNAIL_SIZE, SMALL_THUMBNAIL_SIZE);
-        let thumb_relpath = get_default_thumbnail_relpath(&task.subdir, &task.filename, SMALL_THUMBNAIL_SIZE)?;
-        save_image(&thumbnail, task.root_dir.join(&thumb_relpath))?;
-        small_thumbnail_relpath = Some(thumb_relpath.to_string_lossy().to_string());
+        // 3. Generate thumbnails and extract the dominant colors, from the first frame if animated
+        thumbnail_relpath = Some(save_thumbnail(task, &img, THUMBNAIL_SIZE, fetched.existing)?);
+        small_thumbnail_relpath = Some(save_thumbnail(task, &img, SMALL_THUMBNAIL_SIZE, fetched.existing)?);
+        palette = Some(dominant_colors(&img));
+    } else {
+        // For videos, generate thumbnails from a poster frame if possible, which needs `ffmpeg`.
+        // Failing to extract it doesn't fail the download, the video is just left without thumbnails.
+        if let Ok(frame) = video_poster_frame(&fetched.path) {
+            width = Some(frame.width());
+            height = Some(frame.height());
+            thumbnail_relpath = Some(save_thumbnail(task, &frame, THUMBNAIL_SIZE, fetched.existing)?);
+            small_thumbnail_relpath = Some(save_thumbnail(task, &frame, SMALL_THUMBNAIL_SIZE, fetched.existing)?);
+            palette = Some(dominant_colors(&frame));
+        }
     }
 
     // NOTE: All paths are relative to the root directory
@@ -106,21 +297,47 @@ pub async fn download_image(task: &DownloadTask, overwrite: bool) -> Result<Loca
         small_thumbnail_relpath,
         width,
         height,
-        size,
+        size: fetched.size,
+        checksum,
+        perceptual_hash,
+        animated,
+        md5,
+        palette,
     })
 }
 
+/// Save the thumbnail at the default path, and return its relpath.
+/// Thumbnails of an existing file are kept if already generated.
+fn save_thumbnail(task: &DownloadTask, img: &DynamicImage, size: u32, existing: bool) -> Result<String> {
+    let relpath = get_default_thumbnail_relpath(&task.subdir, &task.filename, size)?;
+    let path = task.root_dir.join(&relpath);
+    if !existing || !path.exists() {
+        save_image(&create_thumbnail(img, size, size), path)?;
+    }
+    Ok(relpath.to_string_lossy().to_string())
+}
+
 pub async fn get_local_image_info(task: &DownloadTask) -> Result<LocalImage> {
     let image_path = task.root_dir.join(&task.subdir).join(&task.filename);
     let extension = get_extension(&task.filename);
 
-    let (mut width, mut height) = (None, None);
-    if !VIDEO_EXTENSIONS.contains(&extension.as_str()) {
-        let (w, h) = image::image_dimensions(&image_path)?;
-        width = Some(w);
-        height = Some(h);
-    }
-    let size = tokio::fs::metadata(&image_path).await?.len();
+    // Decode and hash the file on a blocking thread
+    let (width, height, size, hashes, animated) = {
+        let image_path = image_path.clone();
+        tokio::task::spawn_blocking(move || -> Result<_> {
+            let (mut width, mut height) = (None, None);
+            if !VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+                let (w, h) = image::image_dimensions(&image_path)?;
+                width = Some(w);
+                height = Some(h);
+            }
+            let size = std::fs::metadata(&image_path)?.len();
+            let hashes = dedup::hash_file(&image_path)?;
+            Ok((width, height, size, hashes, is_animated_file(&image_path)))
+        })
+        .await
+        .map_err(std::io::Error::other)??
+    };
 
     // Find thumbnails at inferred paths
     let thumbnail_relpath = get_default_thumbnail_relpath(&task.subdir, &task.filename, THUMBNAIL_SIZE)?;
@@ -141,5 +358,10 @@ pub async fn get_local_image_info(task: &DownloadTask) -> Result<LocalImage> {
         width,
         height,
         size,
+        checksum: Some(hashes.checksum),
+        perceptual_hash: hashes.perceptual_hash,
+        animated,
+        md5: Some(hashes.md5),
+        palette: None,
     })
 }
@@ -1,9 +1,19 @@
+mod collage;
+mod dedup;
+mod disk;
 mod error;
+mod filename;
 mod harvest;
+mod palette;
 mod thumb;
 
+pub use collage::*;
+pub use dedup::*;
+pub use disk::*;
 pub use error::Error;
+pub use filename::*;
 pub use harvest::*;
+pub use palette::*;
 
 use std::path::PathBuf;
 
@@ -29,4 +39,14 @@ pub struct LocalImage {
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub size: u64,
+    /// SHA-256 digest of the file content in hex.
+    pub checksum: Option<String>,
+    /// Difference hash of the image in hex, only available for images.
+    pub perceptual_hash: Option<String>,
+    /// Whether the image is an animated GIF, WebP or APNG.
+    pub animated: bool,
+    /// MD5 digest of the file content in hex, which boorus use to identify files.
+    pub md5: Option<String>,
+    /// Dominant colors of the image, most dominant first, extracted when generating thumbnails.
+    pub palette: Option<Vec<[u8; 3]>>,
 }
@@ -0,0 +1,31 @@
+use image::{imageops::FilterType, DynamicImage};
+
+/// Maximum number of dominant colors of an image.
+const PALETTE_SIZE: usize = 5;
+/// Minimum share of pixels for a color to count as dominant.
+const MIN_COLOR_SHARE: f64 = 0.05;
+
+/// Extract the dominant colors of the image, most dominant first.
+/// Pixels of a downscaled image are grouped into a coarse 8x8x8 RGB grid, and each dominant cell gives its average color.
+pub fn dominant_colors(img: &DynamicImage) -> Vec<[u8; 3]> {
+    let rgb = img.resize_exact(32, 32, FilterType::Triangle).to_rgb8();
+    let mut cells = vec![(0u32, [0u32; 3]); 512];
+    for pixel in rgb.pixels() {
+        let [r, g, b] = pixel.0;
+        let index = ((r >> 5) as usize) << 6 | ((g >> 5) as usize) << 3 | (b >> 5) as usize;
+        let (count, sum) = &mut cells[index];
+        *count += 1;
+        for (sum, value) in sum.iter_mut().zip(pixel.0) {
+            *sum += value as u32;
+        }
+    }
+
+    let total = rgb.pixels().len() as f64;
+    cells.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    cells
+        .into_iter()
+        .take(PALETTE_SIZE)
+        .filter(|(count, _)| *count > 0 && *count as f64 / total >= MIN_COLOR_SHARE)
+        .map(|(count, sum)| sum.map(|sum| (sum / count) as u8))
+        .collect()
+}
@@ -2,25 +2,82 @@ use image::io::Reader as ImageReader;
 use image::{DynamicImage, ImageFormat};
 use jpeg_encoder::{ColorType, Encoder};
 
-use std::io::Cursor;
+use std::io::{BufRead, BufReader, Cursor, Seek};
 use std::path::{Path, PathBuf};
 
 use crate::error::{Error, Result};
 
-pub fn open_image_bytes(bytes: &[u8], filename: impl AsRef<Path>, mime_type: Option<&str>) -> Result<DynamicImage> {
+pub fn open_image_file(path: impl AsRef<Path>, mime_type: Option<&str>) -> Result<DynamicImage> {
+    let path = path.as_ref();
     // Determine image format from mime type and then filename
     let format = mime_type
         .and_then(ImageFormat::from_mime_type)
-        .or(ImageFormat::from_path(filename).ok());
+        .or(ImageFormat::from_path(path).ok());
 
     // 1. Decode image based on the determined format
     if let Some(format) = format {
-        if let Ok(img) = ImageReader::with_format(Cursor::new(bytes), format).decode() {
+        let reader = BufReader::new(std::fs::File::open(path)?);
+        if let Ok(img) = ImageReader::with_format(reader, format).decode() {
             return Ok(img);
         }
     }
     // 2. If failed, decode image directly
-    let img = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?.decode()?;
+    let img = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+    Ok(img)
+}
+
+/// Whether the image file is an animated GIF, WebP or APNG, i.e. has more than one frame.
+/// Only the parts of the file needed by the decoder are read.
+pub fn is_animated_file(path: impl AsRef<Path>) -> bool {
+    std::fs::File::open(path)
+        .map(|file| is_animated_reader(BufReader::new(file)))
+        .unwrap_or_default()
+}
+
+fn is_animated_reader<R: BufRead + Seek>(reader: R) -> bool {
+    use image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
+    use image::AnimationDecoder;
+
+    let Ok(reader) = ImageReader::new(reader).with_guessed_format() else {
+        return false;
+    };
+    let format = reader.format();
+    let reader = reader.into_inner();
+    match format {
+        Some(ImageFormat::Gif) => GifDecoder::new(reader)
+            .map(|decoder| decoder.into_frames().take(2).count() > 1)
+            .unwrap_or_default(),
+        Some(ImageFormat::WebP) => WebPDecoder::new(reader)
+            .map(|decoder| decoder.has_animation())
+            .unwrap_or_default(),
+        Some(ImageFormat::Png) => PngDecoder::new(reader)
+            .map(|decoder| decoder.is_apng())
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+/// Extract a poster frame of the video with `ffmpeg`, which should be available in `PATH`.
+/// The frame is taken at 1 second, or the first frame if the video is shorter.
+pub fn video_poster_frame(path: impl AsRef<Path>) -> Result<DynamicImage> {
+    let path = path.as_ref();
+    let extract = |seek: &str| {
+        std::process::Command::new("ffmpeg")
+            .args(["-v", "error", "-ss", seek, "-i"])
+            .arg(path)
+            .args(["-frames:v", "1", "-f", "image2pipe", "-c:v", "png", "-"])
+            .output()
+    };
+
+    let mut output = extract("1")?;
+    if output.stdout.is_empty() {
+        output = extract("0")?;
+    }
+    if !output.status.success() || output.stdout.is_empty() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::VideoError(format!("{}: {}", path.display(), message)));
+    }
+    let img = ImageReader::with_format(Cursor::new(output.stdout), ImageFormat::Png).decode()?;
     Ok(img)
 }
 
@@ -0,0 +1,100 @@
+use chrono::Utc;
+use diesel::{
+    dsl::sql,
+    prelude::*,
+    sql_types::{Nullable, Timestamp},
+};
+
+use bottle_core::{
+    feed::{AccountDailyUsage, AccountStatsView},
+    Database, Result,
+};
+
+use crate::model;
+
+const RECENT_DAYS: i64 = 30;
+
+pub use bottle_util::http::RequestOutcome;
+
+// MARK: Account request
+
+#[derive(Debug)]
+pub struct AccountRequest;
+
+impl AccountRequest {
+    /// Count a request made with the account today.
+    pub fn record(conn: Database, community: &str, account_id: i32, outcome: RequestOutcome) -> Result<()> {
+        use bottle_core::schema::account_request;
+
+        let now = Utc::now().naive_utc();
+        let (rate_limited, failed) = match outcome {
+            RequestOutcome::Success => (0, 0),
+            RequestOutcome::RateLimited => (1, 0),
+            RequestOutcome::Failed => (0, 1),
+        };
+        let success_date = (outcome == RequestOutcome::Success).then_some(now);
+        diesel::insert_into(account_request::table)
+            .values((
+                account_request::community.eq(community),
+                account_request::account_id.eq(account_id),
+                account_request::day.eq(now.date()),
+                account_request::request_count.eq(1),
+                account_request::rate_limit_count.eq(rate_limited),
+                account_request::error_count.eq(failed),
+                account_request::last_success_date.eq(success_date),
+            ))
+            .on_conflict((
+                account_request::community,
+                account_request::account_id,
+                account_request::day,
+            ))
+            .do_update()
+            .set((
+                account_request::request_count.eq(account_request::request_count + 1),
+                account_request::rate_limit_count.eq(account_request::rate_limit_count + rate_limited),
+                account_request::error_count.eq(account_request::error_count + failed),
+                // Keep the last success date if the request failed
+                account_request::last_success_date.eq(sql::<Nullable<Timestamp>>(
+                    "COALESCE(excluded.last_success_date, account_request.last_success_date)",
+                )),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Summarize the requests of the account. `feed_count` is filled by the caller.
+    pub fn stats(conn: Database, community: &str, account_id: i32) -> Result<AccountStatsView> {
+        use bottle_core::schema::account_request;
+
+        let rows = account_request::table
+            .filter(account_request::community.eq(community))
+            .filter(account_request::account_id.eq(account_id))
+            .order_by(account_request::day.desc())
+            .load::<model::AccountRequest>(conn)?;
+
+        let since = Utc::now().date_naive() - chrono::Duration::days(RECENT_DAYS);
+        Ok(AccountStatsView {
+            account_id,
+            community: community.to_string(),
+            request_count: rows.iter().map(|row| row.request_count as i64).sum(),
+            rate_limit_count: rows.iter().map(|row| row.rate_limit_count as i64).sum(),
+            error_count: rows.iter().map(|row| row.error_count as i64).sum(),
+            feed_count: 0,
+            last_success_date: rows
+                .iter()
+                .filter_map(|row| row.last_success_date)
+                .max()
+                .map(|d| d.and_utc()),
+            days: rows
+                .iter()
+                .filter(|row| row.day > since)
+                .map(|row| AccountDailyUsage {
+                    date: row.day,
+                    request_count: row.request_count as i64,
+                    rate_limit_count: row.rate_limit_count as i64,
+                    error_count: row.error_count as i64,
+                })
+                .collect(),
+        })
+    }
+}
@@ -1,4 +1,7 @@
 use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+
+use std::collections::HashMap;
 
 use bottle_core::{feed::GeneralResponse, library::*, Database, Error, Result};
 
@@ -6,13 +9,119 @@ use crate::model;
 
 // MARK: Album & Folder
 
-const POSITION_GAP: i32 = 100;
+pub(crate) const POSITION_GAP: i32 = 100;
+
+/// Works matching the filter of a smart album, most recently added first, with the total number of them.
+/// The community, the JSON array of tags, the artist and the minimum rating are bound in order, null if not given.
+/// Tags are matched the same way as the booru upload, and artists the same way as the artist statistics.
+/// `{condition}` is replaced with an extra condition on the `work` table.
+const SMART_ALBUM_QUERY: &str = "
+with filter(community, tags, artist, min_rating) as (select ?, ?, ?, ?)
+select work.id as work_id, count(*) over () as total_items
+from work, filter
+where (filter.community is null or work.source = filter.community)
+and (filter.min_rating is null or work.rating >= filter.min_rating)
+and (
+    filter.artist is null
+    or exists (
+        select 1 from tweet
+        where work.source = 'twitter' and tweet.id = work.post_id_int and cast(tweet.user_id as text) = filter.artist
+    )
+    or exists (
+        select 1 from pixiv_illust
+        where work.source = 'pixiv' and pixiv_illust.id = work.post_id_int
+        and cast(pixiv_illust.user_id as text) = filter.artist
+    )
+    or exists (
+        select 1 from yandere_post_tag
+        join yandere_tag on yandere_post_tag.tag_name = yandere_tag.name and yandere_tag.type = 'artist'
+        where work.source = 'yandere' and yandere_post_tag.post_id = work.post_id_int
+        and yandere_tag.name = filter.artist
+    )
+    or exists (
+        select 1 from panda_gallery_tag
+        where work.source = 'panda' and panda_gallery_tag.gallery_id = work.post_id_int
+        and panda_gallery_tag.namespace = 'artist' and panda_gallery_tag.name = filter.artist
+    )
+)
+and not exists (
+    select 1 from json_each(filter.tags) as tag
+    where not exists (
+        select 1 from twitter_tag
+        where work.source = 'twitter' and twitter_tag.tweet_id = work.post_id_int and twitter_tag.tag = tag.value
+    )
+    and not exists (
+        select 1 from pixiv_illust_tag
+        where work.source = 'pixiv' and pixiv_illust_tag.illust_id = work.post_id_int
+        and pixiv_illust_tag.tag = tag.value
+    )
+    and not exists (
+        select 1 from yandere_post_tag
+        where work.source = 'yandere' and yandere_post_tag.post_id = work.post_id_int
+        and yandere_post_tag.tag_name = tag.value
+    )
+    and not exists (
+        select 1 from panda_gallery_tag
+        where work.source = 'panda' and panda_gallery_tag.gallery_id = work.post_id_int
+        and panda_gallery_tag.namespace || ':' || panda_gallery_tag.name = tag.value
+    )
+)
+and {condition}
+order by work.added_date desc, work.id desc
+limit ? offset ?";
+
+#[derive(QueryableByName)]
+struct SmartAlbumWork {
+    #[diesel(sql_type = Integer)]
+    work_id: i32,
+    #[diesel(sql_type = BigInt)]
+    total_items: i64,
+}
+
+/// IDs of the works matching the filter of a smart album and the extra SQL condition on the `work` table,
+/// with the total number of them. The condition is embedded, so it must only contain fixed literals.
+pub(crate) fn smart_album_work_ids(
+    conn: Database,
+    filter: &SmartAlbumFilter,
+    condition: &str,
+    page: i64,
+    page_size: i64,
+) -> Result<(Vec<i32>, i64)> {
+    let tags = (!filter.tags.is_empty())
+        .then(|| serde_json::to_string(&filter.tags))
+        .transpose()?;
+    let works = diesel::sql_query(SMART_ALBUM_QUERY.replace("{condition}", condition))
+        .bind::<Nullable<Text>, _>(&filter.community)
+        .bind::<Nullable<Text>, _>(tags)
+        .bind::<Nullable<Text>, _>(&filter.artist)
+        .bind::<Nullable<Integer>, _>(filter.min_rating)
+        .bind::<BigInt, _>(page_size)
+        .bind::<BigInt, _>(page * page_size)
+        .load::<SmartAlbumWork>(conn)?;
+    let total_items = works.first().map(|work| work.total_items).unwrap_or_default();
+    Ok((works.into_iter().map(|work| work.work_id).collect(), total_items))
+}
+
+/// Filter of the album if it is a smart album.
+pub(crate) fn smart_album_filter(album: &model::Album) -> Result<Option<SmartAlbumFilter>> {
+    Ok(album
+        .filter
+        .as_deref()
+        .map(serde_json::from_str::<SmartAlbumFilter>)
+        .transpose()?)
+}
 
 #[derive(Debug)]
 pub struct Album;
 
 impl Album {
-    pub fn add(conn: Database, name: &str, folder_id: Option<i32>) -> Result<AlbumView> {
+    /// Add an album, or a smart album if the filter is given.
+    pub fn add(
+        conn: Database,
+        name: &str,
+        folder_id: Option<i32>,
+        filter: Option<&SmartAlbumFilter>,
+    ) -> Result<AlbumView> {
         use bottle_core::schema::{album, folder};
 
         // Check the requested folder
@@ -34,6 +143,7 @@ impl Album {
             name: name.to_string(),
             folder_id,
             position: max_position + POSITION_GAP,
+            filter: filter.map(serde_json::to_string).transpose()?,
         };
         let album = diesel::insert_into(album::table)
             .values(new_album)
@@ -41,7 +151,8 @@ impl Album {
             .get_result(conn)?;
 
         tracing::info!(
-            "Added album {} \"{}\"{}, position {}",
+            "Added {}album {} \"{}\"{}, position {}",
+            if filter.is_some() { "smart " } else { "" },
             album.id,
             name,
             folder_id.map(|id| format!(" in folder {}", id)).unwrap_or_default(),
@@ -67,6 +178,75 @@ impl Album {
         Ok(albums.into_iter().map(AlbumView::from).collect())
     }
 
+    /// Whether the file at the path relative to the image directory is a cover of the albums,
+    /// or an image or a thumbnail of their works.
+    pub fn contains_file(conn: Database, album_ids: &[i32], path: &str) -> Result<bool> {
+        use bottle_core::schema::{album, album_work, image, work};
+        use itertools::Itertools;
+
+        let cover_count = album::table
+            .filter(album::id.eq_any(album_ids))
+            .filter(album::cover_path.eq(path))
+            .count()
+            .get_result::<i64>(conn)?;
+        let work_count = album_work::table
+            .inner_join(work::table)
+            .filter(album_work::album_id.eq_any(album_ids))
+            .filter(work::thumbnail_path.eq(path).or(work::small_thumbnail_path.eq(path)))
+            .count()
+            .get_result::<i64>(conn)?;
+        let image_count = album_work::table
+            .inner_join(work::table.inner_join(image::table))
+            .filter(album_work::album_id.eq_any(album_ids))
+            .filter(
+                image::path
+                    .eq(path)
+                    .or(image::thumbnail_path.eq(path))
+                    .or(image::small_thumbnail_path.eq(path)),
+            )
+            .count()
+            .get_result::<i64>(conn)?;
+        if cover_count + work_count + image_count > 0 {
+            return Ok(true);
+        }
+
+        // Works of smart albums are computed from their filters
+        let smart_albums = album::table
+            .filter(album::id.eq_any(album_ids))
+            .filter(album::filter.is_not_null())
+            .load::<model::Album>(conn)?;
+        if smart_albums.is_empty() {
+            return Ok(false);
+        }
+        let work_ids = work::table
+            .filter(work::thumbnail_path.eq(path).or(work::small_thumbnail_path.eq(path)))
+            .select(work::id)
+            .union(
+                image::table
+                    .filter(
+                        image::path
+                            .eq(path)
+                            .or(image::thumbnail_path.eq(path))
+                            .or(image::small_thumbnail_path.eq(path)),
+                    )
+                    .select(image::work_id),
+            )
+            .load::<i32>(conn)?;
+        if work_ids.is_empty() {
+            return Ok(false);
+        }
+        let condition = format!("work.id IN ({})", work_ids.iter().join(", "));
+        for album in smart_albums {
+            if let Some(filter) = smart_album_filter(&album)? {
+                let (_, count) = smart_album_work_ids(conn, &filter, &condition, 0, 1)?;
+                if count > 0 {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
     pub fn rename(conn: Database, album_id: i32, name: &str) -> Result<AlbumView> {
         use bottle_core::schema::album;
         diesel::update(album::table.find(album_id))
@@ -102,14 +282,36 @@ impl Album {
         Ok(album.into())
     }
 
+    /// Set the filter of the album to make it a smart album, or `None` to make it a regular album again.
+    /// Works explicitly added to the album are kept, and shown again once the filter is removed.
+    pub fn set_filter(conn: Database, album_id: i32, filter: Option<&SmartAlbumFilter>) -> Result<AlbumView> {
+        use bottle_core::schema::album;
+
+        Self::get(conn, album_id)?;
+        diesel::update(album::table.find(album_id))
+            .set(album::filter.eq(filter.map(serde_json::to_string).transpose()?))
+            .execute(conn)?;
+        let album = album::table.find(album_id).first::<model::Album>(conn)?;
+
+        tracing::info!(
+            "{} filter of album {}",
+            if filter.is_some() { "Set" } else { "Removed" },
+            album_id
+        );
+        Ok(album.into())
+    }
+
     pub fn add_works(conn: Database, album_id: i32, work_ids: impl IntoIterator<Item = i32>) -> Result<()> {
-        use bottle_core::schema::{album, album_work};
+        use bottle_core::schema::album_work;
         use itertools::Itertools;
 
-        // Check if the album exists
-        let album = album::table.find(album_id).first::<model::Album>(conn).optional()?;
-        if album.is_none() {
-            return Err(Error::ObjectNotFound(format!("Album {}", album_id)));
+        // Check if the album exists, and is not a smart album
+        let album = Self::get(conn, album_id)?;
+        if album.filter.is_some() {
+            return Err(Error::InvalidEndpoint(format!(
+                "Album {} is a smart album, whose works are given by its filter",
+                album_id
+            )));
         }
 
         // Get max position of existing work in the album
@@ -143,18 +345,37 @@ impl Album {
         Ok(())
     }
 
-    pub fn works(conn: Database, album_id: i32, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    pub fn works(
+        conn: Database,
+        album_id: i32,
+        filter: &WorkFilter,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{album_work, image, work};
         use bottle_util::diesel_ext::Paginate;
-
-        // 1. Fetch works
-        let (works, total_items) = album_work::table
-            .inner_join(work::table)
-            .filter(album_work::album_id.eq(album_id))
-            .order_by(album_work::position.asc())
-            .select(work::all_columns)
-            .paginate(page, page_size)
-            .load_and_count::<model::Work>(conn)?;
+        use diesel::{dsl::sql, sql_types::Bool};
+
+        // 1. Fetch works, computed from the filter for smart albums
+        let album = Self::get(conn, album_id)?;
+        let (works, total_items) = if let Some(smart_filter) = smart_album_filter(&album)? {
+            let (work_ids, total_items) =
+                smart_album_work_ids(conn, &smart_filter, &filter.sql_condition(), page, page_size)?;
+            let mut works = work::table
+                .filter(work::id.eq_any(&work_ids))
+                .load::<model::Work>(conn)?;
+            works.sort_by_key(|work| work_ids.iter().position(|id| *id == work.id));
+            (works, total_items)
+        } else {
+            album_work::table
+                .inner_join(work::table)
+                .filter(album_work::album_id.eq(album_id))
+                .filter(sql::<Bool>(&filter.sql_condition()))
+                .order_by(album_work::position.asc())
+                .select(work::all_columns)
+                .paginate(page, page_size)
+                .load_and_count::<model::Work>(conn)?
+        };
 
         // 2. Fetch images
         let work_ids = works.iter().map(|work| work.id);
@@ -173,6 +394,15 @@ impl Album {
         })
     }
 
+    fn get(conn: Database, album_id: i32) -> Result<model::Album> {
+        use bottle_core::schema::album;
+        album::table
+            .find(album_id)
+            .first::<model::Album>(conn)
+            .optional()?
+            .ok_or(Error::ObjectNotFound(format!("Album {}", album_id)))
+    }
+
     pub fn remove_works(conn: Database, album_id: i32, work_ids: impl IntoIterator<Item = i32>) -> Result<()> {
         use bottle_core::schema::album_work;
         use itertools::Itertools;
@@ -189,7 +419,43 @@ impl Album {
         Ok(())
     }
 
-    // TODO: reorder works in album
+    /// IDs of the works explicitly added to the album, in order.
+    pub fn work_ids(conn: Database, album_id: i32) -> Result<Vec<i32>> {
+        use bottle_core::schema::album_work;
+        let work_ids = album_work::table
+            .filter(album_work::album_id.eq(album_id))
+            .order_by(album_work::position.asc())
+            .select(album_work::work_id)
+            .load::<i32>(conn)?;
+        Ok(work_ids)
+    }
+
+    /// Reorder the works in the album by the given work IDs, followed by the other works in their original order.
+    pub fn reorder_works(conn: Database, album_id: i32, work_ids: &[i32]) -> Result<()> {
+        use bottle_core::schema::album_work;
+        use std::collections::HashSet;
+
+        let existing_ids = Self::work_ids(conn, album_id)?;
+        let existing_set = existing_ids.iter().cloned().collect::<HashSet<_>>();
+        let given_set = work_ids.iter().cloned().collect::<HashSet<_>>();
+        let ordered_ids = work_ids
+            .iter()
+            .filter(|id| existing_set.contains(id))
+            .chain(existing_ids.iter().filter(|id| !given_set.contains(id)))
+            .collect::<Vec<_>>();
+
+        conn.transaction(|conn| -> Result<()> {
+            for (i, work_id) in ordered_ids.iter().enumerate() {
+                diesel::update(album_work::table.find((album_id, **work_id)))
+                    .set(album_work::position.eq((i as i32 + 1) * POSITION_GAP))
+                    .execute(conn)?;
+            }
+            Ok(())
+        })?;
+
+        tracing::info!("Reordered {} works in album {}", ordered_ids.len(), album_id);
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -250,6 +516,78 @@ impl Folder {
         Ok(folders.into_iter().map(FolderView::from).collect())
     }
 
+    /// Hierarchy of all the folders and albums in order, with the number of works and the cover of each.
+    /// Works of regular albums are counted in one query, and those of smart albums from their filters.
+    pub fn tree(conn: Database) -> Result<LibraryTreeView> {
+        use bottle_core::schema::{album, album_work, folder};
+
+        let folders = folder::table
+            .order_by(folder::position.asc())
+            .load::<model::Folder>(conn)?;
+        let albums = album::table
+            .order_by(album::position.asc())
+            .load::<model::Album>(conn)?;
+        let mut counts = album_work::table
+            .group_by(album_work::album_id)
+            .select((album_work::album_id, diesel::dsl::count_star()))
+            .load::<(i32, i64)>(conn)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let condition = WorkFilter::default().sql_condition();
+        for album in albums.iter() {
+            if let Some(filter) = smart_album_filter(album)? {
+                let (_, count) = smart_album_work_ids(conn, &filter, &condition, 0, 1)?;
+                counts.insert(album.id, count);
+            }
+        }
+
+        let mut album_nodes = HashMap::<Option<i32>, Vec<AlbumNodeView>>::new();
+        for album in albums {
+            album_nodes.entry(album.folder_id).or_default().push(AlbumNodeView {
+                work_count: counts.get(&album.id).cloned().unwrap_or_default(),
+                album: album.into(),
+            });
+        }
+        let mut subfolders = HashMap::<Option<i32>, Vec<model::Folder>>::new();
+        for folder in folders {
+            subfolders.entry(folder.parent_id).or_default().push(folder);
+        }
+
+        Ok(LibraryTreeView {
+            folders: Self::folder_nodes(None, &mut subfolders, &mut album_nodes),
+            albums: album_nodes.remove(&None).unwrap_or_default(),
+        })
+    }
+
+    /// Build the nodes of the subfolders of the parent, taking them out of the maps so that each is visited once.
+    fn folder_nodes(
+        parent_id: Option<i32>,
+        subfolders: &mut HashMap<Option<i32>, Vec<model::Folder>>,
+        album_nodes: &mut HashMap<Option<i32>, Vec<AlbumNodeView>>,
+    ) -> Vec<FolderNodeView> {
+        let folders = subfolders.remove(&parent_id).unwrap_or_default();
+        folders
+            .into_iter()
+            .map(|folder| {
+                let folders = Self::folder_nodes(Some(folder.id), subfolders, album_nodes);
+                let albums = album_nodes.remove(&Some(folder.id)).unwrap_or_default();
+                let work_count = folders.iter().map(|node| node.work_count).sum::<i64>()
+                    + albums.iter().map(|node| node.work_count).sum::<i64>();
+                let cover_path = albums
+                    .iter()
+                    .find_map(|node| node.album.cover_path.clone())
+                    .or_else(|| folders.iter().find_map(|node| node.cover_path.clone()));
+                FolderNodeView {
+                    folder: folder.into(),
+                    work_count,
+                    cover_path,
+                    folders,
+                    albums,
+                }
+            })
+            .collect()
+    }
+
     pub fn rename(conn: Database, folder_id: i32, name: &str) -> Result<FolderView> {
         use bottle_core::schema::folder;
         diesel::update(folder::table.find(folder_id))
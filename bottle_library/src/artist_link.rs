@@ -0,0 +1,169 @@
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+
+use bottle_core::{library::ArtistLinkView, Database, Error, Result};
+
+use crate::model;
+
+diesel::define_sql_function!(fn lower(x: Text) -> Text);
+
+// MARK: Artist link
+
+/// Statuses of links, starting as suggested until the user confirms or rejects them.
+/// Rejected links are kept so that they are not suggested again.
+const ARTIST_LINK_STATUSES: [&str; 3] = ["suggested", "confirmed", "rejected"];
+
+/// Pixiv users linked to the Twitter usernames on their profiles, by user ID if the Twitter user is saved.
+const PIXIV_TWITTER_QUERY: &str = "
+insert into artist_link (community, user_id, linked_community, linked_user_id, reason)
+select 'pixiv', cast(pixiv_user.id as text), 'twitter',
+    coalesce(cast(twitter_user.id as text), pixiv_user.twitter_username),
+    'Twitter username on the pixiv profile'
+from pixiv_user
+left join twitter_user on lower(twitter_user.username) = lower(pixiv_user.twitter_username)
+where pixiv_user.twitter_username is not null and pixiv_user.twitter_username != ''";
+
+/// Panda artist tags linked to the pixiv users with the same name or account name, ignoring case.
+const PANDA_PIXIV_QUERY: &str = "
+insert into artist_link (community, user_id, linked_community, linked_user_id, reason)
+select 'panda', artist.name, 'pixiv', cast(pixiv_user.id as text), 'Panda artist tag matching the pixiv name'
+from (select distinct name from panda_gallery_tag where namespace = 'artist') artist
+join pixiv_user on artist.name in (lower(pixiv_user.name), replace(lower(pixiv_user.username), '_', ' '))";
+
+/// Paths of Twitter which are not users.
+const TWITTER_RESERVED_PATHS: [&str; 7] = ["i", "intent", "home", "search", "hashtag", "share", "explore"];
+
+#[derive(QueryableByName)]
+struct YandereArtistUrls {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    urls: String,
+}
+
+#[derive(Debug)]
+pub struct ArtistLink;
+
+impl ArtistLink {
+    /// Suggest links from pixiv profiles, URLs of yandere artists and panda artist tags,
+    /// skipping the links suggested before. Returns the number of new suggestions.
+    pub fn suggest(conn: Database) -> Result<usize> {
+        use bottle_core::schema::artist_link;
+
+        let mut count = 0;
+        count += diesel::sql_query(PIXIV_TWITTER_QUERY).execute(conn)?;
+        count += diesel::sql_query(PANDA_PIXIV_QUERY).execute(conn)?;
+
+        let artists = diesel::sql_query("select name, urls from yandere_artist where alias_id is null")
+            .load::<YandereArtistUrls>(conn)?;
+        let mut links = Vec::new();
+        for artist in artists {
+            let urls = serde_json::from_str::<Vec<String>>(&artist.urls)?;
+            for (community, user_id) in urls.iter().filter_map(|url| profile_of_url(url)) {
+                let user_id = match community {
+                    "twitter" => twitter_user_id(conn, &user_id)?.unwrap_or(user_id),
+                    _ => user_id,
+                };
+                links.push(model::NewArtistLink {
+                    community: "yandere".to_string(),
+                    user_id: artist.name.clone(),
+                    linked_community: community.to_string(),
+                    linked_user_id: user_id,
+                    reason: "URL of the yandere artist".to_string(),
+                });
+            }
+        }
+        if !links.is_empty() {
+            count += diesel::insert_into(artist_link::table).values(&links).execute(conn)?;
+        }
+
+        tracing::info!("Suggested {} artist links", count);
+        Ok(count)
+    }
+
+    /// Links with the status, or all links if not given, with the ones involving the user if given, newest first.
+    pub fn list(conn: Database, status: Option<&str>, user: Option<(&str, &str)>) -> Result<Vec<ArtistLinkView>> {
+        use bottle_core::schema::artist_link;
+
+        let mut query = artist_link::table.into_boxed();
+        if let Some(status) = status {
+            query = query.filter(artist_link::status.eq(status.to_string()));
+        }
+        if let Some((community, user_id)) = user {
+            query = query.filter(
+                (artist_link::community
+                    .eq(community.to_string())
+                    .and(artist_link::user_id.eq(user_id.to_string())))
+                .or(artist_link::linked_community
+                    .eq(community.to_string())
+                    .and(artist_link::linked_user_id.eq(user_id.to_string()))),
+            );
+        }
+        let links = query.order(artist_link::id.desc()).load::<model::ArtistLink>(conn)?;
+        Ok(links.into_iter().map(ArtistLinkView::from).collect())
+    }
+
+    /// Confirm or reject the link.
+    pub fn set_status(conn: Database, link_id: i32, status: &str) -> Result<ArtistLinkView> {
+        use bottle_core::schema::artist_link;
+
+        if !ARTIST_LINK_STATUSES.contains(&status) {
+            return Err(Error::UnknownField(format!("artist link status {}", status)));
+        }
+        let link = diesel::update(artist_link::table.find(link_id))
+            .set(artist_link::status.eq(status))
+            .returning(model::ArtistLink::as_returning())
+            .get_result(conn)
+            .optional()?
+            .ok_or(Error::ObjectNotFound(format!("Artist link {}", link_id)))?;
+
+        tracing::info!("Set artist link {} as {}", link_id, status);
+        Ok(link.into())
+    }
+}
+
+/// Twitter user ID of the saved user with the username, ignoring case.
+fn twitter_user_id(conn: Database, username: &str) -> Result<Option<String>> {
+    use bottle_core::schema::twitter_user;
+
+    let user_id = twitter_user::table
+        .filter(lower(twitter_user::username).eq(username.to_lowercase()))
+        .select(twitter_user::id)
+        .first::<i64>(conn)
+        .optional()?;
+    Ok(user_id.map(|id| id.to_string()))
+}
+
+/// Community and user of a profile URL, like `https://twitter.com/name` or `https://www.pixiv.net/users/123`.
+fn profile_of_url(url: &str) -> Option<(&'static str, String)> {
+    let url = url.trim();
+    let rest = url
+        .strip_prefix("https://")
+        .or(url.strip_prefix("http://"))
+        .unwrap_or(url);
+    let (host, path) = rest.split_once('/')?;
+    let host = host.trim_start_matches("www.").trim_start_matches("mobile.");
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let segments = path.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>();
+
+    match host {
+        "twitter.com" | "x.com" => {
+            let name = segments.first()?.trim_start_matches('@');
+            let is_username = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            (is_username && !TWITTER_RESERVED_PATHS.contains(&name)).then(|| ("twitter", name.to_string()))
+        }
+        "pixiv.net" => {
+            let user_id = match segments.as_slice() {
+                ["users", id, ..] | ["en", "users", id, ..] => id.to_string(),
+                ["member.php"] | ["member_illust.php"] => query
+                    .split('&')
+                    .find_map(|param| param.strip_prefix("id="))?
+                    .to_string(),
+                _ => return None,
+            };
+            let is_user_id = !user_id.is_empty() && user_id.chars().all(|c| c.is_ascii_digit());
+            is_user_id.then_some(("pixiv", user_id))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,96 @@
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Text};
+
+use bottle_core::{Database, Result};
+
+use crate::model;
+
+// MARK: Booru upload
+
+/// Works with downloaded images not uploaded to the booru yet,
+/// which are in any of the albums or tagged with any of the tags, given as JSON arrays.
+/// Panda tags are matched as `namespace:name`.
+const PENDING_WORKS_QUERY: &str = "
+with album_ids(id) as (select value from json_each(?)),
+tags(tag) as (select value from json_each(?))
+select distinct work.id as work_id
+from work
+join image on image.work_id = work.id and image.path is not null
+left join booru_upload on booru_upload.booru = ? and booru_upload.image_id = image.id
+where booru_upload.image_id is null and (
+    work.id in (select work_id from album_work where album_id in album_ids)
+    or exists (
+        select 1 from twitter_tag
+        where work.source = 'twitter' and twitter_tag.tweet_id = work.post_id_int and twitter_tag.tag in tags
+    )
+    or exists (
+        select 1 from pixiv_illust_tag
+        where work.source = 'pixiv' and pixiv_illust_tag.illust_id = work.post_id_int and pixiv_illust_tag.tag in tags
+    )
+    or exists (
+        select 1 from yandere_post_tag
+        where work.source = 'yandere' and yandere_post_tag.post_id = work.post_id_int
+        and yandere_post_tag.tag_name in tags
+    )
+    or exists (
+        select 1 from panda_gallery_tag
+        where work.source = 'panda' and panda_gallery_tag.gallery_id = work.post_id_int
+        and panda_gallery_tag.namespace || ':' || panda_gallery_tag.name in tags
+    )
+)
+order by work.id
+limit ?";
+
+#[derive(QueryableByName)]
+struct PendingWork {
+    #[diesel(sql_type = Integer)]
+    work_id: i32,
+}
+
+#[derive(Debug)]
+pub struct BooruUpload;
+
+impl BooruUpload {
+    /// Works to upload to the booru, which are in any of the albums or tagged with any of the tags,
+    /// and have downloaded images not uploaded yet.
+    pub fn pending_works(
+        conn: Database,
+        booru: &str,
+        album_ids: &[i32],
+        tags: &[String],
+        count: i64,
+    ) -> Result<Vec<i32>> {
+        let works = diesel::sql_query(PENDING_WORKS_QUERY)
+            .bind::<Text, _>(serde_json::to_string(album_ids)?)
+            .bind::<Text, _>(serde_json::to_string(tags)?)
+            .bind::<Text, _>(booru)
+            .bind::<BigInt, _>(count)
+            .load::<PendingWork>(conn)?;
+        Ok(works.into_iter().map(|work| work.work_id).collect())
+    }
+
+    /// IDs of the images already uploaded to the booru among the given ones.
+    pub fn uploaded_images(conn: Database, booru: &str, image_ids: &[i32]) -> Result<Vec<i32>> {
+        use bottle_core::schema::booru_upload;
+        let image_ids = booru_upload::table
+            .filter(booru_upload::booru.eq(booru))
+            .filter(booru_upload::image_id.eq_any(image_ids))
+            .select(booru_upload::image_id)
+            .load::<i32>(conn)?;
+        Ok(image_ids)
+    }
+
+    /// Record the image as uploaded to the booru as the remote post.
+    pub fn record(conn: Database, booru: &str, image_id: i32, remote_id: &str) -> Result<()> {
+        use bottle_core::schema::booru_upload;
+        diesel::insert_into(booru_upload::table)
+            .values(model::NewBooruUpload {
+                booru: booru.to_string(),
+                image_id,
+                remote_id: remote_id.to_string(),
+            })
+            .execute(conn)?;
+        tracing::info!("Uploaded image {} to booru {} as post {}", image_id, booru, remote_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+
+use bottle_core::{Database, Error, Result};
+
+use crate::album::{smart_album_filter, smart_album_work_ids};
+use crate::model;
+
+// MARK: Cover
+
+#[derive(Debug)]
+pub struct Cover;
+
+#[derive(QueryableByName)]
+struct FeedId {
+    #[diesel(sql_type = Integer)]
+    id: i32,
+}
+
+#[derive(QueryableByName)]
+struct Thumbnail {
+    #[diesel(sql_type = Text)]
+    path: String,
+}
+
+/// Watch list table of the community, and the table linking it to the posts with the column of post ID.
+pub(crate) fn feed_tables(community: &str) -> Result<(&'static str, &'static str, &'static str)> {
+    match community {
+        "twitter" => Ok(("twitter_watch_list", "twitter_watch_list_tweet", "tweet_id")),
+        "pixiv" => Ok(("pixiv_watch_list", "pixiv_watch_list_illust", "illust_id")),
+        "yandere" => Ok(("yandere_watch_list", "yandere_watch_list_post", "post_id")),
+        "panda" => Ok(("panda_watch_list", "panda_watch_list_gallery", "gallery_id")),
+        _ => Err(Error::InvalidEndpoint(format!("Community {}", community))),
+    }
+}
+
+impl Cover {
+    /// Small thumbnails of the works most recently added to the album.
+    pub fn album_thumbnails(conn: Database, album_id: i32, count: i64) -> Result<Vec<String>> {
+        use bottle_core::schema::{album, album_work, work};
+
+        // Works of smart albums are computed from their filters
+        let album = album::table.find(album_id).first::<model::Album>(conn)?;
+        if let Some(filter) = smart_album_filter(&album)? {
+            let (work_ids, _) = smart_album_work_ids(conn, &filter, "work.small_thumbnail_path IS NOT NULL", 0, count)?;
+            let thumbnails = work::table
+                .filter(work::id.eq_any(&work_ids))
+                .order_by(work::added_date.desc())
+                .select(work::small_thumbnail_path)
+                .load::<Option<String>>(conn)?;
+            return Ok(thumbnails.into_iter().flatten().collect());
+        }
+
+        let thumbnails = album_work::table
+            .inner_join(work::table)
+            .filter(album_work::album_id.eq(album_id))
+            .filter(work::small_thumbnail_path.is_not_null())
+            .order_by(album_work::position.desc())
+            .select(work::small_thumbnail_path)
+            .limit(count)
+            .load::<Option<String>>(conn)?;
+        Ok(thumbnails.into_iter().flatten().collect())
+    }
+
+    /// Small thumbnails of the works most recently archived from the posts of the feed.
+    pub fn feed_thumbnails(conn: Database, community: &str, feed_id: i32, count: i64) -> Result<Vec<String>> {
+        let (_, link_table, post_column) = feed_tables(community)?;
+        let query = format!(
+            "select work.small_thumbnail_path as path
+            from work
+            join {link_table} on work.source = ? and work.post_id_int = {link_table}.{post_column}
+            where {link_table}.watch_list_id = ? and work.small_thumbnail_path is not null
+            order by work.added_date desc, work.id desc
+            limit ?"
+        );
+        let thumbnails = diesel::sql_query(query)
+            .bind::<Text, _>(community)
+            .bind::<Integer, _>(feed_id)
+            .bind::<BigInt, _>(count)
+            .load::<Thumbnail>(conn)?;
+        Ok(thumbnails.into_iter().map(|thumbnail| thumbnail.path).collect())
+    }
+
+    /// IDs of all feeds of the community.
+    pub fn feed_ids(conn: Database, community: &str) -> Result<Vec<i32>> {
+        let (watch_list_table, _, _) = feed_tables(community)?;
+        let ids = diesel::sql_query(format!("select id from {watch_list_table} order by id")).load::<FeedId>(conn)?;
+        Ok(ids.into_iter().map(|feed| feed.id).collect())
+    }
+
+    pub fn set_album_cover(conn: Database, album_id: i32, cover_path: Option<&str>) -> Result<()> {
+        use bottle_core::schema::album;
+        diesel::update(album::table.find(album_id))
+            .set(album::cover_path.eq(cover_path))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn set_feed_cover(conn: Database, community: &str, feed_id: i32, cover_path: Option<&str>) -> Result<()> {
+        let (watch_list_table, _, _) = feed_tables(community)?;
+        diesel::sql_query(format!("update {watch_list_table} set cover_path = ? where id = ?"))
+            .bind::<Nullable<Text>, _>(cover_path)
+            .bind::<Integer, _>(feed_id)
+            .execute(conn)?;
+        Ok(())
+    }
+}
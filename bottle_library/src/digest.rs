@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use bottle_core::{
+    feed::{DigestFrequency, DigestView},
+    Database, Result,
+};
+
+use crate::model;
+
+// MARK: Digest
+
+#[derive(Debug)]
+pub struct Digest;
+
+impl Digest {
+    pub fn add(conn: Database, email: &str, frequency: DigestFrequency) -> Result<DigestView> {
+        use bottle_core::schema::digest;
+
+        let new_digest = model::NewDigest {
+            email: email.to_string(),
+            frequency: frequency.as_str().to_string(),
+        };
+        let digest = diesel::insert_into(digest::table)
+            .values(new_digest)
+            .returning(model::Digest::as_returning())
+            .get_result(conn)?;
+
+        tracing::info!("Added {} digest {} to {}", digest.frequency, digest.id, email);
+        Ok(digest.into())
+    }
+
+    pub fn delete(conn: Database, digest_id: i32) -> Result<()> {
+        use bottle_core::schema::digest;
+        diesel::delete(digest::table.find(digest_id)).execute(conn)?;
+        tracing::info!("Deleted digest {}", digest_id);
+        Ok(())
+    }
+
+    pub fn get(conn: Database, digest_id: i32) -> Result<Option<model::Digest>> {
+        use bottle_core::schema::digest;
+        let digest = digest::table.find(digest_id).first::<model::Digest>(conn).optional()?;
+        Ok(digest)
+    }
+
+    pub fn all(conn: Database) -> Result<Vec<DigestView>> {
+        use bottle_core::schema::digest;
+        let digests = digest::table.order_by(digest::id.asc()).load::<model::Digest>(conn)?;
+        Ok(digests.into_iter().map(DigestView::from).collect())
+    }
+
+    /// Get the digests which are never sent, or last sent a period ago.
+    /// Digests failed recently are skipped until their backoff passes.
+    pub fn due(conn: Database, now: DateTime<Utc>) -> Result<Vec<model::Digest>> {
+        use bottle_core::schema::digest;
+        let digests = digest::table.load::<model::Digest>(conn)?;
+        Ok(digests
+            .into_iter()
+            .filter(|digest| {
+                let period = digest
+                    .frequency
+                    .parse::<DigestFrequency>()
+                    .unwrap_or(DigestFrequency::Weekly)
+                    .period();
+                let sent_due = digest.last_sent_date.is_none_or(|date| date.and_utc() + period <= now);
+                let retry_due = digest
+                    .last_failed_date
+                    .is_none_or(|date| date.and_utc() + retry_backoff(digest.failure_count, period) <= now);
+                sent_due && retry_due
+            })
+            .collect())
+    }
+
+    /// Record the date and disk usage when the digest is sent, and clear its failures.
+    pub fn mark_sent(conn: Database, digest_id: i32, date: DateTime<Utc>, disk_usage: i64) -> Result<()> {
+        use bottle_core::schema::digest;
+        diesel::update(digest::table.find(digest_id))
+            .set((
+                digest::last_sent_date.eq(date.naive_utc()),
+                digest::last_disk_usage.eq(disk_usage),
+                digest::last_failed_date.eq(None::<chrono::NaiveDateTime>),
+                digest::failure_count.eq(0),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Record the date when the digest failed to be rendered or sent, so that its retries back off.
+    pub fn mark_failed(conn: Database, digest_id: i32, date: DateTime<Utc>) -> Result<()> {
+        use bottle_core::schema::digest;
+        diesel::update(digest::table.find(digest_id))
+            .set((
+                digest::last_failed_date.eq(date.naive_utc()),
+                digest::failure_count.eq(digest::failure_count + 1),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+}
+
+/// Delay before retrying a digest after the first failure.
+const RETRY_BASE_MINUTES: i64 = 15;
+/// Maximum exponent of the retry backoff, capping it at 1024 times the base delay.
+const MAX_RETRY_EXPONENT: i32 = 10;
+
+/// Delay before retrying a digest after consecutive failures, doubling with each one but at most its period.
+fn retry_backoff(failure_count: i32, period: chrono::Duration) -> chrono::Duration {
+    let exponent = (failure_count - 1).clamp(0, MAX_RETRY_EXPONENT);
+    chrono::Duration::minutes(RETRY_BASE_MINUTES << exponent).min(period)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff() {
+        let day = chrono::Duration::days(1);
+        assert_eq!(retry_backoff(1, day), chrono::Duration::minutes(15));
+        assert_eq!(retry_backoff(3, day), chrono::Duration::minutes(60));
+        // Capped at the period of the digest
+        assert_eq!(retry_backoff(8, day), day);
+        assert_eq!(
+            retry_backoff(100, chrono::Duration::weeks(1)),
+            chrono::Duration::weeks(1)
+        );
+    }
+}
@@ -3,13 +3,18 @@ use std::path::{Path, PathBuf};
 
 use diesel::prelude::*;
 
-use bottle_core::{Database, Result};
+use bottle_core::{library::ImageQuality, Database, Result};
 use bottle_download::{DownloadTask, LocalImage};
 
 use crate::model;
 
 /// Find the works in the database which are not downloaded yet.
-pub fn get_download_tasks(conn: Database, root_dir: impl AsRef<Path>) -> Result<Vec<DownloadTask>> {
+/// Images are downloaded in the quality of their community, or the original if not given.
+pub fn get_download_tasks(
+    conn: Database,
+    root_dir: impl AsRef<Path>,
+    qualities: &HashMap<String, ImageQuality>,
+) -> Result<Vec<DownloadTask>> {
     use bottle_core::schema::{image, pixiv_illust, tweet, work};
     use itertools::Itertools;
 
@@ -71,18 +76,44 @@ pub fn get_download_tasks(conn: Database, root_dir: impl AsRef<Path>) -> Result<
             subdir.push(user_id.to_string());
         }
 
+        let url = image.remote_url.expect("Download job must have a remote URL");
+        let quality = qualities.get(&community).copied().unwrap_or_default();
+        let (url, filename) = quality_variant(&community, url, image.filename, quality);
         jobs.push(DownloadTask {
-            url: image.remote_url.expect("Download job must have a remote URL"),
-            filename: PathBuf::from(image.filename),
+            url,
+            filename: PathBuf::from(bottle_download::sanitize_filename(&filename)),
             root_dir: root_dir.as_ref().to_path_buf(),
             subdir,
             image_id: image.id,
         });
     }
+    bottle_download::resolve_collisions(&mut jobs);
 
     Ok(jobs)
 }
 
+/// Get the URL and filename of the image in the quality, from those of the original image.
+fn quality_variant(community: &str, url: String, filename: String, quality: ImageQuality) -> (String, String) {
+    match (community, quality) {
+        // e.g. `https://pbs.twimg.com/media/{id}.jpg?name=orig`
+        ("twitter", ImageQuality::High) => (url.replace("name=orig", "name=4096x4096"), filename),
+        ("twitter", ImageQuality::Large) => (url.replace("name=orig", "name=large"), filename),
+        // e.g. `https://i.pximg.net/img-original/img/{date}/{id}_p0.png`
+        // to `https://i.pximg.net/img-master/img/{date}/{id}_p0_master1200.jpg`
+        ("pixiv", ImageQuality::Large) if url.contains("/img-original/") => {
+            let Some((stem, _)) = filename.rsplit_once('.') else {
+                return (url, filename);
+            };
+            let master_filename = format!("{}_master1200.jpg", stem);
+            let url = url
+                .replace("/img-original/", "/img-master/")
+                .replace(&filename, &master_filename);
+            (url, master_filename)
+        }
+        _ => (url, filename),
+    }
+}
+
 /// Update the downloaded image in the database, and return the updated image.
 pub fn update_from_local_image(conn: Database, image_id: i32, local_image: &LocalImage) -> Result<model::Image> {
     use bottle_core::schema::image::dsl::*;
@@ -94,6 +125,19 @@ pub fn update_from_local_image(conn: Database, image_id: i32, local_image: &Loca
     Ok(new_image)
 }
 
+/// Find another downloaded image with the same checksum, which can share the file with the given image.
+pub fn find_duplicate_image(conn: Database, image_id: i32, checksum: &str) -> Result<Option<model::Image>> {
+    use bottle_core::schema::image;
+    let result = image::table
+        .filter(image::checksum.eq(checksum))
+        .filter(image::id.ne(image_id))
+        .filter(image::path.is_not_null())
+        .order(image::id.asc())
+        .first::<model::Image>(conn)
+        .optional()?;
+    Ok(result)
+}
+
 /// Update the downloaded work thumbnail paths in the database.
 pub fn update_work_from_local_image(conn: Database, work_id: i32, local_image: &LocalImage) -> Result<()> {
     use bottle_core::schema::work;
@@ -0,0 +1,58 @@
+use diesel::prelude::*;
+use diesel::sql_types::{Integer, Text};
+
+use bottle_core::{Database, Error, Result};
+
+use crate::cover::feed_tables;
+
+// MARK: Feed failure
+
+/// Failures of the updates of feeds, recorded on their watch lists.
+#[derive(Debug)]
+pub struct FeedFailure;
+
+#[derive(QueryableByName)]
+struct FailureCount {
+    #[diesel(sql_type = Integer)]
+    failure_count: i32,
+}
+
+impl FeedFailure {
+    /// Record the error of a failed update, returning the number of consecutive failures.
+    pub fn record(conn: Database, community: &str, feed_id: i32, error: &str) -> Result<i32> {
+        let (watch_list_table, _, _) = feed_tables(community)?;
+        let result = diesel::sql_query(format!(
+            "update {watch_list_table} set last_error = ?, failure_count = failure_count + 1
+            where id = ? returning failure_count"
+        ))
+        .bind::<Text, _>(error)
+        .bind::<Integer, _>(feed_id)
+        .get_result::<FailureCount>(conn)
+        .optional()?;
+        let count = result.ok_or(Error::ObjectNotFound(format!("Feed {}", feed_id)))?;
+        Ok(count.failure_count)
+    }
+
+    /// Stop watching the feed after too many failures, returning whether it was watched.
+    pub fn pause(conn: Database, community: &str, feed_id: i32) -> Result<bool> {
+        let (watch_list_table, _, _) = feed_tables(community)?;
+        let count = diesel::sql_query(format!(
+            "update {watch_list_table} set watching = false where id = ? and watching"
+        ))
+        .bind::<Integer, _>(feed_id)
+        .execute(conn)?;
+        Ok(count > 0)
+    }
+
+    /// Clear the error after a successful update.
+    pub fn clear(conn: Database, community: &str, feed_id: i32) -> Result<()> {
+        let (watch_list_table, _, _) = feed_tables(community)?;
+        diesel::sql_query(format!(
+            "update {watch_list_table} set last_error = null, failure_count = 0
+            where id = ? and (last_error is not null or failure_count > 0)"
+        ))
+        .bind::<Integer, _>(feed_id)
+        .execute(conn)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use diesel::prelude::*;
+
+use bottle_core::{feed::FeedGroupView, Database, Error, Result};
+
+use crate::{album::POSITION_GAP, model};
+
+// MARK: Feed group
+
+#[derive(Debug)]
+pub struct FeedGroup;
+
+impl FeedGroup {
+    pub fn add(conn: Database, name: &str) -> Result<FeedGroupView> {
+        use bottle_core::schema::feed_group;
+
+        // Get max position of existing groups
+        let max_position = feed_group::table
+            .select(diesel::dsl::max(feed_group::position))
+            .first::<Option<i32>>(conn)?
+            .unwrap_or_default();
+
+        let new_group = model::NewFeedGroup {
+            name: name.to_string(),
+            position: max_position + POSITION_GAP,
+        };
+        let group = diesel::insert_into(feed_group::table)
+            .values(new_group)
+            .returning(model::FeedGroup::as_returning())
+            .get_result(conn)?;
+
+        tracing::info!(
+            "Added feed group {} \"{}\", position {}",
+            group.id,
+            name,
+            group.position
+        );
+        Ok(group.into())
+    }
+
+    pub fn delete(conn: Database, group_id: i32) -> Result<()> {
+        use bottle_core::schema::{feed_group, feed_group_feed};
+        conn.transaction(|conn| {
+            diesel::delete(feed_group_feed::table.filter(feed_group_feed::group_id.eq(group_id))).execute(conn)?;
+            diesel::delete(feed_group::table.find(group_id)).execute(conn)?;
+            Ok::<_, Error>(())
+        })?;
+        tracing::info!("Deleted feed group {}", group_id);
+        Ok(())
+    }
+
+    pub fn get(conn: Database, group_id: i32) -> Result<Option<FeedGroupView>> {
+        use bottle_core::schema::feed_group;
+        let group = feed_group::table
+            .find(group_id)
+            .first::<model::FeedGroup>(conn)
+            .optional()?;
+        Ok(group.map(FeedGroupView::from))
+    }
+
+    pub fn all(conn: Database) -> Result<Vec<FeedGroupView>> {
+        use bottle_core::schema::feed_group;
+        let groups = feed_group::table
+            .order_by(feed_group::position.asc())
+            .load::<model::FeedGroup>(conn)?;
+        Ok(groups.into_iter().map(FeedGroupView::from).collect())
+    }
+
+    pub fn rename(conn: Database, group_id: i32, name: &str) -> Result<FeedGroupView> {
+        use bottle_core::schema::feed_group;
+        diesel::update(feed_group::table.find(group_id))
+            .set((
+                feed_group::name.eq(name),
+                feed_group::modified_date.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+        let group = feed_group::table.find(group_id).first::<model::FeedGroup>(conn)?;
+        tracing::info!("Renamed feed group {} to \"{}\"", group_id, name);
+        Ok(group.into())
+    }
+
+    pub fn reorder(conn: Database, group_id: i32, position: Option<i32>) -> Result<FeedGroupView> {
+        use bottle_core::schema::feed_group;
+
+        // If position is not provided, move the group to the end
+        let position = if let Some(position) = position {
+            position
+        } else {
+            let max_position = feed_group::table
+                .select(diesel::dsl::max(feed_group::position))
+                .first::<Option<i32>>(conn)?
+                .unwrap_or_default();
+            max_position + POSITION_GAP
+        };
+
+        diesel::update(feed_group::table.find(group_id))
+            .set((
+                feed_group::position.eq(position),
+                feed_group::modified_date.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+        let group = feed_group::table.find(group_id).first::<model::FeedGroup>(conn)?;
+
+        tracing::info!("Reordered feed group {} to position {}", group_id, position);
+        Ok(group.into())
+    }
+
+    /// Put a feed into the group at the position, or at the end if not provided.
+    /// A feed belongs to at most one group, so it is moved out of its previous group.
+    pub fn add_feed(
+        conn: Database,
+        group_id: i32,
+        community: &str,
+        feed_id: i32,
+        position: Option<i32>,
+    ) -> Result<model::FeedGroupFeed> {
+        use bottle_core::schema::{feed_group, feed_group_feed};
+
+        // Check if the group exists
+        let group = feed_group::table
+            .find(group_id)
+            .first::<model::FeedGroup>(conn)
+            .optional()?;
+        if group.is_none() {
+            return Err(Error::ObjectNotFound(format!("Feed group {}", group_id)));
+        }
+
+        let position = if let Some(position) = position {
+            position
+        } else {
+            let max_position = feed_group_feed::table
+                .filter(feed_group_feed::group_id.eq(group_id))
+                .select(diesel::dsl::max(feed_group_feed::position))
+                .first::<Option<i32>>(conn)?
+                .unwrap_or_default();
+            max_position + POSITION_GAP
+        };
+
+        let member = model::FeedGroupFeed {
+            community: community.to_string(),
+            feed_id,
+            group_id,
+            position,
+        };
+        diesel::insert_into(feed_group_feed::table)
+            .values(&member)
+            .on_conflict((feed_group_feed::community, feed_group_feed::feed_id))
+            .do_update()
+            .set((
+                feed_group_feed::group_id.eq(group_id),
+                feed_group_feed::position.eq(position),
+            ))
+            .execute(conn)?;
+
+        tracing::info!(
+            "Put {} feed {} into feed group {}, position {}",
+            community,
+            feed_id,
+            group_id,
+            position
+        );
+        Ok(member)
+    }
+
+    /// Take a feed out of its group.
+    pub fn remove_feed(conn: Database, community: &str, feed_id: i32) -> Result<()> {
+        use bottle_core::schema::feed_group_feed;
+        let count = diesel::delete(
+            feed_group_feed::table
+                .filter(feed_group_feed::community.eq(community))
+                .filter(feed_group_feed::feed_id.eq(feed_id)),
+        )
+        .execute(conn)?;
+        if count > 0 {
+            tracing::info!("Removed {} feed {} from its feed group", community, feed_id);
+        }
+        Ok(())
+    }
+
+    /// Feeds in the group, in order.
+    pub fn feeds(conn: Database, group_id: i32) -> Result<Vec<model::FeedGroupFeed>> {
+        use bottle_core::schema::feed_group_feed;
+        let feeds = feed_group_feed::table
+            .filter(feed_group_feed::group_id.eq(group_id))
+            .order_by(feed_group_feed::position.asc())
+            .load::<model::FeedGroupFeed>(conn)?;
+        Ok(feeds)
+    }
+
+    /// Feeds in all groups, ordered by position in each group.
+    pub fn all_feeds(conn: Database) -> Result<Vec<model::FeedGroupFeed>> {
+        use bottle_core::schema::feed_group_feed;
+        let feeds = feed_group_feed::table
+            .order_by((feed_group_feed::group_id.asc(), feed_group_feed::position.asc()))
+            .load::<model::FeedGroupFeed>(conn)?;
+        Ok(feeds)
+    }
+}
@@ -0,0 +1,110 @@
+use diesel::prelude::*;
+
+use bottle_core::{
+    feed::GeneralResponse,
+    library::{ImageTextSearchView, ImageTextView, ImageView, WorkView},
+    Database, Result,
+};
+
+use crate::model;
+
+// MARK: Image text
+
+/// A downloaded image to extract the text of.
+#[derive(Debug, Clone)]
+pub struct ImageTextTask {
+    pub image_id: i32,
+    /// Path relative to the image directory.
+    pub path: String,
+}
+
+#[derive(Debug)]
+pub struct ImageText;
+
+impl ImageText {
+    /// Downloaded images of works from the source whose text is not extracted yet, oldest first.
+    pub fn tasks(conn: Database, source: &str, count: i64) -> Result<Vec<ImageTextTask>> {
+        use bottle_core::schema::{image, image_text, work};
+
+        let tasks = image::table
+            .inner_join(work::table)
+            .left_join(image_text::table)
+            .filter(work::source.eq(source))
+            .filter(image::path.is_not_null())
+            .filter(image_text::image_id.is_null())
+            .order_by(image::id.asc())
+            .select((image::id, image::path.assume_not_null()))
+            .limit(count)
+            .load::<(i32, String)>(conn)?
+            .into_iter()
+            .map(|(image_id, path)| ImageTextTask { image_id, path })
+            .collect();
+        Ok(tasks)
+    }
+
+    /// Save the text extracted from the image, replacing the previous one.
+    pub fn save(conn: Database, image_id: i32, content: &str) -> Result<()> {
+        use bottle_core::schema::image_text;
+
+        let text = model::ImageText {
+            image_id,
+            content: content.to_string(),
+            extracted_date: chrono::Utc::now().naive_utc(),
+        };
+        diesel::replace_into(image_text::table).values(&text).execute(conn)?;
+        Ok(())
+    }
+
+    /// Search works with images whose text contains the query, with the matching images only.
+    pub fn search(conn: Database, query: &str, page: i64, page_size: i64) -> Result<ImageTextSearchView> {
+        use bottle_core::schema::{image, image_text, work};
+        use bottle_util::diesel_ext::Paginate;
+
+        let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let pattern = format!("%{}%", escaped);
+
+        // 1. Fetch works with any matching image
+        let matching_work_ids = image::table
+            .inner_join(image_text::table)
+            .filter(image_text::content.like(&pattern).escape('\\'))
+            .select(image::work_id);
+        let (works, total_items) = work::table
+            .filter(work::id.eq_any(matching_work_ids))
+            .order_by((work::added_date.desc(), work::id.desc()))
+            .select(work::all_columns)
+            .paginate(page, page_size)
+            .load_and_count::<model::Work>(conn)?;
+
+        // 2. Fetch the matching images of the works
+        let work_ids = works.iter().map(|work| work.id).collect::<Vec<_>>();
+        let rows = image::table
+            .inner_join(image_text::table)
+            .filter(image::work_id.eq_any(&work_ids))
+            .filter(image_text::content.like(&pattern).escape('\\'))
+            .order_by((image::work_id.asc(), image::page_index.asc()))
+            .select((image::all_columns, image_text::all_columns))
+            .load::<(model::Image, model::ImageText)>(conn)?;
+
+        let texts = rows
+            .iter()
+            .map(|(image, text)| ImageTextView {
+                image_id: image.id,
+                work_id: image.work_id,
+                page_index: image.page_index,
+                content: text.content.clone(),
+                extracted_date: text.extracted_date.and_utc(),
+            })
+            .collect();
+        Ok(ImageTextSearchView {
+            texts,
+            works: GeneralResponse {
+                works: Some(works.into_iter().map(WorkView::from).collect()),
+                images: Some(rows.into_iter().map(|(image, _)| ImageView::from(image)).collect()),
+                total_items,
+                page,
+                page_size,
+                ..Default::default()
+            },
+        })
+    }
+}
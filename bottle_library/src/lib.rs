@@ -1,9 +1,43 @@
+mod account_request;
 mod album;
+mod artist_link;
+mod booru_upload;
+mod cover;
+mod digest;
 mod download;
+mod feed_failure;
+mod feed_group;
+mod image_text;
 pub mod model;
+mod note;
+mod queue;
+mod remote_status;
+mod repair;
+mod similar;
+mod statistics;
+mod user_presence;
+mod user_tag;
 mod util;
+mod webhook;
 mod work;
 
+pub use account_request::*;
 pub use album::*;
+pub use artist_link::*;
+pub use booru_upload::*;
+pub use cover::*;
+pub use digest::*;
 pub use download::*;
+pub use feed_failure::*;
+pub use feed_group::*;
+pub use image_text::*;
+pub use note::*;
+pub use queue::*;
+pub use remote_status::*;
+pub use repair::*;
+pub use similar::*;
+pub use statistics::*;
+pub use user_presence::*;
+pub use user_tag::*;
+pub use webhook::*;
 pub use work::*;
@@ -3,7 +3,7 @@
 // Notes: Didn't involve much association feature here, like `belongs_to`,
 // since I usually directly build the query instead of starting from a parent object.
 
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
 use serde::Serialize;
 
@@ -33,6 +33,12 @@ pub struct Work {
     pub added_date: NaiveDateTime,
     pub modified_date: NaiveDateTime,
     pub viewed_date: Option<NaiveDateTime>,
+    /// Normalized content rating, which is one of `general`, `sensitive` and `explicit`. None if unrated.
+    pub content_rating: Option<String>,
+    /// Index of the image last read in the work.
+    pub last_read_page: Option<i32>,
+    /// Work which this work is a newer version of.
+    pub previous_work_id: Option<i32>,
 }
 
 #[derive(Insertable, Debug, Clone, Default)]
@@ -49,6 +55,7 @@ pub struct NewWork {
     pub image_count: i32,
     pub name: Option<String>,
     pub caption: Option<String>,
+    pub content_rating: Option<String>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone, Serialize)]
@@ -68,6 +75,18 @@ pub struct Image {
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub size: Option<i32>,
+    /// SHA-256 digest of the file content in hex, used for finding duplicate files.
+    pub checksum: Option<String>,
+    /// Difference hash of the image in hex, used for finding similar images.
+    pub perceptual_hash: Option<String>,
+    /// Whether the image is an animated GIF, WebP or APNG.
+    pub is_animated: bool,
+    /// MD5 digest of the file content in hex, used for finding posts of the same file on boorus.
+    pub md5: Option<String>,
+    /// Comma-separated dominant colors of the image in hex, most dominant first.
+    pub palette: Option<String>,
+    /// Bitmask of the hues of the dominant colors, used for finding works by color.
+    pub palette_hues: Option<i32>,
 }
 
 #[derive(Insertable, Debug, Clone, Default)]
@@ -95,6 +114,12 @@ pub struct ImageUpdate {
     pub width: Option<i32>,
     pub height: Option<i32>,
     pub size: Option<i32>,
+    pub checksum: Option<String>,
+    pub perceptual_hash: Option<String>,
+    pub is_animated: Option<bool>,
+    pub md5: Option<String>,
+    pub palette: Option<String>,
+    pub palette_hues: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize)]
@@ -107,6 +132,9 @@ pub struct Album {
     pub position: i32,
     pub added_date: NaiveDateTime,
     pub modified_date: NaiveDateTime,
+    pub cover_path: Option<String>,
+    /// JSON of the filter if it is a smart album.
+    pub filter: Option<String>,
 }
 
 #[derive(Insertable, Debug, Clone, Default)]
@@ -116,6 +144,7 @@ pub struct NewAlbum {
     pub name: String,
     pub folder_id: Option<i32>,
     pub position: i32,
+    pub filter: Option<String>,
 }
 
 #[derive(AsChangeset, Debug, Clone)]
@@ -166,3 +195,259 @@ pub struct FolderUpdate {
     pub parent_id: Option<i32>,
     pub position: i32,
 }
+
+// MARK: Webhook
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize)]
+#[diesel(table_name = webhook)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Webhook {
+    pub id: i32,
+    pub kind: String,
+    pub community: String,
+    pub feed_id: Option<i32>,
+    pub user_id: Option<String>,
+    pub url: String,
+    pub chat_id: Option<String>,
+    pub bot_token: Option<String>,
+    pub template: Option<String>,
+    pub added_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone, Default)]
+#[diesel(table_name = webhook)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewWebhook {
+    pub kind: String,
+    pub community: String,
+    pub feed_id: Option<i32>,
+    pub user_id: Option<String>,
+    pub url: String,
+    pub chat_id: Option<String>,
+    pub bot_token: Option<String>,
+    pub template: Option<String>,
+}
+
+// MARK: Feed group
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize)]
+#[diesel(table_name = feed_group)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FeedGroup {
+    pub id: i32,
+    pub name: String,
+    pub position: i32,
+    pub added_date: NaiveDateTime,
+    pub modified_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone, Default)]
+#[diesel(table_name = feed_group)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewFeedGroup {
+    pub name: String,
+    pub position: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone, Serialize)]
+#[diesel(table_name = feed_group_feed)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FeedGroupFeed {
+    pub community: String,
+    pub feed_id: i32,
+    pub group_id: i32,
+    pub position: i32,
+}
+
+// MARK: Digest
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize)]
+#[diesel(table_name = digest)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Digest {
+    pub id: i32,
+    pub email: String,
+    pub frequency: String,
+    pub last_sent_date: Option<NaiveDateTime>,
+    /// Total size of the image directory in bytes when the digest was last sent.
+    pub last_disk_usage: Option<i64>,
+    pub added_date: NaiveDateTime,
+    /// When the digest last failed to be rendered or sent.
+    pub last_failed_date: Option<NaiveDateTime>,
+    /// Number of consecutive failures since last sent.
+    pub failure_count: i32,
+}
+
+#[derive(Insertable, Debug, Clone, Default)]
+#[diesel(table_name = digest)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewDigest {
+    pub email: String,
+    pub frequency: String,
+}
+
+// MARK: Account request
+
+#[derive(Queryable, Selectable, Debug, Clone, Serialize)]
+#[diesel(table_name = account_request)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct AccountRequest {
+    pub community: String,
+    pub account_id: i32,
+    pub day: NaiveDate,
+    pub request_count: i32,
+    pub rate_limit_count: i32,
+    pub error_count: i32,
+    pub last_success_date: Option<NaiveDateTime>,
+}
+
+// MARK: Statistics
+
+#[derive(Queryable, Selectable, Debug, Clone, Serialize)]
+#[diesel(table_name = artist_stat)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ArtistStat {
+    pub community: String,
+    pub artist: String,
+    pub artist_name: Option<String>,
+    pub work_count: i32,
+    pub image_count: i32,
+    pub last_added_date: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone, Serialize)]
+#[diesel(table_name = feed_stat)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FeedStat {
+    pub community: String,
+    pub feed_id: i32,
+    pub day: NaiveDate,
+    pub post_count: i32,
+}
+
+// MARK: Reading queue
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize)]
+#[diesel(table_name = reading_queue)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ReadingQueueItem {
+    pub id: i32,
+    pub community: String,
+    pub post_id: String,
+    pub position: i32,
+    pub added_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = reading_queue)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewReadingQueueItem {
+    pub community: String,
+    pub post_id: String,
+    pub position: i32,
+}
+
+// MARK: Remote status
+
+#[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone, Serialize)]
+#[diesel(table_name = remote_status)]
+#[diesel(primary_key(work_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RemoteStatus {
+    pub work_id: i32,
+    pub status_code: i32,
+    pub checked_date: NaiveDateTime,
+    pub vanished_date: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone, Serialize)]
+#[diesel(table_name = image_text)]
+#[diesel(primary_key(image_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ImageText {
+    pub image_id: i32,
+    pub content: String,
+    pub extracted_date: NaiveDateTime,
+}
+
+// MARK: Post note
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize)]
+#[diesel(table_name = post_note)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PostNote {
+    pub id: i32,
+    pub community: Option<String>,
+    pub post_id: Option<String>,
+    pub work_id: Option<i32>,
+    pub content: String,
+    pub added_date: NaiveDateTime,
+    pub modified_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = post_note)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewPostNote {
+    pub community: Option<String>,
+    pub post_id: Option<String>,
+    pub work_id: Option<i32>,
+    pub content: String,
+}
+
+// MARK: User tag
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone, Serialize)]
+#[diesel(table_name = user_tag)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct UserTag {
+    pub id: i32,
+    pub name: String,
+    pub added_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = work_user_tag)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewWorkUserTag {
+    pub work_id: i32,
+    pub tag_id: i32,
+}
+
+// MARK: Booru upload
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = booru_upload)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewBooruUpload {
+    pub booru: String,
+    pub image_id: i32,
+    pub remote_id: String,
+}
+
+// MARK: Artist link
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = artist_link)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ArtistLink {
+    pub id: i32,
+    pub community: String,
+    pub user_id: String,
+    pub linked_community: String,
+    pub linked_user_id: String,
+    pub reason: String,
+    pub status: String,
+    pub created_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = artist_link)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewArtistLink {
+    pub community: String,
+    pub user_id: String,
+    pub linked_community: String,
+    pub linked_user_id: String,
+    pub reason: String,
+}
@@ -0,0 +1,135 @@
+use chrono::Utc;
+use diesel::prelude::*;
+
+use bottle_core::{feed::GeneralResponse, library::NoteView, Database, Error, Result};
+
+use crate::model;
+
+// MARK: Post note
+
+#[derive(Debug)]
+pub struct PostNote;
+
+impl PostNote {
+    /// Add a note to either a post given by the community and post ID, or a work in the library.
+    pub fn add(conn: Database, new_note: model::NewPostNote) -> Result<NoteView> {
+        use bottle_core::schema::{post_note, work};
+
+        let is_post = new_note.community.is_some() && new_note.post_id.is_some();
+        if is_post == new_note.work_id.is_some() {
+            return Err(Error::InvalidEndpoint(
+                "Note requires either a community and a post ID, or a work ID".to_string(),
+            ));
+        }
+        if new_note.content.trim().is_empty() {
+            return Err(Error::InvalidEndpoint("Note content is empty".to_string()));
+        }
+        if let Some(work_id) = new_note.work_id {
+            let count = work::table.find(work_id).count().get_result::<i64>(conn)?;
+            if count == 0 {
+                return Err(Error::ObjectNotFound(format!("Work {}", work_id)));
+            }
+        }
+
+        let note = diesel::insert_into(post_note::table)
+            .values(new_note)
+            .returning(model::PostNote::as_returning())
+            .get_result(conn)?;
+
+        tracing::info!(
+            "Added note {} to {}",
+            note.id,
+            match (&note.community, &note.post_id, note.work_id) {
+                (Some(community), Some(post_id), _) => format!("post {}@{}", post_id, community),
+                (_, _, Some(work_id)) => format!("work {}", work_id),
+                _ => "nothing".to_string(),
+            }
+        );
+        Ok(note.into())
+    }
+
+    pub fn modify(conn: Database, note_id: i32, content: &str) -> Result<NoteView> {
+        use bottle_core::schema::post_note;
+
+        if content.trim().is_empty() {
+            return Err(Error::InvalidEndpoint("Note content is empty".to_string()));
+        }
+        let count = diesel::update(post_note::table.find(note_id))
+            .set((
+                post_note::content.eq(content),
+                post_note::modified_date.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+        if count == 0 {
+            return Err(Error::ObjectNotFound(format!("Note {}", note_id)));
+        }
+        let note = post_note::table.find(note_id).first::<model::PostNote>(conn)?;
+        tracing::info!("Modified note {}", note_id);
+        Ok(note.into())
+    }
+
+    pub fn delete(conn: Database, note_id: i32) -> Result<()> {
+        use bottle_core::schema::post_note;
+        diesel::delete(post_note::table.find(note_id)).execute(conn)?;
+        tracing::info!("Deleted note {}", note_id);
+        Ok(())
+    }
+
+    /// Search notes containing the text, most recently modified first.
+    pub fn search(conn: Database, query: &str, page: i64, page_size: i64) -> Result<Vec<NoteView>> {
+        use bottle_core::schema::post_note;
+
+        let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let notes = post_note::table
+            .filter(post_note::content.like(format!("%{}%", escaped)).escape('\\'))
+            .order(post_note::modified_date.desc())
+            .offset(page * page_size)
+            .limit(page_size)
+            .load::<model::PostNote>(conn)?;
+        Ok(notes.into_iter().map(NoteView::from).collect())
+    }
+
+    /// Add the notes of the posts and works in the response.
+    pub fn adding_to(conn: Database, response: GeneralResponse) -> Result<GeneralResponse> {
+        use bottle_core::schema::post_note;
+
+        let works = response.works.as_deref().unwrap_or_default();
+        let work_ids = works.iter().map(|work| work.id).collect::<Vec<_>>();
+        let posts = response
+            .posts
+            .iter()
+            .flatten()
+            .map(|post| (post.community.as_str(), post.post_id.as_str()))
+            .chain(
+                works
+                    .iter()
+                    .filter_map(|work| work.community.as_deref().zip(work.post_id.as_deref())),
+            )
+            .collect::<Vec<_>>();
+        if work_ids.is_empty() && posts.is_empty() {
+            return Ok(response);
+        }
+
+        let post_ids = posts.iter().map(|(_, post_id)| *post_id).collect::<Vec<_>>();
+        let notes = post_note::table
+            .filter(
+                post_note::work_id
+                    .eq_any(&work_ids)
+                    .or(post_note::post_id.eq_any(&post_ids)),
+            )
+            .order(post_note::added_date.asc())
+            .load::<model::PostNote>(conn)?
+            .into_iter()
+            .filter(|note| match (&note.community, &note.post_id) {
+                (Some(community), Some(post_id)) => posts.contains(&(community.as_str(), post_id.as_str())),
+                _ => true,
+            })
+            .map(NoteView::from)
+            .collect();
+
+        Ok(GeneralResponse {
+            notes: Some(notes),
+            ..response
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use diesel::prelude::*;
+
+use bottle_core::{feed::QueueItemView, Database, Error, Result};
+
+use crate::{album::POSITION_GAP, model};
+
+// MARK: Reading queue
+
+#[derive(Debug)]
+pub struct ReadingQueue;
+
+impl ReadingQueue {
+    /// Push a post to the end of the queue, or to the front if `front` is set.
+    pub fn push(conn: Database, community: &str, post_id: &str, front: bool) -> Result<QueueItemView> {
+        use bottle_core::schema::reading_queue;
+
+        // Check if the post is already in the queue
+        let existing = reading_queue::table
+            .filter(reading_queue::community.eq(community))
+            .filter(reading_queue::post_id.eq(post_id))
+            .first::<model::ReadingQueueItem>(conn)
+            .optional()?;
+        if existing.is_some() {
+            return Err(Error::ObjectAlreadyExists(format!(
+                "Post {} at Community {} in reading queue",
+                post_id, community
+            )));
+        }
+
+        let position = if front {
+            let min_position = reading_queue::table
+                .select(diesel::dsl::min(reading_queue::position))
+                .first::<Option<i32>>(conn)?
+                .unwrap_or_default();
+            min_position - POSITION_GAP
+        } else {
+            let max_position = reading_queue::table
+                .select(diesel::dsl::max(reading_queue::position))
+                .first::<Option<i32>>(conn)?
+                .unwrap_or_default();
+            max_position + POSITION_GAP
+        };
+
+        let new_item = model::NewReadingQueueItem {
+            community: community.to_string(),
+            post_id: post_id.to_string(),
+            position,
+        };
+        let item = diesel::insert_into(reading_queue::table)
+            .values(new_item)
+            .returning(model::ReadingQueueItem::as_returning())
+            .get_result(conn)?;
+
+        tracing::info!(
+            "Pushed post {} at community {} to reading queue, position {}",
+            post_id,
+            community,
+            item.position
+        );
+        Ok(item.into())
+    }
+
+    /// Remove and return the item at the front of the queue.
+    pub fn pop(conn: Database) -> Result<Option<QueueItemView>> {
+        use bottle_core::schema::reading_queue;
+        let item = conn.transaction(|conn| {
+            let item = reading_queue::table
+                .order_by((reading_queue::position.asc(), reading_queue::id.asc()))
+                .first::<model::ReadingQueueItem>(conn)
+                .optional()?;
+            if let Some(item) = &item {
+                diesel::delete(reading_queue::table.find(item.id)).execute(conn)?;
+            }
+            Ok::<_, Error>(item)
+        })?;
+        if let Some(item) = &item {
+            tracing::info!(
+                "Popped post {} at community {} from reading queue",
+                item.post_id,
+                item.community
+            );
+        }
+        Ok(item.map(QueueItemView::from))
+    }
+
+    pub fn delete(conn: Database, item_id: i32) -> Result<()> {
+        use bottle_core::schema::reading_queue;
+        diesel::delete(reading_queue::table.find(item_id)).execute(conn)?;
+        tracing::info!("Deleted reading queue item {}", item_id);
+        Ok(())
+    }
+
+    pub fn reorder(conn: Database, item_id: i32, position: Option<i32>) -> Result<QueueItemView> {
+        use bottle_core::schema::reading_queue;
+
+        // If position is not provided, move the item to the end
+        let position = if let Some(position) = position {
+            position
+        } else {
+            let max_position = reading_queue::table
+                .select(diesel::dsl::max(reading_queue::position))
+                .first::<Option<i32>>(conn)?
+                .unwrap_or_default();
+            max_position + POSITION_GAP
+        };
+
+        let item = diesel::update(reading_queue::table.find(item_id))
+            .set(reading_queue::position.eq(position))
+            .returning(model::ReadingQueueItem::as_returning())
+            .get_result(conn)
+            .optional()?
+            .ok_or(Error::ObjectNotFound(format!("Reading queue item {}", item_id)))?;
+
+        tracing::info!("Reordered reading queue item {} to position {}", item_id, position);
+        Ok(item.into())
+    }
+
+    /// Items of the queue in order, with the total number of items.
+    pub fn items(conn: Database, page: i64, page_size: i64) -> Result<(Vec<QueueItemView>, i64)> {
+        use bottle_core::schema::reading_queue;
+        use bottle_util::diesel_ext::Paginate;
+
+        let (items, total_items) = reading_queue::table
+            .order_by((reading_queue::position.asc(), reading_queue::id.asc()))
+            .paginate(page, page_size)
+            .load_and_count::<model::ReadingQueueItem>(conn)?;
+        Ok((items.into_iter().map(QueueItemView::from).collect(), total_items))
+    }
+}
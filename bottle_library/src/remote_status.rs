@@ -0,0 +1,180 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text, Timestamp};
+
+use bottle_core::{
+    feed::GeneralResponse,
+    library::{ImageView, RemoteStatusView, VanishedArtistView, VanishedReportView, WorkView},
+    Database, Result,
+};
+
+use crate::model;
+
+// MARK: Remote status
+
+/// Artists of the vanished works in each community, the same way as the artist statistics.
+const VANISHED_ARTISTS_QUERY: &str = "
+select community, artist, max(artist_name) as artist_name, count() as work_count
+from (
+    select 'twitter' as community, cast(tweet.user_id as text) as artist, twitter_user.name as artist_name
+    from work
+    join remote_status on remote_status.work_id = work.id and remote_status.vanished_date is not null
+    join tweet on work.source = 'twitter' and work.post_id_int = tweet.id
+    left join twitter_user on tweet.user_id = twitter_user.id
+    union all
+    select 'pixiv', cast(pixiv_illust.user_id as text), pixiv_user.name
+    from work
+    join remote_status on remote_status.work_id = work.id and remote_status.vanished_date is not null
+    join pixiv_illust on work.source = 'pixiv' and work.post_id_int = pixiv_illust.id
+    left join pixiv_user on pixiv_illust.user_id = pixiv_user.id
+    union all
+    select 'yandere', yandere_tag.name, yandere_tag.name
+    from work
+    join remote_status on remote_status.work_id = work.id and remote_status.vanished_date is not null
+    join yandere_post_tag on work.source = 'yandere' and work.post_id_int = yandere_post_tag.post_id
+    join yandere_tag on yandere_post_tag.tag_name = yandere_tag.name and yandere_tag.type = 'artist'
+    union all
+    select 'panda', panda_gallery_tag.name, panda_gallery_tag.name
+    from work
+    join remote_status on remote_status.work_id = work.id and remote_status.vanished_date is not null
+    join panda_gallery_tag on work.source = 'panda' and work.post_id_int = panda_gallery_tag.gallery_id
+    where panda_gallery_tag.namespace = 'artist'
+)
+where ? is null or community = ?
+group by community, artist
+order by work_count desc, community, artist";
+
+/// Works from communities with the remote URL of their first image,
+/// which have never been checked or were checked before the date, never checked first.
+const UNCHECKED_WORKS_QUERY: &str = "
+select work.id as work_id, image.remote_url as url
+from work
+join image on image.id = (
+    select min(id) from image where image.work_id = work.id and image.remote_url is not null
+)
+left join remote_status on remote_status.work_id = work.id
+where work.source is not null and (remote_status.checked_date is null or remote_status.checked_date < ?)
+order by remote_status.checked_date is not null, remote_status.checked_date, work.id
+limit ?";
+
+#[derive(QueryableByName)]
+struct VanishedArtist {
+    #[diesel(sql_type = Text)]
+    community: String,
+    #[diesel(sql_type = Text)]
+    artist: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    artist_name: Option<String>,
+    #[diesel(sql_type = BigInt)]
+    work_count: i64,
+}
+
+/// An archived work to check if its remote source still exists.
+#[derive(QueryableByName, Debug, Clone)]
+pub struct RemoteCheckTask {
+    #[diesel(sql_type = Integer)]
+    pub work_id: i32,
+    #[diesel(sql_type = Text)]
+    pub url: String,
+}
+
+#[derive(Debug)]
+pub struct RemoteStatus;
+
+impl RemoteStatus {
+    /// Works to check, which have never been checked or were checked before the date.
+    pub fn tasks(conn: Database, checked_before: NaiveDateTime, count: i64) -> Result<Vec<RemoteCheckTask>> {
+        let tasks = diesel::sql_query(UNCHECKED_WORKS_QUERY)
+            .bind::<Timestamp, _>(checked_before)
+            .bind::<BigInt, _>(count)
+            .load::<RemoteCheckTask>(conn)?;
+        Ok(tasks)
+    }
+
+    /// Record the result of a check, keeping the date when the remote source was first found vanished.
+    pub fn record(conn: Database, work_id: i32, status_code: i32, vanished: bool) -> Result<RemoteStatusView> {
+        use bottle_core::schema::remote_status;
+
+        let now = chrono::Utc::now().naive_utc();
+        let previous_vanished_date = remote_status::table
+            .find(work_id)
+            .select(remote_status::vanished_date)
+            .first::<Option<NaiveDateTime>>(conn)
+            .optional()?
+            .flatten();
+        let status = model::RemoteStatus {
+            work_id,
+            status_code,
+            checked_date: now,
+            vanished_date: vanished.then(|| previous_vanished_date.unwrap_or(now)),
+        };
+        diesel::replace_into(remote_status::table)
+            .values(&status)
+            .execute(conn)?;
+
+        if vanished && previous_vanished_date.is_none() {
+            tracing::info!("Remote source of work {} has vanished, status {}", work_id, status_code);
+        }
+        Ok(status.into())
+    }
+
+    /// Vanished works from the community, or all communities if not provided, with the number of them by each artist.
+    pub fn vanished_report(
+        conn: Database,
+        community: Option<&str>,
+        page: i64,
+        page_size: i64,
+    ) -> Result<VanishedReportView> {
+        use bottle_core::schema::{image, remote_status, work};
+        use bottle_util::diesel_ext::Paginate;
+
+        // 1. Count vanished works by artist
+        let artists = diesel::sql_query(VANISHED_ARTISTS_QUERY)
+            .bind::<Nullable<Text>, _>(community)
+            .bind::<Nullable<Text>, _>(community)
+            .load::<VanishedArtist>(conn)?
+            .into_iter()
+            .map(|artist| VanishedArtistView {
+                community: artist.community,
+                artist: artist.artist,
+                artist_name: artist.artist_name,
+                work_count: artist.work_count,
+            })
+            .collect();
+
+        // 2. Fetch vanished works
+        let mut query = work::table
+            .inner_join(remote_status::table)
+            .filter(remote_status::vanished_date.is_not_null())
+            .into_boxed();
+        if let Some(community) = community {
+            query = query.filter(work::source.eq(community));
+        }
+        let (rows, total_items) = query
+            .order_by((remote_status::vanished_date.desc(), work::id.desc()))
+            .select((work::all_columns, remote_status::all_columns))
+            .paginate(page, page_size)
+            .load_and_count::<(model::Work, model::RemoteStatus)>(conn)?;
+        let (works, statuses): (Vec<_>, Vec<_>) = rows.into_iter().unzip();
+
+        // 3. Fetch images
+        let work_ids = works.iter().map(|work| work.id);
+        let images = image::table
+            .filter(image::work_id.eq_any(work_ids))
+            .order_by(image::page_index.asc())
+            .load::<model::Image>(conn)?;
+
+        Ok(VanishedReportView {
+            artists,
+            statuses: statuses.into_iter().map(RemoteStatusView::from).collect(),
+            works: GeneralResponse {
+                works: Some(works.into_iter().map(WorkView::from).collect()),
+                images: Some(images.into_iter().map(ImageView::from).collect()),
+                total_items,
+                page,
+                page_size,
+                ..Default::default()
+            },
+        })
+    }
+}
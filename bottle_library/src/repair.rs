@@ -0,0 +1,133 @@
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Nullable};
+
+use std::path::Path;
+
+use bottle_core::{library::RepairReportView, Database, Error, Result};
+
+// MARK: Repair
+
+/// Watch list post tables of communities, with their post ID columns.
+const WATCH_LIST_POST_TABLES: [(&str, &str, &str); 4] = [
+    ("twitter", "twitter_watch_list_tweet", "tweet_id"),
+    ("pixiv", "pixiv_watch_list_illust", "illust_id"),
+    ("yandere", "yandere_watch_list_post", "post_id"),
+    ("panda", "panda_watch_list_gallery", "gallery_id"),
+];
+
+/// Number of images updated in each statement, to stay below the limit of bound parameters.
+const REPAIR_BATCH_SIZE: usize = 500;
+
+#[derive(QueryableByName)]
+struct WatchListPost {
+    #[diesel(sql_type = BigInt)]
+    post_id: i64,
+}
+
+/// Delete posts without sort index from the watch list of the community, or from all of them if not given.
+/// Posts are saved before their sort index is set, so they are left without it if the feed update is interrupted.
+/// Return the IDs of the deleted posts.
+pub fn delete_unsorted_watch_list_posts(
+    conn: Database,
+    community: &str,
+    watch_list_id: Option<i32>,
+) -> Result<Vec<i64>> {
+    let Some((_, table, column)) = WATCH_LIST_POST_TABLES.iter().find(|(name, _, _)| *name == community) else {
+        return Err(Error::UnknownField(format!("Unknown community {}", community)));
+    };
+    let query = format!(
+        "delete from {table} where sort_index is null and watch_list_id = coalesce(?, watch_list_id)
+        returning {column} as post_id"
+    );
+    let post_ids = diesel::sql_query(query)
+        .bind::<Nullable<Integer>, _>(watch_list_id)
+        .load::<WatchListPost>(conn)?
+        .into_iter()
+        .map(|row| row.post_id)
+        .collect();
+    Ok(post_ids)
+}
+
+/// Find and repair inconsistencies left by crashes or manual changes to the library, which should run at startup
+/// before any feed update, since posts being saved by an update don't have sort index yet.
+/// Watch list posts without sort index and orphaned community media are deleted.
+/// Downloaded images whose file is missing are only reported, unless `reset_missing_files` is set, which resets them to
+/// be downloaded again. Even then they are kept if all of them are missing, which is likely an unmounted directory.
+/// Works without any image are only reported.
+pub fn repair_library(
+    conn: Database,
+    image_dir: impl AsRef<Path>,
+    reset_missing_files: bool,
+) -> Result<RepairReportView> {
+    use bottle_core::schema::{
+        image, panda_gallery, panda_media, pixiv_illust, pixiv_media, tweet, twitter_media, work,
+    };
+    use diesel::dsl::{exists, not};
+
+    let image_dir = image_dir.as_ref();
+    let mut report = RepairReportView::default();
+
+    // 1. Find downloaded images whose file is missing
+    let downloaded_images = image::table
+        .filter(image::path.is_not_null())
+        .select((image::id, image::path.assume_not_null()))
+        .load::<(i32, String)>(conn)?;
+    report.missing_file_image_ids = downloaded_images
+        .iter()
+        .filter(|(_, path)| !image_dir.join(path).exists())
+        .map(|(id, _)| *id)
+        .collect();
+    let any_missing = !report.missing_file_image_ids.is_empty();
+    let all_missing = any_missing && report.missing_file_image_ids.len() == downloaded_images.len();
+    report.reset_missing_files = reset_missing_files && any_missing && !all_missing;
+    if reset_missing_files && all_missing {
+        tracing::warn!(
+            "All {} downloaded images are missing from {}, skipped resetting them",
+            downloaded_images.len(),
+            image_dir.display()
+        );
+    }
+
+    // 2. Find works without any image
+    report.empty_work_ids = work::table
+        .filter(not(exists(image::table.filter(image::work_id.eq(work::id)))))
+        .order_by(work::id.asc())
+        .select(work::id)
+        .load::<i32>(conn)?;
+
+    conn.transaction(|conn| -> Result<()> {
+        // 3. Delete watch list posts without sort index
+        for (community, _, _) in WATCH_LIST_POST_TABLES {
+            report.unsorted_watch_list_posts += delete_unsorted_watch_list_posts(conn, community, None)?.len();
+        }
+
+        // 4. Delete media of posts which no longer exist
+        report.orphaned_media +=
+            diesel::delete(twitter_media::table.filter(twitter_media::tweet_id.ne_all(tweet::table.select(tweet::id))))
+                .execute(conn)?;
+        report.orphaned_media += diesel::delete(
+            pixiv_media::table.filter(pixiv_media::illust_id.ne_all(pixiv_illust::table.select(pixiv_illust::id))),
+        )
+        .execute(conn)?;
+        report.orphaned_media += diesel::delete(
+            panda_media::table.filter(panda_media::gallery_id.ne_all(panda_gallery::table.select(panda_gallery::id))),
+        )
+        .execute(conn)?;
+
+        // 5. Reset images with missing files if requested, so that they are downloaded again
+        if report.reset_missing_files {
+            for ids in report.missing_file_image_ids.chunks(REPAIR_BATCH_SIZE) {
+                diesel::update(image::table.filter(image::id.eq_any(ids)))
+                    .set((
+                        image::path.eq(None::<String>),
+                        image::thumbnail_path.eq(None::<String>),
+                        image::small_thumbnail_path.eq(None::<String>),
+                    ))
+                    .execute(conn)?;
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(report)
+}
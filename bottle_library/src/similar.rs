@@ -0,0 +1,115 @@
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer};
+
+use bottle_core::{
+    feed::GeneralResponse,
+    library::{ImageView, WorkFilter, WorkView},
+    Database, Error, Result,
+};
+
+use crate::model;
+
+// MARK: Similar works
+
+/// Works sharing features with the work, the most similar first, with the total number of them.
+/// Features are the artist, the community tags and the user tags of works, weighted 4, 1 and 2 respectively,
+/// and the score of a work is the sum of the weights of the shared features, plus 1 if it is of the same community.
+/// Artists and tags are matched the same way as the statistics, and only within the same community.
+/// Other works of the same post are left out. The work ID, the page size and the offset are bound in order.
+/// `{condition}` is replaced with an extra condition on the `work` table.
+const SIMILAR_WORKS_QUERY: &str = "
+with target_work(id, source, post_id) as (select id, source, post_id from work where id = ?),
+feature(work_id, weight, feature) as (
+    select work.id, 4, 'twitter:artist:' || tweet.user_id
+    from work
+    join tweet on work.source = 'twitter' and work.post_id_int = tweet.id
+    union all
+    select work.id, 4, 'pixiv:artist:' || pixiv_illust.user_id
+    from work
+    join pixiv_illust on work.source = 'pixiv' and work.post_id_int = pixiv_illust.id
+    union all
+    select work.id, case yandere_tag.type when 'artist' then 4 else 1 end,
+        'yandere:' || case yandere_tag.type when 'artist' then 'artist:' else 'tag:' end || yandere_post_tag.tag_name
+    from work
+    join yandere_post_tag on work.source = 'yandere' and work.post_id_int = yandere_post_tag.post_id
+    left join yandere_tag on yandere_post_tag.tag_name = yandere_tag.name
+    union all
+    select work.id, case panda_gallery_tag.namespace when 'artist' then 4 else 1 end,
+        'panda:tag:' || panda_gallery_tag.namespace || ':' || panda_gallery_tag.name
+    from work
+    join panda_gallery_tag on work.source = 'panda' and work.post_id_int = panda_gallery_tag.gallery_id
+    union all
+    select work.id, 1, 'twitter:tag:' || twitter_tag.tag
+    from work
+    join twitter_tag on work.source = 'twitter' and work.post_id_int = twitter_tag.tweet_id
+    union all
+    select work.id, 1, 'pixiv:tag:' || pixiv_illust_tag.tag
+    from work
+    join pixiv_illust_tag on work.source = 'pixiv' and work.post_id_int = pixiv_illust_tag.illust_id
+    union all
+    select work_id, 2, 'user_tag:' || tag_id
+    from work_user_tag
+)
+select work.id as work_id, sum(feature.weight) + (work.source is target_work.source) as score,
+    count(*) over () as total_items
+from feature
+join work on feature.work_id = work.id
+join target_work
+where feature.feature in (select feature from feature where work_id = target_work.id)
+and work.id != target_work.id
+and not (work.source is target_work.source and work.post_id is not null and work.post_id = target_work.post_id)
+and {condition}
+group by work.id
+order by score desc, work.added_date desc, work.id desc
+limit ? offset ?";
+
+#[derive(QueryableByName)]
+struct SimilarWork {
+    #[diesel(sql_type = Integer)]
+    work_id: i32,
+    #[diesel(sql_type = BigInt)]
+    total_items: i64,
+}
+
+/// Works of the library similar to the work by their artists and tags, for recommendations without remote calls.
+/// Works without any shared artist or tag are never included.
+pub fn similar_works(
+    conn: Database,
+    work_id: i32,
+    filter: &WorkFilter,
+    page: i64,
+    page_size: i64,
+) -> Result<GeneralResponse> {
+    use bottle_core::schema::{image, work};
+
+    let exists = diesel::select(diesel::dsl::exists(work::table.find(work_id))).get_result::<bool>(conn)?;
+    if !exists {
+        return Err(Error::ObjectNotFound(format!("Work {}", work_id)));
+    }
+
+    let rows = diesel::sql_query(SIMILAR_WORKS_QUERY.replace("{condition}", &filter.sql_condition()))
+        .bind::<Integer, _>(work_id)
+        .bind::<BigInt, _>(page_size)
+        .bind::<BigInt, _>(page * page_size)
+        .load::<SimilarWork>(conn)?;
+    let total_items = rows.first().map(|row| row.total_items).unwrap_or_default();
+    let work_ids = rows.into_iter().map(|row| row.work_id).collect::<Vec<_>>();
+
+    let mut works = work::table
+        .filter(work::id.eq_any(&work_ids))
+        .load::<model::Work>(conn)?;
+    works.sort_by_key(|work| work_ids.iter().position(|id| *id == work.id));
+    let images = image::table
+        .filter(image::work_id.eq_any(&work_ids))
+        .order_by(image::page_index.asc())
+        .load::<model::Image>(conn)?;
+
+    Ok(GeneralResponse {
+        works: Some(works.into_iter().map(WorkView::from).collect()),
+        images: Some(images.into_iter().map(ImageView::from).collect()),
+        total_items,
+        page,
+        page_size,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,265 @@
+use chrono::NaiveDate;
+use diesel::prelude::*;
+
+use bottle_core::{
+    feed::{FeedDailyPosts, FeedStatsView},
+    library::{ArtistStatsView, TagMonthlyWorks, TagStatsView},
+    Database, Error, Result,
+};
+
+use crate::model;
+
+// MARK: Statistics
+
+/// Queries aggregating the library and feeds into the statistics tables.
+/// Tags of each community are counted per month by when the works were added.
+const REFRESH_QUERIES: [&str; 12] = [
+    // Artists
+    "insert into artist_stat (community, artist, artist_name, work_count, image_count, last_added_date)
+    select 'twitter', cast(tweet.user_id as text), twitter_user.name, count(), sum(work.image_count), max(work.added_date)
+    from work
+    join tweet on work.source = 'twitter' and work.post_id_int = tweet.id
+    left join twitter_user on tweet.user_id = twitter_user.id
+    group by tweet.user_id",
+    "insert into artist_stat (community, artist, artist_name, work_count, image_count, last_added_date)
+    select 'pixiv', cast(pixiv_illust.user_id as text), pixiv_user.name, count(), sum(work.image_count), max(work.added_date)
+    from work
+    join pixiv_illust on work.source = 'pixiv' and work.post_id_int = pixiv_illust.id
+    left join pixiv_user on pixiv_illust.user_id = pixiv_user.id
+    group by pixiv_illust.user_id",
+    "insert into artist_stat (community, artist, artist_name, work_count, image_count, last_added_date)
+    select 'yandere', yandere_tag.name, yandere_tag.name, count(), sum(work.image_count), max(work.added_date)
+    from work
+    join yandere_post_tag on work.source = 'yandere' and work.post_id_int = yandere_post_tag.post_id
+    join yandere_tag on yandere_post_tag.tag_name = yandere_tag.name and yandere_tag.type = 'artist'
+    group by yandere_tag.name",
+    "insert into artist_stat (community, artist, artist_name, work_count, image_count, last_added_date)
+    select 'panda', panda_gallery_tag.name, panda_gallery_tag.name, count(), sum(work.image_count), max(work.added_date)
+    from work
+    join panda_gallery_tag on work.source = 'panda' and work.post_id_int = panda_gallery_tag.gallery_id
+    where panda_gallery_tag.namespace = 'artist'
+    group by panda_gallery_tag.name",
+    // Tags
+    "insert into tag_stat (community, tag, month, work_count)
+    select 'twitter', twitter_tag.tag, strftime('%Y-%m', work.added_date) as month, count(distinct work.id)
+    from work
+    join twitter_tag on work.source = 'twitter' and work.post_id_int = twitter_tag.tweet_id
+    group by twitter_tag.tag, month",
+    "insert into tag_stat (community, tag, month, work_count)
+    select 'pixiv', pixiv_illust_tag.tag, strftime('%Y-%m', work.added_date) as month, count(distinct work.id)
+    from work
+    join pixiv_illust_tag on work.source = 'pixiv' and work.post_id_int = pixiv_illust_tag.illust_id
+    group by pixiv_illust_tag.tag, month",
+    "insert into tag_stat (community, tag, month, work_count)
+    select 'yandere', yandere_post_tag.tag_name, strftime('%Y-%m', work.added_date) as month, count(distinct work.id)
+    from work
+    join yandere_post_tag on work.source = 'yandere' and work.post_id_int = yandere_post_tag.post_id
+    group by yandere_post_tag.tag_name, month",
+    "insert into tag_stat (community, tag, month, work_count)
+    select 'panda', panda_gallery_tag.namespace || ':' || panda_gallery_tag.name as tag,
+        strftime('%Y-%m', work.added_date) as month, count(distinct work.id)
+    from work
+    join panda_gallery_tag on work.source = 'panda' and work.post_id_int = panda_gallery_tag.gallery_id
+    group by tag, month",
+    // Feeds
+    "insert into feed_stat (community, feed_id, day, post_count)
+    select 'twitter', watch_list_id, date(updated_date) as day, sum(count)
+    from twitter_watch_list_history group by watch_list_id, day",
+    "insert into feed_stat (community, feed_id, day, post_count)
+    select 'pixiv', watch_list_id, date(updated_date) as day, sum(count)
+    from pixiv_watch_list_history group by watch_list_id, day",
+    "insert into feed_stat (community, feed_id, day, post_count)
+    select 'yandere', watch_list_id, date(updated_date) as day, sum(count)
+    from yandere_watch_list_history group by watch_list_id, day",
+    "insert into feed_stat (community, feed_id, day, post_count)
+    select 'panda', watch_list_id, date(updated_date) as day, sum(count)
+    from panda_watch_list_history group by watch_list_id, day",
+];
+
+/// Condition of artists without a feed of their posts, or a search feed of their tag.
+const UNWATCHED_ARTIST_CONDITION: &str = "
+not case artist_stat.community
+    when 'twitter' then exists (select 1 from twitter_watch_list
+        where kind = 'posts' and cast(user_id as text) = artist_stat.artist)
+    when 'pixiv' then exists (select 1 from pixiv_watch_list
+        where kind = 'posts' and cast(user_id as text) = artist_stat.artist)
+    when 'yandere' then exists (select 1 from yandere_watch_list
+        where kind = 'search' and instr(' ' || search_query || ' ', ' ' || artist_stat.artist || ' ') > 0)
+    when 'panda' then exists (select 1 from panda_watch_list
+        where kind = 'search' and instr(query, artist_stat.artist) > 0)
+    else 0
+end";
+
+/// Condition of tags without a search feed. Mentions and artist tags are left to the artist suggestions.
+const UNWATCHED_TAG_CONDITION: &str = "
+case tag_stat.community
+    when 'twitter' then tag_stat.tag not like '@%' and not exists (select 1 from twitter_watch_list
+        where kind = 'search' and instr(lower(' ' || search_query || ' '), lower(' #' || tag_stat.tag || ' ')) > 0)
+    when 'pixiv' then not exists (select 1 from pixiv_watch_list
+        where kind = 'search' and instr(' ' || search_query || ' ', ' ' || tag_stat.tag || ' ') > 0)
+    when 'yandere' then not exists (select 1 from yandere_tag where name = tag_stat.tag and type = 'artist')
+        and not exists (select 1 from yandere_watch_list
+        where kind = 'search' and instr(' ' || search_query || ' ', ' ' || tag_stat.tag || ' ') > 0)
+    when 'panda' then tag_stat.tag not like 'artist:%' and not exists (select 1 from panda_watch_list
+        where kind = 'search' and instr(query, substr(tag_stat.tag, instr(tag_stat.tag, ':') + 1)) > 0)
+    else 0
+end";
+
+#[derive(Debug)]
+pub struct Statistics;
+
+impl Statistics {
+    /// Rebuild the statistics tables from the library and feed histories.
+    pub fn refresh(conn: Database) -> Result<()> {
+        use bottle_core::schema::{artist_stat, feed_stat, tag_stat};
+        conn.transaction(|conn| {
+            diesel::delete(artist_stat::table).execute(conn)?;
+            diesel::delete(tag_stat::table).execute(conn)?;
+            diesel::delete(feed_stat::table).execute(conn)?;
+            for query in REFRESH_QUERIES {
+                diesel::sql_query(query).execute(conn)?;
+            }
+            Ok::<_, Error>(())
+        })?;
+        tracing::info!("Refreshed statistics");
+        Ok(())
+    }
+
+    /// Artists with the most archived works.
+    pub fn top_artists(conn: Database, community: Option<&str>, count: i64) -> Result<Vec<ArtistStatsView>> {
+        use bottle_core::schema::artist_stat;
+        let mut query = artist_stat::table.into_boxed();
+        if let Some(community) = community {
+            query = query.filter(artist_stat::community.eq(community));
+        }
+        let artists = query
+            .order_by((artist_stat::work_count.desc(), artist_stat::last_added_date.desc()))
+            .limit(count)
+            .load::<model::ArtistStat>(conn)?;
+        Ok(artists.into_iter().map(ArtistStatsView::from).collect())
+    }
+
+    /// Artists with the most archived works, at least `min_work_count`, but no feed watching them.
+    pub fn unwatched_artists(conn: Database, min_work_count: i32, count: i64) -> Result<Vec<ArtistStatsView>> {
+        use bottle_core::schema::artist_stat;
+        use diesel::{dsl::sql, sql_types::Bool};
+
+        let artists = artist_stat::table
+            .filter(artist_stat::work_count.ge(min_work_count))
+            .filter(sql::<Bool>(UNWATCHED_ARTIST_CONDITION))
+            .order_by((artist_stat::work_count.desc(), artist_stat::last_added_date.desc()))
+            .limit(count)
+            .load::<model::ArtistStat>(conn)?;
+        Ok(artists.into_iter().map(ArtistStatsView::from).collect())
+    }
+
+    /// Tags with the most archived works of all time, at least `min_work_count`, but no search feed of them.
+    /// Returns the community, the tag and the number of works.
+    pub fn unwatched_tags(conn: Database, min_work_count: i64, count: i64) -> Result<Vec<(String, String, i64)>> {
+        use bottle_core::schema::tag_stat;
+        use diesel::{
+            dsl::{sql, sum},
+            sql_types::Bool,
+        };
+
+        let tags = tag_stat::table
+            .filter(sql::<Bool>(UNWATCHED_TAG_CONDITION))
+            .group_by((tag_stat::community, tag_stat::tag))
+            .having(sum(tag_stat::work_count).ge(min_work_count))
+            .select((tag_stat::community, tag_stat::tag, sum(tag_stat::work_count)))
+            .order_by(sum(tag_stat::work_count).desc())
+            .limit(count)
+            .load::<(String, String, Option<i64>)>(conn)?;
+        Ok(tags
+            .into_iter()
+            .map(|(community, tag, total)| (community, tag, total.unwrap_or_default()))
+            .collect())
+    }
+
+    /// Tags with the most archived works since the month, with the number of works added each month.
+    pub fn top_tags(
+        conn: Database,
+        community: Option<&str>,
+        since: NaiveDate,
+        count: i64,
+    ) -> Result<Vec<TagStatsView>> {
+        use bottle_core::schema::tag_stat;
+        use diesel::dsl::sum;
+
+        let since = since.format("%Y-%m").to_string();
+        let mut query = tag_stat::table
+            .filter(tag_stat::month.ge(&since))
+            .group_by((tag_stat::community, tag_stat::tag))
+            .select((tag_stat::community, tag_stat::tag, sum(tag_stat::work_count)))
+            .into_boxed();
+        if let Some(community) = community {
+            query = query.filter(tag_stat::community.eq(community));
+        }
+        let tags = query
+            .order_by(sum(tag_stat::work_count).desc())
+            .limit(count)
+            .load::<(String, String, Option<i64>)>(conn)?;
+
+        let mut results = Vec::new();
+        for (community, tag, total) in tags {
+            let months = tag_stat::table
+                .filter(tag_stat::community.eq(&community))
+                .filter(tag_stat::tag.eq(&tag))
+                .filter(tag_stat::month.ge(&since))
+                .order_by(tag_stat::month.asc())
+                .select((tag_stat::month, tag_stat::work_count))
+                .load::<(String, i32)>(conn)?
+                .into_iter()
+                .map(|(month, work_count)| TagMonthlyWorks {
+                    month,
+                    work_count: work_count as i64,
+                })
+                .collect();
+            results.push(TagStatsView {
+                community,
+                tag,
+                work_count: total.unwrap_or_default(),
+                months,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Number of posts saved to each feed per day since the date, for feeds that saved any.
+    pub fn feed_trends(conn: Database, community: Option<&str>, since: NaiveDate) -> Result<Vec<FeedStatsView>> {
+        use bottle_core::schema::feed_stat;
+        let mut query = feed_stat::table.filter(feed_stat::day.ge(since)).into_boxed();
+        if let Some(community) = community {
+            query = query.filter(feed_stat::community.eq(community));
+        }
+        let rows = query
+            .order_by((
+                feed_stat::community.asc(),
+                feed_stat::feed_id.asc(),
+                feed_stat::day.asc(),
+            ))
+            .load::<model::FeedStat>(conn)?;
+
+        let mut results: Vec<FeedStatsView> = Vec::new();
+        for row in rows {
+            let day = FeedDailyPosts {
+                date: row.day,
+                post_count: row.post_count as i64,
+            };
+            match results.last_mut() {
+                Some(last) if last.community == row.community && last.feed_id == row.feed_id => {
+                    last.post_count += day.post_count;
+                    last.days.push(day);
+                }
+                _ => results.push(FeedStatsView {
+                    community: row.community,
+                    feed_id: row.feed_id,
+                    post_count: day.post_count,
+                    days: vec![day],
+                }),
+            }
+        }
+        results.sort_by_key(|feed| std::cmp::Reverse(feed.post_count));
+        Ok(results)
+    }
+}
@@ -0,0 +1,99 @@
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Integer, Text};
+
+use bottle_core::{Database, Error, Result};
+
+// MARK: User presence
+
+/// Presence of a user of a community across the feeds and the library.
+#[derive(Debug, Clone)]
+pub struct UserPresence {
+    /// Number of saved posts by the user.
+    pub saved_post_count: i64,
+    /// Number of archived posts by the user.
+    pub archived_post_count: i64,
+    /// Feeds including posts by the user, with the number of them, the most first.
+    pub feed_post_counts: Vec<(i32, i64)>,
+}
+
+#[derive(QueryableByName)]
+struct PostCounts {
+    #[diesel(sql_type = BigInt)]
+    saved_post_count: i64,
+    #[diesel(sql_type = BigInt)]
+    archived_post_count: i64,
+}
+
+#[derive(QueryableByName)]
+struct FeedPostCount {
+    #[diesel(sql_type = Integer)]
+    feed_id: i32,
+    #[diesel(sql_type = BigInt)]
+    post_count: i64,
+}
+
+/// Query of post IDs by the user binding the user ID, the table of feed posts and its post ID column.
+/// Users of communities without users are the artist tags, the same way as the artist statistics.
+fn user_post_source(community: &str) -> Result<(&'static str, &'static str, &'static str)> {
+    match community {
+        "twitter" => Ok((
+            "select id from tweet where user_id = cast(? as integer)",
+            "twitter_watch_list_tweet",
+            "tweet_id",
+        )),
+        "pixiv" => Ok((
+            "select id from pixiv_illust where user_id = cast(? as integer)",
+            "pixiv_watch_list_illust",
+            "illust_id",
+        )),
+        "yandere" => Ok((
+            "select yandere_post_tag.post_id from yandere_post_tag
+            join yandere_tag on yandere_post_tag.tag_name = yandere_tag.name and yandere_tag.type = 'artist'
+            where yandere_tag.name = ?",
+            "yandere_watch_list_post",
+            "post_id",
+        )),
+        "panda" => Ok((
+            "select gallery_id from panda_gallery_tag where namespace = 'artist' and name = ?",
+            "panda_watch_list_gallery",
+            "gallery_id",
+        )),
+        _ => Err(Error::InvalidEndpoint(format!("Community {}", community))),
+    }
+}
+
+impl UserPresence {
+    pub fn get(conn: Database, community: &str, user_id: &str) -> Result<Self> {
+        let (posts_query, feed_table, post_column) = user_post_source(community)?;
+
+        // 1. Count saved and archived posts
+        let counts = diesel::sql_query(format!(
+            "select (select count(*) from ({posts_query})) as saved_post_count,
+                (select count(distinct work.post_id_int) from work
+                where work.source = ? and work.post_id_int in ({posts_query})) as archived_post_count"
+        ))
+        .bind::<Text, _>(user_id)
+        .bind::<Text, _>(community)
+        .bind::<Text, _>(user_id)
+        .get_result::<PostCounts>(conn)?;
+
+        // 2. Count posts in each feed
+        let feed_post_counts = diesel::sql_query(format!(
+            "select watch_list_id as feed_id, count(distinct {post_column}) as post_count from {feed_table}
+            where {post_column} in ({posts_query})
+            group by watch_list_id
+            order by post_count desc, watch_list_id"
+        ))
+        .bind::<Text, _>(user_id)
+        .load::<FeedPostCount>(conn)?
+        .into_iter()
+        .map(|count| (count.feed_id, count.post_count))
+        .collect();
+
+        Ok(Self {
+            saved_post_count: counts.saved_post_count,
+            archived_post_count: counts.archived_post_count,
+            feed_post_counts,
+        })
+    }
+}
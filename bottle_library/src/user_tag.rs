@@ -0,0 +1,174 @@
+use diesel::prelude::*;
+use itertools::Itertools;
+
+use std::collections::HashMap;
+
+use bottle_core::{library::UserTagView, Database, Error, Result};
+
+use crate::model;
+
+// MARK: User tag
+
+#[derive(Debug)]
+pub struct UserTag;
+
+impl UserTag {
+    pub fn add(conn: Database, name: &str) -> Result<UserTagView> {
+        use bottle_core::schema::user_tag;
+
+        let name = Self::normalized_name(name)?;
+        let existing = user_tag::table
+            .filter(user_tag::name.eq(&name))
+            .first::<model::UserTag>(conn)
+            .optional()?;
+        if existing.is_some() {
+            return Err(Error::ObjectAlreadyExists(format!("User tag \"{}\"", name)));
+        }
+
+        let tag = diesel::insert_into(user_tag::table)
+            .values(user_tag::name.eq(&name))
+            .returning(model::UserTag::as_returning())
+            .get_result(conn)?;
+
+        tracing::info!("Added user tag {} \"{}\"", tag.id, name);
+        Ok(Self::views(conn, vec![tag])?.remove(0))
+    }
+
+    pub fn rename(conn: Database, tag_id: i32, name: &str) -> Result<UserTagView> {
+        use bottle_core::schema::user_tag;
+
+        let name = Self::normalized_name(name)?;
+        let existing = user_tag::table
+            .filter(user_tag::name.eq(&name))
+            .filter(user_tag::id.ne(tag_id))
+            .first::<model::UserTag>(conn)
+            .optional()?;
+        if existing.is_some() {
+            return Err(Error::ObjectAlreadyExists(format!("User tag \"{}\"", name)));
+        }
+
+        diesel::update(user_tag::table.find(tag_id))
+            .set(user_tag::name.eq(&name))
+            .execute(conn)?;
+        let tag = Self::get(conn, tag_id)?;
+        tracing::info!("Renamed user tag {} to \"{}\"", tag_id, name);
+        Ok(Self::views(conn, vec![tag])?.remove(0))
+    }
+
+    pub fn delete(conn: Database, tag_id: i32) -> Result<()> {
+        use bottle_core::schema::user_tag;
+        diesel::delete(user_tag::table.find(tag_id)).execute(conn)?;
+        tracing::info!("Deleted user tag {}", tag_id);
+        Ok(())
+    }
+
+    pub fn all(conn: Database) -> Result<Vec<UserTagView>> {
+        use bottle_core::schema::user_tag;
+        let tags = user_tag::table
+            .order_by(user_tag::name.asc())
+            .load::<model::UserTag>(conn)?;
+        Self::views(conn, tags)
+    }
+
+    /// User tags starting with the prefix, case-insensitively, the most used first.
+    pub fn autocomplete(conn: Database, prefix: &str, limit: usize) -> Result<Vec<UserTagView>> {
+        use bottle_core::schema::user_tag;
+
+        let escaped = prefix.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let tags = user_tag::table
+            .filter(user_tag::name.like(format!("{}%", escaped)).escape('\\'))
+            .load::<model::UserTag>(conn)?;
+        let mut views = Self::views(conn, tags)?;
+        views.sort_by(|a, b| b.work_count.cmp(&a.work_count).then_with(|| a.name.cmp(&b.name)));
+        views.truncate(limit);
+        Ok(views)
+    }
+
+    /// Get all the user tags of the work.
+    pub fn of_work(conn: Database, work_id: i32) -> Result<Vec<UserTagView>> {
+        use bottle_core::schema::{user_tag, work_user_tag};
+        let tags = user_tag::table
+            .inner_join(work_user_tag::table)
+            .filter(work_user_tag::work_id.eq(work_id))
+            .order_by(user_tag::name.asc())
+            .select(model::UserTag::as_select())
+            .load::<model::UserTag>(conn)?;
+        Self::views(conn, tags)
+    }
+
+    /// Assign the user tag to the works. Works which already have the tag are skipped.
+    pub fn add_works(conn: Database, tag_id: i32, work_ids: impl IntoIterator<Item = i32>) -> Result<()> {
+        use bottle_core::schema::work_user_tag;
+
+        Self::get(conn, tag_id)?;
+        let new_work_tags = work_ids
+            .into_iter()
+            .map(|work_id| model::NewWorkUserTag { work_id, tag_id })
+            .collect::<Vec<_>>();
+        diesel::insert_or_ignore_into(work_user_tag::table)
+            .values(&new_work_tags)
+            .execute(conn)?;
+
+        tracing::info!(
+            "Added user tag {} to works {}",
+            tag_id,
+            new_work_tags.iter().map(|t| t.work_id).join(", ")
+        );
+        Ok(())
+    }
+
+    /// Unassign the user tag from the works.
+    pub fn remove_works(conn: Database, tag_id: i32, work_ids: impl IntoIterator<Item = i32>) -> Result<()> {
+        use bottle_core::schema::work_user_tag;
+
+        let work_ids = work_ids.into_iter().collect::<Vec<_>>();
+        diesel::delete(work_user_tag::table)
+            .filter(work_user_tag::tag_id.eq(tag_id))
+            .filter(work_user_tag::work_id.eq_any(&work_ids))
+            .execute(conn)?;
+
+        tracing::info!("Removed user tag {} from works {}", tag_id, work_ids.iter().join(", "));
+        Ok(())
+    }
+
+    fn get(conn: Database, tag_id: i32) -> Result<model::UserTag> {
+        use bottle_core::schema::user_tag;
+        user_tag::table
+            .find(tag_id)
+            .first::<model::UserTag>(conn)
+            .optional()?
+            .ok_or(Error::ObjectNotFound(format!("User tag {}", tag_id)))
+    }
+
+    fn normalized_name(name: &str) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::InvalidEndpoint("User tag name is required".to_string()));
+        }
+        Ok(name.to_string())
+    }
+
+    /// Prepare the views of the tags with their work counts.
+    fn views(conn: Database, tags: Vec<model::UserTag>) -> Result<Vec<UserTagView>> {
+        use bottle_core::schema::work_user_tag;
+
+        let tag_ids = tags.iter().map(|tag| tag.id).collect::<Vec<_>>();
+        let counts = work_user_tag::table
+            .filter(work_user_tag::tag_id.eq_any(&tag_ids))
+            .group_by(work_user_tag::tag_id)
+            .select((work_user_tag::tag_id, diesel::dsl::count_star()))
+            .load::<(i32, i64)>(conn)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        Ok(tags
+            .into_iter()
+            .map(|tag| UserTagView {
+                id: tag.id,
+                work_count: counts.get(&tag.id).cloned().unwrap_or_default(),
+                name: tag.name,
+                added_date: tag.added_date.and_utc(),
+            })
+            .collect())
+    }
+}
@@ -1,4 +1,7 @@
-use bottle_core::library::*;
+use bottle_core::{
+    feed::{DigestFrequency, DigestView, FeedGroupView, QueueItemView, WebhookView},
+    library::*,
+};
 use bottle_download::LocalImage;
 
 use crate::model;
@@ -41,6 +44,18 @@ impl From<&LocalImage> for model::ImageUpdate {
             width: image.width.map(|v| v as i32),
             height: image.height.map(|v| v as i32),
             size: Some(image.size as i32),
+            checksum: image.checksum.clone(),
+            perceptual_hash: image.perceptual_hash.clone(),
+            is_animated: Some(image.animated),
+            md5: image.md5.clone(),
+            palette: image.palette.as_ref().map(|colors| {
+                colors
+                    .iter()
+                    .map(|[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+            palette_hues: image.palette.as_deref().map(|colors| HueSet::from_colors(colors).0),
         }
     }
 }
@@ -57,6 +72,7 @@ impl From<&RemoteWork> for model::NewWork {
             image_count: work.media_count,
             name: work.name.clone(),
             caption: work.caption.clone(),
+            content_rating: work.content_rating.map(|r| r.to_string()),
         }
     }
 }
@@ -74,11 +90,14 @@ impl From<model::Work> for WorkView {
             caption: work.caption,
             favorite: work.favorite,
             rating: work.rating,
+            content_rating: work.content_rating.and_then(|r| r.parse().ok()),
             thumbnail_path: work.thumbnail_path,
             small_thumbnail_path: work.small_thumbnail_path,
             added_date: work.added_date.and_utc(),
             modified_date: work.modified_date.and_utc(),
             viewed_date: work.viewed_date.map(|d| d.and_utc()),
+            last_read_page: work.last_read_page,
+            previous_work_id: work.previous_work_id,
         }
     }
 }
@@ -98,6 +117,12 @@ impl From<model::Image> for ImageView {
             width: image.width,
             height: image.height,
             size: image.size,
+            checksum: image.checksum,
+            perceptual_hash: image.perceptual_hash,
+            is_animated: image.is_animated,
+            palette: image
+                .palette
+                .map(|palette| palette.split(',').filter(|s| !s.is_empty()).map(String::from).collect()),
         }
     }
 }
@@ -112,6 +137,8 @@ impl From<model::Album> for AlbumView {
             position: album.position,
             added_date: album.added_date.and_utc(),
             modified_date: album.modified_date.and_utc(),
+            cover_path: album.cover_path,
+            filter: album.filter.and_then(|filter| serde_json::from_str(&filter).ok()),
         }
     }
 }
@@ -129,3 +156,129 @@ impl From<model::Folder> for FolderView {
         }
     }
 }
+
+/// Prepare a `NoteView` of a note.
+impl From<model::PostNote> for NoteView {
+    fn from(note: model::PostNote) -> NoteView {
+        NoteView {
+            id: note.id,
+            community: note.community,
+            post_id: note.post_id,
+            work_id: note.work_id,
+            content: note.content,
+            added_date: note.added_date.and_utc(),
+            modified_date: note.modified_date.and_utc(),
+        }
+    }
+}
+
+/// Prepare a `FeedGroupView` of a feed group.
+impl From<model::FeedGroup> for FeedGroupView {
+    fn from(group: model::FeedGroup) -> FeedGroupView {
+        FeedGroupView {
+            id: group.id,
+            name: group.name,
+            position: group.position,
+            added_date: group.added_date.and_utc(),
+            modified_date: group.modified_date.and_utc(),
+        }
+    }
+}
+
+/// Prepare a `QueueItemView` of an item in the reading queue.
+impl From<model::ReadingQueueItem> for QueueItemView {
+    fn from(item: model::ReadingQueueItem) -> QueueItemView {
+        QueueItemView {
+            id: item.id,
+            community: item.community,
+            post_id: item.post_id,
+            position: item.position,
+            added_date: item.added_date.and_utc(),
+        }
+    }
+}
+
+impl From<model::ArtistLink> for ArtistLinkView {
+    fn from(link: model::ArtistLink) -> ArtistLinkView {
+        ArtistLinkView {
+            id: link.id,
+            community: link.community,
+            user_id: link.user_id,
+            linked_community: link.linked_community,
+            linked_user_id: link.linked_user_id,
+            reason: link.reason,
+            status: link.status,
+            created_date: link.created_date.and_utc(),
+        }
+    }
+}
+
+/// Prepare a `RemoteStatusView` of the remote status of a work.
+impl From<model::RemoteStatus> for RemoteStatusView {
+    fn from(status: model::RemoteStatus) -> RemoteStatusView {
+        RemoteStatusView {
+            work_id: status.work_id,
+            status_code: status.status_code,
+            checked_date: status.checked_date.and_utc(),
+            vanished_date: status.vanished_date.map(|d| d.and_utc()),
+        }
+    }
+}
+
+/// Prepare an `ArtistStatsView` of an artist's statistics.
+impl From<model::ArtistStat> for ArtistStatsView {
+    fn from(stat: model::ArtistStat) -> ArtistStatsView {
+        ArtistStatsView {
+            community: stat.community,
+            artist: stat.artist,
+            artist_name: stat.artist_name,
+            work_count: stat.work_count as i64,
+            image_count: stat.image_count as i64,
+            last_added_date: stat.last_added_date.and_utc(),
+        }
+    }
+}
+
+/// Prepare a `WebhookView` of a webhook.
+impl From<model::Webhook> for WebhookView {
+    fn from(webhook: model::Webhook) -> WebhookView {
+        WebhookView {
+            id: webhook.id,
+            kind: webhook.kind.parse().unwrap_or_default(),
+            community: webhook.community,
+            feed_id: webhook.feed_id,
+            user_id: webhook.user_id,
+            url: webhook.url,
+            chat_id: webhook.chat_id,
+            bot_token: webhook.bot_token.as_deref().map(mask_secret),
+            template: webhook.template,
+            added_date: webhook.added_date.and_utc(),
+        }
+    }
+}
+
+/// Mask a secret for views, keeping only its last 4 characters to tell it apart.
+fn mask_secret(secret: &str) -> String {
+    let chars = secret.chars().collect::<Vec<_>>();
+    let visible = if chars.len() > 8 {
+        &chars[chars.len() - 4..]
+    } else {
+        &[][..]
+    };
+    format!("****{}", visible.iter().collect::<String>())
+}
+
+/// Prepare a `DigestView` of a digest.
+impl From<model::Digest> for DigestView {
+    fn from(digest: model::Digest) -> DigestView {
+        DigestView {
+            id: digest.id,
+            email: digest.email,
+            frequency: digest.frequency.parse().unwrap_or(DigestFrequency::Weekly),
+            last_sent_date: digest.last_sent_date.map(|d| d.and_utc()),
+            added_date: digest.added_date.and_utc(),
+            last_failed_date: digest.last_failed_date.map(|d| d.and_utc()),
+            failure_count: digest.failure_count,
+        }
+    }
+}
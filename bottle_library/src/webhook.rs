@@ -0,0 +1,101 @@
+use diesel::prelude::*;
+
+use std::collections::HashMap;
+
+use bottle_core::{feed::WebhookView, Database, Result};
+
+use crate::model;
+
+// MARK: Webhook
+
+#[derive(Debug)]
+pub struct Webhook;
+
+impl Webhook {
+    pub fn add(conn: Database, new_webhook: model::NewWebhook) -> Result<WebhookView> {
+        use bottle_core::schema::webhook;
+
+        let webhook = diesel::insert_into(webhook::table)
+            .values(new_webhook)
+            .returning(model::Webhook::as_returning())
+            .get_result(conn)?;
+
+        tracing::info!(
+            "Added {} webhook {} for {} {}@{}: {}",
+            webhook.kind,
+            webhook.id,
+            if webhook.user_id.is_some() { "artist" } else { "feed" },
+            webhook
+                .user_id
+                .clone()
+                .or(webhook.feed_id.map(|id| id.to_string()))
+                .unwrap_or_default(),
+            webhook.community,
+            webhook.url
+        );
+        Ok(webhook.into())
+    }
+
+    pub fn delete(conn: Database, webhook_id: i32) -> Result<()> {
+        use bottle_core::schema::webhook;
+        diesel::delete(webhook::table.find(webhook_id)).execute(conn)?;
+        tracing::info!("Deleted webhook {}", webhook_id);
+        Ok(())
+    }
+
+    /// Get all webhooks of the feed.
+    pub fn of_feed(conn: Database, community: &str, feed_id: i32) -> Result<Vec<WebhookView>> {
+        use bottle_core::schema::webhook;
+        let webhooks = webhook::table
+            .filter(webhook::community.eq(community))
+            .filter(webhook::feed_id.eq(feed_id))
+            .order_by(webhook::id.asc())
+            .load::<model::Webhook>(conn)?;
+        Ok(webhooks.into_iter().map(WebhookView::from).collect())
+    }
+
+    /// Get all webhooks of the artist.
+    pub fn of_user(conn: Database, community: &str, user_id: &str) -> Result<Vec<WebhookView>> {
+        use bottle_core::schema::webhook;
+        let webhooks = webhook::table
+            .filter(webhook::community.eq(community))
+            .filter(webhook::user_id.eq(user_id))
+            .order_by(webhook::id.asc())
+            .load::<model::Webhook>(conn)?;
+        Ok(webhooks.into_iter().map(WebhookView::from).collect())
+    }
+
+    /// Get all webhooks of any artist in the community.
+    pub fn of_users(conn: Database, community: &str) -> Result<Vec<WebhookView>> {
+        use bottle_core::schema::webhook;
+        let webhooks = webhook::table
+            .filter(webhook::community.eq(community))
+            .filter(webhook::user_id.is_not_null())
+            .order_by(webhook::id.asc())
+            .load::<model::Webhook>(conn)?;
+        Ok(webhooks.into_iter().map(WebhookView::from).collect())
+    }
+
+    /// Get the bot tokens of the Telegram webhooks by their IDs, which are never included in the views.
+    pub fn bot_tokens(conn: Database, webhook_ids: &[i32]) -> Result<HashMap<i32, String>> {
+        use bottle_core::schema::webhook;
+        let tokens = webhook::table
+            .filter(webhook::id.eq_any(webhook_ids))
+            .filter(webhook::bot_token.is_not_null())
+            .select((webhook::id, webhook::bot_token.assume_not_null()))
+            .load::<(i32, String)>(conn)?;
+        Ok(tokens.into_iter().collect())
+    }
+
+    /// Delete all webhooks of the feed, when the feed is deleted.
+    pub fn delete_of_feed(conn: Database, community: &str, feed_id: i32) -> Result<()> {
+        use bottle_core::schema::webhook;
+        diesel::delete(
+            webhook::table
+                .filter(webhook::community.eq(community))
+                .filter(webhook::feed_id.eq(feed_id)),
+        )
+        .execute(conn)?;
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use diesel::prelude::*;
 
 use bottle_core::{
     feed::GeneralResponse,
-    library::{ImageView, RemoteImage, RemoteWork, WorkView},
+    library::{ContentRating, ImageView, LibraryActivityView, RemoteImage, RemoteWork, WorkView},
     Database, Error, Result,
 };
 
@@ -16,7 +16,7 @@ pub fn add_remote_work(conn: Database, remote_work: &RemoteWork) -> Result<Gener
     use bottle_core::schema::{image, work};
     use itertools::Itertools;
 
-    // Check if the work already exists
+    // Check if the work already exists, or the post is a duplicate of another work
     if let Some(source) = &remote_work.source {
         let work = work::table
             .filter(work::source.eq(source))
@@ -27,6 +27,14 @@ pub fn add_remote_work(conn: Database, remote_work: &RemoteWork) -> Result<Gener
         if work.is_some() {
             return Err(Error::ObjectAlreadyExists(remote_work.to_string()));
         }
+        if let Some(post_id) = &remote_work.post_id {
+            if let Some(work_id) = get_duplicate_work(conn, source, post_id)? {
+                return Err(Error::ObjectAlreadyExists(format!(
+                    "{} as duplicate of work {}",
+                    remote_work, work_id
+                )));
+            }
+        }
     }
 
     let result = conn.transaction(|conn| -> Result<GeneralResponse> {
@@ -71,6 +79,26 @@ pub fn delete_work(conn: Database, work_id: i32) -> Result<()> {
     Ok(())
 }
 
+/// Get the works and their images by the work IDs.
+pub fn get_works(conn: Database, work_ids: &[i32]) -> Result<GeneralResponse> {
+    use bottle_core::schema::{image, work};
+
+    let works = work::table
+        .filter(work::id.eq_any(work_ids))
+        .order_by(work::id.asc())
+        .load::<model::Work>(conn)?;
+    let images = image::table
+        .filter(image::work_id.eq_any(work_ids))
+        .order_by(image::page_index.asc())
+        .load::<model::Image>(conn)?;
+
+    Ok(GeneralResponse {
+        works: Some(works.into_iter().map(WorkView::from).collect()),
+        images: Some(images.into_iter().map(ImageView::from).collect()),
+        ..Default::default()
+    })
+}
+
 /// Find the works and images in the database by the community name and post IDs.
 pub fn get_works_by_post_ids(
     conn: Database,
@@ -116,6 +144,47 @@ pub fn get_works_by_post_ids(
     Ok((works, images))
 }
 
+/// Group works added since the date by day and community, latest first.
+pub fn recent_activity(
+    conn: Database,
+    since: chrono::DateTime<chrono::Utc>,
+    cover_count: usize,
+) -> Result<Vec<LibraryActivityView>> {
+    use bottle_core::schema::work;
+
+    let works = work::table
+        .filter(work::added_date.ge(since.naive_utc()))
+        .order_by((work::added_date.desc(), work::id.desc()))
+        .load::<model::Work>(conn)?;
+
+    let mut activities: Vec<LibraryActivityView> = Vec::new();
+    for work in works {
+        let date = work.added_date.date();
+        let index = activities
+            .iter()
+            .position(|activity| activity.date == date && activity.community == work.source);
+        let activity = match index {
+            Some(index) => &mut activities[index],
+            None => {
+                activities.push(LibraryActivityView {
+                    date,
+                    community: work.source.clone(),
+                    work_count: 0,
+                    image_count: 0,
+                    covers: vec![],
+                });
+                activities.last_mut().unwrap()
+            }
+        };
+        activity.work_count += 1;
+        activity.image_count += work.image_count as i64;
+        if activity.covers.len() < cover_count {
+            activity.covers.push(WorkView::from(work));
+        }
+    }
+    Ok(activities)
+}
+
 // MARK: Image
 
 /// Add a remote image to the database, and return an image view for the client.
@@ -162,6 +231,82 @@ pub fn add_remote_image(conn: Database, remote_image: &RemoteImage, work_id: i32
     Ok(result)
 }
 
+/// Get the ID of the work which the image belongs to, if the work has no content rating yet.
+pub fn get_unrated_work_id(conn: Database, image_id: i32) -> Result<Option<i32>> {
+    use bottle_core::schema::{image, work};
+    let result = image::table
+        .inner_join(work::table)
+        .filter(image::id.eq(image_id))
+        .filter(work::content_rating.is_null())
+        .select(work::id)
+        .first::<i32>(conn)
+        .optional()?;
+    Ok(result)
+}
+
+/// Set the content rating of the work, unless it is already rated as more explicit.
+pub fn raise_content_rating(conn: Database, work_id: i32, content_rating: ContentRating) -> Result<()> {
+    use bottle_core::schema::work;
+    conn.transaction(|conn| -> Result<()> {
+        let current = work::table
+            .find(work_id)
+            .select(work::content_rating)
+            .first::<Option<String>>(conn)?
+            .and_then(|r| r.parse::<ContentRating>().ok());
+        if current.is_some_and(|current| current >= content_rating) {
+            return Ok(());
+        }
+        diesel::update(work::table.find(work_id))
+            .set(work::content_rating.eq(content_rating.as_str()))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Record that the work is viewed now, and the index of the image last read if given.
+pub fn mark_work_viewed(conn: Database, work_id: i32, page: Option<i32>) -> Result<WorkView> {
+    use bottle_core::schema::work;
+
+    let work = work::table
+        .find(work_id)
+        .first::<model::Work>(conn)
+        .optional()?
+        .ok_or(Error::ObjectNotFound(format!("Work {}", work_id)))?;
+    if let Some(page) = page {
+        if page < 0 || page >= work.image_count {
+            return Err(Error::InvalidEndpoint(format!("Page {} of work {}", page, work_id)));
+        }
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let work = diesel::update(work::table.find(work_id))
+        .set((
+            work::viewed_date.eq(now),
+            work::last_read_page.eq(page.or(work.last_read_page)),
+        ))
+        .returning(model::Work::as_returning())
+        .get_result(conn)?;
+    Ok(work.into())
+}
+
+/// Link the work as a newer version of the previous work.
+pub fn set_previous_work(conn: Database, work_id: i32, previous_work_id: i32) -> Result<WorkView> {
+    use bottle_core::schema::work;
+
+    let work = diesel::update(work::table.find(work_id))
+        .set(work::previous_work_id.eq(previous_work_id))
+        .returning(model::Work::as_returning())
+        .get_result(conn)
+        .optional()?
+        .ok_or(Error::ObjectNotFound(format!("Work {}", work_id)))?;
+    tracing::info!(
+        "Linked work {} as a newer version of work {}",
+        work_id,
+        previous_work_id
+    );
+    Ok(work.into())
+}
+
 /// Get the image in the database by the image ID.
 pub fn get_image(conn: Database, image_id: i32) -> Result<model::Image> {
     use bottle_core::schema::image;
@@ -175,3 +320,133 @@ pub fn get_images(conn: Database) -> Result<Vec<model::Image>> {
     let result = image::table.load::<model::Image>(conn)?;
     Ok(result)
 }
+
+/// Find the images in the library with the given SHA-256 checksum or MD5 digest, or with a perceptual hash
+/// within `max_distance` bits of the given one, along with their works.
+pub fn lookup_images(
+    conn: Database,
+    checksum: Option<&str>,
+    md5: Option<&str>,
+    perceptual_hash: Option<&str>,
+    max_distance: u32,
+) -> Result<GeneralResponse> {
+    use bottle_core::schema::{image, work};
+
+    // 1. Find images with the same content
+    let mut image_ids = match checksum {
+        Some(checksum) => image::table
+            .filter(image::checksum.eq(checksum))
+            .select(image::id)
+            .load::<i32>(conn)?,
+        None => vec![],
+    };
+    if let Some(md5) = md5 {
+        image_ids.extend(
+            image::table
+                .filter(image::md5.eq(md5))
+                .select(image::id)
+                .load::<i32>(conn)?,
+        );
+    }
+
+    // 2. Find visually similar images
+    if let Some(perceptual_hash) = perceptual_hash {
+        let mut query = image::table
+            .filter(image::perceptual_hash.is_not_null())
+            .select((image::id, image::perceptual_hash))
+            .into_boxed();
+        if let Some(condition) = hash_segment_condition(perceptual_hash, max_distance) {
+            query = query.filter(diesel::dsl::sql::<diesel::sql_types::Bool>(&condition));
+        }
+        let candidates = query.load::<(i32, Option<String>)>(conn)?;
+        image_ids.extend(candidates.into_iter().filter_map(|(id, hash)| {
+            let distance = bottle_download::hash_distance(perceptual_hash, hash.as_deref()?)?;
+            (distance <= max_distance).then_some(id)
+        }));
+    }
+
+    // 3. Get the images and works
+    let images = image::table
+        .filter(image::id.eq_any(&image_ids))
+        .order_by(image::id.asc())
+        .load::<model::Image>(conn)?;
+    let work_ids = images.iter().map(|image| image.work_id);
+    let works = work::table
+        .filter(work::id.eq_any(work_ids))
+        .order_by(work::id.asc())
+        .load::<model::Work>(conn)?;
+
+    Ok(GeneralResponse {
+        works: Some(works.into_iter().map(WorkView::from).collect()),
+        images: Some(images.into_iter().map(ImageView::from).collect()),
+        ..Default::default()
+    })
+}
+
+/// SQL condition preselecting the images whose perceptual hash may be within `max_distance` bits of the given one.
+/// The 16 hex digits of the hash are split into `max_distance + 1` segments. Differing in at most `max_distance`
+/// bits, a similar hash must equal the given one in at least one segment, so the Hamming distance only has to be
+/// computed for the images matching any segment.
+/// None if the hash is invalid or the distance is too large to split, in which case all hashes are compared.
+fn hash_segment_condition(perceptual_hash: &str, max_distance: u32) -> Option<String> {
+    const HASH_DIGITS: usize = 16;
+    let segment_count = max_distance as usize + 1;
+    if perceptual_hash.len() != HASH_DIGITS
+        || !perceptual_hash.chars().all(|c| c.is_ascii_hexdigit())
+        || segment_count > HASH_DIGITS
+    {
+        return None;
+    }
+
+    let hash = perceptual_hash.to_lowercase();
+    let conditions = (0..segment_count).map(|i| {
+        let start = i * HASH_DIGITS / segment_count;
+        let end = (i + 1) * HASH_DIGITS / segment_count;
+        format!(
+            "substr(perceptual_hash, {}, {}) = '{}'",
+            start + 1,
+            end - start,
+            &hash[start..end]
+        )
+    });
+    Some(format!("({})", conditions.collect::<Vec<_>>().join(" OR ")))
+}
+
+// MARK: Duplicate post
+
+/// Link the post to a work from another community with an image of the MD5 digest, if any,
+/// so that the post is not archived as a second copy of the same file. Returns the ID of the linked work.
+pub fn link_duplicate_post(conn: Database, community: &str, post_id: &str, md5: &str) -> Result<Option<i32>> {
+    use bottle_core::schema::{duplicate_post, image, work};
+
+    let work_id = image::table
+        .inner_join(work::table)
+        .filter(image::md5.eq(md5))
+        .filter(work::source.is_null().or(work::source.ne(community)))
+        .order(work::id.asc())
+        .select(work::id)
+        .first::<i32>(conn)
+        .optional()?;
+    if let Some(work_id) = work_id {
+        diesel::insert_or_ignore_into(duplicate_post::table)
+            .values((
+                duplicate_post::community.eq(community),
+                duplicate_post::post_id.eq(post_id),
+                duplicate_post::work_id.eq(work_id),
+            ))
+            .execute(conn)?;
+        tracing::info!("Linked {} post {} to duplicate work {}", community, post_id, work_id);
+    }
+    Ok(work_id)
+}
+
+/// Get the work which the post is linked to as a duplicate.
+pub fn get_duplicate_work(conn: Database, community: &str, post_id: &str) -> Result<Option<i32>> {
+    use bottle_core::schema::duplicate_post;
+    let work_id = duplicate_post::table
+        .find((community, post_id))
+        .select(duplicate_post::work_id)
+        .first::<i32>(conn)
+        .optional()?;
+    Ok(work_id)
+}
@@ -1,10 +1,9 @@
-use std::collections::hash_map::Entry;
 use std::collections::HashSet;
 
 use diesel::prelude::*;
 
 use bottle_core::{feed::*, Error, Result};
-use panda_client::{GalleryListOffset, PandaClient, TagNamespace};
+use panda_client::{FavoriteSearchOption, GalleryListOffset, PandaClient, SearchOption, TagNamespace};
 
 use crate::cache::PandaCache;
 use crate::community::PandaAccount;
@@ -21,20 +20,27 @@ fn from_request(db: Database, request: &EndpointRequest<PandaFeedParams>) -> Res
         id: -1, // Temporary feed
         name: None,
         first_fetch_limit: None,
+        icon_url: None,
+        color: None,
+        cover_path: None,
+        last_error: None,
+        failure_count: 0,
+        mirror_album_id: None,
+        backfill_offset: None,
         watching: false,
         account_id: account.id,
         params: request.params.clone(),
         reached_end: false,
+        favorite_category_name: None,
     })
 }
 
-fn default_client(db: Database) -> Result<PandaClient> {
+fn default_client(db: Database, cache: &mut PandaCache) -> Result<PandaClient> {
     let account = PandaAccount::default(db)?;
     let auth = account
         .auth(db)?
         .ok_or(Error::NotLoggedIn("Invalid account".to_string()))?;
-    let client = PandaClient::new(auth).map_err(anyhow::Error::from)?;
-    Ok(client)
+    cache.client(account.id, &auth)
 }
 
 /// Fetch posts from temporary feed.
@@ -48,13 +54,15 @@ pub async fn fetch_posts<'a>(
     let feed = from_request(db, request)?;
 
     // 1. Fetch results
-    let auth = feed.get_account(db)?.auth(db)?;
+    let account = feed.get_account(db)?;
+    let auth = account.auth(db)?;
     let mut ctx = PandaFetchContext {
         offset: request
             .offset
             .as_ref()
             .map(|offset| GalleryListOffset::OlderThan(offset.clone())),
         direction: Direction::Backward,
+        client: auth.as_ref().map(|auth| cache.client(account.id, auth)).transpose()?,
     };
     let result = feed.fetch(&mut ctx, auth.as_ref()).await?;
 
@@ -103,6 +111,53 @@ pub async fn fetch_posts<'a>(
     })
 }
 
+/// Fetch posts from the normalized endpoint, using the feed params of panda,
+/// or the gallery preview page at the offset for the post detail.
+pub async fn fetch_endpoint<'a>(
+    db: Database<'a>,
+    cache: &'a mut PandaCache,
+    request: &EndpointRequest<Endpoint>,
+) -> Result<EndpointResponse> {
+    if let Endpoint::PostDetail { post_id } = &request.params {
+        let gid = Endpoint::parse_id(post_id, "Gallery ID")?;
+        let page = match &request.offset {
+            Some(offset) => Endpoint::parse_id(offset, "Page")?,
+            None => 0,
+        };
+        return fetch_media_page(db, cache, gid, page).await;
+    }
+    let request = EndpointRequest {
+        params: endpoint_params(&request.params)?,
+        offset: request.offset.clone(),
+    };
+    fetch_posts(db, cache, &request).await
+}
+
+/// Feed params browsed by the normalized endpoint, so that the browsing can be promoted to a feed.
+/// The post detail is not a feed.
+pub fn endpoint_params(endpoint: &Endpoint) -> Result<PandaFeedParams> {
+    let search = |keyword: String| PandaFeedParams::Search {
+        option: SearchOption {
+            keyword: Some(keyword),
+            ..Default::default()
+        },
+        languages: vec![],
+    };
+    let params = match endpoint {
+        Endpoint::UserPosts { user_id } => search(format!("uploader:\"{}\"", user_id.trim())),
+        Endpoint::Likes { user_id: None } => PandaFeedParams::Favorites {
+            option: FavoriteSearchOption::default(),
+            languages: vec![],
+        },
+        Endpoint::Likes { user_id: Some(_) } => {
+            return Err(Error::InvalidEndpoint("Panda favorites of other users".to_string()))
+        }
+        Endpoint::Search { query } => search(query.clone()),
+        Endpoint::PostDetail { .. } => return Err(Error::InvalidEndpoint("Feed of a single post".to_string())),
+    };
+    Ok(params)
+}
+
 /// Fetch a gallery preview page, and store the result in cache.
 pub async fn fetch_media_page<'a>(
     db: Database<'a>,
@@ -111,8 +166,8 @@ pub async fn fetch_media_page<'a>(
     page: u32,
 ) -> Result<EndpointResponse> {
     // 1. Fetch gallery token from cache or database
-    let token = if cache.galleries.contains_key(&gid) {
-        cache.galleries[&gid].token.clone()
+    let token = if let Some(gallery) = cache.galleries.get(&gid) {
+        gallery.token.clone()
     } else {
         use bottle_core::schema::panda_gallery;
         panda_gallery::table
@@ -124,31 +179,21 @@ pub async fn fetch_media_page<'a>(
     };
 
     // 2. Fetch gallery page
-    let client = default_client(db)?;
+    let client = default_client(db, cache)?;
     let result = client.gallery(gid, &token, page).await.map_err(anyhow::Error::from)?;
 
     // 3. Store gallery detail to cache
-    if let Entry::Vacant(e) = cache.galleries.entry(gid) {
-        e.insert(result.gallery.clone());
+    if !cache.galleries.contains_key(&gid) {
+        cache.galleries.insert(gid, result.gallery.clone());
         tracing::info!("Stored panda gallery {} to cache", gid);
     }
-    if let Entry::Vacant(e) = cache.gallery_details.entry(gid) {
-        e.insert(result.detail.clone());
+    if !cache.gallery_details.contains_key(&gid) {
+        cache.gallery_details.insert(gid, result.detail.clone());
         tracing::info!("Stored panda gallery detail {} to cache", gid);
     }
-    if let Entry::Vacant(e) = cache.image_previews.entry(gid) {
-        e.insert(result.previews.clone());
-        tracing::info!(
-            "Stored {} new panda gallery {} previews to cache",
-            result.previews.len(),
-            gid
-        );
-    } else {
+    if let Some(previews) = cache.image_previews.get_mut(&gid) {
         // Only store new previews
-        let existing_indices = cache.image_previews[&gid]
-            .iter()
-            .map(|p| p.index)
-            .collect::<HashSet<_>>();
+        let existing_indices = previews.iter().map(|p| p.index).collect::<HashSet<_>>();
         let new_previews = result
             .previews
             .iter()
@@ -156,8 +201,15 @@ pub async fn fetch_media_page<'a>(
             .cloned()
             .collect::<Vec<_>>();
         let inserted_count = new_previews.len();
-        cache.image_previews.get_mut(&gid).unwrap().extend(new_previews);
+        previews.extend(new_previews);
         tracing::info!("Stored {} new panda gallery {} previews to cache", inserted_count, gid);
+    } else {
+        cache.image_previews.insert(gid, result.previews.clone());
+        tracing::info!(
+            "Stored {} new panda gallery {} previews to cache",
+            result.previews.len(),
+            gid
+        );
     }
 
     // 4. Prepare post and media
@@ -167,15 +219,7 @@ pub async fn fetch_media_page<'a>(
     let media = result
         .previews
         .iter()
-        .map(|preview| MediaView {
-            media_id: format!("{}-{}", gid, preview.index),
-            community: "panda".to_string(),
-            post_id: gid.to_string(),
-            page_index: preview.index as i32,
-            thumbnail_url: Some(preview.thumbnail_url.clone()),
-            extra: Some(serde_json::json!({"panda": { "token": preview.token }})),
-            ..Default::default()
-        })
+        .map(|preview| model::PandaMedia::from_preview(gid as i64, preview).into())
         .collect();
 
     Ok(EndpointResponse {
@@ -195,8 +239,7 @@ pub async fn fetch_media<'a>(
 ) -> Result<EndpointResponse> {
     // 1. Fetch media token from cache and database
     let token = (|| -> Result<String> {
-        if cache.image_previews.contains_key(&gid) {
-            let previews = cache.image_previews.get(&gid).unwrap();
+        if let Some(previews) = cache.image_previews.get(&gid) {
             let preview = previews.iter().find(|p| p.index == page);
             if let Some(preview) = preview {
                 return Ok(preview.token.clone());
@@ -216,20 +259,19 @@ pub async fn fetch_media<'a>(
     })()?;
 
     // 2. Fetch page
-    let client = default_client(db)?;
+    let client = default_client(db, cache)?;
     let result = client.image(gid, &token, page).await.map_err(anyhow::Error::from)?;
 
     // 3. Store image to cache
-    if let Entry::Vacant(e) = cache.images.entry(gid) {
-        e.insert(vec![result.clone()]);
-        tracing::info!("Stored panda media {}-{} to cache", gid, page);
-    } else {
+    if let Some(images) = cache.images.get_mut(&gid) {
         // Only store new images
-        let existing_indices = cache.images[&gid].iter().map(|i| i.index).collect::<HashSet<_>>();
-        if !existing_indices.contains(&result.index) {
-            cache.images.get_mut(&gid).unwrap().push(result.clone());
+        if !images.iter().any(|i| i.index == result.index) {
+            images.push(result.clone());
             tracing::info!("Stored panda media {}-{} to cache", gid, page);
         }
+    } else {
+        cache.images.insert(gid, vec![result.clone()]);
+        tracing::info!("Stored panda media {}-{} to cache", gid, page);
     }
 
     // 4. Prepare media object
@@ -1,29 +1,73 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use diesel::prelude::*;
 
-use bottle_core::{Database, Result};
-use panda_client::{Gallery, GalleryDetail, ImagePreview, ImageResult};
+use bottle_core::{
+    cache::{BoundedCache, CacheStats, ClientCache, RequestRecorder},
+    Database, Result,
+};
+use panda_client::{Gallery, GalleryDetail, ImagePreview, ImageResult, PandaClient, PandaCookie};
 
 use crate::model;
 
 #[derive(Debug, Clone, Default)]
 pub struct PandaCache {
-    pub(crate) galleries: HashMap<u64, Gallery>,
-    pub(crate) gallery_details: HashMap<u64, GalleryDetail>,
-    pub(crate) image_previews: HashMap<u64, Vec<ImagePreview>>,
-    pub(crate) images: HashMap<u64, Vec<ImageResult>>,
+    pub(crate) galleries: BoundedCache<u64, Gallery>,
+    pub(crate) gallery_details: BoundedCache<u64, GalleryDetail>,
+    pub(crate) image_previews: BoundedCache<u64, Vec<ImagePreview>>,
+    pub(crate) images: BoundedCache<u64, Vec<ImageResult>>,
+    pub(crate) clients: ClientCache<PandaClient, PandaCookie>,
 }
 
 impl PandaCache {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `capacity` entities of each kind, each for `ttl` at most.
+    pub fn with_limits(capacity: usize, ttl: Duration) -> Self {
         Self {
-            galleries: HashMap::new(),
-            gallery_details: HashMap::new(),
-            image_previews: HashMap::new(),
-            images: HashMap::new(),
+            galleries: BoundedCache::new(capacity, ttl),
+            gallery_details: BoundedCache::new(capacity, ttl),
+            image_previews: BoundedCache::new(capacity, ttl),
+            images: BoundedCache::new(capacity, ttl),
+            clients: ClientCache::new(),
         }
     }
+
+    pub fn stats(&self) -> Vec<CacheStats> {
+        vec![
+            self.galleries.stats("galleries"),
+            self.gallery_details.stats("gallery_details"),
+            self.image_previews.stats("image_previews"),
+            self.images.stats("images"),
+        ]
+    }
+
+    /// Clear the cached entities, keeping the clients of accounts.
+    pub fn clear(&mut self) {
+        self.galleries.clear();
+        self.gallery_details.clear();
+        self.image_previews.clear();
+        self.images.clear();
+    }
+
+    /// Get the client of the account, shared by feed updates and download jobs.
+    pub fn client(&mut self, account_id: i32, cookie: &PandaCookie) -> Result<PandaClient> {
+        self.clients.get_or_create(account_id, cookie, |cookie, hook| {
+            let client = PandaClient::new(cookie.clone()).map_err(anyhow::Error::from)?;
+            Ok(match hook {
+                Some(hook) => client.with_response_hook(hook),
+                None => client,
+            })
+        })
+    }
+
+    /// Count the requests made by the clients of accounts.
+    pub fn set_request_recorder(&mut self, recorder: RequestRecorder) {
+        self.clients.set_recorder(recorder);
+    }
 }
 
 pub(crate) fn get_gallery(db: Database, cache: &PandaCache, post_id: i64) -> Result<Option<model::PandaGallery>> {
@@ -85,13 +129,7 @@ pub(crate) fn get_media(db: Database, cache: &PandaCache, post_id: i64) -> Resul
         let mut new_media_list = Vec::new();
         for preview in previews.iter() {
             media_dict.entry(preview.index).or_insert_with(|| {
-                let new_media = model::PandaMedia {
-                    gallery_id: post_id,
-                    media_index: preview.index as i32,
-                    token: preview.token.clone(),
-                    thumbnail_url: Some(preview.thumbnail_url.clone()),
-                    ..Default::default()
-                };
+                let new_media = model::PandaMedia::from_preview(post_id, preview);
                 new_media_list.push(new_media.clone());
                 new_media
             });
@@ -9,11 +9,11 @@ use bottle_core::{
     library::{RemoteImage, RemoteWork},
     Result,
 };
-use panda_client::PandaCookie;
+use panda_client::{FavoriteCategory, PandaCookie};
 
 use crate::cache::{self, PandaCache};
 use crate::feed::PandaFeed;
-use crate::model;
+use crate::{model, util};
 
 pub struct PandaCommunity;
 
@@ -40,6 +40,8 @@ pub struct PandaAccount {
     pub id: i32,
     pub name: Option<String>,
     pub username: Option<String>,
+    /// Favorite categories synced from the favorites page.
+    pub favorite_categories: Vec<model::PandaFavoriteCategory>,
 }
 
 #[async_trait]
@@ -54,9 +56,19 @@ impl Account for PandaAccount {
     {
         Some(AccountMetadata {
             credential_scheme: Scheme::Object(HashMap::from([
-                ("ipb_member_id".to_string(), Scheme::String),
-                ("ipb_pass_hash".to_string(), Scheme::String),
-                ("igneous".to_string(), Scheme::String),
+                (
+                    "ipb_member_id".to_string(),
+                    Scheme::field("Cookie ipb_member_id", Scheme::String),
+                ),
+                (
+                    "ipb_pass_hash".to_string(),
+                    Scheme::field("Cookie ipb_pass_hash", Scheme::String),
+                ),
+                ("igneous".to_string(), Scheme::field("Cookie igneous", Scheme::String)),
+                (
+                    "sk".to_string(),
+                    Scheme::field("Cookie sk", Scheme::Optional(Box::new(Scheme::String))),
+                ),
             ])),
             can_fetch_info: false,
             need_refresh: false,
@@ -67,6 +79,16 @@ impl Account for PandaAccount {
         AccountView {
             account_id: self.id,
             community: "panda".to_string(),
+            favorite_categories: Some(
+                self.favorite_categories
+                    .iter()
+                    .map(|c| FavoriteCategoryView {
+                        index: c.category_index,
+                        name: c.name.clone(),
+                        gallery_count: c.gallery_count,
+                    })
+                    .collect(),
+            ),
         }
     }
 
@@ -87,12 +109,11 @@ impl Account for PandaAccount {
         Self: Sized,
     {
         use bottle_core::schema::panda_account::dsl::*;
-        let results = panda_account
+        panda_account
             .load::<model::PandaAccount>(db)?
             .into_iter()
-            .map(Self::from)
-            .collect();
-        Ok(results)
+            .map(|account| Self::from(account).with_favorite_categories(db))
+            .collect()
     }
 
     fn get(db: Database, account_id: i32) -> Result<Option<Self>>
@@ -104,7 +125,9 @@ impl Account for PandaAccount {
             .filter(id.eq(account_id))
             .first::<model::PandaAccount>(db)
             .optional()?;
-        Ok(result.map(Self::from))
+        result
+            .map(|account| Self::from(account).with_favorite_categories(db))
+            .transpose()
     }
 
     fn delete(db: Database, account_id: i32) -> Result<()>
@@ -122,6 +145,11 @@ impl Account for PandaAccount {
         Self: Sized,
     {
         use bottle_core::schema::panda_account::dsl::*;
+        if credential.igneous.is_none() {
+            return Err(bottle_core::Error::InvalidEndpoint(
+                "Panda cookies must contain igneous".to_string(),
+            ));
+        }
         let new_account = model::NewPandaAccount {
             cookies: credential.to_string(),
             ..Default::default()
@@ -150,10 +178,27 @@ impl Account for PandaAccount {
             .filter(id.eq(self.id))
             .select(cookies)
             .first::<String>(db)?;
-        let result = cookie_str.parse::<PandaCookie>().map_err(anyhow::Error::from)?;
+        let result = PandaCookie::from_stored(&cookie_str).map_err(anyhow::Error::from)?;
         Ok(result)
     }
 
+    fn parse_credential(value: &serde_json::Value, path: &str) -> Result<Self::Credential> {
+        let scheme = Self::metadata().unwrap().credential_scheme;
+        let [ipb_member_id, ipb_pass_hash, igneous] =
+            credential_fields(&scheme, value, path, ["ipb_member_id", "ipb_pass_hash", "igneous"])?;
+        let sk = value["sk"]
+            .as_str()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        Ok(PandaCookie {
+            ipb_member_id,
+            ipb_pass_hash,
+            igneous: Some(igneous),
+            sk,
+            others: Vec::new(),
+        })
+    }
+
     async fn fetch(_credential: &Self::Credential) -> Result<Self::InfoResponse> {
         unimplemented!()
     }
@@ -163,7 +208,58 @@ impl PandaAccount {
     pub fn default(db: Database) -> Result<Self> {
         use bottle_core::schema::panda_account::dsl::*;
         let result = panda_account.first::<model::PandaAccount>(db)?;
-        Ok(Self::from(result))
+        Self::from(result).with_favorite_categories(db)
+    }
+
+    /// Replace the favorite categories of the account with the ones on the favorites page.
+    pub fn save_favorite_categories(db: Database, account_id: i32, categories: &[FavoriteCategory]) -> Result<()> {
+        use bottle_core::schema::panda_favorite_category;
+        let now = chrono::Utc::now().naive_utc();
+        let categories = categories
+            .iter()
+            .map(|c| model::PandaFavoriteCategory {
+                account_id,
+                category_index: c.index as i32,
+                name: c.name.clone(),
+                gallery_count: c.gallery_count as i32,
+                updated_date: now,
+            })
+            .collect::<Vec<_>>();
+        db.transaction(|conn| -> Result<()> {
+            diesel::delete(panda_favorite_category::table.filter(panda_favorite_category::account_id.eq(account_id)))
+                .execute(conn)?;
+            diesel::insert_into(panda_favorite_category::table)
+                .values(&categories)
+                .execute(conn)?;
+            Ok(())
+        })?;
+        tracing::info!(
+            "Saved {} favorite categories of panda account {}",
+            categories.len(),
+            account_id
+        );
+        Ok(())
+    }
+
+    /// Name of the favorite category with the index, if it has been synced.
+    pub fn favorite_category_name(db: Database, account_id: i32, index: u32) -> Result<Option<String>> {
+        use bottle_core::schema::panda_favorite_category;
+        let result = panda_favorite_category::table
+            .filter(panda_favorite_category::account_id.eq(account_id))
+            .filter(panda_favorite_category::category_index.eq(index as i32))
+            .select(panda_favorite_category::name)
+            .first::<String>(db)
+            .optional()?;
+        Ok(result)
+    }
+
+    fn with_favorite_categories(mut self, db: Database) -> Result<Self> {
+        use bottle_core::schema::panda_favorite_category;
+        self.favorite_categories = panda_favorite_category::table
+            .filter(panda_favorite_category::account_id.eq(self.id))
+            .order(panda_favorite_category::category_index.asc())
+            .load::<model::PandaFavoriteCategory>(db)?;
+        Ok(self)
     }
 }
 
@@ -207,6 +303,7 @@ impl Post for PandaPost {
             media_count: self.gallery.media_count,
             images,
             name: Some(self.gallery.title.clone()),
+            content_rating: Some(self.gallery.content_rating()),
             ..Default::default()
         };
         bottle_library::add_remote_work(db, &work)
@@ -222,6 +319,57 @@ pub(crate) struct PandaGalleryExtra {
     pub english_title: Option<String>,
     pub parent: Option<String>,
     pub visible: Option<bool>,
+    pub invisible_reason: Option<String>,
+    pub newer_gallery_id: Option<i64>,
     pub language: Option<String>,
-    pub file_size: Option<i32>,
+    pub file_size: Option<i64>,
+    pub expunged: bool,
+    pub disowned: bool,
+}
+
+pub fn get_entities(db: Database, post_ids: impl IntoIterator<Item = i64>) -> Result<GeneralResponse> {
+    use bottle_core::schema::{panda_gallery, panda_media};
+
+    // 1. Fetch posts
+    let galleries = panda_gallery::table
+        .filter(panda_gallery::id.eq_any(post_ids))
+        .select(panda_gallery::all_columns)
+        .load::<model::PandaGallery>(db)?;
+
+    // 2. Fetch associated artists
+    let post_ids = galleries.iter().map(|gallery| gallery.id);
+    let users = util::get_artist_views(db, post_ids.clone())?;
+
+    // 3. Fetch associated media
+    let media = panda_media::table
+        .filter(panda_media::gallery_id.eq_any(post_ids.clone()))
+        .order(panda_media::media_index.asc())
+        .load::<model::PandaMedia>(db)?;
+
+    // 4. Fetch associated tags
+    let tags = util::get_tag_map(db, post_ids)?;
+    let posts = galleries
+        .into_iter()
+        .map(|gallery| gallery.post_view(tags.get(&gallery.id).cloned().unwrap_or_default()))
+        .collect();
+
+    Ok(GeneralResponse {
+        posts: Some(posts),
+        users: Some(users),
+        media: Some(media.into_iter().map(MediaView::from).collect()),
+        ..Default::default()
+    })
+}
+
+/// IDs of the saved posts greater than `after_id` in ascending order, to go through all posts in batches.
+pub fn get_post_ids(db: Database, after_id: i64, count: i64) -> Result<Vec<i64>> {
+    use bottle_core::schema::panda_gallery;
+
+    let post_ids = panda_gallery::table
+        .filter(panda_gallery::id.gt(after_id))
+        .order_by(panda_gallery::id.asc())
+        .select(panda_gallery::id)
+        .limit(count)
+        .load::<i64>(db)?;
+    Ok(post_ids)
 }
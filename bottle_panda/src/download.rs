@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
-use bottle_core::{Database, Error, Result};
+use bottle_core::{library::RemoteWork, Database, Error, Result};
 use panda_client::{Gallery, GalleryDetail, GalleryPageResult, ImageResult};
 
 use crate::model;
@@ -17,8 +18,17 @@ pub struct PandaDownloadTask {
     pub title: String,
     pub has_detail: bool,
     pub media_count: i32,
+    /// Total file size of the gallery in bytes, if known.
+    pub file_size: Option<i64>,
     pub work_id: i32,
     pub image_tasks: Vec<PandaImageTask>,
+    /// Indices of media to download, or all media if not given.
+    pub indices: Option<HashSet<i32>>,
+    /// Whether to download the media still backing off after failures, e.g. when retried by the user.
+    pub ignore_backoff: bool,
+    /// Whether to download the media in index order, so that they can be read before the download finishes.
+    /// Otherwise they are downloaded in the order of completion.
+    pub ordered: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +40,60 @@ pub struct PandaImageTask {
     pub downloaded: bool,
 }
 
+impl PandaDownloadTask {
+    /// Download only the media at the indices, e.g. a chapter of a compilation gallery.
+    pub fn with_indices(mut self, indices: impl IntoIterator<Item = i32>) -> Result<Self> {
+        let indices = indices.into_iter().collect::<HashSet<_>>();
+        if let Some(index) = indices.iter().find(|&&index| index < 0 || index >= self.media_count) {
+            return Err(Error::InvalidEndpoint(format!(
+                "Media {} of panda gallery {} with {} media",
+                index, self.gid, self.media_count
+            )));
+        }
+        self.indices = Some(indices);
+        Ok(self)
+    }
+
+    /// Download the media regardless of their backoff after failures.
+    pub fn ignoring_backoff(mut self) -> Self {
+        self.ignore_backoff = true;
+        self
+    }
+
+    /// Download the media in index order, with a window of concurrent downloads.
+    pub fn with_ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    pub fn is_selected(&self, index: i32) -> bool {
+        self.indices.as_ref().is_none_or(|indices| indices.contains(&index))
+    }
+
+    /// Number of media to download.
+    pub fn selected_count(&self) -> i32 {
+        match &self.indices {
+            Some(indices) => indices.iter().filter(|&&index| index < self.media_count).count() as i32,
+            None => self.media_count,
+        }
+    }
+
+    /// Estimated size in bytes of the selected media not yet downloaded, from the file size of the gallery.
+    pub fn remaining_size(&self) -> Option<u64> {
+        let file_size = self.file_size.filter(|size| *size > 0)?;
+        if self.media_count <= 0 {
+            return None;
+        }
+        let downloaded = self
+            .image_tasks
+            .iter()
+            .filter(|task| task.downloaded && self.is_selected(task.index))
+            .count() as i64;
+        let remaining = (self.selected_count() as i64 - downloaded).max(0);
+        Some((file_size * remaining / self.media_count as i64) as u64)
+    }
+}
+
 pub fn get_download_task(db: Database, gid: i64) -> Result<PandaDownloadTask> {
     use bottle_core::schema::{image, panda_gallery, panda_media, work};
     use bottle_library::model::{Image, Work};
@@ -89,8 +153,12 @@ pub fn get_download_task(db: Database, gid: i64) -> Result<PandaDownloadTask> {
         title: gallery.title.clone(),
         has_detail: gallery.has_detail(),
         media_count: gallery.media_count,
+        file_size: gallery.file_size,
         work_id: work.id,
         image_tasks,
+        indices: None,
+        ignore_backoff: false,
+        ordered: false,
     })
 }
 
@@ -183,8 +251,12 @@ pub fn get_all_download_tasks(db: Database) -> Result<Vec<PandaDownloadTask>> {
             title: gallery.title.clone(),
             has_detail: gallery.has_detail(),
             media_count: gallery.media_count,
+            file_size: gallery.file_size,
             work_id: *work_id,
             image_tasks,
+            indices: None,
+            ignore_backoff: false,
+            ordered: false,
         });
     }
 
@@ -204,6 +276,249 @@ pub fn update_gallery(db: Database, gallery: &Gallery, detail: &GalleryDetail) -
     Ok(gallery)
 }
 
+// MARK: Functions for checking archived galleries
+
+/// Get IDs and tokens of archived galleries not checked since the date, least recently checked first.
+pub fn galleries_to_check(db: Database, checked_before: NaiveDateTime, count: i64) -> Result<Vec<(i64, String)>> {
+    use bottle_core::schema::{panda_gallery, work};
+
+    let gallery_ids = work::table
+        .filter(work::source.eq("panda"))
+        .select(work::post_id_int.assume_not_null());
+    let galleries = panda_gallery::table
+        .filter(panda_gallery::id.eq_any(gallery_ids))
+        .filter(
+            panda_gallery::checked_date
+                .is_null()
+                .or(panda_gallery::checked_date.lt(checked_before)),
+        )
+        .order(panda_gallery::checked_date.asc())
+        .limit(count)
+        .select((panda_gallery::id, panda_gallery::token))
+        .load::<(i64, String)>(db)?;
+    Ok(galleries)
+}
+
+/// Save the newer version of an archived gallery, add it to the library,
+/// and link its work to the work of the previous gallery. Returns the ID of the new work.
+pub fn add_newer_version(db: Database, previous_gid: i64, page: &GalleryPageResult) -> Result<i32> {
+    use bottle_core::schema::{panda_gallery, panda_gallery_tag, panda_tag, work};
+    use bottle_library::model::Work;
+
+    let gid = page.gallery.gid as i64;
+    let previous_work = work::table
+        .filter(work::source.eq("panda"))
+        .filter(work::post_id_int.eq(previous_gid))
+        .first::<Work>(db)
+        .optional()?
+        .ok_or(Error::ObjectNotFound(format!("Work of panda gallery {}", previous_gid)))?;
+
+    // 1. Save the gallery with its tags and detail
+    let new_gallery = model::NewPandaGallery::from(&page.gallery);
+    let tags = util::tags(&page.gallery);
+    let gallery_tags = util::gallery_tags(&page.gallery);
+    db.transaction(|conn| -> Result<()> {
+        diesel::insert_into(panda_gallery::table)
+            .values(&new_gallery)
+            .execute(conn)?;
+        diesel::insert_into(panda_tag::table).values(&tags).execute(conn)?;
+        diesel::insert_into(panda_gallery_tag::table)
+            .values(&gallery_tags)
+            .execute(conn)?;
+        Ok(())
+    })?;
+    let gallery = update_gallery(db, &page.gallery, &page.detail)?;
+
+    // 2. Add the gallery to the library, and leave the images to the download job
+    let existing_work = work::table
+        .filter(work::source.eq("panda"))
+        .filter(work::post_id_int.eq(gid))
+        .first::<Work>(db)
+        .optional()?;
+    let work_id = match existing_work {
+        Some(work) => work.id,
+        None => {
+            let work = RemoteWork {
+                source: Some("panda".to_string()),
+                post_id: Some(gid.to_string()),
+                post_id_int: Some(gid),
+                page_index: None,
+                media_count: gallery.media_count,
+                name: Some(gallery.title.clone()),
+                content_rating: Some(gallery.content_rating()),
+                ..Default::default()
+            };
+            let response = bottle_library::add_remote_work(db, &work)?;
+            response
+                .works
+                .and_then(|works| works.first().map(|work| work.id))
+                .ok_or(Error::ObjectNotFound(format!("Work of panda gallery {}", gid)))?
+        }
+    };
+
+    // 3. Link the work lineage
+    bottle_library::set_previous_work(db, work_id, previous_work.id)?;
+    tracing::info!(
+        "Added panda gallery {} as the newer version of gallery {}",
+        gid,
+        previous_gid
+    );
+    Ok(work_id)
+}
+
+// MARK: Functions for failed downloads
+
+/// Replace the download failures of the attempted media with the new failures, as (media index, error).
+/// Media failing again have their failure counts increased, and the others have their failures cleared.
+pub fn save_failures(db: Database, gallery_id: i64, attempted: &[i32], failures: &[(i32, String)]) -> Result<()> {
+    use bottle_core::schema::panda_download_failure;
+
+    let now = chrono::Utc::now().naive_utc();
+    db.transaction(|conn| -> Result<()> {
+        let previous_counts = panda_download_failure::table
+            .filter(panda_download_failure::gallery_id.eq(gallery_id))
+            .filter(panda_download_failure::media_index.eq_any(attempted))
+            .select((
+                panda_download_failure::media_index,
+                panda_download_failure::failure_count,
+            ))
+            .load::<(i32, i32)>(conn)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let failures = failures
+            .iter()
+            .map(|(index, error)| model::PandaDownloadFailure {
+                gallery_id,
+                media_index: *index,
+                error: error.clone(),
+                failed_date: now,
+                failure_count: previous_counts.get(index).unwrap_or(&0) + 1,
+            })
+            .collect::<Vec<_>>();
+        diesel::delete(
+            panda_download_failure::table
+                .filter(panda_download_failure::gallery_id.eq(gallery_id))
+                .filter(panda_download_failure::media_index.eq_any(attempted)),
+        )
+        .execute(conn)?;
+        diesel::insert_into(panda_download_failure::table)
+            .values(&failures)
+            .execute(conn)?;
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Result of a finished download job, kept to be shown after restart.
+#[derive(Debug, Clone)]
+pub struct PandaDownloadJobRecord {
+    pub gid: i64,
+    pub title: String,
+    /// One of `success`, `partial` and `failed`.
+    pub state: String,
+    pub total: i32,
+    pub success: i32,
+    pub error: Option<String>,
+    /// Media failed to download as (media index, error). Saved separately by `save_failures`.
+    pub failures: Vec<(i32, String)>,
+}
+
+/// Save the result of a finished download job, replacing the previous one of the gallery.
+pub fn save_job_record(db: Database, record: &PandaDownloadJobRecord) -> Result<()> {
+    use bottle_core::schema::panda_download_job;
+
+    let job = model::PandaDownloadJob {
+        gallery_id: record.gid,
+        title: record.title.clone(),
+        state: record.state.clone(),
+        total: record.total,
+        success: record.success,
+        error: record.error.clone(),
+        finished_date: chrono::Utc::now().naive_utc(),
+    };
+    diesel::insert_into(panda_download_job::table)
+        .values(&job)
+        .on_conflict(panda_download_job::gallery_id)
+        .do_update()
+        .set(&job)
+        .execute(db)?;
+    Ok(())
+}
+
+/// Get the results of all finished download jobs, with their failures.
+pub fn job_records(db: Database) -> Result<Vec<PandaDownloadJobRecord>> {
+    use bottle_core::schema::{panda_download_failure, panda_download_job};
+    use itertools::Itertools;
+
+    let jobs = panda_download_job::table
+        .order(panda_download_job::finished_date.asc())
+        .load::<model::PandaDownloadJob>(db)?;
+    let failures = panda_download_failure::table
+        .filter(panda_download_failure::gallery_id.eq_any(jobs.iter().map(|job| job.gallery_id)))
+        .order(panda_download_failure::media_index.asc())
+        .load::<model::PandaDownloadFailure>(db)?
+        .into_iter()
+        .into_group_map_by(|failure| failure.gallery_id);
+
+    let records = jobs
+        .into_iter()
+        .map(|job| PandaDownloadJobRecord {
+            failures: failures
+                .get(&job.gallery_id)
+                .map(|failures| {
+                    failures
+                        .iter()
+                        .map(|failure| (failure.media_index, failure.error.clone()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            gid: job.gallery_id,
+            title: job.title,
+            state: job.state,
+            total: job.total,
+            success: job.success,
+            error: job.error,
+        })
+        .collect();
+    Ok(records)
+}
+
+/// Maximum exponent of the backoff after download failures, capping it at 1024 times the base delay.
+const MAX_BACKOFF_EXPONENT: i32 = 10;
+
+/// Get the media still backing off after failing to download, by their indices with the dates they can be retried.
+/// The backoff starts from `base_secs` after the first failure, and doubles with each consecutive failure.
+pub fn backoff_indices(db: Database, gallery_id: i64, base_secs: u64) -> Result<HashMap<i32, NaiveDateTime>> {
+    use bottle_core::schema::panda_download_failure;
+
+    let now = chrono::Utc::now().naive_utc();
+    let failures = panda_download_failure::table
+        .filter(panda_download_failure::gallery_id.eq(gallery_id))
+        .load::<model::PandaDownloadFailure>(db)?;
+    let indices = failures
+        .into_iter()
+        .filter_map(|failure| {
+            let exponent = (failure.failure_count - 1).clamp(0, MAX_BACKOFF_EXPONENT);
+            let backoff = chrono::Duration::seconds((base_secs as i64).saturating_mul(1 << exponent));
+            let retry_date = failure.failed_date + backoff;
+            (retry_date > now).then_some((failure.media_index, retry_date))
+        })
+        .collect();
+    Ok(indices)
+}
+
+/// Get the indices of media failed to download in the last runs.
+pub fn failed_indices(db: Database, gallery_id: i64) -> Result<Vec<i32>> {
+    use bottle_core::schema::panda_download_failure;
+
+    let indices = panda_download_failure::table
+        .filter(panda_download_failure::gallery_id.eq(gallery_id))
+        .order(panda_download_failure::media_index.asc())
+        .select(panda_download_failure::media_index)
+        .load::<i32>(db)?;
+    Ok(indices)
+}
+
 pub fn save_previews(db: Database, page: &GalleryPageResult) -> Result<()> {
     use bottle_core::schema::panda_media;
 
@@ -1,12 +1,13 @@
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 
 use bottle_core::feed::{Account, Feed};
-use bottle_core::{feed::*, Error, Result};
+use bottle_core::{feed::*, library::WorkFilter, mute::UserMute, Error, Result};
 use panda_client::{
     FavoriteSearchOption, GalleryListOffset, GalleryListResult, PandaClient, PandaCookie, SearchOption,
 };
@@ -15,18 +16,50 @@ use crate::community::PandaAccount;
 use crate::util;
 use crate::{group, model};
 
-#[derive(Debug, Clone, Deserialize)]
+/// Columns of posts for ordering them in feeds and the library.
+const POST_COLUMNS: PostColumns = PostColumns {
+    community: "panda",
+    table: "panda_gallery",
+    post_id: "panda_gallery.id",
+    created_date: "panda_gallery.created_date",
+    media_count: "panda_gallery.media_count",
+    expunged: Some("panda_gallery.expunged"),
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PandaFeedParams {
-    Search { option: SearchOption },
-    Watched { option: SearchOption },
-    Favorites { option: FavoriteSearchOption },
+    Search {
+        option: SearchOption,
+        /// Languages of the galleries saved to the feed, or all languages if empty.
+        #[serde(default)]
+        languages: Vec<String>,
+    },
+    Watched {
+        option: SearchOption,
+        #[serde(default)]
+        languages: Vec<String>,
+    },
+    Favorites {
+        option: FavoriteSearchOption,
+        #[serde(default)]
+        languages: Vec<String>,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct PandaFetchContext {
     pub(crate) offset: Option<GalleryListOffset>,
     pub(crate) direction: Direction,
+    /// Client of the account from the cache, reused across the pages of an update.
+    pub(crate) client: Option<PandaClient>,
+}
+
+impl PandaFetchContext {
+    /// Use the client cached for the account. Needed before fetching.
+    pub fn set_client(&mut self, client: PandaClient) {
+        self.client = Some(client);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +77,40 @@ pub struct PandaFeed {
     pub account_id: i32,
     pub params: PandaFeedParams,
     pub reached_end: bool,
+    /// Name of the favorite category of a favorites feed, synced from the account.
+    pub favorite_category_name: Option<String>,
+    pub icon_url: Option<String>,
+    /// Accent color in `#rrggbb`.
+    pub color: Option<String>,
+    /// Collage of the recent works in the library, relative to the image directory.
+    pub cover_path: Option<String>,
+    /// Error of the last update, if it failed.
+    pub last_error: Option<String>,
+    /// Number of consecutive failed updates.
+    pub failure_count: i32,
+    /// Album mirroring a favorites feed of a category, where its galleries are archived and downloaded in order.
+    pub mirror_album_id: Option<i32>,
+    /// Where the next update starts from, deep in the results, cleared once the update saves a page.
+    pub backfill_offset: Option<PandaBackfillOffset>,
+}
+
+/// Where to start fetching a search feed from, to sample or resume enormous searches from the middle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PandaBackfillOffset {
+    /// Percentage of the results, from 1 to 99.
+    Percentage(u32),
+    /// Galleries posted before the date.
+    Date(chrono::NaiveDate),
+}
+
+impl From<&PandaBackfillOffset> for GalleryListOffset {
+    fn from(offset: &PandaBackfillOffset) -> Self {
+        match offset {
+            PandaBackfillOffset::Percentage(percent) => GalleryListOffset::Percentage(*percent),
+            PandaBackfillOffset::Date(date) => GalleryListOffset::Seek(date.format("%Y-%m-%d").to_string()),
+        }
+    }
 }
 
 #[async_trait]
@@ -59,20 +126,38 @@ impl Feed for PandaFeed {
         vec![
             FeedMetadata {
                 name: "search".to_string(),
-                scheme: Scheme::Object(HashMap::from([("option".to_string(), util::search_option_scheme())])),
+                label: "Search".to_string(),
+                scheme: Scheme::Object(HashMap::from([
+                    (
+                        "option".to_string(),
+                        Scheme::field("Search Option", util::search_option_scheme()),
+                    ),
+                    ("languages".to_string(), util::languages_scheme()),
+                ])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "watched".to_string(),
-                scheme: Scheme::Object(HashMap::from([("option".to_string(), util::search_option_scheme())])),
+                label: "Watched Tags".to_string(),
+                scheme: Scheme::Object(HashMap::from([
+                    (
+                        "option".to_string(),
+                        Scheme::field("Search Option", util::search_option_scheme()),
+                    ),
+                    ("languages".to_string(), util::languages_scheme()),
+                ])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "favorites".to_string(),
-                scheme: Scheme::Object(HashMap::from([(
-                    "option".to_string(),
-                    util::favorite_search_option_scheme(),
-                )])),
+                label: "Favorites".to_string(),
+                scheme: Scheme::Object(HashMap::from([
+                    (
+                        "option".to_string(),
+                        Scheme::field("Search Option", util::favorite_search_option_scheme()),
+                    ),
+                    ("languages".to_string(), util::languages_scheme()),
+                ])),
                 need_auth: true,
             },
         ]
@@ -84,7 +169,14 @@ impl Feed for PandaFeed {
             community: "panda".to_string(),
             name: self.name.clone(),
             watching: self.watching,
-            description: self.params.to_string(),
+            icon_url: self.icon_url.clone(),
+            color: self.color.clone(),
+            cover_path: self.cover_path.clone(),
+            last_error: self.last_error.clone(),
+            failure_count: self.failure_count,
+            require_media: false,
+            min_resolution: None,
+            description: self.description(),
         }
     }
 
@@ -96,7 +188,7 @@ impl Feed for PandaFeed {
         panda_watch_list
             .load::<model::PandaWatchList>(db)?
             .into_iter()
-            .map(Self::try_from)
+            .map(|watch_list| Self::try_from(watch_list)?.with_favorite_category_name(db))
             .collect()
     }
 
@@ -109,7 +201,9 @@ impl Feed for PandaFeed {
             .filter(id.eq(feed_id))
             .first::<model::PandaWatchList>(db)
             .optional()?;
-        result.map(Self::try_from).transpose()
+        result
+            .map(|watch_list| Self::try_from(watch_list)?.with_favorite_category_name(db))
+            .transpose()
     }
 
     fn delete(db: Database, feed_id: i32) -> Result<()>
@@ -137,6 +231,9 @@ impl Feed for PandaFeed {
             account_id,
             kind: params.kind(),
             query: Some(params.query()),
+            icon_url: info.icon_url.clone(),
+            color: info.color.clone(),
+            languages: params.languages_column(),
         };
         let result = diesel::insert_into(panda_watch_list::table)
             .values(&new_watch_list)
@@ -148,7 +245,7 @@ impl Feed for PandaFeed {
             info,
             account_id
         );
-        Self::try_from(result)
+        Self::try_from(result)?.with_favorite_category_name(db)
     }
 
     fn modify(&mut self, db: Database, info: &FeedInfo) -> Result<FeedView> {
@@ -157,6 +254,8 @@ impl Feed for PandaFeed {
             name: info.name.clone(),
             watching: info.watching,
             first_fetch_limit: info.first_fetch_limit,
+            icon_url: info.icon_change(),
+            color: info.color_change(),
         };
         diesel::update(panda_watch_list::table.find(self.id))
             .set(&update)
@@ -164,6 +263,12 @@ impl Feed for PandaFeed {
         self.name = info.name.clone();
         self.watching = info.watching;
         self.first_fetch_limit = info.first_fetch_limit;
+        if let Some(icon_url) = info.icon_change() {
+            self.icon_url = icon_url;
+        }
+        if let Some(color) = info.color_change() {
+            self.color = color;
+        }
         tracing::info!("Modified panda feed {}: {:?}", self.id, info);
         Ok(self.view())
     }
@@ -174,6 +279,11 @@ impl Feed for PandaFeed {
             panda_watch_list_history,
         };
 
+        // Sync favorite categories of the account, which are shown on the favorites page
+        if let Some(categories) = &fetched.favorite_categories {
+            PandaAccount::save_favorite_categories(db, self.account_id, categories)?;
+        }
+
         // (a) If response is empty, we should stop updating
         let empty_result = fetched.galleries.is_empty();
         let no_more_result = match ctx.direction {
@@ -205,13 +315,13 @@ impl Feed for PandaFeed {
             .load::<i64>(db)?;
         let existing_ids = existing_ids.into_iter().collect::<HashSet<_>>();
         let has_existing_result = !existing_ids.is_empty();
-        let galleries = fetched
+        let new_galleries = fetched
             .galleries
             .iter()
             .filter(|g| !existing_ids.contains(&(g.gid as i64)));
 
         // (b) If no posts are new, stop
-        if galleries.clone().count() == 0 {
+        if new_galleries.clone().count() == 0 {
             return Ok(SaveResult {
                 post_ids: vec![],
                 should_stop: true,
@@ -219,9 +329,24 @@ impl Feed for PandaFeed {
             });
         }
 
+        // Skip galleries in other languages or by muted artists,
+        // but keep their offsets in the history to fetch past them
+        let muted_user_ids = UserMute::user_ids(db, "panda")?;
+        let galleries = new_galleries
+            .clone()
+            .filter(|g| self.params.accepts_language(&util::gallery_language(g)))
+            .filter(|g| !util::artists(g).any(|artist| muted_user_ids.contains(artist)));
+        let skipped_count = new_galleries.clone().count() - galleries.clone().count();
+        if skipped_count > 0 {
+            tracing::info!(
+                "Skipped {} panda galleries in other languages or by muted artists",
+                skipped_count
+            );
+        }
+
         // 2. Prepare data to insert
         // Gallery, Tag, GalleryTag
-        let new_galleries = galleries.clone().map(model::NewPandaGallery::from).collect::<Vec<_>>();
+        let gallery_models = galleries.clone().map(model::NewPandaGallery::from).collect::<Vec<_>>();
         let tags = galleries.clone().flat_map(util::tags).collect::<Vec<_>>();
         let gallery_tags = galleries.clone().flat_map(util::gallery_tags).collect::<Vec<_>>();
 
@@ -233,18 +358,27 @@ impl Feed for PandaFeed {
                 gallery_id: g.gid as i64,
                 sort_index: None,
                 stale: false,
+                dismissed: false,
+                favorited_date: g.favorited_date.map(|date| date.naive_utc()),
             })
             .collect::<Vec<_>>();
 
         // WatchListHistory
         let gallery_ids = galleries.clone().map(|g| g.gid.to_string()).collect::<Vec<_>>();
+        // Offsets of favorites are in format of "gid:timestamp" of the favorited date
+        let gallery_offsets = new_galleries
+            .map(|g| match (&self.params, g.favorited_date) {
+                (PandaFeedParams::Favorites { .. }, Some(date)) => favorites_offset(g.gid as i64, date.timestamp()),
+                _ => g.gid.to_string(),
+            })
+            .collect::<Vec<_>>();
         let prev_offset = match fetched.prev_page_offset.as_ref() {
             Some(GalleryListOffset::NewerThan(offset)) => Some(offset.to_string()),
-            _ => gallery_ids.first().cloned(),
+            _ => gallery_offsets.first().cloned(),
         };
         let next_offset = match fetched.next_page_offset.as_ref() {
             Some(GalleryListOffset::OlderThan(offset)) => Some(offset.to_string()),
-            _ => gallery_ids.last().cloned(),
+            _ => gallery_offsets.last().cloned(),
         };
         let history = model::NewPandaWatchListHistory {
             watch_list_id: self.id,
@@ -257,7 +391,7 @@ impl Feed for PandaFeed {
         // 3. Insert data
         db.transaction(|conn| -> Result<()> {
             diesel::insert_into(panda_gallery::table)
-                .values(&new_galleries)
+                .values(&gallery_models)
                 .execute(conn)?;
             diesel::insert_into(panda_tag::table).values(&tags).execute(conn)?;
             diesel::insert_into(panda_gallery_tag::table)
@@ -272,6 +406,13 @@ impl Feed for PandaFeed {
             Ok(())
         })?;
 
+        // The backfill has started from its offset, and continues from the saved history from now on
+        if self.backfill_offset.is_some() {
+            diesel::update(panda_watch_list::table.find(self.id))
+                .set(panda_watch_list::backfill_offset.eq(None::<String>))
+                .execute(db)?;
+        }
+
         // TODO: If first fetch limit is reached, mark feed as reached end
 
         tracing::info!("Saved posts to panda feed {}: {}", self.id, history.ids);
@@ -283,25 +424,13 @@ impl Feed for PandaFeed {
     }
 
     fn handle_before_update(&self, db: Database) -> Result<()> {
-        // Delete watch list posts that don't have sort index
-        use bottle_core::schema::panda_watch_list_gallery::dsl::*;
         use itertools::Itertools;
 
-        let post_ids = panda_watch_list_gallery
-            .filter(watch_list_id.eq(self.id))
-            .filter(sort_index.is_null())
-            .select(gallery_id)
-            .load::<i64>(db)?;
+        // Delete watch list posts that don't have sort index
+        let post_ids = bottle_library::delete_unsorted_watch_list_posts(db, "panda", Some(self.id))?;
         if post_ids.is_empty() {
             return Ok(());
         }
-
-        diesel::delete(
-            panda_watch_list_gallery
-                .filter(watch_list_id.eq(self.id))
-                .filter(sort_index.is_null()),
-        )
-        .execute(db)?;
         tracing::info!(
             "Deleted {} posts without sort index from panda feed {}: {}",
             post_ids.len(),
@@ -356,15 +485,31 @@ impl Feed for PandaFeed {
         Ok(())
     }
 
-    fn posts(&self, db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn posts(
+        &self,
+        db: Database,
+        filter: &PostFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{panda_gallery, panda_media, panda_watch_list_gallery};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{
+            dsl::sql,
+            sql_types::{Bool, Integer},
+        };
 
         // 1. Fetch posts
         let (posts, total_items) = panda_watch_list_gallery::table
             .inner_join(panda_gallery::table)
             .filter(panda_watch_list_gallery::watch_list_id.eq(self.id))
-            .order(panda_watch_list_gallery::sort_index.desc())
+            .filter(sql::<Bool>(
+                &filter.sql_condition(&POST_COLUMNS, "panda_watch_list_gallery"),
+            ))
+            .order(sql::<Integer>(
+                &order.feed_sql(&POST_COLUMNS, "panda_watch_list_gallery.sort_index"),
+            ))
             .select(panda_gallery::all_columns)
             .paginate(page, page_size)
             .load_and_count::<model::PandaGallery>(db)?;
@@ -395,46 +540,75 @@ impl Feed for PandaFeed {
             users: Some(users),
             works: Some(works),
             images: Some(images),
+            notes: None,
             total_items,
             page,
             page_size,
         })
     }
 
+    fn set_dismissed(&self, db: Database, post_ids: &[i64], dismissed: bool) -> Result<usize> {
+        use bottle_core::schema::panda_watch_list_gallery;
+
+        let count = diesel::update(panda_watch_list_gallery::table)
+            .filter(panda_watch_list_gallery::watch_list_id.eq(self.id))
+            .filter(panda_watch_list_gallery::gallery_id.eq_any(post_ids))
+            .set(panda_watch_list_gallery::dismissed.eq(dismissed))
+            .execute(db)?;
+        Ok(count)
+    }
+
     fn get_account(&self, db: Database) -> Result<Self::Account> {
         PandaAccount::get(db, self.account_id)?.ok_or(Error::NotLoggedIn("Invalid account".to_string()))
     }
 
+    /// All feeds continue from the offsets of the saved galleries.
+    fn resumable(&self) -> bool {
+        true
+    }
+
     fn get_fetch_context(&self, db: Database) -> Result<Self::FetchContext> {
+        if let Some(backfill_offset) = &self.backfill_offset {
+            return Ok(Self::FetchContext {
+                offset: Some(backfill_offset.into()),
+                direction: Direction::Backward,
+                client: None,
+            });
+        }
         let offset = match self.params {
             PandaFeedParams::Search { .. } | PandaFeedParams::Watched { .. } if self.reached_end => {
                 self.prev_offset(db)?
             }
-            PandaFeedParams::Search { .. } | PandaFeedParams::Watched { .. } if !self.reached_end => {
-                self.next_offset(db)?
-            }
-            // For favorites feed, offset is in format of "gid:timestamp"
-            // But the timestamp can't be precisely retrieved from webpage
-            // So we can't determine the offset for favorites feed
-            _ => None,
+            PandaFeedParams::Search { .. } | PandaFeedParams::Watched { .. } => self.next_offset(db)?,
+            // For favorites feed, offset is in format of "gid:timestamp",
+            // determined from the favorited dates saved with the galleries
+            PandaFeedParams::Favorites { .. } => self.favorites_offset(db, self.reached_end)?,
         };
         let direction = match offset {
             Some(GalleryListOffset::NewerThan(_)) => Direction::Forward,
             _ => Direction::Backward,
         };
-        Ok(Self::FetchContext { offset, direction })
+        Ok(Self::FetchContext {
+            offset,
+            direction,
+            client: None,
+        })
     }
 
     async fn fetch(&self, ctx: &mut Self::FetchContext, auth: Option<&Self::Auth>) -> Result<Self::FetchResult> {
-        let Some(auth) = auth else {
+        if auth.is_none() {
             return Err(Error::NotLoggedIn("Panda feed needs an account".to_string()));
-        };
-        let client = PandaClient::new(auth.clone()).map_err(anyhow::Error::from)?;
+        }
+        // The client is set from the cache of the account when the context is prepared
+        if ctx.client.is_none() {
+            return Err(Error::NotLoggedIn("Panda feed needs a cached client".to_string()));
+        }
+        let client = ctx.client.as_ref().unwrap();
         let offset = ctx.offset.as_ref();
         let result = match self.params {
-            PandaFeedParams::Search { ref option } => client.search(option, offset).await,
-            PandaFeedParams::Watched { ref option } => client.watched(option, offset).await,
-            PandaFeedParams::Favorites { ref option } => client.favorites(option, offset).await,
+            PandaFeedParams::Search { ref option, .. } => client.search(option, offset).await,
+            PandaFeedParams::Watched { ref option, .. } => client.watched(option, offset).await,
+            PandaFeedParams::Favorites { ref option, .. } => client.favorites(option, offset).await,
         }
         .map_err(anyhow::Error::from)?;
         ctx.offset = match ctx.direction {
@@ -444,16 +618,27 @@ impl Feed for PandaFeed {
         Ok(result)
     }
 
-        fn archived_posts(db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+        fn archived_posts(
+        db: Database,
+        filter: &WorkFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::library::{ImageView, WorkView};
         use bottle_core::schema::{image, panda_gallery, panda_media, work};
         use bottle_library::model::{Image, Work};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{
+            dsl::sql,
+            sql_types::{Bool, Integer},
+        };
 
         // 1. Fetch works
         let (works, total_items) = work::table
             .filter(work::source.eq("panda"))
-            .order(work::added_date.desc())
+            .filter(sql::<Bool>(&filter.sql_condition()))
+            .order(sql::<Integer>(&order.work_sql(&POST_COLUMNS)))
             .paginate(page, page_size)
             .load_and_count::<Work>(db)?;
 
@@ -491,6 +676,7 @@ impl Feed for PandaFeed {
             users: Some(users),
             works: Some(works.into_iter().map(WorkView::from).collect()),
             images: Some(images.into_iter().map(ImageView::from).collect()),
+            notes: None,
             total_items,
             page,
             page_size,
@@ -499,24 +685,35 @@ impl Feed for PandaFeed {
 
         fn archived_posts_grouped_by_user(
         db: Database,
+        filter: &WorkFilter,
         page: i64,
         page_size: i64,
         recent_count: i64,
     ) -> Result<GeneralResponse> {
         use diesel::sql_query;
         let query = sql_query(group::grouped_by_user_query(
-            "select distinct panda_gallery.* from panda_gallery
-            join work on panda_gallery.id = work.post_id_int
-            where work.source = 'panda'",
+            &format!(
+                "select distinct panda_gallery.* from panda_gallery
+                join work on panda_gallery.id = work.post_id_int
+                where work.source = 'panda' and {}",
+                filter.sql_condition()
+            ),
             "order by created_date desc",
         ))
         .into_boxed();
         group::posts_grouped_by_user(db, query, page, page_size, recent_count)
     }
 
-        fn archived_posts_by_user(db: Database, user_id: String, page: i64, page_size: i64) -> Result<GeneralResponse> {
+        fn archived_posts_by_user(
+        db: Database,
+        user_id: String,
+        filter: &WorkFilter,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{panda_gallery, panda_gallery_tag, work};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{dsl::sql, sql_types::Bool};
 
         let results = panda_gallery::table
             .inner_join(panda_gallery_tag::table)
@@ -527,6 +724,7 @@ impl Feed for PandaFeed {
                     .and(panda_gallery_tag::name.eq(&user_id)),
             )
             .filter(work::source.eq("panda"))
+            .filter(sql::<Bool>(&filter.sql_condition()))
             .order(panda_gallery::created_date.desc())
             .select(panda_gallery::all_columns)
             .paginate(page, page_size)
@@ -571,11 +769,49 @@ impl Feed for PandaFeed {
             .load_and_count::<model::PandaGallery>(db)?;
         group::posts_by_user(db, results, user_id, page, page_size)
     }
+
+    fn new_post_count(&self, db: Database, since: DateTime<Utc>) -> Result<i64> {
+        use bottle_core::schema::{panda_gallery, panda_watch_list_gallery};
+
+        let count = panda_watch_list_gallery::table
+            .inner_join(panda_gallery::table)
+            .filter(panda_watch_list_gallery::watch_list_id.eq(self.id))
+            .filter(panda_gallery::added_date.gt(since.naive_utc()))
+            .count()
+            .get_result::<i64>(db)?;
+        Ok(count)
+    }
 }
 
 // MARK: Helpers
 
 impl PandaFeed {
+    /// Describe the feed, labeling favorites feeds with the category name if synced.
+    fn description(&self) -> String {
+        match (&self.params, &self.favorite_category_name) {
+            (PandaFeedParams::Favorites { option, .. }, Some(category_name)) => format!(
+                "Favorites {}{}",
+                category_name,
+                option.keyword.as_ref().map(|s| format!(": {}", s)).unwrap_or_default()
+            ),
+            _ => self.params.to_string(),
+        }
+    }
+
+    fn with_favorite_category_name(mut self, db: Database) -> Result<Self> {
+        if let PandaFeedParams::Favorites {
+            option: FavoriteSearchOption {
+                category_index: Some(index),
+                ..
+            },
+            ..
+        } = &self.params
+        {
+            self.favorite_category_name = PandaAccount::favorite_category_name(db, self.account_id, *index)?;
+        }
+        Ok(self)
+    }
+
     fn prev_offset(&self, db: Database) -> Result<Option<GalleryListOffset>> {
         use bottle_core::schema::panda_watch_list_history::dsl::*;
         let result = panda_watch_list_history
@@ -587,6 +823,31 @@ impl PandaFeed {
         Ok(result.flatten().map(GalleryListOffset::NewerThan))
     }
 
+    /// Offset of the favorites feed at the most recently favorited gallery saved if `newer`, or the least recently one.
+    /// None if no gallery is saved with its favorited date, so that the feed is fetched from the start.
+    fn favorites_offset(&self, db: Database, newer: bool) -> Result<Option<GalleryListOffset>> {
+        use bottle_core::schema::panda_watch_list_gallery::dsl::*;
+        let query = panda_watch_list_gallery
+            .filter(watch_list_id.eq(self.id))
+            .filter(favorited_date.is_not_null())
+            .select((gallery_id, favorited_date))
+            .into_boxed();
+        let query = if newer {
+            query.order((favorited_date.desc(), gallery_id.desc()))
+        } else {
+            query.order((favorited_date.asc(), gallery_id.asc()))
+        };
+        let result = query.first::<(i64, Option<NaiveDateTime>)>(db).optional()?;
+        Ok(result.and_then(|(gid, date)| {
+            let offset = favorites_offset(gid, date?.and_utc().timestamp());
+            Some(if newer {
+                GalleryListOffset::NewerThan(offset)
+            } else {
+                GalleryListOffset::OlderThan(offset)
+            })
+        }))
+    }
+
     fn next_offset(&self, db: Database) -> Result<Option<GalleryListOffset>> {
         use bottle_core::schema::panda_watch_list_history::dsl::*;
         let result = panda_watch_list_history
@@ -599,6 +860,104 @@ impl PandaFeed {
     }
 }
 
+/// Offset of the favorites page at the gallery favorited at the timestamp.
+fn favorites_offset(gid: i64, timestamp: i64) -> String {
+    format!("{}:{}", gid, timestamp)
+}
+
+// MARK: Backfill
+
+impl PandaFeed {
+    /// Start the next update of the search feed from the offset deep in the results.
+    pub fn set_backfill_offset(&mut self, db: Database, offset: PandaBackfillOffset) -> Result<()> {
+        use bottle_core::schema::panda_watch_list;
+
+        if matches!(self.params, PandaFeedParams::Favorites { .. }) {
+            return Err(Error::InvalidEndpoint(format!(
+                "Panda feed {} is a favorites feed, which cannot be backfilled",
+                self.id
+            )));
+        }
+        if let PandaBackfillOffset::Percentage(percent) = offset {
+            if !(1..=99).contains(&percent) {
+                return Err(Error::InvalidEndpoint(format!("Percentage {} not in 1 to 99", percent)));
+            }
+        }
+
+        diesel::update(panda_watch_list::table.find(self.id))
+            .set(panda_watch_list::backfill_offset.eq(serde_json::to_string(&offset)?))
+            .execute(db)?;
+        tracing::info!("Set backfill offset of panda feed {}: {:?}", self.id, offset);
+        self.backfill_offset = Some(offset);
+        Ok(())
+    }
+}
+
+// MARK: Mirror
+
+impl PandaFeed {
+    /// Mirror the favorites feed of a category to the album, or stop mirroring if `None`.
+    pub fn set_mirror_album(&mut self, db: Database, album_id: Option<i32>) -> Result<()> {
+        use bottle_core::schema::panda_watch_list;
+
+        let is_category_favorites = matches!(
+            self.params,
+            PandaFeedParams::Favorites {
+                option: FavoriteSearchOption {
+                    category_index: Some(_),
+                    ..
+                },
+                ..
+            }
+        );
+        if album_id.is_some() && !is_category_favorites {
+            return Err(Error::InvalidEndpoint(format!(
+                "Panda feed {} is not a favorites feed of a category",
+                self.id
+            )));
+        }
+
+        diesel::update(panda_watch_list::table.find(self.id))
+            .set(panda_watch_list::mirror_album_id.eq(album_id))
+            .execute(db)?;
+        self.mirror_album_id = album_id;
+        match album_id {
+            Some(album_id) => tracing::info!("Mirroring panda feed {} to album {}", self.id, album_id),
+            None => tracing::info!("Stopped mirroring panda feed {}", self.id),
+        }
+        Ok(())
+    }
+
+    /// Feeds mirrored to albums.
+    pub fn mirrored(db: Database) -> Result<Vec<Self>> {
+        use bottle_core::schema::panda_watch_list;
+        panda_watch_list::table
+            .filter(panda_watch_list::mirror_album_id.is_not_null())
+            .load::<model::PandaWatchList>(db)?
+            .into_iter()
+            .map(|watch_list| Self::try_from(watch_list)?.with_favorite_category_name(db))
+            .collect()
+    }
+
+    /// Galleries in the feed in order, the most recently favorited first, with their works if archived.
+    pub fn mirror_entries(&self, db: Database) -> Result<Vec<(i64, Option<i32>)>> {
+        use bottle_core::schema::{panda_watch_list_gallery, work};
+        let entries = panda_watch_list_gallery::table
+            .left_join(
+                work::table.on(work::source
+                    .eq("panda")
+                    .and(work::post_id_int.eq(panda_watch_list_gallery::gallery_id.nullable()))
+                    .and(work::page_index.is_null())),
+            )
+            .filter(panda_watch_list_gallery::watch_list_id.eq(self.id))
+            .filter(panda_watch_list_gallery::sort_index.is_not_null())
+            .order(panda_watch_list_gallery::sort_index.desc())
+            .select((panda_watch_list_gallery::gallery_id, work::id.nullable()))
+            .load::<(i64, Option<i32>)>(db)?;
+        Ok(entries)
+    }
+}
+
 impl PandaFeedParams {
     fn kind(&self) -> String {
         match self {
@@ -608,11 +967,82 @@ impl PandaFeedParams {
         }
     }
 
+    /// Languages of the galleries saved to the feed, or empty for all languages.
+    pub fn languages(&self) -> &[String] {
+        match self {
+            PandaFeedParams::Search { languages, .. }
+            | PandaFeedParams::Watched { languages, .. }
+            | PandaFeedParams::Favorites { languages, .. } => languages,
+        }
+    }
+
+    /// Whether galleries in the language are saved to the feed, ignoring case.
+    fn accepts_language(&self, language: &str) -> bool {
+        let languages = self.languages();
+        languages.is_empty() || languages.iter().any(|l| l.trim().eq_ignore_ascii_case(language))
+    }
+
+    fn languages_column(&self) -> Option<String> {
+        let languages = self.languages();
+        (!languages.is_empty()).then(|| {
+            languages
+                .iter()
+                .map(|l| l.trim().to_lowercase())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+    }
+
+    /// Errors of the fields in the params, by paths like `search.option.min_rating`.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let prefix = format!("{}.option", self.kind());
+        let mut errors = Vec::new();
+        match self {
+            PandaFeedParams::Search { option, .. } | PandaFeedParams::Watched { option, .. } => {
+                if option.categories.is_empty() {
+                    errors.push(FieldError::new(format!("{}.categories", prefix), "No category"));
+                }
+                if let Some(rating) = option.min_rating.filter(|rating| !(2..=5).contains(rating)) {
+                    errors.push(FieldError::new(
+                        format!("{}.min_rating", prefix),
+                        format!("Rating {} not in 2 to 5", rating),
+                    ));
+                }
+                if let (Some(min), Some(max)) = (option.min_pages, option.max_pages) {
+                    if min > max {
+                        errors.push(FieldError::new(
+                            format!("{}.max_pages", prefix),
+                            format!("Max pages {} less than min pages {}", max, min),
+                        ));
+                    }
+                }
+            }
+            PandaFeedParams::Favorites { option, .. } => {
+                if let Some(index) = option.category_index.filter(|index| *index > 9) {
+                    errors.push(FieldError::new(
+                        format!("{}.category_index", prefix),
+                        format!("Category {} not in 0 to 9", index),
+                    ));
+                }
+            }
+        }
+        for (i, language) in self.languages().iter().enumerate() {
+            let language = language.trim();
+            if language.is_empty() || language.contains(',') {
+                errors.push(FieldError::new(
+                    format!("{}.languages.{}", self.kind(), i),
+                    format!("Invalid language \"{}\"", language),
+                ));
+            }
+        }
+        errors
+    }
+
     fn query(&self) -> String {
         match self {
-            PandaFeedParams::Search { option } => option.to_string(),
-            PandaFeedParams::Watched { option } => option.to_string(),
-            PandaFeedParams::Favorites { option } => option.to_string(),
+            PandaFeedParams::Search { option, .. } => option.to_string(),
+            PandaFeedParams::Watched { option, .. } => option.to_string(),
+            PandaFeedParams::Favorites { option, .. } => option.to_string(),
         }
     }
 }
@@ -620,13 +1050,13 @@ impl PandaFeedParams {
 impl Display for PandaFeedParams {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let s = match self {
-            PandaFeedParams::Search { option } => option
+            PandaFeedParams::Search { option, .. } => option
                 .keyword
                 .as_ref()
                 .map(|s| format!("Search: {}", s))
                 .unwrap_or("Homepage".to_string()),
             PandaFeedParams::Watched { .. } => "Watched".to_string(),
-            PandaFeedParams::Favorites { option } => format!(
+            PandaFeedParams::Favorites { option, .. } => format!(
                 "Favorites {}{}",
                 option
                     .category_index
@@ -90,7 +90,7 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         user.post_count = artist_to_post_count.get(&user.user_id).cloned();
     }
     // Sort users by post_count
-    users.sort_by(|a, b| b.post_count.cmp(&a.post_count));
+    users.sort_by_key(|u| std::cmp::Reverse(u.post_count));
 
     // 3. Fetch associated posts
     let posts = panda_gallery::table
@@ -130,6 +130,7 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         media: Some(media.into_iter().map(MediaView::from).collect()),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items: artist_count,
         page,
         page_size,
@@ -171,6 +172,7 @@ pub(crate) fn posts_by_user(
         media: Some(media.into_iter().map(MediaView::from).collect()),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items,
         page,
         page_size,
@@ -22,6 +22,44 @@ pub struct NewPandaAccount {
     pub username: Option<String>,
 }
 
+#[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone)]
+#[diesel(primary_key(gallery_id, media_index))]
+#[diesel(table_name = panda_download_failure)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PandaDownloadFailure {
+    pub gallery_id: i64,
+    pub media_index: i32,
+    pub error: String,
+    pub failed_date: NaiveDateTime,
+    /// Number of consecutive runs failing to download the media.
+    pub failure_count: i32,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Insertable, AsChangeset, Debug, Clone)]
+#[diesel(primary_key(gallery_id))]
+#[diesel(table_name = panda_download_job)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PandaDownloadJob {
+    pub gallery_id: i64,
+    pub title: String,
+    pub state: String,
+    pub total: i32,
+    pub success: i32,
+    pub error: Option<String>,
+    pub finished_date: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = panda_favorite_category)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PandaFavoriteCategory {
+    pub account_id: i32,
+    pub category_index: i32,
+    pub name: String,
+    pub gallery_count: i32,
+    pub updated_date: NaiveDateTime,
+}
+
 #[derive(Queryable, Selectable, Identifiable, Debug, Clone, Default)]
 #[diesel(table_name = panda_gallery)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -38,10 +76,21 @@ pub struct PandaGallery {
     pub parent: Option<String>,
     pub visible: Option<bool>,
     pub language: Option<String>,
-    pub file_size: Option<i32>,
     pub created_date: NaiveDateTime,
     pub added_date: NaiveDateTime,
     pub stale: bool,
+    /// Why the gallery is invisible, e.g. `Expunged` or `Replaced`.
+    pub invisible_reason: Option<String>,
+    /// Latest version of the gallery, which replaces it.
+    pub newer_gallery_id: Option<i64>,
+    pub newer_token: Option<String>,
+    /// When the gallery detail was last fetched.
+    pub checked_date: Option<NaiveDateTime>,
+    pub expunged: bool,
+    /// Whether the uploader disowned the gallery.
+    pub disowned: bool,
+    /// Total file size of the gallery in bytes.
+    pub file_size: Option<i64>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -57,6 +106,8 @@ pub struct NewPandaGallery {
     pub rating: f32,
     pub media_count: i32,
     pub created_date: NaiveDateTime,
+    pub expunged: bool,
+    pub disowned: bool,
 }
 
 #[derive(AsChangeset, Debug, Clone, Default)]
@@ -69,7 +120,12 @@ pub struct PandaGalleryUpdate {
     pub parent: Option<String>,
     pub visible: Option<bool>,
     pub language: Option<String>,
-    pub file_size: Option<i32>,
+    pub file_size: Option<i64>,
+    pub invisible_reason: Option<Option<String>>,
+    pub newer_gallery_id: Option<Option<i64>>,
+    pub newer_token: Option<Option<String>>,
+    pub checked_date: Option<NaiveDateTime>,
+    pub expunged: Option<bool>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone, Default)]
@@ -130,6 +186,15 @@ pub struct PandaWatchList {
     pub kind: String,
     pub query: Option<String>,
     pub reached_end: bool,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub cover_path: Option<String>,
+    pub mirror_album_id: Option<i32>,
+    pub backfill_offset: Option<String>,
+    pub last_error: Option<String>,
+    pub failure_count: i32,
+    /// Comma-separated languages of the galleries saved to the feed.
+    pub languages: Option<String>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -142,6 +207,9 @@ pub struct NewPandaWatchList {
     pub account_id: i32,
     pub kind: String,
     pub query: Option<String>,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub languages: Option<String>,
 }
 
 #[derive(AsChangeset, Debug, Clone)]
@@ -151,6 +219,8 @@ pub struct PandaWatchListUpdate {
     pub name: Option<String>,
     pub watching: bool,
     pub first_fetch_limit: Option<i32>,
+    pub icon_url: Option<Option<String>>,
+    pub color: Option<Option<String>>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone)]
@@ -162,6 +232,9 @@ pub struct PandaWatchListGallery {
     pub gallery_id: i64,
     pub sort_index: Option<i32>,
     pub stale: bool,
+    pub dismissed: bool,
+    /// When the gallery was favorited, only for favorites feeds.
+    pub favorited_date: Option<NaiveDateTime>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
@@ -5,10 +5,12 @@ use diesel::prelude::*;
 
 use bottle_core::{
     feed::{MediaView, PostView, Scheme, UserView},
+    library::ContentRating,
     Database, Error, Result,
 };
 use panda_client::{
-    FavoriteSearchOption, Gallery, GalleryCategory, GalleryDetail, GalleryPageResult, ImageResult, SearchOption,
+    FavoriteSearchOption, Gallery, GalleryCategory, GalleryDetail, GalleryPageResult, ImagePreview, ImageResult,
+    SearchOption, TagNamespace, ALL_GALLERY_CATEGORIES,
 };
 
 use crate::community::{PandaAccount, PandaGalleryExtra};
@@ -65,6 +67,27 @@ pub(crate) fn tags(gallery: &Gallery) -> Vec<model::PandaTag> {
         .collect()
 }
 
+/// Names of the `artist:` tags of the gallery.
+pub(crate) fn artists(gallery: &Gallery) -> impl Iterator<Item = &str> {
+    gallery
+        .tags
+        .iter()
+        .filter(|t| matches!(t.namespace, TagNamespace::Artist))
+        .map(|t| t.name.as_str())
+}
+
+/// Language of the gallery in lowercase, from its `language:` tags other than `translated` and `rewrite`.
+/// Galleries without a language tag are in Japanese.
+pub(crate) fn gallery_language(gallery: &Gallery) -> String {
+    gallery
+        .tags
+        .iter()
+        .filter(|t| matches!(t.namespace, TagNamespace::Language))
+        .map(|t| t.name.to_lowercase())
+        .find(|name| name != "translated" && name != "rewrite")
+        .unwrap_or("japanese".to_string())
+}
+
 pub(crate) fn gallery_tags(gallery: &Gallery) -> Vec<model::PandaGalleryTag> {
     gallery
         .tags
@@ -80,13 +103,7 @@ pub(crate) fn gallery_tags(gallery: &Gallery) -> Vec<model::PandaGalleryTag> {
 pub(crate) fn media(page: &GalleryPageResult) -> Vec<model::PandaMedia> {
     page.previews
         .iter()
-        .map(|image| model::PandaMedia {
-            gallery_id: page.gallery.gid as i64,
-            media_index: image.index as i32,
-            token: image.token.clone(),
-            thumbnail_url: Some(image.thumbnail_url.clone()),
-            ..Default::default()
-        })
+        .map(|image| model::PandaMedia::from_preview(page.gallery.gid as i64, image))
         .collect()
 }
 
@@ -96,6 +113,8 @@ pub(crate) fn gallery_extra(gallery: &Gallery) -> PandaGalleryExtra {
         category: gallery.category.to_string(),
         uploader: gallery.uploader.clone().unwrap_or_default(),
         rating: gallery.rating,
+        expunged: gallery.expunged,
+        disowned: gallery.disowned,
         ..Default::default()
     }
 }
@@ -127,18 +146,26 @@ impl From<&Gallery> for model::NewPandaGallery {
             rating: gallery.rating,
             media_count: gallery.image_count as i32,
             created_date: gallery.posted_date.naive_utc(),
+            expunged: gallery.expunged,
+            disowned: gallery.disowned,
         }
     }
 }
 
 impl From<&GalleryDetail> for model::PandaGalleryUpdate {
     fn from(gallery: &GalleryDetail) -> Self {
+        let newer_version = gallery.newer_versions.last();
         Self {
             english_title: Some(gallery.english_title.clone()),
             parent: Some(gallery.parent.clone().unwrap_or_default()),
             visible: Some(gallery.visible),
             language: Some(gallery.language.clone()),
-            file_size: Some(gallery.file_size as i32),
+            file_size: Some(gallery.file_size as i64),
+            invisible_reason: Some(gallery.invisible_reason.clone()),
+            newer_gallery_id: Some(newer_version.map(|version| version.gid as i64)),
+            newer_token: Some(newer_version.map(|version| version.token.clone())),
+            checked_date: Some(chrono::Utc::now().naive_utc()),
+            expunged: Some(gallery.invisible_reason.as_deref() == Some("Expunged")),
             ..Default::default()
         }
     }
@@ -150,6 +177,7 @@ impl From<model::PandaAccount> for PandaAccount {
             id: account.id,
             name: account.name,
             username: account.username,
+            favorite_categories: vec![],
         }
     }
 }
@@ -157,30 +185,50 @@ impl From<model::PandaAccount> for PandaAccount {
 impl TryFrom<model::PandaWatchList> for PandaFeed {
     type Error = Error;
     fn try_from(watch_list: model::PandaWatchList) -> Result<PandaFeed> {
+        let languages = watch_list
+            .languages
+            .as_deref()
+            .map(|languages| languages.split(',').map(str::to_string).collect::<Vec<_>>())
+            .unwrap_or_default();
         Ok(PandaFeed {
             id: watch_list.id,
             name: watch_list.name,
             watching: watch_list.watching,
             first_fetch_limit: watch_list.first_fetch_limit,
+            icon_url: watch_list.icon_url,
+            color: watch_list.color,
+            cover_path: watch_list.cover_path,
+            last_error: watch_list.last_error,
+            failure_count: watch_list.failure_count,
+            mirror_album_id: watch_list.mirror_album_id,
+            backfill_offset: watch_list
+                .backfill_offset
+                .as_deref()
+                .map(serde_json::from_str)
+                .transpose()?,
             account_id: watch_list.account_id,
+            favorite_category_name: None,
             params: match watch_list.kind.as_str() {
                 "search" => PandaFeedParams::Search {
                     option: SearchOption::from_str(&watch_list.query.ok_or(Error::ObjectNotComplete(
                         "panda query cannot be null for search feed".to_string(),
                     ))?)
                     .map_err(anyhow::Error::from)?,
+                    languages,
                 },
                 "watched" => PandaFeedParams::Watched {
                     option: SearchOption::from_str(&watch_list.query.ok_or(Error::ObjectNotComplete(
                         "panda query cannot be null for watched feed".to_string(),
                     ))?)
                     .map_err(anyhow::Error::from)?,
+                    languages,
                 },
                 "favorites" => PandaFeedParams::Favorites {
                     option: FavoriteSearchOption::from_str(&watch_list.query.ok_or(Error::ObjectNotComplete(
                         "panda query cannot be null for favorites feed".to_string(),
                     ))?)
                     .map_err(anyhow::Error::from)?,
+                    languages,
                 },
                 _ => Err(Error::UnknownField(format!(
                     "panda watch list kind {}",
@@ -193,6 +241,15 @@ impl TryFrom<model::PandaWatchList> for PandaFeed {
 }
 
 impl model::PandaGallery {
+    /// Map the category to the content rating. Only Non-H galleries are considered general.
+    pub(crate) fn content_rating(&self) -> ContentRating {
+        match GalleryCategory::from_i32(self.category) {
+            GalleryCategory::NonH => ContentRating::General,
+            GalleryCategory::Misc | GalleryCategory::ImageSet | GalleryCategory::Cosplay => ContentRating::Sensitive,
+            _ => ContentRating::Explicit,
+        }
+    }
+
     pub(crate) fn gallery_extra(&self) -> PandaGalleryExtra {
         PandaGalleryExtra {
             token: self.token.clone(),
@@ -202,8 +259,12 @@ impl model::PandaGallery {
             english_title: self.english_title.clone(),
             parent: self.parent.clone(),
             visible: self.visible,
+            invisible_reason: self.invisible_reason.clone(),
+            newer_gallery_id: self.newer_gallery_id,
             language: self.language.clone(),
             file_size: self.file_size,
+            expunged: self.expunged,
+            disowned: self.disowned,
         }
     }
 
@@ -243,6 +304,7 @@ impl From<model::PandaMedia> for MediaView {
             height: media.height,
             thumbnail_url: media.thumbnail_url,
             extra: Some(serde_json::json!({"panda": {"token": media.token}})),
+            ..Default::default()
         }
     }
 }
@@ -251,6 +313,20 @@ impl model::PandaMedia {
     fn id(&self) -> String {
         format!("{}-{}", self.gallery_id, self.media_index)
     }
+
+    /// Media of the preview, with the approximate size until the image is fetched.
+    pub(crate) fn from_preview(gallery_id: i64, preview: &ImagePreview) -> Self {
+        let size = preview.approximate_size();
+        Self {
+            gallery_id,
+            media_index: preview.index as i32,
+            token: preview.token.clone(),
+            thumbnail_url: Some(preview.thumbnail_url.clone()),
+            width: size.map(|(width, _)| width as i32),
+            height: size.map(|(_, height)| height as i32),
+            ..Default::default()
+        }
+    }
 }
 
 impl From<&ImageResult> for model::PandaMediaUpdate {
@@ -271,8 +347,10 @@ impl PandaGalleryExtra {
             english_title: Some(detail.english_title.clone()),
             parent: detail.parent.clone(),
             visible: Some(detail.visible),
+            invisible_reason: detail.invisible_reason.clone(),
+            newer_gallery_id: detail.newer_versions.last().map(|version| version.gid as i64),
             language: Some(detail.language.clone()),
-            file_size: Some(detail.file_size as i32),
+            file_size: Some(detail.file_size as i64),
             ..self.clone()
         }
     }
@@ -286,33 +364,72 @@ impl From<PandaGalleryExtra> for serde_json::Value {
     }
 }
 
+/// Scheme of the search option, with the defaults of `SearchOption::default()`.
 pub(crate) fn search_option_scheme() -> Scheme {
+    let default = serde_json::to_value(SearchOption::default()).unwrap_or_default();
+    let field = |name: &str, label: &str, scheme: Scheme| {
+        let scheme = Scheme::field(label, scheme).with_default(default[name].clone());
+        (name.to_string(), scheme)
+    };
+    // Categories are serialized by their variant names
+    let categories = ALL_GALLERY_CATEGORIES
+        .iter()
+        .map(|category| (format!("{:?}", category), category.to_string()));
+
     Scheme::Object(HashMap::from([
-        ("keyword".to_string(), Scheme::Optional(Box::new(Scheme::String))),
-        ("categories".to_string(), Scheme::Array(Box::new(Scheme::String))),
-        ("search_name".to_string(), Scheme::Bool),
-        ("search_tags".to_string(), Scheme::Bool),
-        ("search_description".to_string(), Scheme::Bool),
-        ("search_torrent".to_string(), Scheme::Bool),
-        ("search_low_power_tags".to_string(), Scheme::Bool),
-        ("search_downvoted_tags".to_string(), Scheme::Bool),
-        ("search_expunged".to_string(), Scheme::Bool),
-        ("require_torrent".to_string(), Scheme::Bool),
-        ("disable_language_filter".to_string(), Scheme::Bool),
-        ("disable_uploader_filter".to_string(), Scheme::Bool),
-        ("disable_tags_filter".to_string(), Scheme::Bool),
-        ("min_rating".to_string(), Scheme::Optional(Box::new(Scheme::Int))),
-        ("min_pages".to_string(), Scheme::Optional(Box::new(Scheme::Int))),
-        ("max_pages".to_string(), Scheme::Optional(Box::new(Scheme::Int))),
+        field("keyword", "Keyword", Scheme::Optional(Box::new(Scheme::String))),
+        field(
+            "categories",
+            "Categories",
+            Scheme::Array(Box::new(Scheme::choices(categories))),
+        ),
+        field("search_name", "Search Gallery Name", Scheme::Bool),
+        field("search_tags", "Search Gallery Tags", Scheme::Bool),
+        field("search_description", "Search Gallery Description", Scheme::Bool),
+        field("search_torrent", "Search Torrent Filenames", Scheme::Bool),
+        field("search_low_power_tags", "Search Low-Power Tags", Scheme::Bool),
+        field("search_downvoted_tags", "Search Downvoted Tags", Scheme::Bool),
+        field("search_expunged", "Show Expunged Galleries", Scheme::Bool),
+        field("require_torrent", "Require Gallery Torrent", Scheme::Bool),
+        field("disable_language_filter", "Disable Language Filter", Scheme::Bool),
+        field("disable_uploader_filter", "Disable Uploader Filter", Scheme::Bool),
+        field("disable_tags_filter", "Disable Tags Filter", Scheme::Bool),
+        field(
+            "min_rating",
+            "Minimum Rating (2 to 5)",
+            Scheme::Optional(Box::new(Scheme::Int)),
+        ),
+        field("min_pages", "Minimum Pages", Scheme::Optional(Box::new(Scheme::Int))),
+        field("max_pages", "Maximum Pages", Scheme::Optional(Box::new(Scheme::Int))),
     ]))
 }
 
+/// Scheme of the languages of the galleries saved to a feed.
+pub(crate) fn languages_scheme() -> Scheme {
+    Scheme::field(
+        "Languages (e.g. english, all if empty)",
+        Scheme::Array(Box::new(Scheme::String)),
+    )
+    .with_default(serde_json::json!([]))
+}
+
+/// Scheme of the favorite search option, with the defaults of `FavoriteSearchOption::default()`.
 pub(crate) fn favorite_search_option_scheme() -> Scheme {
+    let default = serde_json::to_value(FavoriteSearchOption::default()).unwrap_or_default();
+    let field = |name: &str, label: &str, scheme: Scheme| {
+        let scheme = Scheme::field(label, scheme).with_default(default[name].clone());
+        (name.to_string(), scheme)
+    };
+
     Scheme::Object(HashMap::from([
-        ("keyword".to_string(), Scheme::Optional(Box::new(Scheme::String))),
-        ("category_index".to_string(), Scheme::Optional(Box::new(Scheme::Int))),
-        ("search_name".to_string(), Scheme::Bool),
-        ("search_tags".to_string(), Scheme::Bool),
-        ("search_note".to_string(), Scheme::Bool),
+        field("keyword", "Keyword", Scheme::Optional(Box::new(Scheme::String))),
+        field(
+            "category_index",
+            "Favorite Category (0 to 9)",
+            Scheme::Optional(Box::new(Scheme::Int)),
+        ),
+        field("search_name", "Search Gallery Name", Scheme::Bool),
+        field("search_tags", "Search Gallery Tags", Scheme::Bool),
+        field("search_note", "Search Favorite Notes", Scheme::Bool),
     ]))
 }
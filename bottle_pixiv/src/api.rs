@@ -1,7 +1,8 @@
-use bottle_core::{feed::*, Database, Result};
+use bottle_core::{feed::*, Database, Error, Result};
+use pixiv_client::{Illust, IllustType, Restriction};
 
 use crate::cache::PixivCache;
-use crate::community::PixivAccount;
+use crate::community::{AccessToken, PixivAccount};
 use crate::feed::{PixivFeed, PixivFeedParams, PixivFetchContext};
 use crate::util;
 
@@ -14,6 +15,12 @@ fn from_request(db: Database, request: &EndpointRequest<PixivFeedParams>) -> Res
         id: -1, // Temporary feed
         name: None,
         first_fetch_limit: None,
+        icon_url: None,
+        color: None,
+        cover_path: None,
+        last_error: None,
+        failure_count: 0,
+        min_resolution: None,
         watching: false,
         account_id: account.id,
         params: request.params.clone(),
@@ -27,7 +34,6 @@ pub async fn fetch_posts<'a>(
     cache: &'a mut PixivCache,
     request: &EndpointRequest<PixivFeedParams>,
 ) -> Result<EndpointResponse> {
-    use itertools::Itertools;
     use pixiv_client::Paginated;
 
     // 1. Get feed and account
@@ -35,51 +41,115 @@ pub async fn fetch_posts<'a>(
     let account = feed.get_account(db)?;
 
     // 2. Refresh account if expired
-    if account.expired() {
-        tracing::info!("Account expired, refreshing");
-        let credential = account.credential(db)?;
-        let info = PixivAccount::fetch(&credential).await?;
-        let account = account.update(db, &info)?;
-        tracing::info!("{:?}", account);
-    }
-    let auth = account.auth(db)?;
+    let auth = refreshed_auth(db, &account).await?;
 
     // 3. Fetch posts
     let offset = request.offset.as_ref().map(|o| o.parse::<i64>()).transpose()?;
     let mut ctx = PixivFetchContext {
         offset,
         total_fetched: 0,
+        client: auth.as_ref().map(|auth| cache.client(account.id, auth)).transpose()?,
     };
     let result = feed.fetch(&mut ctx, auth.as_ref()).await?;
+    let next_offset = ctx.offset.map(|o| o.to_string());
+    let reached_end = result.next_url().is_none();
+
+    illusts_response(db, cache, result.illusts, next_offset, reached_end)
+}
+
+/// Fetch posts from the normalized endpoint, using the feed params or API of pixiv.
+pub async fn fetch_endpoint<'a>(
+    db: Database<'a>,
+    cache: &'a mut PixivCache,
+    request: &EndpointRequest<Endpoint>,
+) -> Result<EndpointResponse> {
+    if let Endpoint::PostDetail { post_id } = &request.params {
+        let illust_id = Endpoint::parse_id(post_id, "Illust ID")?;
+        let account = PixivAccount::default(db)?;
+        let auth = refreshed_auth(db, &account)
+            .await?
+            .ok_or(Error::NotLoggedIn("Invalid account".to_string()))?;
+        let client = cache.client(account.id, &auth)?;
+        let illust = client.illust(illust_id).await.map_err(anyhow::Error::from)?;
+        return illusts_response(db, cache, vec![illust], None, true);
+    }
+    let request = EndpointRequest {
+        params: endpoint_params(db, &request.params)?,
+        offset: request.offset.clone(),
+    };
+    fetch_posts(db, cache, &request).await
+}
+
+/// Feed params browsed by the normalized endpoint, so that the browsing can be promoted to a feed.
+/// The post detail is not a feed.
+pub fn endpoint_params(db: Database, endpoint: &Endpoint) -> Result<PixivFeedParams> {
+    let params = match endpoint {
+        Endpoint::UserPosts { user_id } => PixivFeedParams::Posts {
+            user_id: Endpoint::parse_id(user_id, "User ID")?,
+            type_: IllustType::Illust,
+        },
+        Endpoint::Likes { user_id } => PixivFeedParams::Bookmarks {
+            user_id: match user_id {
+                Some(user_id) => Endpoint::parse_id(user_id, "User ID")?,
+                None => PixivAccount::default(db)?
+                    .user_id
+                    .ok_or(Error::ObjectNotComplete("User ID of the pixiv account".to_string()))?,
+            },
+            tag: None,
+            restriction: Restriction::Public,
+        },
+        Endpoint::Search { query } => PixivFeedParams::Search {
+            query: query.clone(),
+            sort: Default::default(),
+            search_target: Default::default(),
+            duration: None,
+            min_bookmarks: None,
+        },
+        Endpoint::PostDetail { .. } => return Err(Error::InvalidEndpoint("Feed of a single post".to_string())),
+    };
+    Ok(params)
+}
+
+/// Get the auth of the account, refreshing the account first if expired.
+pub(crate) async fn refreshed_auth(db: Database<'_>, account: &PixivAccount) -> Result<Option<AccessToken>> {
+    if account.expired() {
+        tracing::info!("Account expired, refreshing");
+        let credential = account.credential(db)?;
+        let info = PixivAccount::fetch(&credential).await?;
+        let account = account.update(db, &info)?;
+        tracing::info!("{:?}", account);
+    }
+    account.auth(db)
+}
 
-    // 3.1. Store results in cache
+/// Store the illusts to cache, and prepare the response with their views and associated works.
+fn illusts_response(
+    db: Database,
+    cache: &mut PixivCache,
+    illusts: Vec<Illust>,
+    next_offset: Option<String>,
+    reached_end: bool,
+) -> Result<EndpointResponse> {
+    use itertools::Itertools;
+
+    // 1. Store results in cache
     cache
         .illusts
-        .extend(result.illusts.iter().map(|illust| (illust.id, illust.clone())));
-    tracing::info!("Stored {} pixiv illusts to cache", result.illusts.len());
+        .extend(illusts.iter().map(|illust| (illust.id, illust.clone())));
+    tracing::info!("Stored {} pixiv illusts to cache", illusts.len());
 
-    // 4. Prepare views
-    let posts = result.illusts.iter().map(util::post_view).collect();
-    let users = result
-        .illusts
+    // 2. Prepare views
+    let posts = illusts.iter().map(util::post_view).collect();
+    let users = illusts
         .iter()
         .map(|illust| &illust.user)
         .unique_by(|user| user.id)
         .map(util::user_view)
         .collect();
-    let media = result
-        .illusts
-        .iter()
-        .flat_map(util::media)
-        .map(MediaView::from)
-        .collect();
+    let media = illusts.iter().flat_map(util::media).map(MediaView::from).collect();
 
-    // 5. Get associated works and images
-    let post_ids = result
-        .illusts
-        .iter()
-        .map(|illust| illust.id.to_string())
-        .collect::<Vec<_>>();
+    // 3. Get associated works and images
+    let post_ids = illusts.iter().map(|illust| illust.id.to_string()).collect::<Vec<_>>();
     let (works, images) = bottle_library::get_works_by_post_ids(db, "pixiv", post_ids, false)?;
 
     Ok(EndpointResponse {
@@ -88,8 +158,8 @@ pub async fn fetch_posts<'a>(
         media,
         works,
         images,
-        next_offset: ctx.offset.map(|o| o.to_string()),
-        reached_end: result.next_url().is_none(),
+        next_offset,
+        reached_end,
         total_items: None,
     })
 }
@@ -1,16 +1,54 @@
-use std::collections::HashMap;
+use std::time::Duration;
 
-use pixiv_client::Illust;
+use bottle_core::{
+    cache::{BoundedCache, CacheStats, ClientCache, RequestRecorder},
+    Result,
+};
+use pixiv_client::{Illust, PixivClient};
+
+use crate::AccessToken;
 
 #[derive(Debug, Clone, Default)]
 pub struct PixivCache {
-    pub(crate) illusts: HashMap<u64, Illust>,
+    pub(crate) illusts: BoundedCache<u64, Illust>,
+    pub(crate) clients: ClientCache<PixivClient, AccessToken>,
 }
 
 impl PixivCache {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `capacity` entities of each kind, each for `ttl` at most.
+    pub fn with_limits(capacity: usize, ttl: Duration) -> Self {
         Self {
-            illusts: HashMap::new(),
+            illusts: BoundedCache::new(capacity, ttl),
+            clients: ClientCache::new(),
         }
     }
+
+    pub fn stats(&self) -> Vec<CacheStats> {
+        vec![self.illusts.stats("illusts")]
+    }
+
+    /// Clear the cached entities, keeping the clients of accounts.
+    pub fn clear(&mut self) {
+        self.illusts.clear();
+    }
+
+    /// Get the client of the account, created again when the access token is refreshed or the locale changes.
+    pub fn client(&mut self, account_id: i32, auth: &AccessToken) -> Result<PixivClient> {
+        self.clients.get_or_create(account_id, auth, |auth, hook| {
+            let client = PixivClient::with_locale(&auth.token, auth.locale.as_deref()).map_err(anyhow::Error::from)?;
+            Ok(match hook {
+                Some(hook) => client.with_response_hook(hook),
+                None => client,
+            })
+        })
+    }
+
+    /// Count the requests made by the clients of accounts.
+    pub fn set_request_recorder(&mut self, recorder: RequestRecorder) {
+        self.clients.set_recorder(recorder);
+    }
 }
@@ -2,10 +2,12 @@ use async_trait::async_trait;
 use diesel::prelude::*;
 use serde::Serialize;
 
+use std::collections::HashMap;
+
 use bottle_core::{
     feed::*,
     library::{RemoteImage, RemoteWork},
-    Result,
+    Error, Result,
 };
 use pixiv_client::{LoginResponse, PixivClient};
 
@@ -17,9 +19,13 @@ use crate::util;
 #[derive(Debug, Clone)]
 
 pub struct RefreshToken(pub String);
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 
-pub struct AccessToken(pub String);
+pub struct AccessToken {
+    pub token: String,
+    /// Language of the API responses of the account.
+    pub locale: Option<String>,
+}
 
 pub struct PixivCommunity;
 
@@ -49,6 +55,7 @@ pub struct PixivAccount {
     pub name: Option<String>,
     pub username: Option<String>,
     pub profile_image_url: Option<String>,
+    pub locale: Option<String>,
 }
 
 #[async_trait]
@@ -62,7 +69,10 @@ impl Account for PixivAccount {
         Self: Sized,
     {
         Some(AccountMetadata {
-            credential_scheme: Scheme::String,
+            credential_scheme: Scheme::Object(HashMap::from([(
+                "refresh_token".to_string(),
+                Scheme::field("Refresh Token", Scheme::String),
+            )])),
             can_fetch_info: true,
             need_refresh: true,
         })
@@ -72,6 +82,7 @@ impl Account for PixivAccount {
         AccountView {
             account_id: self.id,
             community: "pixiv".to_string(),
+            favorite_categories: None,
         }
     }
 
@@ -163,11 +174,14 @@ impl Account for PixivAccount {
 
     fn auth(&self, db: Database) -> Result<Option<Self::Auth>> {
         use bottle_core::schema::pixiv_account::dsl::*;
-        let result = pixiv_account
+        let (token, account_locale) = pixiv_account
             .filter(id.eq(self.id))
-            .select(access_token)
-            .first::<Option<String>>(db)?;
-        Ok(result.map(AccessToken))
+            .select((access_token, locale))
+            .first::<(Option<String>, Option<String>)>(db)?;
+        Ok(token.map(|token| AccessToken {
+            token,
+            locale: account_locale,
+        }))
     }
 
     fn credential(&self, db: Database) -> Result<Self::Credential> {
@@ -179,6 +193,12 @@ impl Account for PixivAccount {
         Ok(RefreshToken(result))
     }
 
+    fn parse_credential(value: &serde_json::Value, path: &str) -> Result<Self::Credential> {
+        let scheme = Self::metadata().unwrap().credential_scheme;
+        let [refresh_token] = credential_fields(&scheme, value, path, ["refresh_token"])?;
+        Ok(RefreshToken(refresh_token))
+    }
+
     async fn fetch(credential: &Self::Credential) -> Result<Self::InfoResponse> {
         let result = PixivClient::login(&credential.0).await.map_err(anyhow::Error::from)?;
         Ok(result)
@@ -191,6 +211,27 @@ impl PixivAccount {
         let result = pixiv_account.first::<model::PixivAccount>(db)?;
         Ok(Self::from(result))
     }
+
+    /// Set the language of the API responses of the account, or the default language of pixiv if `None`.
+    pub fn set_locale(&self, db: Database, new_locale: Option<&str>) -> Result<Self> {
+        use bottle_core::schema::pixiv_account::dsl::*;
+        if let Some(new_locale) = new_locale {
+            let valid = !new_locale.is_empty()
+                && new_locale.len() <= 35
+                && new_locale
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(Error::InvalidEndpoint(format!("Locale {}", new_locale)));
+            }
+        }
+        let result = diesel::update(pixiv_account.filter(id.eq(self.id)))
+            .set(locale.eq(new_locale))
+            .returning(model::PixivAccount::as_returning())
+            .get_result::<model::PixivAccount>(db)?;
+        tracing::info!("Set locale of pixiv account {} to {:?}", self.id, new_locale);
+        Ok(Self::from(result))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -272,7 +313,8 @@ impl Post for PixivPost {
             media_count: images.len() as i32,
             images,
             name: Some(self.illust.title.clone()),
-            caption: Some(self.illust.caption.clone()),
+            caption: Some(self.illust.caption_text()),
+            content_rating: Some(self.illust.content_rating()),
         };
 
         bottle_library::add_remote_work(db, &remote_work)
@@ -282,6 +324,9 @@ impl Post for PixivPost {
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct PixivIllustExtra {
     pub title: String,
+    /// Plain text of the caption.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub caption: String,
     #[serde(rename = "type")]
     pub type_: String,
     pub restrict: bool,
@@ -326,3 +371,16 @@ pub fn get_entities(db: Database, post_ids: impl IntoIterator<Item = i64>) -> Re
         ..Default::default()
     })
 }
+
+/// IDs of the saved posts greater than `after_id` in ascending order, to go through all posts in batches.
+pub fn get_post_ids(db: Database, after_id: i64, count: i64) -> Result<Vec<i64>> {
+    use bottle_core::schema::pixiv_illust;
+
+    let post_ids = pixiv_illust::table
+        .filter(pixiv_illust::id.gt(after_id))
+        .order_by(pixiv_illust::id.asc())
+        .select(pixiv_illust::id)
+        .limit(count)
+        .load::<i64>(db)?;
+    Ok(post_ids)
+}
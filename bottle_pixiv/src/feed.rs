@@ -1,16 +1,31 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use std::collections::{HashMap, HashSet};
 
-use bottle_core::{feed::*, Error, Result};
-use pixiv_client::{FollowingRestriction, IllustList, IllustType, Paginated, PixivClient, Restriction};
+use bottle_core::{feed::*, library::WorkFilter, mute::UserMute, Error, Result};
+use pixiv_client::{
+    FollowingRestriction, IllustList, IllustType, Paginated, PixivClient, Restriction, SearchDuration, SearchSort,
+    SearchTarget,
+};
 
 use crate::community::{AccessToken, PixivAccount, RefreshToken};
 use crate::{group, model, util};
 
-#[derive(Debug, Clone, Deserialize)]
+/// Columns of posts for ordering them in feeds and the library.
+const POST_COLUMNS: PostColumns = PostColumns {
+    community: "pixiv",
+    table: "pixiv_illust",
+    post_id: "pixiv_illust.id",
+    created_date: "pixiv_illust.created_date",
+    media_count: "(SELECT COUNT(*) FROM pixiv_media WHERE pixiv_media.illust_id = pixiv_illust.id)",
+    expunged: None,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PixivFeedParams {
     Timeline {
@@ -28,6 +43,16 @@ pub enum PixivFeedParams {
     },
     Search {
         query: String,
+        #[serde(default)]
+        sort: SearchSort,
+        #[serde(default)]
+        search_target: SearchTarget,
+        /// Period before now, or all the time.
+        #[serde(default)]
+        duration: Option<SearchDuration>,
+        /// Minimum bookmarks of the illusts saved to the feed, counted when they are fetched.
+        #[serde(default)]
+        min_bookmarks: Option<u32>,
     },
 }
 
@@ -35,6 +60,16 @@ pub enum PixivFeedParams {
 pub struct PixivFetchContext {
     pub(crate) offset: Option<i64>,
     pub(crate) total_fetched: usize,
+    /// Client of the account from the cache, reused across the pages of an update.
+    #[serde(skip)]
+    pub(crate) client: Option<PixivClient>,
+}
+
+impl PixivFetchContext {
+    /// Use the client cached for the account. Needed before fetching.
+    pub fn set_client(&mut self, client: PixivClient) {
+        self.client = Some(client);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -46,6 +81,17 @@ pub struct PixivFeed {
     pub account_id: i32,
     pub params: PixivFeedParams,
     pub reached_end: bool,
+    pub icon_url: Option<String>,
+    /// Accent color in `#rrggbb`.
+    pub color: Option<String>,
+    /// Collage of the recent works in the library, relative to the image directory.
+    pub cover_path: Option<String>,
+    /// Error of the last update, if it failed.
+    pub last_error: Option<String>,
+    /// Number of consecutive failed updates.
+    pub failure_count: i32,
+    /// Minimum resolution of the largest media of the posts saved, on its longer side.
+    pub min_resolution: Option<i32>,
 }
 
 #[async_trait]
@@ -61,29 +107,100 @@ impl Feed for PixivFeed {
         vec![
             FeedMetadata {
                 name: "timeline".to_string(),
-                scheme: Scheme::Object(HashMap::from([("restriction".to_string(), Scheme::String)])),
+                label: "Following".to_string(),
+                scheme: Scheme::Object(HashMap::from([(
+                    "restriction".to_string(),
+                    Scheme::field(
+                        "Restriction",
+                        Scheme::choices([("Public", "Public"), ("Private", "Private"), ("All", "All")]),
+                    )
+                    .with_default(json!("All")),
+                )])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "bookmarks".to_string(),
+                label: "Bookmarks".to_string(),
                 scheme: Scheme::Object(HashMap::from([
-                    ("user_id".to_string(), Scheme::Bigint),
-                    ("tag".to_string(), Scheme::Optional(Box::new(Scheme::String))),
-                    ("restriction".to_string(), Scheme::String),
+                    ("user_id".to_string(), Scheme::field("User ID", Scheme::Bigint)),
+                    (
+                        "tag".to_string(),
+                        Scheme::field("Tag", Scheme::Optional(Box::new(Scheme::String))).with_default(json!(null)),
+                    ),
+                    (
+                        "restriction".to_string(),
+                        Scheme::field(
+                            "Restriction",
+                            Scheme::choices([("Public", "Public"), ("Private", "Private")]),
+                        )
+                        .with_default(json!("Public")),
+                    ),
                 ])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "posts".to_string(),
+                label: "User Posts".to_string(),
                 scheme: Scheme::Object(HashMap::from([
-                    ("user_id".to_string(), Scheme::Bigint),
-                    ("type".to_string(), Scheme::String),
+                    ("user_id".to_string(), Scheme::field("User ID", Scheme::Bigint)),
+                    (
+                        "type".to_string(),
+                        Scheme::field(
+                            "Type",
+                            Scheme::choices([("Illust", "Illustrations"), ("Manga", "Manga"), ("Ugoira", "Ugoira")]),
+                        )
+                        .with_default(json!("Illust")),
+                    ),
                 ])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "search".to_string(),
-                scheme: Scheme::Object(HashMap::from([("query".to_string(), Scheme::String)])),
+                label: "Search".to_string(),
+                scheme: Scheme::Object(HashMap::from([
+                    ("query".to_string(), Scheme::field("Query", Scheme::String)),
+                    (
+                        "sort".to_string(),
+                        Scheme::field(
+                            "Sort",
+                            Scheme::choices([
+                                ("DateDesc", "Newest"),
+                                ("DateAsc", "Oldest"),
+                                ("PopularDesc", "Popular (Premium)"),
+                            ]),
+                        )
+                        .with_default(json!("DateDesc")),
+                    ),
+                    (
+                        "search_target".to_string(),
+                        Scheme::field(
+                            "Match",
+                            Scheme::choices([
+                                ("Partial", "Tags (Partial)"),
+                                ("Exact", "Tags (Exact)"),
+                                ("Title", "Title and Caption"),
+                            ]),
+                        )
+                        .with_default(json!("Partial")),
+                    ),
+                    (
+                        "duration".to_string(),
+                        Scheme::field(
+                            "Duration",
+                            Scheme::Optional(Box::new(Scheme::choices([
+                                ("WithinLastDay", "Last Day"),
+                                ("WithinLastWeek", "Last Week"),
+                                ("WithinLastMonth", "Last Month"),
+                            ]))),
+                        )
+                        .with_default(json!(null)),
+                    ),
+                    (
+                        "min_bookmarks".to_string(),
+                        Scheme::field("Minimum Bookmarks", Scheme::Optional(Box::new(Scheme::Int)))
+                            .with_default(json!(null)),
+                    ),
+                ])),
                 need_auth: true,
             },
         ]
@@ -95,6 +212,13 @@ impl Feed for PixivFeed {
             community: "pixiv".to_string(),
             name: self.name.clone(),
             watching: self.watching,
+            icon_url: self.icon_url.clone(),
+            color: self.color.clone(),
+            cover_path: self.cover_path.clone(),
+            last_error: self.last_error.clone(),
+            failure_count: self.failure_count,
+            require_media: false,
+            min_resolution: self.min_resolution,
             description: match &self.params {
                 PixivFeedParams::Timeline { restriction } => format!("{} Timeline", restriction),
                 PixivFeedParams::Bookmarks {
@@ -108,7 +232,7 @@ impl Feed for PixivFeed {
                     restriction,
                 } => format!("{} Bookmarks by {}", restriction, user_id),
                 PixivFeedParams::Posts { user_id, type_ } => format!("{} by {}", type_, user_id),
-                PixivFeedParams::Search { query } => format!("Search {}", query),
+                PixivFeedParams::Search { query, .. } => format!("Search {}", query),
             },
         }
     }
@@ -166,6 +290,13 @@ impl Feed for PixivFeed {
             illust_type: params.illust_type(),
             search_query: params.search_query(),
             restriction: params.restriction(),
+            search_sort: params.search_sort(),
+            search_target: params.search_target(),
+            search_duration: params.search_duration(),
+            search_min_bookmarks: params.min_bookmarks().map(|n| n as i32),
+            icon_url: info.icon_url.clone(),
+            color: info.color.clone(),
+            min_resolution: info.min_resolution,
         };
         let result = diesel::insert_into(pixiv_watch_list::table)
             .values(&new_watch_list)
@@ -186,6 +317,9 @@ impl Feed for PixivFeed {
             name: info.name.clone(),
             watching: info.watching,
             first_fetch_limit: info.first_fetch_limit,
+            icon_url: info.icon_change(),
+            color: info.color_change(),
+            min_resolution: info.min_resolution,
         };
         diesel::update(pixiv_watch_list::table.find(self.id))
             .set(&update)
@@ -193,6 +327,13 @@ impl Feed for PixivFeed {
         self.name = info.name.clone();
         self.watching = info.watching;
         self.first_fetch_limit = info.first_fetch_limit;
+        if let Some(icon_url) = info.icon_change() {
+            self.icon_url = icon_url;
+        }
+        if let Some(color) = info.color_change() {
+            self.color = color;
+        }
+        self.min_resolution = info.min_resolution;
         tracing::info!("Modified pixiv feed {}: {:?}", self.id, info);
         Ok(self.view())
     }
@@ -246,6 +387,14 @@ impl Feed for PixivFeed {
             });
         }
 
+        // Skip illusts of muted users, illusts with too few bookmarks, and small illusts by the first pages
+        let muted_user_ids = UserMute::user_ids(db, "pixiv")?;
+        let min_bookmarks = self.params.min_bookmarks().unwrap_or_default();
+        let illusts = illusts
+            .filter(|illust| !muted_user_ids.contains(&illust.user.id.to_string()))
+            .filter(|illust| illust.total_bookmarks >= min_bookmarks)
+            .filter(|illust| meets_min_resolution(self.min_resolution, [(illust.width, illust.height)]));
+
         // 2. Prepare data for insertion
         // User, Illust, Media, Tag
         let new_users = illusts
@@ -265,6 +414,7 @@ impl Feed for PixivFeed {
                 private_bookmark: self.params.is_private_bookmark(),
                 stale: false,
                 sort_index: None,
+                dismissed: false,
             })
             .collect::<Vec<_>>();
 
@@ -326,25 +476,13 @@ impl Feed for PixivFeed {
     }
 
     fn handle_before_update(&self, db: Database) -> Result<()> {
-        use bottle_core::schema::pixiv_watch_list_illust::dsl::*;
         use itertools::Itertools;
 
         // Delete watch list posts that don't have sort index
-        let post_ids = pixiv_watch_list_illust
-            .filter(watch_list_id.eq(self.id))
-            .filter(sort_index.is_null())
-            .select(illust_id)
-            .load::<i64>(db)?;
+        let post_ids = bottle_library::delete_unsorted_watch_list_posts(db, "pixiv", Some(self.id))?;
         if post_ids.is_empty() {
             return Ok(());
         }
-
-        diesel::delete(
-            pixiv_watch_list_illust
-                .filter(watch_list_id.eq(self.id))
-                .filter(sort_index.is_null()),
-        )
-        .execute(db)?;
         tracing::info!(
             "Deleted {} posts without sort index from pixiv feed {}: {}",
             post_ids.len(),
@@ -399,15 +537,31 @@ impl Feed for PixivFeed {
         Ok(())
     }
 
-    fn posts(&self, db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn posts(
+        &self,
+        db: Database,
+        filter: &PostFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{pixiv_illust, pixiv_media, pixiv_user, pixiv_watch_list_illust};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{
+            dsl::sql,
+            sql_types::{Bool, Integer},
+        };
 
         // 1. Fetch posts
         let (posts, total_items) = pixiv_watch_list_illust::table
             .inner_join(pixiv_illust::table)
             .filter(pixiv_watch_list_illust::watch_list_id.eq(self.id))
-            .order(pixiv_watch_list_illust::sort_index.desc())
+            .filter(sql::<Bool>(
+                &filter.sql_condition(&POST_COLUMNS, "pixiv_watch_list_illust"),
+            ))
+            .order(sql::<Integer>(
+                &order.feed_sql(&POST_COLUMNS, "pixiv_watch_list_illust.sort_index"),
+            ))
             .select(pixiv_illust::all_columns)
             .paginate(page, page_size)
             .load_and_count::<model::PixivIllust>(db)?;
@@ -441,16 +595,33 @@ impl Feed for PixivFeed {
             media: Some(media.into_iter().map(MediaView::from).collect()),
             works: Some(works),
             images: Some(images),
+            notes: None,
             total_items,
             page,
             page_size,
         })
     }
 
+    fn set_dismissed(&self, db: Database, post_ids: &[i64], dismissed: bool) -> Result<usize> {
+        use bottle_core::schema::pixiv_watch_list_illust;
+
+        let count = diesel::update(pixiv_watch_list_illust::table)
+            .filter(pixiv_watch_list_illust::watch_list_id.eq(self.id))
+            .filter(pixiv_watch_list_illust::illust_id.eq_any(post_ids))
+            .set(pixiv_watch_list_illust::dismissed.eq(dismissed))
+            .execute(db)?;
+        Ok(count)
+    }
+
     fn get_account(&self, db: Database) -> Result<Self::Account> {
         PixivAccount::get(db, self.account_id)?.ok_or(Error::NotLoggedIn("Invalid account".to_string()))
     }
 
+    /// Only bookmarks continue from the last bookmark ID. Others always start from the newest posts.
+    fn resumable(&self) -> bool {
+        matches!(self.params, PixivFeedParams::Bookmarks { .. })
+    }
+
     fn get_fetch_context(&self, db: Database) -> Result<Self::FetchContext> {
         let last_bookmark_id = matches!(self.params, PixivFeedParams::Bookmarks { .. })
             .then_some(self.last_bookmark_id(db)?)
@@ -458,14 +629,19 @@ impl Feed for PixivFeed {
         Ok(PixivFetchContext {
             offset: last_bookmark_id,
             total_fetched: 0,
+            client: None,
         })
     }
 
     async fn fetch(&self, ctx: &mut Self::FetchContext, auth: Option<&Self::Auth>) -> Result<Self::FetchResult> {
-        let Some(auth) = auth else {
+        if auth.is_none() {
             return Err(Error::NotLoggedIn("Pixiv feed needs an account".to_string()));
-        };
-        let client = PixivClient::new(&auth.0).map_err(anyhow::Error::from)?;
+        }
+        // The client is set from the cache of the account when the context is prepared
+        if ctx.client.is_none() {
+            return Err(Error::NotLoggedIn("Pixiv feed needs a cached client".to_string()));
+        }
+        let client = ctx.client.as_ref().unwrap();
 
         let offset = ctx.offset;
         let result = match self.params.clone() {
@@ -501,16 +677,42 @@ impl Feed for PixivFeed {
         Ok(result)
     }
 
-    fn archived_posts(db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    async fn fetch_name(&self, ctx: &Self::FetchContext) -> Result<Option<FeedName>> {
+        let (Some(client), Some(user_id)) = (ctx.client.as_ref(), self.params.user_id()) else {
+            return Ok(None);
+        };
+        let detail = client.user(user_id as u64).await.map_err(anyhow::Error::from)?;
+        let name = match self.params {
+            PixivFeedParams::Bookmarks { .. } => format!("Bookmarks by {}", detail.user.name),
+            _ => format!("Posts by {}", detail.user.name),
+        };
+        Ok(Some(FeedName {
+            name,
+            avatar_url: detail.user.profile_image_urls.medium,
+        }))
+    }
+
+    fn archived_posts(
+        db: Database,
+        filter: &WorkFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::library::{ImageView, WorkView};
         use bottle_core::schema::{image, pixiv_illust, pixiv_media, pixiv_user, work};
         use bottle_library::model::{Image, Work};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{
+            dsl::sql,
+            sql_types::{Bool, Integer},
+        };
 
         // 1. Fetch works
         let (works, total_items) = work::table
             .filter(work::source.eq("pixiv"))
-            .order(work::added_date.desc())
+            .filter(sql::<Bool>(&filter.sql_condition()))
+            .order(sql::<Integer>(&order.work_sql(&POST_COLUMNS)))
             .paginate(page, page_size)
             .load_and_count::<Work>(db)?;
 
@@ -555,6 +757,7 @@ impl Feed for PixivFeed {
             media: Some(media.into_iter().map(MediaView::from).collect()),
             works: Some(works),
             images: Some(images.into_iter().map(ImageView::from).collect()),
+            notes: None,
             total_items,
             page,
             page_size,
@@ -563,30 +766,42 @@ impl Feed for PixivFeed {
 
     fn archived_posts_grouped_by_user(
         db: Database,
+        filter: &WorkFilter,
         page: i64,
         page_size: i64,
         recent_count: i64,
     ) -> Result<GeneralResponse> {
         use diesel::dsl::sql_query;
         let query = sql_query(group::grouped_by_user_query(
-            "select distinct pixiv_illust.* from pixiv_illust
-            join work on pixiv_illust.id = work.post_id_int
-            where work.source = 'pixiv'",
+            &format!(
+                "select distinct pixiv_illust.* from pixiv_illust
+                join work on pixiv_illust.id = work.post_id_int
+                where work.source = 'pixiv' and {}",
+                filter.sql_condition()
+            ),
             "order by created_date desc",
         ))
         .into_boxed();
         group::posts_grouped_by_user(db, query, page, page_size, recent_count, true)
     }
 
-    fn archived_posts_by_user(db: Database, user_id: String, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn archived_posts_by_user(
+        db: Database,
+        user_id: String,
+        filter: &WorkFilter,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{pixiv_illust, work};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{dsl::sql, sql_types::Bool};
 
         let user_id = user_id.parse::<i64>()?;
         let results = pixiv_illust::table
             .inner_join(work::table.on(work::post_id_int.eq(pixiv_illust::id.nullable())))
             .filter(pixiv_illust::user_id.eq(user_id))
             .filter(work::source.eq("pixiv"))
+            .filter(sql::<Bool>(&filter.sql_condition()))
             .order(pixiv_illust::created_date.desc())
             .select(pixiv_illust::all_columns)
             .distinct()
@@ -629,6 +844,18 @@ impl Feed for PixivFeed {
             .load_and_count::<model::PixivIllust>(db)?;
         group::posts_by_user(db, results, user_id, page, page_size, false)
     }
+
+    fn new_post_count(&self, db: Database, since: DateTime<Utc>) -> Result<i64> {
+        use bottle_core::schema::{pixiv_illust, pixiv_watch_list_illust};
+
+        let count = pixiv_watch_list_illust::table
+            .inner_join(pixiv_illust::table)
+            .filter(pixiv_watch_list_illust::watch_list_id.eq(self.id))
+            .filter(pixiv_illust::added_date.gt(since.naive_utc()))
+            .count()
+            .get_result::<i64>(db)?;
+        Ok(count)
+    }
 }
 
 // MARK: Helpers
@@ -657,6 +884,31 @@ impl PixivFeedParams {
         }
     }
 
+    /// Errors of the fields in the params, by paths like `posts.user_id`.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let kind = self.kind_str();
+        let mut errors = Vec::new();
+        if let Some(user_id) = self.user_id().filter(|id| *id <= 0) {
+            errors.push(FieldError::new(
+                format!("{}.user_id", kind),
+                format!("Invalid user ID {}", user_id),
+            ));
+        }
+        match self {
+            Self::Bookmarks { tag: Some(tag), .. } if tag.trim().is_empty() => {
+                errors.push(FieldError::new(
+                    format!("{}.tag", kind),
+                    "Empty tag, use null for all bookmarks",
+                ));
+            }
+            Self::Search { query, .. } if query.trim().is_empty() => {
+                errors.push(FieldError::new(format!("{}.query", kind), "Empty query"));
+            }
+            _ => {}
+        }
+        errors
+    }
+
     fn is_private_bookmark(&self) -> bool {
         matches!(
             self,
@@ -699,7 +951,36 @@ impl PixivFeedParams {
 
     fn search_query(&self) -> Option<String> {
         match self {
-            Self::Search { query } => Some(query.clone()),
+            Self::Search { query, .. } => Some(query.clone()),
+            _ => None,
+        }
+    }
+
+    fn search_sort(&self) -> Option<String> {
+        match self {
+            Self::Search { sort, .. } => Some(sort.to_string()),
+            _ => None,
+        }
+    }
+
+    fn search_target(&self) -> Option<String> {
+        match self {
+            Self::Search { search_target, .. } => Some(search_target.to_string()),
+            _ => None,
+        }
+    }
+
+    fn search_duration(&self) -> Option<String> {
+        match self {
+            Self::Search { duration, .. } => duration.as_ref().map(|d| d.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Minimum bookmarks of the illusts saved to the feed, only for searches.
+    pub fn min_bookmarks(&self) -> Option<u32> {
+        match self {
+            Self::Search { min_bookmarks, .. } => *min_bookmarks,
             _ => None,
         }
     }
@@ -111,7 +111,7 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         user.post_count = user_post_counts.get(&user.user_id).cloned();
     }
     // Sort users by post_count
-    users.sort_by(|a, b| b.post_count.cmp(&a.post_count));
+    users.sort_by_key(|u| std::cmp::Reverse(u.post_count));
 
     // 3. Fetch associated posts
     let posts = pixiv_illust::table
@@ -152,6 +152,7 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         media: Some(media.into_iter().map(MediaView::from).collect()),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items: user_count,
         page,
         page_size,
@@ -203,6 +204,7 @@ pub(crate) fn posts_by_user(
         media: Some(media.into_iter().map(MediaView::from).collect()),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items,
         page,
         page_size,
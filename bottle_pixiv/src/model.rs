@@ -16,6 +16,8 @@ pub struct PixivUser {
     pub pawoo_url: Option<String>,
     pub twitter_username: Option<String>,
     pub added_date: NaiveDateTime,
+    /// When the full profile was last fetched, as users saved from posts have only their names and avatars.
+    pub profile_updated_date: Option<NaiveDateTime>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -32,6 +34,21 @@ pub struct NewPixivUser {
     pub twitter_username: Option<String>,
 }
 
+#[derive(AsChangeset, Debug, Clone)]
+#[diesel(table_name = pixiv_user)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct PixivUserProfileUpdate {
+    pub name: String,
+    pub username: String,
+    pub profile_image_url: Option<String>,
+    pub description: String,
+    pub url: Option<String>,
+    pub pawoo_url: Option<String>,
+    pub twitter_username: Option<String>,
+    pub profile_updated_date: Option<NaiveDateTime>,
+}
+
 #[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone)]
 #[diesel(table_name = pixiv_account)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -44,6 +61,8 @@ pub struct PixivAccount {
     pub name: Option<String>,
     pub username: Option<String>,
     pub profile_image_url: Option<String>,
+    /// Language of the API responses, like tag translations, e.g. `en` or `zh-CN`.
+    pub locale: Option<String>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -83,6 +102,8 @@ pub struct PixivIllust {
     pub thumbnail_url: String,
     pub created_date: NaiveDateTime,
     pub added_date: NaiveDateTime,
+    /// Plain text of the caption in HTML.
+    pub caption_text: Option<String>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -100,6 +121,7 @@ pub struct NewPixivIllust {
     pub series_title: Option<String>,
     pub thumbnail_url: String,
     pub created_date: NaiveDateTime,
+    pub caption_text: Option<String>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Debug, Clone)]
@@ -145,6 +167,16 @@ pub struct PixivWatchList {
     pub bookmark_tag: Option<String>,
     pub illust_type: Option<String>,
     pub reached_end: bool,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub cover_path: Option<String>,
+    pub search_sort: Option<String>,
+    pub search_target: Option<String>,
+    pub search_duration: Option<String>,
+    pub last_error: Option<String>,
+    pub failure_count: i32,
+    pub search_min_bookmarks: Option<i32>,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -161,6 +193,13 @@ pub struct NewPixivWatchList {
     pub search_query: Option<String>,
     pub bookmark_tag: Option<String>,
     pub illust_type: Option<String>,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub search_sort: Option<String>,
+    pub search_target: Option<String>,
+    pub search_duration: Option<String>,
+    pub search_min_bookmarks: Option<i32>,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(AsChangeset, Debug, Clone)]
@@ -170,6 +209,9 @@ pub struct PixivWatchListUpdate {
     pub name: Option<String>,
     pub watching: bool,
     pub first_fetch_limit: Option<i32>,
+    pub icon_url: Option<Option<String>>,
+    pub color: Option<Option<String>>,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Debug, Clone)]
@@ -183,6 +225,7 @@ pub struct PixivWatchListIllust {
     pub private_bookmark: bool,
     pub stale: bool,
     pub sort_index: Option<i32>,
+    pub dismissed: bool,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
@@ -0,0 +1,57 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use pixiv_client::PixivClient;
+
+use bottle_core::{Database, Error, Result};
+
+use crate::api::refreshed_auth;
+use crate::cache::PixivCache;
+use crate::community::PixivAccount;
+use crate::model;
+
+/// Users of the archived works whose profiles are never fetched or fetched before the date, oldest first.
+pub fn profile_tasks(db: Database, updated_before: NaiveDateTime, limit: i64) -> Result<Vec<i64>> {
+    use bottle_core::schema::{pixiv_illust, pixiv_user, work};
+
+    let user_ids = pixiv_user::table
+        .inner_join(pixiv_illust::table)
+        .inner_join(work::table.on(work::post_id_int.eq(pixiv_illust::id.nullable())))
+        .filter(work::source.eq("pixiv"))
+        .filter(
+            pixiv_user::profile_updated_date
+                .is_null()
+                .or(pixiv_user::profile_updated_date.lt(updated_before)),
+        )
+        .select(pixiv_user::id)
+        .distinct()
+        .order(pixiv_user::profile_updated_date.asc())
+        .limit(limit)
+        .load::<i64>(db)?;
+    Ok(user_ids)
+}
+
+/// Client of the default account to fetch profiles with, or None if there is no account.
+pub async fn profile_client(db: Database<'_>, cache: &mut PixivCache) -> Result<Option<PixivClient>> {
+    let account = match PixivAccount::default(db) {
+        Ok(account) => account,
+        Err(Error::DatabaseError(diesel::result::Error::NotFound)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let Some(auth) = refreshed_auth(db, &account).await? else {
+        return Ok(None);
+    };
+    Ok(Some(cache.client(account.id, &auth)?))
+}
+
+/// Fetch the full profile of the user, which the posts don't include, and fill the description and URLs.
+pub async fn fetch_profile(db: Database<'_>, client: &PixivClient, user_id: i64) -> Result<()> {
+    use bottle_core::schema::pixiv_user;
+
+    let detail = client.user(user_id as u64).await.map_err(anyhow::Error::from)?;
+    let update = model::PixivUserProfileUpdate::from(&detail);
+    diesel::update(pixiv_user::table.find(user_id))
+        .set(&update)
+        .execute(db)?;
+    tracing::info!("Updated profile of pixiv user {}", user_id);
+    Ok(())
+}
@@ -9,10 +9,12 @@ use crate::{model, PixivIllustExtra};
 
 use bottle_core::{
     feed::{MediaView, PostView, UserView},
-    library::{RemoteImage, RemoteWork},
+    library::{ContentRating, RemoteImage, RemoteWork},
     Database, Error, Result,
 };
-use pixiv_client::{self as client, FollowingRestriction, IllustType, Restriction};
+use pixiv_client::{
+    self as client, FollowingRestriction, IllustType, Restriction, SearchDuration, SearchSort, SearchTarget,
+};
 
 pub(crate) fn get_tag_map(db: Database, post_ids: impl IntoIterator<Item = i64>) -> Result<HashMap<i64, Vec<String>>> {
     use bottle_core::schema::pixiv_illust_tag;
@@ -32,6 +34,22 @@ pub(crate) fn get_tag_map(db: Database, post_ids: impl IntoIterator<Item = i64>)
     Ok(tag_map)
 }
 
+impl From<&client::UserDetail> for model::PixivUserProfileUpdate {
+    fn from(detail: &client::UserDetail) -> Self {
+        let user = &detail.user;
+        Self {
+            name: user.name.clone(),
+            username: user.username.clone(),
+            profile_image_url: user.profile_image_urls.medium.clone(),
+            description: user.comment.clone().unwrap_or_default(),
+            url: detail.profile.webpage.clone().filter(|url| !url.is_empty()),
+            pawoo_url: detail.profile.pawoo_url.clone().filter(|url| !url.is_empty()),
+            twitter_username: Some(detail.profile.twitter_account.clone()).filter(|name| !name.is_empty()),
+            profile_updated_date: Some(chrono::Utc::now().naive_utc()),
+        }
+    }
+}
+
 impl From<&client::User> for model::NewPixivUser {
     fn from(user: &client::User) -> Self {
         Self {
@@ -72,6 +90,7 @@ impl From<&client::Illust> for model::NewPixivIllust {
             series_title: illust.series.as_ref().map(|s| s.title.clone()),
             thumbnail_url: illust.image_urls.large.clone(),
             created_date: illust.create_date.naive_utc(),
+            caption_text: Some(bottle_util::text::html_to_text(&illust.caption)),
         }
     }
 }
@@ -79,6 +98,7 @@ impl From<&client::Illust> for model::NewPixivIllust {
 pub(crate) fn illust_extra(illust: &client::Illust) -> PixivIllustExtra {
     PixivIllustExtra {
         title: illust.title.clone(),
+        caption: bottle_util::text::html_to_text(&illust.caption),
         type_: illust.type_.clone(),
         restrict: illust.restrict > 0,
         sanity_level: illust.sanity_level as i32,
@@ -153,6 +173,7 @@ impl From<model::PixivAccount> for PixivAccount {
             name: account.name,
             username: account.username,
             profile_image_url: account.profile_image_url,
+            locale: account.locale,
         }
     }
 }
@@ -164,6 +185,12 @@ impl TryFrom<model::PixivWatchList> for PixivFeed {
             id: watch_list.id,
             name: watch_list.name,
             first_fetch_limit: watch_list.first_fetch_limit,
+            icon_url: watch_list.icon_url,
+            color: watch_list.color,
+            cover_path: watch_list.cover_path,
+            last_error: watch_list.last_error,
+            failure_count: watch_list.failure_count,
+            min_resolution: watch_list.min_resolution,
             watching: watch_list.watching,
             account_id: watch_list.account_id,
             params: match watch_list.kind.as_str() {
@@ -196,6 +223,24 @@ impl TryFrom<model::PixivWatchList> for PixivFeed {
                     query: watch_list.search_query.ok_or(Error::ObjectNotComplete(
                         "pixiv search query cannot be null for search feed".to_string(),
                     ))?,
+                    sort: watch_list
+                        .search_sort
+                        .map(|s| SearchSort::from_str(&s))
+                        .transpose()
+                        .map_err(anyhow::Error::from)?
+                        .unwrap_or_default(),
+                    search_target: watch_list
+                        .search_target
+                        .map(|s| SearchTarget::from_str(&s))
+                        .transpose()
+                        .map_err(anyhow::Error::from)?
+                        .unwrap_or_default(),
+                    duration: watch_list
+                        .search_duration
+                        .map(|s| SearchDuration::from_str(&s))
+                        .transpose()
+                        .map_err(anyhow::Error::from)?,
+                    min_bookmarks: watch_list.search_min_bookmarks.map(|n| n as u32),
                 },
                 _ => Err(Error::UnknownField(format!(
                     "pixiv watch list kind {}",
@@ -223,9 +268,17 @@ impl From<model::PixivUser> for UserView {
 }
 
 impl model::PixivIllust {
+    /// Plain text of the caption, or converted from HTML if saved before it was stored.
+    pub(crate) fn caption_text(&self) -> String {
+        self.caption_text
+            .clone()
+            .unwrap_or_else(|| bottle_util::text::html_to_text(&self.caption))
+    }
+
     pub(crate) fn illust_extra(&self) -> PixivIllustExtra {
         PixivIllustExtra {
             title: self.title.clone(),
+            caption: self.caption_text(),
             type_: self.type_.clone(),
             restrict: self.restrict,
             sanity_level: self.sanity_level,
@@ -234,6 +287,16 @@ impl model::PixivIllust {
         }
     }
 
+    /// Map the sanity level to the content rating. Pixiv marks R-18 works with level 6 and above.
+    /// `restrict` is about the visibility of the illust, which is not taken into account.
+    pub(crate) fn content_rating(&self) -> ContentRating {
+        match self.sanity_level {
+            level if level >= 6 => ContentRating::Explicit,
+            level if level >= 4 => ContentRating::Sensitive,
+            _ => ContentRating::General,
+        }
+    }
+
     pub fn post_view(&self, tags: Vec<String>) -> PostView {
         PostView {
             post_id: self.id.to_string(),
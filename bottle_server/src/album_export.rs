@@ -0,0 +1,582 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Cursor, Seek, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use image::{codecs::jpeg::JpegDecoder, ColorType, ImageDecoder};
+use serde::Serialize;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use bottle_core::{
+    library::{AlbumView, WorkFilter, WorkView},
+    Database, Error as BottleError, Result as BottleResult,
+};
+use bottle_library::Album;
+
+/// Albums with more images than this are exported by a background job instead of in the request.
+pub const ALBUM_EXPORT_SYNC_MAX_IMAGES: usize = 100;
+/// Quality of images re-encoded to JPEG for PDF pages.
+const PDF_JPEG_QUALITY: u8 = 90;
+/// Largest side of a PDF page in points, which PDF readers are required to support.
+/// Pages are sized by the pixels of their images, scaled down to fit within this.
+const PDF_MAX_PAGE_SIZE: f64 = 14400.0;
+
+/// Format of a packaged album.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlbumExportFormat {
+    /// Comic book archive, a ZIP of the images with a `ComicInfo.xml` bookmarking each work.
+    Cbz,
+    /// PDF with a page for each image and an outline entry for each work.
+    Pdf,
+}
+
+impl AlbumExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AlbumExportFormat::Cbz => "cbz",
+            AlbumExportFormat::Pdf => "pdf",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AlbumExportFormat::Cbz => "application/vnd.comicbook+zip",
+            AlbumExportFormat::Pdf => "application/pdf",
+        }
+    }
+}
+
+impl FromStr for AlbumExportFormat {
+    type Err = BottleError;
+
+    fn from_str(s: &str) -> BottleResult<Self> {
+        match s {
+            "cbz" => Ok(AlbumExportFormat::Cbz),
+            "pdf" => Ok(AlbumExportFormat::Pdf),
+            _ => Err(BottleError::InvalidEndpoint(format!("Export format {}", s))),
+        }
+    }
+}
+
+/// Downloaded images of an album in order, with the index of the first page of each work as the table of contents.
+#[derive(Debug, Clone)]
+pub struct AlbumPages {
+    pub album: AlbumView,
+    /// Paths of the images relative to the image directory.
+    pub paths: Vec<String>,
+    /// Title of each work and the index of its first page.
+    pub contents: Vec<(String, usize)>,
+    /// Number of videos left out since they have no poster frame.
+    pub skipped_videos: usize,
+}
+
+impl AlbumPages {
+    /// Collect the downloaded images of the works in the album, in the order of the album.
+    /// Videos are exported as their poster frames, which are saved as their thumbnails.
+    pub fn load(db: Database, album_id: i32) -> BottleResult<Self> {
+        let album = Album::all(db)?
+            .into_iter()
+            .find(|album| album.id == album_id)
+            .ok_or(BottleError::ObjectNotFound(format!("Album {}", album_id)))?;
+        let response = Album::works(db, album_id, &WorkFilter::default(), 0, i64::MAX)?;
+        let images = response.images.unwrap_or_default();
+
+        let mut paths = Vec::new();
+        let mut contents = Vec::new();
+        let mut skipped_videos = 0;
+        for work in response.works.unwrap_or_default() {
+            let mut work_paths = Vec::new();
+            for image in images.iter().filter(|image| image.work_id == work.id) {
+                let Some(path) = &image.path else {
+                    continue;
+                };
+                if !bottle_download::is_video(path) {
+                    work_paths.push(path.clone());
+                } else if let Some(poster) = &image.thumbnail_path {
+                    work_paths.push(poster.clone());
+                } else {
+                    tracing::warn!("Skipped video {} without poster frame in album export", path);
+                    skipped_videos += 1;
+                }
+            }
+            if work_paths.is_empty() {
+                continue;
+            }
+            contents.push((work_title(&work), paths.len()));
+            paths.extend(work_paths);
+        }
+        Ok(Self {
+            album,
+            paths,
+            contents,
+            skipped_videos,
+        })
+    }
+}
+
+fn work_title(work: &WorkView) -> String {
+    if let Some(name) = work.name.as_ref().filter(|name| !name.trim().is_empty()) {
+        return name.trim().to_string();
+    }
+    match (&work.community, &work.post_id) {
+        (Some(community), Some(post_id)) => format!("{} {}", community, post_id),
+        _ => format!("Work {}", work.id),
+    }
+}
+
+/// Package the pages into the writer. `on_page` is called with the number of pages processed so far.
+pub fn write_album(
+    pages: &AlbumPages,
+    image_dir: &Path,
+    format: AlbumExportFormat,
+    out: impl Write + Seek,
+    on_page: impl FnMut(usize),
+) -> BottleResult<()> {
+    match format {
+        AlbumExportFormat::Cbz => write_cbz(pages, image_dir, out, on_page),
+        AlbumExportFormat::Pdf => write_pdf(pages, image_dir, out, on_page),
+    }
+}
+
+// MARK: CBZ
+
+fn write_cbz(
+    pages: &AlbumPages,
+    image_dir: &Path,
+    out: impl Write + Seek,
+    mut on_page: impl FnMut(usize),
+) -> BottleResult<()> {
+    let mut zip = StoredZip(ZipWriter::new(out));
+    let mut written_pages = Vec::new();
+    for (index, path) in pages.paths.iter().enumerate() {
+        let data = match fs::read(image_dir.join(path)) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!("Skipped missing image {} of album export: {}", path, e);
+                on_page(index + 1);
+                continue;
+            }
+        };
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("jpg")
+            .to_lowercase();
+        let name = format!("{:05}.{}", written_pages.len() + 1, extension);
+        zip.add(&name, &data)?;
+        written_pages.push(index);
+        on_page(index + 1);
+    }
+
+    // Table of contents as bookmarks of the pages, with indices of the pages actually written
+    let mut info = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ComicInfo>\n");
+    let _ = writeln!(info, "  <Title>{}</Title>", xml_escape(&pages.album.name));
+    let _ = writeln!(info, "  <PageCount>{}</PageCount>", written_pages.len());
+    info.push_str("  <Pages>\n");
+    for (written, index) in written_pages.iter().enumerate() {
+        let previous = written.checked_sub(1).map(|i| written_pages[i] + 1).unwrap_or(0);
+        // Bookmark of a work falls on its first page written, in case its leading images are missing
+        match pages
+            .contents
+            .iter()
+            .find(|(_, first)| (previous..=*index).contains(first))
+        {
+            Some((title, _)) => {
+                let _ = writeln!(
+                    info,
+                    "    <Page Image=\"{}\" Bookmark=\"{}\" />",
+                    written,
+                    xml_escape(title)
+                );
+            }
+            None => {
+                let _ = writeln!(info, "    <Page Image=\"{}\" />", written);
+            }
+        }
+    }
+    info.push_str("  </Pages>\n</ComicInfo>\n");
+    zip.add("ComicInfo.xml", info.as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// ZIP archive without compression, since images are compressed already.
+/// ZIP64 records are written when the album exceeds the limits of a plain ZIP archive.
+struct StoredZip<W: Write + Seek>(ZipWriter<W>);
+
+impl<W: Write + Seek> StoredZip<W> {
+    fn add(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .large_file(data.len() as u64 >= u32::MAX as u64);
+        self.0.start_file(name, options)?;
+        self.0.write_all(data)
+    }
+
+    fn finish(self) -> io::Result<()> {
+        self.0.finish()?.flush()
+    }
+}
+
+// MARK: PDF
+
+/// A JPEG image to embed as is in a PDF page.
+struct PdfImage {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    gray: bool,
+}
+
+impl PdfImage {
+    /// JPEG files are embedded as is, and other images are re-encoded to JPEG.
+    fn load(path: &Path) -> BottleResult<Self> {
+        let data = fs::read(path)?;
+        if data.starts_with(&[0xff, 0xd8]) {
+            if let Ok(decoder) = JpegDecoder::new(Cursor::new(&data)) {
+                let (width, height) = decoder.dimensions();
+                let color_type = decoder.color_type();
+                if matches!(color_type, ColorType::L8 | ColorType::Rgb8) {
+                    return Ok(Self {
+                        data,
+                        width,
+                        height,
+                        gray: color_type == ColorType::L8,
+                    });
+                }
+            }
+        }
+
+        let image = image::load_from_memory(&data)
+            .map_err(|e| BottleError::InvalidEndpoint(format!("Image {}: {}", path.display(), e)))?
+            .to_rgb8();
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, PDF_JPEG_QUALITY)
+            .encode_image(&image)
+            .map_err(|e| BottleError::InvalidEndpoint(format!("Image {}: {}", path.display(), e)))?;
+        Ok(Self {
+            data: jpeg,
+            width: image.width(),
+            height: image.height(),
+            gray: false,
+        })
+    }
+}
+
+/// Writer of PDF objects, tracking their offsets for the cross-reference table.
+struct PdfWriter<W: Write> {
+    out: W,
+    offset: usize,
+    /// Offset of each object by its number minus one, filled when the object is written.
+    offsets: Vec<usize>,
+}
+
+impl<W: Write> PdfWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.out.write_all(data)?;
+        self.offset += data.len();
+        Ok(())
+    }
+
+    /// Reserve the number of a new object to write later.
+    fn reserve(&mut self) -> usize {
+        self.offsets.push(0);
+        self.offsets.len()
+    }
+
+    fn object(&mut self, number: usize, body: &str) -> io::Result<()> {
+        self.offsets[number - 1] = self.offset;
+        self.write(format!("{} 0 obj\n{}\nendobj\n", number, body).as_bytes())
+    }
+
+    fn stream(&mut self, number: usize, dictionary: &str, data: &[u8]) -> io::Result<()> {
+        self.offsets[number - 1] = self.offset;
+        let head = format!(
+            "{} 0 obj\n<< {} /Length {} >>\nstream\n",
+            number,
+            dictionary,
+            data.len()
+        );
+        self.write(head.as_bytes())?;
+        self.write(data)?;
+        self.write(b"\nendstream\nendobj\n")
+    }
+}
+
+fn write_pdf(
+    pages: &AlbumPages,
+    image_dir: &Path,
+    out: impl Write + Seek,
+    mut on_page: impl FnMut(usize),
+) -> BottleResult<()> {
+    let mut pdf = PdfWriter {
+        out,
+        offset: 0,
+        offsets: Vec::new(),
+    };
+    pdf.write(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n")?;
+    let catalog = pdf.reserve();
+    let page_tree = pdf.reserve();
+
+    // 1. Pages, each with the image scaled to fill it
+    let mut page_numbers = Vec::new();
+    for (index, path) in pages.paths.iter().enumerate() {
+        let image = match PdfImage::load(&image_dir.join(path)) {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::warn!("Skipped image {} of album export: {}", path, e);
+                page_numbers.push(None);
+                on_page(index + 1);
+                continue;
+            }
+        };
+        let image_number = pdf.reserve();
+        pdf.stream(
+            image_number,
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8 /Filter /DCTDecode",
+                image.width,
+                image.height,
+                if image.gray { "DeviceGray" } else { "DeviceRGB" }
+            ),
+            &image.data,
+        )?;
+        let (width, height) = page_size(image.width, image.height);
+        let content = format!("q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q", width, height);
+        let content_number = pdf.reserve();
+        pdf.stream(content_number, "", content.as_bytes())?;
+        let page_number = pdf.reserve();
+        pdf.object(
+            page_number,
+            &format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                page_tree, width, height, image_number, content_number
+            ),
+        )?;
+        page_numbers.push(Some(page_number));
+        on_page(index + 1);
+    }
+    let kids = page_numbers.iter().flatten().collect::<Vec<_>>();
+    pdf.object(
+        page_tree,
+        &format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.iter().map(|n| format!("{} 0 R", n)).collect::<Vec<_>>().join(" "),
+            kids.len()
+        ),
+    )?;
+
+    // 2. Outline of the works, pointing to the first page written of each
+    let entries = pages
+        .contents
+        .iter()
+        .filter_map(|(title, first)| {
+            let page = page_numbers[*first..].iter().flatten().next()?;
+            Some((title, *page))
+        })
+        .collect::<Vec<_>>();
+    let outlines = if entries.is_empty() {
+        None
+    } else {
+        let outlines = pdf.reserve();
+        let numbers = entries.iter().map(|_| pdf.reserve()).collect::<Vec<_>>();
+        for (i, (title, page)) in entries.iter().enumerate() {
+            let mut item = format!(
+                "<< /Title {} /Parent {} 0 R /Dest [{} 0 R /Fit]",
+                pdf_text(title),
+                outlines,
+                page
+            );
+            if i > 0 {
+                let _ = write!(item, " /Prev {} 0 R", numbers[i - 1]);
+            }
+            if i + 1 < numbers.len() {
+                let _ = write!(item, " /Next {} 0 R", numbers[i + 1]);
+            }
+            item.push_str(" >>");
+            pdf.object(numbers[i], &item)?;
+        }
+        pdf.object(
+            outlines,
+            &format!(
+                "<< /Type /Outlines /First {} 0 R /Last {} 0 R /Count {} >>",
+                numbers[0],
+                numbers[numbers.len() - 1],
+                numbers.len()
+            ),
+        )?;
+        Some(outlines)
+    };
+    pdf.object(
+        catalog,
+        &match outlines {
+            Some(outlines) => format!(
+                "<< /Type /Catalog /Pages {} 0 R /Outlines {} 0 R /PageMode /UseOutlines >>",
+                page_tree, outlines
+            ),
+            None => format!("<< /Type /Catalog /Pages {} 0 R >>", page_tree),
+        },
+    )?;
+    let info = pdf.reserve();
+    pdf.object(info, &format!("<< /Title {} >>", pdf_text(&pages.album.name)))?;
+
+    // 3. Cross-reference table
+    let xref_offset = pdf.offset;
+    let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", pdf.offsets.len() + 1);
+    for offset in pdf.offsets.iter() {
+        let _ = writeln!(xref, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        xref,
+        "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+        pdf.offsets.len() + 1,
+        catalog,
+        info,
+        xref_offset
+    );
+    pdf.write(xref.as_bytes())?;
+    pdf.out.flush()?;
+    Ok(())
+}
+
+/// Size of the page of an image in points, a point for each pixel unless the page would be too large.
+fn page_size(width: u32, height: u32) -> (f64, f64) {
+    let (width, height) = (width as f64, height as f64);
+    let scale = (PDF_MAX_PAGE_SIZE / width.max(height)).min(1.0);
+    (width * scale, height * scale)
+}
+
+/// PDF text string in UTF-16BE, so that titles in any language are shown.
+fn pdf_text(value: &str) -> String {
+    let mut text = String::from("<FEFF");
+    for unit in value.encode_utf16() {
+        let _ = write!(text, "{:04X}", unit);
+    }
+    text.push('>');
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::io::Read;
+
+    /// Album of two works in a temporary image directory: a JPEG and a missing image, then a PNG.
+    fn album_pages(name: &str) -> (AlbumPages, std::path::PathBuf) {
+        let image_dir = std::env::temp_dir().join(format!("bottle_album_export_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&image_dir).unwrap();
+        image::RgbImage::from_pixel(4, 3, image::Rgb([200, 100, 0]))
+            .save(image_dir.join("a.jpg"))
+            .unwrap();
+        image::RgbImage::from_pixel(2, 5, image::Rgb([0, 100, 200]))
+            .save(image_dir.join("b.png"))
+            .unwrap();
+        let now = chrono::Utc::now();
+        let pages = AlbumPages {
+            album: AlbumView {
+                id: 1,
+                name: "Album <1>".to_string(),
+                folder_id: None,
+                position: 0,
+                added_date: now,
+                modified_date: now,
+                cover_path: None,
+                filter: None,
+            },
+            paths: vec!["a.jpg".to_string(), "missing.jpg".to_string(), "b.png".to_string()],
+            contents: vec![("First".to_string(), 0), ("Second".to_string(), 1)],
+            skipped_videos: 0,
+        };
+        (pages, image_dir)
+    }
+
+    #[test]
+    fn test_write_cbz() {
+        let (pages, image_dir) = album_pages("cbz");
+        let mut out = Cursor::new(Vec::new());
+        let mut done = Vec::new();
+        write_album(&pages, &image_dir, AlbumExportFormat::Cbz, &mut out, |n| done.push(n)).unwrap();
+        assert_eq!(done, vec![1, 2, 3]);
+
+        let mut archive = zip::ZipArchive::new(out).unwrap();
+        assert_eq!(
+            archive.file_names().collect::<Vec<_>>(),
+            vec!["00001.jpg", "00002.png", "ComicInfo.xml"]
+        );
+        let mut data = Vec::new();
+        archive.by_name("00002.png").unwrap().read_to_end(&mut data).unwrap();
+        assert_eq!(data, fs::read(image_dir.join("b.png")).unwrap());
+
+        let mut info = String::new();
+        archive
+            .by_name("ComicInfo.xml")
+            .unwrap()
+            .read_to_string(&mut info)
+            .unwrap();
+        assert!(info.contains("<Title>Album &lt;1&gt;</Title>"));
+        assert!(info.contains("<PageCount>2</PageCount>"));
+        // The second work starts at its first image written, after the missing one
+        assert!(info.contains("<Page Image=\"0\" Bookmark=\"First\" />"));
+        assert!(info.contains("<Page Image=\"1\" Bookmark=\"Second\" />"));
+        fs::remove_dir_all(image_dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_pdf() {
+        let (pages, image_dir) = album_pages("pdf");
+        let mut out = Cursor::new(Vec::new());
+        write_album(&pages, &image_dir, AlbumExportFormat::Pdf, &mut out, |_| {}).unwrap();
+        let pdf = out.into_inner();
+        let text = String::from_utf8_lossy(&pdf);
+
+        // Every object in the cross-reference table is at its offset
+        let xref_offset = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .and_then(|line| line.parse::<usize>().ok())
+            .unwrap();
+        assert!(pdf[xref_offset..].starts_with(b"xref\n0 "));
+        let xref = std::str::from_utf8(&pdf[xref_offset..]).unwrap();
+        let offsets = xref
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(!offsets.is_empty());
+        for (i, offset) in offsets.iter().enumerate() {
+            assert!(pdf[*offset..].starts_with(format!("{} 0 obj\n", i + 1).as_bytes()));
+        }
+
+        // Two pages sized by their images, and an outline entry for each work
+        assert!(text.contains("/Type /Pages"));
+        assert!(text.contains("/Count 2 >>"));
+        assert!(text.contains("/MediaBox [0 0 4.00 3.00]"));
+        assert!(text.contains("/MediaBox [0 0 2.00 5.00]"));
+        assert!(text.contains(&format!("/Title {}", pdf_text("First"))));
+        assert!(text.contains(&format!("/Title {}", pdf_text("Second"))));
+        assert!(text.contains("/Type /Outlines"));
+        fs::remove_dir_all(image_dir).unwrap();
+    }
+
+    #[test]
+    fn test_page_size() {
+        assert_eq!(page_size(1200, 1800), (1200.0, 1800.0));
+        assert_eq!(page_size(28800, 7200), (14400.0, 3600.0));
+        assert_eq!(page_size(1000, 144000), (100.0, 14400.0));
+    }
+}
@@ -1,10 +1,44 @@
+mod account_request;
+mod album_export;
+mod artist_link;
+mod booru_upload;
+mod cover;
+mod digest;
 mod download;
 mod entity;
 mod feed;
+mod import;
+mod notify;
+mod ocr;
 mod panda;
+mod panda_check;
+mod panda_mirror;
+mod pixiv_profile;
+mod queue;
+mod remote_check;
+mod repair;
+mod schedule;
+mod statistics;
+mod thumbnail;
 mod util;
 
+pub use account_request::*;
+pub use album_export::*;
+pub use artist_link::*;
+pub use booru_upload::*;
+pub use cover::*;
+pub use digest::*;
 pub use download::*;
 pub use entity::*;
 pub use feed::*;
+pub use import::*;
+pub use ocr::*;
 pub use panda::*;
+pub use panda_check::*;
+pub use panda_mirror::*;
+pub use pixiv_profile::*;
+pub use queue::*;
+pub use remote_check::*;
+pub use repair::*;
+pub use statistics::*;
+pub use thumbnail::*;
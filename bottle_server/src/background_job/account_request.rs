@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use tokio::{sync::mpsc, task};
+
+use bottle_core::cache::RequestRecorder;
+use bottle_library::{AccountRequest, RequestOutcome};
+
+use crate::{error::ServerError, state::DatabasePool};
+
+/// Requests made with accounts, by community and account ID.
+pub type AccountRequestSender = mpsc::UnboundedSender<(String, i32, RequestOutcome)>;
+
+/// Count the requests made by the clients of accounts in the database.
+/// Requests are recorded in the background, so that clients never wait for the database.
+pub fn listen_account_request(pool: DatabasePool) -> AccountRequestSender {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<(String, i32, RequestOutcome)>();
+    task::spawn(async move {
+        while let Some((community, account_id, outcome)) = receiver.recv().await {
+            let recorded = pool
+                .get()
+                .map_err(ServerError::from)
+                .and_then(|mut db| Ok(AccountRequest::record(&mut db, &community, account_id, outcome)?));
+            if let Err(e) = recorded {
+                tracing::warn!(
+                    "Failed to record request of {} account {}: {}",
+                    community,
+                    account_id,
+                    e
+                );
+            }
+        }
+    });
+    sender
+}
+
+/// Recorder of the requests made with accounts of the community, set to its cache of clients.
+pub fn request_recorder(sender: &AccountRequestSender, community: &str) -> RequestRecorder {
+    let sender = sender.clone();
+    let community = community.to_string();
+    Arc::new(move |account_id, outcome| {
+        let _ = sender.send((community.clone(), account_id, outcome));
+    })
+}
@@ -0,0 +1,135 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::BufWriter,
+    sync::Arc,
+};
+
+use serde::Serialize;
+use tokio::{runtime::Handle, sync::RwLock, task};
+use tracing::Instrument;
+
+use crate::{
+    album_export::{self, AlbumExportFormat, AlbumPages},
+    error::Result,
+    state::AppState,
+};
+
+use super::entity::{next_job_id, GeneralJobState};
+
+/// Directory of the exported albums, relative to the image directory.
+const ALBUM_EXPORT_DIR: &str = "exports";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlbumExportJobStateResponse {
+    pub id: u64,
+    pub album_id: i32,
+    pub format: AlbumExportFormat,
+    pub state: GeneralJobState,
+    /// Images to package.
+    pub total: u64,
+    /// Images packaged so far.
+    pub done: u64,
+    /// Videos left out since they have no poster frame to export.
+    pub skipped_videos: u64,
+    /// Exported file relative to the image directory, only when the job succeeded.
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+pub type AlbumExportJobStateMap = Arc<RwLock<HashMap<u64, AlbumExportJobStateResponse>>>;
+
+/// Used in server handler. Start packaging the album into a file under the image directory,
+/// which can be downloaded from the path in the job state when it is done.
+pub async fn send_album_export(
+    app_state: &AppState,
+    pages: AlbumPages,
+    format: AlbumExportFormat,
+) -> Result<AlbumExportJobStateResponse> {
+    let state = AlbumExportJobStateResponse {
+        id: next_job_id(),
+        album_id: pages.album.id,
+        format,
+        state: GeneralJobState::Ready,
+        total: pages.paths.len() as u64,
+        done: 0,
+        skipped_videos: pages.skipped_videos as u64,
+        path: None,
+        error: None,
+    };
+    app_state
+        .album_export_job_state_map
+        .write()
+        .await
+        .insert(state.id, state.clone());
+
+    let app_state = app_state.clone();
+    let job_id = state.id;
+    let span = tracing::info_span!("album_export", job = job_id, album = pages.album.id);
+    task::spawn(
+        async move {
+            let result = {
+                let app_state = app_state.clone();
+                task::spawn_blocking(move || export_album(&app_state, job_id, &pages, format)).await
+            };
+            match result.map_err(anyhow::Error::from) {
+                Ok(Ok(path)) => {
+                    tracing::info!("Album export job done: {}", path);
+                    update_state(&app_state, job_id, |state| {
+                        state.state = GeneralJobState::Success;
+                        state.path = Some(path);
+                    })
+                    .await;
+                }
+                Ok(Err(e)) => fail(&app_state, job_id, e.to_string()).await,
+                Err(e) => fail(&app_state, job_id, e.to_string()).await,
+            }
+        }
+        .instrument(span),
+    );
+
+    Ok(state)
+}
+
+/// Write the album into a file, returning its path relative to the image directory.
+fn export_album(app_state: &AppState, job_id: u64, pages: &AlbumPages, format: AlbumExportFormat) -> Result<String> {
+    let image_dir = &app_state.config.image_dir;
+    let path = format!(
+        "{}/album_{}_{}.{}",
+        ALBUM_EXPORT_DIR,
+        pages.album.id,
+        job_id,
+        format.extension()
+    );
+    fs::create_dir_all(image_dir.join(ALBUM_EXPORT_DIR))?;
+    let file = BufWriter::new(File::create(image_dir.join(&path))?);
+
+    tracing::info!("Album export job started: {} images", pages.paths.len());
+    let handle = Handle::current();
+    handle.block_on(update_state(app_state, job_id, |state| {
+        state.state = GeneralJobState::Running
+    }));
+    let result = album_export::write_album(pages, image_dir, format, file, |done| {
+        handle.block_on(update_state(app_state, job_id, |state| state.done = done as u64));
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_file(image_dir.join(&path));
+        return Err(e.into());
+    }
+    Ok(path)
+}
+
+async fn fail(app_state: &AppState, job_id: u64, error: String) {
+    tracing::error!("Album export job failed: {}", error);
+    update_state(app_state, job_id, |state| {
+        state.state = GeneralJobState::Failed;
+        state.error = Some(error);
+    })
+    .await;
+}
+
+async fn update_state(app_state: &AppState, job_id: u64, f: impl FnOnce(&mut AlbumExportJobStateResponse)) {
+    if let Some(state) = app_state.album_export_job_state_map.write().await.get_mut(&job_id) {
+        f(state);
+    }
+}
@@ -0,0 +1,36 @@
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_library::ArtistLink;
+
+use crate::{error::Result, state::DatabasePool};
+
+use super::entity::next_job_id;
+
+/// Suggest links of artists across communities at startup and periodically.
+pub fn listen_artist_link(pool: DatabasePool, interval: Duration) {
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("artist_link", job = next_job_id());
+            if let Err(e) = suggest_artist_links(pool.clone()).instrument(span).await {
+                tracing::error!("Failed to suggest artist links: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Suggest links of artists from the saved profiles and tags without blocking the runtime.
+pub async fn suggest_artist_links(pool: DatabasePool) -> Result<()> {
+    let span = tracing::Span::current();
+    task::spawn_blocking(move || {
+        let _guard = span.enter();
+        let db = &mut pool.get()?;
+        ArtistLink::suggest(db)?;
+        Ok(())
+    })
+    .await?
+}
@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_library::BooruUpload;
+use bottle_util::http::client_builder;
+
+use crate::{
+    booru::{upload_to_booru, BooruConfig},
+    error::Result,
+    export::export_entries,
+    payload::ExportWorksRequest,
+    state::DatabasePool,
+    util,
+};
+
+use super::entity::next_job_id;
+
+/// Number of works uploaded to each booru in each run.
+const BOORU_UPLOAD_BATCH_SIZE: i64 = 100;
+
+/// Upload the archived works matching each booru at startup and periodically.
+pub fn listen_booru_upload(
+    pool: DatabasePool,
+    image_dir: impl AsRef<Path>,
+    boorus: Vec<BooruConfig>,
+    interval: Duration,
+) {
+    let image_dir = image_dir.as_ref().to_path_buf();
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("booru_upload", job = next_job_id());
+            if let Err(e) = upload_to_boorus(pool.clone(), image_dir.clone(), &boorus)
+                .instrument(span)
+                .await
+            {
+                tracing::error!("Failed to upload to boorus: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Upload a batch of downloaded images of the works in the albums or with the tags of each booru.
+/// Failed images are left to the next run.
+pub async fn upload_to_boorus(pool: DatabasePool, image_dir: PathBuf, boorus: &[BooruConfig]) -> Result<()> {
+    // Each request is bounded, while uploads to Danbooru take several requests
+    let client = client_builder()
+        .timeout(Duration::from_millis(util::DEFAULT_TIMEOUT_MS))
+        .build()?;
+    for booru in boorus {
+        let entries = {
+            let db = &mut pool.get()?;
+            let work_ids =
+                BooruUpload::pending_works(db, &booru.name, &booru.album_ids, &booru.tags, BOORU_UPLOAD_BATCH_SIZE)?;
+            if work_ids.is_empty() {
+                continue;
+            }
+            let entries = export_entries(
+                db,
+                &ExportWorksRequest {
+                    work_ids,
+                    album_id: None,
+                },
+            )?;
+            let image_ids = entries.iter().map(|entry| entry.image.id).collect::<Vec<_>>();
+            let uploaded = BooruUpload::uploaded_images(db, &booru.name, &image_ids)?;
+            entries
+                .into_iter()
+                .filter(|entry| entry.image.path.is_some() && !uploaded.contains(&entry.image.id))
+                .collect::<Vec<_>>()
+        };
+
+        tracing::info!(
+            "Booru upload job started. Uploading {} images to {}",
+            entries.len(),
+            booru.name
+        );
+        let mut uploaded_count = 0;
+        for entry in entries.iter() {
+            let Some(path) = &entry.image.path else {
+                continue;
+            };
+            let bytes = match tokio::fs::read(image_dir.join(path)).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    tracing::warn!("Failed to read image {}: {}", entry.image.id, e);
+                    continue;
+                }
+            };
+            match upload_to_booru(&client, booru, entry, bytes).await {
+                Ok(remote_id) => {
+                    let db = &mut pool.get()?;
+                    BooruUpload::record(db, &booru.name, entry.image.id, &remote_id)?;
+                    uploaded_count += 1;
+                }
+                Err(e) => tracing::warn!("Failed to upload image {} to {}: {}", entry.image.id, booru.name, e),
+            }
+        }
+        tracing::info!(
+            "Booru upload job done. Uploaded {} of {} images to {}",
+            uploaded_count,
+            entries.len(),
+            booru.name
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use std::path::{Path, PathBuf};
+
+use bottle_library::{Album, Cover};
+
+use crate::{error::Result, state::DatabasePool, util::COMMUNITIES};
+
+use super::entity::next_job_id;
+
+/// Number of recent works making up a cover, filling a 3x3 collage.
+const COVER_WORK_COUNT: i64 = 9;
+
+/// Regenerate the covers of albums and feeds at startup and periodically.
+pub fn listen_cover(pool: DatabasePool, image_dir: impl AsRef<Path>, interval: Duration) {
+    let image_dir = image_dir.as_ref().to_path_buf();
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("cover", job = next_job_id());
+            if let Err(e) = generate_covers(pool.clone(), image_dir.clone()).instrument(span).await {
+                tracing::error!("Failed to generate covers: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Composite a collage from the recent works of each album and feed, without blocking the runtime.
+pub async fn generate_covers(pool: DatabasePool, image_dir: PathBuf) -> Result<()> {
+    let span = tracing::Span::current();
+    task::spawn_blocking(move || {
+        let _guard = span.enter();
+        let db = &mut pool.get()?;
+
+        let albums = Album::all(db)?;
+        for album in &albums {
+            let thumbnails = Cover::album_thumbnails(db, album.id, COVER_WORK_COUNT)?;
+            let relpath = PathBuf::from("cover").join("album").join(format!("{}.jpg", album.id));
+            let cover_path = generate_cover(&image_dir, &thumbnails, &relpath);
+            Cover::set_album_cover(db, album.id, cover_path.as_deref())?;
+        }
+
+        let mut feed_count = 0;
+        for community in COMMUNITIES {
+            for feed_id in Cover::feed_ids(db, community)? {
+                let thumbnails = Cover::feed_thumbnails(db, community, feed_id, COVER_WORK_COUNT)?;
+                let relpath = PathBuf::from("cover").join(community).join(format!("{}.jpg", feed_id));
+                let cover_path = generate_cover(&image_dir, &thumbnails, &relpath);
+                Cover::set_feed_cover(db, community, feed_id, cover_path.as_deref())?;
+                feed_count += 1;
+            }
+        }
+
+        tracing::info!("Generated covers of {} albums and {} feeds", albums.len(), feed_count);
+        Ok(())
+    })
+    .await?
+}
+
+/// Returns the relative path of the cover, or `None` if there is no thumbnail to make it from.
+fn generate_cover(image_dir: &Path, thumbnails: &[String], relpath: &Path) -> Option<String> {
+    let paths = thumbnails.iter().map(|path| image_dir.join(path)).collect::<Vec<_>>();
+    match bottle_download::create_collage(&paths, image_dir.join(relpath)) {
+        Ok(true) => Some(relpath.to_string_lossy().to_string()),
+        Ok(false) => None,
+        Err(e) => {
+            tracing::warn!("Failed to generate cover {}: {}", relpath.display(), e);
+            None
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::{collections::HashSet, path::Path};
+
+use chrono::{DateTime, Utc};
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_core::feed::DigestFrequency;
+use bottle_library::model;
+
+use crate::{
+    error::Result,
+    mail::{self, SmtpConfig},
+    state::AppState,
+    util::{FeedWrapper, COMMUNITIES},
+};
+
+use super::{entity::next_job_id, FeedUpdateJobState, ImageDownloadJobState, PandaDownloadJobState};
+
+/// Check periodically and send the digests which are due.
+pub fn listen_digest(app_state: AppState, image_dir: impl AsRef<Path>, config: SmtpConfig, interval: Duration) {
+    let image_dir = image_dir.as_ref().to_path_buf();
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("digest", job = next_job_id());
+            if let Err(e) = send_due_digests(&app_state, &image_dir, &config).instrument(span).await {
+                tracing::error!("Failed to send digests: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+async fn send_due_digests(app_state: &AppState, image_dir: &Path, config: &SmtpConfig) -> Result<()> {
+    let now = Utc::now();
+    let digests = {
+        let db = &mut app_state.pool.get()?;
+        bottle_library::Digest::due(db, now)?
+    };
+    if digests.is_empty() {
+        return Ok(());
+    }
+
+    let dir = image_dir.to_path_buf();
+    let disk_usage = task::spawn_blocking(move || disk_usage(&dir)).await?? as i64;
+    for digest in digests {
+        let (subject, body) = match render_digest(app_state, &digest, disk_usage, now).await {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                tracing::warn!("Failed to render digest {}: {}", digest.id, e);
+                mark_failed(app_state, digest.id, now)?;
+                continue;
+            }
+        };
+        match mail::send_mail(config, &digest.email, &subject, &body).await {
+            Ok(_) => {
+                let db = &mut app_state.pool.get()?;
+                bottle_library::Digest::mark_sent(db, digest.id, now, disk_usage)?;
+                tracing::info!("Sent {} digest {} to {}", digest.frequency, digest.id, digest.email);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to send digest {} to {}: {}", digest.id, digest.email, e);
+                mark_failed(app_state, digest.id, now)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Record the failure of the digest, so that it is retried after a backoff instead of at every check.
+fn mark_failed(app_state: &AppState, digest_id: i32, now: DateTime<Utc>) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    bottle_library::Digest::mark_failed(db, digest_id, now)?;
+    Ok(())
+}
+
+/// Render the subject and plain text body of the digest, which lists new posts of each feed since last sent,
+/// jobs failed currently, and disk usage of the image directory.
+async fn render_digest(
+    app_state: &AppState,
+    digest: &model::Digest,
+    disk_usage: i64,
+    now: DateTime<Utc>,
+) -> Result<(String, String)> {
+    let frequency = digest.frequency.parse().unwrap_or(DigestFrequency::Weekly);
+    let since = digest
+        .last_sent_date
+        .map(|date| date.and_utc())
+        .unwrap_or(now - frequency.period());
+    let subject = format!("Bottle {} digest: {}", frequency.as_str(), now.format("%Y-%m-%d"));
+    let mut lines = vec![format!("Since {}", since.format("%Y-%m-%d %H:%M UTC")), String::new()];
+
+    // 1. New posts per feed
+    lines.push("New posts".to_string());
+    let mut feed_lines = Vec::new();
+    {
+        let db = &mut app_state.pool.get()?;
+        for community in COMMUNITIES {
+            for feed in FeedWrapper::all(db, community)? {
+                let count = feed.new_post_count(db, since)?;
+                if count > 0 {
+                    feed_lines.push(format!("  {}: {}", feed_name(&feed), count));
+                }
+            }
+        }
+    }
+    if feed_lines.is_empty() {
+        feed_lines.push("  None".to_string());
+    }
+    lines.extend(feed_lines);
+    lines.push(String::new());
+
+    // 2. Failed jobs
+    lines.push("Failed jobs".to_string());
+    let mut job_lines = Vec::new();
+    for (id, rx) in app_state.feed_update_state_map.read().await.iter() {
+        if let FeedUpdateJobState::Failed { error } = &*rx.borrow() {
+            job_lines.push(format!("  Feed update {}: {}", id, error));
+        }
+    }
+    match &*app_state.image_download_job_state.borrow() {
+        ImageDownloadJobState::PartialSuccess { total, failures, .. } => job_lines.push(format!(
+            "  Image download: {} of {} images failed",
+            failures.len(),
+            total
+        )),
+        ImageDownloadJobState::Failed { error } => job_lines.push(format!("  Image download: {}", error)),
+        _ => {}
+    }
+    let panda_title_map = app_state.panda_gallery_title_map.read().await.clone();
+    for (id, rx) in app_state.panda_download_state_map.read().await.iter() {
+        let title = panda_title_map.get(id).cloned().unwrap_or(id.0.to_string());
+        match &*rx.borrow() {
+            PandaDownloadJobState::PartialSuccess { total, failures, .. } => job_lines.push(format!(
+                "  Panda download {}: {} of {} images failed",
+                title,
+                failures.len(),
+                total
+            )),
+            PandaDownloadJobState::Failed { error } => job_lines.push(format!("  Panda download {}: {}", title, error)),
+            _ => {}
+        }
+    }
+    if job_lines.is_empty() {
+        job_lines.push("  None".to_string());
+    }
+    lines.extend(job_lines);
+    lines.push(String::new());
+
+    // 3. Disk usage
+    lines.push("Disk usage".to_string());
+    let delta = match digest.last_disk_usage {
+        Some(last) => {
+            let delta = disk_usage - last;
+            let sign = if delta < 0 { "-" } else { "+" };
+            format!(" ({}{} since last digest)", sign, format_size(delta.unsigned_abs()))
+        }
+        None => String::new(),
+    };
+    lines.push(format!("  {}{}", format_size(disk_usage as u64), delta));
+
+    Ok((subject, lines.join("\n")))
+}
+
+fn feed_name(feed: &FeedWrapper) -> String {
+    let view = feed.view();
+    match view.name {
+        Some(name) => format!("{} / {}", view.community, name),
+        None => format!("{} / feed {}", view.community, view.feed_id),
+    }
+}
+
+/// Total size of files in the directory in bytes. Hard-linked files are counted once.
+fn disk_usage(dir: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    let mut seen = HashSet::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if hard_link_id(&metadata).is_none_or(|id| seen.insert(id)) {
+                total += metadata.len();
+            }
+        }
+    }
+    Ok(total)
+}
+
+/// Device and inode of a file which has other hard links.
+#[cfg(unix)]
+fn hard_link_id(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn hard_link_id(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -1,22 +1,29 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-use futures::stream::StreamExt;
+use futures::{stream::StreamExt, FutureExt};
 use serde::Serialize;
 use tokio::{
     sync::{mpsc, watch},
     task,
+    time::Instant,
 };
+use tracing::Instrument;
 
-use bottle_download::{DownloadTask, LocalImage};
+use bottle_core::library::ImageQuality;
+use bottle_download::{DownloadTask, FetchedImage, LocalImage};
 
 use crate::{
+    config::DownloadConfig,
     error::Result,
     state::{AppState, DatabasePool},
     util,
 };
 
-use super::entity::GeneralJobState;
-use super::util::{DEFAULT_DOWNLOAD_CONCURRENCY, DEFAULT_DOWNLOAD_OVERWRITE};
+use super::entity::{next_job_id, DownloadJobResponse, GeneralJobState, TracedJob};
+use super::queue::{job_queue, JobQueue};
+use super::thumbnail::ThumbnailPool;
+use super::util::{classify_image, link_duplicate_image, load_settings};
 
 #[derive(Debug, Clone)]
 pub enum ImageDownloadJobState {
@@ -25,6 +32,7 @@ pub enum ImageDownloadJobState {
         total: u64,
         success: u64,
         failure: u64,
+        started: Instant,
     },
     Success {
         total: u64,
@@ -55,6 +63,7 @@ impl ImageDownloadJobState {
             total,
             success: 0,
             failure: 0,
+            started: Instant::now(),
         }
     }
 
@@ -67,33 +76,19 @@ impl ImageDownloadJobState {
     }
 
     fn adding_success(&self) -> Self {
-        match self {
-            ImageDownloadJobState::Running {
-                total,
-                success,
-                failure,
-            } => ImageDownloadJobState::Running {
-                total: *total,
-                success: *success + 1,
-                failure: *failure,
-            },
-            _ => self.clone(),
+        let mut state = self.clone();
+        if let ImageDownloadJobState::Running { success, .. } = &mut state {
+            *success += 1;
         }
+        state
     }
 
     fn adding_failure(&self) -> Self {
-        match self {
-            ImageDownloadJobState::Running {
-                total,
-                success,
-                failure,
-            } => ImageDownloadJobState::Running {
-                total: *total,
-                success: *success,
-                failure: *failure + 1,
-            },
-            _ => self.clone(),
+        let mut state = self.clone();
+        if let ImageDownloadJobState::Running { failure, .. } = &mut state {
+            *failure += 1;
         }
+        state
     }
 }
 
@@ -115,6 +110,7 @@ impl From<&ImageDownloadJobState> for ImageDownloadJobStateResponse {
                 total,
                 success,
                 failure,
+                ..
             } => Self {
                 state: GeneralJobState::Running,
                 total: *total,
@@ -149,7 +145,29 @@ impl From<&ImageDownloadJobState> for ImageDownloadJobStateResponse {
     }
 }
 
-pub type ImageDownloadJobQueue = mpsc::UnboundedSender<()>;
+impl From<&ImageDownloadJobState> for DownloadJobResponse {
+    fn from(state: &ImageDownloadJobState) -> Self {
+        let started = match state {
+            ImageDownloadJobState::Running { started, .. } => Some((*started, 0)),
+            _ => None,
+        };
+        let response = ImageDownloadJobStateResponse::from(state);
+        Self {
+            id: "image".to_string(),
+            community: None,
+            target: "Images of library works".to_string(),
+            state: response.state,
+            total: response.total,
+            success: response.success,
+            failure: response.failure,
+            error: response.error,
+            ..Default::default()
+        }
+        .with_progress(started)
+    }
+}
+
+pub type ImageDownloadJobQueue = JobQueue<()>;
 pub type ImageDownloadJobStateReceiver = watch::Receiver<ImageDownloadJobState>;
 
 /// Used in server handler
@@ -161,17 +179,22 @@ pub async fn send_image_download(app_state: &AppState) -> Result<()> {
         return Err(anyhow::anyhow!("Image download job is already running"))?;
     }
 
-    app_state.image_download_queue.send(())?;
+    app_state.image_download_queue.send(TracedJob::new(()))?;
     Ok(())
 }
 
-/// Set up before server started
+/// Set up before server started.
+/// If `classifier` is set, downloaded images of unrated works are rated with it.
 pub fn listen_image_download(
     pool: DatabasePool,
     image_dir: impl AsRef<Path>,
+    classifier: Option<PathBuf>,
+    config: DownloadConfig,
+    queue_capacity: usize,
+    thumbnail_pool: ThumbnailPool,
 ) -> (ImageDownloadJobQueue, ImageDownloadJobStateReceiver) {
-    // (1) MPSC channel: job queue
-    let (job_sender, mut job_receiver) = mpsc::unbounded_channel();
+    // (1) MPSC bounded channel: job queue
+    let (job_sender, mut job_receiver) = job_queue("image_download", queue_capacity);
 
     // (2) watch channel: job state
     let (state_sender, state_receiver) = watch::channel(ImageDownloadJobState::Ready);
@@ -179,20 +202,30 @@ pub fn listen_image_download(
 
     let image_dir = image_dir.as_ref().to_path_buf();
     task::spawn(async move {
-        while (job_receiver.recv().await).is_some() {
-            let result = download_images(
-                pool.clone(),
-                state_sender.clone(),
-                &image_dir,
-                DEFAULT_DOWNLOAD_CONCURRENCY,
-                DEFAULT_DOWNLOAD_OVERWRITE,
-            )
-            .await;
+        while let Some(TracedJob { span, .. }) = job_receiver.recv().await {
+            job_receiver.start();
+            let span = tracing::info_span!(parent: &span, "image_download", job = next_job_id());
+            async {
+                let settings = load_settings(&pool);
+                let result = download_images(
+                    pool.clone(),
+                    state_sender.clone(),
+                    &image_dir,
+                    classifier.as_deref(),
+                    settings.download_concurrency.unwrap_or(config.concurrency).max(1),
+                    config.overwrite,
+                    &config.quality,
+                    &thumbnail_pool,
+                )
+                .await;
 
-            if let Err(e) = result {
-                tracing::error!("Image download job failed: {}", e);
-                let _ = state_sender2.send(ImageDownloadJobState::Failed { error: e.to_string() });
+                if let Err(e) = result {
+                    tracing::error!("Image download job failed: {}", e);
+                    let _ = state_sender2.send(ImageDownloadJobState::Failed { error: e.to_string() });
+                }
             }
+            .instrument(span)
+            .await;
         }
     });
 
@@ -204,18 +237,22 @@ enum ImageDownloadMessage {
     Failed,
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn download_images(
     pool: DatabasePool,
     // (2) watch channel: job state
     state_sender: watch::Sender<ImageDownloadJobState>,
     image_dir: impl AsRef<Path>,
+    classifier: Option<&Path>,
     max_concurrency: usize,
     overwrite: bool,
+    qualities: &HashMap<String, ImageQuality>,
+    thumbnail_pool: &ThumbnailPool,
 ) -> Result<()> {
     // 1. Prepare download futures
     let tasks = {
         let conn = &mut pool.get()?;
-        bottle_library::get_download_tasks(conn, image_dir)?
+        bottle_library::get_download_tasks(conn, image_dir, qualities)?
     };
     if tasks.is_empty() {
         tracing::info!("Image download job done. No images to download");
@@ -228,9 +265,30 @@ async fn download_images(
     let (subtask_sender, mut subtask_receiver) = mpsc::channel(1);
     let futures = tasks
         .iter()
-        .map(|task| download_image(pool.clone(), subtask_sender.clone(), task, overwrite))
+        .map(|task| fetch_image(task, overwrite).map(move |result| (task.clone(), result)))
         .collect::<Vec<_>>();
-    let stream = futures::stream::iter(futures).buffer_unordered(max_concurrency);
+    let stream = futures::stream::iter(futures)
+        .buffer_unordered(max_concurrency)
+        // Process fetched images in the thumbnail pool, while the next images are being fetched
+        .map(|(task, fetched)| {
+            let pool = pool.clone();
+            let thumbnail_pool = thumbnail_pool.clone();
+            let subtask_sender = subtask_sender.clone();
+            let classifier = classifier.map(Path::to_path_buf);
+            async move {
+                let result = save_image(
+                    pool,
+                    &thumbnail_pool,
+                    subtask_sender,
+                    &task,
+                    fetched,
+                    classifier.as_deref(),
+                )
+                .await;
+                (task, result)
+            }
+        })
+        .buffer_unordered(thumbnail_pool.workers());
 
     // 2. Listen to subtask results and update job state
     let mut state = ImageDownloadJobState::new_running(task_count);
@@ -252,9 +310,8 @@ async fn download_images(
     state_update_task.abort();
 
     // 4. Collect failures and send final state
-    let failures = tasks
+    let failures = images
         .iter()
-        .zip(images.iter())
         .filter_map(|(task, result)| {
             result.as_ref().err().map(|e| ImageDownloadFailure {
                 url: task.url.clone(),
@@ -277,15 +334,24 @@ async fn download_images(
     Ok(())
 }
 
-async fn download_image(
+async fn fetch_image(task: &DownloadTask, overwrite: bool) -> Result<FetchedImage> {
+    util::retry(|| util::timeout(bottle_download::fetch_image(task, overwrite))).await
+}
+
+async fn save_image(
     pool: DatabasePool,
+    thumbnail_pool: &ThumbnailPool,
     // (3) MPSC channel: monitor subtask results
     subtask_sender: mpsc::Sender<ImageDownloadMessage>,
     task: &DownloadTask,
-    overwrite: bool,
+    fetched: Result<FetchedImage>,
+    classifier: Option<&Path>,
 ) -> Result<LocalImage> {
-    // 1. Download image
-    let result = util::retry(|| util::timeout(bottle_download::download_image(task, overwrite))).await;
+    // 1. Process the fetched image
+    let result = match fetched {
+        Ok(fetched) => thumbnail_pool.process(task.clone(), fetched).await,
+        Err(e) => Err(e),
+    };
 
     // 2. Update database if succeed
     match &result {
@@ -293,6 +359,14 @@ async fn download_image(
             tracing::info!("Downloaded image: {}", image.relpath);
             let conn = &mut pool.get()?;
             bottle_library::update_from_local_image(conn, task.image_id, image)?;
+            if let Err(e) = link_duplicate_image(&pool, &task.root_dir, task.image_id, image).await {
+                tracing::warn!("Failed to deduplicate image {}: {}", image.relpath, e);
+            }
+            if let Some(classifier) = classifier {
+                if let Err(e) = classify_image(&pool, classifier, &task.root_dir, task.image_id, image).await {
+                    tracing::warn!("Failed to classify image {}: {}", image.relpath, e);
+                }
+            }
             subtask_sender.send(ImageDownloadMessage::Success).await?;
         }
         Err(e) => {
@@ -1,8 +1,14 @@
 use serde::Serialize;
+use tokio::time::Instant;
+use tracing::Span;
+
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use super::download::ImageDownloadJobStateResponse;
 use super::feed::FeedUpdateJobStateResponse;
 use super::panda::PandaDownloadJobStateResponse;
+use super::queue::JobQueueStateResponse;
+use super::thumbnail::ThumbnailJobStateResponse;
 
 #[derive(Debug, Clone, Serialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -19,4 +25,74 @@ pub struct JobsStateResponse {
     pub feed_update_jobs: Vec<FeedUpdateJobStateResponse>,
     pub image_download_job: ImageDownloadJobStateResponse,
     pub panda_download_jobs: Vec<PandaDownloadJobStateResponse>,
+    pub thumbnail_job: ThumbnailJobStateResponse,
+    /// Jobs waiting in each queue, to tell if the jobs are stalled.
+    pub queues: Vec<JobQueueStateResponse>,
+}
+
+/// A download job in a uniform shape, either the image download job or a panda gallery download job.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct DownloadJobResponse {
+    pub id: String,
+    pub community: Option<String>,
+    /// What is downloaded, e.g. the gallery title.
+    pub target: String,
+    pub state: GeneralJobState,
+    pub total: u64,
+    pub success: u64,
+    pub failure: u64,
+    /// Fraction of finished images, from 0 to 1.
+    pub progress: f64,
+    /// Images finished per second, only when running.
+    pub speed: Option<f64>,
+    /// Estimated seconds until finished, only when running.
+    pub eta_secs: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl DownloadJobResponse {
+    pub fn finished(&self) -> bool {
+        matches!(self.state, GeneralJobState::Success | GeneralJobState::Failed)
+    }
+
+    /// Fill progress, and speed and ETA by the images finished since the job started.
+    pub(super) fn with_progress(mut self, started: Option<(Instant, u64)>) -> Self {
+        let done = self.success + self.failure;
+        if self.total > 0 {
+            self.progress = done as f64 / self.total as f64;
+        }
+        if let Some((started, done_before)) = started {
+            let elapsed = started.elapsed().as_secs_f64();
+            let done_since = done.saturating_sub(done_before);
+            if elapsed > 0.0 && done_since > 0 {
+                let speed = done_since as f64 / elapsed;
+                self.speed = Some(speed);
+                self.eta_secs = Some((self.total.saturating_sub(done) as f64 / speed).ceil() as u64);
+            }
+        }
+        self
+    }
+}
+
+/// A job sent to a background queue, along with the span where it is sent,
+/// so that logs of the job can be traced back to the request by its ID.
+#[derive(Debug)]
+pub struct TracedJob<T> {
+    pub job: T,
+    pub span: Span,
+}
+
+impl<T> TracedJob<T> {
+    pub fn new(job: T) -> Self {
+        Self {
+            job,
+            span: Span::current(),
+        }
+    }
+}
+
+/// Sequential ID of jobs, to tell apart jobs which are not sent by requests.
+pub fn next_job_id() -> u64 {
+    static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed)
 }
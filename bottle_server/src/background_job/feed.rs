@@ -2,20 +2,29 @@ use std::{collections::HashMap, sync::Arc};
 
 use serde::Serialize;
 use tokio::{
-    sync::{mpsc, watch, RwLock},
+    sync::{watch, RwLock},
     task,
     time::{self, Duration},
 };
+use tracing::Instrument;
 
-use bottle_core::feed::SaveResult;
+use bottle_core::feed::{FeedView, SaveResult};
+use bottle_library::FeedFailure;
 
 use crate::{
-    error::Result,
-    state::{AppState, DatabasePool},
+    config::CommunityConfig,
+    error::{Result, ServerError},
+    state::{AppState, CommunityCaches, DatabasePool},
     util::{self, FeedContextWrapper, FeedIdentifier, FeedWrapper},
 };
 
-use super::{entity::GeneralJobState, util::DEFAULT_DELAY_MS};
+use super::{
+    entity::{next_job_id, GeneralJobState, TracedJob},
+    notify::{notify_feed_paused, notify_webhooks},
+    queue::{job_queue, JobQueue},
+    schedule::{pick_next_job, AccountUsage},
+    util::load_settings,
+};
 
 #[derive(Debug, Clone)]
 pub enum FeedUpdateJobState {
@@ -67,14 +76,25 @@ impl FeedUpdateJobStateResponse {
     }
 }
 
-pub type FeedUpdateJobQueue = mpsc::UnboundedSender<FeedIdentifier>;
+pub type FeedUpdateJobQueue = JobQueue<FeedIdentifier>;
 pub type FeedUpdateJobStateSender = watch::Sender<FeedUpdateJobState>;
 pub type FeedUpdateJobStateReceiver = watch::Receiver<FeedUpdateJobState>;
 pub type FeedUpdateJobStateSenderMap = Arc<RwLock<HashMap<FeedIdentifier, FeedUpdateJobStateSender>>>;
 pub type FeedUpdateJobStateReceiverMap = Arc<RwLock<HashMap<FeedIdentifier, FeedUpdateJobStateReceiver>>>;
 
 /// Used in server handler. Return true if the job sent successfully.
+/// Fail with `QueueFull` if the queue is full.
 pub async fn send_feed_update(app_state: &AppState, id: FeedIdentifier) -> Result<bool> {
+    send_feed_update_inner(app_state, id, false).await
+}
+
+/// Used by producers sending many jobs. Return true if the job sent successfully.
+/// Wait for the queue to have room if it is full.
+pub async fn queue_feed_update(app_state: &AppState, id: FeedIdentifier) -> Result<bool> {
+    send_feed_update_inner(app_state, id, true).await
+}
+
+async fn send_feed_update_inner(app_state: &AppState, id: FeedIdentifier, wait: bool) -> Result<bool> {
     let state = app_state
         .feed_update_state_map
         .read()
@@ -112,67 +132,187 @@ pub async fn send_feed_update(app_state: &AppState, id: FeedIdentifier) -> Resul
             .await
             .insert(id.clone(), state_receiver);
     }
-    app_state
+    let queue = app_state
         .feed_update_queues
         .get(&id.community)
-        .expect("community not found")
-        .send(id)?;
+        .expect("community not found");
+    let sent = if wait {
+        queue.send_wait(TracedJob::new(id.clone())).await
+    } else {
+        queue.send(TracedJob::new(id.clone()))
+    };
+    if let Err(e) = sent {
+        // Finish the job, so that it can be sent again later
+        if let Some(state_sender) = app_state.feed_update_state_sender_map.read().await.get(&id) {
+            let _ = state_sender.send(FeedUpdateJobState::Failed { error: e.to_string() });
+        }
+        return Err(e);
+    }
 
     Ok(true)
 }
 
 /// Set up before server started
-pub fn listen_feed_update(pool: DatabasePool, state_sender_map: FeedUpdateJobStateSenderMap) -> FeedUpdateJobQueue {
-    // (1) MPSC unbounded channel: job queue
+pub fn listen_feed_update(
+    pool: DatabasePool,
+    state_sender_map: FeedUpdateJobStateSenderMap,
+    community: &str,
+    config: CommunityConfig,
+    queue_capacity: usize,
+    caches: CommunityCaches,
+) -> FeedUpdateJobQueue {
+    // (1) MPSC bounded channel: job queue
     // Allow only one job per community to avoid rate limiting
-    let (job_sender, mut job_receiver) = mpsc::unbounded_channel::<FeedIdentifier>();
+    let (job_sender, mut job_receiver) = job_queue(format!("feed_update:{}", community), queue_capacity);
+    let usage = AccountUsage::new(&config);
 
     task::spawn(async move {
-        while let Some(id) = job_receiver.recv().await {
-            let state_sender = state_sender_map
-                .read()
-                .await
-                .get(&id)
-                .expect("job state sender not found")
-                .clone();
+        // Pending jobs with the accounts of their feeds
+        let mut pending: Vec<(TracedJob<FeedIdentifier>, Option<i32>)> = Vec::new();
+        let mut last_account = None;
+        loop {
+            // Wait for a job if none is pending, then take all the jobs queued
+            if pending.is_empty() {
+                let Some(job) = job_receiver.recv().await else {
+                    break;
+                };
+                pending.push(with_account(&pool, job).await);
+            }
+            while let Some(job) = job_receiver.try_recv() {
+                pending.push(with_account(&pool, job).await);
+            }
+
+            // Defer all jobs if every account is near its limit
+            let accounts = pending.iter().map(|(_, account)| *account).collect::<Vec<_>>();
+            let index = match pick_next_job(&accounts, last_account, &usage) {
+                Ok(index) => index,
+                Err(wait) => {
+                    tracing::info!("All accounts are near rate limits, waiting for {} s", wait.as_secs());
+                    time::sleep(wait.max(Duration::from_secs(1))).await;
+                    continue;
+                }
+            };
+            let (TracedJob { job: id, span }, account_id) = pending.remove(index);
+            last_account = Some(account_id);
+            job_receiver.start();
 
-            let result = update_feed(pool.clone(), &id, state_sender.clone(), DEFAULT_DELAY_MS).await;
+            let span = tracing::info_span!(parent: &span, "feed_update", job = next_job_id(), feed = %id);
+            async {
+                let state_sender = state_sender_map
+                    .read()
+                    .await
+                    .get(&id)
+                    .expect("job state sender not found")
+                    .clone();
 
-            if let Err(e) = result {
-                tracing::error!("Feed update job failed: {}. {}", id, e);
-                let _ = state_sender.send(FeedUpdateJobState::Failed { error: e.to_string() });
+                let result = update_feed(pool.clone(), &caches, &id, state_sender.clone(), &config, &usage).await;
+
+                if let Err(e) = &result {
+                    tracing::error!("Feed update job failed: {}. {}", id, e);
+                    let _ = state_sender.send(FeedUpdateJobState::Failed { error: e.to_string() });
+                }
+                match record_failure(&pool, &id, result.as_ref().err(), config.pause_after_failures) {
+                    Ok(Some(feed)) => {
+                        let pool = pool.clone();
+                        task::spawn(
+                            async move {
+                                if let Err(e) = notify_feed_paused(pool, feed).await {
+                                    tracing::warn!("Failed to notify webhooks: {}", e);
+                                }
+                            }
+                            .in_current_span(),
+                        );
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to record the update result of feed {}: {}", id, e),
+                }
             }
+            .instrument(span)
+            .await;
         }
     });
 
     job_sender
 }
 
+/// Record the error of the failed update on the feed, or clear it after a successful one.
+/// Stop watching the feed if it failed too many times in a row, returning the paused feed.
+fn record_failure(
+    pool: &DatabasePool,
+    id: &FeedIdentifier,
+    error: Option<&ServerError>,
+    pause_after_failures: i32,
+) -> Result<Option<FeedView>> {
+    let db = &mut pool.get()?;
+    let Some(error) = error else {
+        FeedFailure::clear(db, &id.community, id.feed_id)?;
+        return Ok(None);
+    };
+    let count = FeedFailure::record(db, &id.community, id.feed_id, &error.to_string())?;
+    if pause_after_failures <= 0 || count < pause_after_failures {
+        return Ok(None);
+    }
+    if !FeedFailure::pause(db, &id.community, id.feed_id)? {
+        return Ok(None);
+    }
+    tracing::warn!("Feed {} stopped being watched after {} failed updates", id, count);
+    Ok(Some(FeedWrapper::from_id(db, id)?.view()))
+}
+
+/// Find the account of the feed to schedule the job. Jobs of missing feeds fail later when they run.
+async fn with_account(pool: &DatabasePool, job: TracedJob<FeedIdentifier>) -> (TracedJob<FeedIdentifier>, Option<i32>) {
+    let pool = pool.clone();
+    let id = job.job.clone();
+    let account_id = task::spawn_blocking(move || {
+        let mut db = pool.get().ok()?;
+        FeedWrapper::from_id(&mut db, &id).ok()?.account_id(&mut db).ok()?
+    })
+    .await
+    .ok()
+    .flatten();
+    (job, account_id)
+}
+
 async fn update_feed(
     pool: DatabasePool,
+    caches: &CommunityCaches,
     id: &FeedIdentifier,
     state_sender: FeedUpdateJobStateSender,
-    delay_ms: u64,
+    config: &CommunityConfig,
+    usage: &AccountUsage,
 ) -> Result<()> {
     // 1. Prepare the feed
-    let (feed, mut context) = {
+    let (feed, account_id, mut context) = {
         let db = &mut pool.get().expect("cannot access database");
         let feed = FeedWrapper::from_id(db, id)?;
+        let account_id = feed.account_id(db)?;
 
         // 2. Handle before update
         feed.handle_before_update(db)?;
 
         // 3. Refresh the account if necessary
         feed.refresh_account(db).await?;
-        let context = feed.get_context(db)?;
-        (feed, context)
+        let context = feed.get_context(db, caches).await?;
+        (feed, account_id, context)
     };
 
-    // 4. Fetch and save the feed
+    // 4. Fetch and save the feed, up to the page limit if the rest can be fetched in the next update
+    let max_pages = load_settings(&pool)
+        .max_pages_per_update
+        .or(config.max_pages_per_update)
+        .filter(|_| feed.resumable());
     let mut fetched = 0;
     let mut results = Vec::new();
     tracing::info!("Feed update job started: {}", id);
     loop {
+        // Wait if the account has reached its limit
+        let wait = usage.wait_time(account_id);
+        if !wait.is_zero() {
+            tracing::info!("Account reached rate limit, waiting for {} s", wait.as_secs());
+            time::sleep(wait).await;
+        }
+        usage.record(account_id);
+
         let (result, new_context) =
             util::retry(|| util::timeout(update_feed_inner(pool.clone(), &feed, &context))).await?;
         context = new_context;
@@ -187,7 +327,16 @@ async fn update_feed(
         if should_stop {
             break;
         }
+        if max_pages.is_some_and(|max_pages| results.len() >= max_pages) {
+            tracing::info!(
+                "Feed {} reached {} pages in this update, continuing next time",
+                feed.id(),
+                results.len()
+            );
+            break;
+        }
 
+        let delay_ms = load_settings(&pool).feed_update_delay_ms.unwrap_or(config.delay_ms);
         time::sleep(Duration::from_millis(delay_ms)).await;
     }
 
@@ -199,10 +348,24 @@ async fn update_feed(
 
     tracing::info!("Feed update job done: {}. Updated {} posts", id, fetched);
     state_sender.send(FeedUpdateJobState::Success { fetched })?;
+
+    // 6. Notify webhooks of new posts in the background
+    let post_ids = results.into_iter().flat_map(|r| r.post_ids).collect::<Vec<_>>();
+    if !post_ids.is_empty() {
+        let feed = feed.view();
+        task::spawn(
+            async move {
+                if let Err(e) = notify_webhooks(pool, feed, post_ids).await {
+                    tracing::warn!("Failed to notify webhooks: {}", e);
+                }
+            }
+            .in_current_span(),
+        );
+    }
     Ok(())
 }
 
-async fn update_feed_inner(
+pub(super) async fn update_feed_inner(
     pool: DatabasePool,
     feed: &FeedWrapper,
     context: &FeedContextWrapper,
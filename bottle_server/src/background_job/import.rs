@@ -0,0 +1,231 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde::Serialize;
+use tokio::{
+    sync::RwLock,
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_core::feed::FeedInfo;
+
+use crate::{
+    error::Result,
+    payload::{ImportRequest, NewFeedRequest},
+    state::AppState,
+    util::{self, FeedWrapper},
+};
+
+use super::{
+    download::send_image_download,
+    entity::{next_job_id, GeneralJobState},
+    feed::update_feed_inner,
+    panda::queue_panda_download,
+    util::load_settings,
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportJobStateResponse {
+    pub id: u64,
+    pub community: String,
+    pub state: GeneralJobState,
+    /// Posts fetched from the community.
+    pub fetched: u64,
+    /// Works added to the library, excluding those already archived.
+    pub added: u64,
+    pub error: Option<String>,
+}
+
+pub type ImportJobStateMap = Arc<RwLock<HashMap<u64, ImportJobStateResponse>>>;
+
+/// Used in server handler. Start a one-time import of the collection of an account into the library.
+/// Unlike feed updates, the whole collection is fetched regardless of posts saved before,
+/// and the temporary feed holding the posts is deleted when the import is done.
+/// Only one import runs for each community at a time.
+pub async fn send_import(app_state: &AppState, request: ImportRequest) -> Result<ImportJobStateResponse> {
+    let community = request.params.community();
+    if !request.params.is_collection() {
+        return Err(bottle_core::Error::InvalidEndpoint(format!(
+            "Import of {} feed other than bookmarks, likes or favorites",
+            community
+        ))
+        .into());
+    }
+
+    let state = {
+        let mut state_map = app_state.import_job_state_map.write().await;
+        if let Some(running) = state_map.values().find(|state| {
+            state.community == community && matches!(state.state, GeneralJobState::Ready | GeneralJobState::Running)
+        }) {
+            return Err(anyhow::anyhow!(
+                "Import job {} of {} is already running",
+                running.id,
+                community
+            ))?;
+        }
+        let state = ImportJobStateResponse {
+            id: next_job_id(),
+            community: community.to_string(),
+            state: GeneralJobState::Ready,
+            fetched: 0,
+            added: 0,
+            error: None,
+        };
+        state_map.insert(state.id, state.clone());
+        state
+    };
+
+    let app_state = app_state.clone();
+    let job_id = state.id;
+    let span = tracing::info_span!("import", job = job_id, community);
+    task::spawn(
+        async move {
+            if let Err(e) = import(&app_state, job_id, request).await {
+                tracing::error!("Import job failed: {}", e);
+                update_state(&app_state, job_id, |state| {
+                    state.state = GeneralJobState::Failed;
+                    state.error = Some(e.to_string());
+                })
+                .await;
+            }
+        }
+        .instrument(span),
+    );
+
+    Ok(state)
+}
+
+async fn import(app_state: &AppState, job_id: u64, request: ImportRequest) -> Result<()> {
+    let community = request.params.community();
+
+    // 1. Add a temporary feed not watched, to fetch and save posts with
+    let feed = {
+        let db = &mut app_state.pool.get()?;
+        let account_id = match request.account_id {
+            Some(account_id) => Some(account_id),
+            None => util::default_account_id(db, community)?,
+        };
+        let feed_request = NewFeedRequest {
+            params: request.params.clone(),
+            info: FeedInfo {
+                name: Some(format!("Import {}", job_id)),
+                watching: false,
+                first_fetch_limit: None,
+                icon_url: None,
+                color: None,
+                clear_icon: false,
+                clear_color: false,
+                require_media: false,
+                min_resolution: None,
+            },
+            account_id,
+        };
+        FeedWrapper::add(db, &feed_request)?
+    };
+
+    let result = import_feed(app_state, job_id, &feed, &request).await;
+
+    // 2. Delete the temporary feed anyway, the posts and works are kept
+    {
+        let db = &mut app_state.pool.get()?;
+        FeedWrapper::delete(db, &feed.id())?;
+    }
+    let (fetched, added, post_ids) = result?;
+
+    tracing::info!("Import job done. Fetched {} posts, added {} works", fetched, added);
+    update_state(app_state, job_id, |state| state.state = GeneralJobState::Success).await;
+
+    // 3. Queue downloads of the imported works
+    if request.download && added > 0 {
+        if community == "panda" {
+            for post_id in post_ids.iter() {
+                let task = {
+                    let db = &mut app_state.pool.get()?;
+                    bottle_panda::download::get_download_task(db, post_id.parse()?)
+                };
+                match task {
+                    Ok(task) => {
+                        if let Err(e) = queue_panda_download(app_state, task).await {
+                            tracing::warn!("Failed to queue download of panda gallery {}: {}", post_id, e);
+                        }
+                    }
+                    Err(bottle_core::Error::ObjectAlreadyExists(_)) => {}
+                    Err(e) => tracing::warn!("Failed to queue download of panda gallery {}: {}", post_id, e),
+                }
+            }
+        } else if let Err(e) = send_image_download(app_state).await {
+            tracing::warn!("Failed to queue image download: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Fetch the whole feed and add every post to the library.
+/// Returns the number of fetched posts, added works, and the IDs of posts added.
+async fn import_feed(
+    app_state: &AppState,
+    job_id: u64,
+    feed: &FeedWrapper,
+    request: &ImportRequest,
+) -> Result<(u64, u64, Vec<String>)> {
+    let community = request.params.community();
+    let mut context = {
+        let db = &mut app_state.pool.get()?;
+        feed.refresh_account(db).await?;
+        feed.get_context(db, &app_state.caches()).await?
+    };
+
+    tracing::info!("Import job started: {}", feed.id());
+    update_state(app_state, job_id, |state| state.state = GeneralJobState::Running).await;
+    let mut fetched = 0;
+    let mut added = 0;
+    let mut added_ids = Vec::new();
+    let mut results = Vec::new();
+    loop {
+        let (result, new_context) =
+            util::retry(|| util::timeout(update_feed_inner(app_state.pool.clone(), feed, &context))).await?;
+        context = new_context;
+        fetched += result.post_ids.len() as u64;
+
+        // Add the posts to the library, skipping those already archived
+        for post_id in result.post_ids.iter() {
+            match util::add_post_to_library(app_state, community, post_id, None).await {
+                Ok(_) => {
+                    added += 1;
+                    added_ids.push(post_id.clone());
+                }
+                Err(bottle_core::Error::ObjectAlreadyExists(_)) => {}
+                Err(e) => tracing::warn!("Failed to add {} post {} to library: {}", community, post_id, e),
+            }
+        }
+        update_state(app_state, job_id, |state| {
+            state.fetched = fetched;
+            state.added = added;
+        })
+        .await;
+
+        let should_stop = result.should_stop || request.max_posts.is_some_and(|max| fetched >= max);
+        results.push(result);
+        if should_stop {
+            break;
+        }
+
+        let delay_ms = load_settings(&app_state.pool)
+            .feed_update_delay_ms
+            .unwrap_or(app_state.config.community(community).delay_ms);
+        time::sleep(Duration::from_millis(delay_ms)).await;
+    }
+
+    {
+        let db = &mut app_state.pool.get()?;
+        feed.handle_after_update(db, results.iter())?;
+    }
+    Ok((fetched, added, added_ids))
+}
+
+async fn update_state(app_state: &AppState, job_id: u64, f: impl FnOnce(&mut ImportJobStateResponse)) {
+    if let Some(state) = app_state.import_job_state_map.write().await.get_mut(&job_id) {
+        f(state);
+    }
+}
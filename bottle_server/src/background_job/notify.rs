@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::json;
+
+use bottle_core::feed::{FeedView, PostView, WebhookKind, WebhookView};
+use bottle_util::http::client_builder;
+
+use crate::{error::Result, state::DatabasePool, util};
+
+use super::util::MAX_NOTIFIED_POSTS;
+
+/// Summary of a feed update sent to generic webhooks.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedUpdateSummary {
+    pub community: String,
+    pub feed_id: i32,
+    pub feed_name: Option<String>,
+    pub post_count: usize,
+    pub post_ids: Vec<String>,
+}
+
+impl FeedUpdateSummary {
+    pub fn new(feed: &FeedView, post_ids: Vec<String>) -> Self {
+        Self {
+            community: feed.community.clone(),
+            feed_id: feed.feed_id,
+            feed_name: feed.name.clone(),
+            post_count: post_ids.len(),
+            post_ids,
+        }
+    }
+}
+
+/// Notify webhooks of the feed, and webhooks of the artists of new posts.
+/// Failures of each webhook are logged but not propagated.
+pub async fn notify_webhooks(pool: DatabasePool, feed: FeedView, post_ids: Vec<String>) -> Result<()> {
+    // 1. Find webhooks and posts to notify
+    let (feed_webhooks, user_webhooks, bot_tokens, posts) = {
+        let db = &mut pool.get()?;
+        let feed_webhooks = bottle_library::Webhook::of_feed(db, &feed.community, feed.feed_id)?;
+        let user_webhooks = bottle_library::Webhook::of_users(db, &feed.community)?;
+        if feed_webhooks.is_empty() && user_webhooks.is_empty() {
+            return Ok(());
+        }
+        let ids = feed_webhooks
+            .iter()
+            .chain(user_webhooks.iter())
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+        let bot_tokens = bottle_library::Webhook::bot_tokens(db, &ids)?;
+        let posts = util::get_posts(db, &feed.community, &post_ids)?;
+        (feed_webhooks, user_webhooks, bot_tokens, posts)
+    };
+
+    let mut notifications = Vec::new();
+    for webhook in feed_webhooks {
+        let title = format!(
+            "{}: {} new posts",
+            feed.name
+                .clone()
+                .unwrap_or(format!("{} feed {}", feed.community, feed.feed_id)),
+            post_ids.len()
+        );
+        let summary = FeedUpdateSummary::new(&feed, post_ids.clone());
+        notifications.push((webhook, title, summary, posts.clone()));
+    }
+    for webhook in user_webhooks {
+        let Some(user_id) = &webhook.user_id else {
+            continue;
+        };
+        let user_posts = posts
+            .iter()
+            .filter(|post| is_by_user(post, user_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        if user_posts.is_empty() {
+            continue;
+        }
+        let title = format!("{}: {} new posts by {}", feed.community, user_posts.len(), user_id);
+        let summary = FeedUpdateSummary::new(&feed, user_posts.iter().map(|p| p.post_id.clone()).collect());
+        notifications.push((webhook, title, summary, user_posts));
+    }
+
+    // 2. Send notifications
+    let client = client_builder().build()?;
+    for (webhook, title, summary, posts) in notifications {
+        let result = match webhook.kind {
+            WebhookKind::Generic => util::timeout(send_generic(&client, &webhook, &summary)).await,
+            WebhookKind::Discord => util::timeout(send_discord(&client, &webhook, &title, &posts)).await,
+            WebhookKind::Telegram => {
+                let bot_token = bot_tokens.get(&webhook.id).map(String::as_str).unwrap_or_default();
+                util::timeout(send_telegram(&client, &webhook, bot_token, &title, &posts)).await
+            }
+        };
+        match result {
+            Ok(_) => tracing::info!("Notified webhook {} of {} new posts", webhook.id, summary.post_count),
+            Err(e) => tracing::warn!("Failed to notify webhook {}: {}", webhook.id, e),
+        }
+    }
+    Ok(())
+}
+
+/// Failure of a feed after which it stopped being watched, sent to generic webhooks.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedPausedSummary {
+    pub community: String,
+    pub feed_id: i32,
+    pub feed_name: Option<String>,
+    pub failure_count: i32,
+    pub error: Option<String>,
+}
+
+/// Notify webhooks of the feed that it stopped being watched after failing repeatedly.
+/// Templates of generic webhooks are for new posts, so the summary is always sent as is.
+pub async fn notify_feed_paused(pool: DatabasePool, feed: FeedView) -> Result<()> {
+    let (webhooks, bot_tokens) = {
+        let db = &mut pool.get()?;
+        let webhooks = bottle_library::Webhook::of_feed(db, &feed.community, feed.feed_id)?;
+        let ids = webhooks.iter().map(|w| w.id).collect::<Vec<_>>();
+        (webhooks, bottle_library::Webhook::bot_tokens(db, &ids)?)
+    };
+    if webhooks.is_empty() {
+        return Ok(());
+    }
+
+    let title = format!(
+        "{}: stopped watching after {} failed updates. {}",
+        feed.name
+            .clone()
+            .unwrap_or(format!("{} feed {}", feed.community, feed.feed_id)),
+        feed.failure_count,
+        feed.last_error.clone().unwrap_or_default()
+    );
+    let summary = FeedPausedSummary {
+        community: feed.community.clone(),
+        feed_id: feed.feed_id,
+        feed_name: feed.name.clone(),
+        failure_count: feed.failure_count,
+        error: feed.last_error.clone(),
+    };
+    let client = client_builder().build()?;
+    for webhook in webhooks {
+        let request = match webhook.kind {
+            WebhookKind::Generic => client.post(&webhook.url).json(&summary),
+            WebhookKind::Discord => client
+                .post(&webhook.url)
+                .json(&json!({ "content": truncate(&title, 1900) })),
+            WebhookKind::Telegram => client
+                .post(telegram_url(&webhook, &bot_tokens, "sendMessage"))
+                .json(&json!({ "chat_id": webhook.chat_id, "text": title })),
+        };
+        let result = util::timeout(async {
+            request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(without_url)
+        })
+        .await;
+        match result {
+            Ok(_) => tracing::info!("Notified webhook {} of paused feed {}", webhook.id, feed.feed_id),
+            Err(e) => tracing::warn!("Failed to notify webhook {}: {}", webhook.id, e),
+        }
+    }
+    Ok(())
+}
+
+async fn send_generic(client: &reqwest::Client, webhook: &WebhookView, summary: &FeedUpdateSummary) -> Result<()> {
+    let body = match &webhook.template {
+        Some(template) => render_template(template, summary)?,
+        None => serde_json::to_string(summary)?,
+    };
+    client
+        .post(&webhook.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(without_url)?;
+    Ok(())
+}
+
+/// Send one message with an embed for each post, which shows the thumbnail and links to the post.
+async fn send_discord(client: &reqwest::Client, webhook: &WebhookView, title: &str, posts: &[PostView]) -> Result<()> {
+    let embeds = posts
+        .iter()
+        .take(MAX_NOTIFIED_POSTS)
+        .map(|post| {
+            json!({
+                "title": truncate(&post.text, 200),
+                "url": util::post_url(post),
+                "thumbnail": post.thumbnail_url.as_ref().map(|url| json!({ "url": url })),
+                "timestamp": post.created_date.to_rfc3339(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let body = json!({
+        "content": with_remaining(title, posts.len()),
+        "embeds": embeds,
+    });
+    client
+        .post(&webhook.url)
+        .json(&body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(without_url)?;
+    Ok(())
+}
+
+/// Send a photo for each post with a caption linking to the post.
+/// Fall back to a text message if the post has no thumbnail or Telegram cannot fetch it.
+/// The bot API URL is built here from the token, which is kept out of the errors.
+async fn send_telegram(
+    client: &reqwest::Client,
+    webhook: &WebhookView,
+    bot_token: &str,
+    title: &str,
+    posts: &[PostView],
+) -> Result<()> {
+    let chat_id = webhook.chat_id.clone().unwrap_or_default();
+    let bot_url = format!("{}/bot{}", webhook.url, bot_token);
+    let send_message = |text: String| {
+        let request = client
+            .post(format!("{}/sendMessage", bot_url))
+            .json(&json!({ "chat_id": chat_id, "text": text }));
+        async move {
+            request
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(without_url)
+        }
+    };
+
+    send_message(with_remaining(title, posts.len())).await?;
+    for post in posts.iter().take(MAX_NOTIFIED_POSTS) {
+        let caption = match util::post_url(post) {
+            Some(url) => format!("{}\n{}", truncate(&post.text, 200), url),
+            None => truncate(&post.text, 200),
+        };
+        if let Some(thumbnail_url) = &post.thumbnail_url {
+            let response = client
+                .post(format!("{}/sendPhoto", bot_url))
+                .json(&json!({ "chat_id": chat_id, "photo": thumbnail_url, "caption": caption }))
+                .send()
+                .await
+                .map_err(without_url)?;
+            if response.status().is_success() {
+                continue;
+            }
+        }
+        send_message(caption).await?;
+    }
+    Ok(())
+}
+
+fn telegram_url(webhook: &WebhookView, bot_tokens: &HashMap<i32, String>, method: &str) -> String {
+    let bot_token = bot_tokens.get(&webhook.id).map(String::as_str).unwrap_or_default();
+    format!("{}/bot{}/{}", webhook.url, bot_token, method)
+}
+
+/// Webhook URLs may hold secrets like bot tokens, which shouldn't be logged with the errors.
+fn without_url(error: reqwest::Error) -> reqwest::Error {
+    error.without_url()
+}
+
+/// Replace the placeholders in the template with JSON-escaped values,
+/// so that they can be put inside JSON strings of the template.
+fn render_template(template: &str, summary: &FeedUpdateSummary) -> Result<String> {
+    let escape = |s: &str| -> Result<String> {
+        let quoted = serde_json::to_string(s)?;
+        Ok(quoted[1..quoted.len() - 1].to_string())
+    };
+    let feed_name = summary.feed_name.clone().unwrap_or_default();
+    Ok(template
+        .replace("{community}", &escape(&summary.community)?)
+        .replace("{feed_id}", &summary.feed_id.to_string())
+        .replace("{feed_name}", &escape(&feed_name)?)
+        .replace("{post_count}", &summary.post_count.to_string())
+        .replace("{post_ids}", &escape(&summary.post_ids.join(", "))?))
+}
+
+/// Whether the post is by the artist, either as the author or tagged as the artist.
+fn is_by_user(post: &PostView, user_id: &str) -> bool {
+    post.user_id.as_deref() == Some(user_id)
+        || post.tags.as_ref().is_some_and(|tags| {
+            tags.iter()
+                .any(|tag| tag == user_id || tag.strip_prefix("artist:") == Some(user_id))
+        })
+}
+
+fn with_remaining(title: &str, count: usize) -> String {
+    if count > MAX_NOTIFIED_POSTS {
+        format!("{} (showing {})", title, MAX_NOTIFIED_POSTS)
+    } else {
+        title.to_string()
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        format!("{}…", text.chars().take(max_chars).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,80 @@
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use std::path::{Path, PathBuf};
+
+use bottle_library::ImageText;
+
+use crate::{error::Result, state::DatabasePool};
+
+use super::entity::next_job_id;
+
+/// Number of images processed in each run.
+const OCR_BATCH_SIZE: i64 = 200;
+/// Only pages of panda galleries are processed, which are mostly manga and doujinshi.
+const OCR_SOURCE: &str = "panda";
+
+/// Extract the text of downloaded gallery pages with the OCR command at startup and periodically.
+pub fn listen_ocr(pool: DatabasePool, image_dir: impl AsRef<Path>, command: PathBuf, interval: Duration) {
+    let image_dir = image_dir.as_ref().to_path_buf();
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("ocr", job = next_job_id());
+            if let Err(e) = extract_image_texts(pool.clone(), &image_dir, &command)
+                .instrument(span)
+                .await
+            {
+                tracing::error!("Failed to extract image texts: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Extract the text of a batch of downloaded gallery pages, one at a time since OCR is CPU intensive.
+/// The command is an executable which takes the image path as the only argument, and prints the text,
+/// e.g. a script running `tesseract <path> - -l jpn` or sending the image to an OCR service.
+/// Pages the command fails on are saved without text and not retried, but the run stops if it cannot be started.
+pub async fn extract_image_texts(pool: DatabasePool, image_dir: &Path, command: &Path) -> Result<()> {
+    let tasks = {
+        let db = &mut pool.get()?;
+        ImageText::tasks(db, OCR_SOURCE, OCR_BATCH_SIZE)?
+    };
+    if tasks.is_empty() {
+        tracing::info!("OCR job done. No images to process");
+        return Ok(());
+    }
+
+    tracing::info!("OCR job started. Processing {} images", tasks.len());
+    let mut failure_count = 0;
+    for task in tasks.iter() {
+        let output = tokio::process::Command::new(command)
+            .arg(image_dir.join(&task.path))
+            .output()
+            .await?;
+        let content = if output.status.success() {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        } else {
+            tracing::warn!(
+                "OCR command exited with {} on image {}: {}",
+                output.status,
+                task.image_id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            failure_count += 1;
+            String::new()
+        };
+
+        let db = &mut pool.get()?;
+        ImageText::save(db, task.image_id, &content)?;
+    }
+    tracing::info!(
+        "OCR job done. Processed {} images, {} failed",
+        tasks.len() - failure_count,
+        failure_count
+    );
+    Ok(())
+}
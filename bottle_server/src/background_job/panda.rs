@@ -2,28 +2,35 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use futures::StreamExt;
+use futures::{FutureExt, StreamExt};
 use itertools::Itertools;
 use serde::Serialize;
 use tokio::{
     sync::{mpsc, watch, RwLock},
     task,
-    time::{self, Duration},
+    time::{self, Duration, Instant},
 };
+use tracing::Instrument;
 
 use bottle_core::{library::RemoteImage, Database};
 use bottle_download::{DownloadTask, LocalImage};
-use bottle_panda::download::{PandaDownloadTask, PandaImageTask};
+use bottle_panda::{
+    download::{PandaDownloadJobRecord, PandaDownloadTask, PandaImageTask},
+    PandaCache,
+};
 use panda_client::PandaClient;
 
 use crate::util;
 use crate::{
-    error::Result,
+    config::DownloadConfig,
+    error::{Result, ServerError},
     state::{AppState, DatabasePool},
 };
 
-use super::entity::GeneralJobState;
-use super::util::{DEFAULT_DELAY_MS, DEFAULT_DOWNLOAD_CONCURRENCY, DEFAULT_DOWNLOAD_OVERWRITE};
+use super::entity::{next_job_id, DownloadJobResponse, GeneralJobState, TracedJob};
+use super::queue::{job_queue, JobQueue};
+use super::thumbnail::ThumbnailPool;
+use super::util::{link_duplicate_image, load_settings};
 
 const GUESSED_PAGE_SIZE: i32 = 20;
 
@@ -50,6 +57,9 @@ pub enum PandaDownloadJobState {
         total: i32,
         success: i32,
         failure: i32,
+        /// When the download started, and the number of images already downloaded before.
+        started: Instant,
+        skipped: i32,
     },
     Success {
         total: i32,
@@ -81,11 +91,55 @@ impl PandaDownloadJobState {
         )
     }
 
+    /// Record of the finished state, to be kept across restarts.
+    fn record(&self, gid: i64, title: String) -> Option<PandaDownloadJobRecord> {
+        let (state, total, success, error) = match self {
+            PandaDownloadJobState::Success { total } => ("success", *total, *total, None),
+            PandaDownloadJobState::PartialSuccess { total, success, .. } => ("partial", *total, *success, None),
+            PandaDownloadJobState::Failed { error } => ("failed", 0, 0, Some(error.clone())),
+            _ => return None,
+        };
+        Some(PandaDownloadJobRecord {
+            gid,
+            title,
+            state: state.to_string(),
+            total,
+            success,
+            error,
+            failures: vec![],
+        })
+    }
+
+    fn from_record(record: &PandaDownloadJobRecord) -> Self {
+        match record.state.as_str() {
+            "success" => PandaDownloadJobState::Success { total: record.total },
+            "partial" => PandaDownloadJobState::PartialSuccess {
+                total: record.total,
+                success: record.success,
+                failures: record
+                    .failures
+                    .iter()
+                    .map(|(index, error)| PandaImageDownloadFailure {
+                        gid: record.gid,
+                        index: *index,
+                        error: error.clone(),
+                    })
+                    .collect(),
+            },
+            _ => PandaDownloadJobState::Failed {
+                error: record.error.clone().unwrap_or_default(),
+            },
+        }
+    }
+
     fn new_running(tasks: &[PandaImageTask]) -> Self {
+        let skipped = tasks.iter().filter(|t| t.downloaded).count() as i32;
         Self::Running {
             total: tasks.len() as i32,
-            success: tasks.iter().filter(|t| t.downloaded).count() as i32,
+            success: skipped,
             failure: 0,
+            started: Instant::now(),
+            skipped,
         }
     }
 
@@ -98,33 +152,19 @@ impl PandaDownloadJobState {
     }
 
     fn adding_success(&self) -> Self {
-        match self {
-            PandaDownloadJobState::Running {
-                total,
-                success,
-                failure,
-            } => PandaDownloadJobState::Running {
-                total: *total,
-                success: *success + 1,
-                failure: *failure,
-            },
-            _ => self.clone(),
+        let mut state = self.clone();
+        if let PandaDownloadJobState::Running { success, .. } = &mut state {
+            *success += 1;
         }
+        state
     }
 
     fn adding_failure(&self) -> Self {
-        match self {
-            PandaDownloadJobState::Running {
-                total,
-                success,
-                failure,
-            } => PandaDownloadJobState::Running {
-                total: *total,
-                success: *success,
-                failure: *failure + 1,
-            },
-            _ => self.clone(),
+        let mut state = self.clone();
+        if let PandaDownloadJobState::Running { failure, .. } = &mut state {
+            *failure += 1;
         }
+        state
     }
 }
 
@@ -157,6 +197,7 @@ impl PandaDownloadJobStateResponse {
                 total,
                 success,
                 failure,
+                ..
             } => Self {
                 state: GeneralJobState::Running,
                 metadata_fetched: true,
@@ -196,14 +237,47 @@ impl PandaDownloadJobStateResponse {
     }
 }
 
-pub type PandaDownloadJobQueue = mpsc::UnboundedSender<PandaDownloadJob>;
+impl DownloadJobResponse {
+    pub fn from_panda(id: &PandaGalleryID, title: String, state: &PandaDownloadJobState) -> Self {
+        let started = match state {
+            PandaDownloadJobState::Running { started, skipped, .. } => Some((*started, *skipped as u64)),
+            _ => None,
+        };
+        let response = PandaDownloadJobStateResponse::new(id, title, state);
+        Self {
+            id: format!("panda/{}", id.0),
+            community: Some("panda".to_string()),
+            target: response.title,
+            state: response.state,
+            total: response.total_images as u64,
+            success: response.success_images as u64,
+            failure: response.failure_images as u64,
+            error: response.error,
+            ..Default::default()
+        }
+        .with_progress(started)
+    }
+}
+
+pub type PandaDownloadJobQueue = JobQueue<PandaDownloadJob>;
 pub type PandaDownloadJobStateSender = watch::Sender<PandaDownloadJobState>;
 pub type PandaDownloadJobStateReceiver = watch::Receiver<PandaDownloadJobState>;
 pub type PandaDownloadJobStateSenderMap = Arc<RwLock<HashMap<PandaGalleryID, PandaDownloadJobStateSender>>>;
 pub type PandaDownloadJobStateReceiverMap = Arc<RwLock<HashMap<PandaGalleryID, PandaDownloadJobStateReceiver>>>;
 
-/// Used in server handler, return true if the job sent successfully
+/// Used in server handler, return true if the job sent successfully.
+/// Fail with `QueueFull` if the queue is full.
 pub async fn send_panda_download(app_state: &AppState, task: PandaDownloadTask) -> Result<bool> {
+    send_panda_download_inner(app_state, task, false).await
+}
+
+/// Used by producers sending many jobs, return true if the job sent successfully.
+/// Wait for the queue to have room if it is full.
+pub async fn queue_panda_download(app_state: &AppState, task: PandaDownloadTask) -> Result<bool> {
+    send_panda_download_inner(app_state, task, true).await
+}
+
+async fn send_panda_download_inner(app_state: &AppState, task: PandaDownloadTask, wait: bool) -> Result<bool> {
     let job = PandaDownloadJob(task);
     let id = job.id();
 
@@ -251,46 +325,107 @@ pub async fn send_panda_download(app_state: &AppState, task: PandaDownloadTask)
             .await
             .insert(id.clone(), job.0.title.clone());
     }
-    app_state.panda_download_queue.send(job)?;
+    let sent = if wait {
+        app_state.panda_download_queue.send_wait(TracedJob::new(job)).await
+    } else {
+        app_state.panda_download_queue.send(TracedJob::new(job))
+    };
+    if let Err(e) = sent {
+        // Finish the job, so that it can be sent again later
+        if let Some(state_sender) = app_state.panda_download_state_sender_map.read().await.get(&id) {
+            let _ = state_sender.send(PandaDownloadJobState::Failed { error: e.to_string() });
+        }
+        return Err(e);
+    }
 
     Ok(true)
 }
 
+/// Restore the results of finished jobs before restart, so that they are still shown.
+pub async fn restore_panda_download_jobs(app_state: &AppState) -> Result<()> {
+    let records = {
+        let db = &mut app_state.pool.get()?;
+        bottle_panda::download::job_records(db)?
+    };
+
+    let mut sender_map = app_state.panda_download_state_sender_map.write().await;
+    let mut receiver_map = app_state.panda_download_state_map.write().await;
+    let mut title_map = app_state.panda_gallery_title_map.write().await;
+    for record in records.iter() {
+        let id = PandaGalleryID(record.gid);
+        let (state_sender, state_receiver) = watch::channel(PandaDownloadJobState::from_record(record));
+        sender_map.insert(id.clone(), state_sender);
+        receiver_map.insert(id.clone(), state_receiver);
+        title_map.insert(id, record.title.clone());
+    }
+
+    tracing::info!("Restored {} finished panda download jobs", records.len());
+    Ok(())
+}
+
 /// Set up before server started
+#[allow(clippy::too_many_arguments)]
 pub fn listen_panda_download(
     pool: DatabasePool,
     state_sender_map: PandaDownloadJobStateSenderMap,
     image_dir: impl AsRef<Path>,
+    config: DownloadConfig,
+    delay_ms: u64,
+    queue_capacity: usize,
+    thumbnail_pool: ThumbnailPool,
+    panda_cache: Arc<RwLock<PandaCache>>,
 ) -> Result<PandaDownloadJobQueue> {
-    // (1) MPSC unbounded channel: job queue
-    let (job_sender, mut job_receiver) = mpsc::unbounded_channel::<PandaDownloadJob>();
+    // (1) MPSC bounded channel: job queue
+    let (job_sender, mut job_receiver) = job_queue::<PandaDownloadJob>("panda_download", queue_capacity);
 
     let image_dir = image_dir.as_ref().to_path_buf();
     task::spawn(async move {
-        while let Some(job) = job_receiver.recv().await {
-            let state_sender = state_sender_map
-                .read()
-                .await
-                .get(&job.id())
-                .expect("job state sender not found")
-                .clone();
-
-            let gid = job.id().0;
-            let result = download_gallery(
-                &pool,
-                state_sender.clone(),
-                job,
-                &image_dir,
-                DEFAULT_DOWNLOAD_CONCURRENCY,
-                DEFAULT_DOWNLOAD_OVERWRITE,
-                DEFAULT_DELAY_MS,
-            )
-            .await;
+        while let Some(TracedJob { job, span }) = job_receiver.recv().await {
+            job_receiver.start();
+            let span = tracing::info_span!(parent: &span, "panda_download", job = next_job_id(), gallery = job.id().0);
+            async {
+                let state_sender = state_sender_map
+                    .read()
+                    .await
+                    .get(&job.id())
+                    .expect("job state sender not found")
+                    .clone();
+
+                let gid = job.id().0;
+                let title = job.0.title.clone();
+                let settings = load_settings(&pool);
+                let result = download_gallery(
+                    &pool,
+                    &panda_cache,
+                    state_sender.clone(),
+                    job,
+                    &image_dir,
+                    settings.download_concurrency.unwrap_or(config.concurrency).max(1),
+                    settings.panda_download_delay_ms.unwrap_or(delay_ms),
+                    &config,
+                    &thumbnail_pool,
+                )
+                .await;
+
+                if let Err(e) = result {
+                    tracing::error!("Panda download job failed: Gallery {}. {}", gid, e);
+                    let _ = state_sender.send(PandaDownloadJobState::Failed { error: e.to_string() });
+                }
 
-            if let Err(e) = result {
-                tracing::error!("Panda download job failed: Gallery {}. {}", gid, e);
-                let _ = state_sender.send(PandaDownloadJobState::Failed { error: e.to_string() });
+                // Keep the result across restarts
+                let record = state_sender.borrow().record(gid, title);
+                if let Some(record) = record {
+                    let saved = pool
+                        .get()
+                        .map_err(ServerError::from)
+                        .and_then(|mut db| Ok(bottle_panda::download::save_job_record(&mut db, &record)?));
+                    if let Err(e) = saved {
+                        tracing::warn!("Failed to save panda download job result: Gallery {}. {}", gid, e);
+                    }
+                }
             }
+            .instrument(span)
+            .await;
         }
     });
 
@@ -305,16 +440,21 @@ enum PandaDownloadMessage {
 #[allow(clippy::too_many_arguments)]
 async fn download_gallery(
     pool: &DatabasePool,
+    panda_cache: &RwLock<PandaCache>,
     // (2) Watch channel: job state
     state_sender: watch::Sender<PandaDownloadJobState>,
     job: PandaDownloadJob,
     image_dir: impl AsRef<Path>,
     max_concurrency: usize,
-    overwrite: bool,
     delay_ms: u64,
+    config: &DownloadConfig,
+    thumbnail_pool: &ThumbnailPool,
 ) -> Result<()> {
     use bottle_core::feed::Account;
 
+    let overwrite = config.overwrite;
+    let reserved_space = config.reserved_space_mb * 1024 * 1024;
+
     // 1. Get account and then panda client
     let client = {
         let db = &mut pool.get()?;
@@ -322,7 +462,7 @@ async fn download_gallery(
         let auth = account.auth(db)?.ok_or(bottle_core::Error::NotLoggedIn(
             "Downloading panda gallery needs an account".to_string(),
         ))?;
-        PandaClient::new(auth)?
+        panda_cache.write().await.client(account.id, &auth)?
     };
 
     // 1. Fetch incomplete post/media metadata, and update the task
@@ -333,13 +473,54 @@ async fn download_gallery(
         fetch_metadata(db, &client, state_sender.clone(), task, delay_ms).await
     }?;
 
+    // Fail fast if the gallery won't fit in the free space, keeping some space reserved
+    if let Some(remaining_size) = gallery_task.remaining_size() {
+        bottle_download::ensure_space(image_dir.as_ref(), remaining_size + reserved_space)?;
+    }
+
+    // Skip the images still backing off after failing in the last runs, unless retried by the user
+    let backoff = if gallery_task.ignore_backoff {
+        HashMap::new()
+    } else {
+        let db = &mut pool.get()?;
+        bottle_panda::download::backoff_indices(db, gallery_task.gid, config.image_backoff_secs)?
+    };
+
     // 2. Prepare download futures
     // (3) MPSC channel: monitor subtask results
     let (subtask_sender, mut subtask_receiver) = mpsc::channel(1);
-    let image_tasks = gallery_task.image_tasks.iter().filter(|task| !task.downloaded);
+    let selected_tasks = gallery_task
+        .image_tasks
+        .iter()
+        .filter(|task| gallery_task.is_selected(task.index))
+        .cloned()
+        .collect::<Vec<_>>();
+    let backoff_failures = selected_tasks
+        .iter()
+        .filter(|task| !task.downloaded)
+        .filter_map(|task| {
+            backoff.get(&task.index).map(|retry_date| PandaImageDownloadFailure {
+                gid: gallery_task.gid,
+                index: task.index,
+                error: format!("Backing off until {} after failures", retry_date.and_utc().to_rfc3339()),
+            })
+        })
+        .collect::<Vec<_>>();
+    if !backoff_failures.is_empty() {
+        tracing::info!(
+            "Panda gallery {}: Skipping {} images backing off after failures",
+            gallery_task.gid,
+            backoff_failures.len()
+        );
+    }
+    let image_tasks = selected_tasks
+        .iter()
+        .filter(|task| !task.downloaded && !backoff.contains_key(&task.index))
+        .sorted_by_key(|task| task.index);
     let futures = image_tasks
         .clone()
         .map(|task| {
+            let index = task.index;
             download_image_wrapped(
                 pool.clone(),
                 client.clone(),
@@ -348,13 +529,22 @@ async fn download_gallery(
                 &gallery_task,
                 image_dir.as_ref(),
                 overwrite,
+                thumbnail_pool,
             )
+            // Results come in the order of completion, so keep the index along with them
+            .map(move |result| (index, result))
         })
         .collect::<Vec<_>>();
-    let stream = futures::stream::iter(futures).buffer_unordered(max_concurrency);
+    let mut stream = if gallery_task.ordered {
+        futures::stream::iter(futures).buffered(max_concurrency).left_stream()
+    } else {
+        futures::stream::iter(futures)
+            .buffer_unordered(max_concurrency)
+            .right_stream()
+    };
 
     // 3. Listen to subtask results and update job state
-    let mut state = PandaDownloadJobState::new_running(&gallery_task.image_tasks);
+    let mut state = PandaDownloadJobState::new_running(&selected_tasks);
     state_sender.send(state.clone())?;
     let state_sender2 = state_sender.clone();
     let state_update_task = task::spawn(async move {
@@ -373,42 +563,101 @@ async fn download_gallery(
         gallery_task.gid,
         image_tasks.clone().count()
     );
-    let images = stream.collect::<Vec<_>>().await;
+    // Stop early when most images fail, which likely means the account is banned, dropping the images in progress
+    let mut images = Vec::new();
+    let mut tripped = false;
+    while let Some(image) = stream.next().await {
+        images.push(image);
+        let failure_count = images.iter().filter(|(_, result)| result.is_err()).count();
+        if images.len() >= config.breaker_min_attempts
+            && failure_count as f64 >= images.len() as f64 * config.breaker_failure_ratio
+        {
+            tripped = true;
+            break;
+        }
+    }
+    drop(stream);
     state_update_task.abort();
 
     // 5. Collect failures and send final state
-    let failures = image_tasks
-        .zip(images.iter())
-        .filter_map(|(task, result)| {
+    let failures = images
+        .iter()
+        .filter_map(|(index, result)| {
             result.as_ref().err().map(|e| PandaImageDownloadFailure {
-                gid: task.gid,
-                index: task.index,
+                gid: gallery_task.gid,
+                index: *index,
                 error: e.to_string(),
             })
         })
+        .sorted_by_key(|failure| failure.index)
         .collect::<Vec<_>>();
+    // Keep the failures, so that only the failed images can be retried later
+    let attempted = images.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+    if let Err(e) = save_failures(pool, gallery_task.gid, &attempted, &failures) {
+        tracing::warn!(
+            "Failed to save download failures of panda gallery {}: {}",
+            gallery_task.gid,
+            e
+        );
+    }
+    if tripped {
+        let error = format!(
+            "Stopped after {} of {} attempted images failed, the account may be banned. Last error: {}",
+            failures.len(),
+            images.len(),
+            images
+                .iter()
+                .rev()
+                .find_map(|(_, result)| result.as_ref().err())
+                .map(|e| e.to_string())
+                .unwrap_or_default()
+        );
+        tracing::error!("Panda download job stopped: Gallery {}. {}", gallery_task.gid, error);
+        state_sender2.send(PandaDownloadJobState::Failed { error })?;
+        return Ok(());
+    }
+    let failures = failures
+        .into_iter()
+        .chain(backoff_failures)
+        .sorted_by_key(|failure| failure.index)
+        .collect::<Vec<_>>();
+    let total = gallery_task.selected_count();
     if failures.is_empty() {
         tracing::info!(
             "Panda download job done: Gallery {}. Downloaded all {} images",
             gallery_task.gid,
-            gallery_task.media_count
+            total
         );
-        state_sender2.send(PandaDownloadJobState::Success {
-            total: gallery_task.media_count,
-        })?;
+        state_sender2.send(PandaDownloadJobState::Success { total })?;
     } else {
         tracing::warn!(
             "Panda download job done: Gallery {}. Downloaded {} images, failed to download {} images",
             gallery_task.gid,
-            gallery_task.media_count - failures.len() as i32,
+            total - failures.len() as i32,
             failures.len()
         );
-        state_sender2.send(PandaDownloadJobState::new_partial(gallery_task.media_count, failures))?;
+        state_sender2.send(PandaDownloadJobState::new_partial(total, failures))?;
     };
 
     Ok(())
 }
 
+fn save_failures(
+    pool: &DatabasePool,
+    gid: i64,
+    attempted: &[i32],
+    failures: &[PandaImageDownloadFailure],
+) -> Result<()> {
+    let db = &mut pool.get()?;
+    let failures = failures
+        .iter()
+        .map(|failure| (failure.index, failure.error.clone()))
+        .collect::<Vec<_>>();
+    bottle_panda::download::save_failures(db, gid, attempted, &failures)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn download_image_wrapped(
     pool: DatabasePool,
     client: PandaClient,
@@ -417,8 +666,9 @@ async fn download_image_wrapped(
     gallery_task: &PandaDownloadTask,
     image_dir: impl AsRef<Path>,
     overwrite: bool,
+    thumbnail_pool: &ThumbnailPool,
 ) -> Result<LocalImage> {
-    let result = download_image(&pool, &client, task, gallery_task, image_dir, overwrite).await;
+    let result = download_image(&pool, &client, task, gallery_task, image_dir, overwrite, thumbnail_pool).await;
     let _ = match &result {
         Ok(_) => {
             tracing::info!("Panda gallery {}: Downloaded image {}", task.gid, task.index);
@@ -444,25 +694,26 @@ async fn download_image(
     gallery_task: &PandaDownloadTask,
     image_dir: impl AsRef<Path>,
     overwrite: bool,
+    thumbnail_pool: &ThumbnailPool,
 ) -> Result<LocalImage> {
     // 1. Fetch image info
     let result = client.image(task.gid as u64, &task.token, task.index as u32).await?;
 
     // 2. Download image
-    let index_prefix = format!(
-        "{:0width$}",
-        task.index,
-        width = gallery_task.media_count.to_string().len()
-    );
     let download_task = DownloadTask {
         url: result.url.clone(),
         root_dir: image_dir.as_ref().to_path_buf(),
         subdir: PathBuf::from("panda").join(task.gid.to_string()),
-        filename: PathBuf::from(format!("{}_{}", index_prefix, result.filename)),
+        filename: PathBuf::from(bottle_download::indexed_filename(
+            task.index,
+            gallery_task.media_count,
+            &result.filename,
+        )),
         // Only a placeholder, create image record after downloading
         image_id: 0,
     };
-    let local_image = util::retry(|| util::timeout(bottle_download::download_image(&download_task, overwrite))).await?;
+    let fetched = util::retry(|| util::timeout(bottle_download::fetch_image(&download_task, overwrite))).await?;
+    let local_image = thumbnail_pool.process(download_task, fetched).await?;
 
     // 3. Update image and panda_media
     let db = &mut pool.get()?;
@@ -485,6 +736,11 @@ async fn download_image(
         bottle_library::update_work_from_local_image(db, gallery_task.work_id, &local_image)?;
     }
 
+    // 4. Share the file with an identical image if there is any
+    if let Err(e) = link_duplicate_image(pool, image_dir, image_id, &local_image).await {
+        tracing::warn!("Failed to deduplicate image {}: {}", local_image.relpath, e);
+    }
+
     Ok(local_image)
 }
 
@@ -508,7 +764,14 @@ async fn fetch_metadata<'a>(
     let mut page_size: Option<i32> = None;
     let mut media_count = task.media_count;
     let mut existing_indices = task.image_tasks.iter().map(|m| m.index).collect::<HashSet<_>>();
-    let mut pages = pages_to_fetch(media_count, page_count, page_size, &existing_indices);
+    let selected_indices = task.indices.clone();
+    let mut pages = pages_to_fetch(
+        media_count,
+        page_count,
+        page_size,
+        &existing_indices,
+        selected_indices.as_ref(),
+    );
     tracing::info!(
         "Panda gallery {}: Incomplete pages: {}",
         task.gid,
@@ -546,6 +809,7 @@ async fn fetch_metadata<'a>(
         }
         if !task.has_detail || count_inconsistent {
             bottle_panda::download::update_gallery(db, &result.gallery, &result.detail)?;
+            task.file_size = Some(result.detail.file_size as i64);
         }
 
         // 2. Check if media tokens are consistent. If not, refetch all media
@@ -601,7 +865,13 @@ async fn fetch_metadata<'a>(
         }
 
         // Determine next page to fetch
-        pages = pages_to_fetch(media_count, page_count, page_size, &existing_indices);
+        pages = pages_to_fetch(
+            media_count,
+            page_count,
+            page_size,
+            &existing_indices,
+            selected_indices.as_ref(),
+        );
 
         // Send state update
         state = PandaDownloadJobState::FetchingMetadata {
@@ -626,11 +896,15 @@ fn pages_to_fetch(
     page_count: Option<i32>,
     page_size: Option<i32>,
     existing_indices: &HashSet<i32>,
+    selected_indices: Option<&HashSet<i32>>,
 ) -> Vec<i32> {
     let page_size = page_size.unwrap_or(GUESSED_PAGE_SIZE);
     let page_count = page_count.unwrap_or(guessed_page_count(media_count, page_size));
 
-    let required_indices = HashSet::from_iter(0..media_count)
+    // Only fetch pages with selected media, if there is a selection
+    let required_indices = (0..media_count)
+        .filter(|index| selected_indices.is_none_or(|indices| indices.contains(index)))
+        .collect::<HashSet<_>>()
         .difference(existing_indices)
         .cloned()
         .collect::<HashSet<_>>();
@@ -0,0 +1,126 @@
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_core::feed::Account;
+use panda_client::PandaClient;
+
+use crate::{error::Result, state::AppState, util};
+
+use super::entity::next_job_id;
+use super::panda::queue_panda_download;
+use super::util::load_settings;
+
+/// Number of galleries checked in each run.
+const PANDA_CHECK_BATCH_SIZE: i64 = 100;
+/// Galleries are checked again after the days since the last check.
+const PANDA_RECHECK_DAYS: i64 = 30;
+
+/// Check archived panda galleries at startup and periodically.
+pub fn listen_panda_check(app_state: AppState, interval: Duration) {
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("panda_check", job = next_job_id());
+            if let Err(e) = check_panda_galleries(&app_state, None).instrument(span).await {
+                tracing::error!("Failed to check panda galleries: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Refetch the detail of a batch of archived panda galleries, to find out if they are expunged or replaced.
+/// If `follow` (or the setting) is on, the newer version of a replaced gallery is added to the library
+/// as a newer version of the archived work, and downloaded.
+pub async fn check_panda_galleries(app_state: &AppState, follow: Option<bool>) -> Result<()> {
+    let settings = load_settings(&app_state.pool);
+    let follow = follow.or(settings.panda_follow_newer_versions).unwrap_or_default();
+    let delay_ms = settings
+        .panda_download_delay_ms
+        .unwrap_or(app_state.config.panda.delay_ms);
+
+    let (client, galleries) = {
+        let db = &mut app_state.pool.get()?;
+        let checked_before = chrono::Utc::now().naive_utc() - chrono::Duration::days(PANDA_RECHECK_DAYS);
+        let galleries = bottle_panda::download::galleries_to_check(db, checked_before, PANDA_CHECK_BATCH_SIZE)?;
+        if galleries.is_empty() {
+            tracing::info!("Panda check job done. No galleries to check");
+            return Ok(());
+        }
+
+        let account = bottle_panda::PandaAccount::default(db)?;
+        let auth = account.auth(db)?.ok_or(bottle_core::Error::NotLoggedIn(
+            "Checking panda galleries needs an account".to_string(),
+        ))?;
+        let client = app_state.panda_cache.write().await.client(account.id, &auth)?;
+        (client, galleries)
+    };
+
+    tracing::info!("Panda check job started. Checking {} galleries", galleries.len());
+    let mut replaced_count = 0;
+    let mut followed_count = 0;
+    for (gid, token) in galleries.iter() {
+        match check_gallery(app_state, &client, *gid, token, follow, delay_ms).await {
+            Ok((replaced, followed)) => {
+                replaced_count += replaced as usize;
+                followed_count += followed as usize;
+            }
+            Err(e) => tracing::warn!("Failed to check panda gallery {}: {}", gid, e),
+        }
+        time::sleep(Duration::from_millis(delay_ms)).await;
+    }
+
+    tracing::info!(
+        "Panda check job done. Checked {} galleries, {} replaced, {} newer versions added",
+        galleries.len(),
+        replaced_count,
+        followed_count
+    );
+    Ok(())
+}
+
+/// Returns whether the gallery is replaced, and whether its newer version is added to the library.
+async fn check_gallery(
+    app_state: &AppState,
+    client: &PandaClient,
+    gid: i64,
+    token: &str,
+    follow: bool,
+    delay_ms: u64,
+) -> Result<(bool, bool)> {
+    let result = util::retry(|| util::timeout(client.gallery(gid as u64, token, 0))).await?;
+    let updated = {
+        let db = &mut app_state.pool.get()?;
+        bottle_panda::download::update_gallery(db, &result.gallery, &result.detail)?
+    };
+    if let Some(reason) = &updated.invisible_reason {
+        tracing::info!("Panda gallery {} is invisible: {}", gid, reason);
+    }
+
+    let (Some(newer_gid), Some(newer_token)) = (updated.newer_gallery_id, updated.newer_token) else {
+        return Ok((false, false));
+    };
+    tracing::info!("Panda gallery {} is replaced by gallery {}", gid, newer_gid);
+    if !follow {
+        return Ok((true, false));
+    }
+
+    // Fetch the newer version, add it to the library and download it
+    time::sleep(Duration::from_millis(delay_ms)).await;
+    let result = util::retry(|| util::timeout(client.gallery(newer_gid as u64, &newer_token, 0))).await?;
+    let task = {
+        let db = &mut app_state.pool.get()?;
+        bottle_panda::download::add_newer_version(db, gid, &result)?;
+        bottle_panda::download::get_download_task(db, newer_gid)
+    };
+    match task {
+        Ok(task) => {
+            queue_panda_download(app_state, task).await?;
+        }
+        Err(bottle_core::Error::ObjectAlreadyExists(_)) => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok((true, true))
+}
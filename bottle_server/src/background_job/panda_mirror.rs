@@ -0,0 +1,161 @@
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_library::Album;
+use bottle_panda::PandaFeed;
+
+use crate::{
+    error::Result,
+    state::AppState,
+    util::{self, FeedIdentifier},
+};
+
+use super::entity::next_job_id;
+use super::feed::{queue_feed_update, FeedUpdateJobState};
+use super::panda::queue_panda_download;
+
+/// Mirror panda favorites feeds to their albums at startup and periodically.
+pub fn listen_panda_mirror(app_state: AppState, interval: Duration) {
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("panda_mirror", job = next_job_id());
+            if let Err(e) = mirror_panda_favorites(&app_state).instrument(span).await {
+                tracing::error!("Failed to mirror panda favorites: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Used in server handler. Mirror the feed to its album in the background.
+pub fn send_panda_mirror(app_state: &AppState, feed: PandaFeed) {
+    let app_state = app_state.clone();
+    let span = tracing::info_span!("panda_mirror", job = next_job_id(), feed = feed.id);
+    task::spawn(
+        async move {
+            if let Err(e) = mirror_feed(&app_state, &feed).await {
+                tracing::warn!("Failed to mirror panda feed {}: {}", feed.id, e);
+            }
+        }
+        .instrument(span),
+    );
+}
+
+/// Update every panda favorites feed mirrored to an album, then archive, download and add its new galleries to the album,
+/// keeping the album in the order of the favorites.
+pub async fn mirror_panda_favorites(app_state: &AppState) -> Result<()> {
+    let feeds = {
+        let db = &mut app_state.pool.get()?;
+        PandaFeed::mirrored(db)?
+    };
+    if feeds.is_empty() {
+        return Ok(());
+    }
+
+    tracing::info!("Panda mirror job started. Mirroring {} feeds", feeds.len());
+    for feed in feeds.iter() {
+        if let Err(e) = mirror_feed(app_state, feed).await {
+            tracing::warn!("Failed to mirror panda feed {}: {}", feed.id, e);
+        }
+    }
+    tracing::info!("Panda mirror job done");
+    Ok(())
+}
+
+/// Mirror the feed to its album, after updating the feed and waiting for the update to finish.
+async fn mirror_feed(app_state: &AppState, feed: &PandaFeed) -> Result<()> {
+    let Some(album_id) = feed.mirror_album_id else {
+        return Ok(());
+    };
+
+    // 1. Update the feed, or wait for the update already running
+    let id = FeedIdentifier::new("panda", feed.id);
+    queue_feed_update(app_state, id.clone()).await?;
+    let mut state_receiver = app_state
+        .feed_update_state_map
+        .read()
+        .await
+        .get(&id)
+        .expect("job state receiver not found")
+        .clone();
+    let state = state_receiver.wait_for(|state| state.finished()).await?.clone();
+    if let FeedUpdateJobState::Failed { error } = state {
+        tracing::warn!(
+            "Feed update of panda feed {} failed, mirroring saved galleries: {}",
+            feed.id,
+            error
+        );
+    }
+
+    // 2. Archive the galleries not in the library yet
+    let entries = {
+        let db = &mut app_state.pool.get()?;
+        feed.mirror_entries(db)?
+    };
+    let mut work_ids = Vec::new();
+    let mut added = 0;
+    for (gid, work_id) in entries.iter() {
+        let work_id = match work_id {
+            Some(work_id) => *work_id,
+            None => match util::add_post_to_library(app_state, "panda", &gid.to_string(), None).await {
+                Ok(response) => {
+                    added += 1;
+                    match response.works.and_then(|works| works.first().map(|work| work.id)) {
+                        Some(work_id) => work_id,
+                        None => continue,
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to add panda gallery {} to library: {}", gid, e);
+                    continue;
+                }
+            },
+        };
+        work_ids.push(work_id);
+    }
+
+    // 3. Add the works missing from the album, and order the album by the favorites
+    {
+        let db = &mut app_state.pool.get()?;
+        let album_work_ids = Album::work_ids(db, album_id)?;
+        let new_work_ids = work_ids
+            .iter()
+            .filter(|work_id| !album_work_ids.contains(work_id))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !new_work_ids.is_empty() {
+            Album::add_works(db, album_id, new_work_ids)?;
+        }
+        Album::reorder_works(db, album_id, &work_ids)?;
+    }
+
+    // 4. Download the galleries not downloaded yet
+    let mut queued = 0;
+    for (gid, _) in entries.iter() {
+        let task = {
+            let db = &mut app_state.pool.get()?;
+            bottle_panda::download::get_download_task(db, *gid)
+        };
+        match task {
+            Ok(task) => {
+                if queue_panda_download(app_state, task).await? {
+                    queued += 1;
+                }
+            }
+            Err(bottle_core::Error::ObjectAlreadyExists(_)) => {}
+            Err(e) => tracing::warn!("Failed to queue download of panda gallery {}: {}", gid, e),
+        }
+    }
+
+    tracing::info!(
+        "Mirrored panda feed {} to album {}. Added {} galleries to library, queued {} downloads",
+        feed.id,
+        album_id,
+        added,
+        queued
+    );
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use crate::{error::Result, state::AppState};
+
+use super::entity::next_job_id;
+
+/// Number of users fetched in each run.
+const PIXIV_PROFILE_BATCH_SIZE: i64 = 100;
+/// Profiles are fetched again after the days since the last fetch.
+const PIXIV_PROFILE_REFRESH_DAYS: i64 = 90;
+/// Delay between the requests, to avoid the rate limit of pixiv.
+const PIXIV_PROFILE_DELAY: Duration = Duration::from_secs(1);
+
+/// Fill the profiles of pixiv users in the library at startup and periodically.
+pub fn listen_pixiv_profile(app_state: AppState, interval: Duration) {
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("pixiv_profile", job = next_job_id());
+            if let Err(e) = fetch_pixiv_profiles(&app_state).instrument(span).await {
+                tracing::error!("Failed to fetch pixiv profiles: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Fetch the profiles of a batch of pixiv users of archived works, which are saved from posts without
+/// descriptions or URLs. Failures of single users are left to the next run.
+pub async fn fetch_pixiv_profiles(app_state: &AppState) -> Result<()> {
+    let pool = &app_state.pool;
+    let tasks = {
+        let db = &mut pool.get()?;
+        let updated_before = chrono::Utc::now().naive_utc() - chrono::Duration::days(PIXIV_PROFILE_REFRESH_DAYS);
+        bottle_pixiv::profile_tasks(db, updated_before, PIXIV_PROFILE_BATCH_SIZE)?
+    };
+    if tasks.is_empty() {
+        tracing::info!("Pixiv profile job done. No users to fetch");
+        return Ok(());
+    }
+    let client = {
+        let db = &mut pool.get()?;
+        let cache = &mut app_state.pixiv_cache.write().await;
+        bottle_pixiv::profile_client(db, cache).await?
+    };
+    let Some(client) = client else {
+        tracing::info!("Pixiv profile job skipped. No pixiv account");
+        return Ok(());
+    };
+
+    tracing::info!("Pixiv profile job started. Fetching {} users", tasks.len());
+    let mut failure_count = 0;
+    for user_id in tasks.iter() {
+        let db = &mut pool.get()?;
+        if let Err(e) = bottle_pixiv::fetch_profile(db, &client, *user_id).await {
+            tracing::warn!("Failed to fetch profile of pixiv user {}: {}", user_id, e);
+            failure_count += 1;
+        }
+        time::sleep(PIXIV_PROFILE_DELAY).await;
+    }
+    tracing::info!(
+        "Pixiv profile job done. Fetched {} users, {} failed",
+        tasks.len() - failure_count,
+        failure_count
+    );
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use serde::Serialize;
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError},
+        Notify,
+    },
+    time::Duration,
+};
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use crate::error::Result;
+
+use super::entity::TracedJob;
+
+/// Delay suggested to clients before sending jobs again to a full queue.
+const QUEUE_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Sender of a bounded background job queue, so that jobs can't pile up without limit if the job stalls.
+/// Jobs are counted as queued until they start, including those taken from the channel to be scheduled.
+#[derive(Debug)]
+pub struct JobQueue<T> {
+    name: String,
+    sender: mpsc::Sender<TracedJob<T>>,
+    queued: Arc<AtomicUsize>,
+    started: Arc<Notify>,
+}
+
+#[derive(Debug)]
+pub struct JobReceiver<T> {
+    receiver: mpsc::Receiver<TracedJob<T>>,
+    queued: Arc<AtomicUsize>,
+    started: Arc<Notify>,
+}
+
+/// Number of jobs waiting in a queue, for the job status.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobQueueStateResponse {
+    pub name: String,
+    pub queued: usize,
+    pub capacity: usize,
+}
+
+/// A job is rejected since the queue is full, responded with 429 and `Retry-After`.
+#[derive(Debug)]
+pub struct QueueFull {
+    pub name: String,
+    pub retry_after: Duration,
+}
+
+impl std::fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Job queue {} is full, retry after {} s",
+            self.name,
+            self.retry_after.as_secs()
+        )
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
+/// Create a job queue holding at most `capacity` jobs.
+pub fn job_queue<T>(name: impl Into<String>, capacity: usize) -> (JobQueue<T>, JobReceiver<T>) {
+    let (sender, receiver) = mpsc::channel(capacity.max(1));
+    let queued = Arc::new(AtomicUsize::new(0));
+    let started = Arc::new(Notify::new());
+    (
+        JobQueue {
+            name: name.into(),
+            sender,
+            queued: queued.clone(),
+            started: started.clone(),
+        },
+        JobReceiver {
+            receiver,
+            queued,
+            started,
+        },
+    )
+}
+
+impl<T> Clone for JobQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            sender: self.sender.clone(),
+            queued: self.queued.clone(),
+            started: self.started.clone(),
+        }
+    }
+}
+
+impl<T> JobQueue<T> {
+    /// Send the job without waiting, or fail with `QueueFull` if the queue is full.
+    /// Used by endpoints sending a single job, so that the client can retry later.
+    pub fn send(&self, job: TracedJob<T>) -> Result<()> {
+        match self.try_send(job) {
+            Ok(_) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(QueueFull {
+                name: self.name.clone(),
+                retry_after: QUEUE_RETRY_AFTER,
+            })?,
+            Err(TrySendError::Closed(_)) => Err(anyhow::anyhow!("Job queue {} is closed", self.name))?,
+        }
+    }
+
+    /// Send the job, waiting until a queued job starts if the queue is full.
+    /// Used by producers sending many jobs in the background, so that none of them is dropped.
+    pub async fn send_wait(&self, mut job: TracedJob<T>) -> Result<()> {
+        loop {
+            // Register for the notification before trying, so that a job started in between is not missed
+            let started = self.started.notified();
+            match self.try_send(job) {
+                Ok(_) => return Ok(()),
+                Err(TrySendError::Full(returned)) => {
+                    job = returned;
+                    started.await;
+                }
+                Err(TrySendError::Closed(_)) => return Err(anyhow::anyhow!("Job queue {} is closed", self.name).into()),
+            }
+        }
+    }
+
+    fn try_send(&self, job: TracedJob<T>) -> std::result::Result<(), TrySendError<TracedJob<T>>> {
+        // Count the job before sending, so that the receiver never counts it down first
+        let queued = self.queued.fetch_add(1, Ordering::Relaxed);
+        let result = if self.sender.is_closed() {
+            Err(TrySendError::Closed(job))
+        } else if queued >= self.sender.max_capacity() {
+            Err(TrySendError::Full(job))
+        } else {
+            self.sender.try_send(job)
+        };
+        if result.is_err() {
+            self.queued.fetch_sub(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    pub fn state(&self) -> JobQueueStateResponse {
+        JobQueueStateResponse {
+            name: self.name.clone(),
+            queued: self.queued.load(Ordering::Relaxed),
+            capacity: self.sender.max_capacity(),
+        }
+    }
+}
+
+impl<T> JobReceiver<T> {
+    pub async fn recv(&mut self) -> Option<TracedJob<T>> {
+        self.receiver.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Option<TracedJob<T>> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Mark a received job as started, which is no longer counted as queued.
+    pub fn start(&self) {
+        self.queued.fetch_sub(1, Ordering::Relaxed);
+        self.started.notify_waiters();
+    }
+}
@@ -0,0 +1,92 @@
+use futures::stream::StreamExt;
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_library::{RemoteCheckTask, RemoteStatus};
+
+use crate::{error::Result, state::DatabasePool, util};
+
+use super::entity::next_job_id;
+
+/// Number of works checked in each run.
+const REMOTE_CHECK_BATCH_SIZE: i64 = 500;
+/// Works are checked again after the days since the last check.
+const REMOTE_RECHECK_DAYS: i64 = 30;
+const REMOTE_CHECK_CONCURRENCY: usize = 4;
+
+/// Check the remote sources of archived works at startup and periodically.
+pub fn listen_remote_check(pool: DatabasePool, interval: Duration) {
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("remote_check", job = next_job_id());
+            if let Err(e) = check_remote_sources(pool.clone()).instrument(span).await {
+                tracing::error!("Failed to check remote sources: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Check if the remote sources of a batch of archived works still exist, by the first image of each work.
+/// A work is recorded as vanished if its image is gone, while other failures are left to the next run.
+pub async fn check_remote_sources(pool: DatabasePool) -> Result<()> {
+    let tasks = {
+        let db = &mut pool.get()?;
+        let checked_before = chrono::Utc::now().naive_utc() - chrono::Duration::days(REMOTE_RECHECK_DAYS);
+        RemoteStatus::tasks(db, checked_before, REMOTE_CHECK_BATCH_SIZE)?
+    };
+    if tasks.is_empty() {
+        tracing::info!("Remote check job done. No works to check");
+        return Ok(());
+    }
+
+    tracing::info!("Remote check job started. Checking {} works", tasks.len());
+    let futures = tasks
+        .iter()
+        .map(|task| check_remote_source(&pool, task))
+        .collect::<Vec<_>>();
+    let results = futures::stream::iter(futures)
+        .buffer_unordered(REMOTE_CHECK_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    let vanished_count = results.iter().filter(|result| matches!(result, Ok(Some(true)))).count();
+    let unknown_count = results.iter().filter(|result| !matches!(result, Ok(Some(_)))).count();
+    tracing::info!(
+        "Remote check job done. Checked {} works, {} vanished, {} unknown",
+        tasks.len(),
+        vanished_count,
+        unknown_count
+    );
+    Ok(())
+}
+
+/// Returns whether the remote source has vanished, or None if it cannot be told.
+async fn check_remote_source(pool: &DatabasePool, task: &RemoteCheckTask) -> Result<Option<bool>> {
+    let status_code = match util::timeout(bottle_download::probe_remote(&task.url)).await {
+        Ok(status_code) => status_code,
+        Err(e) => {
+            tracing::warn!("Failed to check remote source of work {}: {}", task.work_id, e);
+            return Ok(None);
+        }
+    };
+    let vanished = match status_code {
+        200..=399 => false,
+        404 | 410 => true,
+        _ => {
+            tracing::warn!(
+                "Cannot tell if remote source of work {} exists, status {}",
+                task.work_id,
+                status_code
+            );
+            return Ok(None);
+        }
+    };
+
+    let db = &mut pool.get()?;
+    RemoteStatus::record(db, task.work_id, status_code as i32, vanished)?;
+    Ok(Some(vanished))
+}
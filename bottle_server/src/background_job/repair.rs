@@ -0,0 +1,77 @@
+use tokio::task;
+use tracing::Instrument;
+
+use std::path::Path;
+
+use crate::{error::Result, state::DatabasePool};
+
+use super::entity::next_job_id;
+
+/// Number of IDs shown in the logs of the repair, the rest are only counted.
+const LOG_SAMPLE_SIZE: usize = 10;
+
+/// Repair inconsistencies of the library at startup, which must finish before any feed update starts.
+/// Failures are logged without stopping the server, since the library is still usable.
+pub async fn repair_library(pool: DatabasePool, image_dir: impl AsRef<Path>, reset_missing_images: bool) {
+    let span = tracing::info_span!("repair", job = next_job_id());
+    if let Err(e) = run_repair(pool, image_dir.as_ref(), reset_missing_images)
+        .instrument(span)
+        .await
+    {
+        tracing::error!("Failed to repair library: {}", e);
+    }
+}
+
+async fn run_repair(pool: DatabasePool, image_dir: &Path, reset_missing_images: bool) -> Result<()> {
+    tracing::info!("Repair job started");
+    let image_dir = image_dir.to_path_buf();
+    let report = task::spawn_blocking(move || -> Result<_> {
+        let db = &mut pool.get()?;
+        Ok(bottle_library::repair_library(db, &image_dir, reset_missing_images)?)
+    })
+    .await??;
+
+    if !report.empty_work_ids.is_empty() {
+        tracing::warn!(
+            "Found {} works without any image, e.g. {}",
+            report.empty_work_ids.len(),
+            sample_ids(&report.empty_work_ids)
+        );
+    }
+    if !report.missing_file_image_ids.is_empty() {
+        let action = if report.reset_missing_files {
+            "reset to be downloaded again"
+        } else {
+            "not reset, enable `download.reset_missing_images` to download them again"
+        };
+        tracing::warn!(
+            "Found {} images with missing files, {}, e.g. {}",
+            report.missing_file_image_ids.len(),
+            action,
+            sample_ids(&report.missing_file_image_ids)
+        );
+    }
+    tracing::info!(
+        "Repair job done. Deleted {} watch list posts without sort index and {} orphaned media, \
+        found {} works without images and {} images with missing files",
+        report.unsorted_watch_list_posts,
+        report.orphaned_media,
+        report.empty_work_ids.len(),
+        report.missing_file_image_ids.len()
+    );
+    Ok(())
+}
+
+/// First IDs of the list for the logs, with the number of the rest.
+fn sample_ids(ids: &[i32]) -> String {
+    let sample = ids
+        .iter()
+        .take(LOG_SAMPLE_SIZE)
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    match ids.len().saturating_sub(LOG_SAMPLE_SIZE) {
+        0 => sample,
+        rest => format!("{} and {} more", sample, rest),
+    }
+}
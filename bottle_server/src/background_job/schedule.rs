@@ -0,0 +1,188 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use tokio::time::{Duration, Instant};
+
+use crate::config::CommunityConfig;
+
+/// Fraction of the limit from which an account is considered near its limit,
+/// so that its feeds are deferred in favor of feeds of other accounts.
+const NEAR_LIMIT_RATIO: f64 = 0.9;
+
+/// Requests made recently by each account of a community, used to keep accounts within rate limits.
+#[derive(Debug, Clone)]
+pub struct AccountUsage {
+    max_requests: Option<usize>,
+    window: Duration,
+    requests: Arc<Mutex<HashMap<Option<i32>, VecDeque<Instant>>>>,
+}
+
+impl AccountUsage {
+    pub fn new(config: &CommunityConfig) -> Self {
+        Self {
+            max_requests: config.max_requests,
+            window: Duration::from_secs(config.window_secs),
+            requests: Default::default(),
+        }
+    }
+
+    /// Record a request made by the account. `None` for feeds without accounts.
+    pub fn record(&self, account_id: Option<i32>) {
+        let mut requests = self.requests.lock().unwrap();
+        requests.entry(account_id).or_default().push_back(Instant::now());
+    }
+
+    /// Number of requests made by the account in the window.
+    pub fn count(&self, account_id: Option<i32>) -> usize {
+        let mut requests = self.requests.lock().unwrap();
+        let Some(times) = requests.get_mut(&account_id) else {
+            return 0;
+        };
+        while times.front().is_some_and(|time| time.elapsed() >= self.window) {
+            times.pop_front();
+        }
+        times.len()
+    }
+
+    pub fn near_limit(&self, account_id: Option<i32>) -> bool {
+        match self.near_threshold() {
+            Some(threshold) => self.count(account_id) >= threshold,
+            None => false,
+        }
+    }
+
+    fn near_threshold(&self) -> Option<usize> {
+        self.max_requests
+            .map(|max| ((max as f64 * NEAR_LIMIT_RATIO).ceil() as usize).max(1))
+    }
+
+    /// Time to wait until the account can make another request. Zero if it is within the limit.
+    pub fn wait_time(&self, account_id: Option<i32>) -> Duration {
+        self.wait_time_below(account_id, self.max_requests)
+    }
+
+    /// Time to wait until the account is no longer near its limit.
+    pub fn wait_time_near(&self, account_id: Option<i32>) -> Duration {
+        self.wait_time_below(account_id, self.near_threshold())
+    }
+
+    fn wait_time_below(&self, account_id: Option<i32>, threshold: Option<usize>) -> Duration {
+        let Some(threshold) = threshold.filter(|&threshold| threshold > 0) else {
+            return Duration::ZERO;
+        };
+        let count = self.count(account_id);
+        if count < threshold {
+            return Duration::ZERO;
+        }
+        // Wait until enough requests fall out of the window
+        let requests = self.requests.lock().unwrap();
+        requests
+            .get(&account_id)
+            .and_then(|times| times.get(count - threshold))
+            .map(|time| self.window.saturating_sub(time.elapsed()))
+            .unwrap_or_default()
+    }
+}
+
+/// Choose the next job from the pending jobs with their accounts.
+/// Skip jobs whose account is near its limit, and prefer a different account from the last job,
+/// so that feeds of different accounts are interleaved.
+/// Return the time to wait instead if all accounts are near their limits.
+pub fn pick_next_job(
+    accounts: &[Option<i32>],
+    last_account: Option<Option<i32>>,
+    usage: &AccountUsage,
+) -> std::result::Result<usize, Duration> {
+    let available = (0..accounts.len())
+        .filter(|&i| !usage.near_limit(accounts[i]))
+        .collect::<Vec<_>>();
+    if available.is_empty() {
+        let wait = accounts
+            .iter()
+            .map(|&account| usage.wait_time_near(account))
+            .min()
+            .unwrap_or_default();
+        return Err(wait);
+    }
+    let index = available
+        .iter()
+        .find(|&&i| Some(accounts[i]) != last_account)
+        .unwrap_or(&available[0]);
+    Ok(*index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn usage(max_requests: Option<usize>, window: Duration) -> AccountUsage {
+        AccountUsage {
+            max_requests,
+            window,
+            requests: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_wait_time_per_account() {
+        let usage = usage(Some(2), Duration::from_secs(60));
+        usage.record(Some(1));
+        assert_eq!(usage.wait_time(Some(1)), Duration::ZERO);
+        usage.record(Some(1));
+        let wait = usage.wait_time(Some(1));
+        assert!(wait > Duration::from_secs(59) && wait <= Duration::from_secs(60));
+        // Other accounts have their own windows
+        assert_eq!(usage.count(Some(2)), 0);
+        assert_eq!(usage.wait_time(Some(2)), Duration::ZERO);
+        assert_eq!(usage.wait_time(None), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_wait_time_unlimited() {
+        let usage = usage(None, Duration::from_secs(60));
+        usage.record(Some(1));
+        assert_eq!(usage.wait_time(Some(1)), Duration::ZERO);
+        assert!(!usage.near_limit(Some(1)));
+    }
+
+    #[test]
+    fn test_expire_out_of_window() {
+        let usage = usage(Some(1), Duration::from_millis(20));
+        usage.record(Some(1));
+        assert_eq!(usage.count(Some(1)), 1);
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        assert_eq!(usage.count(Some(1)), 0);
+        assert_eq!(usage.wait_time(Some(1)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pick_different_account() {
+        let usage = usage(None, Duration::from_secs(60));
+        let accounts = [Some(1), Some(1), Some(2)];
+        assert_eq!(pick_next_job(&accounts, None, &usage), Ok(0));
+        assert_eq!(pick_next_job(&accounts, Some(Some(1)), &usage), Ok(2));
+        assert_eq!(pick_next_job(&accounts, Some(Some(2)), &usage), Ok(0));
+        // Fall back to the first job if all of them have the last account
+        assert_eq!(pick_next_job(&[Some(1), Some(1)], Some(Some(1)), &usage), Ok(0));
+    }
+
+    #[test]
+    fn test_skip_account_near_limit() {
+        let usage = usage(Some(10), Duration::from_secs(60));
+        for _ in 0..9 {
+            usage.record(Some(1));
+        }
+        assert!(usage.near_limit(Some(1)));
+        assert_eq!(pick_next_job(&[Some(1), Some(2)], None, &usage), Ok(1));
+        assert_eq!(pick_next_job(&[Some(1), Some(2)], Some(Some(2)), &usage), Ok(1));
+    }
+
+    #[test]
+    fn test_wait_when_all_near_limit() {
+        let usage = usage(Some(1), Duration::from_secs(60));
+        usage.record(Some(1));
+        usage.record(Some(2));
+        let wait = pick_next_job(&[Some(1), Some(2)], None, &usage).unwrap_err();
+        assert!(wait > Duration::from_secs(59) && wait <= Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,36 @@
+use tokio::{
+    task,
+    time::{self, Duration},
+};
+use tracing::Instrument;
+
+use bottle_library::Statistics;
+
+use crate::{error::Result, state::DatabasePool};
+
+use super::entity::next_job_id;
+
+/// Refresh the statistics at startup and periodically.
+pub fn listen_statistics(pool: DatabasePool, interval: Duration) {
+    task::spawn(async move {
+        loop {
+            let span = tracing::info_span!("statistics", job = next_job_id());
+            if let Err(e) = refresh_statistics(pool.clone()).instrument(span).await {
+                tracing::error!("Failed to refresh statistics: {}", e);
+            }
+            time::sleep(interval).await;
+        }
+    });
+}
+
+/// Rebuild the statistics tables without blocking the runtime.
+pub async fn refresh_statistics(pool: DatabasePool) -> Result<()> {
+    let span = tracing::Span::current();
+    task::spawn_blocking(move || {
+        let _guard = span.enter();
+        let db = &mut pool.get()?;
+        Statistics::refresh(db)?;
+        Ok(())
+    })
+    .await?
+}
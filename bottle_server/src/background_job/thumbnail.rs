@@ -0,0 +1,96 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use serde::Serialize;
+use tokio::{sync::Semaphore, task};
+
+use bottle_download::{DownloadTask, FetchedImage, LocalImage};
+
+use crate::error::Result;
+
+/// Worker pool processing downloaded images, i.e. computing hashes and generating thumbnails.
+/// Images are processed on blocking threads, with the concurrency apart from that of network downloads.
+#[derive(Debug, Clone)]
+pub struct ThumbnailPool {
+    workers: usize,
+    /// Images wait for a permit in the order they are queued
+    semaphore: Arc<Semaphore>,
+    progress: Arc<ThumbnailProgress>,
+}
+
+#[derive(Debug, Default)]
+struct ThumbnailProgress {
+    queued: AtomicU64,
+    running: AtomicU64,
+    success: AtomicU64,
+    failure: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThumbnailJobStateResponse {
+    pub workers: usize,
+    pub queued: u64,
+    pub running: u64,
+    pub success: u64,
+    pub failure: u64,
+}
+
+impl ThumbnailPool {
+    pub fn new(workers: usize) -> Self {
+        let workers = workers.max(1);
+        Self {
+            workers,
+            semaphore: Arc::new(Semaphore::new(workers)),
+            progress: Default::default(),
+        }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Queue the fetched image and wait until it is processed.
+    pub async fn process(&self, task: DownloadTask, fetched: FetchedImage) -> Result<LocalImage> {
+        let progress = &self.progress;
+        progress.queued.fetch_add(1, Ordering::Relaxed);
+        let permit = self.semaphore.clone().acquire_owned().await;
+        progress.queued.fetch_sub(1, Ordering::Relaxed);
+        let permit = permit?;
+
+        progress.running.fetch_add(1, Ordering::Relaxed);
+        let result = task::spawn_blocking(move || {
+            let _permit = permit;
+            bottle_download::process_image(&task, &fetched)
+        })
+        .await;
+        progress.running.fetch_sub(1, Ordering::Relaxed);
+
+        match result {
+            Ok(Ok(local_image)) => {
+                progress.success.fetch_add(1, Ordering::Relaxed);
+                Ok(local_image)
+            }
+            Ok(Err(e)) => {
+                progress.failure.fetch_add(1, Ordering::Relaxed);
+                Err(e.into())
+            }
+            Err(e) => {
+                progress.failure.fetch_add(1, Ordering::Relaxed);
+                Err(e.into())
+            }
+        }
+    }
+
+    pub fn state(&self) -> ThumbnailJobStateResponse {
+        let progress = &self.progress;
+        ThumbnailJobStateResponse {
+            workers: self.workers,
+            queued: progress.queued.load(Ordering::Relaxed),
+            running: progress.running.load(Ordering::Relaxed),
+            success: progress.success.load(Ordering::Relaxed),
+            failure: progress.failure.load(Ordering::Relaxed),
+        }
+    }
+}
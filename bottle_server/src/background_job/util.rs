@@ -1,3 +1,99 @@
-pub const DEFAULT_DELAY_MS: u64 = 1000;
-pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 5;
-pub const DEFAULT_DOWNLOAD_OVERWRITE: bool = true;
+use std::path::Path;
+
+use bottle_core::{library::ContentRating, setting::Settings};
+use bottle_download::LocalImage;
+
+use crate::{
+    error::{Result, ServerError},
+    state::DatabasePool,
+};
+
+pub const MAX_NOTIFIED_POSTS: usize = 10;
+
+/// Read the runtime settings when a job starts, so that they take effect without restart.
+/// Fall back to the config if they cannot be read.
+pub fn load_settings(pool: &DatabasePool) -> Settings {
+    let result = pool
+        .get()
+        .map_err(ServerError::from)
+        .and_then(|mut conn| Ok(Settings::load(&mut conn)?));
+    result.unwrap_or_else(|e| {
+        tracing::warn!("Failed to load settings: {}", e);
+        Settings::default()
+    })
+}
+
+/// If an identical file is already in the library, replace the downloaded file with a hard link to it.
+pub async fn link_duplicate_image(
+    pool: &DatabasePool,
+    image_dir: impl AsRef<Path>,
+    image_id: i32,
+    local_image: &LocalImage,
+) -> Result<()> {
+    let Some(checksum) = &local_image.checksum else {
+        return Ok(());
+    };
+    let duplicate = {
+        let conn = &mut pool.get()?;
+        bottle_library::find_duplicate_image(conn, image_id, checksum)?
+    };
+    let Some(existing_path) = duplicate.and_then(|image| image.path) else {
+        return Ok(());
+    };
+
+    if bottle_download::link_duplicate(image_dir, &local_image.relpath, &existing_path).await? {
+        tracing::info!(
+            "Linked image {} at {} to identical file {}",
+            image_id,
+            local_image.relpath,
+            existing_path
+        );
+    }
+    Ok(())
+}
+
+/// Rate the work of a downloaded image with the local content classifier,
+/// if its community provides no content rating, e.g. Twitter.
+/// The classifier is an executable which takes the image path as the only argument,
+/// and prints one of `general`, `sensitive` and `explicit`.
+/// A work with multiple images is rated as the most explicit one among them.
+pub async fn classify_image(
+    pool: &DatabasePool,
+    classifier: impl AsRef<Path>,
+    image_dir: impl AsRef<Path>,
+    image_id: i32,
+    local_image: &LocalImage,
+) -> Result<()> {
+    let work_id = {
+        let conn = &mut pool.get()?;
+        bottle_library::get_unrated_work_id(conn, image_id)?
+    };
+    let Some(work_id) = work_id else {
+        return Ok(());
+    };
+
+    let output = tokio::process::Command::new(classifier.as_ref())
+        .arg(image_dir.as_ref().join(&local_image.relpath))
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Content classifier exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))?;
+    }
+    let content_rating = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<ContentRating>()?;
+
+    let conn = &mut pool.get()?;
+    bottle_library::raise_content_rating(conn, work_id, content_rating)?;
+    tracing::info!(
+        "Classified image {} of work {} as {}",
+        image_id,
+        work_id,
+        content_rating
+    );
+    Ok(())
+}
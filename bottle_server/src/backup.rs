@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use bottle_core::{
+    feed::{Account, FeedInfo, FeedView},
+    setting::Settings,
+    Database, Error as BottleError, Result as BottleResult,
+};
+use bottle_library::FeedGroup;
+use bottle_panda::PandaAccount;
+use bottle_pixiv::{PixivAccount, RefreshToken};
+use bottle_twitter::TwitterAccount;
+use panda_client::PandaCookie;
+use twitter_client::SessionCookie;
+
+use crate::{
+    payload::{FeedParams, NewFeedRequest},
+    util::{default_account_id, FeedWrapper, COMMUNITIES},
+};
+
+/// Version of the backup format, increased on incompatible changes.
+const BACKUP_VERSION: u32 = 1;
+
+/// Declarative backup of an instance: accounts, feeds, feed groups and settings.
+/// Posts and the library are not included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    pub accounts: Vec<AccountBackup>,
+    pub feeds: Vec<FeedBackup>,
+    pub feed_groups: Vec<FeedGroupBackup>,
+    pub settings: Settings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountBackup {
+    pub community: String,
+    /// ID in the exported instance, referred by the feeds.
+    pub account_id: i32,
+    /// Cookies or refresh token, omitted if secrets are excluded.
+    pub credential: Option<String>,
+    pub name: Option<String>,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedBackup {
+    /// ID in the exported instance, referred by the feed groups.
+    pub feed_id: i32,
+    pub params: FeedParams,
+    pub info: FeedInfo,
+    pub account_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedGroupBackup {
+    pub name: String,
+    pub feeds: Vec<FeedGroupMemberBackup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedGroupMemberBackup {
+    pub community: String,
+    pub feed_id: i32,
+}
+
+/// Portable list of feeds to share between users or instances, like an OPML subscription list.
+/// Unlike the backup, it refers to no accounts, and feeds are added with the default account of the community.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedList {
+    pub version: u32,
+    pub feeds: Vec<FeedListItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedListItem {
+    pub community: String,
+    pub name: Option<String>,
+    pub params: FeedParams,
+    /// Name of the feed group it belongs to.
+    pub group: Option<String>,
+}
+
+/// Result of importing a feed list. Feeds already existing or without an account are skipped.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeedListImportResult {
+    pub feeds: Vec<FeedView>,
+    pub skipped: Vec<String>,
+}
+
+/// Result of restoring a backup. Objects which can't be restored are skipped with the reasons.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RestoreResult {
+    pub account_count: usize,
+    pub feed_count: usize,
+    pub feed_group_count: usize,
+    pub skipped: Vec<String>,
+}
+
+pub fn export_backup(db: Database, include_secrets: bool) -> BottleResult<Backup> {
+    // 1. Accounts
+    let mut accounts = Vec::new();
+    for account in TwitterAccount::all(db)? {
+        let credential = include_secrets.then(|| account.credential(db)).transpose()?;
+        accounts.push(account_backup(&account, credential.map(|c| c.to_string())));
+    }
+    for account in PixivAccount::all(db)? {
+        let credential = include_secrets.then(|| account.credential(db)).transpose()?;
+        accounts.push(account_backup(&account, credential.map(|c| c.0)));
+    }
+    for account in PandaAccount::all(db)? {
+        let credential = include_secrets.then(|| account.credential(db)).transpose()?;
+        accounts.push(account_backup(&account, credential.map(|c| c.to_string())));
+    }
+
+    // 2. Feeds
+    let mut feeds = Vec::new();
+    for community in COMMUNITIES {
+        for feed in FeedWrapper::all(db, community)? {
+            feeds.push(FeedBackup {
+                feed_id: feed.id().feed_id,
+                params: feed.params(),
+                info: feed.info(),
+                account_id: feed.account_id(db)?,
+            });
+        }
+    }
+
+    // 3. Feed groups
+    let members = FeedGroup::all_feeds(db)?;
+    let feed_groups = FeedGroup::all(db)?
+        .into_iter()
+        .map(|group| FeedGroupBackup {
+            name: group.name,
+            feeds: members
+                .iter()
+                .filter(|member| member.group_id == group.id)
+                .map(|member| FeedGroupMemberBackup {
+                    community: member.community.clone(),
+                    feed_id: member.feed_id,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Backup {
+        version: BACKUP_VERSION,
+        accounts,
+        feeds,
+        feed_groups,
+        settings: Settings::load(db)?,
+    })
+}
+
+fn account_backup(account: &impl Account, credential: Option<String>) -> AccountBackup {
+    let view = account.view();
+    let info = account.info().unwrap_or_default();
+    AccountBackup {
+        community: view.community,
+        account_id: view.account_id,
+        credential,
+        name: info.name,
+        username: info.username,
+    }
+}
+
+/// Restore the backup on top of the current instance.
+/// Accounts without credentials are replaced by the default account of the community, if any.
+/// Objects already in the instance are kept and reported as skipped: accounts with the same credential or username,
+/// feeds with the same parameters, and feed groups with the same name, to which the restored feeds are added.
+pub fn restore_backup(db: Database, backup: &Backup) -> BottleResult<RestoreResult> {
+    if backup.version > BACKUP_VERSION {
+        return Err(BottleError::InvalidEndpoint(format!(
+            "Backup version {}",
+            backup.version
+        )));
+    }
+    let mut result = RestoreResult::default();
+
+    // 1. Accounts, mapped from the exported ID to the restored or existing ID
+    let mut existing_accounts = HashMap::new();
+    for community in ["twitter", "pixiv", "panda"] {
+        existing_accounts.insert(community, existing_account_backups(db, community)?);
+    }
+    let mut account_ids = HashMap::new();
+    for account in backup.accounts.iter() {
+        let credential = account
+            .credential
+            .as_deref()
+            .map(|credential| normalize_credential(&account.community, credential))
+            .transpose()?;
+        let existing = existing_accounts.get(account.community.as_str()).and_then(|existing| {
+            existing.iter().find(|existing| {
+                (credential.is_some() && existing.credential == credential)
+                    || (account.username.is_some() && existing.username == account.username)
+            })
+        });
+        let account_id = match (existing, &credential) {
+            (Some(existing), _) => {
+                result.skipped.push(format!(
+                    "{} account {} already exists as account {}",
+                    account.community, account.account_id, existing.account_id
+                ));
+                Some(existing.account_id)
+            }
+            (None, Some(credential)) => {
+                let account_id = add_account(db, &account.community, credential)?;
+                result.account_count += 1;
+                Some(account_id)
+            }
+            (None, None) => default_account_id(db, &account.community).ok().flatten(),
+        };
+        match account_id {
+            Some(account_id) => {
+                account_ids.insert((account.community.clone(), account.account_id), account_id);
+            }
+            None => result.skipped.push(format!(
+                "{} account {} without credential",
+                account.community, account.account_id
+            )),
+        }
+    }
+
+    // 2. Feeds, mapped from the exported ID to the restored or existing ID
+    let mut existing_feeds = Vec::new();
+    for community in COMMUNITIES {
+        for feed in FeedWrapper::all(db, community)? {
+            existing_feeds.push((serde_json::to_value(feed.params())?, feed.id()));
+        }
+    }
+    let mut feed_ids = HashMap::new();
+    for feed in backup.feeds.iter() {
+        let community = feed.params.community();
+        let params = serde_json::to_value(&feed.params)?;
+        if let Some((_, existing)) = existing_feeds.iter().find(|(existing, _)| existing == &params) {
+            result.skipped.push(format!(
+                "{} feed {} already exists as feed {}",
+                community, feed.feed_id, existing.feed_id
+            ));
+            feed_ids.insert((community.to_string(), feed.feed_id), existing.feed_id);
+            continue;
+        }
+        let account_id = match feed.account_id {
+            Some(account_id) => match account_ids.get(&(community.to_string(), account_id)) {
+                Some(account_id) => Some(*account_id),
+                None => {
+                    result
+                        .skipped
+                        .push(format!("{} feed {} of missing account", community, feed.feed_id));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let request = NewFeedRequest {
+            params: feed.params.clone(),
+            info: feed.info.clone(),
+            account_id,
+        };
+        let restored = FeedWrapper::add(db, &request)?;
+        feed_ids.insert((community.to_string(), feed.feed_id), restored.id().feed_id);
+        existing_feeds.push((params, restored.id()));
+        result.feed_count += 1;
+    }
+
+    // 3. Feed groups with the restored feeds, merged into the existing groups of the same name
+    let existing_groups = FeedGroup::all(db)?;
+    let mut members = FeedGroup::all_feeds(db)?
+        .into_iter()
+        .map(|member| (member.group_id, member.community, member.feed_id))
+        .collect::<Vec<_>>();
+    for group in backup.feed_groups.iter() {
+        let group_id = match existing_groups.iter().find(|existing| existing.name == group.name) {
+            Some(existing) => {
+                result
+                    .skipped
+                    .push(format!("Feed group \"{}\" already exists", group.name));
+                existing.id
+            }
+            None => {
+                result.feed_group_count += 1;
+                FeedGroup::add(db, &group.name)?.id
+            }
+        };
+        for member in group.feeds.iter() {
+            let Some(feed_id) = feed_ids.get(&(member.community.clone(), member.feed_id)) else {
+                continue;
+            };
+            let member = (group_id, member.community.clone(), *feed_id);
+            if !members.contains(&member) {
+                FeedGroup::add_feed(db, group_id, &member.1, member.2, None)?;
+                members.push(member);
+            }
+        }
+    }
+
+    // 4. Settings
+    let settings = serde_json::to_value(&backup.settings)?;
+    if let Some(changes) = settings.as_object() {
+        Settings::update(db, changes.clone())?;
+    }
+
+    tracing::info!(
+        "Restored backup: {} accounts, {} feeds, {} feed groups, {} skipped",
+        result.account_count,
+        result.feed_count,
+        result.feed_group_count,
+        result.skipped.len()
+    );
+    Ok(result)
+}
+
+/// Backups of the accounts of the community, with credentials to match the restored accounts against.
+fn existing_account_backups(db: Database, community: &str) -> BottleResult<Vec<AccountBackup>> {
+    let mut accounts = Vec::new();
+    match community {
+        "twitter" => {
+            for account in TwitterAccount::all(db)? {
+                let credential = account.credential(db)?;
+                accounts.push(account_backup(&account, Some(credential.to_string())));
+            }
+        }
+        "pixiv" => {
+            for account in PixivAccount::all(db)? {
+                let credential = account.credential(db)?;
+                accounts.push(account_backup(&account, Some(credential.0)));
+            }
+        }
+        "panda" => {
+            for account in PandaAccount::all(db)? {
+                let credential = account.credential(db)?;
+                accounts.push(account_backup(&account, Some(credential.to_string())));
+            }
+        }
+        _ => {}
+    }
+    Ok(accounts)
+}
+
+/// Credential in the form exported from the accounts of the community, so that it can be compared.
+fn normalize_credential(community: &str, credential: &str) -> BottleResult<String> {
+    let credential = match community {
+        "twitter" => credential
+            .parse::<SessionCookie>()
+            .map_err(|_| BottleError::InvalidEndpoint("Twitter cookies must contain ct0 and auth_token".to_string()))?
+            .to_string(),
+        "panda" => credential
+            .parse::<PandaCookie>()
+            .map_err(|_| {
+                BottleError::InvalidEndpoint(
+                    "Panda cookies must contain ipb_member_id, ipb_pass_hash and igneous".to_string(),
+                )
+            })?
+            .to_string(),
+        _ => credential.to_string(),
+    };
+    Ok(credential)
+}
+
+fn add_account(db: Database, community: &str, credential: &str) -> BottleResult<i32> {
+    let account = match community {
+        "twitter" => {
+            let cookie = credential.parse().map_err(|_| {
+                BottleError::InvalidEndpoint("Twitter cookies must contain ct0 and auth_token".to_string())
+            })?;
+            TwitterAccount::add(db, &cookie)?.view()
+        }
+        "pixiv" => PixivAccount::add(db, &RefreshToken(credential.to_string()))?.view(),
+        "panda" => {
+            let cookie = credential.parse().map_err(|_| {
+                BottleError::InvalidEndpoint(
+                    "Panda cookies must contain ipb_member_id, ipb_pass_hash and igneous".to_string(),
+                )
+            })?;
+            PandaAccount::add(db, &cookie)?.view()
+        }
+        _ => {
+            return Err(BottleError::InvalidEndpoint(format!(
+                "Account of community {}",
+                community
+            )))
+        }
+    };
+    Ok(account.account_id)
+}
+
+/// List the watched feeds, or all feeds if `watching_only` is false.
+pub fn export_feed_list(db: Database, watching_only: bool) -> BottleResult<FeedList> {
+    let groups = FeedGroup::all(db)?;
+    let members = FeedGroup::all_feeds(db)?;
+    let mut feeds = Vec::new();
+    for community in COMMUNITIES {
+        for feed in FeedWrapper::all(db, community)? {
+            let info = feed.info();
+            if watching_only && !info.watching {
+                continue;
+            }
+            let id = feed.id();
+            let group = members
+                .iter()
+                .find(|member| member.community == id.community && member.feed_id == id.feed_id)
+                .and_then(|member| groups.iter().find(|group| group.id == member.group_id))
+                .map(|group| group.name.clone());
+            feeds.push(FeedListItem {
+                community: id.community,
+                name: info.name,
+                params: feed.params(),
+                group,
+            });
+        }
+    }
+    Ok(FeedList {
+        version: BACKUP_VERSION,
+        feeds,
+    })
+}
+
+/// Add the feeds in the list as watched feeds, skipping those with the same parameters as existing ones.
+/// Feed groups are matched by name, and created if missing.
+pub fn import_feed_list(db: Database, list: &FeedList) -> BottleResult<FeedListImportResult> {
+    if list.version > BACKUP_VERSION {
+        return Err(BottleError::InvalidEndpoint(format!(
+            "Feed list version {}",
+            list.version
+        )));
+    }
+    let mut result = FeedListImportResult::default();
+
+    // Parameters of the existing feeds to find duplicates
+    let mut existing = Vec::new();
+    for community in COMMUNITIES {
+        for feed in FeedWrapper::all(db, community)? {
+            existing.push(serde_json::to_value(feed.params())?);
+        }
+    }
+
+    let mut groups = FeedGroup::all(db)?;
+    for item in list.feeds.iter() {
+        let community = item.params.community();
+        let params = serde_json::to_value(&item.params)?;
+        if existing.contains(&params) {
+            result
+                .skipped
+                .push(format!("{} feed {} already exists", community, params));
+            continue;
+        }
+        let account_id = match default_account_id(db, community) {
+            Ok(account_id) => account_id,
+            Err(BottleError::NotLoggedIn(_)) => {
+                result
+                    .skipped
+                    .push(format!("{} feed {} without account", community, params));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let request = NewFeedRequest {
+            params: item.params.clone(),
+            info: FeedInfo {
+                name: item.name.clone(),
+                watching: true,
+                first_fetch_limit: None,
+                icon_url: None,
+                color: None,
+                clear_icon: false,
+                clear_color: false,
+                require_media: false,
+                min_resolution: None,
+            },
+            account_id,
+        };
+        let feed = FeedWrapper::add(db, &request)?;
+        let id = feed.id();
+        if let Some(name) = &item.group {
+            let group_id = match groups.iter().find(|group| &group.name == name) {
+                Some(group) => group.id,
+                None => {
+                    let group = FeedGroup::add(db, name)?;
+                    let group_id = group.id;
+                    groups.push(group);
+                    group_id
+                }
+            };
+            FeedGroup::add_feed(db, group_id, &id.community, id.feed_id, None)?;
+        }
+        existing.push(params);
+        result.feeds.push(feed.view());
+    }
+
+    tracing::info!(
+        "Imported feed list: {} feeds added, {} skipped",
+        result.feeds.len(),
+        result.skipped.len()
+    );
+    Ok(result)
+}
@@ -0,0 +1,192 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::time::{self, Duration};
+
+use bottle_core::library::ContentRating;
+
+use crate::{error::Result, export::ExportEntry};
+
+/// Times to check if an upload to Danbooru has been processed, a second apart.
+const DANBOORU_UPLOAD_CHECK_COUNT: usize = 30;
+
+/// Self-hosted booru to upload archived works to.
+/// Works in any of the albums, or tagged with any of the tags, are uploaded once downloaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BooruConfig {
+    /// Unique name of the booru, recorded with the uploads.
+    pub name: String,
+    pub kind: BooruKind,
+    pub url: String,
+    pub username: String,
+    /// Login token on Szurubooru, or API key on Danbooru.
+    pub api_key: String,
+    #[serde(default)]
+    pub album_ids: Vec<i32>,
+    /// Tags of the posts, as `namespace:name` for panda.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BooruKind {
+    Szurubooru,
+    Danbooru,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemotePost {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct DanbooruUpload {
+    id: i64,
+    status: String,
+    #[serde(default)]
+    upload_media_assets: Vec<RemotePost>,
+}
+
+/// Tags of the image on the booru, with the author as an artist tag.
+/// Tags can't contain whitespaces on boorus, so they are replaced with underscores.
+fn booru_tags(entry: &ExportEntry, kind: BooruKind) -> Vec<String> {
+    let mut tags = entry
+        .tags
+        .iter()
+        .map(|tag| match (kind, tag.split_once(':')) {
+            (BooruKind::Danbooru, Some(("parody", name))) => format!("copyright:{}", name),
+            _ => tag.clone(),
+        })
+        .chain(entry.creator.iter().map(|creator| format!("artist:{}", creator)))
+        .map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("_").to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect::<Vec<_>>();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Upload the image file to the booru with the tags, rating and sources of the entry.
+/// Returns the ID of the post on the booru.
+pub async fn upload_to_booru(
+    client: &reqwest::Client,
+    config: &BooruConfig,
+    entry: &ExportEntry,
+    bytes: Vec<u8>,
+) -> Result<String> {
+    let tags = booru_tags(entry, config.kind);
+    let file = Part::bytes(bytes).file_name(entry.image.filename.clone());
+    let post = match config.kind {
+        BooruKind::Szurubooru => upload_to_szurubooru(client, config, entry, tags, file).await?,
+        BooruKind::Danbooru => upload_to_danbooru(client, config, entry, tags, file).await?,
+    };
+    Ok(post.id.to_string())
+}
+
+async fn upload_to_szurubooru(
+    client: &reqwest::Client,
+    config: &BooruConfig,
+    entry: &ExportEntry,
+    tags: Vec<String>,
+    file: Part,
+) -> Result<RemotePost> {
+    let safety = match entry.work.content_rating {
+        Some(ContentRating::General) => "safe",
+        Some(ContentRating::Sensitive) | None => "sketchy",
+        Some(ContentRating::Explicit) => "unsafe",
+    };
+    let metadata = json!({
+        "tags": tags,
+        "safety": safety,
+        "source": entry.urls.join("\n"),
+    });
+    let form = Form::new().part("content", file).text("metadata", metadata.to_string());
+    let token = BASE64.encode(format!("{}:{}", config.username, config.api_key));
+    let post = client
+        .post(format!("{}/api/posts", config.url))
+        .header(reqwest::header::AUTHORIZATION, format!("Token {}", token))
+        .header(reqwest::header::ACCEPT, "application/json")
+        .multipart(form)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(post)
+}
+
+/// Upload the file first, then create a post from the media asset once the upload is processed.
+async fn upload_to_danbooru(
+    client: &reqwest::Client,
+    config: &BooruConfig,
+    entry: &ExportEntry,
+    tags: Vec<String>,
+    file: Part,
+) -> Result<RemotePost> {
+    // 1. Upload the file
+    let form = Form::new().part("upload[files][0]", file);
+    let mut upload: DanbooruUpload = client
+        .post(format!("{}/uploads.json", config.url))
+        .basic_auth(&config.username, Some(&config.api_key))
+        .multipart(form)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    // 2. Wait until the upload is processed
+    for _ in 0..DANBOORU_UPLOAD_CHECK_COUNT {
+        if upload.status != "pending" && upload.status != "processing" {
+            break;
+        }
+        time::sleep(Duration::from_secs(1)).await;
+        upload = client
+            .get(format!("{}/uploads/{}.json", config.url, upload.id))
+            .basic_auth(&config.username, Some(&config.api_key))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+    }
+    let Some(asset) = upload
+        .upload_media_assets
+        .first()
+        .filter(|_| upload.status == "completed")
+    else {
+        return Err(anyhow::anyhow!(
+            "Danbooru upload {} status {}",
+            upload.id,
+            upload.status
+        ))?;
+    };
+
+    // 3. Create the post
+    let rating = match entry.work.content_rating {
+        Some(ContentRating::General) => "g",
+        Some(ContentRating::Sensitive) => "s",
+        Some(ContentRating::Explicit) => "e",
+        None => "q",
+    };
+    let source = entry.urls.first().cloned().unwrap_or_default();
+    let asset_id = asset.id.to_string();
+    let tag_string = tags.join(" ");
+    let post = client
+        .post(format!("{}/posts.json", config.url))
+        .basic_auth(&config.username, Some(&config.api_key))
+        .form(&[
+            ("upload_media_asset_id", asset_id.as_str()),
+            ("post[tag_string]", tag_string.as_str()),
+            ("post[rating]", rating),
+            ("post[source]", source.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(post)
+}
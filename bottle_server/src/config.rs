@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use bottle_core::{
+    cache::{DEFAULT_CACHE_CAPACITY, DEFAULT_CACHE_TTL},
+    library::ImageQuality,
+};
+
+use crate::{booru::BooruConfig, error::Result, hydrus::HydrusConfig, mail::SmtpConfig};
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Server configuration, loaded from the TOML file at `CONFIG_FILE` (`config.toml` by default) at startup.
+/// Environment variables override the corresponding fields of the file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// `SERVER_ADDRESS`
+    pub server_address: String,
+    /// `DATABASE_URL`
+    pub database_url: String,
+    /// `IMAGE_DIR`
+    pub image_dir: PathBuf,
+    /// `CLIENT_LOG_DIR`. Responses of community APIs are logged here if set.
+    pub client_log_dir: Option<PathBuf>,
+    /// `PANDA_SELECTORS_FILE`. TOML file overriding the CSS selectors of the panda parsers, reloaded by each client.
+    pub panda_selectors_file: Option<PathBuf>,
+    /// `CONTENT_CLASSIFIER`. Executable rating images of communities without content ratings.
+    pub content_classifier: Option<PathBuf>,
+    /// `OCR_COMMAND`. Executable extracting the text of panda gallery pages, which are searchable if set.
+    pub ocr_command: Option<PathBuf>,
+    /// `EXPORT_DIR`. Root of the static sites rendered on request, `exports` under the image directory if not set.
+    pub export_dir: Option<PathBuf>,
+    pub database: DatabaseConfig,
+    pub download: DownloadConfig,
+    pub scheduler: SchedulerConfig,
+    pub cache: CacheConfig,
+    pub twitter: CommunityConfig,
+    pub pixiv: CommunityConfig,
+    pub yandere: CommunityConfig,
+    pub panda: CommunityConfig,
+    /// `SMTP_HOST`, `SMTP_PORT`, `SMTP_USERNAME`, `SMTP_PASSWORD` and `SMTP_FROM`. Digests are sent if set.
+    pub smtp: Option<SmtpConfig>,
+    /// `HYDRUS_API_URL` and `HYDRUS_ACCESS_KEY`. Works can be exported to the Hydrus client if set.
+    pub hydrus: Option<HydrusConfig>,
+    /// Boorus to upload archived works to.
+    pub boorus: Vec<BooruConfig>,
+    /// Read-only gallery of selected albums, served on a separate address if set.
+    pub public: Option<PublicConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub max_connections: u32,
+    pub busy_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadConfig {
+    /// Number of images downloaded at the same time. Values below 1 are read as 1.
+    pub concurrency: usize,
+    /// Whether to overwrite existing files.
+    pub overwrite: bool,
+    /// Quality of images to download by community. Original if not set.
+    pub quality: HashMap<String, ImageQuality>,
+    /// Number of images processed for thumbnails at the same time. The number of CPUs by default.
+    pub thumbnail_workers: usize,
+    /// Free space in MiB kept on the image volume, panda galleries not fitting in the rest are not downloaded.
+    pub reserved_space_mb: u64,
+    /// Delay before retrying a panda image failed to download, doubling with each consecutive failure.
+    pub image_backoff_secs: u64,
+    /// Ratio of failed images which stops a panda gallery download early, as the account is likely banned.
+    pub breaker_failure_ratio: f64,
+    /// Number of images attempted before the failure ratio can stop a panda gallery download.
+    pub breaker_min_attempts: usize,
+    /// Whether the repair at startup resets downloaded images whose files are missing, so that they are downloaded
+    /// again. They are only reported if not set.
+    pub reset_missing_images: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// Interval to check if any digest is due.
+    pub digest_check_interval_secs: u64,
+    /// Interval to refresh the statistics.
+    pub statistics_refresh_interval_secs: u64,
+    /// Interval to regenerate the covers of albums and feeds.
+    pub cover_refresh_interval_secs: u64,
+    /// Interval to check if the remote sources of archived works still exist.
+    pub remote_check_interval_secs: u64,
+    /// Interval to check if archived panda galleries are expunged or replaced.
+    pub panda_check_interval_secs: u64,
+    /// Interval to update panda favorites feeds mirrored to albums, and archive and download their new galleries.
+    pub panda_mirror_interval_secs: u64,
+    /// Interval to upload archived works to the boorus.
+    pub booru_upload_interval_secs: u64,
+    /// Interval to suggest links of artists across communities.
+    pub artist_link_interval_secs: u64,
+    /// Interval to fetch the profiles of pixiv users in the library.
+    pub pixiv_profile_interval_secs: u64,
+    /// Interval to extract the text of downloaded panda gallery pages, if the OCR command is set.
+    pub ocr_interval_secs: u64,
+    /// Maximum number of jobs waiting in each job queue, e.g. feed updates of a community.
+    /// Single jobs sent by requests are rejected with 429 until the queue drains,
+    /// while bulk and background producers wait for room.
+    pub job_queue_capacity: usize,
+}
+
+/// Public gallery which exposes only the albums and their images, without accounts, feeds or jobs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicConfig {
+    pub address: String,
+    /// Token required in the `Authorization: Bearer` header or the `token` query parameter.
+    /// The gallery is not started if it is empty.
+    pub token: String,
+    pub album_ids: Vec<i32>,
+}
+
+/// Limits of the caches of community entities fetched from APIs, applied to each kind of entities.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    pub capacity: usize,
+    pub ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommunityConfig {
+    /// Delay between requests to the community, to avoid rate limiting.
+    pub delay_ms: u64,
+    /// Maximum number of feed requests made by an account in the window. Unlimited if not set.
+    pub max_requests: Option<usize>,
+    pub window_secs: u64,
+    /// Maximum number of pages fetched in an update of a feed. Unlimited if not set.
+    /// Only applies to the feeds whose next update continues from where the last one stopped.
+    pub max_pages_per_update: Option<usize>,
+    /// Consecutive failed updates after which a feed stops being watched. Never if 0.
+    pub pause_after_failures: i32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server_address: "0.0.0.0:6000".to_string(),
+            database_url: String::new(),
+            image_dir: PathBuf::new(),
+            client_log_dir: None,
+            panda_selectors_file: None,
+            content_classifier: None,
+            ocr_command: None,
+            export_dir: None,
+            database: Default::default(),
+            download: Default::default(),
+            scheduler: Default::default(),
+            cache: Default::default(),
+            twitter: Default::default(),
+            pixiv: Default::default(),
+            yandere: Default::default(),
+            panda: Default::default(),
+            smtp: None,
+            hydrus: None,
+            boorus: Vec::new(),
+            public: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 16,
+            busy_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 5,
+            overwrite: true,
+            quality: HashMap::new(),
+            thumbnail_workers: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            reserved_space_mb: 1024,
+            image_backoff_secs: 10 * 60,
+            breaker_failure_ratio: 0.8,
+            breaker_min_attempts: 10,
+            reset_missing_images: false,
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CACHE_CAPACITY,
+            ttl_secs: DEFAULT_CACHE_TTL.as_secs(),
+        }
+    }
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            digest_check_interval_secs: 60 * 60,
+            statistics_refresh_interval_secs: 6 * 60 * 60,
+            cover_refresh_interval_secs: 24 * 60 * 60,
+            remote_check_interval_secs: 24 * 60 * 60,
+            panda_check_interval_secs: 7 * 24 * 60 * 60,
+            panda_mirror_interval_secs: 6 * 60 * 60,
+            booru_upload_interval_secs: 60 * 60,
+            artist_link_interval_secs: 24 * 60 * 60,
+            pixiv_profile_interval_secs: 24 * 60 * 60,
+            ocr_interval_secs: 60 * 60,
+            job_queue_capacity: 1000,
+        }
+    }
+}
+
+impl Default for CommunityConfig {
+    fn default() -> Self {
+        Self {
+            delay_ms: 1000,
+            max_requests: None,
+            window_secs: 15 * 60,
+            max_pages_per_update: None,
+            pause_after_failures: 5,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file if exists, then apply environment variables.
+    pub fn load() -> Result<Self> {
+        let path = env::var("CONFIG_FILE").unwrap_or(DEFAULT_CONFIG_PATH.to_string());
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && env::var("CONFIG_FILE").is_err() => Config::default(),
+            Err(e) => return Err(anyhow::anyhow!("Cannot read config file {}: {}", path, e).into()),
+        };
+        config.apply_env();
+
+        if config.database_url.is_empty() {
+            return Err(anyhow::anyhow!("DATABASE_URL must be set").into());
+        }
+        if config.image_dir.as_os_str().is_empty() {
+            return Err(anyhow::anyhow!("IMAGE_DIR must be set").into());
+        }
+        config.image_dir = config
+            .image_dir
+            .canonicalize()
+            .map_err(|e| anyhow::anyhow!("IMAGE_DIR must be a valid path: {}", e))?;
+        Ok(config)
+    }
+
+    fn apply_env(&mut self) {
+        if let Ok(addr) = env::var("SERVER_ADDRESS") {
+            self.server_address = addr;
+        }
+        if let Ok(url) = env::var("DATABASE_URL") {
+            self.database_url = url;
+        }
+        if let Ok(dir) = env::var("IMAGE_DIR") {
+            self.image_dir = dir.into();
+        }
+        if let Ok(dir) = env::var("CLIENT_LOG_DIR") {
+            self.client_log_dir = Some(dir.into());
+        }
+        if let Ok(path) = env::var("PANDA_SELECTORS_FILE") {
+            self.panda_selectors_file = Some(path.into());
+        }
+        if let Ok(path) = env::var("CONTENT_CLASSIFIER") {
+            self.content_classifier = Some(path.into());
+        }
+        if let Ok(path) = env::var("OCR_COMMAND") {
+            self.ocr_command = Some(path.into());
+        }
+        if let Ok(dir) = env::var("EXPORT_DIR") {
+            self.export_dir = Some(dir.into());
+        }
+        if let Ok(host) = env::var("SMTP_HOST") {
+            let smtp = self.smtp.get_or_insert_with(Default::default);
+            smtp.host = host;
+        }
+        if let Some(smtp) = &mut self.smtp {
+            if let Some(port) = env::var("SMTP_PORT").ok().and_then(|port| port.parse().ok()) {
+                smtp.port = port;
+            }
+            if let Ok(username) = env::var("SMTP_USERNAME") {
+                smtp.username = Some(username);
+            }
+            if let Ok(password) = env::var("SMTP_PASSWORD") {
+                smtp.password = Some(password);
+            }
+            if let Ok(from) = env::var("SMTP_FROM") {
+                smtp.from = from;
+            }
+            if smtp.from.is_empty() {
+                smtp.from = smtp.username.clone().unwrap_or_default();
+            }
+        }
+        if let Ok(url) = env::var("HYDRUS_API_URL") {
+            let hydrus = self.hydrus.get_or_insert_with(Default::default);
+            hydrus.api_url = url;
+        }
+        if let Some(hydrus) = &mut self.hydrus {
+            if let Ok(key) = env::var("HYDRUS_ACCESS_KEY") {
+                hydrus.access_key = key;
+            }
+        }
+    }
+
+    /// Copy of the config without secrets, for displaying.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if let Some(smtp) = &mut config.smtp {
+            smtp.password = smtp.password.as_ref().map(|_| "********".to_string());
+        }
+        if let Some(hydrus) = &mut config.hydrus {
+            hydrus.access_key = "********".to_string();
+        }
+        for booru in config.boorus.iter_mut() {
+            booru.api_key = "********".to_string();
+        }
+        if let Some(public) = &mut config.public {
+            public.token = "********".to_string();
+        }
+        config
+    }
+
+    /// Root directory which static sites are rendered under.
+    pub fn export_root(&self) -> PathBuf {
+        self.export_dir.clone().unwrap_or(self.image_dir.join("exports"))
+    }
+
+    /// Config of the community, the default one for unknown communities.
+    pub fn community(&self, community: &str) -> CommunityConfig {
+        match community {
+            "twitter" => self.twitter.clone(),
+            "pixiv" => self.pixiv.clone(),
+            "yandere" => self.yandere.clone(),
+            "panda" => self.panda.clone(),
+            _ => CommunityConfig::default(),
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
+use serde_json::json;
 
-use bottle_core::Error as BottleError;
+use bottle_core::{feed::FieldError, Error as BottleError};
+
+use crate::background_job::QueueFull;
 
 pub type Result<T> = std::result::Result<T, ServerError>;
 
@@ -29,6 +32,14 @@ impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
         tracing::error!("{}", self);
         let status = self.status_code();
+        if let Some(errors) = self.field_errors() {
+            let body = json!({ "error": self.to_string(), "fields": errors });
+            return (status, Json(body)).into_response();
+        }
+        if let Some(queue_full) = self.0.chain().find_map(|cause| cause.downcast_ref::<QueueFull>()) {
+            let retry_after = queue_full.retry_after.as_secs().to_string();
+            return (status, [(header::RETRY_AFTER, retry_after)], self.to_string()).into_response();
+        }
         (status, self.to_string()).into_response()
     }
 }
@@ -37,11 +48,20 @@ impl ServerError {
     fn status_code(&self) -> StatusCode {
         let err = &self.0;
         for cause in err.chain() {
+            if cause.is::<QueueFull>() {
+                return StatusCode::TOO_MANY_REQUESTS;
+            }
+            if let Some(diesel::result::Error::NotFound) = cause.downcast_ref::<diesel::result::Error>() {
+                return StatusCode::NOT_FOUND;
+            }
             if let Some(err) = cause.downcast_ref::<bottle_download::Error>() {
                 match err {
                     bottle_download::Error::InvalidUrl(_) => return StatusCode::BAD_REQUEST,
                     bottle_download::Error::NetworkError(_) => return StatusCode::BAD_GATEWAY,
                     bottle_download::Error::IncompleteDownload(_) => return StatusCode::BAD_GATEWAY,
+                    bottle_download::Error::UnexpectedStatus { status: 404 | 410, .. } => return StatusCode::NOT_FOUND,
+                    bottle_download::Error::UnexpectedStatus { .. } => return StatusCode::BAD_GATEWAY,
+                    bottle_download::Error::InsufficientSpace { .. } => return StatusCode::INSUFFICIENT_STORAGE,
                     _ => return StatusCode::INTERNAL_SERVER_ERROR,
                 }
             }
@@ -70,6 +90,7 @@ impl ServerError {
             if let Some(err) = cause.downcast_ref::<panda_client::Error>() {
                 match err {
                     panda_client::Error::RateLimit(_) => return StatusCode::TOO_MANY_REQUESTS,
+                    panda_client::Error::InvalidCookie(_) => return StatusCode::BAD_REQUEST,
                     panda_client::Error::NetworkError(_) => return StatusCode::BAD_GATEWAY,
                     _ => return StatusCode::INTERNAL_SERVER_ERROR,
                 }
@@ -77,9 +98,11 @@ impl ServerError {
             if let Some(err) = cause.downcast_ref::<BottleError>() {
                 match err {
                     BottleError::ObjectNotFound(_) => return StatusCode::NOT_FOUND,
+                    BottleError::DatabaseError(diesel::result::Error::NotFound) => return StatusCode::NOT_FOUND,
                     BottleError::ObjectAlreadyExists(_) => return StatusCode::CONFLICT,
                     BottleError::ObjectNotComplete(_) => return StatusCode::BAD_REQUEST,
                     BottleError::InvalidEndpoint(_) => return StatusCode::BAD_REQUEST,
+                    BottleError::InvalidFields(_) => return StatusCode::UNPROCESSABLE_ENTITY,
                     BottleError::NotLoggedIn(_) => return StatusCode::UNAUTHORIZED,
                     BottleError::RateLimit(_) => return StatusCode::TOO_MANY_REQUESTS,
                     BottleError::Timeout(_) => return StatusCode::GATEWAY_TIMEOUT,
@@ -90,6 +113,16 @@ impl ServerError {
         StatusCode::INTERNAL_SERVER_ERROR
     }
 
+    /// Errors of the fields in the request, responded as JSON.
+    fn field_errors(&self) -> Option<&Vec<FieldError>> {
+        self.0
+            .chain()
+            .find_map(|cause| match cause.downcast_ref::<BottleError>() {
+                Some(BottleError::InvalidFields(errors)) => Some(errors),
+                _ => None,
+            })
+    }
+
     pub fn retryable(&self) -> bool {
         let status = self.status_code();
         matches!(status, StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT)
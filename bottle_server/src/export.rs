@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use bottle_core::{
+    feed::{MediaView, PostView},
+    library::{ImageView, WorkFilter, WorkView},
+    Database, Result as BottleResult,
+};
+use bottle_library::Album;
+
+use crate::{
+    payload::ExportWorksRequest,
+    util::{self, COMMUNITIES},
+};
+
+/// An image in the library to export to another tool, along with the metadata of its work and post.
+#[derive(Debug, Clone)]
+pub struct ExportEntry {
+    pub work: WorkView,
+    pub image: ImageView,
+    /// Tags of the post as in the community, e.g. `artist:name` for panda.
+    pub tags: Vec<String>,
+    /// Name of the author of the post.
+    pub creator: Option<String>,
+    /// Links to the post and the original image.
+    pub urls: Vec<String>,
+}
+
+/// Collect the images of the works in the request with the metadata of their posts.
+pub fn export_entries(db: Database, request: &ExportWorksRequest) -> BottleResult<Vec<ExportEntry>> {
+    let mut work_ids = request.work_ids.clone();
+    if let Some(album_id) = request.album_id {
+        let response = Album::works(db, album_id, &WorkFilter::default(), 0, i64::MAX)?;
+        work_ids.extend(response.works.unwrap_or_default().iter().map(|work| work.id));
+    }
+    let response = bottle_library::get_works(db, &work_ids)?;
+    let works = response
+        .works
+        .unwrap_or_default()
+        .into_iter()
+        .map(|work| (work.id, work))
+        .collect::<HashMap<_, _>>();
+
+    // Posts and users of the works in each community
+    let mut posts = HashMap::new();
+    let mut users = HashMap::new();
+    for community in COMMUNITIES {
+        let post_ids = works
+            .values()
+            .filter(|work| work.community.as_deref() == Some(community))
+            .filter_map(|work| work.post_id.clone())
+            .collect::<Vec<_>>();
+        if post_ids.is_empty() {
+            continue;
+        }
+        let entities = util::get_entities(db, community, &post_ids)?;
+        for post in entities.posts.unwrap_or_default() {
+            posts.insert((post.community.clone(), post.post_id.clone()), post);
+        }
+        for user in entities.users.unwrap_or_default() {
+            users.insert((user.community.clone(), user.user_id.clone()), user);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for image in response.images.unwrap_or_default() {
+        let Some(work) = works.get(&image.work_id) else {
+            continue;
+        };
+        let post = match (&work.community, &work.post_id) {
+            (Some(community), Some(post_id)) => posts.get(&(community.clone(), post_id.clone())),
+            _ => None,
+        };
+        let creator = post
+            .and_then(|post| Some((post.community.clone(), post.user_id.clone()?)))
+            .and_then(|key| users.get(&key))
+            .and_then(|user| user.name.clone().or(user.username.clone()));
+        entries.push(ExportEntry {
+            work: work.clone(),
+            tags: post.and_then(|post| post.tags.clone()).unwrap_or_default(),
+            creator,
+            urls: entry_urls(post, &image),
+            image,
+        });
+    }
+    Ok(entries)
+}
+
+fn entry_urls(post: Option<&PostView>, image: &ImageView) -> Vec<String> {
+    let mut urls = Vec::new();
+    if let Some(url) = post.and_then(util::post_url) {
+        urls.push(url);
+    }
+    if let Some(url) = &image.remote_url {
+        urls.push(url.clone());
+    }
+    urls
+}
+
+/// A post with its media, as a line of the post metadata export.
+#[derive(Debug, Clone, Serialize)]
+struct PostRecord<'a> {
+    post: &'a PostView,
+    media: Vec<&'a MediaView>,
+}
+
+/// Export a batch of saved posts of the community with IDs greater than `after_id` as JSON lines.
+/// Returns the lines and the last post ID, or None if there are no more posts.
+pub fn export_posts(db: Database, community: &str, after_id: i64, count: i64) -> BottleResult<(String, Option<i64>)> {
+    let post_ids = util::get_post_ids(db, community, after_id, count)?;
+    let Some(last_id) = post_ids.last().copied() else {
+        return Ok((String::new(), None));
+    };
+    let post_ids = post_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    let response = util::get_entities(db, community, &post_ids)?;
+
+    let media = response.media.unwrap_or_default();
+    let mut lines = String::new();
+    for post in response.posts.unwrap_or_default().iter() {
+        let record = PostRecord {
+            post,
+            media: media.iter().filter(|media| media.post_id == post.post_id).collect(),
+        };
+        lines.push_str(&serde_json::to_string(&record)?);
+        lines.push('\n');
+    }
+    Ok((lines, Some(last_id)))
+}
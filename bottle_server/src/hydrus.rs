@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use bottle_util::http::client_builder;
+
+use crate::{error::Result, export::ExportEntry};
+
+const ACCESS_KEY_HEADER: &str = "Hydrus-Client-API-Access-Key";
+/// Import status of files which are imported or already in the database.
+const IMPORTED_STATUSES: [i32; 2] = [1, 2];
+
+/// Hydrus Network client to export works to through its Client API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HydrusConfig {
+    pub api_url: String,
+    /// Access key with the permissions to import files, add tags and add URLs.
+    pub access_key: String,
+    /// Local tag service to add tags to.
+    pub tag_service: String,
+}
+
+impl Default for HydrusConfig {
+    fn default() -> Self {
+        Self {
+            api_url: "http://127.0.0.1:45869".to_string(),
+            access_key: String::new(),
+            tag_service: "my tags".to_string(),
+        }
+    }
+}
+
+/// Result of exporting images to Hydrus. Images not downloaded or failed to export are skipped with the reasons.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HydrusExportResult {
+    pub exported: usize,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddFileResponse {
+    status: i32,
+    hash: String,
+    #[serde(default)]
+    note: String,
+}
+
+/// Tags of the image in Hydrus namespaces, with the creator and the title of the work.
+fn hydrus_tags(entry: &ExportEntry) -> Vec<String> {
+    let mut tags = entry
+        .tags
+        .iter()
+        .map(|tag| match tag.split_once(':') {
+            Some(("artist", name)) => format!("creator:{}", name),
+            Some(("parody", name)) => format!("series:{}", name),
+            _ => tag.clone(),
+        })
+        .collect::<Vec<_>>();
+    if let Some(creator) = &entry.creator {
+        tags.push(format!("creator:{}", creator));
+    }
+    if let Some(name) = &entry.work.name {
+        tags.push(format!("title:{}", name));
+    }
+    if let Some(community) = &entry.work.community {
+        tags.push(format!("source:{}", community));
+    }
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Import the downloaded images into the Hydrus client, and add their tags and URLs.
+pub async fn export_to_hydrus(config: &HydrusConfig, image_dir: &Path, entries: &[ExportEntry]) -> HydrusExportResult {
+    let client = client_builder().build().unwrap_or_default();
+    let mut result = HydrusExportResult::default();
+    for entry in entries {
+        let Some(path) = &entry.image.path else {
+            result.skipped.push(format!("Image {} not downloaded", entry.image.id));
+            continue;
+        };
+        match export_image(&client, config, &image_dir.join(path), entry).await {
+            Ok(_) => result.exported += 1,
+            Err(e) => result.skipped.push(format!("Image {}: {}", entry.image.id, e)),
+        }
+    }
+    tracing::info!(
+        "Exported {} images to Hydrus, {} skipped",
+        result.exported,
+        result.skipped.len()
+    );
+    result
+}
+
+async fn export_image(client: &reqwest::Client, config: &HydrusConfig, path: &Path, entry: &ExportEntry) -> Result<()> {
+    // 1. Import the file
+    let bytes = tokio::fs::read(path).await?;
+    let response: AddFileResponse = client
+        .post(format!("{}/add_files/add_file", config.api_url))
+        .header(ACCESS_KEY_HEADER, &config.access_key)
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .body(bytes)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if !IMPORTED_STATUSES.contains(&response.status) {
+        return Err(anyhow::anyhow!("Import status {}: {}", response.status, response.note))?;
+    }
+
+    // 2. Add the tags
+    let tags = hydrus_tags(entry);
+    if !tags.is_empty() {
+        let body = json!({
+            "hash": response.hash,
+            "service_names_to_tags": { (config.tag_service.clone()): tags },
+        });
+        client
+            .post(format!("{}/add_tags/add_tags", config.api_url))
+            .header(ACCESS_KEY_HEADER, &config.access_key)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+
+    // 3. Associate the URLs
+    if !entry.urls.is_empty() {
+        let body = json!({
+            "hash": response.hash,
+            "urls_to_add": entry.urls,
+        });
+        client
+            .post(format!("{}/add_urls/associate_url", config.api_url))
+            .header(ACCESS_KEY_HEADER, &config.access_key)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+    Ok(())
+}
+
+/// Write a Hydrus sidecar next to each downloaded image, named as the image with `.txt` appended,
+/// with a tag per line, for importing the image directory with an import folder.
+pub async fn write_hydrus_sidecars(image_dir: &Path, entries: &[ExportEntry]) -> HydrusExportResult {
+    let mut result = HydrusExportResult::default();
+    for entry in entries {
+        let Some(path) = &entry.image.path else {
+            result.skipped.push(format!("Image {} not downloaded", entry.image.id));
+            continue;
+        };
+        let sidecar = image_dir.join(format!("{}.txt", path));
+        let content = hydrus_tags(entry).join("\n");
+        match tokio::fs::write(&sidecar, content).await {
+            Ok(_) => result.exported += 1,
+            Err(e) => result.skipped.push(format!("Image {}: {}", entry.image.id, e)),
+        }
+    }
+    tracing::info!(
+        "Wrote {} Hydrus sidecars, {} skipped",
+        result.exported,
+        result.skipped.len()
+    );
+    result
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use lettre::{
+    message::header::ContentType,
+    transport::smtp::{
+        authentication::Credentials,
+        client::{Tls, TlsParameters},
+    },
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+const IMPLICIT_TLS_PORT: u16 = 465;
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// SMTP server to send digests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Sender address. Default to the username.
+    pub from: String,
+}
+
+impl Default for SmtpConfig {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 587,
+            username: None,
+            password: None,
+            from: String::new(),
+        }
+    }
+}
+
+/// Send a plain text mail.
+/// Use implicit TLS on port 465, otherwise upgrade with STARTTLS.
+/// STARTTLS is required when credentials are given, so that they are never sent in cleartext.
+pub async fn send_mail(config: &SmtpConfig, to: &str, subject: &str, body: &str) -> Result<()> {
+    let message = Message::builder()
+        .from(config.from.parse()?)
+        .to(to.parse()?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body.to_string())?;
+
+    let parameters = TlsParameters::new(config.host.clone())?;
+    let tls = if config.port == IMPLICIT_TLS_PORT {
+        Tls::Wrapper(parameters)
+    } else if config.username.is_some() {
+        Tls::Required(parameters)
+    } else {
+        Tls::Opportunistic(parameters)
+    };
+    let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+        .port(config.port)
+        .tls(tls)
+        .timeout(Some(SMTP_TIMEOUT));
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    // The transport timeout only bounds connecting, so bound the whole exchange as well
+    let transport = builder.build();
+    tokio::time::timeout(SMTP_TIMEOUT * 2, transport.send(message))
+        .await
+        .map_err(|_| anyhow::anyhow!("SMTP server timed out"))??;
+    Ok(())
+}
@@ -1,22 +1,33 @@
+mod album_export;
 mod background_job;
+mod backup;
+mod booru;
+mod config;
 mod error;
+mod export;
+mod hydrus;
+mod mail;
 mod payload;
 mod router;
 mod state;
+mod static_site;
 mod util;
 
 use axum::Router;
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool};
 use dotenvy::dotenv;
-use tokio::sync::{mpsc, RwLock};
-use tower_http::{services::ServeDir, trace::TraceLayer};
+use tokio::sync::RwLock;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    services::ServeDir,
+    trace::TraceLayer,
+};
 use tracing_subscriber::filter::{EnvFilter, LevelFilter};
 use util::ConnectionOptions;
 
 use std::collections::HashMap;
 use std::env;
-use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -25,7 +36,10 @@ use bottle_pixiv::PixivCache;
 use bottle_twitter::TwitterCache;
 use bottle_yandere::YandereCache;
 
-use crate::{state::AppState, util::FeedIdentifier};
+use crate::{
+    config::{CommunityConfig, Config},
+    state::{AppState, CommunityCaches},
+};
 
 #[tokio::main]
 async fn main() {
@@ -43,60 +57,107 @@ async fn main() {
         .add_directive("selectors=info".parse().unwrap());
     tracing_subscriber::fmt().with_env_filter(filter).compact().init();
 
-    // 2. Initialize database
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+    // 2. Load config
+    let config = Config::load().expect("cannot load config");
+    if let Some(dir) = &config.client_log_dir {
+        // Clients read the log directory from the environment
+        env::set_var("CLIENT_LOG_DIR", dir);
+    }
+    if let Some(path) = &config.panda_selectors_file {
+        env::set_var("PANDA_SELECTORS_FILE", path);
+    }
+
+    // 3. Initialize database
+    let manager = ConnectionManager::<SqliteConnection>::new(&config.database_url);
     let pool = Pool::builder()
-        .max_size(16)
+        .max_size(config.database.max_connections)
         .connection_customizer(Box::new(ConnectionOptions {
             enable_wal: true,
             enable_foreign_keys: true,
-            busy_timeout: Some(Duration::from_secs(30)),
+            busy_timeout: Some(Duration::from_secs(config.database.busy_timeout_secs)),
         }))
         .build(manager)
         .unwrap();
+    // Repair before the background jobs start, so that no feed is being updated
+    background_job::repair_library(pool.clone(), &config.image_dir, config.download.reset_missing_images).await;
 
-    // 3. Initialize static file server
-    let image_dir = env::var("IMAGE_DIR").expect("IMAGE_DIR must be set");
-    let image_dir = PathBuf::from(image_dir)
-        .canonicalize()
-        .expect("IMAGE_DIR must be a valid path");
+    // 4. Initialize static file server
+    let image_dir = config.image_dir.clone();
     let serve_dir = ServeDir::new(&image_dir);
 
-    // 4. Initialize cache
-    let twitter_cache = Arc::new(RwLock::new(TwitterCache::new()));
-    let pixiv_cache = Arc::new(RwLock::new(PixivCache::new()));
-    let yandere_cache = Arc::new(RwLock::new(YandereCache::new()));
-    let panda_cache = Arc::new(RwLock::new(PandaCache::new()));
+    // 5. Initialize cache
+    let (capacity, ttl) = (config.cache.capacity, Duration::from_secs(config.cache.ttl_secs));
+    let twitter_cache = Arc::new(RwLock::new(TwitterCache::with_limits(capacity, ttl)));
+    let pixiv_cache = Arc::new(RwLock::new(PixivCache::with_limits(capacity, ttl)));
+    let yandere_cache = Arc::new(RwLock::new(YandereCache::with_limits(capacity, ttl)));
+    let panda_cache = Arc::new(RwLock::new(PandaCache::with_limits(capacity, ttl)));
+    let account_request_sender = background_job::listen_account_request(pool.clone());
+    let recorder = |community| background_job::request_recorder(&account_request_sender, community);
+    twitter_cache.write().await.set_request_recorder(recorder("twitter"));
+    pixiv_cache.write().await.set_request_recorder(recorder("pixiv"));
+    panda_cache.write().await.set_request_recorder(recorder("panda"));
+    let caches = CommunityCaches {
+        twitter: twitter_cache.clone(),
+        pixiv: pixiv_cache.clone(),
+        panda: panda_cache.clone(),
+    };
 
-    // 5. Initialize background jobs
+    // 6. Initialize background jobs
+    let queue_capacity = config.scheduler.job_queue_capacity;
     let feed_update_state_sender_map = Arc::new(RwLock::new(HashMap::new()));
     let feed_update_state_map = Arc::new(RwLock::new(HashMap::new()));
-    let feed_update_queue = |community: &str| -> (String, mpsc::UnboundedSender<FeedIdentifier>) {
-        (
-            community.to_string(),
-            background_job::listen_feed_update(pool.clone(), feed_update_state_sender_map.clone()),
-        )
-    };
+    let feed_update_queue =
+        |community: &str, config: &CommunityConfig| -> (String, background_job::FeedUpdateJobQueue) {
+            (
+                community.to_string(),
+                background_job::listen_feed_update(
+                    pool.clone(),
+                    feed_update_state_sender_map.clone(),
+                    community,
+                    config.clone(),
+                    queue_capacity,
+                    caches.clone(),
+                ),
+            )
+        };
     let feed_update_queues = HashMap::from([
-        feed_update_queue("twitter"),
-        feed_update_queue("pixiv"),
-        feed_update_queue("yandere"),
-        feed_update_queue("panda"),
+        feed_update_queue("twitter", &config.twitter),
+        feed_update_queue("pixiv", &config.pixiv),
+        feed_update_queue("yandere", &config.yandere),
+        feed_update_queue("panda", &config.panda),
     ]);
 
-    let (image_download_queue, image_download_job_state) =
-        background_job::listen_image_download(pool.clone(), &image_dir);
+    let thumbnail_pool = background_job::ThumbnailPool::new(config.download.thumbnail_workers);
+    let (image_download_queue, image_download_job_state) = background_job::listen_image_download(
+        pool.clone(),
+        &image_dir,
+        config.content_classifier.clone(),
+        config.download.clone(),
+        queue_capacity,
+        thumbnail_pool.clone(),
+    );
 
     let panda_download_state_sender_map = Arc::new(RwLock::new(HashMap::new()));
     let panda_download_state_map = Arc::new(RwLock::new(HashMap::new()));
-    let panda_download_queue =
-        background_job::listen_panda_download(pool.clone(), panda_download_state_sender_map.clone(), &image_dir)
-            .expect("cannot start panda download job");
+    let panda_download_queue = background_job::listen_panda_download(
+        pool.clone(),
+        panda_download_state_sender_map.clone(),
+        &image_dir,
+        config.download.clone(),
+        config.panda.delay_ms,
+        queue_capacity,
+        thumbnail_pool.clone(),
+        panda_cache.clone(),
+    )
+    .expect("cannot start panda download job");
     let panda_gallery_title_map = Arc::new(RwLock::new(HashMap::new()));
+    let import_job_state_map = Arc::new(RwLock::new(HashMap::new()));
+    let album_export_job_state_map = Arc::new(RwLock::new(HashMap::new()));
 
-    // 6. Setup state and router
+    // 7. Setup state and router
+    let config = Arc::new(config);
     let app_state = AppState {
+        config: config.clone(),
         pool,
         twitter_cache,
         pixiv_cache,
@@ -105,13 +166,80 @@ async fn main() {
         feed_update_queues,
         feed_update_state_sender_map,
         feed_update_state_map,
+        thumbnail_pool,
         image_download_queue,
         image_download_job_state,
         panda_download_queue,
         panda_download_state_sender_map,
         panda_download_state_map,
         panda_gallery_title_map,
+        import_job_state_map,
+        album_export_job_state_map,
     };
+    if let Err(e) = background_job::restore_panda_download_jobs(&app_state).await {
+        tracing::error!("Failed to restore panda download jobs: {}", e);
+    }
+
+    let interval = Duration::from_secs(config.scheduler.statistics_refresh_interval_secs);
+    background_job::listen_statistics(app_state.pool.clone(), interval);
+
+    let interval = Duration::from_secs(config.scheduler.cover_refresh_interval_secs);
+    background_job::listen_cover(app_state.pool.clone(), &image_dir, interval);
+
+    let interval = Duration::from_secs(config.scheduler.remote_check_interval_secs);
+    background_job::listen_remote_check(app_state.pool.clone(), interval);
+
+    let interval = Duration::from_secs(config.scheduler.panda_check_interval_secs);
+    background_job::listen_panda_check(app_state.clone(), interval);
+
+    let interval = Duration::from_secs(config.scheduler.panda_mirror_interval_secs);
+    background_job::listen_panda_mirror(app_state.clone(), interval);
+
+    let interval = Duration::from_secs(config.scheduler.artist_link_interval_secs);
+    background_job::listen_artist_link(app_state.pool.clone(), interval);
+
+    let interval = Duration::from_secs(config.scheduler.pixiv_profile_interval_secs);
+    background_job::listen_pixiv_profile(app_state.clone(), interval);
+
+    if let Some(ocr_command) = &config.ocr_command {
+        let interval = Duration::from_secs(config.scheduler.ocr_interval_secs);
+        background_job::listen_ocr(app_state.pool.clone(), &image_dir, ocr_command.clone(), interval);
+    }
+
+    if !config.boorus.is_empty() {
+        let interval = Duration::from_secs(config.scheduler.booru_upload_interval_secs);
+        background_job::listen_booru_upload(app_state.pool.clone(), &image_dir, config.boorus.clone(), interval);
+    }
+
+    if let Some(smtp_config) = &config.smtp {
+        let interval = Duration::from_secs(config.scheduler.digest_check_interval_secs);
+        background_job::listen_digest(app_state.clone(), &image_dir, smtp_config.clone(), interval);
+    }
+
+    // Public gallery on its own address, so that the rest of the API is never exposed with it
+    if let Some(public) = config.public.as_ref().filter(|public| {
+        let valid = !public.token.trim().is_empty();
+        if !valid {
+            tracing::error!("Public gallery is not started, since its token is empty");
+        }
+        valid
+    }) {
+        let public_app = router::public::public_router(app_state.clone())
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(util::make_request_span)
+                    .on_request(()),
+            )
+            .with_state(app_state.clone());
+        let addr = public.address.clone();
+        tracing::info!("Public gallery starting at {}", addr);
+        tokio::spawn(async move {
+            axum::Server::bind(&addr.parse().unwrap())
+                .serve(public_app.into_make_service())
+                .await
+                .unwrap();
+        });
+    }
 
     let app = Router::new()
         .merge(router::account::account_router())
@@ -120,12 +248,21 @@ async fn main() {
         .merge(router::library::library_router())
         .merge(router::api::api_router())
         .merge(router::job::job_router())
+        .merge(router::admin::admin_router())
+        .merge(router::statistics::statistics_router())
         .nest_service("/image", serve_dir)
-        .layer(TraceLayer::new_for_http().on_request(()))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(util::make_request_span)
+                .on_request(()),
+        )
+        // Echo or generate `x-request-id`, which is recorded in the span of the request and its jobs
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .with_state(app_state);
 
-    // 7. Start server
-    let addr = env::var("SERVER_ADDRESS").expect("SERVER_ADDRESS must be set");
+    // 8. Start server
+    let addr = &config.server_address;
     tracing::info!("Server starting at {}", addr);
     axum::Server::bind(&addr.parse().unwrap())
         .serve(app.into_make_service())
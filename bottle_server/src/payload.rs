@@ -1,6 +1,11 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use bottle_core::feed::FeedInfo;
+use std::path::PathBuf;
+
+use bottle_core::{
+    feed::{DigestFrequency, Endpoint, FeedInfo, FieldError, WebhookKind},
+    Error as BottleError, Result as BottleResult,
+};
 use bottle_panda::PandaFeedParams;
 use bottle_pixiv::PixivFeedParams;
 use bottle_twitter::TwitterFeedParams;
@@ -15,8 +20,126 @@ pub struct NewFeedRequest {
     pub account_id: Option<i32>,
 }
 
-/// Enum of feed parameters for different community.
+/// Request for promoting a browsed normalized endpoint to a new feed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromoteEndpointRequest {
+    pub params: Endpoint,
+    pub info: FeedInfo,
+    pub account_id: Option<i32>,
+}
+
+/// Request for watching an artist on several communities at once,
+/// by adding a posts feed of each identity of the artist and grouping them in a new feed group.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchArtistRequest {
+    /// Name of the new feed group.
+    pub name: String,
+    pub identities: Vec<ArtistIdentity>,
+    /// Whether the feeds are updated periodically, true by default.
+    pub watching: Option<bool>,
+    pub first_fetch_limit: Option<i32>,
+}
+
+/// An identity of an artist in a community.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArtistIdentity {
+    pub community: String,
+    /// User ID or username on Twitter, user ID on Pixiv, or the artist tag on Yandere and Panda.
+    pub user_id: String,
+    /// If not provided, the default account of the community is used.
+    pub account_id: Option<i32>,
+}
+
+/// Request for importing the whole bookmarks, likes or favorites of an account into the library at once.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportRequest {
+    /// Parameters of a bookmarks, likes or favorites feed.
+    pub params: FeedParams,
+    /// If not provided, the default account of the community is used.
+    pub account_id: Option<i32>,
+    /// Stop after the number of posts are fetched, to bound the import.
+    pub max_posts: Option<u64>,
+    /// Whether to download the images of the imported works.
+    #[serde(default)]
+    pub download: bool,
+}
+
+/// Works in the library to export, given by IDs or as a whole album.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportWorksRequest {
+    #[serde(default)]
+    pub work_ids: Vec<i32>,
+    pub album_id: Option<i32>,
+}
+
+/// Request for rendering albums into a static HTML gallery.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StaticSiteRequest {
+    pub album_ids: Vec<i32>,
+    /// Directory to write the site to, relative to the export root of the server, created if missing.
+    pub output_dir: PathBuf,
+}
+
+/// Request for adding a webhook to a feed or an artist.
 #[derive(Debug, Clone, Deserialize)]
+pub struct NewWebhookRequest {
+    #[serde(default)]
+    pub kind: WebhookKind,
+    /// For Telegram, the bot API server, with the bot token in the path like `https://api.telegram.org/bot<token>`
+    /// if `bot_token` is not given.
+    pub url: String,
+    /// Required for Telegram.
+    pub chat_id: Option<String>,
+    /// Token of the Telegram bot, stored apart from the URL and never returned.
+    pub bot_token: Option<String>,
+    /// Body template with placeholders `{community}`, `{feed_id}`, `{feed_name}`, `{post_count}` and `{post_ids}`.
+    pub template: Option<String>,
+}
+
+/// Request for adding a note to either a post or a work.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewNoteRequest {
+    pub community: Option<String>,
+    pub post_id: Option<String>,
+    pub work_id: Option<i32>,
+    pub content: String,
+}
+
+/// Request for modifying the content of a note.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NoteContentRequest {
+    pub content: String,
+}
+
+/// Request for subscribing an email address to digests.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewDigestRequest {
+    pub email: String,
+    pub frequency: DigestFrequency,
+}
+
+/// Request for adding an account from the credential matching `credential_scheme` in the community metadata.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAccountRequest {
+    pub credential: serde_json::Value,
+}
+
+/// Request for setting the language of the API responses of an account.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountLocaleRequest {
+    /// Language like `en` or `zh-CN`, or `None` for the default language of the community.
+    pub locale: Option<String>,
+}
+
+/// Request for adding an account from cookies exported from the browser.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportCookieRequest {
+    /// A cookie header string, or a Netscape `cookies.txt` export.
+    pub cookies: String,
+}
+
+/// Enum of feed parameters for different community.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum FeedParams {
     Twitter(TwitterFeedParams),
@@ -24,3 +147,42 @@ pub enum FeedParams {
     Panda(PandaFeedParams),
     Yandere(YandereFeedParams),
 }
+
+impl FeedParams {
+    pub fn community(&self) -> &'static str {
+        match self {
+            FeedParams::Twitter(_) => "twitter",
+            FeedParams::Pixiv(_) => "pixiv",
+            FeedParams::Panda(_) => "panda",
+            FeedParams::Yandere(_) => "yandere",
+        }
+    }
+
+    /// Validate the params of the community, with errors of the fields by their paths in the request.
+    pub fn validate(&self) -> BottleResult<()> {
+        let errors = match self {
+            FeedParams::Twitter(params) => params.validate(),
+            FeedParams::Pixiv(params) => params.validate(),
+            FeedParams::Panda(params) => params.validate(),
+            FeedParams::Yandere(params) => params.validate(),
+        };
+        if errors.is_empty() {
+            return Ok(());
+        }
+        let errors = errors
+            .into_iter()
+            .map(|e| FieldError::new(format!("params.{}.{}", self.community(), e.field), e.message))
+            .collect();
+        Err(BottleError::InvalidFields(errors))
+    }
+
+    /// Whether the feed is a collection of the account, like bookmarks, likes or favorites.
+    pub fn is_collection(&self) -> bool {
+        matches!(
+            self,
+            FeedParams::Twitter(TwitterFeedParams::Bookmarks | TwitterFeedParams::Likes { .. })
+                | FeedParams::Pixiv(PixivFeedParams::Bookmarks { .. })
+                | FeedParams::Panda(PandaFeedParams::Favorites { .. })
+        )
+    }
+}
@@ -1,6 +1,9 @@
 pub mod account;
+pub mod admin;
 pub mod api;
 pub mod feed;
 pub mod job;
 pub mod library;
+pub mod public;
+pub mod statistics;
 pub mod work;
@@ -1,22 +1,36 @@
 use axum::{
     extract::{Path, State},
     response::Json,
-    routing::get,
+    routing::{get, post},
     Router,
 };
 
-use bottle_core::feed::{Account, AccountView};
+use bottle_core::{
+    feed::{Account, AccountStatsView, AccountView},
+    Database,
+};
+use bottle_library::AccountRequest;
 use bottle_panda::PandaAccount;
 use bottle_pixiv::PixivAccount;
 use bottle_twitter::TwitterAccount;
 use bottle_yandere::YandereAccount;
+use twitter_client::SessionCookie;
 
-use crate::{error::Result, state::AppState};
+use crate::{
+    error::Result,
+    payload::{AccountLocaleRequest, ImportCookieRequest, NewAccountRequest},
+    state::AppState,
+    util::FeedWrapper,
+};
 
 pub fn account_router() -> Router<AppState> {
     Router::new()
         .route("/:community/accounts", get(get_accounts))
         .route("/:community/account/:id", get(get_account))
+        .route("/accounts/:community/:id/stats", get(get_account_stats))
+        .route("/:community/account/:id/locale", post(set_account_locale))
+        .route("/:community/account", post(add_account))
+        .route("/:community/account/import", post(import_account))
 }
 
 async fn get_accounts(
@@ -46,7 +60,112 @@ async fn get_account(
     Path((community, id)): Path<(String, i32)>,
 ) -> Result<Json<AccountView>> {
     let db = &mut app_state.pool.get()?;
+    let account = find_account(db, &community, id)?;
+
+    Ok(Json(account))
+}
+
+async fn get_account_stats(
+    State(app_state): State<AppState>,
+    Path((community, id)): Path<(String, i32)>,
+) -> Result<Json<AccountStatsView>> {
+    let db = &mut app_state.pool.get()?;
+    // Check if the account exists
+    let _account = find_account(db, &community, id)?;
+
+    let mut stats = AccountRequest::stats(db, &community, id)?;
+    let mut feed_count = 0;
+    for feed in FeedWrapper::all(db, &community)? {
+        if feed.account_id(db).ok().flatten() == Some(id) {
+            feed_count += 1;
+        }
+    }
+    stats.feed_count = feed_count;
+
+    Ok(Json(stats))
+}
+
+/// Add an account from the credential, after logging in if the community supports fetching the account info.
+async fn add_account(
+    State(app_state): State<AppState>,
+    Path(community): Path<String>,
+    Json(payload): Json<NewAccountRequest>,
+) -> Result<Json<AccountView>> {
     let account = match community.as_str() {
+        "twitter" => {
+            let cookie = TwitterAccount::parse_credential(&payload.credential, "credential")?;
+            let info = TwitterAccount::fetch(&cookie).await?;
+            let db = &mut app_state.pool.get()?;
+            TwitterAccount::add(db, &cookie)?.update(db, &info)?.view()
+        }
+        "pixiv" => {
+            let token = PixivAccount::parse_credential(&payload.credential, "credential")?;
+            let info = PixivAccount::fetch(&token).await?;
+            let db = &mut app_state.pool.get()?;
+            PixivAccount::add(db, &token)?.update(db, &info)?.view()
+        }
+        "panda" => {
+            let cookie = PandaAccount::parse_credential(&payload.credential, "credential")?;
+            let db = &mut app_state.pool.get()?;
+            PandaAccount::add(db, &cookie)?.view()
+        }
+        _ => {
+            return Err(bottle_core::Error::InvalidEndpoint(format!("Account of community {}", community)).into());
+        }
+    };
+
+    Ok(Json(account))
+}
+
+/// Set the language of the API responses of the account, which only pixiv supports for tag translations.
+async fn set_account_locale(
+    State(app_state): State<AppState>,
+    Path((community, id)): Path<(String, i32)>,
+    Json(payload): Json<AccountLocaleRequest>,
+) -> Result<Json<AccountView>> {
+    if community != "pixiv" {
+        return Err(bottle_core::Error::InvalidEndpoint(format!("Locale of community {}", community)).into());
+    }
+    let db = &mut app_state.pool.get()?;
+    let account = PixivAccount::get(db, id)?.ok_or(bottle_core::Error::ObjectNotFound(format!(
+        "Account {} at Community {}",
+        id, community
+    )))?;
+    let account = account.set_locale(db, payload.locale.as_deref())?;
+
+    Ok(Json(account.view()))
+}
+
+/// Add an account from browser cookies, after checking that they are logged in.
+/// If the logged-in user already has an account, its cookies are replaced instead, so that its feeds keep working.
+async fn import_account(
+    State(app_state): State<AppState>,
+    Path(community): Path<String>,
+    Json(payload): Json<ImportCookieRequest>,
+) -> Result<Json<AccountView>> {
+    if community != "twitter" {
+        return Err(
+            bottle_core::Error::InvalidEndpoint(format!("Importing cookies for community {}", community)).into(),
+        );
+    }
+    let cookie = payload
+        .cookies
+        .parse::<SessionCookie>()
+        .map_err(|_| bottle_core::Error::InvalidEndpoint("Cookies must contain ct0 and auth_token".to_string()))?;
+    let info = TwitterAccount::fetch(&cookie).await?;
+
+    let db = &mut app_state.pool.get()?;
+    let account = match TwitterAccount::find_by_user(db, info.id as i64)? {
+        Some(account) => account.set_credential(db, &cookie)?,
+        None => TwitterAccount::add(db, &cookie)?,
+    };
+    let account = account.update(db, &info)?;
+
+    Ok(Json(account.view()))
+}
+
+fn find_account(db: Database, community: &str, id: i32) -> Result<AccountView> {
+    let account = match community {
         "twitter" => TwitterAccount::get(db, id)?.map(|a| a.view()),
         "pixiv" => PixivAccount::get(db, id)?.map(|a| a.view()),
         "yandere" => YandereAccount::get(db, id)?.map(|a| a.view()),
@@ -57,6 +176,5 @@ async fn get_account(
         "Account {} at Community {}",
         id, community
     )))?;
-
-    Ok(Json(account))
+    Ok(account)
 }
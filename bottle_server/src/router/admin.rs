@@ -0,0 +1,155 @@
+use axum::{
+    body::StreamBody,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Json},
+    routing::{delete, get, patch, post},
+    Router,
+};
+use diesel::Connection;
+use serde_json::{Map, Value};
+
+use std::collections::{HashMap, VecDeque};
+
+use bottle_core::{cache::CacheStats, setting::Settings, Error as BottleError};
+
+use crate::{
+    backup::{self, Backup, RestoreResult},
+    config::Config,
+    error::Result,
+    export,
+    state::AppState,
+    util::COMMUNITIES,
+};
+
+/// Number of posts in each chunk of the post metadata export.
+const POST_EXPORT_BATCH_SIZE: i64 = 500;
+
+pub fn admin_router() -> Router<AppState> {
+    Router::new()
+        .route("/admin/config", get(get_config))
+        .route("/admin/settings", get(get_settings))
+        .route("/admin/settings", patch(modify_settings))
+        .route("/admin/export", get(export_backup))
+        .route("/admin/import", post(import_backup))
+        .route("/admin/export/posts.jsonl", get(export_posts))
+        .route("/admin/caches", get(get_caches))
+        .route("/admin/caches", delete(clear_caches))
+}
+
+async fn get_config(State(app_state): State<AppState>) -> Json<Config> {
+    Json(app_state.config.redacted())
+}
+
+async fn get_settings(State(app_state): State<AppState>) -> Result<Json<Settings>> {
+    let db = &mut app_state.pool.get()?;
+    let settings = Settings::load(db)?;
+
+    Ok(Json(settings))
+}
+
+async fn modify_settings(
+    State(app_state): State<AppState>,
+    Json(changes): Json<Map<String, Value>>,
+) -> Result<Json<Settings>> {
+    let db = &mut app_state.pool.get()?;
+    let settings = Settings::update(db, changes)?;
+    tracing::info!("Modified settings: {:?}", settings);
+
+    Ok(Json(settings))
+}
+
+/// Size and limits of the caches of each community.
+async fn get_caches(State(app_state): State<AppState>) -> Json<HashMap<String, Vec<CacheStats>>> {
+    Json(HashMap::from([
+        ("twitter".to_string(), app_state.twitter_cache.read().await.stats()),
+        ("pixiv".to_string(), app_state.pixiv_cache.read().await.stats()),
+        ("yandere".to_string(), app_state.yandere_cache.read().await.stats()),
+        ("panda".to_string(), app_state.panda_cache.read().await.stats()),
+    ]))
+}
+
+/// Clear the cached entities of the `community`, or of all communities if not given.
+/// Clients of accounts are kept.
+async fn clear_caches(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<HashMap<String, Vec<CacheStats>>>> {
+    let community = params.get("community").map(String::as_str);
+    if let Some(community) = community.filter(|community| !COMMUNITIES.contains(community)) {
+        Err(BottleError::InvalidEndpoint(format!("Community {}", community)))?;
+    }
+    let selected = |name: &str| community.is_none_or(|community| community == name);
+    if selected("twitter") {
+        app_state.twitter_cache.write().await.clear();
+    }
+    if selected("pixiv") {
+        app_state.pixiv_cache.write().await.clear();
+    }
+    if selected("yandere") {
+        app_state.yandere_cache.write().await.clear();
+    }
+    if selected("panda") {
+        app_state.panda_cache.write().await.clear();
+    }
+    tracing::info!("Cleared caches of {}", community.unwrap_or("all communities"));
+
+    Ok(get_caches(State(app_state)).await)
+}
+
+/// Export accounts, feeds, feed groups and settings. Credentials of accounts are included only if `secrets` is true.
+async fn export_backup(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Backup>> {
+    let include_secrets = params.get("secrets").is_some_and(|value| value == "true");
+
+    let db = &mut app_state.pool.get()?;
+    let backup = backup::export_backup(db, include_secrets)?;
+
+    Ok(Json(backup))
+}
+
+/// Restore an exported backup on top of the current accounts, feeds and settings.
+async fn import_backup(State(app_state): State<AppState>, Json(backup): Json<Backup>) -> Result<Json<RestoreResult>> {
+    let db = &mut app_state.pool.get()?;
+    let result = db.transaction(|db| backup::restore_backup(db, &backup))?;
+
+    Ok(Json(result))
+}
+
+/// Stream the saved posts of the `community`, or of all communities if not given,
+/// as JSON lines of each post with its media, in batches so that the whole export is never held in memory.
+async fn export_posts(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<impl IntoResponse> {
+    let communities = match params.get("community") {
+        Some(community) if COMMUNITIES.contains(&community.as_str()) => VecDeque::from([community.clone()]),
+        Some(community) => Err(BottleError::InvalidEndpoint(format!("Community {}", community)))?,
+        None => COMMUNITIES.iter().map(|community| community.to_string()).collect(),
+    };
+
+    let pool = app_state.pool.clone();
+    let stream = futures::stream::try_unfold((communities, 0), move |(mut communities, mut after_id)| {
+        let pool = pool.clone();
+        async move {
+            while let Some(community) = communities.front() {
+                let db = &mut pool.get()?;
+                match export::export_posts(db, community, after_id, POST_EXPORT_BATCH_SIZE)? {
+                    (lines, Some(last_id)) => return Ok::<_, anyhow::Error>(Some((lines, (communities, last_id)))),
+                    (_, None) => {
+                        communities.pop_front();
+                        after_id = 0;
+                    }
+                }
+            }
+            Ok(None)
+        }
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        StreamBody::new(stream),
+    ))
+}
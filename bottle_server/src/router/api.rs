@@ -7,16 +7,26 @@ use axum::{
 
 use std::collections::HashMap;
 
-use bottle_core::feed::{EndpointRequest, EndpointResponse};
+use bottle_core::{
+    feed::{Endpoint, EndpointRequest, EndpointResponse, FeedView},
+    Error as BottleError,
+};
 use bottle_panda::PandaFeedParams;
 use bottle_pixiv::PixivFeedParams;
 use bottle_twitter::TwitterFeedParams;
 use bottle_yandere::YandereFeedParams;
 
-use crate::{error::Result, state::AppState};
+use crate::{
+    error::Result,
+    payload::{FeedParams, NewFeedRequest, PromoteEndpointRequest},
+    router::feed::add_named_feed,
+    state::AppState,
+};
 
 pub fn api_router() -> Router<AppState> {
     Router::new()
+        .route("/api/:community", post(fetch_endpoint))
+        .route("/api/:community/promote", post(promote_endpoint))
         .route("/twitter/api", post(fetch_twitter_api))
         .route("/pixiv/api", post(fetch_pixiv_api))
         .route("/yandere/api", post(fetch_yandere_api))
@@ -25,6 +35,63 @@ pub fn api_router() -> Router<AppState> {
         .route("/panda/api/post/:gid/media/:page", get(fetch_panda_media))
 }
 
+/// Browse the community through the normalized endpoint, with responses in the same shape for all communities.
+async fn fetch_endpoint(
+    State(app_state): State<AppState>,
+    Path(community): Path<String>,
+    Json(payload): Json<EndpointRequest<Endpoint>>,
+) -> Result<Json<EndpointResponse>> {
+    let db = &mut app_state.pool.get()?;
+    let response = match community.as_str() {
+        "twitter" => {
+            let cache = &mut app_state.twitter_cache.write().await;
+            bottle_twitter::api::fetch_endpoint(db, cache, &payload).await?
+        }
+        "pixiv" => {
+            let cache = &mut app_state.pixiv_cache.write().await;
+            bottle_pixiv::api::fetch_endpoint(db, cache, &payload).await?
+        }
+        "yandere" => {
+            let cache = &mut app_state.yandere_cache.write().await;
+            bottle_yandere::api::fetch_endpoint(db, cache, &payload).await?
+        }
+        "panda" => {
+            let cache = &mut app_state.panda_cache.write().await;
+            bottle_panda::api::fetch_endpoint(db, cache, &payload).await?
+        }
+        _ => return Err(BottleError::InvalidEndpoint(format!("Community {}", community)).into()),
+    };
+    Ok(Json(response))
+}
+
+/// Add a feed of the browsed endpoint, so that its posts are kept and updated.
+/// Posts browsed before are still in the cache, and can be archived without fetching them again.
+async fn promote_endpoint(
+    State(app_state): State<AppState>,
+    Path(community): Path<String>,
+    Json(request): Json<PromoteEndpointRequest>,
+) -> Result<Json<FeedView>> {
+    let db = &mut app_state.pool.get()?;
+    let params = match community.as_str() {
+        "twitter" => FeedParams::Twitter(bottle_twitter::api::endpoint_params(db, &request.params)?),
+        "pixiv" => FeedParams::Pixiv(bottle_pixiv::api::endpoint_params(db, &request.params)?),
+        "yandere" => FeedParams::Yandere(bottle_yandere::api::endpoint_params(&request.params)?),
+        "panda" => FeedParams::Panda(bottle_panda::api::endpoint_params(&request.params)?),
+        _ => return Err(BottleError::InvalidEndpoint(format!("Community {}", community)).into()),
+    };
+    let request = NewFeedRequest {
+        params,
+        info: request.info,
+        account_id: request.account_id,
+    };
+    request.params.validate()?;
+    request.info.validate()?;
+    let feed = add_named_feed(db, &app_state.caches(), &request).await?;
+    tracing::info!("Promoted browsing of {} to feed {}", community, feed.feed_id);
+
+    Ok(Json(feed))
+}
+
 async fn fetch_twitter_api(
     State(app_state): State<AppState>,
     Json(payload): Json<EndpointRequest<TwitterFeedParams>>,
@@ -4,34 +4,80 @@ use axum::{
     routing::{delete, get, post},
     Router,
 };
+use diesel::Connection;
 use serde_json::{json, Value};
 
 use std::collections::HashMap;
 
-use bottle_core::feed::*;
-use bottle_panda::PandaCommunity;
-use bottle_pixiv::PixivCommunity;
-use bottle_twitter::TwitterCommunity;
-use bottle_yandere::YandereCommunity;
+use bottle_core::{
+    feed::*,
+    library::{AlbumView, ArtistLinkView},
+    mute::UserMute,
+    Database, Error as BottleError,
+};
+use bottle_library::{model, Album, ArtistLink, FeedGroup, PostNote, Statistics, UserPresence, Webhook};
+use bottle_panda::{PandaCommunity, PandaFeed};
+use bottle_pixiv::{PixivCommunity, PixivFeed};
+use bottle_twitter::{TwitterCommunity, TwitterFeed, TwitterUsername};
+use bottle_yandere::{YandereCommunity, YandereFeed};
 
 use crate::{
+    background_job::send_panda_mirror,
+    backup::{self, FeedList, FeedListImportResult},
     error::Result,
-    payload::NewFeedRequest,
-    state::AppState,
-    util::{get_page_and_size, FeedIdentifier, FeedWrapper, DEFAULT_RECENT_COUNT},
+    payload::{ArtistIdentity, NewFeedRequest, NewWebhookRequest, WatchArtistRequest},
+    state::{AppState, CommunityCaches},
+    util::{
+        artist_feed_params, default_account_id, deserialize_request, get_page_and_size, get_post_filter,
+        get_post_order, tag_feed_params, FeedIdentifier, FeedWrapper, COMMUNITIES, DEFAULT_RECENT_COUNT,
+    },
 };
 
+const DEFAULT_SUGGESTION_COUNT: i64 = 20;
+const DEFAULT_SUGGESTION_MIN_WORKS: i32 = 10;
+
 pub fn feed_router() -> Router<AppState> {
     Router::new()
         .route("/metadata", get(metadata))
         .route("/feed", post(add_feed))
+        .route("/artist/watch", post(watch_artist))
+        .route("/feed_suggestions", get(get_feed_suggestions))
+        .route("/feed_list", get(export_feed_list))
+        .route("/feed_list", post(import_feed_list))
         .route("/:community/feeds", get(get_feeds))
         .route("/:community/feed/:id", get(get_feed))
         .route("/:community/feed/:id", delete(delete_feed))
         .route("/:community/feed/:id", post(modify_feed))
         .route("/:community/feed/:id/posts", get(get_feed_posts))
+        .route("/:community/feed/:id/post/:post_id/dismiss", post(dismiss_feed_post))
+        .route("/:community/feed/:id/post/:post_id/dismiss", delete(restore_feed_post))
         .route("/:community/feed/:id/users", get(get_feed_users))
         .route("/:community/feed/:id/user/:user_id", get(get_feed_user_posts))
+        .route("/:community/feed/:id/webhooks", get(get_feed_webhooks))
+        .route("/:community/feed/:id/webhook", post(add_feed_webhook))
+        .route("/:community/user/:user_id/webhooks", get(get_user_webhooks))
+        .route("/:community/user/:user_id/webhook", post(add_user_webhook))
+        .route("/webhook/:id", delete(delete_webhook))
+        .route("/muted_users", get(get_muted_users))
+        .route("/:community/user/:user_id/mute", post(mute_user))
+        .route("/:community/user/:user_id/mute", delete(unmute_user))
+        .route("/twitter/user/:user_id/usernames", get(get_twitter_usernames))
+        .route("/yandere/artist/:name/resolve", post(resolve_yandere_artist))
+        .route("/panda/feed/:id/mirror", post(mirror_panda_feed))
+        .route("/panda/feed/:id/mirror", delete(unmirror_panda_feed))
+        .route("/users/:community/:id", get(get_user_profile))
+        .route("/artist_links", get(get_artist_links))
+        .route("/artist_link/:id/confirm", post(confirm_artist_link))
+        .route("/artist_link/:id/reject", post(reject_artist_link))
+        // Feed group
+        .route("/feeds", get(get_feed_tree))
+        .route("/feed_group", post(add_feed_group))
+        .route("/feed_groups", get(get_feed_groups))
+        .route("/feed_group/:id/rename", post(rename_feed_group))
+        .route("/feed_group/:id/reorder", post(reorder_feed_group))
+        .route("/feed_group/:id", delete(delete_feed_group))
+        .route("/:community/feed/:id/group", post(put_feed_into_group))
+        .route("/:community/feed/:id/group", delete(remove_feed_from_group))
 }
 
 async fn metadata() -> Json<Value> {
@@ -68,13 +114,150 @@ async fn get_feed(
     Ok(Json(feed))
 }
 
-async fn add_feed(State(app_state): State<AppState>, Json(request): Json<NewFeedRequest>) -> Result<Json<FeedView>> {
+async fn add_feed(State(app_state): State<AppState>, Json(request): Json<Value>) -> Result<Json<FeedView>> {
+    let request: NewFeedRequest = deserialize_request(request)?;
+    request.params.validate()?;
+    request.info.validate()?;
     let db = &mut app_state.pool.get()?;
-    let feed = FeedWrapper::add(db, &request)?.view();
+    let feed = add_named_feed(db, &app_state.caches(), &request).await?;
 
     Ok(Json(feed))
 }
 
+/// Add the feed, and name it after the watched user or list if not named,
+/// with its avatar as the icon if no icon is given.
+pub(crate) async fn add_named_feed<'a>(
+    db: Database<'a>,
+    caches: &CommunityCaches,
+    request: &NewFeedRequest,
+) -> Result<FeedView> {
+    let mut feed = FeedWrapper::add(db, request)?;
+    if request.info.name.is_none() || request.info.icon_url.is_none() {
+        match feed.fetch_name(db, caches).await {
+            Ok(Some(name)) => {
+                let info = FeedInfo {
+                    name: request.info.name.clone().or(Some(name.name)),
+                    icon_url: request.info.icon_url.clone().or(name.avatar_url),
+                    ..request.info.clone()
+                };
+                return Ok(feed.modify(db, &info)?);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to fetch name of feed {}: {}", feed.id(), e),
+        }
+    }
+    Ok(feed.view())
+}
+
+/// Add a posts feed for each identity of the artist, and put them into a new feed group.
+async fn watch_artist(
+    State(app_state): State<AppState>,
+    Json(request): Json<WatchArtistRequest>,
+) -> Result<Json<FeedGroupNode>> {
+    if request.identities.is_empty() {
+        return Err(bottle_core::Error::InvalidEndpoint("No identity of the artist".to_string()).into());
+    }
+    let info = FeedInfo {
+        name: None,
+        watching: request.watching.unwrap_or(true),
+        first_fetch_limit: request.first_fetch_limit,
+        icon_url: None,
+        color: None,
+        clear_icon: false,
+        clear_color: false,
+        require_media: false,
+        min_resolution: None,
+    };
+
+    let db = &mut app_state.pool.get()?;
+    // Resolve all the feeds before adding any of them
+    let feed_requests = request
+        .identities
+        .iter()
+        .map(|identity| artist_feed_request(db, identity, &info))
+        .collect::<Result<Vec<_>>>()?;
+
+    let group = FeedGroup::add(db, &request.name)?;
+    let mut feeds = Vec::with_capacity(feed_requests.len());
+    for feed_request in feed_requests.iter() {
+        let feed = add_named_feed(db, &app_state.caches(), feed_request).await?;
+        FeedGroup::add_feed(db, group.id, &feed.community, feed.feed_id, None)?;
+        feeds.push(feed);
+    }
+    tracing::info!(
+        "Watching artist {} by {} feeds in group {}",
+        request.name,
+        feeds.len(),
+        group.id
+    );
+
+    Ok(Json(FeedGroupNode { group, feeds }))
+}
+
+/// Request for the posts feed of the artist in the community.
+fn artist_feed_request(db: Database, identity: &ArtistIdentity, info: &FeedInfo) -> Result<NewFeedRequest> {
+    // Twitter users can be also found by their usernames
+    let artist = match identity.community.as_str() {
+        "twitter" => bottle_twitter::resolve_user_id(db, &identity.user_id)?.to_string(),
+        _ => identity.user_id.clone(),
+    };
+    let params = artist_feed_params(&identity.community, &artist)?;
+    let account_id = match identity.account_id {
+        Some(account_id) => Some(account_id),
+        None => default_account_id(db, &identity.community)?,
+    };
+
+    Ok(NewFeedRequest {
+        params,
+        info: info.clone(),
+        account_id,
+    })
+}
+
+/// Feeds to create for the artists and tags frequent in the library but not watched by any feed yet.
+async fn get_feed_suggestions(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<FeedSuggestionView>>> {
+    let count = match params.get("count") {
+        Some(count) => count.parse::<i64>()?,
+        None => DEFAULT_SUGGESTION_COUNT,
+    };
+    let min_work_count = match params.get("min_works") {
+        Some(min_work_count) => min_work_count.parse::<i32>()?,
+        None => DEFAULT_SUGGESTION_MIN_WORKS,
+    };
+
+    let db = &mut app_state.pool.get()?;
+    let mut suggestions = Vec::new();
+    for artist in Statistics::unwatched_artists(db, min_work_count, count)? {
+        let params = artist_feed_params(&artist.community, &artist.artist)?;
+        suggestions.push(FeedSuggestionView {
+            community: artist.community,
+            reason: "artist".to_string(),
+            key: artist.artist,
+            name: artist.artist_name,
+            work_count: artist.work_count,
+            params: serde_json::to_value(params)?,
+        });
+    }
+    for (community, tag, work_count) in Statistics::unwatched_tags(db, min_work_count as i64, count)? {
+        let params = tag_feed_params(&community, &tag)?;
+        suggestions.push(FeedSuggestionView {
+            community,
+            reason: "tag".to_string(),
+            key: tag.clone(),
+            name: Some(tag),
+            work_count,
+            params: serde_json::to_value(params)?,
+        });
+    }
+    suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.work_count));
+    suggestions.truncate(count.max(0) as usize);
+
+    Ok(Json(suggestions))
+}
+
 async fn delete_feed(State(app_state): State<AppState>, Path((community, id)): Path<(String, i32)>) -> Result<()> {
     let db = &mut app_state.pool.get()?;
     let feed_id = FeedIdentifier::new(&community, id);
@@ -88,6 +271,7 @@ async fn modify_feed(
     Path((community, id)): Path<(String, i32)>,
     Json(info): Json<FeedInfo>,
 ) -> Result<Json<FeedView>> {
+    info.validate()?;
     let db = &mut app_state.pool.get()?;
 
     let feed_id = FeedIdentifier::new(&community, id);
@@ -102,10 +286,13 @@ async fn get_feed_posts(
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<GeneralResponse>> {
     let (page, page_size) = get_page_and_size(&params);
+    let filter = get_post_filter(&params)?;
+    let order = get_post_order(&params)?;
 
     let db = &mut app_state.pool.get()?;
     let feed_id = FeedIdentifier::new(&community, id);
-    let result = FeedWrapper::from_id(db, &feed_id)?.posts(db, page, page_size)?;
+    let result = FeedWrapper::from_id(db, &feed_id)?.posts(db, &filter, order, page, page_size)?;
+    let result = PostNote::adding_to(db, result)?;
 
     Ok(Json(result))
 }
@@ -128,6 +315,28 @@ async fn get_feed_users(
     Ok(Json(result))
 }
 
+async fn dismiss_feed_post(
+    State(app_state): State<AppState>,
+    Path((community, id, post_id)): Path<(String, i32, i64)>,
+) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    let feed_id = FeedIdentifier::new(&community, id);
+    FeedWrapper::from_id(db, &feed_id)?.set_dismissed(db, &[post_id], true)?;
+
+    Ok(())
+}
+
+async fn restore_feed_post(
+    State(app_state): State<AppState>,
+    Path((community, id, post_id)): Path<(String, i32, i64)>,
+) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    let feed_id = FeedIdentifier::new(&community, id);
+    FeedWrapper::from_id(db, &feed_id)?.set_dismissed(db, &[post_id], false)?;
+
+    Ok(())
+}
+
 async fn get_feed_user_posts(
     State(app_state): State<AppState>,
     Path((community, feed_id, user_id)): Path<(String, i32, String)>,
@@ -138,6 +347,476 @@ async fn get_feed_user_posts(
     let db = &mut app_state.pool.get()?;
     let feed_id = FeedIdentifier::new(&community, feed_id);
     let result = FeedWrapper::from_id(db, &feed_id)?.user_posts(db, user_id, page, page_size)?;
+    let result = PostNote::adding_to(db, result)?;
 
     Ok(Json(result))
 }
+
+/// Profile of a user, assembled from the saved posts of all feeds and the archived works.
+async fn get_user_profile(
+    State(app_state): State<AppState>,
+    Path((community, user_id)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<UserProfileView>> {
+    let recent_count = params
+        .get("recent_count")
+        .and_then(|p| p.parse::<i64>().ok())
+        .unwrap_or(DEFAULT_RECENT_COUNT);
+
+    let db = &mut app_state.pool.get()?;
+    // Twitter users can be also found by their current or previous usernames, and yandere artists by their aliases
+    let user_id = match community.as_str() {
+        "twitter" => bottle_twitter::resolve_user_id(db, &user_id)?.to_string(),
+        "yandere" => bottle_yandere::canonical_artist(db, &user_id)?,
+        _ => user_id,
+    };
+    let presence = UserPresence::get(db, &community, &user_id)?;
+
+    let filter = Default::default();
+    let recent = match community.as_str() {
+        "twitter" => TwitterFeed::archived_posts_by_user(db, user_id.clone(), &filter, 0, recent_count),
+        "pixiv" => PixivFeed::archived_posts_by_user(db, user_id.clone(), &filter, 0, recent_count),
+        "yandere" => YandereFeed::archived_posts_by_user(db, user_id.clone(), &filter, 0, recent_count),
+        "panda" => PandaFeed::archived_posts_by_user(db, user_id.clone(), &filter, 0, recent_count),
+        _ => Err(bottle_core::Error::InvalidEndpoint(format!("Community {}", community))),
+    }?;
+
+    let mut user = recent.users.as_ref().and_then(|users| users.first().cloned());
+    let mut feeds = Vec::with_capacity(presence.feed_post_counts.len());
+    for (feed_id, post_count) in presence.feed_post_counts {
+        let feed = FeedWrapper::from_id(db, &FeedIdentifier::new(&community, feed_id))?;
+        // Take the profile from saved posts if none of them is archived
+        if user.is_none() {
+            let posts = feed.user_posts(db, user_id.clone(), 0, 1)?;
+            user = posts.users.and_then(|users| users.into_iter().next());
+        }
+        feeds.push(UserFeedView {
+            feed: feed.view(),
+            post_count,
+        });
+    }
+
+    Ok(Json(UserProfileView {
+        community,
+        user_id,
+        user,
+        feeds,
+        saved_post_count: presence.saved_post_count,
+        archived_post_count: presence.archived_post_count,
+        recent,
+    }))
+}
+
+/// Export the watched feeds as a portable list, or all feeds if `all` is true.
+async fn export_feed_list(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<FeedList>> {
+    let watching_only = params.get("all").is_none_or(|value| value != "true");
+
+    let db = &mut app_state.pool.get()?;
+    let list = backup::export_feed_list(db, watching_only)?;
+
+    Ok(Json(list))
+}
+
+async fn import_feed_list(
+    State(app_state): State<AppState>,
+    Json(list): Json<FeedList>,
+) -> Result<Json<FeedListImportResult>> {
+    let db = &mut app_state.pool.get()?;
+    let result = db.transaction(|db| backup::import_feed_list(db, &list))?;
+
+    Ok(Json(result))
+}
+
+// MARK: Webhook
+
+async fn get_feed_webhooks(
+    State(app_state): State<AppState>,
+    Path((community, id)): Path<(String, i32)>,
+) -> Result<Json<Vec<WebhookView>>> {
+    let db = &mut app_state.pool.get()?;
+    let webhooks = Webhook::of_feed(db, &community, id)?;
+
+    Ok(Json(webhooks))
+}
+
+async fn add_feed_webhook(
+    State(app_state): State<AppState>,
+    Path((community, id)): Path<(String, i32)>,
+    Json(request): Json<NewWebhookRequest>,
+) -> Result<Json<WebhookView>> {
+    let new_webhook = new_webhook(&community, Some(id), None, request)?;
+
+    let db = &mut app_state.pool.get()?;
+    // Make sure the feed exists
+    let feed_id = FeedIdentifier::new(&community, id);
+    FeedWrapper::from_id(db, &feed_id)?;
+    let webhook = Webhook::add(db, new_webhook)?;
+
+    Ok(Json(webhook))
+}
+
+async fn get_user_webhooks(
+    State(app_state): State<AppState>,
+    Path((community, user_id)): Path<(String, String)>,
+) -> Result<Json<Vec<WebhookView>>> {
+    let db = &mut app_state.pool.get()?;
+    let webhooks = Webhook::of_user(db, &community, &user_id)?;
+
+    Ok(Json(webhooks))
+}
+
+/// Usernames the twitter user has used. The user is either an ID or any username it has used.
+async fn get_twitter_usernames(
+    State(app_state): State<AppState>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Vec<TwitterUsername>>> {
+    let db = &mut app_state.pool.get()?;
+    let user_id = bottle_twitter::resolve_user_id(db, &user_id)?;
+    let usernames = bottle_twitter::username_history(db, user_id)?;
+
+    Ok(Json(usernames))
+}
+
+/// Links of artists across communities, filtered by `status`, and by the artist given by `community` and `user_id`.
+async fn get_artist_links(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ArtistLinkView>>> {
+    let status = params.get("status").map(String::as_str);
+    let user = params
+        .get("community")
+        .zip(params.get("user_id"))
+        .map(|(community, user_id)| (community.as_str(), user_id.as_str()));
+
+    let db = &mut app_state.pool.get()?;
+    let links = ArtistLink::list(db, status, user)?;
+
+    Ok(Json(links))
+}
+
+async fn confirm_artist_link(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<Json<ArtistLinkView>> {
+    let db = &mut app_state.pool.get()?;
+    let link = ArtistLink::set_status(db, id, "confirmed")?;
+
+    Ok(Json(link))
+}
+
+/// Reject the link, which is kept so that it's not suggested again.
+async fn reject_artist_link(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<Json<ArtistLinkView>> {
+    let db = &mut app_state.pool.get()?;
+    let link = ArtistLink::set_status(db, id, "rejected")?;
+
+    Ok(Json(link))
+}
+
+/// Resolve the aliases and URLs of the yandere artist from yandere, so that its profile shows its links,
+/// and its posts under the aliases are grouped under the canonical artist.
+async fn resolve_yandere_artist(State(app_state): State<AppState>, Path(name): Path<String>) -> Result<Json<UserView>> {
+    let db = &mut app_state.pool.get()?;
+    let user = bottle_yandere::resolve_artist(db, &name).await?;
+
+    Ok(Json(user))
+}
+
+/// Mirror the panda favorites feed of a category to the album given by `album_id`, or to a new album named after the category,
+/// so that its galleries are archived, downloaded and kept in the album in the order of the favorites.
+/// The feed is mirrored once right away, and then periodically.
+async fn mirror_panda_feed(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<AlbumView>> {
+    let (feed, album) = {
+        let db = &mut app_state.pool.get()?;
+        db.transaction(|db| -> Result<(PandaFeed, AlbumView)> {
+            let mut feed = PandaFeed::get(db, id)?.ok_or(BottleError::ObjectNotFound(format!("Panda feed {}", id)))?;
+            let album = match params.get("album_id") {
+                Some(album_id) => {
+                    let album_id = album_id.parse::<i32>()?;
+                    let album = Album::all(db)?
+                        .into_iter()
+                        .find(|album| album.id == album_id)
+                        .ok_or(BottleError::ObjectNotFound(format!("Album {}", album_id)))?;
+                    if album.filter.is_some() {
+                        Err(BottleError::InvalidEndpoint(format!(
+                            "Album {} is a smart album, which cannot mirror a feed",
+                            album_id
+                        )))?;
+                    }
+                    album
+                }
+                None => {
+                    let name = feed
+                        .favorite_category_name
+                        .clone()
+                        .or(feed.name.clone())
+                        .unwrap_or_else(|| format!("Panda favorites {}", id));
+                    Album::add(db, &name, None, None)?
+                }
+            };
+            feed.set_mirror_album(db, Some(album.id))?;
+            Ok((feed, album))
+        })?
+    };
+
+    send_panda_mirror(&app_state, feed);
+    Ok(Json(album))
+}
+
+/// Stop mirroring the panda feed. The album and its works are kept.
+async fn unmirror_panda_feed(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    let mut feed = PandaFeed::get(db, id)?.ok_or(BottleError::ObjectNotFound(format!("Panda feed {}", id)))?;
+    feed.set_mirror_album(db, None)?;
+    Ok(())
+}
+
+async fn add_user_webhook(
+    State(app_state): State<AppState>,
+    Path((community, user_id)): Path<(String, String)>,
+    Json(request): Json<NewWebhookRequest>,
+) -> Result<Json<WebhookView>> {
+    if !matches!(community.as_str(), "twitter" | "pixiv" | "yandere" | "panda") {
+        return Err(bottle_core::Error::InvalidEndpoint(format!("Community {}", community)).into());
+    }
+    let new_webhook = new_webhook(&community, None, Some(user_id), request)?;
+
+    let db = &mut app_state.pool.get()?;
+    let webhook = Webhook::add(db, new_webhook)?;
+
+    Ok(Json(webhook))
+}
+
+async fn delete_webhook(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    Webhook::delete(db, id)?;
+
+    Ok(())
+}
+
+async fn get_muted_users(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<UserMute>>> {
+    let db = &mut app_state.pool.get()?;
+    let mutes = UserMute::all(db, params.get("community").map(String::as_str))?;
+
+    Ok(Json(mutes))
+}
+
+/// Mute the user in all feeds of the community, with an optional `reason`, so that their posts are no longer saved.
+async fn mute_user(
+    State(app_state): State<AppState>,
+    Path((community, user_id)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<UserMute>> {
+    let db = &mut app_state.pool.get()?;
+    let user_id = muted_user_id(db, &community, user_id)?;
+    let mute = UserMute::add(db, &community, &user_id, params.get("reason").map(String::as_str))?;
+    tracing::info!("Muted {} user {}", community, user_id);
+
+    Ok(Json(mute))
+}
+
+async fn unmute_user(
+    State(app_state): State<AppState>,
+    Path((community, user_id)): Path<(String, String)>,
+) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    let user_id = muted_user_id(db, &community, user_id)?;
+    UserMute::remove(db, &community, &user_id)?;
+    tracing::info!("Unmuted {} user {}", community, user_id);
+
+    Ok(())
+}
+
+/// User ID as the feeds save it, where Twitter users can be also given by their usernames.
+fn muted_user_id(db: Database, community: &str, user_id: String) -> Result<String> {
+    let user_id = match community {
+        "twitter" => bottle_twitter::resolve_user_id(db, &user_id)?.to_string(),
+        "pixiv" | "yandere" | "panda" => user_id,
+        _ => return Err(BottleError::InvalidEndpoint(format!("Community {}", community)).into()),
+    };
+    Ok(user_id)
+}
+
+/// Validate the request and prepare a webhook to insert.
+fn new_webhook(
+    community: &str,
+    feed_id: Option<i32>,
+    user_id: Option<String>,
+    request: NewWebhookRequest,
+) -> Result<model::NewWebhook> {
+    reqwest::Url::parse(&request.url)
+        .map_err(|e| bottle_core::Error::InvalidEndpoint(format!("Webhook URL {}: {}", request.url, e)))?;
+    let (url, bot_token) = match request.kind {
+        WebhookKind::Telegram => {
+            if request.chat_id.is_none() {
+                return Err(bottle_core::Error::InvalidEndpoint("Chat ID is required for Telegram".to_string()).into());
+            }
+            telegram_bot(&request.url, request.bot_token)?
+        }
+        _ => (request.url, None),
+    };
+
+    Ok(model::NewWebhook {
+        kind: request.kind.as_str().to_string(),
+        community: community.to_string(),
+        feed_id,
+        user_id,
+        url,
+        chat_id: request.chat_id,
+        bot_token,
+        template: request.template,
+    })
+}
+
+/// Split the bot API server and the bot token of a Telegram webhook, taking the token from the URL if not given.
+fn telegram_bot(url: &str, bot_token: Option<String>) -> Result<(String, Option<String>)> {
+    let url = url.trim_end_matches('/');
+    let (server, url_token) = match url.rsplit_once("/bot") {
+        Some((server, token)) if !token.is_empty() && !token.contains('/') => (server, Some(token.to_string())),
+        _ => (url, None),
+    };
+    let bot_token =
+        bot_token
+            .filter(|token| !token.trim().is_empty())
+            .or(url_token)
+            .ok_or(bottle_core::Error::InvalidEndpoint(
+                "Bot token is required for Telegram".to_string(),
+            ))?;
+    Ok((server.to_string(), Some(bot_token.trim().to_string())))
+}
+
+// MARK: Feed group
+
+/// Get feeds of all communities organized by their groups.
+async fn get_feed_tree(State(app_state): State<AppState>) -> Result<Json<FeedTreeView>> {
+    let db = &mut app_state.pool.get()?;
+
+    let mut feed_map = HashMap::new();
+    for community in COMMUNITIES {
+        for feed in FeedWrapper::all(db, community)? {
+            feed_map.insert(feed.id(), feed.view());
+        }
+    }
+
+    let mut groups = FeedGroup::all(db)?
+        .into_iter()
+        .map(|group| FeedGroupNode { group, feeds: vec![] })
+        .collect::<Vec<_>>();
+    for member in FeedGroup::all_feeds(db)? {
+        let feed_id = FeedIdentifier::new(&member.community, member.feed_id);
+        let Some(node) = groups.iter_mut().find(|node| node.group.id == member.group_id) else {
+            continue;
+        };
+        if let Some(feed) = feed_map.remove(&feed_id) {
+            node.feeds.push(feed);
+        }
+    }
+
+    // Remaining feeds are not in any group
+    let mut ungrouped = feed_map.into_values().collect::<Vec<_>>();
+    ungrouped.sort_by(|a, b| {
+        let community_index = |community: &str| COMMUNITIES.iter().position(|c| *c == community);
+        (community_index(&a.community), a.feed_id).cmp(&(community_index(&b.community), b.feed_id))
+    });
+
+    Ok(Json(FeedTreeView { groups, ungrouped }))
+}
+
+async fn add_feed_group(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<FeedGroupView>> {
+    let name = params.get("name").ok_or(bottle_core::Error::InvalidEndpoint(
+        "Feed group name is required".to_string(),
+    ))?;
+
+    let db = &mut app_state.pool.get()?;
+    let group = FeedGroup::add(db, name)?;
+
+    Ok(Json(group))
+}
+
+async fn get_feed_groups(State(app_state): State<AppState>) -> Result<Json<Vec<FeedGroupView>>> {
+    let db = &mut app_state.pool.get()?;
+    let groups = FeedGroup::all(db)?;
+
+    Ok(Json(groups))
+}
+
+async fn rename_feed_group(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<FeedGroupView>> {
+    let name = params.get("name").ok_or(bottle_core::Error::InvalidEndpoint(
+        "Feed group name is required".to_string(),
+    ))?;
+
+    let db = &mut app_state.pool.get()?;
+    let group = FeedGroup::rename(db, id, name)?;
+
+    Ok(Json(group))
+}
+
+async fn reorder_feed_group(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<FeedGroupView>> {
+    let position = match params.get("position") {
+        Some(position) => Some(position.parse::<i32>()?),
+        None => None,
+    };
+
+    let db = &mut app_state.pool.get()?;
+    let group = FeedGroup::reorder(db, id, position)?;
+
+    Ok(Json(group))
+}
+
+async fn delete_feed_group(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    FeedGroup::delete(db, id)?;
+
+    Ok(())
+}
+
+/// Put the feed into the group at `position`, or at the end of the group.
+async fn put_feed_into_group(
+    State(app_state): State<AppState>,
+    Path((community, id)): Path<(String, i32)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<model::FeedGroupFeed>> {
+    let group_id = params
+        .get("group_id")
+        .ok_or(bottle_core::Error::InvalidEndpoint("Group ID is required".to_string()))?
+        .parse::<i32>()?;
+    let position = match params.get("position") {
+        Some(position) => Some(position.parse::<i32>()?),
+        None => None,
+    };
+
+    let db = &mut app_state.pool.get()?;
+    // Make sure the feed exists
+    let feed_id = FeedIdentifier::new(&community, id);
+    FeedWrapper::from_id(db, &feed_id)?;
+    let member = FeedGroup::add_feed(db, group_id, &community, id, position)?;
+
+    Ok(Json(member))
+}
+
+async fn remove_feed_from_group(
+    State(app_state): State<AppState>,
+    Path((community, id)): Path<(String, i32)>,
+) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    FeedGroup::remove_feed(db, &community, id)?;
+
+    Ok(())
+}
@@ -1,25 +1,49 @@
+use std::collections::HashMap;
+
+use tokio::task;
+use tracing::Instrument;
+
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::Json,
-    routing::get,
+    routing::{delete, get, post},
     Router,
 };
 
+use bottle_core::feed::{DigestView, Feed};
+use bottle_library::{Digest, FeedGroup};
+use bottle_panda::{PandaBackfillOffset, PandaFeed};
+
 use crate::{
     background_job::*,
     error::Result,
+    payload::{ImportRequest, NewDigestRequest},
     state::AppState,
-    util::{FeedIdentifier, FeedWrapper},
+    util::{parse_page_ranges, FeedIdentifier, FeedWrapper},
 };
 
 pub fn job_router() -> Router<AppState> {
     Router::new()
         .route("/jobs", get(get_jobs))
+        .route("/jobs/downloads", get(get_download_jobs))
+        .route("/jobs/imports", get(get_import_jobs))
+        .route("/jobs/album_exports", get(get_album_export_jobs))
+        .route("/import", post(handle_import))
         .route("/:community/feed/:id/update", get(handle_update_feed))
+        .route("/panda/feed/:id/backfill", post(handle_backfill_panda_feed))
         .route("/:community/feeds/update", get(handle_update_all_feed))
+        .route("/feed_group/:id/update", get(handle_update_feed_group))
         .route("/images/download", get(handle_download_image))
+        .route("/covers/generate", get(handle_generate_covers))
+        .route("/boorus/upload", get(handle_upload_to_boorus))
         .route("/panda/galleries/download", get(handle_download_all_panda_gallery))
         .route("/panda/gallery/:id/download", get(handle_download_panda_gallery))
+        .route("/panda/gallery/:id/retry_failures", post(handle_retry_panda_failures))
+        .route("/panda/galleries/check", get(handle_check_panda_galleries))
+        .route("/panda/favorites/mirror", get(handle_mirror_panda_favorites))
+        .route("/digests", get(get_digests))
+        .route("/digest", post(add_digest))
+        .route("/digest/:id", delete(delete_digest))
 }
 
 async fn handle_update_feed(
@@ -40,27 +64,114 @@ async fn handle_update_feed(
     Ok(())
 }
 
+/// Update the panda search feed starting from the percentage or the date in the body, deep in the results,
+/// and continue from there in the following updates until the end is reached.
+async fn handle_backfill_panda_feed(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(offset): Json<PandaBackfillOffset>,
+) -> Result<()> {
+    {
+        let db = &mut app_state.pool.get()?;
+        let mut feed =
+            PandaFeed::get(db, id)?.ok_or(bottle_core::Error::ObjectNotFound(format!("Panda feed {}", id)))?;
+        feed.set_backfill_offset(db, offset)?;
+    }
+
+    let id = FeedIdentifier::new("panda", id);
+    let did_send = send_feed_update(&app_state, id.clone()).await?;
+    if !did_send {
+        tracing::warn!(
+            "Feed {} update job is already running, backfill starts in the next update",
+            id
+        );
+    }
+    Ok(())
+}
+
+/// Update the watched feeds of the community. Feeds stopped being watched, like those failing repeatedly, are skipped.
+/// Jobs are queued in the background, waiting for room if the queue is full.
 async fn handle_update_all_feed(State(app_state): State<AppState>, Path(community): Path<String>) -> Result<()> {
-    let db = &mut app_state.pool.get()?;
-    let feeds = FeedWrapper::all(db, &community)?;
+    let ids = {
+        let db = &mut app_state.pool.get()?;
+        FeedWrapper::all(db, &community)?
+            .iter()
+            .filter(|feed| feed.view().watching)
+            .map(|feed| feed.id())
+            .collect::<Vec<_>>()
+    };
+
+    queue_feed_updates(app_state, ids);
+    Ok(())
+}
 
-    for feed in feeds.iter() {
-        let did_send = send_feed_update(&app_state, feed.id()).await?;
-        if !did_send {
-            tracing::warn!("Feed {} update job is already running", feed.id());
+/// Update the watched feeds in the group. Jobs are queued in the background, waiting for room if the queue is full.
+async fn handle_update_feed_group(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let ids = {
+        let db = &mut app_state.pool.get()?;
+        if FeedGroup::get(db, id)?.is_none() {
+            return Err(bottle_core::Error::ObjectNotFound(format!("Feed group {}", id)).into());
         }
-    }
+        let mut ids = Vec::new();
+        for member in FeedGroup::feeds(db, id)? {
+            let feed_id = FeedIdentifier::new(&member.community, member.feed_id);
+            if FeedWrapper::from_id(db, &feed_id).is_ok_and(|feed| feed.view().watching) {
+                ids.push(feed_id);
+            }
+        }
+        ids
+    };
 
+    queue_feed_updates(app_state, ids);
     Ok(())
 }
 
+fn queue_feed_updates(app_state: AppState, ids: Vec<FeedIdentifier>) {
+    task::spawn(
+        async move {
+            for id in ids {
+                match queue_feed_update(&app_state, id.clone()).await {
+                    Ok(true) => {}
+                    Ok(false) => tracing::warn!("Feed {} update job is already running", id),
+                    Err(e) => tracing::error!("Failed to queue feed {} update: {}", id, e),
+                }
+            }
+        }
+        .in_current_span(),
+    );
+}
+
 async fn handle_download_image(State(app_state): State<AppState>) -> Result<()> {
     send_image_download(&app_state).await
 }
 
-async fn handle_download_panda_gallery(State(app_state): State<AppState>, Path(id): Path<i64>) -> Result<()> {
+async fn handle_generate_covers(State(app_state): State<AppState>) -> Result<()> {
+    generate_covers(app_state.pool.clone(), app_state.config.image_dir.clone()).await
+}
+
+async fn handle_upload_to_boorus(State(app_state): State<AppState>) -> Result<()> {
+    upload_to_boorus(
+        app_state.pool.clone(),
+        app_state.config.image_dir.clone(),
+        &app_state.config.boorus,
+    )
+    .await
+}
+
+/// Download the gallery, or only the pages in `pages` if given, e.g. `1-20,25`.
+/// Images are downloaded in page order if `ordered` is true, so that they can be read before the job finishes.
+async fn handle_download_panda_gallery(
+    State(app_state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<()> {
     let db = &mut app_state.pool.get()?;
-    let tasks = bottle_panda::download::get_download_task(db, id)?;
+    let mut tasks = bottle_panda::download::get_download_task(db, id)?;
+    if let Some(pages) = params.get("pages") {
+        tasks = tasks.with_indices(parse_page_ranges(pages)?)?;
+    }
+    let ordered = params.get("ordered").is_some_and(|value| value == "true");
+    tasks = tasks.with_ordered(ordered);
 
     let did_send = send_panda_download(&app_state, tasks).await?;
     if !did_send {
@@ -71,21 +182,100 @@ async fn handle_download_panda_gallery(State(app_state): State<AppState>, Path(i
     Ok(())
 }
 
-async fn handle_download_all_panda_gallery(State(app_state): State<AppState>) -> Result<()> {
+/// Download again only the images failed in the last runs, without fetching the whole gallery metadata.
+async fn handle_retry_panda_failures(State(app_state): State<AppState>, Path(id): Path<i64>) -> Result<()> {
     let db = &mut app_state.pool.get()?;
-    let tasks = bottle_panda::download::get_all_download_tasks(db)?;
+    let indices = bottle_panda::download::failed_indices(db, id)?;
+    if indices.is_empty() {
+        return Err(bottle_core::Error::ObjectNotFound(format!("Failed images of panda gallery {}", id)).into());
+    }
+    let tasks = bottle_panda::download::get_download_task(db, id)?
+        .with_indices(indices)?
+        .ignoring_backoff();
 
-    if tasks.is_empty() {
-        tracing::info!("Panda download job done. No gallery to download");
+    let did_send = send_panda_download(&app_state, tasks).await?;
+    if !did_send {
+        tracing::warn!("Panda gallery {} download job is already running", id);
+        return Err(anyhow::anyhow!("Panda gallery {} download job is already running", id))?;
     }
 
-    for task in tasks {
-        send_panda_download(&app_state, task).await?;
+    Ok(())
+}
+
+/// Download all galleries not downloaded yet. Jobs are queued in the background, waiting for room if the queue is full.
+async fn handle_download_all_panda_gallery(State(app_state): State<AppState>) -> Result<()> {
+    let tasks = {
+        let db = &mut app_state.pool.get()?;
+        bottle_panda::download::get_all_download_tasks(db)?
+    };
+
+    if tasks.is_empty() {
+        tracing::info!("Panda download job done. No gallery to download");
     }
 
+    task::spawn(
+        async move {
+            for task in tasks {
+                let gid = task.gid;
+                if let Err(e) = queue_panda_download(&app_state, task).await {
+                    tracing::error!("Failed to queue download of panda gallery {}: {}", gid, e);
+                }
+            }
+        }
+        .in_current_span(),
+    );
     Ok(())
 }
 
+/// Check archived galleries for expunged or replaced ones, and archive the newer versions if `follow` is true.
+async fn handle_check_panda_galleries(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<()> {
+    let follow = params.get("follow").map(|value| value == "true");
+    check_panda_galleries(&app_state, follow).await
+}
+
+/// Update the panda favorites feeds mirrored to albums, and archive, download and add their new galleries to the albums.
+async fn handle_mirror_panda_favorites(State(app_state): State<AppState>) -> Result<()> {
+    mirror_panda_favorites(&app_state).await
+}
+
+/// Import the whole bookmarks, likes or favorites of an account into the library, and download them if requested.
+async fn handle_import(
+    State(app_state): State<AppState>,
+    Json(request): Json<ImportRequest>,
+) -> Result<Json<ImportJobStateResponse>> {
+    let state = send_import(&app_state, request).await?;
+    Ok(Json(state))
+}
+
+/// Import jobs since the server started, latest first.
+async fn get_import_jobs(State(app_state): State<AppState>) -> Json<Vec<ImportJobStateResponse>> {
+    let mut jobs = app_state
+        .import_job_state_map
+        .read()
+        .await
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    jobs.sort_by_key(|job| std::cmp::Reverse(job.id));
+    Json(jobs)
+}
+
+/// Album export jobs since the server started, latest first.
+async fn get_album_export_jobs(State(app_state): State<AppState>) -> Json<Vec<AlbumExportJobStateResponse>> {
+    let mut jobs = app_state
+        .album_export_job_state_map
+        .read()
+        .await
+        .values()
+        .cloned()
+        .collect::<Vec<_>>();
+    jobs.sort_by_key(|job| std::cmp::Reverse(job.id));
+    Json(jobs)
+}
+
 async fn get_jobs(State(app_state): State<AppState>) -> Json<JobsStateResponse> {
     let feed_update_state_map = app_state.feed_update_state_map.read().await.clone();
 
@@ -107,9 +297,79 @@ async fn get_jobs(State(app_state): State<AppState>) -> Json<JobsStateResponse>
         }
     }
 
+    let mut queues = app_state
+        .feed_update_queues
+        .values()
+        .map(|queue| queue.state())
+        .collect::<Vec<_>>();
+    queues.sort_by(|a, b| a.name.cmp(&b.name));
+    queues.push(app_state.image_download_queue.state());
+    queues.push(app_state.panda_download_queue.state());
+
     Json(JobsStateResponse {
         feed_update_jobs,
         image_download_job,
         panda_download_jobs,
+        thumbnail_job: app_state.thumbnail_pool.state(),
+        queues,
     })
 }
+
+/// All download jobs in a uniform list, running ones first. Finished jobs are included if `finished` is true.
+async fn get_download_jobs(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Json<Vec<DownloadJobResponse>> {
+    let include_finished = params.get("finished").is_some_and(|value| value == "true");
+
+    let mut jobs = Vec::new();
+    // The image download job is idle when ready, unlike queued panda jobs
+    let image_job = DownloadJobResponse::from(&*app_state.image_download_job_state.borrow());
+    if !matches!(image_job.state, GeneralJobState::Ready) {
+        jobs.push(image_job);
+    }
+    let panda_state_map = app_state.panda_download_state_map.read().await.clone();
+    let panda_title_map = app_state.panda_gallery_title_map.read().await.clone();
+    for (id, rx) in panda_state_map.iter() {
+        if let Some(title) = panda_title_map.get(id) {
+            let state = rx.borrow().clone();
+            jobs.push(DownloadJobResponse::from_panda(id, title.clone(), &state));
+        }
+    }
+
+    let mut jobs = jobs
+        .into_iter()
+        .filter(|job| include_finished || !job.finished())
+        .collect::<Vec<_>>();
+    jobs.sort_by_key(|job| !matches!(job.state, GeneralJobState::Running));
+    Json(jobs)
+}
+
+async fn get_digests(State(app_state): State<AppState>) -> Result<Json<Vec<DigestView>>> {
+    let db = &mut app_state.pool.get()?;
+    let digests = Digest::all(db)?;
+
+    Ok(Json(digests))
+}
+
+async fn add_digest(
+    State(app_state): State<AppState>,
+    Json(request): Json<NewDigestRequest>,
+) -> Result<Json<DigestView>> {
+    let email = request.email.trim();
+    if !email.contains('@') || email.contains(|c: char| c.is_whitespace() || c == '<' || c == '>') {
+        return Err(bottle_core::Error::InvalidEndpoint(format!("Email address {}", email)).into());
+    }
+
+    let db = &mut app_state.pool.get()?;
+    let digest = Digest::add(db, email, request.frequency)?;
+
+    Ok(Json(digest))
+}
+
+async fn delete_digest(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    Digest::delete(db, id)?;
+
+    Ok(())
+}
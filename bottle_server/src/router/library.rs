@@ -1,32 +1,50 @@
 use axum::{
-    extract::{Path, Query, State},
-    response::Json,
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{delete, get, post},
     Router,
 };
+use itertools::Itertools;
 
 use std::collections::HashMap;
 
 use bottle_core::{
-    feed::GeneralResponse,
-    library::{AlbumView, FolderView},
+    feed::{GeneralResponse, QueueItemView, ReadingQueueView},
+    library::{
+        AlbumView, FolderView, ImageTextSearchView, LibraryActivityView, LibraryTreeView, NoteView, SmartAlbumFilter,
+        UserTagView, VanishedReportView,
+    },
 };
-use bottle_library::{Album, Folder};
+use bottle_library::{Album, Folder, ImageText, PostNote, ReadingQueue, RemoteStatus, UserTag};
 
 use crate::{
+    album_export::{self, AlbumExportFormat, AlbumPages, ALBUM_EXPORT_SYNC_MAX_IMAGES},
+    background_job::{check_remote_sources, send_album_export},
     error::Result,
+    export::export_entries,
+    hydrus::{self, HydrusExportResult},
+    payload::{ExportWorksRequest, NewNoteRequest, NoteContentRequest, StaticSiteRequest},
     state::AppState,
-    util::{self, get_page_and_size},
+    static_site::{self, StaticSiteResult},
+    util::{
+        self, get_page_and_size, get_work_filter, COMMUNITIES, DEFAULT_HASH_DISTANCE, DEFAULT_RECENT_DAYS,
+        MAX_RECENT_DAYS, MAX_UPLOAD_SIZE, RECENT_COVER_COUNT,
+    },
 };
 
 pub fn library_router() -> Router<AppState> {
     Router::new()
         // Album
         .route("/album", post(add_album))
+        .route("/album/smart", post(add_smart_album))
         .route("/albums", get(get_albums))
         .route("/album/:id/rename", post(rename_album))
         .route("/album/:id/reorder", post(reorder_album))
         .route("/album/:id", delete(delete_album))
+        .route("/album/:id/filter", post(set_album_filter))
+        .route("/album/:id/filter", delete(delete_album_filter))
         .route("/album/:id/works", post(add_album_works))
         .route("/album/:id/works", get(get_album_works))
         .route("/album/:id/works", delete(delete_album_works))
@@ -36,6 +54,41 @@ pub fn library_router() -> Router<AppState> {
         .route("/folder/:id/rename", post(rename_folder))
         .route("/folder/:id/reorder", post(reorder_folder))
         .route("/folder/:id", delete(delete_folder))
+        .route("/library/tree", get(get_library_tree))
+        .route("/library/recent", get(get_recent_activity))
+        .route("/library/vanished", get(get_vanished_report))
+        .route("/library/vanished/check", get(handle_check_remote_sources))
+        // Reading queue
+        .route("/queue", get(get_reading_queue))
+        .route("/queue/pop", post(pop_reading_queue))
+        .route("/queue/:id/reorder", post(reorder_reading_queue_item))
+        .route("/queue/:id", delete(delete_reading_queue_item))
+        .route("/:community/post/:id/queue", post(push_reading_queue))
+        // User tag
+        .route("/user_tag", post(add_user_tag))
+        .route("/user_tags", get(get_user_tags))
+        .route("/user_tags/autocomplete", get(autocomplete_user_tags))
+        .route("/user_tag/:id/rename", post(rename_user_tag))
+        .route("/user_tag/:id", delete(delete_user_tag))
+        .route("/user_tag/:id/works", post(add_user_tag_works))
+        .route("/user_tag/:id/works", delete(delete_user_tag_works))
+        .route("/work/:id/user_tags", get(get_work_user_tags))
+        // Note
+        .route("/note", post(add_note))
+        .route("/note/:id", post(modify_note))
+        .route("/note/:id", delete(delete_note))
+        .route("/notes", get(search_notes))
+        .route("/library/text", get(search_image_texts))
+        // Lookup
+        .route("/library/lookup", get(lookup_by_hash))
+        .route(
+            "/library/lookup",
+            post(lookup_by_file).layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE)),
+        )
+        // Export
+        .route("/library/export/hydrus", post(export_to_hydrus))
+        .route("/library/export/static_site", post(export_static_site))
+        .route("/library/albums/:id/export", get(export_album))
 }
 
 // MARK: Album
@@ -53,11 +106,61 @@ async fn add_album(
     };
 
     let conn = &mut app_state.pool.get()?;
-    let album = Album::add(conn, name, folder_id)?;
+    let album = Album::add(conn, name, folder_id, None)?;
 
     Ok(Json(album))
 }
 
+/// Add a smart album whose works are the ones in the library matching the filter in the body.
+async fn add_smart_album(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    Json(filter): Json<SmartAlbumFilter>,
+) -> Result<Json<AlbumView>> {
+    let name = params.get("name").ok_or(bottle_core::Error::InvalidEndpoint(
+        "Album name is required".to_string(),
+    ))?;
+    let folder_id = match params.get("folder_id") {
+        Some(id) => Some(id.parse::<i32>()?),
+        None => None,
+    };
+    validate_smart_album_filter(&filter)?;
+
+    let conn = &mut app_state.pool.get()?;
+    let album = Album::add(conn, name, folder_id, Some(&filter))?;
+
+    Ok(Json(album))
+}
+
+async fn set_album_filter(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(filter): Json<SmartAlbumFilter>,
+) -> Result<Json<AlbumView>> {
+    validate_smart_album_filter(&filter)?;
+
+    let conn = &mut app_state.pool.get()?;
+    let album = Album::set_filter(conn, id, Some(&filter))?;
+
+    Ok(Json(album))
+}
+
+async fn delete_album_filter(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<Json<AlbumView>> {
+    let conn = &mut app_state.pool.get()?;
+    let album = Album::set_filter(conn, id, None)?;
+
+    Ok(Json(album))
+}
+
+fn validate_smart_album_filter(filter: &SmartAlbumFilter) -> Result<()> {
+    if let Some(community) = &filter.community {
+        if !COMMUNITIES.contains(&community.as_str()) {
+            Err(bottle_core::Error::InvalidEndpoint(format!("Community {}", community)))?;
+        }
+    }
+    Ok(())
+}
+
 async fn get_albums(State(app_state): State<AppState>) -> Result<Json<Vec<AlbumView>>> {
     let conn = &mut app_state.pool.get()?;
     let albums = Album::all(conn)?;
@@ -119,7 +222,7 @@ async fn add_album_works(
         .collect::<std::result::Result<Vec<_>, std::num::ParseIntError>>()?;
 
     let conn = &mut app_state.pool.get()?;
-    Album::add_works(conn, id, work_ids.into_iter())?;
+    Album::add_works(conn, id, work_ids)?;
 
     Ok(())
 }
@@ -130,12 +233,14 @@ async fn get_album_works(
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<GeneralResponse>> {
     let (page, page_size) = get_page_and_size(&params);
+    let filter = get_work_filter(&params)?;
 
     let conn = &mut app_state.pool.get()?;
-    let response = Album::works(conn, id, page, page_size)?;
+    let response = Album::works(conn, id, &filter, page, page_size)?;
 
-    // Add community entities to the response
+    // Add community entities and notes to the response
     let response = util::adding_community_entities(conn, response)?;
+    let response = PostNote::adding_to(conn, response)?;
 
     Ok(Json(response))
 }
@@ -153,7 +258,7 @@ async fn delete_album_works(
         .collect::<std::result::Result<Vec<_>, std::num::ParseIntError>>()?;
 
     let conn = &mut app_state.pool.get()?;
-    Album::remove_works(conn, id, work_ids.into_iter())?;
+    Album::remove_works(conn, id, work_ids)?;
 
     Ok(())
 }
@@ -185,6 +290,14 @@ async fn get_folders(State(app_state): State<AppState>) -> Result<Json<Vec<Folde
     Ok(Json(folders))
 }
 
+/// Hierarchy of the folders and albums with their work counts and covers, for showing the sidebar at once.
+async fn get_library_tree(State(app_state): State<AppState>) -> Result<Json<LibraryTreeView>> {
+    let conn = &mut app_state.pool.get()?;
+    let tree = Folder::tree(conn)?;
+
+    Ok(Json(tree))
+}
+
 async fn rename_folder(
     State(app_state): State<AppState>,
     Path(id): Path<i32>,
@@ -225,3 +338,468 @@ async fn delete_folder(State(app_state): State<AppState>, Path(id): Path<i32>) -
     Folder::delete(conn, id)?;
     Ok(())
 }
+
+// MARK: Reading queue
+
+/// Items of the reading queue in order, with their posts.
+async fn get_reading_queue(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<ReadingQueueView>> {
+    let (page, page_size) = get_page_and_size(&params);
+
+    let db = &mut app_state.pool.get()?;
+    let (items, total_items) = ReadingQueue::items(db, page, page_size)?;
+
+    // Add community entities of the posts
+    let mut posts = Vec::new();
+    let mut media = Vec::new();
+    let mut users = Vec::new();
+    let post_ids_by_community = items
+        .iter()
+        .map(|item| (item.community.as_str(), item.post_id.clone()))
+        .into_group_map();
+    for (community, post_ids) in post_ids_by_community {
+        let response = util::get_entities(db, community, &post_ids)?;
+        posts.extend(response.posts.unwrap_or_default());
+        media.extend(response.media.unwrap_or_default());
+        users.extend(response.users.unwrap_or_default());
+    }
+    let entities = GeneralResponse {
+        posts: Some(posts),
+        media: Some(media),
+        users: Some(users),
+        total_items,
+        page,
+        page_size,
+        ..Default::default()
+    };
+
+    Ok(Json(ReadingQueueView { items, entities }))
+}
+
+/// Push a post to the end of the queue, or to the front with `front=true`.
+async fn push_reading_queue(
+    State(app_state): State<AppState>,
+    Path((community, post_id)): Path<(String, String)>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<QueueItemView>> {
+    let front = params.get("front").is_some_and(|value| value == "true");
+
+    let db = &mut app_state.pool.get()?;
+    if util::get_posts(db, &community, std::slice::from_ref(&post_id))?.is_empty() {
+        return Err(bottle_core::Error::ObjectNotFound(format!("Post {} at Community {}", post_id, community)).into());
+    }
+    let item = ReadingQueue::push(db, &community, &post_id, front)?;
+
+    Ok(Json(item))
+}
+
+/// Remove and return the item at the front of the queue, or null if the queue is empty.
+async fn pop_reading_queue(State(app_state): State<AppState>) -> Result<Json<Option<QueueItemView>>> {
+    let db = &mut app_state.pool.get()?;
+    let item = ReadingQueue::pop(db)?;
+    Ok(Json(item))
+}
+
+async fn reorder_reading_queue_item(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<QueueItemView>> {
+    let position = match params.get("position") {
+        Some(position) => Some(position.parse::<i32>()?),
+        None => None,
+    };
+
+    let db = &mut app_state.pool.get()?;
+    let item = ReadingQueue::reorder(db, id, position)?;
+
+    Ok(Json(item))
+}
+
+async fn delete_reading_queue_item(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let db = &mut app_state.pool.get()?;
+    ReadingQueue::delete(db, id)?;
+    Ok(())
+}
+
+// MARK: User tag
+
+const DEFAULT_AUTOCOMPLETE_LIMIT: usize = 10;
+
+async fn add_user_tag(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<UserTagView>> {
+    let name = params.get("name").ok_or(bottle_core::Error::InvalidEndpoint(
+        "User tag name is required".to_string(),
+    ))?;
+
+    let conn = &mut app_state.pool.get()?;
+    let tag = UserTag::add(conn, name)?;
+
+    Ok(Json(tag))
+}
+
+async fn get_user_tags(State(app_state): State<AppState>) -> Result<Json<Vec<UserTagView>>> {
+    let conn = &mut app_state.pool.get()?;
+    let tags = UserTag::all(conn)?;
+
+    Ok(Json(tags))
+}
+
+/// User tags starting with `q`, the most used first.
+async fn autocomplete_user_tags(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<UserTagView>>> {
+    let prefix = params.get("q").map(String::as_str).unwrap_or_default();
+    let limit = match params.get("limit") {
+        Some(limit) => limit.parse::<usize>()?,
+        None => DEFAULT_AUTOCOMPLETE_LIMIT,
+    };
+
+    let conn = &mut app_state.pool.get()?;
+    let tags = UserTag::autocomplete(conn, prefix, limit)?;
+
+    Ok(Json(tags))
+}
+
+async fn rename_user_tag(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<UserTagView>> {
+    let name = params.get("name").ok_or(bottle_core::Error::InvalidEndpoint(
+        "User tag name is required".to_string(),
+    ))?;
+
+    let conn = &mut app_state.pool.get()?;
+    let tag = UserTag::rename(conn, id, name)?;
+
+    Ok(Json(tag))
+}
+
+async fn delete_user_tag(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let conn = &mut app_state.pool.get()?;
+    UserTag::delete(conn, id)?;
+    Ok(())
+}
+
+async fn add_user_tag_works(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<()> {
+    let work_ids = params
+        .get("work_ids")
+        .ok_or(bottle_core::Error::InvalidEndpoint("Work IDs are required".to_string()))?
+        .split(',')
+        .map(|s| s.parse::<i32>())
+        .collect::<std::result::Result<Vec<_>, std::num::ParseIntError>>()?;
+
+    let conn = &mut app_state.pool.get()?;
+    UserTag::add_works(conn, id, work_ids)?;
+
+    Ok(())
+}
+
+async fn delete_user_tag_works(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<()> {
+    let work_ids = params
+        .get("work_ids")
+        .ok_or(bottle_core::Error::InvalidEndpoint("Work IDs are required".to_string()))?
+        .split(',')
+        .map(|s| s.parse::<i32>())
+        .collect::<std::result::Result<Vec<_>, std::num::ParseIntError>>()?;
+
+    let conn = &mut app_state.pool.get()?;
+    UserTag::remove_works(conn, id, work_ids)?;
+
+    Ok(())
+}
+
+async fn get_work_user_tags(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<Json<Vec<UserTagView>>> {
+    let conn = &mut app_state.pool.get()?;
+    let tags = UserTag::of_work(conn, id)?;
+
+    Ok(Json(tags))
+}
+
+// MARK: Note
+
+async fn add_note(State(app_state): State<AppState>, Json(request): Json<NewNoteRequest>) -> Result<Json<NoteView>> {
+    let conn = &mut app_state.pool.get()?;
+    let note = PostNote::add(
+        conn,
+        bottle_library::model::NewPostNote {
+            community: request.community,
+            post_id: request.post_id,
+            work_id: request.work_id,
+            content: request.content,
+        },
+    )?;
+
+    Ok(Json(note))
+}
+
+async fn modify_note(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(request): Json<NoteContentRequest>,
+) -> Result<Json<NoteView>> {
+    let conn = &mut app_state.pool.get()?;
+    let note = PostNote::modify(conn, id, &request.content)?;
+
+    Ok(Json(note))
+}
+
+async fn delete_note(State(app_state): State<AppState>, Path(id): Path<i32>) -> Result<()> {
+    let conn = &mut app_state.pool.get()?;
+    PostNote::delete(conn, id)?;
+    Ok(())
+}
+
+/// Search notes containing the text `q`.
+async fn search_notes(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<NoteView>>> {
+    let (page, page_size) = get_page_and_size(&params);
+    let query = params
+        .get("q")
+        .ok_or(bottle_core::Error::InvalidEndpoint("Query is required".to_string()))?;
+
+    let conn = &mut app_state.pool.get()?;
+    let notes = PostNote::search(conn, query, page, page_size)?;
+
+    Ok(Json(notes))
+}
+
+/// Search works with images whose extracted text contains `q`, with the matching images and their texts.
+async fn search_image_texts(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<ImageTextSearchView>> {
+    let (page, page_size) = get_page_and_size(&params);
+    let query = params
+        .get("q")
+        .ok_or(bottle_core::Error::InvalidEndpoint("Query is required".to_string()))?;
+
+    let conn = &mut app_state.pool.get()?;
+    let mut result = ImageText::search(conn, query, page, page_size)?;
+    result.works = util::adding_community_entities(conn, result.works)?;
+    result.works = PostNote::adding_to(conn, result.works)?;
+
+    Ok(Json(result))
+}
+
+// MARK: Lookup
+
+/// Get works added in the last `days` days, grouped by day and community.
+async fn get_recent_activity(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<LibraryActivityView>>> {
+    let days = match params.get("days") {
+        Some(days) => days.parse::<i64>()?.clamp(1, MAX_RECENT_DAYS),
+        None => DEFAULT_RECENT_DAYS,
+    };
+    // Start from the beginning of the day
+    let today = chrono::Utc::now().date_naive();
+    let since = (today - chrono::Duration::days(days - 1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+
+    let conn = &mut app_state.pool.get()?;
+    let activities = bottle_library::recent_activity(conn, since, RECENT_COVER_COUNT)?;
+
+    Ok(Json(activities))
+}
+
+/// Archived works whose remote source has vanished, optionally from a `community`, with counts by artist.
+async fn get_vanished_report(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<VanishedReportView>> {
+    let (page, page_size) = get_page_and_size(&params);
+    let community = params.get("community").map(|c| c.as_str());
+
+    let conn = &mut app_state.pool.get()?;
+    let mut report = RemoteStatus::vanished_report(conn, community, page, page_size)?;
+
+    // Add community entities to the response
+    report.works = util::adding_community_entities(conn, report.works)?;
+
+    Ok(Json(report))
+}
+
+async fn handle_check_remote_sources(State(app_state): State<AppState>) -> Result<()> {
+    check_remote_sources(app_state.pool.clone()).await
+}
+
+/// Find images by a SHA-256 checksum (64 hex digits), an MD5 digest (32 hex digits) or a perceptual hash (16 hex digits).
+async fn lookup_by_hash(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<GeneralResponse>> {
+    let hash = params
+        .get("hash")
+        .ok_or(bottle_core::Error::InvalidEndpoint("Hash is required".to_string()))?
+        .to_lowercase();
+    if !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        Err(bottle_core::Error::InvalidEndpoint(format!("Invalid hash {}", hash)))?;
+    }
+    let max_distance = get_max_distance(&params)?;
+
+    let (checksum, md5, perceptual_hash) = match hash.len() {
+        64 => (Some(hash.as_str()), None, None),
+        32 => (None, Some(hash.as_str()), None),
+        16 => (None, None, Some(hash.as_str())),
+        _ => Err(bottle_core::Error::InvalidEndpoint(format!("Invalid hash {}", hash)))?,
+    };
+
+    let conn = &mut app_state.pool.get()?;
+    let response = bottle_library::lookup_images(conn, checksum, md5, perceptual_hash, max_distance)?;
+
+    Ok(Json(response))
+}
+
+/// Find images identical or similar to the uploaded file in the request body.
+async fn lookup_by_file(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    body: Bytes,
+) -> Result<Json<GeneralResponse>> {
+    if body.is_empty() {
+        Err(bottle_core::Error::InvalidEndpoint("File is required".to_string()))?;
+    }
+    let max_distance = get_max_distance(&params)?;
+    // Decoding the image for its perceptual hash is CPU-bound
+    let hashes = tokio::task::spawn_blocking(move || bottle_download::hash_image(&body)).await?;
+
+    let conn = &mut app_state.pool.get()?;
+    let response = bottle_library::lookup_images(
+        conn,
+        Some(&hashes.checksum),
+        None,
+        hashes.perceptual_hash.as_deref(),
+        max_distance,
+    )?;
+
+    Ok(Json(response))
+}
+
+fn get_max_distance(params: &HashMap<String, String>) -> Result<u32> {
+    let max_distance = match params.get("max_distance") {
+        Some(distance) => distance.parse::<u32>()?,
+        None => DEFAULT_HASH_DISTANCE,
+    };
+    Ok(max_distance)
+}
+
+// MARK: Export
+
+/// Export the images of the works to the Hydrus client through its API,
+/// or write tag sidecars next to the images with `sidecar=true` for importing them with an import folder.
+async fn export_to_hydrus(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    Json(request): Json<ExportWorksRequest>,
+) -> Result<Json<HydrusExportResult>> {
+    let sidecar = params.get("sidecar").is_some_and(|value| value == "true");
+    let entries = {
+        let conn = &mut app_state.pool.get()?;
+        export_entries(conn, &request)?
+    };
+
+    let image_dir = &app_state.config.image_dir;
+    let result = if sidecar {
+        hydrus::write_hydrus_sidecars(image_dir, &entries).await
+    } else {
+        let config = app_state
+            .config
+            .hydrus
+            .as_ref()
+            .ok_or(anyhow::anyhow!("Hydrus is not configured"))?;
+        hydrus::export_to_hydrus(config, image_dir, &entries).await
+    };
+    Ok(Json(result))
+}
+
+/// Package the works of the album in order into a CBZ or PDF given by `format`, bookmarking the first page of each work.
+/// Small albums are returned as a file directly, and larger ones are exported by a job whose state is returned,
+/// with the path of the file relative to the image directory when it is done.
+async fn export_album(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Response> {
+    let format = params
+        .get("format")
+        .map(|format| format.parse::<AlbumExportFormat>())
+        .transpose()?
+        .unwrap_or(AlbumExportFormat::Cbz);
+    let pages = {
+        let conn = &mut app_state.pool.get()?;
+        AlbumPages::load(conn, id)?
+    };
+
+    if pages.paths.len() > ALBUM_EXPORT_SYNC_MAX_IMAGES {
+        let state = send_album_export(&app_state, pages, format).await?;
+        return Ok((StatusCode::ACCEPTED, Json(state)).into_response());
+    }
+
+    let filename = format!("{}.{}", pages.album.name, format.extension());
+    let data = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let mut data = std::io::Cursor::new(Vec::new());
+        album_export::write_album(&pages, &app_state.config.image_dir, format, &mut data, |_| {})?;
+        Ok(data.into_inner())
+    })
+    .await??;
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename*=UTF-8''{}", urlencoding::encode(&filename)),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+/// Render the albums into a self-contained static HTML gallery in the directory, without blocking the runtime.
+async fn export_static_site(
+    State(app_state): State<AppState>,
+    Json(request): Json<StaticSiteRequest>,
+) -> Result<Json<StaticSiteResult>> {
+    // Only directories under the export root can be written
+    let relative = request
+        .output_dir
+        .components()
+        .all(|c| matches!(c, std::path::Component::Normal(_)));
+    if !relative || request.output_dir.as_os_str().is_empty() {
+        return Err(bottle_core::Error::InvalidEndpoint(format!(
+            "Output directory {} must be relative to the export root without `..`",
+            request.output_dir.display()
+        ))
+        .into());
+    }
+    let output_dir = app_state.config.export_root().join(&request.output_dir);
+
+    let result = tokio::task::spawn_blocking(move || -> Result<StaticSiteResult> {
+        let conn = &mut app_state.pool.get()?;
+        let result =
+            static_site::render_static_site(conn, &app_state.config.image_dir, &request.album_ids, &output_dir)?;
+        Ok(result)
+    })
+    .await??;
+    Ok(Json(result))
+}
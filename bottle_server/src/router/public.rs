@@ -0,0 +1,108 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use tower_http::services::ServeDir;
+
+use std::collections::HashMap;
+
+use bottle_core::{feed::GeneralResponse, library::AlbumView};
+use bottle_library::Album;
+
+use crate::{
+    error::Result,
+    state::AppState,
+    util::{self, get_page_and_size, get_work_filter},
+};
+
+/// Router of the read-only public gallery, exposing only the albums in the config and their images.
+pub fn public_router(app_state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/albums", get(get_albums))
+        .route("/album/:id/works", get(get_album_works))
+        .nest_service("/image", ServeDir::new(&app_state.config.image_dir))
+        .layer(middleware::from_fn_with_state(app_state, authorize))
+}
+
+/// Reject requests without the token, and requests of images not in the public albums.
+async fn authorize<B>(State(app_state): State<AppState>, request: Request<B>, next: Next<B>) -> Response {
+    let Some(config) = app_state.config.public.as_ref() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query = request.uri().query().unwrap_or_default();
+    let query_token = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .and_then(|token| urlencoding::decode(token).ok());
+    let authorized = |token: Option<&str>| token.is_some_and(|token| util::constant_time_eq(token, &config.token));
+    if config.token.is_empty() || !(authorized(bearer) || authorized(query_token.as_deref())) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    if let Some(path) = request.uri().path().strip_prefix("/image/") {
+        let Ok(path) = urlencoding::decode(path) else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let contains = app_state
+            .pool
+            .get()
+            .map_err(anyhow::Error::from)
+            .and_then(|mut conn| Ok(Album::contains_file(&mut conn, &config.album_ids, &path)?));
+        match contains {
+            Ok(true) => {}
+            Ok(false) => return StatusCode::NOT_FOUND.into_response(),
+            Err(e) => {
+                tracing::error!("Failed to check public file {}: {}", path, e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+    next.run(request).await
+}
+
+async fn get_albums(State(app_state): State<AppState>) -> Result<Json<Vec<AlbumView>>> {
+    let album_ids = public_album_ids(&app_state);
+    let conn = &mut app_state.pool.get()?;
+    let albums = Album::all(conn)?
+        .into_iter()
+        .filter(|album| album_ids.contains(&album.id))
+        .collect();
+
+    Ok(Json(albums))
+}
+
+async fn get_album_works(
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<GeneralResponse>> {
+    if !public_album_ids(&app_state).contains(&id) {
+        Err(bottle_core::Error::ObjectNotFound(format!("Album {}", id)))?;
+    }
+    let (page, page_size) = get_page_and_size(&params);
+    let filter = get_work_filter(&params)?;
+
+    let conn = &mut app_state.pool.get()?;
+    let response = Album::works(conn, id, &filter, page, page_size)?;
+    let response = util::adding_community_entities(conn, response)?;
+
+    Ok(Json(response))
+}
+
+fn public_album_ids(app_state: &AppState) -> Vec<i32> {
+    app_state
+        .config
+        .public
+        .as_ref()
+        .map(|config| config.album_ids.clone())
+        .unwrap_or_default()
+}
@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::{Datelike, Duration, Utc};
+
+use std::collections::HashMap;
+
+use bottle_core::{
+    feed::FeedStatsView,
+    library::{ArtistStatsView, TagStatsView},
+};
+use bottle_library::Statistics;
+
+use crate::{background_job::refresh_statistics, error::Result, state::AppState};
+
+const DEFAULT_STATS_COUNT: i64 = 50;
+const DEFAULT_TAG_MONTHS: i64 = 12;
+const DEFAULT_FEED_DAYS: i64 = 30;
+
+/// Statistics are pre-aggregated periodically, so they may lag behind the library.
+pub fn statistics_router() -> Router<AppState> {
+    Router::new()
+        .route("/statistics/artists", get(get_artist_stats))
+        .route("/statistics/tags", get(get_tag_stats))
+        .route("/statistics/feeds", get(get_feed_stats))
+        .route("/statistics/refresh", get(handle_refresh_statistics))
+}
+
+/// Top artists by archived works.
+async fn get_artist_stats(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<ArtistStatsView>>> {
+    let count = get_param(&params, "count", DEFAULT_STATS_COUNT)?;
+
+    let db = &mut app_state.pool.get()?;
+    let artists = Statistics::top_artists(db, params.get("community").map(|s| s.as_str()), count)?;
+
+    Ok(Json(artists))
+}
+
+/// Top tags by archived works in the last `months` months, with their frequency each month.
+async fn get_tag_stats(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<TagStatsView>>> {
+    let count = get_param(&params, "count", DEFAULT_STATS_COUNT)?;
+    let months = get_param(&params, "months", DEFAULT_TAG_MONTHS)?.max(1);
+    // First day of the earliest month
+    let today = Utc::now().date_naive();
+    let month_index = today.year() as i64 * 12 + today.month0() as i64 - (months - 1);
+    let since = chrono::NaiveDate::from_ymd_opt((month_index / 12) as i32, (month_index % 12) as u32 + 1, 1)
+        .ok_or(bottle_core::Error::InvalidEndpoint(format!("Months {}", months)))?;
+
+    let db = &mut app_state.pool.get()?;
+    let tags = Statistics::top_tags(db, params.get("community").map(|s| s.as_str()), since, count)?;
+
+    Ok(Json(tags))
+}
+
+/// Posts saved to each feed per day in the last `days` days.
+async fn get_feed_stats(
+    State(app_state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<Vec<FeedStatsView>>> {
+    let days = get_param(&params, "days", DEFAULT_FEED_DAYS)?.max(1);
+    let since = Utc::now().date_naive() - Duration::days(days - 1);
+
+    let db = &mut app_state.pool.get()?;
+    let feeds = Statistics::feed_trends(db, params.get("community").map(|s| s.as_str()), since)?;
+
+    Ok(Json(feeds))
+}
+
+async fn handle_refresh_statistics(State(app_state): State<AppState>) -> Result<()> {
+    refresh_statistics(app_state.pool.clone()).await
+}
+
+fn get_param(params: &HashMap<String, String>, key: &str, default: i64) -> Result<i64> {
+    match params.get(key) {
+        Some(value) => Ok(value.parse::<i64>()?),
+        None => Ok(default),
+    }
+}
@@ -7,22 +7,31 @@ use axum::{
 
 use std::collections::HashMap;
 
-use bottle_core::feed::{Feed, GeneralResponse, Post};
-use bottle_panda::{PandaFeed, PandaPost};
-use bottle_pixiv::{PixivFeed, PixivPost};
-use bottle_twitter::{TwitterFeed, TwitterPost};
-use bottle_yandere::{YandereFeed, YanderePost};
+use bottle_core::{
+    feed::{Feed, GeneralResponse},
+    library::WorkView,
+};
+use bottle_library::PostNote;
+use bottle_panda::PandaFeed;
+use bottle_pixiv::PixivFeed;
+use bottle_twitter::TwitterFeed;
+use bottle_yandere::YandereFeed;
 
 use crate::{
     error::Result,
     state::AppState,
-    util::{get_page_and_size, DEFAULT_RECENT_COUNT},
+    util::{
+        add_post_to_library, adding_community_entities, get_page_and_size, get_post_order, get_work_filter,
+        DEFAULT_RECENT_COUNT,
+    },
 };
 
 pub fn work_router() -> Router<AppState> {
     Router::new()
         .route("/:community/post/:id/work", post(add_work))
         .route("/work/:id", delete(delete_work))
+        .route("/work/:id/viewed", post(mark_work_viewed))
+        .route("/works/:id/similar", get(get_similar_works))
         .route("/:community/works", get(get_archived_posts))
         .route("/:community/work/users", get(get_archived_users))
         .route("/:community/work/user/:user_id", get(get_archived_user_posts))
@@ -35,35 +44,7 @@ async fn add_work(
 ) -> Result<Json<GeneralResponse>> {
     let page = params.get("page").and_then(|p| p.parse::<i32>().ok());
 
-    let db = &mut app_state.pool.get()?;
-
-    let result = match community.as_str() {
-        "twitter" => {
-            let cache_lock = app_state.twitter_cache.clone();
-            let cache = &cache_lock.read().await;
-            TwitterPost::get(db, cache, &post_id)?.map(|p| p.add_to_library(db, page))
-        }
-        "pixiv" => {
-            let cache_lock = app_state.pixiv_cache.clone();
-            let cache = &cache_lock.read().await;
-            PixivPost::get(db, cache, &post_id)?.map(|p| p.add_to_library(db, page))
-        }
-        "yandere" => {
-            let cache_lock = app_state.yandere_cache.clone();
-            let cache = &cache_lock.read().await;
-            YanderePost::get(db, cache, &post_id)?.map(|p| p.add_to_library(db, page))
-        }
-        "panda" => {
-            let cache_lock = app_state.panda_cache.clone();
-            let cache = &cache_lock.read().await;
-            PandaPost::get(db, cache, &post_id)?.map(|p| p.add_to_library(db, page))
-        }
-        _ => None,
-    }
-    .ok_or(bottle_core::Error::ObjectNotFound(format!(
-        "Post {} at Community {}",
-        post_id, community
-    )))??;
+    let result = add_post_to_library(&app_state, &community, &post_id, page).await?;
 
     Ok(Json(result))
 }
@@ -74,12 +55,43 @@ async fn delete_work(State(app_state): State<AppState>, Path(work_id): Path<i32>
     Ok(())
 }
 
+/// Record viewing the work, with the optional `page` of the image last read to resume from.
+async fn mark_work_viewed(
+    State(app_state): State<AppState>,
+    Path(work_id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<WorkView>> {
+    let page = params.get("page").map(|p| p.parse::<i32>()).transpose()?;
+
+    let conn = &mut app_state.pool.get()?;
+    let work = bottle_library::mark_work_viewed(conn, work_id, page)?;
+    Ok(Json(work))
+}
+
+/// Other works of the library ranked by the artists and tags shared with the work, the most similar first.
+async fn get_similar_works(
+    State(app_state): State<AppState>,
+    Path(work_id): Path<i32>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<GeneralResponse>> {
+    let (page, page_size) = get_page_and_size(&params);
+    let filter = get_work_filter(&params)?;
+
+    let conn = &mut app_state.pool.get()?;
+    let response = bottle_library::similar_works(conn, work_id, &filter, page, page_size)?;
+    let response = adding_community_entities(conn, response)?;
+    let response = PostNote::adding_to(conn, response)?;
+
+    Ok(Json(response))
+}
+
 async fn get_archived_users(
     State(app_state): State<AppState>,
     Path(community): Path<String>,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<GeneralResponse>> {
     let (page, page_size) = get_page_and_size(&params);
+    let filter = get_work_filter(&params)?;
     let recent_count = params
         .get("recent_count")
         .and_then(|p| p.parse::<i64>().ok())
@@ -87,10 +99,10 @@ async fn get_archived_users(
 
     let db = &mut app_state.pool.get()?;
     let result = match community.as_str() {
-        "twitter" => TwitterFeed::archived_posts_grouped_by_user(db, page, page_size, recent_count),
-        "pixiv" => PixivFeed::archived_posts_grouped_by_user(db, page, page_size, recent_count),
-        "yandere" => YandereFeed::archived_posts_grouped_by_user(db, page, page_size, recent_count),
-        "panda" => PandaFeed::archived_posts_grouped_by_user(db, page, page_size, recent_count),
+        "twitter" => TwitterFeed::archived_posts_grouped_by_user(db, &filter, page, page_size, recent_count),
+        "pixiv" => PixivFeed::archived_posts_grouped_by_user(db, &filter, page, page_size, recent_count),
+        "yandere" => YandereFeed::archived_posts_grouped_by_user(db, &filter, page, page_size, recent_count),
+        "panda" => PandaFeed::archived_posts_grouped_by_user(db, &filter, page, page_size, recent_count),
         _ => Err(bottle_core::Error::InvalidEndpoint(format!("Community {}", community))),
     }?;
 
@@ -103,15 +115,17 @@ async fn get_archived_user_posts(
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<GeneralResponse>> {
     let (page, page_size) = get_page_and_size(&params);
+    let filter = get_work_filter(&params)?;
 
     let db = &mut app_state.pool.get()?;
     let result = match community.as_str() {
-        "twitter" => TwitterFeed::archived_posts_by_user(db, user_id, page, page_size),
-        "pixiv" => PixivFeed::archived_posts_by_user(db, user_id, page, page_size),
-        "yandere" => YandereFeed::archived_posts_by_user(db, user_id, page, page_size),
-        "panda" => PandaFeed::archived_posts_by_user(db, user_id, page, page_size),
+        "twitter" => TwitterFeed::archived_posts_by_user(db, user_id, &filter, page, page_size),
+        "pixiv" => PixivFeed::archived_posts_by_user(db, user_id, &filter, page, page_size),
+        "yandere" => YandereFeed::archived_posts_by_user(db, user_id, &filter, page, page_size),
+        "panda" => PandaFeed::archived_posts_by_user(db, user_id, &filter, page, page_size),
         _ => Err(bottle_core::Error::InvalidEndpoint(format!("Community {}", community))),
     }?;
+    let result = PostNote::adding_to(db, result)?;
 
     Ok(Json(result))
 }
@@ -122,15 +136,18 @@ async fn get_archived_posts(
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<GeneralResponse>> {
     let (page, page_size) = get_page_and_size(&params);
+    let filter = get_work_filter(&params)?;
+    let order = get_post_order(&params)?;
 
     let db = &mut app_state.pool.get()?;
     let result = match community.as_str() {
-        "twitter" => TwitterFeed::archived_posts(db, page, page_size),
-        "pixiv" => PixivFeed::archived_posts(db, page, page_size),
-        "yandere" => YandereFeed::archived_posts(db, page, page_size),
-        "panda" => PandaFeed::archived_posts(db, page, page_size),
+        "twitter" => TwitterFeed::archived_posts(db, &filter, order, page, page_size),
+        "pixiv" => PixivFeed::archived_posts(db, &filter, order, page, page_size),
+        "yandere" => YandereFeed::archived_posts(db, &filter, order, page, page_size),
+        "panda" => PandaFeed::archived_posts(db, &filter, order, page, page_size),
         _ => Err(bottle_core::Error::InvalidEndpoint(format!("Community {}", community))),
     }?;
+    let result = PostNote::adding_to(db, result)?;
 
     Ok(Json(result))
 }
@@ -10,12 +10,14 @@ use bottle_pixiv::PixivCache;
 use bottle_twitter::TwitterCache;
 use bottle_yandere::YandereCache;
 
-use crate::background_job::*;
+use crate::{background_job::*, config::Config};
 
 pub type DatabasePool = Pool<ConnectionManager<SqliteConnection>>;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
+    /// Read-only config loaded at startup
+    pub config: Arc<Config>,
     pub pool: DatabasePool,
 
     /// Cache for community entities fetched from APIs
@@ -32,6 +34,9 @@ pub struct AppState {
     /// Feed update job state: feed -> state receiver
     pub feed_update_state_map: FeedUpdateJobStateReceiverMap,
 
+    /// Worker pool for processing downloaded images, shared by download jobs
+    pub thumbnail_pool: ThumbnailPool,
+
     /// Image download job queue
     pub image_download_queue: ImageDownloadJobQueue,
     /// Image download job state
@@ -44,4 +49,27 @@ pub struct AppState {
     /// Panda download job state: gallery -> state receiver
     pub panda_download_state_map: PandaDownloadJobStateReceiverMap,
     pub panda_gallery_title_map: Arc<RwLock<HashMap<PandaGalleryID, String>>>,
+
+    /// Import job state: job ID -> state
+    pub import_job_state_map: ImportJobStateMap,
+    /// Album export job state: job ID -> state
+    pub album_export_job_state_map: AlbumExportJobStateMap,
+}
+
+impl AppState {
+    pub fn caches(&self) -> CommunityCaches {
+        CommunityCaches {
+            twitter: self.twitter_cache.clone(),
+            pixiv: self.pixiv_cache.clone(),
+            panda: self.panda_cache.clone(),
+        }
+    }
+}
+
+/// Caches of the communities with clients of accounts, shared with background jobs started before the app state.
+#[derive(Debug, Clone)]
+pub struct CommunityCaches {
+    pub twitter: Arc<RwLock<TwitterCache>>,
+    pub pixiv: Arc<RwLock<PixivCache>>,
+    pub panda: Arc<RwLock<PandaCache>>,
 }
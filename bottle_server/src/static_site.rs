@@ -0,0 +1,235 @@
+use std::{collections::HashSet, fmt::Write, fs, path::Path};
+
+use serde::Serialize;
+
+use bottle_core::{
+    library::{AlbumView, WorkFilter},
+    Database, Error as BottleError, Result as BottleResult,
+};
+use bottle_library::Album;
+
+use crate::{
+    export::{export_entries, ExportEntry},
+    payload::ExportWorksRequest,
+};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; background: #fafafa; color: #222; }
+a { color: inherit; }
+.grid { display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 1em; }
+.grid img { width: 100%; aspect-ratio: 1; object-fit: cover; border-radius: 4px; }
+.page img { display: block; max-width: 100%; margin: 1em auto; }
+.meta { color: #666; }
+";
+
+/// Result of rendering albums into a static site.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StaticSiteResult {
+    pub album_count: usize,
+    pub work_count: usize,
+    /// Images and thumbnails copied into the site.
+    pub file_count: usize,
+}
+
+/// Render the albums into a self-contained static HTML gallery under the output directory:
+/// an index of albums, a page of thumbnails for each album, and a page of images and metadata for each work.
+/// The images and thumbnails are copied into `images` so that the site doesn't depend on the server.
+pub fn render_static_site(
+    db: Database,
+    image_dir: &Path,
+    album_ids: &[i32],
+    output_dir: &Path,
+) -> BottleResult<StaticSiteResult> {
+    let albums = Album::all(db)?
+        .into_iter()
+        .filter(|album| album_ids.contains(&album.id))
+        .collect::<Vec<_>>();
+    if albums.is_empty() {
+        return Err(BottleError::ObjectNotFound(format!("Albums {:?}", album_ids)));
+    }
+
+    let mut site = Site {
+        image_dir,
+        output_dir,
+        copied: HashSet::new(),
+        result: StaticSiteResult::default(),
+    };
+    fs::create_dir_all(output_dir)?;
+
+    // 1. Index of albums
+    let mut body = String::from("<h1>Albums</h1>\n<div class=\"grid\">\n");
+    for album in albums.iter() {
+        let cover = album
+            .cover_path
+            .as_deref()
+            .map(|path| site.copy(path, ""))
+            .transpose()?
+            .flatten();
+        write_card(
+            &mut body,
+            &format!("album-{}/index.html", album.id),
+            cover.as_deref(),
+            &album.name,
+        );
+    }
+    body.push_str("</div>\n");
+    write_page(&output_dir.join("index.html"), "Albums", &body)?;
+
+    // 2. Pages of each album and its works
+    for album in albums.iter() {
+        site.render_album(db, album)?;
+        site.result.album_count += 1;
+    }
+
+    tracing::info!(
+        "Rendered static site of {} albums, {} works and {} files to {}",
+        site.result.album_count,
+        site.result.work_count,
+        site.result.file_count,
+        output_dir.display()
+    );
+    Ok(site.result)
+}
+
+struct Site<'a> {
+    image_dir: &'a Path,
+    output_dir: &'a Path,
+    /// Files already copied into the site.
+    copied: HashSet<String>,
+    result: StaticSiteResult,
+}
+
+impl Site<'_> {
+    fn render_album(&mut self, db: Database, album: &AlbumView) -> BottleResult<()> {
+        let album_dir = self.output_dir.join(format!("album-{}", album.id));
+        fs::create_dir_all(&album_dir)?;
+
+        let works = Album::works(db, album.id, &WorkFilter::default(), 0, i64::MAX)?
+            .works
+            .unwrap_or_default();
+        let request = ExportWorksRequest {
+            work_ids: vec![],
+            album_id: Some(album.id),
+        };
+        let entries = export_entries(db, &request)?;
+
+        let mut body = format!(
+            "<p><a href=\"../index.html\">Albums</a></p>\n<h1>{}</h1>\n",
+            escape(&album.name)
+        );
+        body.push_str("<div class=\"grid\">\n");
+        for work in works.iter() {
+            let work_entries = entries
+                .iter()
+                .filter(|entry| entry.work.id == work.id)
+                .collect::<Vec<_>>();
+            let thumbnail = work.thumbnail_path.as_deref().or(work_entries
+                .iter()
+                .find_map(|entry| entry.image.thumbnail_path.as_deref()));
+            let thumbnail = thumbnail.map(|path| self.copy(path, "../")).transpose()?.flatten();
+            let title = work.name.clone().unwrap_or_else(|| format!("Work {}", work.id));
+            write_card(
+                &mut body,
+                &format!("work-{}.html", work.id),
+                thumbnail.as_deref(),
+                &title,
+            );
+
+            self.render_work(&album_dir, &title, &work_entries)?;
+            self.result.work_count += 1;
+        }
+        body.push_str("</div>\n");
+        write_page(&album_dir.join("index.html"), &album.name, &body)
+    }
+
+    fn render_work(&mut self, album_dir: &Path, title: &str, entries: &[&ExportEntry]) -> BottleResult<()> {
+        let Some(first) = entries.first() else {
+            return Ok(());
+        };
+        let mut body = format!("<p><a href=\"index.html\">Back</a></p>\n<h1>{}</h1>\n", escape(title));
+
+        // Metadata of the work and its post
+        body.push_str("<div class=\"meta\">\n");
+        if let Some(creator) = &first.creator {
+            let _ = writeln!(body, "<p>By {}</p>", escape(creator));
+        }
+        if let Some(caption) = &first.work.caption {
+            let _ = writeln!(body, "<p>{}</p>", escape(caption));
+        }
+        if !first.tags.is_empty() {
+            let tags = first.tags.iter().map(|tag| escape(tag)).collect::<Vec<_>>().join(", ");
+            let _ = writeln!(body, "<p>Tags: {}</p>", tags);
+        }
+        for url in first.urls.iter().filter(|url| is_web_url(url)).take(1) {
+            let _ = writeln!(body, "<p>Source: <a href=\"{0}\">{0}</a></p>", escape(url));
+        }
+        let _ = writeln!(body, "<p>Added {}</p>", first.work.added_date.format("%Y-%m-%d"));
+        body.push_str("</div>\n<div class=\"page\">\n");
+
+        // Images in order
+        let mut entries = entries.to_vec();
+        entries.sort_by_key(|entry| entry.image.page_index);
+        for entry in entries {
+            let source = entry.image.path.as_deref().or(entry.image.thumbnail_path.as_deref());
+            if let Some(src) = source.map(|path| self.copy(path, "../")).transpose()?.flatten() {
+                let _ = writeln!(body, "<img src=\"{}\" loading=\"lazy\">", escape(&src));
+            }
+        }
+        body.push_str("</div>\n");
+        write_page(&album_dir.join(format!("work-{}.html", first.work.id)), title, &body)
+    }
+
+    /// Copy the file relative to the image directory into the site, if not copied yet and the file exists.
+    /// Returns the link to the file from a page with the prefix to the site root.
+    fn copy(&mut self, relpath: &str, prefix: &str) -> BottleResult<Option<String>> {
+        let source = self.image_dir.join(relpath);
+        if !source.is_file() {
+            return Ok(None);
+        }
+        if self.copied.insert(relpath.to_string()) {
+            let dest = self.output_dir.join("images").join(relpath);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&source, &dest)?;
+            self.result.file_count += 1;
+        }
+        let link = relpath
+            .split('/')
+            .map(urlencoding::encode)
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok(Some(format!("{}images/{}", prefix, link)))
+    }
+}
+
+fn write_card(body: &mut String, href: &str, thumbnail: Option<&str>, title: &str) {
+    let _ = write!(body, "<a href=\"{}\"><figure>", escape(href));
+    if let Some(thumbnail) = thumbnail {
+        let _ = write!(body, "<img src=\"{}\" loading=\"lazy\">", escape(thumbnail));
+    }
+    let _ = writeln!(body, "<figcaption>{}</figcaption></figure></a>", escape(title));
+}
+
+fn write_page(path: &Path, title: &str, body: &str) -> BottleResult<()> {
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    );
+    fs::write(path, html)?;
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether the URL is on the web, so that no `javascript:` or other URLs are linked.
+fn is_web_url(url: &str) -> bool {
+    reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
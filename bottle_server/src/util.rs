@@ -1,8 +1,9 @@
 use std::{collections::HashMap, future::Future, result::Result, time::Duration};
 
+use chrono::{DateTime, Utc};
 use diesel::{connection::SimpleConnection, SqliteConnection};
 
-use bottle_core::{feed::*, Database, Error as BottleError, Result as BottleResult};
+use bottle_core::{feed::*, library::WorkFilter, Database, Error as BottleError, Result as BottleResult};
 use bottle_panda::*;
 use bottle_pixiv::*;
 use bottle_twitter::*;
@@ -11,10 +12,17 @@ use bottle_yandere::*;
 use crate::{
     error::ServerError,
     payload::{FeedParams, NewFeedRequest},
+    state::{AppState, CommunityCaches},
 };
 
+pub const COMMUNITIES: [&str; 4] = ["twitter", "pixiv", "yandere", "panda"];
 pub const DEFAULT_PAGE_SIZE: i64 = 30;
 pub const DEFAULT_RECENT_COUNT: i64 = 10;
+pub const DEFAULT_HASH_DISTANCE: u32 = 8;
+pub const DEFAULT_RECENT_DAYS: i64 = 7;
+pub const MAX_RECENT_DAYS: i64 = 90;
+pub const RECENT_COVER_COUNT: usize = 4;
+pub const MAX_UPLOAD_SIZE: usize = 64 * 1024 * 1024;
 pub const DEFAULT_TIMEOUT_MS: u64 = 30000;
 pub const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
 pub const DEFAULT_RETRY_COUNT: usize = 5;
@@ -28,6 +36,172 @@ pub fn get_page_and_size(params: &HashMap<String, String>) -> (i64, i64) {
     (page, page_size)
 }
 
+/// Parse the work filter from query parameters.
+/// `content_rating` is a comma-separated list of `general`, `sensitive`, `explicit` and `unrated`.
+/// `user_tag` is a comma-separated list of user tag IDs, all of which the works must have.
+/// `orientation` is one of `portrait`, `landscape` and `square`, and `min_width`, `min_height`, `min_aspect` and
+/// `max_aspect` (width / height) bound the image size. All of them must hold on the same image of the work.
+/// `color` is a color name like `red`, `blue` or `monochrome`, or a hex color, matching the hue of any dominant color.
+pub fn get_work_filter(params: &HashMap<String, String>) -> BottleResult<WorkFilter> {
+    let content_ratings = params
+        .get("content_rating")
+        .map(|value| {
+            value
+                .split(',')
+                .map(|s| match s.trim() {
+                    "unrated" => Ok(None),
+                    s => s
+                        .parse()
+                        .map(Some)
+                        .map_err(|_| BottleError::InvalidEndpoint(format!("Content rating {}", s))),
+                })
+                .collect::<BottleResult<Vec<_>>>()
+        })
+        .transpose()?;
+    let get_bool = |key: &str| {
+        params
+            .get(key)
+            .map(|value| {
+                value
+                    .parse::<bool>()
+                    .map_err(|_| BottleError::InvalidEndpoint(format!("{} {}", key, value)))
+            })
+            .transpose()
+    };
+    let viewed = get_bool("viewed")?;
+    let in_progress = get_bool("in_progress")?.unwrap_or_default();
+    let user_tag_ids = params
+        .get("user_tag")
+        .map(|value| {
+            value
+                .split(',')
+                .map(|s| {
+                    s.trim()
+                        .parse::<i32>()
+                        .map_err(|_| BottleError::InvalidEndpoint(format!("User tag {}", s)))
+                })
+                .collect::<BottleResult<Vec<_>>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    let orientation = params
+        .get("orientation")
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| BottleError::InvalidEndpoint(format!("Orientation {}", value)))
+        })
+        .transpose()?;
+    let get_min_size = |key: &str| {
+        params
+            .get(key)
+            .map(|value| match value.parse::<i32>() {
+                Ok(size) if size > 0 => Ok(size),
+                _ => Err(BottleError::InvalidEndpoint(format!("{} {}", key, value))),
+            })
+            .transpose()
+    };
+    let min_width = get_min_size("min_width")?;
+    let min_height = get_min_size("min_height")?;
+    let get_aspect = |key: &str| {
+        params
+            .get(key)
+            .map(|value| match value.parse::<f64>() {
+                Ok(aspect) if aspect.is_finite() && aspect > 0.0 => Ok(aspect),
+                _ => Err(BottleError::InvalidEndpoint(format!("{} {}", key, value))),
+            })
+            .transpose()
+    };
+    let min_aspect = get_aspect("min_aspect")?;
+    let max_aspect = get_aspect("max_aspect")?;
+    let color = params
+        .get("color")
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| BottleError::InvalidEndpoint(format!("Color {}", value)))
+        })
+        .transpose()?;
+    Ok(WorkFilter {
+        content_ratings,
+        viewed,
+        in_progress,
+        user_tag_ids,
+        orientation,
+        min_width,
+        min_height,
+        min_aspect,
+        max_aspect,
+        color,
+    })
+}
+
+/// `archived`, `dismissed` and `expunged` are booleans.
+/// Dismissed posts and expunged posts are hidden unless `dismissed` or `expunged` is given.
+pub fn get_post_filter(params: &HashMap<String, String>) -> BottleResult<PostFilter> {
+    let get_bool = |key: &str| {
+        params
+            .get(key)
+            .map(|value| {
+                value
+                    .parse::<bool>()
+                    .map_err(|_| BottleError::InvalidEndpoint(format!("{} {}", key, value)))
+            })
+            .transpose()
+    };
+    let archived = get_bool("archived")?;
+    let dismissed = get_bool("dismissed")?.or(Some(false));
+    let expunged = get_bool("expunged")?.or(Some(false));
+    Ok(PostFilter {
+        archived,
+        dismissed,
+        expunged,
+    })
+}
+
+/// `order` is one of `added` (default), `created`, `rating` and `media_count`.
+pub fn get_post_order(params: &HashMap<String, String>) -> BottleResult<PostOrder> {
+    params
+        .get("order")
+        .map(|value| {
+            value
+                .parse::<PostOrder>()
+                .map_err(|_| BottleError::InvalidEndpoint(format!("Order {}", value)))
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Parse comma-separated page numbers and ranges starting from 1, e.g. `1-20,25`, into media indices.
+pub fn parse_page_ranges(value: &str) -> BottleResult<Vec<i32>> {
+    let invalid = || BottleError::InvalidEndpoint(format!("Pages {}", value));
+    let mut indices = Vec::new();
+    for part in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (part, part),
+        };
+        let start = start.parse::<i32>().map_err(|_| invalid())?;
+        let end = end.parse::<i32>().map_err(|_| invalid())?;
+        if start < 1 || end < start {
+            return Err(invalid());
+        }
+        indices.extend(start - 1..end);
+    }
+    if indices.is_empty() {
+        return Err(invalid());
+    }
+    Ok(indices)
+}
+
+/// Deserialize the request body, with the path of the field failing to deserialize, e.g. `params.twitter.posts.user_id`.
+pub fn deserialize_request<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> BottleResult<T> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let field = e.path().to_string();
+        BottleError::InvalidFields(vec![FieldError::new(field, e.into_inner().to_string())])
+    })
+}
+
 pub fn timeout<T, E: Into<ServerError>>(
     f: impl Future<Output = Result<T, E>>,
 ) -> impl Future<Output = Result<T, ServerError>> {
@@ -44,7 +218,48 @@ pub fn retry<R, T: Future<Output = Result<R, ServerError>>, F: FnMut() -> T>(
 ) -> impl Future<Output = Result<R, ServerError>> {
     use tokio_retry::{strategy::FixedInterval, RetryIf};
     let strategy = FixedInterval::from_millis(DEFAULT_RETRY_DELAY_MS).take(DEFAULT_RETRY_COUNT);
-    RetryIf::spawn(strategy, f, |e: &ServerError| e.retryable())
+    RetryIf::start(strategy, f, |e: &ServerError| e.retryable())
+}
+
+/// Span of a request with its `x-request-id`, which is also the parent span of jobs sent by the request.
+pub fn make_request_span<B>(request: &axum::http::Request<B>) -> tracing::Span {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    tracing::info_span!(
+        "request",
+        id = %request_id,
+        method = %request.method(),
+        uri = %redacted_uri(request.uri()),
+    )
+}
+
+/// URI with the values of secret query parameters masked, for logging.
+fn redacted_uri(uri: &axum::http::Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if name == "token" => format!("{}=****", name),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}?{}", uri.path(), query)
+}
+
+/// Compare secrets in time independent of where they differ, so that they can't be guessed byte by byte.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut diff = a.len() ^ b.len();
+    for (i, x) in a.iter().enumerate() {
+        diff |= (x ^ b.get(i % b.len().max(1)).copied().unwrap_or_default()) as usize;
+    }
+    diff == 0
 }
 
 // MARK: Database
@@ -174,6 +389,59 @@ impl FeedWrapper {
         }
     }
 
+    pub fn params(&self) -> FeedParams {
+        match self {
+            Self::Twitter(feed) => FeedParams::Twitter(feed.params.clone()),
+            Self::Pixiv(feed) => FeedParams::Pixiv(feed.params.clone()),
+            Self::Yandere(feed) => FeedParams::Yandere(feed.params.clone()),
+            Self::Panda(feed) => FeedParams::Panda(feed.params.clone()),
+        }
+    }
+
+    pub fn info(&self) -> FeedInfo {
+        let (name, watching, first_fetch_limit, icon_url, color) = match self {
+            Self::Twitter(feed) => (
+                &feed.name,
+                feed.watching,
+                feed.first_fetch_limit,
+                &feed.icon_url,
+                &feed.color,
+            ),
+            Self::Pixiv(feed) => (
+                &feed.name,
+                feed.watching,
+                feed.first_fetch_limit,
+                &feed.icon_url,
+                &feed.color,
+            ),
+            Self::Yandere(feed) => (
+                &feed.name,
+                feed.watching,
+                feed.first_fetch_limit,
+                &feed.icon_url,
+                &feed.color,
+            ),
+            Self::Panda(feed) => (
+                &feed.name,
+                feed.watching,
+                feed.first_fetch_limit,
+                &feed.icon_url,
+                &feed.color,
+            ),
+        };
+        FeedInfo {
+            name: name.clone(),
+            watching,
+            first_fetch_limit,
+            icon_url: icon_url.clone(),
+            color: color.clone(),
+            clear_icon: false,
+            clear_color: false,
+            require_media: self.view().require_media,
+            min_resolution: self.view().min_resolution,
+        }
+    }
+
     pub fn view(&self) -> FeedView {
         match self {
             Self::Twitter(feed) => feed.view(),
@@ -211,6 +479,27 @@ impl FeedWrapper {
             "yandere" => YandereFeed::delete(db, id.feed_id),
             "panda" => PandaFeed::delete(db, id.feed_id),
             _ => Err(BottleError::InvalidEndpoint(format!("Community {}", id.community))),
+        }?;
+        bottle_library::FeedGroup::remove_feed(db, &id.community, id.feed_id)?;
+        bottle_library::Webhook::delete_of_feed(db, &id.community, id.feed_id)
+    }
+
+    /// ID of the account which the feed fetches with, if the community requires one.
+    pub fn account_id(&self, db: Database) -> BottleResult<Option<i32>> {
+        match self {
+            Self::Twitter(feed) => Ok(Some(feed.get_account(db)?.view().account_id)),
+            Self::Pixiv(feed) => Ok(Some(feed.get_account(db)?.view().account_id)),
+            Self::Yandere(_) => Ok(None),
+            Self::Panda(feed) => Ok(Some(feed.get_account(db)?.view().account_id)),
+        }
+    }
+
+    pub fn new_post_count(&self, db: Database, since: DateTime<Utc>) -> BottleResult<i64> {
+        match self {
+            Self::Twitter(feed) => feed.new_post_count(db, since),
+            Self::Pixiv(feed) => feed.new_post_count(db, since),
+            Self::Yandere(feed) => feed.new_post_count(db, since),
+            Self::Panda(feed) => feed.new_post_count(db, since),
         }
     }
 
@@ -223,18 +512,29 @@ impl FeedWrapper {
         }
     }
 
-    pub fn get_context(&self, db: Database) -> BottleResult<FeedContextWrapper> {
+    /// Prepare the context of an update, with the client of the account from the cache.
+    pub async fn get_context<'a>(
+        &self,
+        db: Database<'a>,
+        caches: &CommunityCaches,
+    ) -> BottleResult<FeedContextWrapper> {
         match self {
             Self::Twitter(feed) => {
                 let account = feed.get_account(db)?;
                 let auth = account.auth(db)?;
-                let context = feed.get_fetch_context(db)?;
+                let mut context = feed.get_fetch_context(db)?;
+                if let Some(auth) = &auth {
+                    context.set_client(caches.twitter.write().await.client(account.id, auth)?);
+                }
                 Ok(FeedContextWrapper::Twitter { auth, context })
             }
             Self::Pixiv(feed) => {
                 let account = feed.get_account(db)?;
                 let auth = account.auth(db)?;
-                let context = feed.get_fetch_context(db)?;
+                let mut context = feed.get_fetch_context(db)?;
+                if let Some(auth) = &auth {
+                    context.set_client(caches.pixiv.write().await.client(account.id, auth)?);
+                }
                 Ok(FeedContextWrapper::Pixiv { auth, context })
             }
             Self::Yandere(feed) => {
@@ -244,7 +544,10 @@ impl FeedWrapper {
             Self::Panda(feed) => {
                 let account = feed.get_account(db)?;
                 let auth = account.auth(db)?;
-                let context = feed.get_fetch_context(db)?;
+                let mut context = feed.get_fetch_context(db)?;
+                if let Some(auth) = &auth {
+                    context.set_client(caches.panda.write().await.client(account.id, auth)?);
+                }
                 Ok(FeedContextWrapper::Panda { auth, context })
             }
         }
@@ -263,6 +566,20 @@ impl FeedWrapper {
         Ok(())
     }
 
+    /// Fetch a readable name for the feed from the community, with the client of the account from the cache.
+    /// Refresh the account first if necessary.
+    pub async fn fetch_name<'a>(&self, db: Database<'a>, caches: &CommunityCaches) -> BottleResult<Option<FeedName>> {
+        self.refresh_account(db).await?;
+        let context = self.get_context(db, caches).await?;
+        match (self, &context) {
+            (Self::Twitter(feed), FeedContextWrapper::Twitter { context, .. }) => feed.fetch_name(context).await,
+            (Self::Pixiv(feed), FeedContextWrapper::Pixiv { context, .. }) => feed.fetch_name(context).await,
+            (Self::Yandere(feed), FeedContextWrapper::Yandere { context, .. }) => feed.fetch_name(context).await,
+            (Self::Panda(feed), FeedContextWrapper::Panda { context, .. }) => feed.fetch_name(context).await,
+            _ => unreachable!(),
+        }
+    }
+
     pub async fn fetch_and_save<'a>(
         &self,
         db: Database<'a>,
@@ -308,6 +625,15 @@ impl FeedWrapper {
         }
     }
 
+    pub fn resumable(&self) -> bool {
+        match self {
+            Self::Twitter(feed) => feed.resumable(),
+            Self::Pixiv(feed) => feed.resumable(),
+            Self::Yandere(feed) => feed.resumable(),
+            Self::Panda(feed) => feed.resumable(),
+        }
+    }
+
     pub fn handle_before_update(&self, db: Database) -> BottleResult<()> {
         match self {
             Self::Pixiv(feed) => feed.handle_before_update(db),
@@ -330,12 +656,28 @@ impl FeedWrapper {
         }
     }
 
-    pub fn posts(&self, db: Database, page: i64, page_size: i64) -> BottleResult<GeneralResponse> {
+    pub fn posts(
+        &self,
+        db: Database,
+        filter: &PostFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> BottleResult<GeneralResponse> {
+        match self {
+            Self::Twitter(feed) => feed.posts(db, filter, order, page, page_size),
+            Self::Pixiv(feed) => feed.posts(db, filter, order, page, page_size),
+            Self::Yandere(feed) => feed.posts(db, filter, order, page, page_size),
+            Self::Panda(feed) => feed.posts(db, filter, order, page, page_size),
+        }
+    }
+
+    pub fn set_dismissed(&self, db: Database, post_ids: &[i64], dismissed: bool) -> BottleResult<usize> {
         match self {
-            Self::Twitter(feed) => feed.posts(db, page, page_size),
-            Self::Pixiv(feed) => feed.posts(db, page, page_size),
-            Self::Yandere(feed) => feed.posts(db, page, page_size),
-            Self::Panda(feed) => feed.posts(db, page, page_size),
+            Self::Twitter(feed) => feed.set_dismissed(db, post_ids, dismissed),
+            Self::Pixiv(feed) => feed.set_dismissed(db, post_ids, dismissed),
+            Self::Yandere(feed) => feed.set_dismissed(db, post_ids, dismissed),
+            Self::Panda(feed) => feed.set_dismissed(db, post_ids, dismissed),
         }
     }
 
@@ -364,6 +706,159 @@ impl FeedWrapper {
     }
 }
 
+/// ID of the first account of the community, for communities that require one.
+pub fn default_account_id(db: Database, community: &str) -> BottleResult<Option<i32>> {
+    let account = match community {
+        "twitter" => TwitterAccount::default(db).map(|account| account.view()),
+        "pixiv" => PixivAccount::default(db).map(|account| account.view()),
+        "panda" => PandaAccount::default(db).map(|account| account.view()),
+        "yandere" => return Ok(None),
+        _ => return Err(BottleError::InvalidEndpoint(format!("Community {}", community))),
+    };
+    match account {
+        Ok(account) => Ok(Some(account.account_id)),
+        Err(BottleError::DatabaseError(diesel::result::Error::NotFound)) => {
+            Err(BottleError::NotLoggedIn(format!("No {} account", community)))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Add the saved post to the library, or only the page of it if given.
+pub async fn add_post_to_library(
+    app_state: &AppState,
+    community: &str,
+    post_id: &str,
+    page: Option<i32>,
+) -> BottleResult<GeneralResponse> {
+    let db = &mut app_state.pool.get().map_err(anyhow::Error::from)?;
+    match community {
+        "twitter" => {
+            let cache = &app_state.twitter_cache.read().await;
+            TwitterPost::get(db, cache, post_id)?.map(|p| p.add_to_library(db, page))
+        }
+        "pixiv" => {
+            let cache = &app_state.pixiv_cache.read().await;
+            PixivPost::get(db, cache, post_id)?.map(|p| p.add_to_library(db, page))
+        }
+        "yandere" => {
+            let cache = &app_state.yandere_cache.read().await;
+            YanderePost::get(db, cache, post_id)?.map(|p| p.add_to_library(db, page))
+        }
+        "panda" => {
+            let cache = &app_state.panda_cache.read().await;
+            PandaPost::get(db, cache, post_id)?.map(|p| p.add_to_library(db, page))
+        }
+        _ => None,
+    }
+    .ok_or(BottleError::ObjectNotFound(format!(
+        "Post {} at Community {}",
+        post_id, community
+    )))?
+}
+
+/// Parameters of the feed of posts by the artist, i.e. the user ID, or the artist tag for communities without users.
+pub fn artist_feed_params(community: &str, artist: &str) -> BottleResult<FeedParams> {
+    match community {
+        "twitter" => Ok(FeedParams::Twitter(TwitterFeedParams::Posts {
+            user_id: artist.parse::<i64>()?,
+        })),
+        "pixiv" => Ok(FeedParams::Pixiv(PixivFeedParams::Posts {
+            user_id: artist.parse::<i64>()?,
+            type_: pixiv_client::IllustType::Illust,
+        })),
+        "yandere" => Ok(FeedParams::Yandere(YandereFeedParams::Search {
+            query: artist.to_string(),
+        })),
+        "panda" => panda_search_params(&format!("artist:{}", artist)),
+        _ => Err(BottleError::InvalidEndpoint(format!("Community {}", community))),
+    }
+}
+
+/// Parameters of the search feed of the tag.
+pub fn tag_feed_params(community: &str, tag: &str) -> BottleResult<FeedParams> {
+    match community {
+        "twitter" => Ok(FeedParams::Twitter(TwitterFeedParams::Search {
+            option: twitter_client::SearchOption {
+                keyword: Some(format!("#{}", tag)),
+                ..Default::default()
+            },
+            min_likes: None,
+        })),
+        "pixiv" => Ok(FeedParams::Pixiv(PixivFeedParams::Search {
+            query: tag.to_string(),
+            sort: Default::default(),
+            search_target: pixiv_client::SearchTarget::Exact,
+            duration: None,
+            min_bookmarks: None,
+        })),
+        "yandere" => Ok(FeedParams::Yandere(YandereFeedParams::Search {
+            query: tag.to_string(),
+        })),
+        "panda" => panda_search_params(tag),
+        _ => Err(BottleError::InvalidEndpoint(format!("Community {}", community))),
+    }
+}
+
+/// Search of the exact panda tag in `namespace:name`.
+fn panda_search_params(tag: &str) -> BottleResult<FeedParams> {
+    let keyword = match tag.split_once(':') {
+        Some((namespace, name)) => format!("{}:\"{}$\"", namespace, name),
+        None => format!("\"{}$\"", tag),
+    };
+    let option = panda_client::SearchOption {
+        keyword: Some(keyword),
+        ..Default::default()
+    };
+    Ok(FeedParams::Panda(PandaFeedParams::Search {
+        option,
+        languages: vec![],
+    }))
+}
+
+/// Get post views of the posts in the database.
+pub fn get_posts(db: Database, community: &str, post_ids: &[String]) -> BottleResult<Vec<PostView>> {
+    let response = get_entities(db, community, post_ids)?;
+    Ok(response.posts.unwrap_or_default())
+}
+
+/// IDs of the saved posts of the community greater than `after_id` in ascending order.
+pub fn get_post_ids(db: Database, community: &str, after_id: i64, count: i64) -> BottleResult<Vec<i64>> {
+    match community {
+        "twitter" => bottle_twitter::get_post_ids(db, after_id, count),
+        "pixiv" => bottle_pixiv::get_post_ids(db, after_id, count),
+        "yandere" => bottle_yandere::get_post_ids(db, after_id, count),
+        "panda" => bottle_panda::get_post_ids(db, after_id, count),
+        _ => Err(BottleError::InvalidEndpoint(format!("Community {}", community))),
+    }
+}
+
+/// Get the posts in the database along with their media and users.
+pub fn get_entities(db: Database, community: &str, post_ids: &[String]) -> BottleResult<GeneralResponse> {
+    let post_ids = post_ids.iter().filter_map(|id| id.parse::<i64>().ok());
+    match community {
+        "twitter" => bottle_twitter::get_entities(db, post_ids),
+        "pixiv" => bottle_pixiv::get_entities(db, post_ids),
+        "yandere" => bottle_yandere::get_entities(db, post_ids),
+        "panda" => bottle_panda::get_entities(db, post_ids),
+        _ => Err(BottleError::InvalidEndpoint(format!("Community {}", community))),
+    }
+}
+
+/// Link to the post on the community website.
+pub fn post_url(post: &PostView) -> Option<String> {
+    match post.community.as_str() {
+        "twitter" => Some(format!("https://twitter.com/i/status/{}", post.post_id)),
+        "pixiv" => Some(format!("https://www.pixiv.net/artworks/{}", post.post_id)),
+        "yandere" => Some(format!("https://yande.re/post/show/{}", post.post_id)),
+        "panda" => {
+            let token = post.extra.as_ref()?.get("panda")?.get("token")?.as_str()?;
+            Some(format!("https://exhentai.org/g/{}/{}/", post.post_id, token))
+        }
+        _ => None,
+    }
+}
+
 pub fn adding_community_entities(db: Database, response: GeneralResponse) -> BottleResult<GeneralResponse> {
     let mut users = Vec::new();
     let mut posts = Vec::new();
@@ -1,4 +1,5 @@
-use bottle_core::{feed::*, Database, Result};
+use bottle_core::{feed::*, Database, Error, Result};
+use twitter_client::{SearchOption, Tweet};
 
 use crate::cache::TwitterCache;
 use crate::community::TwitterAccount;
@@ -14,6 +15,13 @@ fn from_request(db: Database, request: &EndpointRequest<TwitterFeedParams>) -> R
         id: -1, // Temporary feed
         name: None,
         first_fetch_limit: None,
+        icon_url: None,
+        color: None,
+        cover_path: None,
+        last_error: None,
+        failure_count: 0,
+        min_resolution: None,
+        require_media: false,
         watching: false,
         account_id: account.id,
         params: request.params.clone(),
@@ -27,34 +35,97 @@ pub async fn fetch_posts<'a>(
     cache: &'a mut TwitterCache,
     request: &EndpointRequest<TwitterFeedParams>,
 ) -> Result<EndpointResponse> {
-    use itertools::Itertools;
-
     // 1. Fetch results
     let feed = from_request(db, request)?;
-    let auth = feed.get_account(db)?.auth(db)?;
+    let account = feed.get_account(db)?;
+    let auth = account.auth(db)?;
     let mut ctx = TwitterFetchContext {
         cursor: request.offset.clone(),
         direction: Direction::Backward,
+        backfill: false,
+        until: None,
+        oldest: None,
+        client: auth.as_ref().map(|auth| cache.client(account.id, auth)).transpose()?,
     };
     let result = feed.fetch(&mut ctx, auth.as_ref()).await?;
+    let reached_end = result.tweets.is_empty();
+
+    tweets_response(db, cache, result.tweets, ctx.cursor, reached_end)
+}
+
+/// Fetch posts from the normalized endpoint, using the feed params or API of twitter.
+pub async fn fetch_endpoint<'a>(
+    db: Database<'a>,
+    cache: &'a mut TwitterCache,
+    request: &EndpointRequest<Endpoint>,
+) -> Result<EndpointResponse> {
+    if let Endpoint::PostDetail { post_id } = &request.params {
+        let tweet_id = Endpoint::parse_id(post_id, "Tweet ID")?;
+        let account = TwitterAccount::default(db)?;
+        let auth = account
+            .auth(db)?
+            .ok_or(Error::NotLoggedIn("Invalid account".to_string()))?;
+        let client = cache.client(account.id, &auth)?;
+        let tweet = client.tweet_by_id(tweet_id).await.map_err(anyhow::Error::from)?;
+        return tweets_response(db, cache, vec![tweet], None, true);
+    }
+    let request = EndpointRequest {
+        params: endpoint_params(db, &request.params)?,
+        offset: request.offset.clone(),
+    };
+    fetch_posts(db, cache, &request).await
+}
+
+/// Feed params browsed by the normalized endpoint, so that the browsing can be promoted to a feed.
+/// The post detail is not a feed.
+pub fn endpoint_params(db: Database, endpoint: &Endpoint) -> Result<TwitterFeedParams> {
+    let params = match endpoint {
+        Endpoint::UserPosts { user_id } => TwitterFeedParams::Posts {
+            user_id: Endpoint::parse_id(user_id, "User ID")?,
+        },
+        Endpoint::Likes { user_id: Some(user_id) } => TwitterFeedParams::Likes {
+            user_id: Endpoint::parse_id(user_id, "User ID")?,
+        },
+        Endpoint::Likes { user_id: None } => TwitterFeedParams::Likes {
+            user_id: TwitterAccount::default(db)?
+                .user_id
+                .ok_or(Error::ObjectNotComplete("User ID of the twitter account".to_string()))?,
+        },
+        Endpoint::Search { query } => TwitterFeedParams::Search {
+            option: query.parse::<SearchOption>().map_err(anyhow::Error::from)?,
+            min_likes: None,
+        },
+        Endpoint::PostDetail { .. } => return Err(Error::InvalidEndpoint("Feed of a single post".to_string())),
+    };
+    Ok(params)
+}
+
+/// Store the tweets to cache, and prepare the response with their views and associated works.
+fn tweets_response(
+    db: Database,
+    cache: &mut TwitterCache,
+    tweets: Vec<Tweet>,
+    next_offset: Option<String>,
+    reached_end: bool,
+) -> Result<EndpointResponse> {
+    use itertools::Itertools;
 
-    // 2. Store tweets to cache
-    cache.tweets.extend(result.tweets.iter().map(|t| (t.id, t.clone())));
-    tracing::info!("Stored {} tweets to cache", result.tweets.len());
+    // 1. Store tweets to cache
+    cache.tweets.extend(tweets.iter().map(|t| (t.id, t.clone())));
+    tracing::info!("Stored {} tweets to cache", tweets.len());
 
-    // 3. Prepare views
-    let posts = result.tweets.iter().map(util::post_view).collect();
-    let users = result
-        .tweets
+    // 2. Prepare views
+    let posts = tweets.iter().map(util::post_view).collect();
+    let users = tweets
         .iter()
         .map(|t| &t.user)
         .unique_by(|u| u.id)
         .map(util::user_view)
         .collect();
-    let media = result.tweets.iter().flat_map(util::media_views).collect();
+    let media = tweets.iter().flat_map(util::media_views).collect();
 
-    // 4. Get associated works and images
-    let post_ids = result.tweets.iter().map(|t| t.id.to_string());
+    // 3. Get associated works and images
+    let post_ids = tweets.iter().map(|t| t.id.to_string());
     let (works, images) = bottle_library::get_works_by_post_ids(db, "twitter", post_ids, false)?;
 
     Ok(EndpointResponse {
@@ -63,8 +134,8 @@ pub async fn fetch_posts<'a>(
         media,
         works,
         images,
-        next_offset: ctx.cursor,
-        reached_end: result.tweets.is_empty(),
+        next_offset,
+        reached_end,
         total_items: None,
     })
 }
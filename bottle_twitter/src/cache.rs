@@ -1,14 +1,52 @@
-use std::collections::HashMap;
+use std::time::Duration;
 
-use twitter_client::Tweet;
+use bottle_core::{
+    cache::{BoundedCache, CacheStats, ClientCache, RequestRecorder},
+    Result,
+};
+use twitter_client::{SessionCookie, Tweet, TwitterClient};
 
 #[derive(Debug, Clone, Default)]
 pub struct TwitterCache {
-    pub(crate) tweets: HashMap<u64, Tweet>,
+    pub(crate) tweets: BoundedCache<u64, Tweet>,
+    pub(crate) clients: ClientCache<TwitterClient, SessionCookie>,
 }
 
 impl TwitterCache {
     pub fn new() -> Self {
-        Self { tweets: HashMap::new() }
+        Self::default()
+    }
+
+    /// Keep at most `capacity` entities of each kind, each for `ttl` at most.
+    pub fn with_limits(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            tweets: BoundedCache::new(capacity, ttl),
+            clients: ClientCache::new(),
+        }
+    }
+
+    pub fn stats(&self) -> Vec<CacheStats> {
+        vec![self.tweets.stats("tweets")]
+    }
+
+    /// Clear the cached entities, keeping the clients of accounts.
+    pub fn clear(&mut self) {
+        self.tweets.clear();
+    }
+
+    /// Get the client of the account for the session cookie.
+    pub fn client(&mut self, account_id: i32, cookie: &SessionCookie) -> Result<TwitterClient> {
+        self.clients.get_or_create(account_id, cookie, |cookie, hook| {
+            let client = TwitterClient::new(cookie.clone()).map_err(anyhow::Error::from)?;
+            Ok(match hook {
+                Some(hook) => client.with_response_hook(hook),
+                None => client,
+            })
+        })
+    }
+
+    /// Count the requests made by the clients of accounts.
+    pub fn set_request_recorder(&mut self, recorder: RequestRecorder) {
+        self.clients.set_recorder(recorder);
     }
 }
@@ -11,7 +11,7 @@ use bottle_core::{
 use twitter_client::{Account as AccountResult, SessionCookie, TwitterClient};
 
 use crate::{cache::TwitterCache, feed::TwitterFeed};
-use crate::{model, util};
+use crate::{model, user, util};
 
 pub struct TwitterCommunity;
 
@@ -54,8 +54,11 @@ impl Account for TwitterAccount {
     {
         Some(AccountMetadata {
             credential_scheme: Scheme::Object(HashMap::from([
-                ("ct0".to_string(), Scheme::String),
-                ("auth_token".to_string(), Scheme::String),
+                ("ct0".to_string(), Scheme::field("Cookie ct0", Scheme::String)),
+                (
+                    "auth_token".to_string(),
+                    Scheme::field("Cookie auth_token", Scheme::String),
+                ),
             ])),
             can_fetch_info: true,
             need_refresh: false,
@@ -66,6 +69,7 @@ impl Account for TwitterAccount {
         AccountView {
             account_id: self.id,
             community: "twitter".to_string(),
+            favorite_categories: None,
         }
     }
 
@@ -164,6 +168,12 @@ impl Account for TwitterAccount {
         Ok(result)
     }
 
+    fn parse_credential(value: &serde_json::Value, path: &str) -> Result<Self::Credential> {
+        let scheme = Self::metadata().unwrap().credential_scheme;
+        let [ct0, auth_token] = credential_fields(&scheme, value, path, ["ct0", "auth_token"])?;
+        Ok(SessionCookie { ct0, auth_token })
+    }
+
     async fn fetch(credential: &Self::Credential) -> Result<Self::InfoResponse> {
         let client = TwitterClient::new(credential.clone()).map_err(anyhow::Error::from)?;
         let mut accounts = client.accounts().await.map_err(anyhow::Error::from)?;
@@ -177,6 +187,28 @@ impl TwitterAccount {
         let result = twitter_account.first::<model::TwitterAccount>(db)?;
         Ok(Self::from(result))
     }
+
+    /// Get the account of the twitter user, if any.
+    pub fn find_by_user(db: Database, twitter_user_id: i64) -> Result<Option<Self>> {
+        use bottle_core::schema::twitter_account::dsl::*;
+        let result = twitter_account
+            .filter(user_id.eq(twitter_user_id))
+            .order_by(id.asc())
+            .first::<model::TwitterAccount>(db)
+            .optional()?;
+        Ok(result.map(Self::from))
+    }
+
+    /// Replace the cookies of the account, e.g. with refreshed ones from the browser.
+    pub fn set_credential(&self, db: Database, credential: &SessionCookie) -> Result<Self> {
+        use bottle_core::schema::twitter_account::dsl::*;
+        let result = diesel::update(twitter_account.filter(id.eq(self.id)))
+            .set(cookies.eq(credential.to_string()))
+            .returning(model::TwitterAccount::as_returning())
+            .get_result(db)?;
+        tracing::info!("Replaced cookies of twitter account {}", self.id);
+        Ok(Self::from(result))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -189,7 +221,7 @@ impl Post for TwitterPost {
     type Cache = TwitterCache;
 
     fn get(db: Database, cache: &Self::Cache, post_id: &str) -> Result<Option<Self>> {
-        use bottle_core::schema::{tweet, twitter_media, twitter_user};
+        use bottle_core::schema::{tweet, twitter_media, twitter_tag};
         let post_id = post_id.parse::<i64>()?;
 
         // 1. Try to get the tweet from database
@@ -201,11 +233,10 @@ impl Post for TwitterPost {
                 let new_tweet = model::NewTweet::from(tweet);
                 let new_user = model::NewTwitterUser::from(&tweet.user);
                 let media = util::media(tweet);
+                let tags = util::tags(tweet);
 
                 result = db.transaction(|conn| -> Result<Option<model::Tweet>> {
-                    diesel::insert_into(twitter_user::table)
-                        .values(&new_user)
-                        .execute(conn)?;
+                    user::save_users(conn, &[new_user])?;
                     let result = Some(
                         diesel::insert_into(tweet::table)
                             .values(&new_tweet)
@@ -215,6 +246,7 @@ impl Post for TwitterPost {
                     if !media.is_empty() {
                         diesel::insert_into(twitter_media::table).values(&media).execute(conn)?;
                     }
+                    diesel::insert_into(twitter_tag::table).values(&tags).execute(conn)?;
                     Ok(result)
                 })?;
 
@@ -260,7 +292,7 @@ impl Post for TwitterPost {
             page_index: page,
             media_count: images.len() as i32,
             images,
-            caption: Some(self.tweet.caption.clone()),
+            caption: Some(self.tweet.display_text()),
             ..Default::default()
         };
         bottle_library::add_remote_work(db, &remote_work)
@@ -289,10 +321,31 @@ pub fn get_entities(db: Database, post_ids: impl IntoIterator<Item = i64>) -> Re
         .order(twitter_media::page.asc())
         .load::<model::TwitterMedia>(db)?;
 
+    // 4. Fetch associated tags
+    let tags = util::get_tag_map(db, post_ids)?;
+
     Ok(GeneralResponse {
-        posts: Some(posts.into_iter().map(PostView::from).collect()),
+        posts: Some(
+            posts
+                .iter()
+                .map(|post| post.post_view(tags.get(&post.id).cloned().unwrap_or_default()))
+                .collect(),
+        ),
         users: Some(users.into_iter().map(UserView::from).collect()),
         media: Some(media.into_iter().map(MediaView::from).collect()),
         ..Default::default()
     })
 }
+
+/// IDs of the saved posts greater than `after_id` in ascending order, to go through all posts in batches.
+pub fn get_post_ids(db: Database, after_id: i64, count: i64) -> Result<Vec<i64>> {
+    use bottle_core::schema::tweet;
+
+    let post_ids = tweet::table
+        .filter(tweet::id.gt(after_id))
+        .order_by(tweet::id.asc())
+        .select(tweet::id)
+        .limit(count)
+        .load::<i64>(db)?;
+    Ok(post_ids)
+}
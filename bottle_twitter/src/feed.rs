@@ -1,29 +1,112 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, Utc};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
-use bottle_core::{feed::*, Error, Result};
-use twitter_client::{SessionCookie, TimelineResult, TwitterClient};
+use bottle_core::{feed::*, library::WorkFilter, mute::UserMute, Error, Result};
+use twitter_client::{SearchOption, SessionCookie, TimelineResult, TwitterClient};
 
 use crate::community::TwitterAccount;
-use crate::{group, model, util};
+use crate::{group, model, user, util};
+
+/// Columns of posts for ordering them in feeds and the library.
+const POST_COLUMNS: PostColumns = PostColumns {
+    community: "twitter",
+    table: "tweet",
+    post_id: "tweet.id",
+    created_date: "tweet.created_date",
+    media_count: "(SELECT COUNT(*) FROM twitter_media WHERE twitter_media.tweet_id = tweet.id)",
+    expunged: None,
+};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TwitterFeedParams {
-    Timeline,
+    Timeline {
+        #[serde(default)]
+        retweets: RetweetMode,
+    },
     Bookmarks,
-    Likes { user_id: i64 },
-    Posts { user_id: i64 },
-    List { list_id: i64 },
-    Search { query: String },
+    Likes {
+        user_id: i64,
+    },
+    Posts {
+        user_id: i64,
+    },
+    List {
+        list_id: i64,
+        #[serde(default)]
+        retweets: RetweetMode,
+    },
+    Search {
+        option: SearchOption,
+        /// Minimum likes of the tweets saved to the feed, counted when they are fetched.
+        #[serde(default)]
+        min_likes: Option<u32>,
+    },
+}
+
+/// How retweets in the timeline and lists are saved.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetweetMode {
+    /// Save retweets as they are.
+    #[default]
+    Keep,
+    /// Skip retweets entirely.
+    Skip,
+    /// Save the original tweets instead, once even if retweeted several times.
+    Collapse,
+}
+
+impl RetweetMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetweetMode::Keep => "keep",
+            RetweetMode::Skip => "skip",
+            RetweetMode::Collapse => "collapse",
+        }
+    }
+}
+
+impl FromStr for RetweetMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "keep" => Ok(RetweetMode::Keep),
+            "skip" => Ok(RetweetMode::Skip),
+            "collapse" => Ok(RetweetMode::Collapse),
+            _ => Err(Error::UnknownField(format!("twitter retweet mode {}", s))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TwitterFetchContext {
     pub(crate) cursor: Option<String>,
     pub(crate) direction: Direction,
+    /// Whether the search feed is backfilling older tweets, which goes on past the saved tweets.
+    #[serde(default)]
+    pub(crate) backfill: bool,
+    /// End of the date window of the search, exclusive, appended to the query as `until:`.
+    #[serde(default)]
+    pub(crate) until: Option<NaiveDate>,
+    /// Oldest tweet fetched in the current cursor chain of the search.
+    #[serde(default)]
+    pub(crate) oldest: Option<DateTime<Utc>>,
+    /// Client of the account from the cache, reused across the pages of an update.
+    #[serde(skip)]
+    pub(crate) client: Option<TwitterClient>,
+}
+
+impl TwitterFetchContext {
+    /// Use the client cached for the account. Needed before fetching.
+    pub fn set_client(&mut self, client: TwitterClient) {
+        self.client = Some(client);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +124,19 @@ pub struct TwitterFeed {
     pub account_id: i32,
     pub params: TwitterFeedParams,
     pub reached_end: bool,
+    pub icon_url: Option<String>,
+    /// Accent color in `#rrggbb`.
+    pub color: Option<String>,
+    /// Collage of the recent works in the library, relative to the image directory.
+    pub cover_path: Option<String>,
+    /// Error of the last update, if it failed.
+    pub last_error: Option<String>,
+    /// Number of consecutive failed updates.
+    pub failure_count: i32,
+    /// Whether text-only tweets are skipped when saving.
+    pub require_media: bool,
+    /// Minimum resolution of the largest media of the posts saved, on its longer side.
+    pub min_resolution: Option<i32>,
 }
 
 #[async_trait]
@@ -56,32 +152,57 @@ impl Feed for TwitterFeed {
         vec![
             FeedMetadata {
                 name: "timeline".to_string(),
-                scheme: Scheme::Null,
+                label: "Home Timeline".to_string(),
+                scheme: Scheme::Object(HashMap::from([("retweets".to_string(), util::retweet_mode_scheme())])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "bookmarks".to_string(),
+                label: "Bookmarks".to_string(),
                 scheme: Scheme::Null,
                 need_auth: true,
             },
             FeedMetadata {
                 name: "likes".to_string(),
-                scheme: Scheme::Object(HashMap::from([("user_id".to_string(), Scheme::Bigint)])),
+                label: "Likes".to_string(),
+                scheme: Scheme::Object(HashMap::from([(
+                    "user_id".to_string(),
+                    Scheme::field("User ID", Scheme::Bigint),
+                )])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "posts".to_string(),
-                scheme: Scheme::Object(HashMap::from([("user_id".to_string(), Scheme::Bigint)])),
+                label: "User Posts".to_string(),
+                scheme: Scheme::Object(HashMap::from([(
+                    "user_id".to_string(),
+                    Scheme::field("User ID", Scheme::Bigint),
+                )])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "list".to_string(),
-                scheme: Scheme::Object(HashMap::from([("list_id".to_string(), Scheme::Bigint)])),
+                label: "List".to_string(),
+                scheme: Scheme::Object(HashMap::from([
+                    ("list_id".to_string(), Scheme::field("List ID", Scheme::Bigint)),
+                    ("retweets".to_string(), util::retweet_mode_scheme()),
+                ])),
                 need_auth: true,
             },
             FeedMetadata {
                 name: "search".to_string(),
-                scheme: Scheme::Object(HashMap::from([("query".to_string(), Scheme::String)])),
+                label: "Search".to_string(),
+                scheme: Scheme::Object(HashMap::from([
+                    (
+                        "option".to_string(),
+                        Scheme::field("Search Option", util::search_option_scheme()),
+                    ),
+                    (
+                        "min_likes".to_string(),
+                        Scheme::field("Minimum Likes", Scheme::Optional(Box::new(Scheme::Int)))
+                            .with_default(serde_json::json!(null)),
+                    ),
+                ])),
                 need_auth: true,
             },
         ]
@@ -93,13 +214,20 @@ impl Feed for TwitterFeed {
             community: "twitter".to_string(),
             name: self.name.clone(),
             watching: self.watching,
+            icon_url: self.icon_url.clone(),
+            color: self.color.clone(),
+            cover_path: self.cover_path.clone(),
+            last_error: self.last_error.clone(),
+            failure_count: self.failure_count,
+            require_media: self.require_media,
+            min_resolution: self.min_resolution,
             description: match &self.params {
-                TwitterFeedParams::Timeline => "Timeline".to_string(),
+                TwitterFeedParams::Timeline { .. } => "Timeline".to_string(),
                 TwitterFeedParams::Bookmarks => "Bookmarks".to_string(),
                 TwitterFeedParams::Likes { user_id } => format!("Likes by {}", user_id),
                 TwitterFeedParams::Posts { user_id } => format!("Posts by {}", user_id),
-                TwitterFeedParams::List { list_id } => format!("List {}", list_id),
-                TwitterFeedParams::Search { query } => format!("Search {}", query),
+                TwitterFeedParams::List { list_id, .. } => format!("List {}", list_id),
+                TwitterFeedParams::Search { option, .. } => format!("Search {}", option),
             },
         }
     }
@@ -143,6 +271,12 @@ impl Feed for TwitterFeed {
             user_id: params.user_id(),
             twitter_list_id: params.twitter_list_id(),
             search_query: params.search_query(),
+            icon_url: info.icon_url.clone(),
+            color: info.color.clone(),
+            min_resolution: info.min_resolution,
+            retweets: params.retweets_column(),
+            search_min_likes: params.min_likes().map(|n| n as i32),
+            require_media: info.require_media,
         };
         let result = diesel::insert_into(twitter_watch_list::table)
             .values(&new_watch_list)
@@ -163,6 +297,10 @@ impl Feed for TwitterFeed {
             name: info.name.clone(),
             watching: info.watching,
             first_fetch_limit: info.first_fetch_limit,
+            icon_url: info.icon_change(),
+            color: info.color_change(),
+            min_resolution: info.min_resolution,
+            require_media: info.require_media,
         };
         diesel::update(twitter_watch_list::table.find(self.id))
             .set(&update)
@@ -170,14 +308,21 @@ impl Feed for TwitterFeed {
         self.name = info.name.clone();
         self.watching = info.watching;
         self.first_fetch_limit = info.first_fetch_limit;
+        if let Some(icon_url) = info.icon_change() {
+            self.icon_url = icon_url;
+        }
+        if let Some(color) = info.color_change() {
+            self.color = color;
+        }
+        self.min_resolution = info.min_resolution;
+        self.require_media = info.require_media;
         tracing::info!("Modified twitter feed {}: {:?}", self.id, info);
         Ok(self.view())
     }
 
     fn save(&self, db: Database, fetched: &Self::FetchResult, ctx: &Self::FetchContext) -> Result<SaveResult> {
         use bottle_core::schema::{
-            tweet, twitter_media, twitter_user, twitter_watch_list, twitter_watch_list_history,
-            twitter_watch_list_tweet,
+            tweet, twitter_media, twitter_tag, twitter_watch_list, twitter_watch_list_history, twitter_watch_list_tweet,
         };
 
         // (a) If response is empty, we should stop updating
@@ -198,25 +343,47 @@ impl Feed for TwitterFeed {
             });
         }
 
-        // 1. Filter out tweets already exist in database
-        let fetched_ids = fetched.tweets.iter().map(|t| t.id as i64).collect::<Vec<_>>();
+        // 1. Filter out tweets already exist in database, after collapsing retweets if the feed asks so
+        let retweets = self.params.retweets();
+        let entries = util::timeline_tweets(fetched, retweets);
+        let fetched_ids = entries.iter().map(|(t, _)| t.id as i64).collect::<Vec<_>>();
         let existing_ids = twitter_watch_list_tweet::table
             .filter(twitter_watch_list_tweet::watch_list_id.eq(self.id))
             .filter(twitter_watch_list_tweet::tweet_id.eq_any(&fetched_ids))
             .select(twitter_watch_list_tweet::tweet_id)
             .load::<i64>(db)?;
         let existing_ids = existing_ids.into_iter().map(|id| id as u64).collect::<HashSet<_>>();
-        let tweets = fetched.tweets.iter().filter(|t| !existing_ids.contains(&t.id));
+        let entries = entries
+            .into_iter()
+            .filter(|(t, _)| !existing_ids.contains(&t.id))
+            .collect::<Vec<_>>();
 
-        // (b) If all tweets already exist in database, we should stop updating
-        if tweets.clone().count() == 0 {
+        // (b) If all tweets already exist in database, we should stop updating,
+        // unless the search is backfilling, where windows overlap by a day
+        if entries.is_empty() {
             return Ok(SaveResult {
                 post_ids: vec![],
-                should_stop: true,
+                should_stop: !ctx.backfill,
                 reached_end: false,
             });
         }
 
+        // Skip tweets of muted users, tweets not wanted by the feed, and tweets with too few likes
+        let muted_user_ids = UserMute::user_ids(db, "twitter")?;
+        let min_likes = self.params.min_likes().unwrap_or_default();
+        let entries = entries
+            .into_iter()
+            .filter(|(t, _)| !muted_user_ids.contains(&t.user.id.to_string()))
+            .filter(|(t, _)| retweets != RetweetMode::Skip || t.retweeted_status.is_none())
+            .filter(|(t, _)| !self.require_media || !t.media.is_empty())
+            .filter(|(t, _)| {
+                let sizes = t.media.iter().map(|m| (m.original_info.width, m.original_info.height));
+                meets_min_resolution(self.min_resolution, sizes)
+            })
+            .filter(|(t, _)| t.favorite_count >= min_likes)
+            .collect::<Vec<_>>();
+        let tweets = entries.iter().map(|(t, _)| *t);
+
         // 2. Prepare data for insertion
         // User, Tweet, Media
         let new_users = tweets
@@ -225,18 +392,17 @@ impl Feed for TwitterFeed {
             .collect::<Vec<_>>();
         let new_tweets = tweets.clone().map(model::NewTweet::from).collect::<Vec<_>>();
         let media = tweets.clone().flat_map(util::media).collect::<Vec<_>>();
+        let tags = tweets.clone().flat_map(util::tags).collect::<Vec<_>>();
 
         // WatchListTweet
-        let watch_list_tweets = fetched
-            .tweets
+        let watch_list_tweets = entries
             .iter()
-            .zip(fetched.sort_indices.iter())
-            .filter(|(tweet, _)| !existing_ids.contains(&tweet.id))
             .map(|(tweet, sort_index)| model::TwitterWatchListTweet {
                 watch_list_id: self.id,
                 tweet_id: tweet.id as i64,
                 sort_index: Some(*sort_index as i64),
                 stale: false,
+                dismissed: false,
             })
             .collect::<Vec<_>>();
 
@@ -255,11 +421,10 @@ impl Feed for TwitterFeed {
 
         // 3. Insert data
         db.transaction(|conn| -> Result<()> {
-            diesel::insert_into(twitter_user::table)
-                .values(&new_users)
-                .execute(conn)?;
+            user::save_users(conn, &new_users)?;
             diesel::insert_into(tweet::table).values(&new_tweets).execute(conn)?;
             diesel::insert_into(twitter_media::table).values(&media).execute(conn)?;
+            diesel::insert_into(twitter_tag::table).values(&tags).execute(conn)?;
             diesel::insert_into(twitter_watch_list_tweet::table)
                 .values(&watch_list_tweets)
                 .execute(conn)?;
@@ -274,7 +439,7 @@ impl Feed for TwitterFeed {
         tracing::info!("Saved tweets for twitter feed {}: {}", self.id, history.ids);
         Ok(SaveResult {
             post_ids: tweet_ids,
-            should_stop: !existing_ids.is_empty(),
+            should_stop: !existing_ids.is_empty() && !ctx.backfill,
             reached_end: false,
         })
     }
@@ -291,15 +456,31 @@ impl Feed for TwitterFeed {
         unimplemented!()
     }
 
-    fn posts(&self, db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn posts(
+        &self,
+        db: Database,
+        filter: &PostFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{tweet, twitter_media, twitter_user, twitter_watch_list_tweet};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{
+            dsl::sql,
+            sql_types::{Bool, Integer},
+        };
 
         // 1. Fetch posts
         let (posts, total_items) = twitter_watch_list_tweet::table
             .inner_join(tweet::table)
             .filter(twitter_watch_list_tweet::watch_list_id.eq(self.id))
-            .order(twitter_watch_list_tweet::sort_index.desc())
+            .filter(sql::<Bool>(
+                &filter.sql_condition(&POST_COLUMNS, "twitter_watch_list_tweet"),
+            ))
+            .order(sql::<Integer>(
+                &order.feed_sql(&POST_COLUMNS, "twitter_watch_list_tweet.sort_index"),
+            ))
             .select(tweet::all_columns)
             .paginate(page, page_size)
             .load_and_count::<model::Tweet>(db)?;
@@ -317,26 +498,55 @@ impl Feed for TwitterFeed {
             .order(twitter_media::page.asc())
             .load::<model::TwitterMedia>(db)?;
 
-        // 4. Fetch associated works
+        // 4. Fetch associated tags
+        let tags = util::get_tag_map(db, posts.iter().map(|tweet| tweet.id))?;
+
+        // 5. Fetch associated works
         let tweet_ids = posts.iter().map(|tweet| tweet.id.to_string());
         let (works, images) = bottle_library::get_works_by_post_ids(db, "twitter", tweet_ids, false)?;
 
         Ok(GeneralResponse {
-            posts: Some(posts.into_iter().map(PostView::from).collect()),
+            posts: Some(
+                posts
+                    .iter()
+                    .map(|tweet| tweet.post_view(tags.get(&tweet.id).cloned().unwrap_or_default()))
+                    .collect(),
+            ),
             users: Some(users.into_iter().map(UserView::from).collect()),
             media: Some(media.into_iter().map(MediaView::from).collect()),
             works: Some(works),
             images: Some(images),
+            notes: None,
             total_items,
             page,
             page_size,
         })
     }
 
+    fn set_dismissed(&self, db: Database, post_ids: &[i64], dismissed: bool) -> Result<usize> {
+        use bottle_core::schema::twitter_watch_list_tweet;
+
+        let count = diesel::update(twitter_watch_list_tweet::table)
+            .filter(twitter_watch_list_tweet::watch_list_id.eq(self.id))
+            .filter(twitter_watch_list_tweet::tweet_id.eq_any(post_ids))
+            .set(twitter_watch_list_tweet::dismissed.eq(dismissed))
+            .execute(db)?;
+        Ok(count)
+    }
+
     fn get_account(&self, db: Database) -> Result<Self::Account> {
         TwitterAccount::get(db, self.account_id)?.ok_or(Error::NotLoggedIn("Invalid account".to_string()))
     }
 
+    /// Likes continue from the saved cursors, and search feeds only while backfilling.
+    fn resumable(&self) -> bool {
+        match self.params {
+            TwitterFeedParams::Likes { .. } => true,
+            TwitterFeedParams::Search { .. } => !self.reached_end,
+            _ => false,
+        }
+    }
+
     fn get_fetch_context(&self, db: Database) -> Result<Self::FetchContext> {
         let cursor = match self.params {
             TwitterFeedParams::Likes { .. } if self.reached_end => self.top_cursor(db)?,
@@ -349,19 +559,40 @@ impl Feed for TwitterFeed {
             _ if self.reached_end => Direction::Forward,
             _ => Direction::Backward,
         };
-        Ok(TwitterFetchContext { cursor, direction })
+        // Search feeds not reached end resume backfilling from the day of the oldest saved tweet
+        let backfill = matches!(self.params, TwitterFeedParams::Search { .. }) && !self.reached_end;
+        let until = if backfill {
+            self.oldest_created_date(db)?
+                .and_then(|date| date.date().checked_add_days(Days::new(1)))
+        } else {
+            None
+        };
+        Ok(TwitterFetchContext {
+            cursor,
+            direction,
+            backfill,
+            until,
+            oldest: None,
+            client: None,
+        })
     }
 
     async fn fetch(&self, ctx: &mut Self::FetchContext, auth: Option<&Self::Auth>) -> Result<Self::FetchResult> {
-        let Some(auth) = auth else {
+        if auth.is_none() {
             return Err(Error::NotLoggedIn("Twitter feed needs an account".to_string()));
-        };
-        let client = TwitterClient::new(auth.clone()).map_err(anyhow::Error::from)?;
+        }
+        // The client is set from the cache of the account when the context is prepared
+        if ctx.client.is_none() {
+            return Err(Error::NotLoggedIn("Twitter feed needs a cached client".to_string()));
+        }
+        if let TwitterFeedParams::Search { ref option, .. } = self.params {
+            return self.fetch_search(ctx, option).await;
+        }
+        let client = ctx.client.as_ref().unwrap();
         let cursor = ctx.cursor.as_deref();
         let result = match self.params {
             TwitterFeedParams::Likes { user_id } => client.likes(user_id as u64, cursor).await,
             TwitterFeedParams::Posts { user_id } => client.user_tweets(user_id as u64, cursor).await,
-            TwitterFeedParams::Search { ref query } => client.search(query, cursor).await,
             _ => todo!(),
         }
         .map_err(anyhow::Error::from)?;
@@ -374,16 +605,55 @@ impl Feed for TwitterFeed {
         Ok(result)
     }
 
-    fn archived_posts(db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    async fn fetch_name(&self, ctx: &Self::FetchContext) -> Result<Option<FeedName>> {
+        let Some(client) = ctx.client.as_ref() else {
+            return Ok(None);
+        };
+        let name = match self.params {
+            TwitterFeedParams::Likes { user_id } | TwitterFeedParams::Posts { user_id } => {
+                let user = client.user_by_id(user_id as u64).await.map_err(anyhow::Error::from)?;
+                let name = match self.params {
+                    TwitterFeedParams::Likes { .. } => format!("Likes by {}", user.name),
+                    _ => format!("Posts by {}", user.name),
+                };
+                FeedName {
+                    name,
+                    avatar_url: user.profile_image_url_https,
+                }
+            }
+            TwitterFeedParams::List { list_id, .. } => {
+                let list = client.list_by_id(list_id as u64).await.map_err(anyhow::Error::from)?;
+                FeedName {
+                    name: format!("List {}", list.name),
+                    avatar_url: list.banner_url,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(name))
+    }
+
+    fn archived_posts(
+        db: Database,
+        filter: &WorkFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::library::{ImageView, WorkView};
         use bottle_core::schema::{image, tweet, twitter_media, twitter_user, work};
         use bottle_library::model::{Image, Work};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{
+            dsl::sql,
+            sql_types::{Bool, Integer},
+        };
 
         // 1. Fetch works
         let (works, total_items) = work::table
             .filter(work::source.eq("twitter"))
-            .order(work::added_date.desc())
+            .filter(sql::<Bool>(&filter.sql_condition()))
+            .order(sql::<Integer>(&order.work_sql(&POST_COLUMNS)))
             .paginate(page, page_size)
             .load_and_count::<Work>(db)?;
 
@@ -416,12 +686,21 @@ impl Feed for TwitterFeed {
         let works = works.into_iter().map(WorkView::from).collect::<Vec<_>>();
         let media = group::filter_media_by_works(&media, &works);
 
+        // 6. Fetch associated tags
+        let tags = util::get_tag_map(db, posts.iter().map(|tweet| tweet.id))?;
+
         Ok(GeneralResponse {
-            posts: Some(posts.into_iter().map(PostView::from).collect()),
+            posts: Some(
+                posts
+                    .iter()
+                    .map(|tweet| tweet.post_view(tags.get(&tweet.id).cloned().unwrap_or_default()))
+                    .collect(),
+            ),
             users: Some(users.into_iter().map(UserView::from).collect()),
             media: Some(media.into_iter().map(MediaView::from).collect()),
             works: Some(works),
             images: Some(images.into_iter().map(ImageView::from).collect()),
+            notes: None,
             total_items,
             page,
             page_size,
@@ -430,30 +709,42 @@ impl Feed for TwitterFeed {
 
     fn archived_posts_grouped_by_user(
         db: Database,
+        filter: &WorkFilter,
         page: i64,
         page_size: i64,
         recent_count: i64,
     ) -> Result<GeneralResponse> {
         use diesel::dsl::sql_query;
         let query = sql_query(group::grouped_by_user_query(
-            "select distinct tweet.* from tweet
-            join work on tweet.id = work.post_id_int
-            where work.source = 'twitter'",
+            &format!(
+                "select distinct tweet.* from tweet
+                join work on tweet.id = work.post_id_int
+                where work.source = 'twitter' and {}",
+                filter.sql_condition()
+            ),
             "order by created_date desc",
         ))
         .into_boxed();
         group::posts_grouped_by_user(db, query, page, page_size, recent_count, true)
     }
 
-    fn archived_posts_by_user(db: Database, user_id: String, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn archived_posts_by_user(
+        db: Database,
+        user_id: String,
+        filter: &WorkFilter,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{tweet, work};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{dsl::sql, sql_types::Bool};
 
-        let user_id = user_id.parse::<i64>()?;
+        let user_id = user::resolve_user_id(db, &user_id)?;
         let results = tweet::table
             .inner_join(work::table.on(work::post_id_int.eq(tweet::id.nullable())))
             .filter(tweet::user_id.eq(user_id))
             .filter(work::source.eq("twitter"))
+            .filter(sql::<Bool>(&filter.sql_condition()))
             .order(tweet::created_date.desc())
             .select(tweet::all_columns)
             .distinct()
@@ -485,7 +776,7 @@ impl Feed for TwitterFeed {
         use bottle_core::schema::{tweet, twitter_watch_list_tweet};
         use bottle_util::diesel_ext::Paginate;
 
-        let user_id = user_id.parse::<i64>()?;
+        let user_id = user::resolve_user_id(db, &user_id)?;
         let results = twitter_watch_list_tweet::table
             .inner_join(tweet::table)
             .filter(tweet::user_id.eq(user_id))
@@ -496,11 +787,78 @@ impl Feed for TwitterFeed {
             .load_and_count::<model::Tweet>(db)?;
         group::posts_by_user(db, results, user_id, page, page_size, false)
     }
+
+    fn new_post_count(&self, db: Database, since: DateTime<Utc>) -> Result<i64> {
+        use bottle_core::schema::{tweet, twitter_watch_list_tweet};
+
+        let count = twitter_watch_list_tweet::table
+            .inner_join(tweet::table)
+            .filter(twitter_watch_list_tweet::watch_list_id.eq(self.id))
+            .filter(tweet::added_date.gt(since.naive_utc()))
+            .count()
+            .get_result::<i64>(db)?;
+        Ok(count)
+    }
+}
+
+// MARK: Search
+
+impl TwitterFeed {
+    /// Fetch a page of the search. Search only returns recent results in a cursor chain,
+    /// so when the chain runs dry while backfilling, the search continues in a window ending at
+    /// the day of the oldest tweet fetched, until a window has no tweets.
+    async fn fetch_search(&self, ctx: &mut TwitterFetchContext, option: &SearchOption) -> Result<TimelineResult> {
+        let client = ctx.client.clone().unwrap();
+        loop {
+            let option = SearchOption {
+                until: option.until.into_iter().chain(ctx.until).min(),
+                ..option.clone()
+            };
+            let result = client
+                .search(&option, ctx.cursor.as_deref())
+                .await
+                .map_err(anyhow::Error::from)?;
+            ctx.cursor = result.bottom_cursor().map(|c| c.value().to_string());
+
+            let oldest = result.tweets.iter().map(|t| t.created_at).min();
+            if oldest.is_some() {
+                ctx.oldest = oldest.min(ctx.oldest).or(oldest);
+                return Ok(result);
+            }
+
+            // The cursor chain ran dry, slice the next window if the search has gone past any tweet
+            let Some(until) = ctx.backfill.then_some(ctx.oldest).flatten().and_then(|oldest| {
+                let until = oldest.date_naive().checked_add_days(Days::new(1))?;
+                match ctx.until {
+                    // Always move the window back, even if the whole chain was in a single day
+                    Some(current) if until >= current => current.checked_sub_days(Days::new(1)),
+                    _ => Some(until),
+                }
+            }) else {
+                return Ok(result);
+            };
+            tracing::info!("Search of twitter feed {} continues until {}", self.id, until);
+            ctx.until = Some(until);
+            ctx.oldest = None;
+            ctx.cursor = None;
+        }
+    }
 }
 
 // MARK: Helpers
 
 impl TwitterFeed {
+    /// Creation date of the oldest tweet saved in the feed.
+    fn oldest_created_date(&self, db: Database) -> Result<Option<NaiveDateTime>> {
+        use bottle_core::schema::{tweet, twitter_watch_list_tweet};
+        let result = twitter_watch_list_tweet::table
+            .inner_join(tweet::table)
+            .filter(twitter_watch_list_tweet::watch_list_id.eq(self.id))
+            .select(diesel::dsl::min(tweet::created_date))
+            .first::<Option<NaiveDateTime>>(db)?;
+        Ok(result)
+    }
+
     fn top_cursor(&self, db: Database) -> Result<Option<String>> {
         use bottle_core::schema::twitter_watch_list_history::dsl::*;
         let result = twitter_watch_list_history
@@ -527,7 +885,7 @@ impl TwitterFeed {
 impl TwitterFeedParams {
     fn kind(&self) -> String {
         match self {
-            TwitterFeedParams::Timeline => "timeline".to_string(),
+            TwitterFeedParams::Timeline { .. } => "timeline".to_string(),
             TwitterFeedParams::Bookmarks => "bookmarks".to_string(),
             TwitterFeedParams::Likes { .. } => "likes".to_string(),
             TwitterFeedParams::Posts { .. } => "posts".to_string(),
@@ -536,13 +894,62 @@ impl TwitterFeedParams {
         }
     }
 
+    /// Errors of the fields in the params, by paths like `posts.user_id`.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let kind = self.kind();
+        let mut errors = Vec::new();
+        if let Some(user_id) = self.user_id().filter(|id| *id <= 0) {
+            errors.push(FieldError::new(
+                format!("{}.user_id", kind),
+                format!("Invalid user ID {}", user_id),
+            ));
+        }
+        if let Some(list_id) = self.twitter_list_id().filter(|id| *id <= 0) {
+            errors.push(FieldError::new(
+                format!("{}.list_id", kind),
+                format!("Invalid list ID {}", list_id),
+            ));
+        }
+        if let TwitterFeedParams::Search { option, .. } = self {
+            let prefix = format!("{}.option", kind);
+            if option.is_empty() {
+                errors.push(FieldError::new(format!("{}.keyword", prefix), "Empty query"));
+            }
+            if option.from_user.as_ref().is_some_and(|user| user.trim().is_empty()) {
+                errors.push(FieldError::new(format!("{}.from_user", prefix), "Empty user"));
+            }
+            if let (Some(since), Some(until)) = (option.since, option.until) {
+                if since >= until {
+                    errors.push(FieldError::new(
+                        format!("{}.until", prefix),
+                        format!("Until {} not after since {}", until, since),
+                    ));
+                }
+            }
+        }
+        errors
+    }
+
     fn twitter_list_id(&self) -> Option<i64> {
         match self {
-            TwitterFeedParams::List { list_id } => Some(*list_id),
+            TwitterFeedParams::List { list_id, .. } => Some(*list_id),
             _ => None,
         }
     }
 
+    /// How retweets are saved to the feed, where only the timeline and lists have retweets of others.
+    pub fn retweets(&self) -> RetweetMode {
+        match self {
+            TwitterFeedParams::Timeline { retweets } | TwitterFeedParams::List { retweets, .. } => *retweets,
+            _ => RetweetMode::Keep,
+        }
+    }
+
+    fn retweets_column(&self) -> Option<String> {
+        let retweets = self.retweets();
+        (retweets != RetweetMode::Keep).then(|| retweets.as_str().to_string())
+    }
+
     fn user_id(&self) -> Option<i64> {
         match self {
             TwitterFeedParams::Likes { user_id } => Some(*user_id),
@@ -553,7 +960,15 @@ impl TwitterFeedParams {
 
     fn search_query(&self) -> Option<String> {
         match self {
-            TwitterFeedParams::Search { query } => Some(query.clone()),
+            TwitterFeedParams::Search { option, .. } => Some(option.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Minimum likes of the tweets saved to the feed, only for searches.
+    pub fn min_likes(&self) -> Option<u32> {
+        match self {
+            TwitterFeedParams::Search { min_likes, .. } => *min_likes,
             _ => None,
         }
     }
@@ -8,12 +8,12 @@ use diesel::{
 use std::collections::{HashMap, HashSet};
 
 use bottle_core::{
-    feed::{GeneralResponse, MediaView, PostView, UserView},
+    feed::{GeneralResponse, MediaView, UserView},
     library::WorkView,
     Database, Result,
 };
 
-use crate::model;
+use crate::{model, util};
 
 // MARK: Internal methods for artist grouping
 
@@ -110,7 +110,7 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         user.post_count = user_to_post_count.get(&user.user_id).cloned();
     }
     // Sort users by post_count
-    users.sort_by(|a, b| b.post_count.cmp(&a.post_count));
+    users.sort_by_key(|u| std::cmp::Reverse(u.post_count));
 
     // 3. Fetch associated posts
     let posts = tweet::table
@@ -138,12 +138,21 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         media = filter_media_by_works(&media, &works);
     }
 
+    // 6. Fetch associated tags
+    let tags = util::get_tag_map(db, posts.iter().map(|post| post.id))?;
+
     Ok(GeneralResponse {
-        posts: Some(posts.into_iter().map(PostView::from).collect()),
+        posts: Some(
+            posts
+                .iter()
+                .map(|post| post.post_view(tags.get(&post.id).cloned().unwrap_or_default()))
+                .collect(),
+        ),
         users: Some(users),
         media: Some(media.into_iter().map(MediaView::from).collect()),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items: user_count,
         page,
         page_size,
@@ -183,12 +192,21 @@ pub(crate) fn posts_by_user(
         media = filter_media_by_works(&media, &works);
     }
 
+    // 4. Fetch associated tags
+    let tags = util::get_tag_map(db, tweets.iter().map(|t| t.id))?;
+
     Ok(GeneralResponse {
-        posts: Some(tweets.into_iter().map(PostView::from).collect()),
+        posts: Some(
+            tweets
+                .iter()
+                .map(|t| t.post_view(tags.get(&t.id).cloned().unwrap_or_default()))
+                .collect(),
+        ),
         users: Some(vec![UserView::from(user)]),
         media: Some(media.into_iter().map(MediaView::from).collect()),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items,
         page,
         page_size,
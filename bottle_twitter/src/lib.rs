@@ -4,8 +4,10 @@ mod community;
 mod feed;
 mod group;
 mod model;
+mod user;
 mod util;
 
 pub use cache::*;
 pub use community::*;
 pub use feed::*;
+pub use user::*;
@@ -18,7 +18,7 @@ pub struct TwitterUser {
     pub added_date: NaiveDateTime,
 }
 
-#[derive(Insertable, Debug, Clone)]
+#[derive(Insertable, AsChangeset, Debug, Clone)]
 #[diesel(table_name = twitter_user)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
 pub struct NewTwitterUser {
@@ -32,6 +32,27 @@ pub struct NewTwitterUser {
     pub created_date: NaiveDateTime,
 }
 
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
+#[diesel(table_name = twitter_username_history)]
+#[diesel(primary_key(user_id, username))]
+#[diesel(belongs_to(TwitterUser, foreign_key = user_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct TwitterUsernameHistory {
+    pub user_id: i64,
+    pub username: String,
+    pub name: String,
+    pub first_seen_date: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = twitter_username_history)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewTwitterUsernameHistory {
+    pub user_id: i64,
+    pub username: String,
+    pub name: String,
+}
+
 #[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone)]
 #[diesel(table_name = twitter_account)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -68,9 +89,12 @@ pub struct TwitterAccountUpdate {
 pub struct Tweet {
     pub id: i64,
     pub user_id: i64,
+    /// Raw text of the tweet.
     pub caption: String,
     pub created_date: NaiveDateTime,
     pub added_date: NaiveDateTime,
+    /// Display text of the tweet, with links expanded and entities unescaped.
+    pub text: Option<String>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -81,6 +105,18 @@ pub struct NewTweet {
     pub user_id: i64,
     pub caption: String,
     pub created_date: NaiveDateTime,
+    pub text: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Insertable, Associations, Debug, Clone)]
+#[diesel(table_name = twitter_tag)]
+#[diesel(primary_key(tweet_id, tag))]
+#[diesel(belongs_to(Tweet))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct TwitterTag {
+    pub tweet_id: i64,
+    /// Hashtag without `#`, or mentioned username with `@`.
+    pub tag: String,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Debug, Clone)]
@@ -152,6 +188,15 @@ pub struct TwitterWatchList {
     pub user_id: Option<i64>,
     pub search_query: Option<String>,
     pub reached_end: bool,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub cover_path: Option<String>,
+    pub last_error: Option<String>,
+    pub failure_count: i32,
+    pub retweets: Option<String>,
+    pub search_min_likes: Option<i32>,
+    pub require_media: bool,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -166,6 +211,12 @@ pub struct NewTwitterWatchList {
     pub twitter_list_id: Option<i64>,
     pub user_id: Option<i64>,
     pub search_query: Option<String>,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub retweets: Option<String>,
+    pub search_min_likes: Option<i32>,
+    pub require_media: bool,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(AsChangeset, Debug, Clone)]
@@ -175,6 +226,10 @@ pub struct TwitterWatchListUpdate {
     pub name: Option<String>,
     pub watching: bool,
     pub first_fetch_limit: Option<i32>,
+    pub icon_url: Option<Option<String>>,
+    pub color: Option<Option<String>>,
+    pub require_media: bool,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Debug, Clone)]
@@ -187,6 +242,7 @@ pub struct TwitterWatchListTweet {
     pub tweet_id: i64,
     pub sort_index: Option<i64>,
     pub stale: bool,
+    pub dismissed: bool,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use itertools::Itertools;
+use serde::Serialize;
+
+use bottle_core::{Database, Error, Result};
+
+use crate::model;
+
+diesel::define_sql_function!(fn lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text);
+
+/// A username the user has used.
+#[derive(Debug, Clone, Serialize)]
+pub struct TwitterUsername {
+    pub username: String,
+    pub name: String,
+    pub first_seen_date: DateTime<Utc>,
+}
+
+/// Save the users, updating the existing ones, and keep a history of their usernames.
+pub(crate) fn save_users(db: Database, users: &[model::NewTwitterUser]) -> Result<()> {
+    use bottle_core::schema::{twitter_user, twitter_username_history};
+
+    let users = users.iter().unique_by(|user| user.id).collect::<Vec<_>>();
+    for user in users.iter() {
+        diesel::insert_into(twitter_user::table)
+            .values(*user)
+            .on_conflict(twitter_user::id)
+            .do_update()
+            .set(*user)
+            .execute(db)?;
+    }
+
+    let history = users
+        .iter()
+        .map(|user| model::NewTwitterUsernameHistory {
+            user_id: user.id,
+            username: user.username.clone(),
+            name: user.name.clone(),
+        })
+        .collect::<Vec<_>>();
+    diesel::insert_into(twitter_username_history::table)
+        .values(&history)
+        .execute(db)?;
+    Ok(())
+}
+
+/// Resolve the user ID from either an ID, or a current or previous username with or without `@`.
+pub fn resolve_user_id(db: Database, user: &str) -> Result<i64> {
+    use bottle_core::schema::{twitter_user, twitter_username_history};
+
+    if let Ok(user_id) = user.parse::<i64>() {
+        return Ok(user_id);
+    }
+    let username = user.trim_start_matches('@').to_lowercase();
+
+    // Prefer the current username, then the most recent user who used it
+    let current = twitter_user::table
+        .filter(lower(twitter_user::username).eq(&username))
+        .select(twitter_user::id)
+        .first::<i64>(db)
+        .optional()?;
+    if let Some(user_id) = current {
+        return Ok(user_id);
+    }
+    let previous = twitter_username_history::table
+        .filter(lower(twitter_username_history::username).eq(&username))
+        .order(twitter_username_history::first_seen_date.desc())
+        .select(twitter_username_history::user_id)
+        .first::<i64>(db)
+        .optional()?;
+    previous.ok_or(Error::ObjectNotFound(format!("Twitter user @{}", username)))
+}
+
+/// Usernames the user has used, the latest first.
+pub fn username_history(db: Database, user_id: i64) -> Result<Vec<TwitterUsername>> {
+    use bottle_core::schema::twitter_username_history;
+
+    let records = twitter_username_history::table
+        .filter(twitter_username_history::user_id.eq(user_id))
+        .order(twitter_username_history::first_seen_date.desc())
+        .load::<model::TwitterUsernameHistory>(db)?;
+    Ok(records
+        .into_iter()
+        .map(|record| TwitterUsername {
+            username: record.username,
+            name: record.name,
+            first_seen_date: record.first_seen_date.and_utc(),
+        })
+        .collect())
+}
@@ -1,15 +1,36 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use diesel::prelude::*;
+use itertools::Itertools;
+
 use bottle_core::{
-    feed::{MediaView, PostView, UserView},
+    feed::{MediaView, PostView, Scheme, UserView},
     library::{RemoteImage, RemoteWork},
-    Error, Result,
+    Database, Error, Result,
 };
 
 use twitter_client as client;
 
 use crate::community::TwitterAccount;
-use crate::feed::{TwitterFeed, TwitterFeedParams};
+use crate::feed::{RetweetMode, TwitterFeed, TwitterFeedParams};
 use crate::model;
 
+pub(crate) fn get_tag_map(db: Database, post_ids: impl IntoIterator<Item = i64>) -> Result<HashMap<i64, Vec<String>>> {
+    use bottle_core::schema::twitter_tag;
+
+    let records = twitter_tag::table
+        .filter(twitter_tag::tweet_id.eq_any(post_ids))
+        .load::<model::TwitterTag>(db)?;
+
+    let mut tag_map = HashMap::new();
+    for record in records {
+        tag_map.entry(record.tweet_id).or_insert_with(Vec::new).push(record.tag);
+    }
+
+    Ok(tag_map)
+}
+
 impl From<&client::User> for model::NewTwitterUser {
     fn from(user: &client::User) -> Self {
         model::NewTwitterUser {
@@ -45,17 +66,29 @@ impl From<&client::Tweet> for model::NewTweet {
             user_id: tweet.user.id as i64,
             caption: tweet.full_text.clone(),
             created_date: tweet.created_at.naive_utc(),
+            text: Some(tweet_text(tweet)),
         }
     }
 }
 
+/// Display text of the tweet, with `t.co` links expanded and links to the media removed.
+pub(crate) fn tweet_text(tweet: &client::Tweet) -> String {
+    let urls = tweet
+        .urls
+        .iter()
+        .map(|url| (url.url.as_str(), url.expanded_url.as_str()));
+    let media_urls = tweet.media.iter().map(|m| m.url.url.as_str());
+    bottle_util::text::unescape_html(&bottle_util::text::expand_urls(&tweet.full_text, urls, media_urls))
+}
+
 pub(crate) fn post_view(tweet: &client::Tweet) -> PostView {
     PostView {
         post_id: tweet.id.to_string(),
         community: "twitter".to_string(),
         user_id: Some(tweet.user.id.to_string()),
-        text: tweet.full_text.clone(),
+        text: tweet_text(tweet),
         thumbnail_url: None,
+        tags: Some(tag_names(tweet)),
         media_count: Some(tweet.media.len() as i32),
         created_date: tweet.created_at,
         added_date: None,
@@ -63,6 +96,37 @@ pub(crate) fn post_view(tweet: &client::Tweet) -> PostView {
     }
 }
 
+/// Hashtags of the tweet, and usernames mentioned in it with `@`.
+fn tag_names(tweet: &client::Tweet) -> Vec<String> {
+    let hashtags = tweet.hashtags.iter().map(|hashtag| hashtag.text.clone());
+    let mentions = mentions(&tweet.full_text).map(|username| format!("@{}", username));
+    hashtags.chain(mentions).unique().collect()
+}
+
+/// Usernames mentioned in the text, like `@username` not preceded by a word character.
+fn mentions(text: &str) -> impl Iterator<Item = &str> {
+    let is_username_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    text.match_indices('@').filter_map(move |(i, _)| {
+        let preceded = text[..i]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        let rest = &text[i + 1..];
+        let end = rest.find(|c: char| !is_username_char(c)).unwrap_or(rest.len());
+        (!preceded && end > 0).then(|| &rest[..end])
+    })
+}
+
+pub(crate) fn tags(tweet: &client::Tweet) -> Vec<model::TwitterTag> {
+    tag_names(tweet)
+        .into_iter()
+        .map(|tag| model::TwitterTag {
+            tweet_id: tweet.id as i64,
+            tag,
+        })
+        .collect()
+}
+
 pub(crate) fn media(tweet: &client::Tweet) -> Vec<model::TwitterMedia> {
     tweet
         .media
@@ -96,6 +160,7 @@ pub(crate) fn media_views(tweet: &client::Tweet) -> Vec<MediaView> {
             thumbnail_url: thumbnail_url(m),
             width: Some(m.original_info.width as i32),
             height: Some(m.original_info.height as i32),
+            animated: Some(m.type_ == "animated_gif"),
             extra: Some(serde_json::json!({"twitter": { "type": m.type_ }})),
         })
         .collect()
@@ -117,14 +182,25 @@ impl TryFrom<model::TwitterWatchList> for TwitterFeed {
     type Error = Error;
 
     fn try_from(watch_list: model::TwitterWatchList) -> Result<Self> {
+        let retweets = match watch_list.retweets.as_deref() {
+            Some(retweets) => RetweetMode::from_str(retweets)?,
+            None => RetweetMode::Keep,
+        };
         Ok(TwitterFeed {
             id: watch_list.id,
             name: watch_list.name,
             first_fetch_limit: watch_list.first_fetch_limit,
+            icon_url: watch_list.icon_url,
+            color: watch_list.color,
+            cover_path: watch_list.cover_path,
+            last_error: watch_list.last_error,
+            failure_count: watch_list.failure_count,
+            min_resolution: watch_list.min_resolution,
+            require_media: watch_list.require_media,
             watching: watch_list.watching,
             account_id: watch_list.account_id,
             params: match watch_list.kind.as_str() {
-                "timeline" => TwitterFeedParams::Timeline,
+                "timeline" => TwitterFeedParams::Timeline { retweets },
                 "bookmarks" => TwitterFeedParams::Bookmarks,
                 "likes" => TwitterFeedParams::Likes {
                     user_id: watch_list.user_id.ok_or(Error::ObjectNotComplete(
@@ -140,11 +216,14 @@ impl TryFrom<model::TwitterWatchList> for TwitterFeed {
                     list_id: watch_list.twitter_list_id.ok_or(Error::ObjectNotComplete(
                         "twitter list id cannot be null for list feed".to_string(),
                     ))?,
+                    retweets,
                 },
                 "search" => TwitterFeedParams::Search {
-                    query: watch_list.search_query.ok_or(Error::ObjectNotComplete(
-                        "twitter search query cannot be null for search feed".to_string(),
-                    ))?,
+                    option: client::SearchOption::from_str(&watch_list.search_query.ok_or(
+                        Error::ObjectNotComplete("twitter search query cannot be null for search feed".to_string()),
+                    )?)
+                    .map_err(anyhow::Error::from)?,
+                    min_likes: watch_list.search_min_likes.map(|n| n as u32),
                 },
                 _ => Err(Error::UnknownField(format!(
                     "twitter watch list kind {}",
@@ -156,6 +235,62 @@ impl TryFrom<model::TwitterWatchList> for TwitterFeed {
     }
 }
 
+/// Tweets of the result with their sort indices. Retweets are replaced with the original tweets if collapsed,
+/// keeping only the first occurrence of each tweet.
+pub(crate) fn timeline_tweets(result: &client::TimelineResult, retweets: RetweetMode) -> Vec<(&client::Tweet, u64)> {
+    let mut ids = HashSet::new();
+    result
+        .tweets
+        .iter()
+        .zip(result.sort_indices.iter())
+        .map(|(tweet, sort_index)| match (retweets, &tweet.retweeted_status) {
+            (RetweetMode::Collapse, Some(original)) => (original.as_ref(), *sort_index),
+            _ => (tweet, *sort_index),
+        })
+        .filter(|(tweet, _)| ids.insert(tweet.id))
+        .collect()
+}
+
+/// Scheme of how retweets are saved to a feed.
+pub(crate) fn retweet_mode_scheme() -> Scheme {
+    Scheme::field(
+        "Retweets",
+        Scheme::choices([("keep", "Keep"), ("skip", "Skip"), ("collapse", "Collapse to Original")]),
+    )
+    .with_default(serde_json::json!("keep"))
+}
+
+/// Scheme of the search option, with the defaults of `SearchOption::default()`.
+pub(crate) fn search_option_scheme() -> Scheme {
+    let default = serde_json::to_value(client::SearchOption::default()).unwrap_or_default();
+    let field = |name: &str, label: &str, scheme: Scheme| {
+        let scheme = Scheme::field(label, scheme).with_default(default[name].clone());
+        (name.to_string(), scheme)
+    };
+
+    Scheme::Object(HashMap::from([
+        field("keyword", "Keyword", Scheme::Optional(Box::new(Scheme::String))),
+        field(
+            "from_user",
+            "From User (Screen Name)",
+            Scheme::Optional(Box::new(Scheme::String)),
+        ),
+        field("min_faves", "Minimum Likes", Scheme::Optional(Box::new(Scheme::Int))),
+        field("media_only", "Media Only", Scheme::Bool),
+        field("exclude_retweets", "Exclude Retweets", Scheme::Bool),
+        field(
+            "since",
+            "Since (YYYY-MM-DD)",
+            Scheme::Optional(Box::new(Scheme::String)),
+        ),
+        field(
+            "until",
+            "Until (YYYY-MM-DD, Exclusive)",
+            Scheme::Optional(Box::new(Scheme::String)),
+        ),
+    ]))
+}
+
 impl From<model::TwitterUser> for UserView {
     fn from(user: model::TwitterUser) -> Self {
         UserView {
@@ -171,21 +306,6 @@ impl From<model::TwitterUser> for UserView {
     }
 }
 
-impl From<model::Tweet> for PostView {
-    fn from(tweet: model::Tweet) -> Self {
-        PostView {
-            post_id: tweet.id.to_string(),
-            user_id: Some(tweet.user_id.to_string()),
-            community: "twitter".to_string(),
-            text: tweet.caption,
-            thumbnail_url: None,
-            created_date: tweet.created_date.and_utc(),
-            added_date: Some(tweet.added_date.and_utc()),
-            ..Default::default()
-        }
-    }
-}
-
 impl From<model::TwitterMedia> for MediaView {
     fn from(media: model::TwitterMedia) -> Self {
         let url = media.original_url();
@@ -199,6 +319,7 @@ impl From<model::TwitterMedia> for MediaView {
             thumbnail_url,
             width: Some(media.width),
             height: Some(media.height),
+            animated: Some(media.type_ == "animated_gif"),
             extra: Some(serde_json::json!({"twitter": { "type": media.type_ }})),
         }
     }
@@ -226,6 +347,29 @@ impl TryFrom<&model::TwitterMedia> for RemoteWork {
     }
 }
 
+impl model::Tweet {
+    /// Display text of the tweet, or the raw text unescaped if saved before it was stored.
+    pub(crate) fn display_text(&self) -> String {
+        self.text
+            .clone()
+            .unwrap_or_else(|| bottle_util::text::unescape_html(&self.caption))
+    }
+
+    pub fn post_view(&self, tags: Vec<String>) -> PostView {
+        PostView {
+            post_id: self.id.to_string(),
+            user_id: Some(self.user_id.to_string()),
+            community: "twitter".to_string(),
+            text: self.display_text(),
+            thumbnail_url: None,
+            tags: Some(tags),
+            created_date: self.created_date.and_utc(),
+            added_date: Some(self.added_date.and_utc()),
+            ..Default::default()
+        }
+    }
+}
+
 impl model::TwitterMedia {
     fn original_url(&self) -> String {
         match self.type_.as_str() {
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Interval of TCP and HTTP/2 keep-alive pings.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Idle connections are kept in the pool for this long, longer than the gap between pages of a feed update.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Builder of HTTP clients with compressed responses and kept-alive connections,
+/// so that a client reused across requests doesn't repeat TLS handshakes.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(KEEP_ALIVE_INTERVAL)
+        .http2_keep_alive_interval(KEEP_ALIVE_INTERVAL)
+        .http2_keep_alive_while_idle(true)
+}
+
+/// Outcome of a request made by a client, reported to its response hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    Success,
+    RateLimited,
+    Failed,
+}
+
+impl RequestOutcome {
+    /// Outcome of a request which failed with the error.
+    pub fn of_error(error: &reqwest::Error) -> Self {
+        match error.status() {
+            Some(reqwest::StatusCode::TOO_MANY_REQUESTS) => RequestOutcome::RateLimited,
+            _ => RequestOutcome::Failed,
+        }
+    }
+}
+
+/// Called by a client after each of its requests, e.g. to count the requests made with an account.
+#[derive(Clone)]
+pub struct ResponseHook(Arc<dyn Fn(RequestOutcome) + Send + Sync>);
+
+impl ResponseHook {
+    pub fn new(f: impl Fn(RequestOutcome) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub fn call(&self, outcome: RequestOutcome) {
+        (self.0)(outcome)
+    }
+}
+
+impl std::fmt::Debug for ResponseHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ResponseHook")
+    }
+}
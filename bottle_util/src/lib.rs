@@ -1,6 +1,8 @@
 pub mod diesel_ext;
+pub mod http;
 pub mod iso8601;
 pub mod macros;
 pub mod parsing;
+pub mod text;
 
 pub use parsing::*;
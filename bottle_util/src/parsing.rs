@@ -27,7 +27,7 @@ pub fn parse_query_str(query_str: &str) -> Result<HashMap<String, String>> {
 pub fn parse_filename(url: &str) -> Result<String> {
     let url = Url::parse(url)?;
     url.path_segments()
-        .and_then(|segments| segments.last())
+        .and_then(|mut segments| segments.next_back())
         .map(|s| s.to_string())
         .ok_or(ParsingError::InvalidUrl(url.to_string()))
 }
@@ -0,0 +1,90 @@
+/// Replace the short links in the text with their expanded URLs, and remove the links in `removed`,
+/// e.g. `t.co` links in a tweet, where links to attached media are removed.
+pub fn expand_urls<'a>(
+    text: &str,
+    urls: impl IntoIterator<Item = (&'a str, &'a str)>,
+    removed: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let mut text = text.to_string();
+    for (short_url, expanded_url) in urls {
+        if !short_url.is_empty() {
+            text = text.replace(short_url, expanded_url);
+        }
+    }
+    for url in removed {
+        if !url.is_empty() {
+            text = text.replace(url, "");
+        }
+    }
+    text.trim().to_string()
+}
+
+/// Convert an HTML fragment to plain text, e.g. a pixiv caption.
+/// Line breaks are kept for `<br>` and paragraphs, other tags are stripped with their content kept,
+/// and entities are unescaped.
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            // Not a tag, keep the rest as it is
+            text.push_str(&rest[start..]);
+            rest = "";
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_lowercase();
+        let name = tag.trim_start_matches('/').split([' ', '/']).next().unwrap_or_default();
+        if name == "br" || (name == "p" && tag.starts_with('/')) {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    unescape_html(&text).trim().to_string()
+}
+
+/// Unescape HTML entities, including the named ones commonly seen in posts and numeric ones.
+pub fn unescape_html(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse::<u32>().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
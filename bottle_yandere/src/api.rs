@@ -1,4 +1,4 @@
-use bottle_core::{feed::*, Database, Result};
+use bottle_core::{feed::*, Database, Error, Result};
 use yandere_client::TagType;
 
 use crate::{
@@ -20,6 +20,12 @@ pub async fn fetch_posts<'a>(
         id: -1, // Temporary feed
         name: None,
         first_fetch_limit: None,
+        icon_url: None,
+        color: None,
+        cover_path: None,
+        last_error: None,
+        failure_count: 0,
+        min_resolution: None,
         watching: false,
         params: request.params.clone(),
         reached_end: false,
@@ -30,7 +36,7 @@ pub async fn fetch_posts<'a>(
         .map(|o| o.parse::<u32>())
         .transpose()?
         .unwrap_or(1);
-    let mut ctx = YandereFetchContext { page };
+    let mut ctx = YandereFetchContext { page, etag: None };
     let result = feed.fetch(&mut ctx, None).await?;
 
     // 1.1. Store posts, tags and pools in cache
@@ -40,11 +46,10 @@ pub async fn fetch_posts<'a>(
         .pools
         .extend(result.pools.iter().map(|pool| (pool.id, pool.clone())));
     for item in result.pool_posts.iter() {
-        cache
-            .post_pools
-            .entry(item.post_id)
-            .or_default()
-            .push(item.clone());
+        match cache.post_pools.get_mut(&item.post_id) {
+            Some(items) => items.push(item.clone()),
+            None => cache.post_pools.insert(item.post_id, vec![item.clone()]),
+        }
     }
     tracing::info!(
         "Stored {} yandere posts, {} tags, {} pools, {} pool-posts to cache",
@@ -80,3 +85,37 @@ pub async fn fetch_posts<'a>(
         total_items: None,
     })
 }
+
+/// Fetch posts from the normalized endpoint, as searches with the metatags of yandere.
+pub async fn fetch_endpoint<'a>(
+    db: Database<'a>,
+    cache: &'a mut YandereCache,
+    request: &EndpointRequest<Endpoint>,
+) -> Result<EndpointResponse> {
+    let params = match &request.params {
+        Endpoint::PostDetail { post_id } => YandereFeedParams::Search {
+            query: format!("id:{}", Endpoint::parse_id::<u64>(post_id, "Post ID")?),
+        },
+        endpoint => endpoint_params(endpoint)?,
+    };
+    let request = EndpointRequest {
+        params,
+        offset: request.offset.clone(),
+    };
+    fetch_posts(db, cache, &request).await
+}
+
+/// Feed params browsed by the normalized endpoint, so that the browsing can be promoted to a feed.
+/// The post detail is not a feed.
+pub fn endpoint_params(endpoint: &Endpoint) -> Result<YandereFeedParams> {
+    let query = match endpoint {
+        Endpoint::UserPosts { user_id } => format!("user:{}", user_id.trim()),
+        Endpoint::Likes { user_id: Some(user_id) } => format!("vote:3:{} order:vote", user_id.trim()),
+        Endpoint::Likes { user_id: None } => {
+            return Err(Error::InvalidEndpoint("Yandere likes without a user".to_string()))
+        }
+        Endpoint::Search { query } => query.clone(),
+        Endpoint::PostDetail { .. } => return Err(Error::InvalidEndpoint("Feed of a single post".to_string())),
+    };
+    Ok(YandereFeedParams::Search { query })
+}
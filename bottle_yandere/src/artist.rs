@@ -0,0 +1,110 @@
+use diesel::prelude::*;
+use itertools::Itertools;
+
+use bottle_core::{feed::UserView, Database, Error, Result};
+
+use crate::{model, util};
+
+/// Fetch the artist of the tag from yandere and save it, along with the other artists found by the name.
+/// Returns the canonical artist if the tag is an alias, with its URLs and aliases.
+pub async fn resolve_artist(db: Database<'_>, name: &str) -> Result<UserView> {
+    let artists = yandere_client::fetch_artists(name).await.map_err(anyhow::Error::from)?;
+    if !artists.iter().any(|artist| artist.name == name) {
+        return Err(Error::ObjectNotFound(format!("Yandere artist {}", name)));
+    }
+    save_artists(db, &artists)?;
+
+    let canonical = canonical_artist(db, name)?;
+    tracing::info!("Resolved yandere artist {} to {}", name, canonical);
+    let mut users = vec![util::artist_view(canonical)];
+    fill_artist_info(db, &mut users)?;
+    Ok(users.remove(0))
+}
+
+/// Save the artists, updating the existing ones.
+fn save_artists(db: Database, artists: &[yandere_client::ArtistResult]) -> Result<()> {
+    use bottle_core::schema::yandere_artist;
+
+    let artists = artists.iter().map(model::NewYandereArtist::from).collect::<Vec<_>>();
+    for artist in artists.iter() {
+        diesel::insert_into(yandere_artist::table)
+            .values(artist)
+            .on_conflict(yandere_artist::id)
+            .do_update()
+            .set(artist)
+            .execute(db)?;
+    }
+    Ok(())
+}
+
+/// Name of the artist the tag is an alias of, or the tag itself if it's not an alias or not resolved yet.
+pub fn canonical_artist(db: Database, name: &str) -> Result<String> {
+    use bottle_core::schema::yandere_artist;
+
+    let alias_id = yandere_artist::table
+        .filter(yandere_artist::name.eq(name))
+        .select(yandere_artist::alias_id)
+        .first::<Option<i64>>(db)
+        .optional()?
+        .flatten();
+    let Some(alias_id) = alias_id else {
+        return Ok(name.to_string());
+    };
+    let canonical = yandere_artist::table
+        .find(alias_id)
+        .select(yandere_artist::name)
+        .first::<String>(db)
+        .optional()?;
+    Ok(canonical.unwrap_or(name.to_string()))
+}
+
+/// Tags of the artist and its aliases, to find the posts of the artist under any of its names.
+pub(crate) fn artist_names(db: Database, name: &str) -> Result<Vec<String>> {
+    use bottle_core::schema::yandere_artist;
+
+    let mut names = vec![name.to_string()];
+    let artist_id = yandere_artist::table
+        .filter(yandere_artist::name.eq(name))
+        .select(yandere_artist::id)
+        .first::<i64>(db)
+        .optional()?;
+    if let Some(artist_id) = artist_id {
+        let aliases = yandere_artist::table
+            .filter(yandere_artist::alias_id.eq(artist_id))
+            .select(yandere_artist::name)
+            .load::<String>(db)?;
+        names.extend(aliases);
+    }
+    Ok(names)
+}
+
+/// Fill the URLs and aliases of the resolved artists.
+pub(crate) fn fill_artist_info(db: Database, users: &mut [UserView]) -> Result<()> {
+    use bottle_core::schema::yandere_artist;
+
+    let names = users.iter().map(|user| user.user_id.clone()).collect::<Vec<_>>();
+    let artists = yandere_artist::table
+        .filter(yandere_artist::name.eq_any(&names))
+        .load::<model::YandereArtist>(db)?;
+    if artists.is_empty() {
+        return Ok(());
+    }
+    let artist_ids = artists.iter().map(|artist| artist.id).collect::<Vec<_>>();
+    let aliases = yandere_artist::table
+        .filter(yandere_artist::alias_id.eq_any(&artist_ids))
+        .select((yandere_artist::alias_id.assume_not_null(), yandere_artist::name))
+        .load::<(i64, String)>(db)?
+        .into_iter()
+        .into_group_map();
+
+    for user in users.iter_mut() {
+        let Some(artist) = artists.iter().find(|artist| artist.name == user.user_id) else {
+            continue;
+        };
+        let urls = serde_json::from_str::<Vec<String>>(&artist.urls)?;
+        user.url = urls.first().cloned();
+        user.links = Some(urls);
+        user.aliases = aliases.get(&artist.id).cloned();
+    }
+    Ok(())
+}
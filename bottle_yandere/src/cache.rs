@@ -1,26 +1,48 @@
-use std::collections::HashMap;
+use std::time::Duration;
 
+use bottle_core::cache::{BoundedCache, CacheStats};
 use yandere_client::{PoolPostResult, PoolResult, PostResult, TagType};
 
 #[derive(Debug, Clone, Default)]
 pub struct YandereCache {
     /// post_id -> PostResult
-    pub(crate) posts: HashMap<u64, PostResult>,
+    pub(crate) posts: BoundedCache<u64, PostResult>,
     /// pool_id -> PoolResult
-    pub(crate) pools: HashMap<u64, PoolResult>,
+    pub(crate) pools: BoundedCache<u64, PoolResult>,
     /// post_id -> PoolPostResults
-    pub(crate) post_pools: HashMap<u64, Vec<PoolPostResult>>,
+    pub(crate) post_pools: BoundedCache<u64, Vec<PoolPostResult>>,
     /// tag -> TagType
-    pub(crate) tags: HashMap<String, TagType>,
+    pub(crate) tags: BoundedCache<String, TagType>,
 }
 
 impl YandereCache {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `capacity` entities of each kind, each for `ttl` at most.
+    pub fn with_limits(capacity: usize, ttl: Duration) -> Self {
         Self {
-            posts: HashMap::new(),
-            pools: HashMap::new(),
-            post_pools: HashMap::new(),
-            tags: HashMap::new(),
+            posts: BoundedCache::new(capacity, ttl),
+            pools: BoundedCache::new(capacity, ttl),
+            post_pools: BoundedCache::new(capacity, ttl),
+            tags: BoundedCache::new(capacity, ttl),
         }
     }
+
+    pub fn stats(&self) -> Vec<CacheStats> {
+        vec![
+            self.posts.stats("posts"),
+            self.pools.stats("pools"),
+            self.post_pools.stats("post_pools"),
+            self.tags.stats("tags"),
+        ]
+    }
+
+    pub fn clear(&mut self) {
+        self.posts.clear();
+        self.pools.clear();
+        self.post_pools.clear();
+        self.tags.clear();
+    }
 }
@@ -99,6 +99,10 @@ impl Account for YandereAccount {
         unimplemented!()
     }
 
+    fn parse_credential(_value: &serde_json::Value, _path: &str) -> Result<Self::Credential> {
+        unimplemented!()
+    }
+
     async fn fetch(_credential: &Self::Credential) -> Result<Self::InfoResponse> {
         unimplemented!()
     }
@@ -186,3 +190,31 @@ pub struct YanderePostExtra {
     pub has_children: bool,
     pub parent_id: Option<i64>,
 }
+
+pub fn get_entities(db: Database, post_ids: impl IntoIterator<Item = i64>) -> Result<GeneralResponse> {
+    use bottle_core::schema::yandere_post;
+
+    let posts = yandere_post::table
+        .filter(yandere_post::id.eq_any(post_ids))
+        .select(yandere_post::all_columns)
+        .load::<model::YanderePost>(db)?;
+
+    Ok(GeneralResponse {
+        posts: Some(posts.iter().map(PostView::from).collect()),
+        media: Some(posts.iter().map(MediaView::from).collect()),
+        ..Default::default()
+    })
+}
+
+/// IDs of the saved posts greater than `after_id` in ascending order, to go through all posts in batches.
+pub fn get_post_ids(db: Database, after_id: i64, count: i64) -> Result<Vec<i64>> {
+    use bottle_core::schema::yandere_post;
+
+    let post_ids = yandere_post::table
+        .filter(yandere_post::id.gt(after_id))
+        .order_by(yandere_post::id.asc())
+        .select(yandere_post::id)
+        .limit(count)
+        .load::<i64>(db)?;
+    Ok(post_ids)
+}
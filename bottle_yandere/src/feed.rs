@@ -1,16 +1,28 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use diesel::{dsl::sql_query, prelude::*, sql_types::Integer};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
-use bottle_core::{feed::*, Database, Result};
+use bottle_core::{feed::*, library::WorkFilter, mute::UserMute, Database, Result};
 use yandere_client::APIResult;
 
 use crate::community::YandereAccount;
-use crate::{group, model, util};
+use crate::{artist, group, model, util};
+
+/// Columns of posts for ordering them in feeds and the library.
+const POST_COLUMNS: PostColumns = PostColumns {
+    community: "yandere",
+    table: "yandere_post",
+    post_id: "yandere_post.id",
+    created_date: "yandere_post.created_date",
+    media_count: "1",
+    expunged: None,
+};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum YandereFeedParams {
     Search { query: String },
@@ -20,6 +32,8 @@ pub enum YandereFeedParams {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YandereFetchContext {
     pub(crate) page: u32,
+    /// ETag of the first page in the last update, to check if the feed has changed.
+    pub(crate) etag: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +44,17 @@ pub struct YandereFeed {
     pub first_fetch_limit: Option<i32>,
     pub params: YandereFeedParams,
     pub reached_end: bool,
+    pub icon_url: Option<String>,
+    /// Accent color in `#rrggbb`.
+    pub color: Option<String>,
+    /// Collage of the recent works in the library, relative to the image directory.
+    pub cover_path: Option<String>,
+    /// Error of the last update, if it failed.
+    pub last_error: Option<String>,
+    /// Number of consecutive failed updates.
+    pub failure_count: i32,
+    /// Minimum resolution of the largest media of the posts saved, on its longer side.
+    pub min_resolution: Option<i32>,
 }
 
 #[async_trait]
@@ -45,12 +70,20 @@ impl Feed for YandereFeed {
         vec![
             FeedMetadata {
                 name: "search".to_string(),
-                scheme: Scheme::Object(HashMap::from([("query".to_string(), Scheme::String)])),
+                label: "Search".to_string(),
+                scheme: Scheme::Object(HashMap::from([(
+                    "query".to_string(),
+                    Scheme::field("Tags", Scheme::String).with_default(json!("")),
+                )])),
                 need_auth: false,
             },
             FeedMetadata {
                 name: "pool".to_string(),
-                scheme: Scheme::Object(HashMap::from([("pool_id".to_string(), Scheme::Int)])),
+                label: "Pool".to_string(),
+                scheme: Scheme::Object(HashMap::from([(
+                    "pool_id".to_string(),
+                    Scheme::field("Pool ID", Scheme::Int),
+                )])),
                 need_auth: false,
             },
         ]
@@ -62,6 +95,13 @@ impl Feed for YandereFeed {
             community: "yandere".to_string(),
             name: self.name.clone(),
             watching: self.watching,
+            icon_url: self.icon_url.clone(),
+            color: self.color.clone(),
+            cover_path: self.cover_path.clone(),
+            last_error: self.last_error.clone(),
+            failure_count: self.failure_count,
+            require_media: false,
+            min_resolution: self.min_resolution,
             description: match &self.params {
                 YandereFeedParams::Search { query } => format!("Search {}", query),
                 YandereFeedParams::Pool { pool_id } => format!("Pool {}", pool_id),
@@ -115,6 +155,9 @@ impl Feed for YandereFeed {
             kind: params.kind_str().to_string(),
             search_query: params.search_query(),
             pool_id: params.pool_id(),
+            icon_url: info.icon_url.clone(),
+            color: info.color.clone(),
+            min_resolution: info.min_resolution,
         };
         let result = diesel::insert_into(yandere_watch_list::table)
             .values(&new_watch_list)
@@ -129,6 +172,9 @@ impl Feed for YandereFeed {
             name: info.name.clone(),
             watching: info.watching,
             first_fetch_limit: info.first_fetch_limit,
+            icon_url: info.icon_change(),
+            color: info.color_change(),
+            min_resolution: info.min_resolution,
         };
         diesel::update(yandere_watch_list::table.find(self.id))
             .set(&update)
@@ -136,6 +182,13 @@ impl Feed for YandereFeed {
         self.name = info.name.clone();
         self.watching = info.watching;
         self.first_fetch_limit = info.first_fetch_limit;
+        if let Some(icon_url) = info.icon_change() {
+            self.icon_url = icon_url;
+        }
+        if let Some(color) = info.color_change() {
+            self.color = color;
+        }
+        self.min_resolution = info.min_resolution;
         tracing::info!("Updated yandere feed {}: {:?}", self.id, info);
         Ok(self.view())
     }
@@ -146,12 +199,23 @@ impl Feed for YandereFeed {
             yandere_watch_list_history, yandere_watch_list_post,
         };
 
+        // (0) If the first page is not modified since the last update, there is nothing new
+        if fetched.not_modified {
+            tracing::info!("Yandere feed {} not modified", self.id);
+            return Ok(SaveResult {
+                post_ids: vec![],
+                should_stop: true,
+                reached_end: self.reached_end,
+            });
+        }
+
         // (a) If no posts are fetched, mark the feed as reached end
         if fetched.posts.is_empty() {
             diesel::update(yandere_watch_list::table)
                 .filter(yandere_watch_list::id.eq(self.id))
                 .set(yandere_watch_list::reached_end.eq(true))
                 .execute(db)?;
+            self.save_etag(db, fetched)?;
             tracing::info!("Set yandere feed {} as reached end", self.id);
             return Ok(SaveResult {
                 post_ids: vec![],
@@ -172,6 +236,7 @@ impl Feed for YandereFeed {
 
         // (b) If no posts are new, stop
         if posts.clone().count() == 0 {
+            self.save_etag(db, fetched)?;
             return Ok(SaveResult {
                 post_ids: vec![],
                 should_stop: true,
@@ -179,6 +244,12 @@ impl Feed for YandereFeed {
             });
         }
 
+        // Skip posts of muted artists, and small posts
+        let muted_user_ids = UserMute::user_ids(db, "yandere")?;
+        let posts = posts
+            .filter(|post| !util::artists(fetched, post).any(|artist| muted_user_ids.contains(artist)))
+            .filter(|post| meets_min_resolution(self.min_resolution, [(post.width, post.height)]));
+
         // 2. Prepare data to insert
         // Post, Tag, PostTag
         let new_posts = posts.clone().map(model::NewYanderePost::from).collect::<Vec<_>>();
@@ -196,6 +267,7 @@ impl Feed for YandereFeed {
                 watch_list_id: self.id,
                 post_id: post.id as i64,
                 sort_index: None,
+                dismissed: false,
             })
             .collect::<Vec<_>>();
 
@@ -226,6 +298,12 @@ impl Feed for YandereFeed {
             diesel::insert_into(yandere_watch_list_history::table)
                 .values(&history)
                 .execute(conn)?;
+            self.save_etag(conn, fetched)?;
+
+            // Link posts of files already archived from other communities, instead of archiving them again
+            for post in posts.clone() {
+                bottle_library::link_duplicate_post(conn, "yandere", &post.id.to_string(), &post.md5)?;
+            }
             Ok(())
         })?;
 
@@ -240,25 +318,13 @@ impl Feed for YandereFeed {
     }
 
     fn handle_before_update(&self, db: Database) -> Result<()> {
-        // Delete watch list posts that don't have sort index
-        use bottle_core::schema::yandere_watch_list_post;
         use itertools::Itertools;
 
-        let post_ids = yandere_watch_list_post::table
-            .filter(yandere_watch_list_post::watch_list_id.eq(self.id))
-            .filter(yandere_watch_list_post::sort_index.is_null())
-            .select(yandere_watch_list_post::post_id)
-            .load::<i64>(db)?;
+        // Delete watch list posts that don't have sort index
+        let post_ids = bottle_library::delete_unsorted_watch_list_posts(db, "yandere", Some(self.id))?;
         if post_ids.is_empty() {
             return Ok(());
         }
-
-        diesel::delete(
-            yandere_watch_list_post::table
-                .filter(yandere_watch_list_post::watch_list_id.eq(self.id))
-                .filter(yandere_watch_list_post::sort_index.is_null()),
-        )
-        .execute(db)?;
         tracing::info!(
             "Deleted {} posts without sort index for yandere feed {}: {}",
             post_ids.len(),
@@ -308,15 +374,28 @@ impl Feed for YandereFeed {
         Ok(())
     }
 
-    fn posts(&self, db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn posts(
+        &self,
+        db: Database,
+        filter: &PostFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{yandere_post, yandere_watch_list_post};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{dsl::sql, sql_types::Bool};
 
         // 1. Fetch posts
         let (posts, total_items) = yandere_watch_list_post::table
             .inner_join(yandere_post::table)
             .filter(yandere_watch_list_post::watch_list_id.eq(self.id))
-            .order(yandere_watch_list_post::sort_index.desc())
+            .filter(sql::<Bool>(
+                &filter.sql_condition(&POST_COLUMNS, "yandere_watch_list_post"),
+            ))
+            .order(sql::<Integer>(
+                &order.feed_sql(&POST_COLUMNS, "yandere_watch_list_post.sort_index"),
+            ))
             .select(yandere_post::all_columns)
             .paginate(page, page_size)
             .load_and_count::<model::YanderePost>(db)?;
@@ -335,42 +414,75 @@ impl Feed for YandereFeed {
             users: Some(users),
             works: Some(works),
             images: Some(images),
+            notes: None,
             total_items,
             page,
             page_size,
         })
     }
 
+    fn set_dismissed(&self, db: Database, post_ids: &[i64], dismissed: bool) -> Result<usize> {
+        use bottle_core::schema::yandere_watch_list_post;
+
+        let count = diesel::update(yandere_watch_list_post::table)
+            .filter(yandere_watch_list_post::watch_list_id.eq(self.id))
+            .filter(yandere_watch_list_post::post_id.eq_any(post_ids))
+            .set(yandere_watch_list_post::dismissed.eq(dismissed))
+            .execute(db)?;
+        Ok(count)
+    }
+
     fn get_account(&self, _db: Database) -> Result<Self::Account> {
         unimplemented!()
     }
 
-    fn get_fetch_context(&self, _db: Database) -> Result<Self::FetchContext> {
-        Ok(YandereFetchContext { page: 1 })
+    fn get_fetch_context(&self, db: Database) -> Result<Self::FetchContext> {
+        use bottle_core::schema::yandere_watch_list;
+        let etag = yandere_watch_list::table
+            .find(self.id)
+            .select(yandere_watch_list::etag)
+            .first::<Option<String>>(db)
+            .optional()?
+            .flatten();
+        Ok(YandereFetchContext { page: 1, etag })
     }
 
     async fn fetch(&self, ctx: &mut Self::FetchContext, _auth: Option<&Self::Auth>) -> Result<Self::FetchResult> {
-        let result = match self.params {
-            YandereFeedParams::Search { ref query } => yandere_client::fetch_posts(query, ctx.page).await,
+        // Only a feed fetched to the end can stop at an unchanged first page
+        let etag = ctx.etag.as_deref().filter(|_| self.reached_end && ctx.page == 1);
+        let mut result = match self.params {
+            YandereFeedParams::Search { ref query } => yandere_client::fetch_posts(query, ctx.page, etag).await,
             YandereFeedParams::Pool { pool_id } => {
-                yandere_client::fetch_posts(&format!("pool:{}", pool_id), ctx.page).await
+                yandere_client::fetch_posts(&format!("pool:{}", pool_id), ctx.page, etag).await
             }
         }
         .map_err(anyhow::Error::from)?;
+        // Only the ETag of the first page tells if the feed has changed
+        if ctx.page != 1 {
+            result.etag = None;
+        }
         ctx.page += 1;
         Ok(result)
     }
 
-    fn archived_posts(db: Database, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn archived_posts(
+        db: Database,
+        filter: &WorkFilter,
+        order: PostOrder,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::library::{ImageView, WorkView};
         use bottle_core::schema::{image, work, yandere_post};
         use bottle_library::model::{Image, Work};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{dsl::sql, sql_types::Bool};
 
         // 1. Fetch works
         let (works, total_items) = work::table
             .filter(work::source.eq("yandere"))
-            .order(work::added_date.desc())
+            .filter(sql::<Bool>(&filter.sql_condition()))
+            .order(sql::<Integer>(&order.work_sql(&POST_COLUMNS)))
             .paginate(page, page_size)
             .load_and_count::<Work>(db)?;
 
@@ -399,6 +511,7 @@ impl Feed for YandereFeed {
             users: Some(users),
             works: Some(works.into_iter().map(WorkView::from).collect()),
             images: Some(images.into_iter().map(ImageView::from).collect()),
+            notes: None,
             total_items,
             page,
             page_size,
@@ -407,30 +520,43 @@ impl Feed for YandereFeed {
 
     fn archived_posts_grouped_by_user(
         db: Database,
+        filter: &WorkFilter,
         page: i64,
         page_size: i64,
         recent_count: i64,
     ) -> Result<GeneralResponse> {
         use diesel::dsl::sql_query;
         let query = sql_query(group::grouped_by_user_query(
-            "select distinct yandere_post.* from yandere_post
-            join work on yandere_post.id = work.post_id_int
-            where work.source = 'yandere'",
+            &format!(
+                "select distinct yandere_post.* from yandere_post
+                join work on yandere_post.id = work.post_id_int
+                where work.source = 'yandere' and {}",
+                filter.sql_condition()
+            ),
             "order by created_date desc",
         ))
         .into_boxed();
         group::posts_grouped_by_user(db, query, page, page_size, recent_count)
     }
 
-    fn archived_posts_by_user(db: Database, user_id: String, page: i64, page_size: i64) -> Result<GeneralResponse> {
+    fn archived_posts_by_user(
+        db: Database,
+        user_id: String,
+        filter: &WorkFilter,
+        page: i64,
+        page_size: i64,
+    ) -> Result<GeneralResponse> {
         use bottle_core::schema::{work, yandere_post, yandere_post_tag, yandere_tag};
         use bottle_util::diesel_ext::Paginate;
+        use diesel::{dsl::sql, sql_types::Bool};
 
+        let names = artist::artist_names(db, &user_id)?;
         let results = yandere_post::table
             .inner_join(yandere_post_tag::table.inner_join(yandere_tag::table))
             .inner_join(work::table.on(work::post_id_int.eq(yandere_post::id.nullable())))
-            .filter(yandere_tag::name.eq(&user_id).and(yandere_tag::type_.eq("artist")))
+            .filter(yandere_tag::name.eq_any(&names).and(yandere_tag::type_.eq("artist")))
             .filter(work::source.eq("yandere"))
+            .filter(sql::<Bool>(&filter.sql_condition()))
             .order(yandere_post::created_date.desc())
             .select(yandere_post::all_columns)
             .distinct()
@@ -461,20 +587,47 @@ impl Feed for YandereFeed {
         use bottle_core::schema::{yandere_post, yandere_post_tag, yandere_tag, yandere_watch_list_post};
         use bottle_util::diesel_ext::Paginate;
 
+        let names = artist::artist_names(db, &user_id)?;
         let results = yandere_watch_list_post::table
             .inner_join(yandere_post::table.inner_join(yandere_post_tag::table.inner_join(yandere_tag::table)))
             .filter(yandere_watch_list_post::watch_list_id.eq(self.id))
-            .filter(yandere_tag::name.eq(&user_id).and(yandere_tag::type_.eq("artist")))
+            .filter(yandere_tag::name.eq_any(&names).and(yandere_tag::type_.eq("artist")))
             .order(yandere_watch_list_post::sort_index.desc())
             .select(yandere_post::all_columns)
+            .distinct()
             .paginate(page, page_size)
             .load_and_count::<model::YanderePost>(db)?;
         group::posts_by_user(db, results, user_id, page, page_size)
     }
+
+    fn new_post_count(&self, db: Database, since: DateTime<Utc>) -> Result<i64> {
+        use bottle_core::schema::{yandere_post, yandere_watch_list_post};
+
+        let count = yandere_watch_list_post::table
+            .inner_join(yandere_post::table)
+            .filter(yandere_watch_list_post::watch_list_id.eq(self.id))
+            .filter(yandere_post::added_date.gt(since.naive_utc()))
+            .count()
+            .get_result::<i64>(db)?;
+        Ok(count)
+    }
 }
 
 // MARK: Helpers
 
+impl YandereFeed {
+    /// Store the ETag of the first page once its posts are saved.
+    fn save_etag(&self, db: Database, fetched: &APIResult) -> Result<()> {
+        use bottle_core::schema::yandere_watch_list;
+        if let Some(etag) = &fetched.etag {
+            diesel::update(yandere_watch_list::table.find(self.id))
+                .set(yandere_watch_list::etag.eq(etag))
+                .execute(db)?;
+        }
+        Ok(())
+    }
+}
+
 impl YandereFeedParams {
     fn kind_str(&self) -> &str {
         match self {
@@ -483,6 +636,15 @@ impl YandereFeedParams {
         }
     }
 
+    /// Errors of the fields in the params, by paths like `pool.pool_id`.
+    /// An empty search query is valid, searching all posts.
+    pub fn validate(&self) -> Vec<FieldError> {
+        match self.pool_id().filter(|id| *id <= 0) {
+            Some(pool_id) => vec![FieldError::new("pool.pool_id", format!("Invalid pool ID {}", pool_id))],
+            None => vec![],
+        }
+    }
+
     fn search_query(&self) -> Option<String> {
         match self {
             YandereFeedParams::Search { query } => Some(query.clone()),
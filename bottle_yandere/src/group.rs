@@ -12,7 +12,7 @@ use bottle_core::{
     Database, Result,
 };
 
-use crate::{model, util};
+use crate::{artist, model, util};
 
 // MARK: Internal methods for grouping posts by artist
 
@@ -30,15 +30,18 @@ struct RecentRow {
 }
 
 /// Generate query for artist-grouped recent post, with given source post query.
+/// Aliases of resolved artists are grouped under their canonical artists.
 /// Binds are `page_size`, `offset` and `recent_count`.
 pub(crate) fn grouped_by_user_query(post_query: &str, window_order_clause: &str) -> String {
     format!(
         "with posts as materialized (
-                select *, yandere_tag.name from (
+                select distinct yandere_post.*, coalesce(canonical_artist.name, yandere_tag.name) as name from (
                     {}
                 ) yandere_post
                 join yandere_post_tag on yandere_post.id = yandere_post_tag.post_id
                 join yandere_tag on yandere_post_tag.tag_name = yandere_tag.name
+                left join yandere_artist alias_artist on yandere_tag.name = alias_artist.name
+                left join yandere_artist canonical_artist on alias_artist.alias_id = canonical_artist.id
                 where yandere_tag.type = 'artist'
             ), artists as materialized (
                 select *, count() over () as artist_count from (
@@ -94,12 +97,13 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         .unique()
         .map(util::artist_view)
         .collect::<Vec<_>>();
+    artist::fill_artist_info(db, &mut users)?;
     // Add post_count field to users
     for user in &mut users {
         user.post_count = artist_to_post_count.get(&user.user_id).cloned();
     }
     // Sort users by post_count
-    users.sort_by(|a, b| b.post_count.cmp(&a.post_count));
+    users.sort_by_key(|u| std::cmp::Reverse(u.post_count));
 
     // 3. Fetch associated posts
     let posts = yandere_post::table
@@ -129,6 +133,7 @@ pub(crate) fn posts_grouped_by_user<Q: QueryFragment<Sqlite>>(
         media: Some(media),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items: artist_count,
         page,
         page_size,
@@ -144,6 +149,8 @@ pub(crate) fn posts_by_user(
     page_size: i64,
 ) -> Result<GeneralResponse> {
     let (posts, total_items) = results;
+    let mut users = vec![util::artist_view(user_id)];
+    artist::fill_artist_info(db, &mut users)?;
 
     // Fetch associated works
     let post_ids = posts.iter().map(|p| p.id.to_string());
@@ -151,10 +158,11 @@ pub(crate) fn posts_by_user(
 
     Ok(GeneralResponse {
         posts: Some(posts.iter().map(PostView::from).collect()),
-        users: Some(vec![util::artist_view(user_id)]),
+        users: Some(users),
         media: Some(posts.iter().map(MediaView::from).collect()),
         works: Some(works),
         images: Some(images),
+        notes: None,
         total_items,
         page,
         page_size,
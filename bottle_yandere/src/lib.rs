@@ -1,4 +1,5 @@
 pub mod api;
+mod artist;
 mod cache;
 mod community;
 mod feed;
@@ -6,6 +7,7 @@ mod group;
 mod model;
 mod util;
 
+pub use artist::*;
 pub use cache::*;
 pub use community::*;
 pub use feed::*;
@@ -103,6 +103,13 @@ pub struct YandereWatchList {
     pub search_query: Option<String>,
     pub pool_id: Option<i32>,
     pub reached_end: bool,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub cover_path: Option<String>,
+    pub etag: Option<String>,
+    pub last_error: Option<String>,
+    pub failure_count: i32,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -115,6 +122,9 @@ pub struct NewYandereWatchList {
     pub kind: String,
     pub search_query: Option<String>,
     pub pool_id: Option<i32>,
+    pub icon_url: Option<String>,
+    pub color: Option<String>,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(AsChangeset, Debug, Clone)]
@@ -124,6 +134,9 @@ pub struct YandereWatchListUpdate {
     pub name: Option<String>,
     pub watching: bool,
     pub first_fetch_limit: Option<i32>,
+    pub icon_url: Option<Option<String>>,
+    pub color: Option<Option<String>>,
+    pub min_resolution: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Identifiable, Associations, Debug, Clone)]
@@ -156,4 +169,31 @@ pub struct YandereWatchListPost {
     pub watch_list_id: i32,
     pub post_id: i64,
     pub sort_index: Option<i32>,
+    pub dismissed: bool,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = yandere_artist)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct YandereArtist {
+    pub id: i64,
+    pub name: String,
+    pub alias_id: Option<i64>,
+    pub group_id: Option<i64>,
+    /// JSON array of the URLs.
+    pub urls: String,
+    pub updated_date: NaiveDateTime,
+}
+
+#[derive(Insertable, AsChangeset, Debug, Clone)]
+#[diesel(table_name = yandere_artist)]
+#[diesel(treat_none_as_null = true)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct NewYandereArtist {
+    pub id: i64,
+    pub name: String,
+    pub alias_id: Option<i64>,
+    pub group_id: Option<i64>,
+    pub urls: String,
+    pub updated_date: NaiveDateTime,
 }
@@ -2,13 +2,14 @@ use diesel::prelude::*;
 
 use bottle_core::{
     feed::{MediaView, PostView, UserView},
-    library::{RemoteImage, RemoteWork},
+    library::{ContentRating, RemoteImage, RemoteWork},
     Database, Error, Result,
 };
 use yandere_client::{self as client};
 
 use crate::model;
 use crate::{
+    artist,
     community::YanderePostExtra,
     feed::{YandereFeed, YandereFeedParams},
 };
@@ -33,7 +34,8 @@ pub(crate) fn get_artist_views(db: Database, post_ids: impl Iterator<Item = i64>
         .select(yandere_tag::name)
         .distinct()
         .load::<String>(db)?;
-    let views = names.into_iter().map(artist_view).collect();
+    let mut views = names.into_iter().map(artist_view).collect::<Vec<_>>();
+    artist::fill_artist_info(db, &mut views)?;
     Ok(views)
 }
 
@@ -60,6 +62,19 @@ impl From<&client::PostResult> for model::NewYanderePost {
     }
 }
 
+impl From<&client::ArtistResult> for model::NewYandereArtist {
+    fn from(artist: &client::ArtistResult) -> Self {
+        model::NewYandereArtist {
+            id: artist.id as i64,
+            name: artist.name.clone(),
+            alias_id: artist.alias_id.map(|v| v as i64),
+            group_id: artist.group_id.map(|v| v as i64),
+            urls: serde_json::to_string(&artist.urls).unwrap_or_default(),
+            updated_date: chrono::Utc::now().naive_utc(),
+        }
+    }
+}
+
 pub(crate) fn post_extra_result(post: &client::PostResult) -> YanderePostExtra {
     YanderePostExtra {
         creator_id: post.creator_id.map(|v| v as i64),
@@ -131,6 +146,12 @@ impl TryFrom<model::YandereWatchList> for YandereFeed {
             name: watch_list.name,
             watching: watch_list.watching,
             first_fetch_limit: watch_list.first_fetch_limit,
+            icon_url: watch_list.icon_url,
+            color: watch_list.color,
+            cover_path: watch_list.cover_path,
+            last_error: watch_list.last_error,
+            failure_count: watch_list.failure_count,
+            min_resolution: watch_list.min_resolution,
             params,
             reached_end: watch_list.reached_end,
         })
@@ -149,6 +170,18 @@ pub(crate) fn post_extra(post: &model::YanderePost) -> YanderePostExtra {
     }
 }
 
+impl model::YanderePost {
+    /// Map the rating `s`, `q` and `e` to the content rating.
+    pub(crate) fn content_rating(&self) -> Option<ContentRating> {
+        match self.rating.as_str() {
+            "s" => Some(ContentRating::General),
+            "q" => Some(ContentRating::Sensitive),
+            "e" => Some(ContentRating::Explicit),
+            _ => None,
+        }
+    }
+}
+
 impl From<&model::YanderePost> for PostView {
     fn from(post: &model::YanderePost) -> PostView {
         PostView {
@@ -200,6 +233,7 @@ impl TryFrom<model::YanderePost> for RemoteWork {
             media_count: 1,
             images: vec![image],
             page_index: Some(0),
+            content_rating: post.content_rating(),
             ..Default::default()
         })
     }
@@ -216,6 +250,16 @@ pub(crate) fn tags(result: &client::APIResult) -> Vec<model::YandereTag> {
         .collect::<Vec<_>>()
 }
 
+/// Artist tags of the post, by the tag types in the result.
+pub(crate) fn artists<'a>(
+    result: &'a client::APIResult,
+    post: &'a client::PostResult,
+) -> impl Iterator<Item = &'a str> + 'a {
+    post.tags
+        .split_whitespace()
+        .filter(|tag| matches!(result.tags.get(*tag), Some(client::TagType::Artist)))
+}
+
 pub(crate) fn pools(result: &client::APIResult) -> Vec<model::YanderePool> {
     use itertools::Itertools;
     result
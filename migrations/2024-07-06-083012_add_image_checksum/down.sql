@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS index_image_checksum;
+
+ALTER TABLE image DROP COLUMN checksum;
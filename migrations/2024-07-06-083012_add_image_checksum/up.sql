@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE image ADD COLUMN checksum TEXT;
+
+CREATE INDEX IF NOT EXISTS index_image_checksum ON image(checksum);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX IF EXISTS index_work_content_rating;
+
+ALTER TABLE work DROP COLUMN content_rating;
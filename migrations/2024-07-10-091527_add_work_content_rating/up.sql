@@ -0,0 +1,36 @@
+-- Your SQL goes here
+ALTER TABLE work ADD COLUMN content_rating TEXT;
+
+CREATE INDEX IF NOT EXISTS index_work_content_rating ON work(content_rating);
+
+-- Fill the content rating of existing works from their posts, as done when saving new works
+UPDATE work SET content_rating = (
+    SELECT CASE yandere_post.rating
+        WHEN 's' THEN 'general'
+        WHEN 'q' THEN 'sensitive'
+        WHEN 'e' THEN 'explicit'
+    END
+    FROM yandere_post WHERE yandere_post.id = work.post_id_int
+)
+WHERE source = 'yandere';
+
+UPDATE work SET content_rating = (
+    SELECT CASE
+        WHEN pixiv_illust.sanity_level >= 6 THEN 'explicit'
+        WHEN pixiv_illust.sanity_level >= 4 THEN 'sensitive'
+        ELSE 'general'
+    END
+    FROM pixiv_illust WHERE pixiv_illust.id = work.post_id_int
+)
+WHERE source = 'pixiv';
+
+-- Categories: 8 is Non-H; 0, 5 and 6 are Misc, Image Set and Cosplay; unknown ones are read as Misc
+UPDATE work SET content_rating = (
+    SELECT CASE
+        WHEN panda_gallery.category = 8 THEN 'general'
+        WHEN panda_gallery.category IN (0, 5, 6) OR panda_gallery.category NOT BETWEEN 0 AND 9 THEN 'sensitive'
+        ELSE 'explicit'
+    END
+    FROM panda_gallery WHERE panda_gallery.id = work.post_id_int
+)
+WHERE source = 'panda';
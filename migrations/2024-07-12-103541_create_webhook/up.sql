@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE webhook(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL DEFAULT 'generic',
+    community TEXT NOT NULL,
+    feed_id INTEGER,
+    user_id TEXT,
+    url TEXT NOT NULL,
+    chat_id TEXT,
+    bot_token TEXT,
+    template TEXT,
+    added_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS index_webhook_community_feed_id ON webhook(community, feed_id);
+CREATE INDEX IF NOT EXISTS index_webhook_community_user_id ON webhook(community, user_id);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE digest(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    email TEXT NOT NULL,
+    frequency TEXT NOT NULL,
+    last_sent_date DATETIME,
+    last_disk_usage BIGINT,
+    added_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
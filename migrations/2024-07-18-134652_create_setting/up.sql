@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE setting(
+    key TEXT NOT NULL PRIMARY KEY,
+    value TEXT NOT NULL,
+    modified_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE account_request(
+    community TEXT NOT NULL,
+    account_id INTEGER NOT NULL,
+    day DATE NOT NULL,
+    request_count INTEGER NOT NULL DEFAULT 0,
+    rate_limit_count INTEGER NOT NULL DEFAULT 0,
+    error_count INTEGER NOT NULL DEFAULT 0,
+    last_success_date DATETIME,
+    PRIMARY KEY (community, account_id, day)
+);
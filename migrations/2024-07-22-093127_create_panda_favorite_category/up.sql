@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE panda_favorite_category(
+    account_id INTEGER NOT NULL REFERENCES panda_account(id) ON DELETE CASCADE,
+    category_index INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    gallery_count INTEGER NOT NULL,
+    updated_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (account_id, category_index)
+);
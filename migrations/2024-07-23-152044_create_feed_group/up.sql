@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE feed_group(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    added_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    modified_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE feed_group_feed(
+    community TEXT NOT NULL,
+    feed_id INTEGER NOT NULL,
+    group_id INTEGER NOT NULL REFERENCES feed_group(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (community, feed_id)
+);
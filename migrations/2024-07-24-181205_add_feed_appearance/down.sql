@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE twitter_watch_list DROP COLUMN icon_url;
+ALTER TABLE twitter_watch_list DROP COLUMN color;
+ALTER TABLE pixiv_watch_list DROP COLUMN icon_url;
+ALTER TABLE pixiv_watch_list DROP COLUMN color;
+ALTER TABLE yandere_watch_list DROP COLUMN icon_url;
+ALTER TABLE yandere_watch_list DROP COLUMN color;
+ALTER TABLE panda_watch_list DROP COLUMN icon_url;
+ALTER TABLE panda_watch_list DROP COLUMN color;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+ALTER TABLE twitter_watch_list ADD COLUMN icon_url TEXT;
+ALTER TABLE twitter_watch_list ADD COLUMN color TEXT;
+ALTER TABLE pixiv_watch_list ADD COLUMN icon_url TEXT;
+ALTER TABLE pixiv_watch_list ADD COLUMN color TEXT;
+ALTER TABLE yandere_watch_list ADD COLUMN icon_url TEXT;
+ALTER TABLE yandere_watch_list ADD COLUMN color TEXT;
+ALTER TABLE panda_watch_list ADD COLUMN icon_url TEXT;
+ALTER TABLE panda_watch_list ADD COLUMN color TEXT;
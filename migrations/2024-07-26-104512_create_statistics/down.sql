@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE feed_stat;
+DROP TABLE tag_stat;
+DROP TABLE artist_stat;
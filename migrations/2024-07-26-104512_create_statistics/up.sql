@@ -0,0 +1,26 @@
+-- Your SQL goes here
+CREATE TABLE artist_stat(
+    community TEXT NOT NULL,
+    artist TEXT NOT NULL,
+    artist_name TEXT,
+    work_count INTEGER NOT NULL,
+    image_count INTEGER NOT NULL,
+    last_added_date DATETIME NOT NULL,
+    PRIMARY KEY (community, artist)
+);
+
+CREATE TABLE tag_stat(
+    community TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    month TEXT NOT NULL,
+    work_count INTEGER NOT NULL,
+    PRIMARY KEY (community, tag, month)
+);
+
+CREATE TABLE feed_stat(
+    community TEXT NOT NULL,
+    feed_id INTEGER NOT NULL,
+    day DATE NOT NULL,
+    post_count INTEGER NOT NULL,
+    PRIMARY KEY (community, feed_id, day)
+);
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE album DROP COLUMN cover_path;
+ALTER TABLE twitter_watch_list DROP COLUMN cover_path;
+ALTER TABLE pixiv_watch_list DROP COLUMN cover_path;
+ALTER TABLE yandere_watch_list DROP COLUMN cover_path;
+ALTER TABLE panda_watch_list DROP COLUMN cover_path;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE album ADD COLUMN cover_path TEXT;
+ALTER TABLE twitter_watch_list ADD COLUMN cover_path TEXT;
+ALTER TABLE pixiv_watch_list ADD COLUMN cover_path TEXT;
+ALTER TABLE yandere_watch_list ADD COLUMN cover_path TEXT;
+ALTER TABLE panda_watch_list ADD COLUMN cover_path TEXT;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE work ADD COLUMN last_read_page INTEGER;
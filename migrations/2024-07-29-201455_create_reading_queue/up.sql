@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE reading_queue(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    community TEXT NOT NULL,
+    post_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    added_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (community, post_id)
+);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE remote_status(
+    work_id INTEGER NOT NULL PRIMARY KEY REFERENCES work(id) ON DELETE CASCADE,
+    status_code INTEGER NOT NULL,
+    checked_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    vanished_date DATETIME
+);
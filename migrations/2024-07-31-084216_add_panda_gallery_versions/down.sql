@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE panda_gallery DROP COLUMN invisible_reason;
+ALTER TABLE panda_gallery DROP COLUMN newer_gallery_id;
+ALTER TABLE panda_gallery DROP COLUMN newer_token;
+ALTER TABLE panda_gallery DROP COLUMN checked_date;
+ALTER TABLE work DROP COLUMN previous_work_id;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE panda_gallery ADD COLUMN invisible_reason TEXT;
+ALTER TABLE panda_gallery ADD COLUMN newer_gallery_id BIGINT;
+ALTER TABLE panda_gallery ADD COLUMN newer_token TEXT;
+ALTER TABLE panda_gallery ADD COLUMN checked_date DATETIME;
+ALTER TABLE work ADD COLUMN previous_work_id INTEGER REFERENCES work(id) ON DELETE SET NULL;
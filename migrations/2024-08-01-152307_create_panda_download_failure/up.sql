@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE panda_download_failure(
+    gallery_id BIGINT NOT NULL REFERENCES panda_gallery(id) ON DELETE CASCADE,
+    media_index INTEGER NOT NULL,
+    error TEXT NOT NULL,
+    failed_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (gallery_id, media_index)
+);
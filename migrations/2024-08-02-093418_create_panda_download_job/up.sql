@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE panda_download_job(
+    gallery_id BIGINT NOT NULL PRIMARY KEY REFERENCES panda_gallery(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    state TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    success INTEGER NOT NULL,
+    error TEXT,
+    finished_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE image ADD COLUMN is_animated BOOLEAN NOT NULL DEFAULT FALSE;
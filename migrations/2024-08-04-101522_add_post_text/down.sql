@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE tweet DROP COLUMN text;
+ALTER TABLE pixiv_illust DROP COLUMN caption_text;
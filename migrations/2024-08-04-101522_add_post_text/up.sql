@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE tweet ADD COLUMN text TEXT;
+ALTER TABLE pixiv_illust ADD COLUMN caption_text TEXT;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE twitter_tag(
+    tweet_id BIGINT NOT NULL REFERENCES tweet(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (tweet_id, tag) ON CONFLICT IGNORE
+);
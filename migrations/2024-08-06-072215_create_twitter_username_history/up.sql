@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE twitter_username_history(
+    user_id BIGINT NOT NULL REFERENCES twitter_user(id) ON DELETE CASCADE,
+    username TEXT NOT NULL,
+    name TEXT NOT NULL,
+    first_seen_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, username) ON CONFLICT IGNORE
+);
+
+INSERT INTO twitter_username_history (user_id, username, name, first_seen_date)
+SELECT id, username, name, added_date FROM twitter_user;
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE booru_upload(
+    booru TEXT NOT NULL,
+    image_id INTEGER NOT NULL REFERENCES image(id) ON DELETE CASCADE,
+    remote_id TEXT NOT NULL,
+    uploaded_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (booru, image_id)
+);
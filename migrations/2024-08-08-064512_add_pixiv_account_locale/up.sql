@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE pixiv_account ADD COLUMN locale TEXT;
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE yandere_watch_list DROP COLUMN etag;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE yandere_watch_list ADD COLUMN etag TEXT;
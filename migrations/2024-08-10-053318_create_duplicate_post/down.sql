@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE duplicate_post;
+DROP INDEX IF EXISTS index_image_md5;
+ALTER TABLE image DROP COLUMN md5;
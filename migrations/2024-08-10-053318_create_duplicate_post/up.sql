@@ -0,0 +1,11 @@
+-- Your SQL goes here
+ALTER TABLE image ADD COLUMN md5 TEXT;
+CREATE INDEX IF NOT EXISTS index_image_md5 ON image(md5);
+
+CREATE TABLE duplicate_post(
+    community TEXT NOT NULL,
+    post_id TEXT NOT NULL,
+    work_id INTEGER NOT NULL REFERENCES work(id) ON DELETE CASCADE,
+    linked_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (community, post_id)
+);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE twitter_watch_list_tweet DROP COLUMN dismissed;
+ALTER TABLE pixiv_watch_list_illust DROP COLUMN dismissed;
+ALTER TABLE yandere_watch_list_post DROP COLUMN dismissed;
+ALTER TABLE panda_watch_list_gallery DROP COLUMN dismissed;
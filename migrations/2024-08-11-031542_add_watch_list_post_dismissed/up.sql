@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE twitter_watch_list_tweet ADD COLUMN dismissed BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE pixiv_watch_list_illust ADD COLUMN dismissed BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE yandere_watch_list_post ADD COLUMN dismissed BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE panda_watch_list_gallery ADD COLUMN dismissed BOOLEAN NOT NULL DEFAULT 0;
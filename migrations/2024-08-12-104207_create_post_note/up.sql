@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE post_note(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    /* Note of either a post given by the community and post ID, or a work in the library. */
+    community TEXT,
+    post_id TEXT,
+    work_id INTEGER REFERENCES work(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    added_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    modified_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS index_post_note_community_post_id ON post_note(community, post_id);
+CREATE INDEX IF NOT EXISTS index_post_note_work_id ON post_note(work_id);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE work_user_tag;
+DROP TABLE user_tag;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE user_tag(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    added_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE work_user_tag(
+    work_id INTEGER NOT NULL REFERENCES work(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES user_tag(id) ON DELETE CASCADE,
+    added_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (work_id, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS index_work_user_tag_tag_id ON work_user_tag(tag_id);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE panda_watch_list ADD COLUMN mirror_album_id INTEGER REFERENCES album(id) ON DELETE SET NULL;
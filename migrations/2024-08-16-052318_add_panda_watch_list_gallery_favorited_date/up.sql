@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE panda_watch_list_gallery ADD COLUMN favorited_date DATETIME;
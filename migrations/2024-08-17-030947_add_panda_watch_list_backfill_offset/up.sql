@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE panda_watch_list ADD COLUMN backfill_offset TEXT;
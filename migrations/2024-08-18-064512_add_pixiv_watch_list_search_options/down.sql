@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE pixiv_watch_list DROP COLUMN search_duration;
+ALTER TABLE pixiv_watch_list DROP COLUMN search_target;
+ALTER TABLE pixiv_watch_list DROP COLUMN search_sort;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE pixiv_watch_list ADD COLUMN search_sort TEXT;
+ALTER TABLE pixiv_watch_list ADD COLUMN search_target TEXT;
+ALTER TABLE pixiv_watch_list ADD COLUMN search_duration TEXT;
@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE twitter_watch_list DROP COLUMN failure_count;
+ALTER TABLE twitter_watch_list DROP COLUMN last_error;
+ALTER TABLE pixiv_watch_list DROP COLUMN failure_count;
+ALTER TABLE pixiv_watch_list DROP COLUMN last_error;
+ALTER TABLE yandere_watch_list DROP COLUMN failure_count;
+ALTER TABLE yandere_watch_list DROP COLUMN last_error;
+ALTER TABLE panda_watch_list DROP COLUMN failure_count;
+ALTER TABLE panda_watch_list DROP COLUMN last_error;
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+ALTER TABLE twitter_watch_list ADD COLUMN last_error TEXT;
+ALTER TABLE twitter_watch_list ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE pixiv_watch_list ADD COLUMN last_error TEXT;
+ALTER TABLE pixiv_watch_list ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE yandere_watch_list ADD COLUMN last_error TEXT;
+ALTER TABLE yandere_watch_list ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE panda_watch_list ADD COLUMN last_error TEXT;
+ALTER TABLE panda_watch_list ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE panda_gallery DROP COLUMN disowned;
+ALTER TABLE panda_gallery DROP COLUMN expunged;
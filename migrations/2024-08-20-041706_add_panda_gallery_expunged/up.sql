@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE panda_gallery ADD COLUMN expunged BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE panda_gallery ADD COLUMN disowned BOOLEAN NOT NULL DEFAULT 0;
+UPDATE panda_gallery SET expunged = 1 WHERE invisible_reason = 'Expunged';
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE panda_download_failure DROP COLUMN failure_count;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE panda_download_failure ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 1;
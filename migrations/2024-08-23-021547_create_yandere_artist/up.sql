@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE yandere_artist(
+    id BIGINT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    alias_id BIGINT,
+    group_id BIGINT,
+    urls TEXT NOT NULL,
+    updated_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS index_yandere_artist_name ON yandere_artist(name);
+CREATE INDEX IF NOT EXISTS index_yandere_artist_alias_id ON yandere_artist(alias_id);
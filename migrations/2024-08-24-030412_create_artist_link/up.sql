@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE artist_link(
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    community TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    linked_community TEXT NOT NULL,
+    linked_user_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'suggested',
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (community, user_id, linked_community, linked_user_id) ON CONFLICT IGNORE
+);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE pixiv_user DROP COLUMN profile_updated_date;
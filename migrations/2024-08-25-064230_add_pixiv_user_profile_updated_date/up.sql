@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE pixiv_user ADD COLUMN profile_updated_date DATETIME;
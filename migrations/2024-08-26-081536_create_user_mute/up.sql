@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE user_mute(
+    community TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    reason TEXT,
+    created_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (community, user_id)
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE twitter_watch_list ADD COLUMN retweets TEXT;
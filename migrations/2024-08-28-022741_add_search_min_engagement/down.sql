@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE twitter_watch_list DROP COLUMN search_min_likes;
+ALTER TABLE pixiv_watch_list DROP COLUMN search_min_bookmarks;
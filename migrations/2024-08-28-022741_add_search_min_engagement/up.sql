@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE twitter_watch_list ADD COLUMN search_min_likes INTEGER;
+ALTER TABLE pixiv_watch_list ADD COLUMN search_min_bookmarks INTEGER;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE twitter_watch_list DROP COLUMN min_resolution;
+ALTER TABLE pixiv_watch_list DROP COLUMN min_resolution;
+ALTER TABLE yandere_watch_list DROP COLUMN min_resolution;
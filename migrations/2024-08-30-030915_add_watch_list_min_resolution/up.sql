@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE twitter_watch_list ADD COLUMN min_resolution INTEGER;
+ALTER TABLE pixiv_watch_list ADD COLUMN min_resolution INTEGER;
+ALTER TABLE yandere_watch_list ADD COLUMN min_resolution INTEGER;
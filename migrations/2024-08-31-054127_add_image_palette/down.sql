@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE image DROP COLUMN palette_hues;
+ALTER TABLE image DROP COLUMN palette;
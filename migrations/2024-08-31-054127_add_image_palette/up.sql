@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE image ADD COLUMN palette TEXT;
+ALTER TABLE image ADD COLUMN palette_hues INTEGER;
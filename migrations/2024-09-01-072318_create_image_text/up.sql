@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE image_text(
+    image_id INTEGER NOT NULL PRIMARY KEY REFERENCES image(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    extracted_date DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE panda_gallery ADD COLUMN file_size_int INTEGER;
+UPDATE panda_gallery SET file_size_int = CASE WHEN file_size > 2147483647 THEN NULL ELSE file_size END;
+ALTER TABLE panda_gallery DROP COLUMN file_size;
+ALTER TABLE panda_gallery RENAME COLUMN file_size_int TO file_size;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE panda_gallery ADD COLUMN file_size_bytes BIGINT;
+/* Sizes of galleries over 2 GiB were stored wrapped into 32 bits. */
+UPDATE panda_gallery SET file_size_bytes = CASE WHEN file_size < 0 THEN file_size + 4294967296 ELSE file_size END;
+ALTER TABLE panda_gallery DROP COLUMN file_size;
+ALTER TABLE panda_gallery RENAME COLUMN file_size_bytes TO file_size;
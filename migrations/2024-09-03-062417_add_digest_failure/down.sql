@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE digest DROP COLUMN failure_count;
+ALTER TABLE digest DROP COLUMN last_failed_date;
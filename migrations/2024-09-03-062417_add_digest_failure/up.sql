@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE digest ADD COLUMN last_failed_date DATETIME;
+ALTER TABLE digest ADD COLUMN failure_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,94 @@
+//! Parse a saved HTML page with the panda parsers, to quickly check and fix parser breakage
+//! when the site changes its markup. Pages can be saved by setting `CLIENT_LOG_DIR` on the server.
+//! Selectors are overridden by the file at `PANDA_SELECTORS_FILE` if set, the same as the client.
+//!
+//! Usage: `cargo run --bin panda-parse -- <list|gallery|image> <file.html>`
+
+use std::{env, fmt::Debug, fs, process};
+
+use scraper::Html;
+
+use panda_client::{
+    parse_ban, parse_gallery_list, parse_gallery_page, parse_image_page,
+    selectors::{self, PandaSelector},
+    Error,
+};
+
+/// Length of the HTML of the first matched element to print.
+const SNIPPET_LENGTH: usize = 200;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let [kind, path] = args.as_slice() else {
+        eprintln!("Usage: panda-parse <list|gallery|image> <file.html>");
+        process::exit(2);
+    };
+    let html = match fs::read_to_string(path) {
+        Ok(html) => html,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", path, e);
+            process::exit(2);
+        }
+    };
+    if let Ok(path) = env::var("PANDA_SELECTORS_FILE") {
+        if let Err(e) = selectors::load_overrides(&path) {
+            eprintln!("Cannot load selectors from {}: {}", path, e);
+            process::exit(2);
+        }
+    }
+    let doc = Html::parse_document(&html);
+    if let Some(ban) = parse_ban(&doc) {
+        eprintln!("The page is a ban notice: {}", ban);
+        process::exit(1);
+    }
+
+    let ok = match kind.as_str() {
+        "list" => report(&doc, parse_gallery_list(&doc), selectors::list::ALL),
+        "gallery" => report(&doc, parse_gallery_page(&doc), selectors::gallery::ALL),
+        "image" => report(&doc, parse_image_page(&doc), selectors::image::ALL),
+        _ => {
+            eprintln!("Unknown page kind {}, expected list, gallery or image", kind);
+            process::exit(2);
+        }
+    };
+    if !ok {
+        process::exit(1);
+    }
+}
+
+/// Print the parsed result, or the error with the number of matches of each selector of the page.
+fn report<T: Debug>(doc: &Html, result: Result<T, Error>, selectors: &[&PandaSelector]) -> bool {
+    let error = match result {
+        Ok(result) => {
+            println!("{:#?}", result);
+            return true;
+        }
+        Err(e) => e,
+    };
+    println!("Error: {}", error);
+
+    // Field names in the errors correspond to selector names, e.g. `posted date` to `POSTED_DATE`
+    let failed_field = match &error {
+        Error::InvalidHTML(field) => Some(field.to_uppercase().replace(' ', "_")),
+        _ => None,
+    };
+    println!("Selectors:");
+    for selector in selectors {
+        let mut matches = doc.select(selector);
+        let first = matches.next();
+        let count = first.map_or(0, |_| matches.count() + 1);
+        let failed = failed_field.as_deref() == Some(selector.name);
+        println!(
+            "{} {:<24} {:>4} matches  {}",
+            if failed { ">" } else { " " },
+            selector.name,
+            count,
+            selector.css()
+        );
+        if let (true, Some(first)) = (failed, first) {
+            let snippet = first.html().chars().take(SNIPPET_LENGTH).collect::<String>();
+            println!("    First match: {}", snippet);
+        }
+    }
+    false
+}
@@ -6,8 +6,12 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error("Rate Limit: {0}")]
     RateLimit(String),
+    #[error("Invalid cookie: {0}")]
+    InvalidCookie(String),
     #[error("Invalid HTML: {0}")]
     InvalidHTML(String),
+    #[error("Invalid selector: {0}")]
+    InvalidSelector(String),
     #[error("IO Error: {0}")]
     IOError(#[from] std::io::Error),
     #[error("Network Error: {0}")]
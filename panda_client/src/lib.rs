@@ -2,39 +2,59 @@ mod consts;
 mod error;
 mod parsing;
 mod result;
-mod selectors;
+pub mod selectors;
 
-use reqwest::{header, Client, Url};
+use reqwest::{header, Url};
 use scraper::Html;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-use bottle_util::{build_params, parsing::parse_query_str};
+use bottle_util::{
+    build_params,
+    http::{client_builder, RequestOutcome, ResponseHook},
+    parsing::{parse_cookie_str, parse_query_str},
+};
 
 use crate::consts::*;
 pub use crate::error::Error;
 use crate::error::Result;
-use crate::parsing::*;
+pub use crate::parsing::{parse_ban, parse_gallery_list, parse_gallery_page, parse_image_page};
 pub use crate::result::*;
 
 #[derive(Debug, Clone)]
 pub struct PandaClient {
     pub cookie: PandaCookie,
     client: reqwest::Client,
+    response_hook: Option<ResponseHook>,
 }
 
 impl PandaClient {
+    /// Selectors are overridden by the TOML file at `PANDA_SELECTORS_FILE` if set, reloaded on each construction.
     pub fn new(cookie: PandaCookie) -> Result<Self> {
+        if let Ok(path) = std::env::var("PANDA_SELECTORS_FILE") {
+            selectors::load_overrides(path)?;
+        }
+
         let cookie_string = cookie.to_string();
         let mut headers = header::HeaderMap::new();
         headers.insert(header::COOKIE, header::HeaderValue::from_str(&cookie_string).unwrap());
 
-        let client = Client::builder().default_headers(headers).build()?;
+        let client = client_builder().default_headers(headers).build()?;
 
-        Ok(PandaClient { cookie, client })
+        Ok(PandaClient {
+            cookie,
+            client,
+            response_hook: None,
+        })
+    }
+
+    /// Report the outcome of each request to the hook.
+    pub fn with_response_hook(mut self, hook: ResponseHook) -> Self {
+        self.response_hook = Some(hook);
+        self
     }
 
     pub async fn search(&self, option: &SearchOption, offset: Option<&GalleryListOffset>) -> Result<GalleryListResult> {
@@ -79,6 +99,19 @@ impl PandaClient {
 
 impl PandaClient {
     async fn fetch(&self, path: &str, query: impl IntoIterator<Item = (String, String)>) -> Result<Html> {
+        let result = self.fetch_page(path, query).await;
+        if let Some(hook) = &self.response_hook {
+            hook.call(match &result {
+                Ok(_) => RequestOutcome::Success,
+                Err(Error::RateLimit(_)) => RequestOutcome::RateLimited,
+                Err(Error::NetworkError(e)) => RequestOutcome::of_error(e),
+                Err(_) => RequestOutcome::Failed,
+            });
+        }
+        result
+    }
+
+    async fn fetch_page(&self, path: &str, query: impl IntoIterator<Item = (String, String)>) -> Result<Html> {
         let mut url = Url::parse(BASE_URL)?;
         url.set_path(path);
         url.query_pairs_mut().extend_pairs(query);
@@ -110,27 +143,76 @@ async fn log(path: &str, content: &str) -> Result<()> {
     Ok(())
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PandaCookie {
-    pub content: String,
+    pub ipb_member_id: String,
+    pub ipb_pass_hash: String,
+    /// Required to access ExHentai, missing in the cookies of some accounts added before it was validated.
+    pub igneous: Option<String>,
+    /// Key of the site settings profile, using the default profile if not given.
+    pub sk: Option<String>,
+    /// Other cookies kept as they are, sorted by name.
+    pub others: Vec<(String, String)>,
+}
+
+impl PandaCookie {
+    /// Parse the stored cookies of an account leniently, not requiring `igneous` and keeping the unknown cookies.
+    pub fn from_stored(s: &str) -> Result<Self> {
+        let mut cookie_map = parse_cookie_str(s)?;
+        let mut take = |name: &str| cookie_map.remove(name).filter(|value| !value.is_empty());
+        let ipb_member_id = take("ipb_member_id").ok_or(Error::InvalidCookie(s.to_string()))?;
+        let ipb_pass_hash = take("ipb_pass_hash").ok_or(Error::InvalidCookie(s.to_string()))?;
+        let igneous = take("igneous");
+        let sk = take("sk");
+        // `sl` is always overridden when sending the cookies
+        cookie_map.remove("sl");
+        let mut others = cookie_map.into_iter().collect::<Vec<_>>();
+        others.sort();
+        Ok(PandaCookie {
+            ipb_member_id,
+            ipb_pass_hash,
+            igneous,
+            sk,
+            others,
+        })
+    }
 }
 
 impl Display for PandaCookie {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ipb_member_id={}; ipb_pass_hash={}",
+            self.ipb_member_id, self.ipb_pass_hash
+        )?;
+        if let Some(igneous) = &self.igneous {
+            write!(f, "; igneous={}", igneous)?;
+        }
+        if let Some(sk) = &self.sk {
+            write!(f, "; sk={}", sk)?;
+        }
+        for (name, value) in self.others.iter() {
+            write!(f, "; {}={}", name, value)?;
+        }
         // Set `sl=dm_2` to force extended page view!
-        write!(f, "{}; sl=dm_2", self.content)
+        write!(f, "; sl=dm_2")
     }
 }
 
 impl FromStr for PandaCookie {
     type Err = Error;
 
+    /// Parse new cookies from a cookie header string, requiring `igneous`.
     fn from_str(s: &str) -> Result<Self> {
-        Ok(PandaCookie { content: s.to_string() })
+        let cookie = Self::from_stored(s)?;
+        if cookie.igneous.is_none() {
+            return Err(Error::InvalidCookie(s.to_string()));
+        }
+        Ok(cookie)
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchOption {
     pub keyword: Option<String>,
     pub categories: Vec<GalleryCategory>,
@@ -263,7 +345,7 @@ impl Display for SearchOption {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FavoriteSearchOption {
     pub keyword: Option<String>,
     pub category_index: Option<u32>,
@@ -335,6 +417,31 @@ impl GalleryListOffset {
             GalleryListOffset::NewerThan(id) => build_params! { required prev => id },
             GalleryListOffset::OlderThan(id) => build_params! { required next => id },
             GalleryListOffset::Percentage(percent) => build_params! { required range => percent },
+            GalleryListOffset::Seek(date) => build_params! { required seek => date },
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_cookie() {
+        let stored = "ipb_member_id=1; ipb_pass_hash=abc; yay=louder; sl=dm_2";
+        let cookie = PandaCookie::from_stored(stored).unwrap();
+        assert_eq!(cookie.igneous, None);
+        assert_eq!(cookie.others, vec![("yay".to_string(), "louder".to_string())]);
+        assert_eq!(
+            cookie.to_string(),
+            "ipb_member_id=1; ipb_pass_hash=abc; yay=louder; sl=dm_2"
+        );
+        assert!(stored.parse::<PandaCookie>().is_err());
+
+        let cookie = "ipb_member_id=1; ipb_pass_hash=abc; igneous=xyz"
+            .parse::<PandaCookie>()
+            .unwrap();
+        assert_eq!(cookie.igneous.as_deref(), Some("xyz"));
+        assert!(PandaCookie::from_stored("igneous=xyz").is_err());
+    }
+}
@@ -163,6 +163,12 @@ pub fn parse_gallery_list(doc: &Html) -> Result<GalleryListResult> {
             (text != "(Disowned)").then_some(text.to_string())
         }
 
+        fn parse_disowned(e: ElementRef) -> bool {
+            e.select(&UPLOADER_CELL)
+                .next()
+                .is_some_and(|e| e.text().any(|s| s.trim() == "(Disowned)"))
+        }
+
         fn parse_image_count(e: ElementRef) -> Option<u32> {
             let text = e.select(&IMAGE_COUNT).next()?.text().next()?.trim();
             text.split_whitespace().next()?.parse::<u32>().ok()
@@ -190,6 +196,8 @@ pub fn parse_gallery_list(doc: &Html) -> Result<GalleryListResult> {
         let favorited_category_name = parse_favorited_category_name(e);
         let rating = parse_coarse_rating(e).ok_or(Error::InvalidHTML("rating".to_string()))?;
         let uploader = parse_uploader(e);
+        let disowned = parse_disowned(e);
+        let expunged = e.select(&EXPUNGED).next().is_some();
         let image_count = parse_image_count(e).ok_or(Error::InvalidHTML("image count".to_string()))?;
         let favorited_date = parse_favorited_date(e);
 
@@ -210,6 +218,8 @@ pub fn parse_gallery_list(doc: &Html) -> Result<GalleryListResult> {
             favorited_date,
             favorited_category_index,
             favorited_category_name,
+            expunged,
+            disowned,
         })
     }
 
@@ -274,6 +284,12 @@ pub fn parse_gallery_page(doc: &Html) -> Result<GalleryPageResult> {
         (text != "(Disowned)").then_some(text.to_string())
     }
 
+    fn parse_disowned(doc: &Html) -> bool {
+        doc.select(&UPLOADER_CELL)
+            .next()
+            .is_some_and(|e| e.text().any(|s| s.trim() == "(Disowned)"))
+    }
+
     fn parse_posted_date(doc: &Html) -> Option<DateTime<Utc>> {
         let text = doc.select(&POSTED_DATE).next()?.text().next()?.trim();
         parse_date(text)
@@ -289,6 +305,24 @@ pub fn parse_gallery_page(doc: &Html) -> Result<GalleryPageResult> {
         Some(text == "Yes")
     }
 
+    /// Reason in the parentheses of an invisible gallery, e.g. `Expunged` in `No (Expunged)`.
+    fn parse_invisible_reason(doc: &Html) -> Option<String> {
+        let text = doc.select(&VISIBLE).next()?.text().next()?.trim();
+        let reason = text.strip_prefix("No")?.trim().strip_prefix('(')?.strip_suffix(')')?;
+        Some(reason.to_string())
+    }
+
+    /// Newer versions of the gallery, oldest first.
+    fn parse_newer_versions(doc: &Html) -> Vec<GalleryVersion> {
+        doc.select(&NEWER_VERSIONS)
+            .filter_map(|link| {
+                let (gid, token) = parse_gid_token(link.value().attr("href")?)?;
+                let title = link.text().collect::<String>().trim().to_string();
+                Some(GalleryVersion { gid, token, title })
+            })
+            .collect()
+    }
+
     fn parse_language(doc: &Html) -> Option<String> {
         let text = doc.select(&LANGUAGE).next()?.text().next()?.trim();
         Some(text.split_whitespace().next()?.to_string())
@@ -388,6 +422,19 @@ pub fn parse_gallery_page(doc: &Html) -> Result<GalleryPageResult> {
                 url,
                 thumbnail_url,
                 filename,
+                thumbnail_width: parse_style_px(style_str, "width"),
+                thumbnail_height: parse_style_px(style_str, "height"),
+            })
+        }
+
+        // Extract pixels of the property from style like `width:200px;height:283px;background:...`
+        fn parse_style_px(style: &str, property: &str) -> Option<u32> {
+            style.split(';').find_map(|declaration| {
+                let (name, value) = declaration.split_once(':')?;
+                if name.trim() != property {
+                    return None;
+                }
+                value.trim().strip_suffix("px")?.parse::<u32>().ok()
             })
         }
 
@@ -405,6 +452,8 @@ pub fn parse_gallery_page(doc: &Html) -> Result<GalleryPageResult> {
     let posted_date = parse_posted_date(doc).ok_or(Error::InvalidHTML("posted date".to_string()))?;
     let parent = parse_parent(doc);
     let visible = parse_visible(doc).ok_or(Error::InvalidHTML("visible".to_string()))?;
+    let invisible_reason = parse_invisible_reason(doc);
+    let newer_versions = parse_newer_versions(doc);
     let language = parse_language(doc).ok_or(Error::InvalidHTML("language".to_string()))?;
     let file_size = parse_file_size(doc).ok_or(Error::InvalidHTML("file size".to_string()))?;
     let image_count = parse_image_count(doc).ok_or(Error::InvalidHTML("image count".to_string()))?;
@@ -438,11 +487,15 @@ pub fn parse_gallery_page(doc: &Html) -> Result<GalleryPageResult> {
         favorited_date: None,
         favorited_category_index,
         favorited_category_name,
+        expunged: invisible_reason.as_deref() == Some("Expunged"),
+        disowned: parse_disowned(doc),
     };
     let detail = GalleryDetail {
         english_title,
         parent,
         visible,
+        invisible_reason,
+        newer_versions,
         language,
         file_size,
         favorited_count,
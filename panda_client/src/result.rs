@@ -22,6 +22,10 @@ pub struct Gallery {
     pub favorited_date: Option<DateTime<Utc>>,
     pub favorited_category_index: Option<u32>,
     pub favorited_category_name: Option<String>,
+    /// Whether the gallery is expunged, with its posted date struck through in lists.
+    pub expunged: bool,
+    /// Whether the uploader disowned the gallery, shown as `(Disowned)` in place of the uploader.
+    pub disowned: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -29,12 +33,23 @@ pub struct GalleryDetail {
     pub english_title: String,
     pub parent: Option<String>,
     pub visible: bool,
+    /// Why the gallery is invisible, e.g. `Expunged` or `Replaced`.
+    pub invisible_reason: Option<String>,
+    pub newer_versions: Vec<GalleryVersion>,
     pub language: String,
     pub file_size: u32,
     pub favorited_count: u32,
     pub rating_count: u32,
 }
 
+/// A newer version of a gallery, which replaces it.
+#[derive(Debug, Clone, Serialize)]
+pub struct GalleryVersion {
+    pub gid: u64,
+    pub token: String,
+    pub title: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct GalleryListResult {
     pub galleries: Vec<Gallery>,
@@ -78,6 +93,24 @@ pub struct ImagePreview {
     pub url: String,
     pub thumbnail_url: String,
     pub filename: String,
+    /// Size of the thumbnail, which keeps the aspect ratio of the image.
+    pub thumbnail_width: Option<u32>,
+    pub thumbnail_height: Option<u32>,
+}
+
+/// Width of images resampled by the site by default.
+const RESAMPLED_IMAGE_WIDTH: u32 = 1280;
+
+impl ImagePreview {
+    /// Approximate size of the image before fetching it, as the thumbnail scaled to the resampled width.
+    pub fn approximate_size(&self) -> Option<(u32, u32)> {
+        let (width, height) = (self.thumbnail_width?, self.thumbnail_height?);
+        if width == 0 {
+            return None;
+        }
+        let scaled_height = (height as f64 * RESAMPLED_IMAGE_WIDTH as f64 / width as f64).round() as u32;
+        Some((RESAMPLED_IMAGE_WIDTH, scaled_height))
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -238,4 +271,6 @@ pub enum GalleryListOffset {
     NewerThan(String),
     OlderThan(String),
     Percentage(u32),
+    /// Galleries posted before the date in `YYYY-MM-DD`, by the jump feature of the site.
+    Seek(String),
 }
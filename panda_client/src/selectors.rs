@@ -1,65 +1,177 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+use lazy_static::lazy_static;
+use scraper::Selector;
+
+use crate::error::{Error, Result};
+
+/// Define the selectors as statics, along with `ALL` listing them,
+/// which helps diagnose parsing errors when the site changes its markup.
+macro_rules! selectors {
+    (section = $section:literal; $($name:ident => $css:expr,)*) => {
+        use super::PandaSelector;
+
+        $(pub static $name: PandaSelector = PandaSelector::new($section, stringify!($name), $css);)*
+
+        pub static ALL: &[&PandaSelector] = &[$(&$name),*];
+    };
+}
+
+lazy_static! {
+    /// CSS and parsed selectors overriding the built-in ones, by `section.NAME`.
+    /// Parsed selectors are leaked to be borrowed as the statics, and reused while the CSS is unchanged.
+    static ref OVERRIDES: RwLock<HashMap<String, (String, &'static Selector)>> = RwLock::new(HashMap::new());
+}
+
+/// CSS selector parsed lazily from the built-in CSS, unless overridden by the selector file.
+pub struct PandaSelector {
+    pub section: &'static str,
+    pub name: &'static str,
+    default_css: &'static str,
+    default: OnceLock<Selector>,
+}
+
+impl PandaSelector {
+    const fn new(section: &'static str, name: &'static str, css: &'static str) -> Self {
+        PandaSelector {
+            section,
+            name,
+            default_css: css,
+            default: OnceLock::new(),
+        }
+    }
+
+    fn key(&self) -> String {
+        format!("{}.{}", self.section, self.name)
+    }
+
+    /// CSS of the selector in effect.
+    pub fn css(&self) -> String {
+        match OVERRIDES.read().unwrap().get(&self.key()) {
+            Some((css, _)) => css.clone(),
+            None => self.default_css.to_string(),
+        }
+    }
+}
+
+impl Deref for PandaSelector {
+    type Target = Selector;
+
+    fn deref(&self) -> &Selector {
+        if let Some((_, selector)) = OVERRIDES.read().unwrap().get(&self.key()) {
+            return selector;
+        }
+        self.default.get_or_init(|| Selector::parse(self.default_css).unwrap())
+    }
+}
+
+/// Load the selectors overriding the built-in ones from the TOML file, replacing the previous overrides.
+/// The file has a table for each section, `list`, `gallery` and `image`, mapping the selector names to CSS, e.g.
+/// ```toml
+/// [gallery]
+/// TITLE = "#gj"
+/// ```
+pub fn load_overrides(path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    let sections: HashMap<String, HashMap<String, String>> =
+        toml::from_str(&content).map_err(|e| Error::InvalidSelector(format!("{}: {}", path.display(), e)))?;
+
+    let mut overrides = OVERRIDES.write().unwrap();
+    let mut result = HashMap::new();
+    for (section, selectors) in sections {
+        let all = match section.as_str() {
+            "list" => list::ALL,
+            "gallery" => gallery::ALL,
+            "image" => image::ALL,
+            _ => return Err(Error::InvalidSelector(format!("unknown section {}", section))),
+        };
+        for (name, css) in selectors {
+            let name = name.to_uppercase();
+            if !all.iter().any(|selector| selector.name == name) {
+                return Err(Error::InvalidSelector(format!("unknown selector {}.{}", section, name)));
+            }
+            let key = format!("{}.{}", section, name);
+            let selector = match overrides.get(&key) {
+                Some((old_css, selector)) if *old_css == css => *selector,
+                _ => {
+                    let selector =
+                        Selector::parse(&css).map_err(|e| Error::InvalidSelector(format!("{} {}: {}", key, css, e)))?;
+                    &*Box::leak(Box::new(selector))
+                }
+            };
+            result.insert(key, (css, selector));
+        }
+    }
+    if !result.is_empty() {
+        tracing::debug!("Loaded {} selector overrides from {}", result.len(), path.display());
+    }
+    *overrides = result;
+    Ok(())
+}
+
 pub mod list {
-    use lazy_static::lazy_static;
-    use scraper::Selector;
-
-    lazy_static! {
-        pub static ref GALLERY: Selector = Selector::parse("table.itg > tbody > tr").unwrap();
-        pub static ref RESULT_STRING: Selector = Selector::parse("div.searchtext p").unwrap();
-        pub static ref LINK_TO_FIRST: Selector = Selector::parse("#ufirst").unwrap();
-        pub static ref LINK_TO_PREV: Selector = Selector::parse("#uprev").unwrap();
-        pub static ref LINK_TO_NEXT: Selector = Selector::parse("#unext").unwrap();
-        pub static ref LINK_TO_LAST: Selector = Selector::parse("#ulast").unwrap();
-        pub static ref URL: Selector = Selector::parse("td.gl1e a").unwrap();
-        pub static ref THUMBNAIL_URL: Selector = Selector::parse("td.gl1e img").unwrap();
-        pub static ref CATEGORY: Selector = Selector::parse("div.gl3e > div:first-child").unwrap();
-        pub static ref POSTED_DATE: Selector = Selector::parse("div.gl3e > div:nth-child(2)").unwrap();
-        pub static ref RATING: Selector = Selector::parse("div.gl3e > div:nth-child(3)").unwrap();
-        pub static ref UPLOADER: Selector = Selector::parse("div.gl3e > div:nth-child(4) > a").unwrap();
-        pub static ref IMAGE_COUNT: Selector = Selector::parse("div.gl3e > div:nth-child(5)").unwrap();
-        pub static ref FAVORITED_DATE: Selector =
-            Selector::parse("div.gl3e > div:nth-child(7) > p:nth-child(2)").unwrap();
-        pub static ref TAGS: Selector = Selector::parse("div.gl4e td div").unwrap();
-        pub static ref FAVORITE_CATEGORIES: Selector = Selector::parse("div.fp").unwrap();
+    selectors! {
+        section = "list";
+        GALLERY => "table.itg > tbody > tr",
+        RESULT_STRING => "div.searchtext p",
+        LINK_TO_FIRST => "#ufirst",
+        LINK_TO_PREV => "#uprev",
+        LINK_TO_NEXT => "#unext",
+        LINK_TO_LAST => "#ulast",
+        URL => "td.gl1e a",
+        THUMBNAIL_URL => "td.gl1e img",
+        CATEGORY => "div.gl3e > div:first-child",
+        POSTED_DATE => "div.gl3e > div:nth-child(2)",
+        RATING => "div.gl3e > div:nth-child(3)",
+        UPLOADER => "div.gl3e > div:nth-child(4) > a",
+        UPLOADER_CELL => "div.gl3e > div:nth-child(4)",
+        EXPUNGED => "div.gl3e > div:nth-child(2) > s",
+        IMAGE_COUNT => "div.gl3e > div:nth-child(5)",
+        FAVORITED_DATE => "div.gl3e > div:nth-child(7) > p:nth-child(2)",
+        TAGS => "div.gl4e td div",
+        FAVORITE_CATEGORIES => "div.fp",
     }
 }
 
 pub mod gallery {
-    use lazy_static::lazy_static;
-    use scraper::Selector;
-
-    lazy_static! {
-        pub static ref THUMBNAIL_URL: Selector = Selector::parse("#gd1 > div").unwrap();
-        pub static ref TITLE: Selector = Selector::parse("#gj").unwrap();
-        pub static ref ENGLISH_TITLE: Selector = Selector::parse("#gn").unwrap();
-        pub static ref CATEGORY: Selector = Selector::parse("#gdc > div").unwrap();
-        pub static ref UPLOADER: Selector = Selector::parse("#gdn > a").unwrap();
-        pub static ref POSTED_DATE: Selector = Selector::parse("#gdd tr:nth-child(1) > td:last-child").unwrap();
-        pub static ref PARENT: Selector = Selector::parse("#gdd tr:nth-child(2) > td:last-child").unwrap();
-        pub static ref VISIBLE: Selector = Selector::parse("#gdd tr:nth-child(3) > td:last-child").unwrap();
-        pub static ref LANGUAGE: Selector = Selector::parse("#gdd tr:nth-child(4) > td:last-child").unwrap();
-        pub static ref FILE_SIZE: Selector = Selector::parse("#gdd tr:nth-child(5) > td:last-child").unwrap();
-        pub static ref IMAGE_COUNT: Selector = Selector::parse("#gdd tr:nth-child(6) > td:last-child").unwrap();
-        pub static ref FAVORITED_COUNT: Selector = Selector::parse("#favcount").unwrap();
-        pub static ref RATING: Selector = Selector::parse("#rating_label").unwrap();
-        pub static ref RATING_COUNT: Selector = Selector::parse("#rating_count").unwrap();
-        pub static ref FAVORITE_CATEGORY: Selector = Selector::parse("#fav div.i").unwrap();
-        pub static ref TAGS: Selector = Selector::parse("#taglist a").unwrap();
-        pub static ref URL: Selector = Selector::parse("table.ptt td:nth-child(2) > a").unwrap();
-        pub static ref PREVIEW_PAGE_COUNT: Selector = Selector::parse("table.ptt td:nth-last-child(2) > a").unwrap();
-        pub static ref PREVIEWS: Selector = Selector::parse("#gdt > a").unwrap();
-        pub static ref PREVIEW_THUMBNAIL_URL: Selector = Selector::parse("div > div").unwrap();
+    selectors! {
+        section = "gallery";
+        THUMBNAIL_URL => "#gd1 > div",
+        TITLE => "#gj",
+        ENGLISH_TITLE => "#gn",
+        CATEGORY => "#gdc > div",
+        UPLOADER => "#gdn > a",
+        UPLOADER_CELL => "#gdn",
+        POSTED_DATE => "#gdd tr:nth-child(1) > td:last-child",
+        PARENT => "#gdd tr:nth-child(2) > td:last-child",
+        VISIBLE => "#gdd tr:nth-child(3) > td:last-child",
+        LANGUAGE => "#gdd tr:nth-child(4) > td:last-child",
+        FILE_SIZE => "#gdd tr:nth-child(5) > td:last-child",
+        IMAGE_COUNT => "#gdd tr:nth-child(6) > td:last-child",
+        FAVORITED_COUNT => "#favcount",
+        RATING => "#rating_label",
+        RATING_COUNT => "#rating_count",
+        FAVORITE_CATEGORY => "#fav div.i",
+        TAGS => "#taglist a",
+        URL => "table.ptt td:nth-child(2) > a",
+        PREVIEW_PAGE_COUNT => "table.ptt td:nth-last-child(2) > a",
+        PREVIEWS => "#gdt > a",
+        PREVIEW_THUMBNAIL_URL => "div > div",
+        NEWER_VERSIONS => "#gnd > a",
 
         // Comment
     }
 }
 
 pub mod image {
-    use lazy_static::lazy_static;
-    use scraper::Selector;
-
-    lazy_static! {
-        pub static ref PAGE_NUMBER: Selector = Selector::parse("#i2 span:first-child").unwrap();
-        pub static ref FILE_INFO: Selector = Selector::parse("#i2 > div:last-child").unwrap();
-        pub static ref URL: Selector = Selector::parse("#i3 img").unwrap();
+    selectors! {
+        section = "image";
+        PAGE_NUMBER => "#i2 span:first-child",
+        FILE_INFO => "#i2 > div:last-child",
+        URL => "#i3 img",
     }
 }
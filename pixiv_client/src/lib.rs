@@ -3,7 +3,7 @@ mod error;
 mod response;
 mod result;
 
-use reqwest::{header, Client, Url};
+use reqwest::{header, Url};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
@@ -11,7 +11,10 @@ use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-use bottle_util::build_params;
+use bottle_util::{
+    build_params,
+    http::{client_builder, RequestOutcome, ResponseHook},
+};
 
 use crate::consts::*;
 pub use crate::error::Error;
@@ -21,17 +24,39 @@ pub use crate::result::*;
 #[derive(Debug, Clone)]
 pub struct PixivClient {
     client: reqwest::Client,
+    response_hook: Option<ResponseHook>,
 }
 
 impl PixivClient {
     pub fn new(access_token: &str) -> Result<PixivClient> {
+        Self::with_locale(access_token, None)
+    }
+
+    /// Create a client requesting responses in the language, e.g. `en` or `zh-CN`,
+    /// which decides the language of tag translations.
+    pub fn with_locale(access_token: &str, locale: Option<&str>) -> Result<PixivClient> {
         let auth_str = format!("Bearer {}", access_token);
         let mut headers = header::HeaderMap::new();
         headers.insert(header::AUTHORIZATION, header::HeaderValue::from_str(&auth_str).unwrap());
+        if let Some(locale) = locale {
+            let value =
+                header::HeaderValue::from_str(locale).map_err(|_| Error::InvalidField(format!("Locale {}", locale)))?;
+            headers.insert(header::ACCEPT_LANGUAGE, value.clone());
+            headers.insert("App-Accept-Language", value);
+        }
+
+        let client = client_builder().default_headers(headers).build()?;
 
-        let client = Client::builder().default_headers(headers).build()?;
+        Ok(PixivClient {
+            client,
+            response_hook: None,
+        })
+    }
 
-        Ok(PixivClient { client })
+    /// Report the outcome of each request to the hook.
+    pub fn with_response_hook(mut self, hook: ResponseHook) -> Self {
+        self.response_hook = Some(hook);
+        self
     }
 
     pub async fn login(refresh_token: &str) -> Result<LoginResponse> {
@@ -50,7 +75,7 @@ impl PixivClient {
         form.insert("grant_type", "refresh_token");
         form.insert("refresh_token", refresh_token);
 
-        let client = Client::builder().default_headers(headers).build()?;
+        let client = client_builder().default_headers(headers).build()?;
         let response = client.post(LOGIN_URL).form(&form).send().await?.error_for_status()?;
         let content = response.text().await?;
 
@@ -243,8 +268,123 @@ impl FromStr for FollowingRestriction {
     }
 }
 
+/// Order of the search results.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum SearchSort {
+    #[default]
+    DateDesc,
+    DateAsc,
+    /// Only available to premium accounts.
+    PopularDesc,
+}
+
+impl Display for SearchSort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchSort::DateDesc => write!(f, "date_desc"),
+            SearchSort::DateAsc => write!(f, "date_asc"),
+            SearchSort::PopularDesc => write!(f, "popular_desc"),
+        }
+    }
+}
+
+impl FromStr for SearchSort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "date_desc" => Ok(SearchSort::DateDesc),
+            "date_asc" => Ok(SearchSort::DateAsc),
+            "popular_desc" => Ok(SearchSort::PopularDesc),
+            _ => Err(Error::InvalidField(s.to_string())),
+        }
+    }
+}
+
+/// How the search query matches the illusts.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub enum SearchTarget {
+    /// Tags partially matching the query.
+    #[default]
+    Partial,
+    /// Tags exactly matching the query.
+    Exact,
+    /// Title and caption containing the query.
+    Title,
+}
+
+impl Display for SearchTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchTarget::Partial => write!(f, "partial_match_for_tags"),
+            SearchTarget::Exact => write!(f, "exact_match_for_tags"),
+            SearchTarget::Title => write!(f, "title_and_caption"),
+        }
+    }
+}
+
+impl FromStr for SearchTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "partial_match_for_tags" => Ok(SearchTarget::Partial),
+            "exact_match_for_tags" => Ok(SearchTarget::Exact),
+            "title_and_caption" => Ok(SearchTarget::Title),
+            _ => Err(Error::InvalidField(s.to_string())),
+        }
+    }
+}
+
+/// Period of the search results before now.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum SearchDuration {
+    WithinLastDay,
+    WithinLastWeek,
+    WithinLastMonth,
+}
+
+impl Display for SearchDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchDuration::WithinLastDay => write!(f, "within_last_day"),
+            SearchDuration::WithinLastWeek => write!(f, "within_last_week"),
+            SearchDuration::WithinLastMonth => write!(f, "within_last_month"),
+        }
+    }
+}
+
+impl FromStr for SearchDuration {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "within_last_day" => Ok(SearchDuration::WithinLastDay),
+            "within_last_week" => Ok(SearchDuration::WithinLastWeek),
+            "within_last_month" => Ok(SearchDuration::WithinLastMonth),
+            _ => Err(Error::InvalidField(s.to_string())),
+        }
+    }
+}
+
 impl PixivClient {
     async fn get<T, I>(&self, path: &str, query: I) -> Result<T>
+    where
+        T: DeserializeOwned,
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let result = self.fetch(path, query).await;
+        if let Some(hook) = &self.response_hook {
+            hook.call(match &result {
+                Ok(_) => RequestOutcome::Success,
+                Err(Error::NetworkError(e)) => RequestOutcome::of_error(e),
+                Err(_) => RequestOutcome::Failed,
+            });
+        }
+        result
+    }
+
+    async fn fetch<T, I>(&self, path: &str, query: I) -> Result<T>
     where
         T: DeserializeOwned,
         I: IntoIterator<Item = (String, String)>,
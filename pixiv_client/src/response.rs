@@ -34,6 +34,9 @@ pub struct User {
     #[serde(rename = "account")]
     pub username: String,
     pub profile_image_urls: ProfileImageUrls,
+    /// Description of the user, only in the user detail.
+    #[serde(default)]
+    pub comment: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -99,4 +99,5 @@ pub const GRAPHQL_QIDS: phf::Map<&str, &str> = phf_map! {
     "HomeLatestTimeline" => "zhX91JE87mWvfprhYE97xA",
     "HomeTimeline" => "HCosKfLNW1AcOo3la3mMgg",
     "Bookmarks" => "tmd4ifV8RHltzn8ymGg1aw",
+    "ListByRestId" => "wXzyA5vM_aVkBL9G8Vp3kw",
 };
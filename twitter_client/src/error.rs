@@ -9,6 +9,8 @@ pub enum Error {
     InvalidGraphqlResponse,
     #[error("Invalid cookie: {0}")]
     InvalidCookie(String),
+    #[error("Invalid search query: {0}")]
+    InvalidQuery(String),
     #[error("Invalid endpoint: {0}")]
     InvalidEndpoint(String),
     #[error("Cannot encode/decode JSON: {0}")]
@@ -2,25 +2,31 @@ mod consts;
 mod error;
 mod response;
 mod result;
+mod search;
 #[cfg(test)]
 mod test;
 mod util;
 
-use reqwest::{header, Client, Response, Url};
+use reqwest::{header, Response, Url};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 use consts::*;
 use response::{AccountResponse, GraphqlResponse};
 pub use result::*;
+pub use search::*;
 
 pub use crate::error::Error;
 use crate::error::Result;
 
-use bottle_util::parse_cookie_str;
+use bottle_util::{
+    http::{client_builder, RequestOutcome, ResponseHook},
+    parse_cookie_str,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SessionCookie {
     pub ct0: String,
     pub auth_token: String,
@@ -35,8 +41,19 @@ impl Display for SessionCookie {
 impl FromStr for SessionCookie {
     type Err = Error;
 
+    /// Parse from a cookie header string, optionally prefixed with `Cookie:`,
+    /// or a Netscape `cookies.txt` export of the browser.
     fn from_str(s: &str) -> Result<Self> {
-        let mut cookie_map = parse_cookie_str(s)?;
+        let mut cookie_map = if s.contains('\t') {
+            parse_cookies_txt(s)
+        } else {
+            let s = s.trim();
+            let s = s
+                .get(..7)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("cookie:"))
+                .map_or(s, |_| &s[7..]);
+            parse_cookie_str(s)?
+        };
         let ct0 = cookie_map.remove("ct0").ok_or(Error::InvalidCookie(s.to_string()))?;
         let auth_token = cookie_map
             .remove("auth_token")
@@ -45,12 +62,33 @@ impl FromStr for SessionCookie {
     }
 }
 
+/// Parse cookies of Twitter domains from a Netscape `cookies.txt` export.
+/// Each line has tab-separated fields: domain, subdomain flag, path, secure flag, expiry, name and value.
+fn parse_cookies_txt(s: &str) -> HashMap<String, String> {
+    s.lines()
+        .map(|line| line.trim().strip_prefix("#HttpOnly_").unwrap_or(line.trim()))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            if fields.len() < 7 {
+                return None;
+            }
+            let domain = fields[0].trim_start_matches('.');
+            let is_twitter = ["twitter.com", "x.com"]
+                .iter()
+                .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)));
+            is_twitter.then(|| (fields[5].to_string(), fields[6].trim().to_string()))
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct TwitterClient {
     pub session_cookie: SessionCookie,
     client: reqwest::Client,
     default_variables: serde_json::Map<String, Value>,
     default_features: serde_json::Map<String, Value>,
+    response_hook: Option<ResponseHook>,
 }
 
 impl TwitterClient {
@@ -68,7 +106,7 @@ impl TwitterClient {
         headers.insert("x-twitter-client-language", header::HeaderValue::from_static("en"));
         headers.insert("x-twitter-auth-type", header::HeaderValue::from_static("OAuth2Session"));
 
-        let client = Client::builder()
+        let client = client_builder()
             .user_agent(USER_AGENT)
             .default_headers(headers)
             .build()?;
@@ -87,9 +125,16 @@ impl TwitterClient {
             client,
             default_variables,
             default_features,
+            response_hook: None,
         })
     }
 
+    /// Report the outcome of each request to the hook.
+    pub fn with_response_hook(mut self, hook: ResponseHook) -> Self {
+        self.response_hook = Some(hook);
+        self
+    }
+
     pub async fn accounts(&self) -> Result<Vec<Account>> {
         let response: AccountResponse = self.rest_get("/account/multi/list.json").await?;
         Ok(response.users)
@@ -103,6 +148,11 @@ impl TwitterClient {
         self.graphql_get("UsersByRestIds", [("userIds", user_ids)]).await
     }
 
+    pub async fn list_by_id(&self, list_id: u64) -> Result<List> {
+        self.graphql_get("ListByRestId", [("listId", list_id.to_string())])
+            .await
+    }
+
     pub async fn tweet_by_id(&self, tweet_id: u64) -> Result<Tweet> {
         self.graphql_get("TweetResultByRestId", [("tweetId", tweet_id)]).await
     }
@@ -152,9 +202,9 @@ impl TwitterClient {
         self.graphql_get("Following", variables).await
     }
 
-    pub async fn search(&self, query: &str, cursor: Option<&str>) -> Result<TimelineResult> {
+    pub async fn search(&self, option: &SearchOption, cursor: Option<&str>) -> Result<TimelineResult> {
         let mut variables: Vec<(&str, Value)> = [
-            ("rawQuery", query.into()),
+            ("rawQuery", option.query().into()),
             ("count", SEARCH_API_MAX_COUNT.into()),
             ("product", "Latest".into()),
             ("querySource", "typed_query".into()),
@@ -168,7 +218,26 @@ impl TwitterClient {
 }
 
 impl TwitterClient {
+    fn report<T>(&self, result: &Result<T>) {
+        if let Some(hook) = &self.response_hook {
+            hook.call(match result {
+                Ok(_) => RequestOutcome::Success,
+                Err(Error::NetworkError(e)) => RequestOutcome::of_error(e),
+                Err(_) => RequestOutcome::Failed,
+            });
+        }
+    }
+
     async fn rest_get<R>(&self, path: &str) -> Result<R>
+    where
+        R: serde::de::DeserializeOwned,
+    {
+        let result = self.fetch_rest(path).await;
+        self.report(&result);
+        result
+    }
+
+    async fn fetch_rest<R>(&self, path: &str) -> Result<R>
     where
         R: serde::de::DeserializeOwned,
     {
@@ -183,6 +252,17 @@ impl TwitterClient {
     }
 
     async fn graphql_get<I, V, R>(&self, endpoint: &str, variables: I) -> Result<R>
+    where
+        I: IntoIterator<Item = (&'static str, V)>,
+        V: Into<Value>,
+        R: TryFrom<GraphqlResponse, Error = Error>,
+    {
+        let result = self.fetch_graphql(endpoint, variables).await;
+        self.report(&result);
+        result
+    }
+
+    async fn fetch_graphql<I, V, R>(&self, endpoint: &str, variables: I) -> Result<R>
     where
         I: IntoIterator<Item = (&'static str, V)>,
         V: Into<Value>,
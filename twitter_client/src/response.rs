@@ -154,6 +154,9 @@ pub struct LegacyTweet {
     pub retweet_count: u32,
     pub reply_count: u32,
     pub quote_count: u32,
+    /// Original tweet, if this is a retweet.
+    #[serde(default)]
+    pub retweeted_status_result: Option<Box<TweetResult>>,
 }
 
 #[serde_as]
@@ -269,6 +272,30 @@ pub enum UserResponse {
     },
 }
 
+// List
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct BannerMediaInfo {
+    pub original_img_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct BannerMedia {
+    pub media_info: BannerMediaInfo,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct List {
+    pub id_str: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub member_count: u32,
+    pub custom_banner_media: Option<BannerMedia>,
+    pub default_banner_media: Option<BannerMedia>,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub enum Data {
     #[serde(rename = "user")]
@@ -279,6 +306,8 @@ pub enum Data {
     Tweet(TweetResult),
     #[serde(rename = "search_by_raw_query")]
     Search { search_timeline: TimelineL4 },
+    #[serde(rename = "list")]
+    List(List),
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -57,6 +57,7 @@ impl TimelineCursor {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(untagged)]
 enum UserUrl {
@@ -104,6 +105,19 @@ pub struct Tweet {
     pub reply_count: u32,
     pub quote_count: u32,
     pub user: User,
+    /// Original tweet, if this is a retweet.
+    #[serde(default)]
+    pub retweeted_status: Option<Box<Tweet>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct List {
+    pub id: u64,
+    pub name: String,
+    pub description: String,
+    pub member_count: u32,
+    /// Banner image of the list, custom or default.
+    pub banner_url: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -203,6 +217,10 @@ impl From<response::Tweet> for Tweet {
             reply_count: tweet.legacy.reply_count,
             quote_count: tweet.legacy.quote_count,
             user: tweet.core.user.result.into(),
+            retweeted_status: tweet
+                .legacy
+                .retweeted_status_result
+                .map(|result| Box::new(result.result.into())),
         }
     }
 }
@@ -251,6 +269,26 @@ impl TryFrom<GraphqlResponse> for Tweet {
     }
 }
 
+impl TryFrom<GraphqlResponse> for List {
+    type Error = Error;
+
+    fn try_from(value: GraphqlResponse) -> Result<Self, Self::Error> {
+        match value.data {
+            Data::List(list) => {
+                let banner = list.custom_banner_media.or(list.default_banner_media);
+                Ok(List {
+                    id: list.id_str.parse().map_err(|_| Error::InvalidGraphqlResponse)?,
+                    name: list.name,
+                    description: list.description,
+                    member_count: list.member_count,
+                    banner_url: banner.map(|media| media.media_info.original_img_url),
+                })
+            }
+            _ => Err(Error::InvalidGraphqlResponse),
+        }
+    }
+}
+
 impl TryFrom<GraphqlResponse> for TimelineResult {
     type Error = Error;
 
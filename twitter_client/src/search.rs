@@ -0,0 +1,92 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+/// Date format of `since:` and `until:` in search queries.
+const SEARCH_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchOption {
+    pub keyword: Option<String>,
+    /// Screen name of the author, without `@`.
+    pub from_user: Option<String>,
+    pub min_faves: Option<u32>,
+    pub media_only: bool,
+    pub exclude_retweets: bool,
+    /// Earliest day of the tweets, inclusive.
+    pub since: Option<NaiveDate>,
+    /// Latest day of the tweets, exclusive.
+    pub until: Option<NaiveDate>,
+}
+
+impl SearchOption {
+    /// Raw query of the search, with the operators after the keyword.
+    pub fn query(&self) -> String {
+        let mut terms = Vec::new();
+        if let Some(keyword) = self.keyword.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+            terms.push(keyword.to_string());
+        }
+        if let Some(user) = &self.from_user {
+            terms.push(format!("from:{}", user.trim_start_matches('@')));
+        }
+        if let Some(min_faves) = self.min_faves {
+            terms.push(format!("min_faves:{}", min_faves));
+        }
+        if self.media_only {
+            terms.push("filter:media".to_string());
+        }
+        if self.exclude_retweets {
+            terms.push("-filter:retweets".to_string());
+        }
+        if let Some(since) = self.since {
+            terms.push(format!("since:{}", since.format(SEARCH_DATE_FORMAT)));
+        }
+        if let Some(until) = self.until {
+            terms.push(format!("until:{}", until.format(SEARCH_DATE_FORMAT)));
+        }
+        terms.join(" ")
+    }
+
+    /// Whether the search has no condition.
+    pub fn is_empty(&self) -> bool {
+        self.query().is_empty()
+    }
+}
+
+impl Display for SearchOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.query())
+    }
+}
+
+impl FromStr for SearchOption {
+    type Err = Error;
+
+    /// Parse from a raw query, where the terms other than the known operators make up the keyword.
+    fn from_str(s: &str) -> Result<Self> {
+        let parse_date = |value: &str| {
+            NaiveDate::parse_from_str(value, SEARCH_DATE_FORMAT).map_err(|_| Error::InvalidQuery(s.to_string()))
+        };
+
+        let mut option = SearchOption::default();
+        let mut keywords = Vec::new();
+        for term in s.split_whitespace() {
+            match term.split_once(':') {
+                Some(("from", user)) if !user.is_empty() => option.from_user = Some(user.to_string()),
+                Some(("min_faves", count)) => {
+                    option.min_faves = Some(count.parse().map_err(|_| Error::InvalidQuery(s.to_string()))?)
+                }
+                Some(("filter", "media")) => option.media_only = true,
+                Some(("-filter", "retweets")) => option.exclude_retweets = true,
+                Some(("since", date)) => option.since = Some(parse_date(date)?),
+                Some(("until", date)) => option.until = Some(parse_date(date)?),
+                _ => keywords.push(term),
+            }
+        }
+        option.keyword = (!keywords.is_empty()).then(|| keywords.join(" "));
+        Ok(option)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::response::GraphqlResponse;
 use crate::result::{TimelineResult, Tweet, User};
+use crate::{SearchOption, SessionCookie};
 
 fn read_response(endpoint: &str) -> GraphqlResponse {
     let path = std::fs::read_dir("log")
@@ -60,3 +61,28 @@ fn test_parse_following() {
     let response = read_response("Following");
     let _timeline: TimelineResult = response.try_into().unwrap();
 }
+
+#[test]
+fn test_parse_session_cookie() {
+    let header = "Cookie: guest_id=v1; ct0=abc; auth_token=def";
+    let cookie: SessionCookie = header.parse().unwrap();
+    assert_eq!((cookie.ct0.as_str(), cookie.auth_token.as_str()), ("abc", "def"));
+
+    let cookies_txt = "# Netscape HTTP Cookie File\n\
+        .x.com\tTRUE\t/\tTRUE\t1767225600\tct0\tabc\n\
+        #HttpOnly_.x.com\tTRUE\t/\tTRUE\t1767225600\tauth_token\tdef\n\
+        .example.com\tTRUE\t/\tTRUE\t1767225600\tct0\tother\n";
+    let cookie: SessionCookie = cookies_txt.parse().unwrap();
+    assert_eq!((cookie.ct0.as_str(), cookie.auth_token.as_str()), ("abc", "def"));
+}
+
+#[test]
+fn test_search_option_query() {
+    let query = "cat OR dog from:someone min_faves:100 filter:media -filter:retweets since:2023-01-01 until:2024-01-01";
+    let option: SearchOption = query.parse().unwrap();
+    assert_eq!(option.keyword.as_deref(), Some("cat OR dog"));
+    assert_eq!(option.from_user.as_deref(), Some("someone"));
+    assert_eq!(option.min_faves, Some(100));
+    assert!(option.media_only && option.exclude_retweets);
+    assert_eq!(option.query(), query);
+}
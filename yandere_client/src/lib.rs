@@ -1,9 +1,11 @@
 mod error;
 mod result;
 
-use reqwest::Url;
+use std::sync::OnceLock;
 
-use bottle_util::build_params;
+use reqwest::{header, Client, StatusCode, Url};
+
+use bottle_util::{build_params, http::client_builder};
 
 pub use crate::error::Error;
 use crate::error::Result;
@@ -11,7 +13,20 @@ pub use crate::result::*;
 
 const BASE_URL: &str = "https://yande.re";
 
-pub async fn fetch_posts(query: &str, page: u32) -> Result<APIResult> {
+/// The API needs no authentication, so a single client is shared to reuse connections across requests.
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+fn client() -> Result<&'static Client> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = client_builder().build()?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+/// Fetch a page of posts. If the ETag of the last response is given, the request is conditional,
+/// and the result is empty and marked as not modified if the posts are unchanged.
+pub async fn fetch_posts(query: &str, page: u32, etag: Option<&str>) -> Result<APIResult> {
     let params = build_params! {
         required api_version => 2,
         required tags => query,
@@ -22,11 +37,40 @@ pub async fn fetch_posts(query: &str, page: u32) -> Result<APIResult> {
     };
     let url = Url::parse_with_params(&format!("{}/post.json", BASE_URL), &params)?;
 
-    let response = reqwest::get(url).await?.error_for_status()?;
+    let mut request = client()?.get(url);
+    if let Some(etag) = etag {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    let response = request.send().await?.error_for_status()?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(APIResult {
+            not_modified: true,
+            ..Default::default()
+        });
+    }
+    let etag = response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
     let content = response.text().await?;
 
     log(query, &content).await?;
-    let result: APIResult = serde_json::from_str(&content)?;
+    let mut result: APIResult = serde_json::from_str(&content)?;
+    result.etag = etag;
+    Ok(result)
+}
+
+/// Fetch the artists matching the name, with their aliases and URLs.
+pub async fn fetch_artists(name: &str) -> Result<Vec<ArtistResult>> {
+    let params = build_params! {
+        required name
+    };
+    let url = Url::parse_with_params(&format!("{}/artist.json", BASE_URL), &params)?;
+
+    let content = client()?.get(url).send().await?.error_for_status()?.text().await?;
+    log(&format!("artist_{}", name), &content).await?;
+    let result = serde_json::from_str(&content)?;
     Ok(result)
 }
 
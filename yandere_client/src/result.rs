@@ -8,12 +8,18 @@ use serde::{Deserialize, Serialize};
 
 use bottle_util::iso8601;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct APIResult {
     pub posts: Vec<PostResult>,
     pub tags: HashMap<String, TagType>,
     pub pools: Vec<PoolResult>,
     pub pool_posts: Vec<PoolPostResult>,
+    /// ETag of the response, to be sent as `If-None-Match` to check for changes later.
+    #[serde(skip)]
+    pub etag: Option<String>,
+    /// Whether the response was not modified since the ETag sent, with nothing else in the result.
+    #[serde(skip)]
+    pub not_modified: bool,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -78,6 +84,16 @@ pub struct PoolPostResult {
     pub active: bool,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ArtistResult {
+    pub id: u64,
+    pub name: String,
+    /// ID of the artist this name is an alias of.
+    pub alias_id: Option<u64>,
+    pub group_id: Option<u64>,
+    pub urls: Vec<String>,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum TagType {